@@ -0,0 +1,41 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Backtest the default game config and print a daily economy report.
+//!
+//! Run with `cargo run --example simulate --features simulation [players] [days] [seed]`.
+
+use predictive_manager::simulation::{Simulation, SimulationParams};
+use predictive_manager::GameConfig;
+
+fn main() {
+    let mut args = std::env::args().skip(1).map(|arg| arg.parse::<u64>().expect("arguments are numbers"));
+    let defaults = SimulationParams::default();
+    let params = SimulationParams {
+        players: args.next().map_or(defaults.players, |players| players as u32),
+        days: args.next().map_or(defaults.days, |days| days as u32),
+        seed: args.next().unwrap_or(defaults.seed),
+        ..defaults
+    };
+    let config = GameConfig::default();
+    let opening_supply = Simulation::opening_supply(&config, &params);
+    let report = Simulation::run(config, params);
+
+    println!("day,total_supply,circulating,locked,daily_rewards,trading_fees,burned,inflation_bps,gini_bps,open_markets");
+    for day in &report.days {
+        println!(
+            "{},{},{},{},{},{},{},{},{},{}",
+            day.day,
+            day.total_supply(),
+            day.circulating,
+            day.locked,
+            day.flows.daily_rewards,
+            day.flows.trading_fees,
+            day.flows.burned,
+            day.inflation_bps,
+            day.gini_bps,
+            day.open_markets,
+        );
+    }
+    println!("total inflation: {} bps", report.total_inflation_bps(opening_supply));
+}
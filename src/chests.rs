@@ -0,0 +1,144 @@
+// Mystery chests: players earn a chest every few levels and open it for a drop from the table
+// the admin configures. The drop is picked by hashing the chest with the chain and the time it
+// was earned, all fixed before the player can open it, so every validator picks the same one,
+// waiting for a lucky block doesn't change it, and anyone can recompute it from the seed kept
+// on the chest.
+
+use async_graphql::{InputObject, SimpleObject};
+use linera_sdk::linera_base_types::{Amount, BcsHashable, ChainId, CryptoHash, Timestamp};
+use serde::{Deserialize, Serialize};
+
+use crate::state::PlayerId;
+
+/// Index of a chest among the chests a player earned
+pub type ChestId = u32;
+
+/// Maximum number of drops in a chest table
+pub const MAX_CHEST_DROPS: usize = 20;
+
+/// One possible content of a chest, drawn with probability `weight` over the table's total
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "ChestDropInput")]
+pub struct ChestDrop {
+    pub weight: u32,
+    pub tokens: Amount,
+    pub xp: u64,
+}
+
+/// When chests are earned and what they can hold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject, InputObject)]
+#[graphql(input_name = "ChestTableInput")]
+pub struct ChestTable {
+    /// A chest is earned on reaching every multiple of this level; zero earns none
+    pub levels_per_chest: u32,
+    pub drops: Vec<ChestDrop>,
+}
+
+impl Default for ChestTable {
+    fn default() -> Self {
+        Self {
+            levels_per_chest: 5,
+            drops: vec![
+                ChestDrop { weight: 60, tokens: Amount::from_tokens(10), xp: 0 },
+                ChestDrop { weight: 30, tokens: Amount::ZERO, xp: 100 },
+                ChestDrop { weight: 10, tokens: Amount::from_tokens(50), xp: 0 },
+            ],
+        }
+    }
+}
+
+impl ChestTable {
+    /// Whether every drop can be drawn, and there is one to draw whenever chests are earned
+    pub fn is_valid(&self) -> bool {
+        self.drops.len() <= MAX_CHEST_DROPS
+            && self.drops.iter().all(|drop| drop.weight > 0)
+            && (self.levels_per_chest == 0 || !self.drops.is_empty())
+    }
+
+    /// Levels above `old_level` up to `new_level` that earn a chest
+    pub fn chest_levels(&self, old_level: u32, new_level: u32) -> impl Iterator<Item = u32> {
+        let every = self.levels_per_chest;
+        (old_level.saturating_add(1)..=new_level).filter(move |level| every > 0 && level % every == 0)
+    }
+
+    /// The drop a roll lands on, each drop covering a range of rolls as wide as its weight
+    pub fn pick(&self, roll: u64) -> Option<ChestDrop> {
+        let total: u64 = self.drops.iter().map(|drop| u64::from(drop.weight)).sum();
+        if total == 0 {
+            return None;
+        }
+        let mut remaining = roll % total;
+        for drop in &self.drops {
+            if remaining < u64::from(drop.weight) {
+                return Some(*drop);
+            }
+            remaining -= u64::from(drop.weight);
+        }
+        None
+    }
+}
+
+/// A chest a player earned, and what it held once opened
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct MysteryChest {
+    pub id: ChestId,
+    /// Level whose milestone earned the chest
+    pub level: u32,
+    pub awarded_at: Timestamp,
+    pub opened_at: Option<Timestamp>,
+    /// Hash the drop was picked from, so anyone can recompute it
+    pub seed: Option<CryptoHash>,
+    pub contents: Option<ChestDrop>,
+}
+
+/// On-chain inputs a chest's drop is picked from, all settled when the chest is earned
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChestSeed {
+    pub player: PlayerId,
+    pub chest_id: ChestId,
+    pub chain_id: ChainId,
+    pub level: u32,
+    pub awarded_at: Timestamp,
+}
+
+impl BcsHashable<'_> for ChestSeed {}
+
+impl ChestSeed {
+    /// The seed's hash, and the roll taken from it for `ChestTable::pick`
+    pub fn roll(&self) -> (CryptoHash, u64) {
+        let hash = CryptoHash::new(self);
+        (hash, <[u64; 4]>::from(hash)[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chest_drop(weight: u32, tokens: u128) -> ChestDrop {
+        ChestDrop { weight, tokens: Amount::from_tokens(tokens), xp: 0 }
+    }
+
+    #[test]
+    fn rolls_follow_the_weights() {
+        let table = ChestTable {
+            levels_per_chest: 5,
+            drops: vec![chest_drop(3, 1), chest_drop(1, 2)],
+        };
+        let picks: Vec<u128> = (0..8).map(|roll| u128::from(table.pick(roll).unwrap().tokens)).collect();
+        let one = u128::from(Amount::ONE);
+        assert_eq!(picks, [1, 1, 1, 2, 1, 1, 1, 2].map(|tokens| tokens * one));
+        assert_eq!(ChestTable { levels_per_chest: 0, drops: Vec::new() }.pick(7), None);
+    }
+
+    #[test]
+    fn chests_are_earned_at_level_multiples() {
+        let table = ChestTable::default();
+        assert_eq!(table.chest_levels(4, 11).collect::<Vec<_>>(), [5, 10]);
+        assert_eq!(table.chest_levels(5, 9).count(), 0);
+        let disabled = ChestTable { levels_per_chest: 0, ..table };
+        assert_eq!(disabled.chest_levels(1, 100).count(), 0);
+        assert!(disabled.is_valid());
+        assert!(!ChestTable { levels_per_chest: 1, drops: Vec::new() }.is_valid());
+    }
+}
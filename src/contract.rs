@@ -1,12 +1,21 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
 use linera_sdk::{
-    linera_base_types::{Amount, Timestamp, WithContractAbi},
-    views::View,
+    abis::fungible::{Account, FungibleOperation, FungibleResponse, FungibleTokenAbi},
+    linera_base_types::{AccountOwner, Amount, ApplicationId, ChainId, CryptoHash, TimeDelta, Timestamp, WithContractAbi},
+    views::{RootView, View},
     Contract, ContractRuntime,
 };
+use predictive_manager::chests::{ChestDrop, ChestId, ChestSeed, ChestTable, MysteryChest};
+use predictive_manager::events::{EventBoost, EventId, MarketCategory, SpecialEvent, MAX_SCHEDULED_EVENTS};
+use predictive_manager::math;
+use predictive_manager::metrics::{day_index, EconomyDay, TokenFlow, TradeSide};
+use predictive_manager::search;
 use predictive_manager::state::*;
-use std::collections::BTreeMap;
+use predictive_manager::units::{PricePerShare, Shares, Tokens, TradeAmounts};
+use predictive_manager::{OperationResponse, TradeFill};
+use std::collections::{btree_map::Entry, BTreeMap, BTreeSet};
+use std::ops::Bound;
 use thiserror::Error;
 use linera_sdk::views::ViewError;
 
@@ -28,6 +37,7 @@ pub enum ContractError {
     #[error("market ended")] MarketEnded,
     #[error("invalid outcome")] InvalidOutcome,
     #[error("slippage exceeded")] SlippageExceeded,
+    #[error("trade would move the price too far")] PriceImpactExceeded,
     #[error("no position")] NoPosition,
     #[error("insufficient shares")] InsufficientShares,
     #[error("market not ready for voting")] MarketNotReadyForVoting,
@@ -43,6 +53,154 @@ pub enum ContractError {
     #[error("oracle not ready")] OracleNotReady,
     #[error("not resolved")] NotResolved,
     #[error("no winnings")] NoWinnings,
+    #[error("invalid payout weights")] InvalidPayoutWeights,
+    #[error("invalid ranked outcomes")] InvalidRankedOutcomes,
+    #[error("market access denied")] MarketAccessDenied,
+    #[error("invalid market visibility")] InvalidVisibility,
+    #[error("invalid market metadata")] InvalidMetadata,
+    #[error("invalid resolution evidence")] InvalidEvidence,
+    #[error("market edit window closed")] EditWindowClosed,
+    #[error("invalid end time")] InvalidEndTime,
+    #[error("early close not allowed")] EarlyCloseNotAllowed,
+    #[error("not eligible for cost basis exit")] NotEligibleForExit,
+    #[error("order expired")] OrderExpired,
+    #[error("invalid announcement")] InvalidAnnouncement,
+    #[error("market already resolved")] MarketAlreadyResolved,
+    #[error("too many subscribers")] TooManySubscribers,
+    #[error("not the home chain")] NotHomeChain,
+    #[error("already the home chain")] AlreadyHomeChain,
+    #[error("effective time must give at least the config's notice period")] InvalidEffectiveTime,
+    #[error("no pending config change")] NoPendingConfigChange,
+    #[error("markets are suspended in emergency mode")] EmergencyModeActive,
+    #[error("emergency mode is not active")] EmergencyModeInactive,
+    #[error("invalid recovery owner")] InvalidRecoveryOwner,
+    #[error("signer is not the account's recovery owner")] NotRecoveryOwner,
+    #[error("recovery owner was linked too recently")] RecoveryCooldown,
+    #[error("market already rated")] AlreadyRated,
+    #[error("challenge not found")] ChallengeNotFound,
+    #[error("guild staking proposal not found")] GuildStakeNotFound,
+    #[error("guild staking proposal already executed")] GuildStakeExecuted,
+    #[error("guild token withdrawal not found")] GuildWithdrawalNotFound,
+    #[error("guild token withdrawal already executed")] GuildWithdrawalExecuted,
+    #[error("officer already approved this proposal")] AlreadyApproved,
+    #[error("invalid approval threshold")] InvalidApprovalThreshold,
+    #[error("player is self-excluded")] SelfExcluded,
+    #[error("daily loss limit reached")] LossLimitExceeded,
+    #[error("risk controls cannot be loosened yet")] RiskControlsLocked,
+    #[error("invalid challenge")] InvalidChallenge,
+    #[error("challenge is not in the required state")] InvalidChallengeStatus,
+    #[error("challenge has not ended")] ChallengeNotEnded,
+    #[error("invalid parlay")] InvalidParlay,
+    #[error("parlay leg on a market the player created or can resolve")] ConflictedParlayLeg,
+    #[error("treasury cannot cover the parlay's winnings")] ParlayPoolExhausted,
+    #[error("parlay not found")] ParlayNotFound,
+    #[error("parlay cannot be settled yet")] ParlayNotSettled,
+    #[error("invalid bootstrapping phase")] InvalidBootstrap,
+    #[error("market is not bootstrapping")] NotBootstrapping,
+    #[error("bootstrapping phase has not ended")] BootstrapNotEnded,
+    #[error("invalid curve parameters for this market type")] InvalidCurveParams,
+    #[error("market is in its final grace period")] InGracePeriod,
+    #[error("invalid blind finish")] InvalidBlindFinish,
+    #[error("market only takes sealed trades now")] BlindFinishActive,
+    #[error("market is not in its blind finish")] NotInBlindFinish,
+    #[error("blind trades can't be revealed now")] RevealWindowClosed,
+    #[error("no sealed trade matches this reveal")] InvalidReveal,
+    #[error("blind trades are still being revealed")] RevealPeriodOpen,
+    #[error("winnings were already paid out")] AlreadySettled,
+    #[error("claim window has closed")] ClaimWindowExpired,
+    #[error("claim window is still open")] ClaimWindowOpen,
+    #[error("claim expiry warning is not due")] ExpiryWarningNotDue,
+    #[error("market can't be archived yet")] ArchiveTooEarly,
+    #[error("market still has unpaid winnings")] MarketNotSettled,
+    #[error("not a staked resolver")] NotResolver,
+    #[error("resolver stake is below the minimum")] InsufficientStake,
+    #[error("resolver has votes on unresolved markets")] ResolverVotesPending,
+    #[error("not on the arbitration council")] NotArbitrator,
+    #[error("invalid scalar range")] InvalidScalarRange,
+    #[error("invalid oracle result")] InvalidOracleResult,
+    #[error("market vote is not contested")] NotContested,
+    #[error("invalid auction")] InvalidAuction,
+    #[error("auction not found")] AuctionNotFound,
+    #[error("auction is closed")] AuctionClosed,
+    #[error("invalid share offer")] InvalidShareOffer,
+    #[error("invalid market group")] InvalidMarketGroup,
+    #[error("market group not found")] MarketGroupNotFound,
+    #[error("market group is already resolved")] MarketGroupResolved,
+    #[error("market settles with its group")] MarketInGroup,
+    #[error("invalid bracket")] InvalidBracket,
+    #[error("bracket not found")] BracketNotFound,
+    #[error("bracket is locked")] BracketLocked,
+    #[error("already entered this bracket")] AlreadyEntered,
+    #[error("invalid bracket picks")] InvalidBracketPicks,
+    #[error("bracket match can't be scored yet")] BracketMatchPending,
+    #[error("bracket match already scored")] BracketMatchScored,
+    #[error("share offer not found")] ShareOfferNotFound,
+    #[error("share offer is closed")] ShareOfferClosed,
+    #[error("not an approved market maker")] NotMarketMaker,
+    #[error("invalid market maker")] InvalidMarketMaker,
+    #[error("invalid bot quote")] InvalidBotQuote,
+    #[error("bot quote not found")] BotQuoteNotFound,
+    #[error("bot quote is closed")] BotQuoteClosed,
+    #[error("quote doesn't cover that many shares")] QuoteSizeExceeded,
+    #[error("market maker risk limit exceeded")] MarketMakerLimitExceeded,
+    #[error("config changes can't change the admin")] AdminChangeNotAllowed,
+    #[error("admin role hasn't been offered to this player")] NotPendingAdmin,
+    #[error("invalid shop item")] InvalidShopItem,
+    #[error("shop item not found")] ItemNotFound,
+    #[error("item is not on sale")] ItemUnavailable,
+    #[error("item is sold out")] ItemSoldOut,
+    #[error("item already owned")] ItemAlreadyOwned,
+    #[error("item not owned")] ItemNotOwned,
+    #[error("invalid raffle")] InvalidRaffle,
+    #[error("raffle not found")] RaffleNotFound,
+    #[error("raffle is no longer selling tickets")] RaffleClosed,
+    #[error("raffle hasn't ended yet")] RaffleNotEnded,
+    #[error("invalid season")] InvalidSeason,
+    #[error("season not found")] SeasonNotFound,
+    #[error("season has already started")] SeasonStarted,
+    #[error("season has ended")] SeasonEnded,
+    #[error("premium pass already owned")] PremiumPassOwned,
+    #[error("no season pass rewards to claim")] NoPassRewards,
+    #[error("season hasn't ended yet")] SeasonNotEnded,
+    #[error("leagues were already settled for this season")] LeaguesSettled,
+    #[error("invalid league rewards")] InvalidLeagueRewards,
+    #[error("guild has no token application")] NoGuildTokenApplication,
+    #[error("guild token pool is not empty")] GuildTokenPoolNotEmpty,
+    #[error("unexpected response from token application")] UnexpectedTokenResponse,
+    #[error("leaderboard was refreshed recently")] LeaderboardFresh,
+    #[error("claim not achieved")] ClaimNotAchieved,
+    #[error("invalid attestation")] InvalidAttestation,
+    #[error("achievement not found")] AchievementNotFound,
+    #[error("invalid translation")] InvalidTranslation,
+    #[error("invalid market import")] InvalidImport,
+    #[error("invalid special event")] InvalidEvent,
+    #[error("too many special events scheduled")] TooManyEvents,
+    #[error("special event not found")] EventNotFound,
+    #[error("invalid mentorship")] InvalidMentorship,
+    #[error("mentor has too many apprentices")] TooManyApprentices,
+    #[error("player has too many mentorship offers")] TooManyMentorshipOffers,
+    #[error("mentorship not found")] MentorshipNotFound,
+    #[error("invalid featured market list")] InvalidFeaturedMarkets,
+    #[error("creator has too many active markets")] TooManyActiveMarkets,
+    #[error("positions are locked until the market is resolved")] PositionsLocked,
+    #[error("not the engagement relayer")] NotEngagementRelayer,
+    #[error("invalid batch of views")] InvalidViewBatch,
+    #[error("invalid outcome correction")] InvalidOutcomeCorrection,
+    #[error("market is not in its review window")] NotInReview,
+    #[error("market is in its review window")] MarketInReview,
+    #[error("market already flagged by this player")] AlreadyFlagged,
+    #[error("invalid ambiguity flag")] InvalidAmbiguityFlag,
+    #[error("market is paused for clarification")] MarketPaused,
+    #[error("market is not paused")] MarketNotPaused,
+    #[error("market hasn't been edited since it was paused")] MarketNotClarified,
+    #[error("no outcome correction pending")] NoPendingCorrection,
+    #[error("invalid chest table")] InvalidChestTable,
+    #[error("chest not found")] ChestNotFound,
+    #[error("chest already opened")] ChestAlreadyOpened,
+    #[error(transparent)]
+    InvalidConfig(#[from] ConfigError),
+    #[error(transparent)]
+    InvalidTransition(#[from] InvalidTransition),
     #[error(transparent)]
     View(#[from] ViewError),
 }
@@ -76,14 +234,21 @@ impl Contract for PredictionMarketContract {
     }
 
     async fn instantiate(&mut self, config: GameConfig) {
+        config.validate().expect("Invalid game config");
         self.state.config.set(config);
         self.state.total_supply.set(Amount::ZERO);
+        self.state.total_burned.set(Amount::ZERO);
         self.state.next_market_id.set(0);
+        self.state.next_guild_id.set(0);
+        self.state.guild_ids_migrated.set(true);
         let _ = self.initialize_achievements().await;
+        let _ = self.initialize_guild_achievements().await;
         self.state.leaderboard.set(Leaderboard {
             top_traders: Vec::new(),
             top_guilds: Vec::new(),
+            top_forecasters: Vec::new(),
             last_updated: self.runtime.system_time(),
+            total_ranked_players: 0,
         });
         
         // Initialize enhanced leaderboard
@@ -93,25 +258,53 @@ impl Contract for PredictionMarketContract {
     async fn execute_operation(&mut self, operation: Self::Operation) -> Self::Response {
         let player_id = self.runtime.authenticated_signer().unwrap();
         let current_time = self.runtime.system_time();
+        let _ = self.apply_due_config_change(current_time).await;
+        if *self.state.emergency_mode.get() && Self::is_market_operation(&operation) {
+            return OperationResponse::Done;
+        }
 
-        match operation {
+        let payload = serde_json::to_value(&operation).unwrap_or_default();
+        let mut response = OperationResponse::Done;
+        let result = match operation {
             predictive_manager::Operation::RegisterPlayer { display_name } => {
-                let _ = self.register_player(player_id, display_name, current_time).await;
+                self.register_player(player_id, display_name, current_time).await
             }
-            predictive_manager::Operation::UpdateProfile { display_name } => {
-                let _ = self.update_player_profile(player_id, display_name).await;
+            predictive_manager::Operation::UpdateProfile { display_name, privacy } => {
+                self.update_player_profile(player_id, display_name, privacy).await
             }
             predictive_manager::Operation::ClaimDailyReward => {
-                let _ = self.claim_daily_reward(player_id, current_time).await;
+                self.claim_daily_reward(player_id, current_time).await
+            }
+            predictive_manager::Operation::LinkRecoveryOwner { owner } => {
+                self.link_recovery_owner(player_id, owner, current_time).await
+            }
+            predictive_manager::Operation::RecoverAccount { old_player_id } => {
+                self.recover_account(player_id, old_player_id, current_time).await
+            }
+            predictive_manager::Operation::SetLossLimit { per_day } => {
+                self.set_loss_limit(player_id, per_day, current_time).await
+            }
+            predictive_manager::Operation::SelfExclude { until } => {
+                self.self_exclude(player_id, until, current_time).await
             }
             predictive_manager::Operation::CreateMarket { 
                 title, 
                 description, 
                 outcome_names, 
                 duration_seconds, 
-                resolution_method 
+                resolution_method,
+                payout_weights,
+                visibility,
+                allowed_guild,
+                allowlist,
+                metadata,
+                allow_early_close,
+                bootstrap_seconds,
+                blind_finish_seconds,
             } => {
-                let _ = self.create_market(
+                let creation_cost = self.state.config.get().market_creation_cost;
+                let creator_bond = self.state.config.get().market_creator_bond;
+                self.create_market(
                     player_id,
                     title,
                     description,
@@ -119,83 +312,524 @@ impl Contract for PredictionMarketContract {
                     outcome_names,
                     duration_seconds,
                     resolution_method,
+                    payout_weights.unwrap_or_else(|| vec![BASIS_POINTS]),
+                    visibility.unwrap_or_default(),
+                    allowed_guild,
+                    allowlist.unwrap_or_default(),
+                    metadata.unwrap_or_default(),
+                    allow_early_close.unwrap_or(false),
+                    bootstrap_seconds,
+                    blind_finish_seconds,
+                    creation_cost,
+                    creator_bond,
                     current_time,
-                ).await;
+                ).await.map(|market_id| response = OperationResponse::MarketCreated { market_id })
+            }
+            predictive_manager::Operation::CommitToOutcome { market_id, outcome_id, amount } => {
+                self
+                    .commit_to_outcome(player_id, market_id, outcome_id, amount, current_time)
+                    .await
+            }
+            predictive_manager::Operation::FinalizeBootstrap { market_id } => {
+                self.finalize_bootstrap(market_id, current_time).await
+            }
+            predictive_manager::Operation::CommitBlindTrade { market_id, commitment, amount } => {
+                self
+                    .commit_blind_trade(player_id, market_id, commitment, amount, current_time)
+                    .await
+            }
+            predictive_manager::Operation::RevealBlindTrade { market_id, outcome_id, salt } => {
+                self
+                    .reveal_blind_trade(player_id, market_id, outcome_id, salt, current_time)
+                    .await
+            }
+            predictive_manager::Operation::UpdateMarket { market_id, title, description, end_time } => {
+                self.update_market(player_id, market_id, title, description, end_time, current_time).await
+            }
+            predictive_manager::Operation::ProposeOutcomeCorrection { market_id, outcome_id, new_name } => {
+                self.propose_outcome_correction(player_id, market_id, outcome_id, new_name, current_time).await
+            }
+            predictive_manager::Operation::ApproveOutcomeCorrection { market_id } => {
+                self.approve_outcome_correction(player_id, market_id, current_time).await
+            }
+            predictive_manager::Operation::FlagAmbiguousMarket { market_id, reason } => {
+                self.flag_ambiguous_market(player_id, market_id, reason, current_time).await
+            }
+            predictive_manager::Operation::ResumeMarket { market_id } => {
+                self.resume_market(player_id, market_id, current_time).await
+            }
+            predictive_manager::Operation::CloseMarketEarly { market_id } => {
+                self.close_market_early(player_id, market_id, current_time).await
+            }
+            predictive_manager::Operation::ExitAtCostBasis { market_id } => {
+                self.exit_at_cost_basis(player_id, market_id).await
+            }
+            predictive_manager::Operation::UpdateMarketAllowlist { market_id, add, remove } => {
+                self.update_market_allowlist(player_id, market_id, add, remove).await
             }
             predictive_manager::Operation::BuyShares { 
                 market_id, 
                 outcome_id, 
                 amount, 
-                max_price_per_share 
+                max_price_per_share,
+                valid_until,
             } => {
-                let _ = self.buy_shares(
+                self.buy_shares(
                     player_id,
                     market_id,
                     outcome_id,
                     amount,
                     max_price_per_share,
+                    valid_until,
                     current_time,
-                ).await;
+                ).await.map(|fill| response = OperationResponse::SharesBought(fill))
             }
             predictive_manager::Operation::SellShares { 
                 market_id, 
                 outcome_id, 
                 shares, 
-                min_price_per_share 
+                min_price_per_share,
+                valid_until,
             } => {
-                let _ = self.sell_shares(
+                self.sell_shares(
                     player_id,
                     market_id,
                     outcome_id,
                     shares,
                     min_price_per_share,
+                    valid_until,
+                    current_time,
+                ).await.map(|fill| response = OperationResponse::SharesSold(fill))
+            }
+            predictive_manager::Operation::MintCompleteSet { market_id, sets, max_cost } => {
+                self.mint_complete_set(player_id, market_id, sets, max_cost, current_time).await
+            }
+            predictive_manager::Operation::BurnCompleteSet { market_id, sets, min_value } => {
+                self.burn_complete_set(player_id, market_id, sets, min_value, current_time).await
+            }
+            predictive_manager::Operation::StartPositionAuction {
+                market_id,
+                outcome_id,
+                shares,
+                start_price,
+                floor_price,
+                duration_seconds,
+            } => {
+                self.start_position_auction(
+                    player_id,
+                    market_id,
+                    outcome_id,
+                    shares,
+                    start_price,
+                    floor_price,
+                    duration_seconds,
                     current_time,
-                ).await;
+                ).await.map(|auction_id| response = OperationResponse::AuctionStarted { market_id, auction_id })
+            }
+            predictive_manager::Operation::FillPositionAuction { market_id, auction_id, shares, max_price_per_share } => {
+                self.fill_position_auction(player_id, market_id, auction_id, shares, max_price_per_share, current_time).await
+            }
+            predictive_manager::Operation::CancelPositionAuction { market_id, auction_id } => {
+                self.cancel_position_auction(player_id, market_id, auction_id).await
+            }
+            predictive_manager::Operation::TransferShares { market_id, outcome_id, to, shares } => {
+                self.transfer_shares(player_id, market_id, outcome_id, to, shares, current_time).await
+            }
+            predictive_manager::Operation::CreateShareOffer { market_id, outcome_id, shares, price, buyer } => {
+                self.create_share_offer(player_id, market_id, outcome_id, shares, price, buyer, current_time)
+                    .await
+                    .map(|offer_id| response = OperationResponse::ShareOfferCreated { market_id, offer_id })
+            }
+            predictive_manager::Operation::AcceptShareOffer { market_id, offer_id, price } => {
+                self.accept_share_offer(player_id, market_id, offer_id, price, current_time).await
+            }
+            predictive_manager::Operation::CancelShareOffer { market_id, offer_id } => {
+                self.cancel_share_offer(player_id, market_id, offer_id).await
+            }
+            predictive_manager::Operation::ApproveMarketMaker { player, rebate_bps, limits } => {
+                self.approve_market_maker(player_id, player, rebate_bps, limits, current_time).await
+            }
+            predictive_manager::Operation::RevokeMarketMaker { player } => {
+                self.revoke_market_maker(player_id, player).await
+            }
+            predictive_manager::Operation::PostTwoSidedQuotes { quotes } => {
+                self.post_two_sided_quotes(player_id, quotes, current_time)
+                    .await
+                    .map(|quote_ids| response = OperationResponse::QuotesPosted { quote_ids })
+            }
+            predictive_manager::Operation::FillBotQuote { market_id, quote_id, side, shares, price_per_share } => {
+                self.fill_bot_quote(player_id, market_id, quote_id, side, shares, price_per_share, current_time).await
             }
             predictive_manager::Operation::VoteOnOutcome { 
                 market_id, 
-                outcome_id 
+                outcome_id,
+                evidence,
             } => {
-                let _ = self.vote_on_outcome(player_id, market_id, outcome_id, current_time).await;
+                self.vote_on_outcome(player_id, market_id, outcome_id, evidence, current_time).await
             }
             predictive_manager::Operation::TriggerResolution { market_id } => {
-                let _ = self.trigger_market_resolution(market_id, current_time).await;
+                self.trigger_market_resolution(market_id, current_time).await
+            }
+            predictive_manager::Operation::ResolveMarket { market_id, ranked_outcomes, evidence } => {
+                self.resolve_market_by_creator(player_id, market_id, ranked_outcomes, evidence, current_time).await
+            }
+            predictive_manager::Operation::SubmitOracleResult { market_id, outcome_id, value } => {
+                self.submit_oracle_result(player_id, market_id, outcome_id, value, current_time).await
+            }
+            predictive_manager::Operation::RateMarket { market_id, thumbs_up } => {
+                self.rate_market(player_id, market_id, thumbs_up).await
             }
             predictive_manager::Operation::ClaimWinnings { market_id } => {
-                let _ = self.claim_winnings(player_id, market_id).await;
+                self.claim_winnings(player_id, market_id, current_time).await
+            }
+            predictive_manager::Operation::RegisterAsResolver { stake } => {
+                self.register_as_resolver(player_id, stake, current_time).await
+            }
+            predictive_manager::Operation::WithdrawResolverStake => {
+                self.withdraw_resolver_stake(player_id).await
+            }
+            predictive_manager::Operation::SetArbitrator { member, is_arbitrator } => {
+                self.set_arbitrator(player_id, member, is_arbitrator)
+            }
+            predictive_manager::Operation::CreateMarketGroup { name, market_ids, resolver, prize } => {
+                self.create_market_group(player_id, name, market_ids, resolver, prize, current_time)
+                    .await
+                    .map(|group_id| response = OperationResponse::MarketGroupCreated { group_id })
+            }
+            predictive_manager::Operation::ResolveMarketGroup { group_id, results } => {
+                self.resolve_market_group(player_id, group_id, results, current_time).await
+            }
+            predictive_manager::Operation::CreateBracket { name, teams, entry_fee, locks_at, payout_weights } => {
+                self.create_bracket(player_id, name, teams, entry_fee, locks_at, payout_weights, current_time)
+                    .await
+                    .map(|bracket_id| response = OperationResponse::BracketCreated { bracket_id })
+            }
+            predictive_manager::Operation::EnterBracket { bracket_id, picks } => {
+                self.enter_bracket(player_id, bracket_id, picks, current_time).await
+            }
+            predictive_manager::Operation::LinkBracketMatch { bracket_id, match_index, market_id } => {
+                self.link_bracket_match(player_id, bracket_id, match_index, market_id).await
+            }
+            predictive_manager::Operation::ScoreBracketMatch { bracket_id, match_index } => {
+                self.score_bracket_match(bracket_id, match_index).await
+            }
+            predictive_manager::Operation::ArbitrateMarket { market_id, outcome } => {
+                self.arbitrate_market(player_id, market_id, outcome, current_time).await
+            }
+            predictive_manager::Operation::WarnExpiringClaims { market_id } => {
+                self.warn_expiring_claims(market_id, current_time).await
+            }
+            predictive_manager::Operation::SweepExpiredWinnings { market_id } => {
+                self.sweep_expired_winnings(market_id, current_time).await
+            }
+            predictive_manager::Operation::ArchiveMarket { market_id } => {
+                self.archive_market(market_id, current_time).await
+            }
+            predictive_manager::Operation::SubscribeToMarket { market_id, chain_id } => {
+                self.subscribe_to_market(player_id, market_id, chain_id).await
+            }
+            predictive_manager::Operation::CreateHeadToHead {
+                opponent,
+                outcome_names,
+                stake,
+                duration_seconds,
+            } => {
+                self
+                    .create_head_to_head(player_id, opponent, outcome_names, stake, duration_seconds, current_time)
+                    .await
+                    .map(|challenge_id| response = OperationResponse::ChallengeCreated { challenge_id })
+            }
+            predictive_manager::Operation::AcceptChallenge { challenge_id } => {
+                self.accept_challenge(player_id, challenge_id, current_time).await
+            }
+            predictive_manager::Operation::CancelChallenge { challenge_id } => {
+                self.cancel_challenge(player_id, challenge_id).await
+            }
+            predictive_manager::Operation::ReportChallengeOutcome { challenge_id, outcome_id } => {
+                self
+                    .report_challenge_outcome(player_id, challenge_id, outcome_id, current_time)
+                    .await
+            }
+            predictive_manager::Operation::OfferMentorship { apprentice } => {
+                self.offer_mentorship(player_id, apprentice).await
+            }
+            predictive_manager::Operation::AcceptMentorship { mentor } => {
+                self.accept_mentorship(player_id, mentor, current_time).await
+            }
+            predictive_manager::Operation::DeclineMentorship { mentor } => {
+                self.decline_mentorship(player_id, mentor).await
+            }
+            predictive_manager::Operation::EndMentorship { apprentice } => {
+                self.end_mentorship(player_id, apprentice).await
+            }
+            predictive_manager::Operation::CreateParlay { legs, stake } => {
+                self.create_parlay(player_id, legs, stake, current_time)
+                    .await
+                    .map(|parlay_id| response = OperationResponse::ParlayCreated { parlay_id })
+            }
+            predictive_manager::Operation::SettleParlay { parlay_id } => {
+                self.settle_parlay(parlay_id).await
+            }
+            predictive_manager::Operation::JoinNetwork => {
+                self.join_network()
             }
             predictive_manager::Operation::CreateGuild { name } => {
-                let _ = self.create_guild(player_id, name, current_time).await;
+                self.create_guild(player_id, name, current_time)
+                    .await
+                    .map(|guild_id| response = OperationResponse::GuildCreated { guild_id })
             }
             predictive_manager::Operation::JoinGuild { guild_id } => {
-                let _ = self.join_guild(player_id, guild_id).await;
+                self.join_guild(player_id, guild_id).await
             }
             predictive_manager::Operation::LeaveGuild => {
-                let _ = self.leave_guild(player_id).await;
+                self.leave_guild(player_id).await
             }
             predictive_manager::Operation::ContributeToGuild { amount } => {
-                let _ = self.contribute_to_guild(player_id, amount).await;
+                self.contribute_to_guild(player_id, amount).await
+            }
+            predictive_manager::Operation::SetGuildOfficer { member, is_officer } => {
+                self.set_guild_officer(player_id, member, is_officer).await
+            }
+            predictive_manager::Operation::PostGuildAnnouncement { text } => {
+                self.post_guild_announcement(player_id, text, current_time).await
+            }
+            predictive_manager::Operation::GuildBuyShares { market_id, outcome_id, amount } => {
+                self
+                    .guild_buy_shares(player_id, market_id, outcome_id, amount, current_time)
+                    .await
+                    .map(|proposal_id| response = OperationResponse::GuildStakeProposed { proposal_id })
+            }
+            predictive_manager::Operation::ApproveGuildStake { proposal_id } => {
+                self.approve_guild_stake(player_id, proposal_id, current_time).await
+            }
+            predictive_manager::Operation::SetGuildStakeApprovals { approvals } => {
+                self.set_guild_stake_approvals(player_id, approvals).await
+            }
+            predictive_manager::Operation::ClaimGuildWinnings { market_id } => {
+                self.claim_guild_winnings(player_id, market_id).await
+            }
+            predictive_manager::Operation::SetGuildTokenApplication { application_id } => {
+                self.set_guild_token_application(player_id, application_id).await
+            }
+            predictive_manager::Operation::DepositGuildTokens { amount } => {
+                self.deposit_guild_tokens(player_id, amount).await
+            }
+            predictive_manager::Operation::WithdrawGuildTokens { amount, recipient } => {
+                self
+                    .withdraw_guild_tokens(player_id, amount, recipient, current_time)
+                    .await
+                    .map(|withdrawal_id| response = OperationResponse::GuildWithdrawalProposed { withdrawal_id })
+            }
+            predictive_manager::Operation::ApproveGuildTokenWithdrawal { withdrawal_id } => {
+                self.approve_guild_token_withdrawal(player_id, withdrawal_id).await
+            }
+            predictive_manager::Operation::ReconcileGuildTokens { application_id } => {
+                self.reconcile_guild_tokens(application_id, current_time).await
             }
             predictive_manager::Operation::UpdateGameConfig { config } => {
-                let _ = self.update_game_config(player_id, config).await;
+                self.update_game_config(player_id, config, current_time).await
+            }
+            predictive_manager::Operation::ProposeConfigChange { config, effective_at } => {
+                self
+                    .propose_config_change(player_id, config, effective_at, current_time)
+                    .await
+            }
+            predictive_manager::Operation::CancelConfigChange => {
+                self.cancel_config_change(player_id).await
+            }
+            predictive_manager::Operation::TransferAdmin { new_admin } => {
+                self.transfer_admin(player_id, new_admin)
+            }
+            predictive_manager::Operation::AcceptAdmin => {
+                self.accept_admin(player_id).await
+            }
+            predictive_manager::Operation::RenounceAdmin => {
+                self.renounce_admin(player_id).await
+            }
+            predictive_manager::Operation::ImportMarkets { markets } => {
+                self.import_markets(player_id, markets, current_time)
+                    .await
+                    .map(|results| response = OperationResponse::MarketsImported { results })
+            }
+            predictive_manager::Operation::ScheduleEvent {
+                name,
+                starts_at,
+                ends_at,
+                xp_multiplier_bps,
+                fee_multiplier_bps,
+                category,
+            } => self
+                .schedule_event(
+                    player_id,
+                    name,
+                    starts_at,
+                    ends_at,
+                    xp_multiplier_bps,
+                    fee_multiplier_bps,
+                    category,
+                    current_time,
+                )
+                .map(|event_id| response = OperationResponse::EventScheduled { event_id }),
+            predictive_manager::Operation::CancelEvent { event_id } => self.cancel_event(player_id, event_id),
+            predictive_manager::Operation::TranslateAchievement { achievement_id, locale, name, description } => {
+                self.translate_achievement(player_id, achievement_id, locale, name, description).await
+            }
+            predictive_manager::Operation::CreateShopItem { name, kind, price, max_supply } => {
+                self.create_shop_item(player_id, name, kind, price, max_supply)
+                    .await
+                    .map(|item_id| response = OperationResponse::ShopItemCreated { item_id })
+            }
+            predictive_manager::Operation::SetShopItemAvailable { item_id, available } => {
+                self.set_shop_item_available(player_id, item_id, available).await
+            }
+            predictive_manager::Operation::PurchaseItem { item_id } => {
+                self.purchase_item(player_id, item_id).await
             }
+            predictive_manager::Operation::EquipItem { item_id } => {
+                self.equip_item(player_id, item_id).await
+            }
+            predictive_manager::Operation::UnequipItem { kind } => {
+                self.unequip_item(player_id, kind).await
+            }
+            predictive_manager::Operation::CreateRaffle {
+                ticket_price,
+                duration_seconds,
+                winner_count,
+                treasury_top_up,
+                recurring,
+            } => {
+                self.create_raffle(
+                    player_id,
+                    ticket_price,
+                    duration_seconds,
+                    winner_count,
+                    treasury_top_up,
+                    recurring,
+                    current_time,
+                )
+                .map(|raffle_id| response = OperationResponse::RaffleCreated { raffle_id })
+            }
+            predictive_manager::Operation::BuyRaffleTickets { raffle_id, tickets } => {
+                self.buy_raffle_tickets(player_id, raffle_id, tickets, current_time).await
+            }
+            predictive_manager::Operation::DrawRaffle { raffle_id } => {
+                self.draw_raffle(raffle_id, current_time).await
+            }
+            predictive_manager::Operation::SetChestTable { table } => {
+                self.set_chest_table(player_id, table)
+            }
+            predictive_manager::Operation::OpenChest { chest_id } => {
+                self.open_chest(player_id, chest_id, current_time)
+                    .await
+                    .map(|contents| response = OperationResponse::ChestOpened { chest_id, contents })
+            }
+            predictive_manager::Operation::CreateSeason {
+                name,
+                starts_at,
+                duration_seconds,
+                premium_price,
+                tiers,
+            } => {
+                self.create_season(player_id, name, starts_at, duration_seconds, premium_price, tiers, current_time)
+                    .await
+                    .map(|season_id| response = OperationResponse::SeasonCreated { season_id })
+            }
+            predictive_manager::Operation::SetPassTiers { season_id, tiers } => {
+                self.set_pass_tiers(player_id, season_id, tiers, current_time).await
+            }
+            predictive_manager::Operation::BuyPremiumPass { season_id } => {
+                self.buy_premium_pass(player_id, season_id, current_time).await
+            }
+            predictive_manager::Operation::ClaimPassRewards { season_id } => {
+                self.claim_pass_rewards(player_id, season_id).await
+            }
+            predictive_manager::Operation::SetLeagueRewards { tier, rewards } => {
+                self.set_league_rewards(player_id, tier, rewards)
+            }
+            predictive_manager::Operation::SettleGuildLeagues { season_id } => {
+                self.settle_guild_leagues(season_id, current_time).await
+            }
+            predictive_manager::Operation::RefreshLeaderboard => {
+                self.refresh_leaderboard(player_id, current_time).await
+            }
+            predictive_manager::Operation::SetFeaturedMarkets { market_ids } => {
+                self.set_featured_markets(player_id, market_ids).await
+            }
+            predictive_manager::Operation::SelectFeaturedMarket => {
+                self.select_featured_market(current_time).await
+            }
+            predictive_manager::Operation::RecordView { market_id } => {
+                self.record_view(player_id, market_id, current_time).await
+            }
+            predictive_manager::Operation::RecordViews { views } => {
+                self.record_views(player_id, views, current_time).await
+            }
+            predictive_manager::Operation::RequestAttestation { claim, recipient } => {
+                self.request_attestation(player_id, claim, recipient, current_time)
+                    .await
+                    .map(|(attestation_id, hash)| response = OperationResponse::AttestationIssued { attestation_id, hash })
+            }
+            predictive_manager::Operation::SetEmergencyMode { enabled } => {
+                self.set_emergency_mode(player_id, enabled)
+            }
+            predictive_manager::Operation::SetAnnouncement { text, severity, expires_at } => {
+                self.set_announcement(player_id, text, severity, expires_at, current_time).await
+            }
+            predictive_manager::Operation::ClearAnnouncement => {
+                self.clear_announcement(player_id).await
+            }
+            predictive_manager::Operation::SetNotificationFilter { filter } => {
+                self.set_notification_filter(player_id, filter)
+            }
+            predictive_manager::Operation::EmergencyWithdraw => {
+                self.emergency_withdraw(player_id).await
+            }
+        };
+        if result.is_ok() {
+            self.log_event(EventSource::Operation, Some(player_id), payload);
         }
+        response
     }
 
     async fn execute_message(&mut self, message: Message) {
-        match message {
-            Message::MarketCreated { .. } => {}
-            Message::MarketResolved { .. } => {}
-            Message::TradeExecuted { .. } => {}
-            Message::PlayerLeveledUp { .. } => {}
-            Message::AchievementUnlocked { .. } => {}
-            Message::GuildCreated { .. } => {}
+        let payload = serde_json::to_value(&message).unwrap_or_default();
+        let result = match message {
+            // Notifications don't change state, so they aren't logged
+            Message::MarketCreated { .. }
+            | Message::MarketResolved { .. }
+            | Message::TradeExecuted { .. }
+            | Message::PlayerLeveledUp { .. }
+            | Message::AchievementUnlocked { .. }
+            | Message::GuildCreated { .. }
+            | Message::GuildAchievementUnlocked { .. }
+            | Message::ClaimExpiring { .. } => return,
+            Message::RegisterChain => {
+                self.register_network_chain().await
+            }
+            Message::SyncConfig { config } => {
+                self.sync_config(*config)
+            }
+            Message::SyncAnnouncement { announcement } => {
+                self.sync_announcement(announcement)
+            }
+            Message::PlayerStatsUpdated { player_id, delta } => {
+                self.apply_player_stats(player_id, delta).await
+            }
+            Message::SettleWinners { market_id, after } => {
+                self.settle_winners(market_id, after).await
+            }
+            Message::AttestationIssued { attestation } => {
+                self.receive_attestation(attestation)
+            }
+        };
+        if result.is_ok() {
+            self.log_event(EventSource::Message, None, payload);
         }
     }
 
 
-    async fn store(self) {
-        // State is automatically saved by Linera SDK
+    async fn store(mut self) {
+        self.state.save().await.expect("Failed to save state");
     }
 }
 
@@ -216,6 +850,8 @@ impl PredictionMarketContract {
                 reward_tokens: Amount::from_tokens(50),
                 reward_xp: 100,
                 requirement: AchievementRequirement::ParticipateInMarkets(1),
+                localized_names: BTreeMap::new(),
+                localized_descriptions: BTreeMap::new(),
             },
             Achievement {
                 id: 2,
@@ -224,6 +860,8 @@ impl PredictionMarketContract {
                 reward_tokens: Amount::from_tokens(200),
                 reward_xp: 500,
                 requirement: AchievementRequirement::CreateMarkets(5),
+                localized_names: BTreeMap::new(),
+                localized_descriptions: BTreeMap::new(),
             },
             Achievement {
                 id: 3,
@@ -232,6 +870,8 @@ impl PredictionMarketContract {
                 reward_tokens: Amount::from_tokens(500),
                 reward_xp: 1000,
                 requirement: AchievementRequirement::WinMarkets(10),
+                localized_names: BTreeMap::new(),
+                localized_descriptions: BTreeMap::new(),
             },
             Achievement {
                 id: 4,
@@ -240,6 +880,8 @@ impl PredictionMarketContract {
                 reward_tokens: Amount::from_tokens(300),
                 reward_xp: 750,
                 requirement: AchievementRequirement::WinStreak(5),
+                localized_names: BTreeMap::new(),
+                localized_descriptions: BTreeMap::new(),
             },
             Achievement {
                 id: 5,
@@ -248,6 +890,8 @@ impl PredictionMarketContract {
                 reward_tokens: Amount::from_tokens(1000),
                 reward_xp: 2000,
                 requirement: AchievementRequirement::TotalProfit(Amount::from_tokens(1000)),
+                localized_names: BTreeMap::new(),
+                localized_descriptions: BTreeMap::new(),
             },
             Achievement {
                 id: 6,
@@ -256,6 +900,8 @@ impl PredictionMarketContract {
                 reward_tokens: Amount::from_tokens(150),
                 reward_xp: 300,
                 requirement: AchievementRequirement::JoinGuild,
+                localized_names: BTreeMap::new(),
+                localized_descriptions: BTreeMap::new(),
             },
             Achievement {
                 id: 7,
@@ -264,6 +910,8 @@ impl PredictionMarketContract {
                 reward_tokens: Amount::from_tokens(400),
                 reward_xp: 1000,
                 requirement: AchievementRequirement::ReachLevel(10),
+                localized_names: BTreeMap::new(),
+                localized_descriptions: BTreeMap::new(),
             },
         ];
         
@@ -273,6 +921,46 @@ impl PredictionMarketContract {
         Ok(())
     }
 
+    /// Initialize the guild achievement system
+    /// Collective milestones whose rewards go to the guild's shared pool
+    async fn initialize_guild_achievements(&mut self) -> Result<(), ContractError> {
+        let achievements = vec![
+            GuildAchievement {
+                id: 1,
+                name: "Seed Fund".to_string(),
+                description: "Contribute 100 tokens to the shared pool".to_string(),
+                reward_tokens: Amount::from_tokens(25),
+                requirement: GuildAchievementRequirement::PoolContributed(Amount::from_tokens(100)),
+            },
+            GuildAchievement {
+                id: 2,
+                name: "War Chest".to_string(),
+                description: "Contribute 1000 tokens to the shared pool".to_string(),
+                reward_tokens: Amount::from_tokens(150),
+                requirement: GuildAchievementRequirement::PoolContributed(Amount::from_tokens(1000)),
+            },
+            GuildAchievement {
+                id: 3,
+                name: "Recruiters".to_string(),
+                description: "Recruit 5 members".to_string(),
+                reward_tokens: Amount::from_tokens(50),
+                requirement: GuildAchievementRequirement::MembersRecruited(5),
+            },
+            GuildAchievement {
+                id: 4,
+                name: "Full House".to_string(),
+                description: "Recruit 20 members".to_string(),
+                reward_tokens: Amount::from_tokens(250),
+                requirement: GuildAchievementRequirement::MembersRecruited(20),
+            },
+        ];
+
+        for achievement in achievements {
+            self.state.guild_achievements.insert(&achievement.id.clone(), achievement)?;
+        }
+        Ok(())
+    }
+
     /// Register a new player in the prediction market game
     /// Creates a player account with initial tokens and sets up their profile
     /// 
@@ -317,21 +1005,35 @@ impl PredictionMarketContract {
             best_win_streak: 0,
             guild_id: None,
             achievements_earned: Vec::new(),
-            active_markets: Vec::new(),
+            recovery_owner: None,
+            recovery_linked_at: None,
+            risk_controls: RiskControls::default(),
+            equipped_items: EquippedItems::default(),
+            forecasts_scored: 0,
+            brier_score_total: 0,
+            trading_volume: Amount::ZERO,
+            privacy: PrivacySettings::default(),
         };
 
-        self.state.players.insert(&player_id, player)?;
+        let delta = PlayerStatsDelta {
+            display_name: player.display_name.clone(),
+            level: player.level,
+            ..PlayerStatsDelta::default()
+        };
+        self.save_player(player).await?;
+        self.report_player_stats(player_id, delta);
 
         let total_supply = self.state.total_supply.get().saturating_add(initial_tokens);
         self.state.total_supply.set(total_supply);
-        Ok(())
+        self.record_token_flow(TokenFlow::RegistrationBonus, initial_tokens).await
     }
     /// Update a player's profile information
-    /// Allows players to change their display name
+    /// Allows players to change their display name and privacy settings
     /// 
     /// # Arguments
     /// * `player_id` - The player to update
     /// * `display_name` - New display name (can be None to clear)
+    /// * `privacy` - New privacy settings (None keeps the current ones)
     /// 
     /// # Returns
     /// * `Ok(())` - Profile updated successfully
@@ -340,10 +1042,14 @@ impl PredictionMarketContract {
         &mut self,
         player_id: PlayerId,
         display_name: Option<String>,
+        privacy: Option<PrivacySettings>,
     ) -> Result<(), ContractError> {
         let mut player = self.get_player(&player_id).await?;
         player.display_name = display_name;
-        self.state.players.insert(&player_id, player)?;
+        if let Some(privacy) = privacy {
+            player.privacy = privacy;
+        }
+        self.save_player(player).await?;
         Ok(())
     }
 
@@ -379,12 +1085,12 @@ impl PredictionMarketContract {
         player.token_balance = player.token_balance.saturating_add(reward);
         player.total_earned = player.total_earned.saturating_add(reward);
         player.last_login = current_time;
-        self.state.players.insert(&player_id, player)?;
+        self.save_player(player).await?;
 
         let total_supply = self.state.total_supply.get();
         let new_total = total_supply.saturating_add(reward);
         self.state.total_supply.set(new_total);
-        Ok(())
+        self.record_token_flow(TokenFlow::DailyReward, reward).await
     }
 
     /// Create a new prediction market
@@ -398,13 +1104,31 @@ impl PredictionMarketContract {
     /// * `outcome_names` - List of possible outcomes (minimum 2)
     /// * `duration_seconds` - How long the market stays active
     /// * `resolution_method` - How the market will be resolved (Oracle, Automated, Creator)
+    /// * `payout_weights` - Payout weight in basis points for each winning rank
+    /// * `visibility` - Who may trade and vote (Public, GuildOnly, Whitelist)
+    /// * `allowed_guild` - The guild a GuildOnly market is restricted to
+    /// * `allowlist` - Players allowed into a Whitelist market
+    /// * `metadata` - Image, resolution source and locale for rendering the market
+    /// * `allow_early_close` - Whether the creator may close the market before its end time
+    /// * `bootstrap_seconds` - Optional commitment phase before trading opens
+    /// * `blind_finish_seconds` - Optional sealed-trade phase before the end time
+    /// * `creation_cost` - Tokens charged to the creator
     /// * `current_time` - Current timestamp for market timing
     /// 
     /// # Returns
-    /// * `Ok(())` - Market created successfully
-    /// * `Err(InsufficientBalance)` - Creator doesn't have enough tokens for creation cost
+    /// * `Ok(market_id)` - Market created successfully
+    /// * `Err(InsufficientBalance)` - Creator doesn't have enough tokens for creation cost and bond
+    /// * `Err(TooManyActiveMarkets)` - Creator is at their cap of active markets
     /// * `Err(InvalidOutcomeCount)` - Too few or too many outcomes
     /// * `Err(DurationTooShort)` - Market duration below minimum
+    /// * `Err(InvalidPayoutWeights)` - Weights are empty, exceed the outcome count, or sum above 100%,
+    ///   or a designated oracle market has more than one winning rank
+    /// * `Err(InvalidScalarRange)` - Scalar range is empty
+    /// * `Err(InvalidVisibility)` - GuildOnly market without a guild the creator belongs to
+    /// * `Err(InvalidMetadata)` - Metadata fields are empty or too long, there are too many
+    ///   translations, or outcome display hints are malformed or outnumber the outcomes
+    /// * `Err(InvalidBlindFinish)` - Blind finish is zero or longer than the market
+    #[allow(clippy::too_many_arguments)]
     async fn create_market(
         &mut self,
         creator: PlayerId,
@@ -414,11 +1138,23 @@ impl PredictionMarketContract {
         outcome_names: Vec<String>,
         duration_seconds: u64,
         resolution_method: ResolutionMethod,
+        payout_weights: Vec<u32>,
+        visibility: MarketVisibility,
+        allowed_guild: Option<GuildId>,
+        allowlist: Vec<PlayerId>,
+        mut metadata: MarketMetadata,
+        allow_early_close: bool,
+        bootstrap_seconds: Option<u64>,
+        blind_finish_seconds: Option<u64>,
+        creation_cost: Amount,
+        creator_bond: Amount,
         current_time: Timestamp,
-    ) -> Result<(), ContractError> {
+    ) -> Result<MarketId, ContractError> {
         let config = self.state.config.get();
-        let market_creation_cost = config.market_creation_cost;
+        let market_creation_cost = creation_cost;
+        let review_seconds = config.ambiguity_review_seconds;
         let mut player = self.get_player(&creator).await?;
+        self.ensure_below_market_cap(&player).await?;
 
         if outcome_names.len() < 2 || outcome_names.len() > config.max_outcomes_per_market {
             return Err(ContractError::InvalidOutcomeCount);
@@ -426,18 +1162,59 @@ impl PredictionMarketContract {
         if duration_seconds < config.min_market_duration_seconds {
             return Err(ContractError::DurationTooShort);
         }
-        if player.token_balance < market_creation_cost {
+        let total_weight: u64 = payout_weights.iter().map(|w| *w as u64).sum();
+        if payout_weights.is_empty()
+            || payout_weights.len() >= outcome_names.len()
+            || payout_weights.contains(&0)
+            || total_weight > BASIS_POINTS as u64
+            || (matches!(resolution_method, ResolutionMethod::DesignatedOracle { .. }) && payout_weights.len() > 1)
+        {
+            return Err(ContractError::InvalidPayoutWeights);
+        }
+        if let ResolutionMethod::DesignatedOracle { scalar_range: Some(range), .. } = resolution_method {
+            if range.min >= range.max {
+                return Err(ContractError::InvalidScalarRange);
+            }
+        }
+        let allowed_guild = match visibility {
+            MarketVisibility::GuildOnly => {
+                let guild_id = allowed_guild.ok_or(ContractError::InvalidVisibility)?;
+                if player.guild_id != Some(guild_id) {
+                    return Err(ContractError::InvalidVisibility);
+                }
+                Some(guild_id)
+            }
+            MarketVisibility::Public | MarketVisibility::Whitelist => None,
+        };
+        Self::validate_metadata(&metadata)?;
+        if metadata.outcome_display.len() > outcome_names.len() {
+            return Err(ContractError::InvalidMetadata);
+        }
+        let curve = config.curve_for(&market_type);
+        if !curve.is_valid() {
+            return Err(ContractError::InvalidCurveParams);
+        }
+        if bootstrap_seconds.is_some_and(|seconds| seconds == 0 || seconds > MAX_BOOTSTRAP_SECONDS) {
+            return Err(ContractError::InvalidBootstrap);
+        }
+        if blind_finish_seconds.is_some_and(|seconds| seconds == 0 || seconds > duration_seconds) {
+            return Err(ContractError::InvalidBlindFinish);
+        }
+        if player.token_balance < market_creation_cost.saturating_add(creator_bond) {
             return Err(ContractError::InsufficientBalance);
         }
         
         // Deduct market creation cost from player's points (no external transfer needed)
-        
+        // The bond is held on the market until it resolves
         player.token_balance = player
             .token_balance
-            .saturating_sub(market_creation_cost);
+            .saturating_sub(market_creation_cost)
+            .saturating_sub(creator_bond);
         player.total_spent = player.total_spent.saturating_add(market_creation_cost);
+        self.record_token_flow(TokenFlow::CreationCost, market_creation_cost).await?;
 
         let market_id = self.generate_market_id().await?;
+        let outcome_display = std::mem::take(&mut metadata.outcome_display);
         let outcomes: Vec<Outcome> = outcome_names
             .into_iter()
             .enumerate()
@@ -446,41 +1223,114 @@ impl PredictionMarketContract {
                 name,
                 total_shares: Amount::ZERO,
                 current_price: Amount::from_tokens(1),
+                holders: 0,
+                open_interest: Amount::ZERO,
+                display: outcome_display.get(i).cloned().unwrap_or_default(),
             })
             .collect();
 
-        let end_time = Timestamp::from(current_time.micros() + duration_seconds * 1_000_000);
+        // Trading runs for the full duration after any review window and bootstrapping phase
+        let review_end = (review_seconds > 0)
+            .then(|| Timestamp::from(current_time.micros() + review_seconds * 1_000_000));
+        let opens_at = review_end.unwrap_or(current_time);
+        let bootstrap_end = bootstrap_seconds
+            .map(|seconds| Timestamp::from(opens_at.micros() + seconds * 1_000_000));
+        let trading_start = bootstrap_end.unwrap_or(opens_at);
+        let end_time = self.round_end_time(Timestamp::from(trading_start.micros() + duration_seconds * 1_000_000));
+        let slug = self.claim_slug(market_id, &title).await?;
         let market = Market {
             id: market_id,
             creator,
             title,
+            slug: slug.clone(),
             description,
             market_type,
             outcomes,
             creation_time: current_time,
             end_time,
             resolution_time: None,
-            status: MarketStatus::Active,
+            status: if bootstrap_end.is_some() {
+                MarketStatus::Bootstrapping
+            } else {
+                MarketStatus::Active
+            },
             total_liquidity: Amount::ZERO,
             positions: BTreeMap::new(),
+            guild_positions: BTreeMap::new(),
             total_participants: 0,
             base_price: Amount::from_tokens(1),
-            smoothing_factor: 1.5,
+            curve,
             winning_outcome: None,
             resolution_method,
+            payout_weights,
+            winning_outcomes: Vec::new(),
+            visibility,
+            allowed_guild,
+            allowlist,
+            metadata,
+            edit_history: Vec::new(),
+            allow_early_close,
+            closed_early_at: None,
+            current_holders: 0,
+            volume: Amount::ZERO,
+            fees_collected: Amount::ZERO,
+            creator_fees_earned: Amount::ZERO,
+            thumbs_up: 0,
+            thumbs_down: 0,
+            bootstrap_end,
+            bootstrap_commitments: Vec::new(),
+            blind_finish_seconds: blind_finish_seconds.unwrap_or(0),
+            blind_commitments: Vec::new(),
+            claims_expire_at: None,
+            expiry_warning_sent: false,
+            resolution_evidence: Vec::new(),
+            oracle_value: None,
+            auctions: Vec::new(),
+            share_offers: Vec::new(),
+            bot_quotes: Vec::new(),
+            group_id: None,
+            pending_correction: None,
+            voting_start: None,
+            voting_end: None,
+            creator_volume: Amount::ZERO,
+            creator_bond,
+            bond_status: BondStatus::Held,
+            review_end,
+            ambiguity_flags: Vec::new(),
+            paused_at: None,
         };
 
+        let keywords = search::market_keywords(&market.title, &market.description);
+        self.update_search_index(market_id, &BTreeSet::new(), &keywords).await?;
+        let mut stats = self.state.creator_stats.get(&creator).await?.unwrap_or_default();
+        stats.markets_created += 1;
+        self.state.creator_stats.insert(&creator, stats)?;
+        self.state.markets_by_end_time.insert(&MarketEndKey { end_time, market_id })?;
         self.state.markets.insert(&market_id, market)?;
-        self.state.players.insert(&creator, player)?;
+        self.save_player(player).await?;
 
         // Distribute market creation fee to creator (if any)
         self.distribute_market_creator_fee(creator, market_creation_cost).await?;
 
         self
             .runtime
-            .prepare_message(Message::MarketCreated { market_id, creator })
+            .prepare_message(Message::MarketCreated { market_id, creator, slug })
             .send_to(self.runtime.chain_id());
 
+        Ok(market_id)
+    }
+
+    /// Reject a new market from a creator already at their cap of active markets; the admin
+    /// is never capped
+    async fn ensure_below_market_cap(&self, creator: &Player) -> Result<(), ContractError> {
+        if self.ensure_admin(creator.id).is_ok() {
+            return Ok(());
+        }
+        let stats = self.state.creator_stats.get(&creator.id).await?.unwrap_or_default();
+        let limit = self.state.config.get().creator_market_cap.limit(creator.level, &stats);
+        if limit.is_some_and(|limit| stats.active_markets() >= limit) {
+            return Err(ContractError::TooManyActiveMarkets);
+        }
         Ok(())
     }
 
@@ -493,14 +1343,22 @@ impl PredictionMarketContract {
     /// * `outcome_id` - Which outcome to buy shares for
     /// * `amount` - How many tokens to invest
     /// * `max_price_per_share` - Maximum price willing to pay per share (slippage protection)
+    /// * `valid_until` - Optional deadline after which the order is no longer executed
     /// * `current_time` - Current timestamp for market timing
     /// 
     /// # Returns
-    /// * `Ok(())` - Shares purchased successfully
+    /// * `Ok(fill)` - Shares purchased successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(OrderExpired)` - The block time is past the order's deadline
     /// * `Err(MarketNotActive)` - Market is not active
     /// * `Err(MarketEnded)` - Market has already ended
     /// * `Err(InsufficientBalance)` - Player doesn't have enough tokens
     /// * `Err(SlippageExceeded)` - Price per share exceeds maximum
+    /// * `Err(PriceImpactExceeded)` - Buy would take the player's price impact in this market's
+    ///   current window past its limit and `reject_oversized_buys` is set, or the limit is used
+    ///   up; otherwise only the part within the limit is filled
+    /// * `Err(MarketAccessDenied)` - Player is not allowed into a private market
+    #[allow(clippy::too_many_arguments)]
     async fn buy_shares(
         &mut self,
         player_id: PlayerId,
@@ -508,73 +1366,86 @@ impl PredictionMarketContract {
         outcome_id: OutcomeId,
         amount: Amount,
         max_price_per_share: Amount,
+        valid_until: Option<Timestamp>,
         current_time: Timestamp,
-    ) -> Result<(), ContractError> {
+    ) -> Result<TradeFill, ContractError> {
+        Self::ensure_order_valid(valid_until, current_time)?;
         let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
         let mut player = self.get_player(&player_id).await?;
 
+        Self::ensure_market_access(&market, &player)?;
         if market.status != MarketStatus::Active {
             return Err(ContractError::MarketNotActive);
         }
-        if current_time >= market.end_time {
-            return Err(ContractError::MarketEnded);
-        }
+        self.ensure_trading_open(&market, current_time)?;
         if outcome_id >= market.outcomes.len() as OutcomeId {
             return Err(ContractError::InvalidOutcome);
         }
+        let mut impact = self.price_impact_window(market_id, player_id, current_time).await?;
+        let filled = market.buy_within_impact_limit(outcome_id, amount, impact.used_bps);
+        if filled == Amount::ZERO || (filled < amount && self.state.config.get().reject_oversized_buys) {
+            return Err(ContractError::PriceImpactExceeded);
+        }
+        let amount = filled;
         if player.token_balance < amount {
             return Err(ContractError::InsufficientBalance);
         }
+        Self::charge_risk_limits(&mut player, amount, current_time)?;
 
-        // Deduct bet amount from player's points (no external transfer needed)
-
-        let shares = self.calculate_shares_for_amount(&market, outcome_id, amount)?;
-        // Avoid dividing Amount by Amount; compare totals instead
-        if amount > max_price_per_share {
+        // The player pays exactly `amount`; the fee comes out of it and the rest buys shares
+        let (trade, shares) = self.price_buy(&market, outcome_id, Tokens::new(amount), current_time)?;
+        let max_price = PricePerShare::new(max_price_per_share);
+        if PricePerShare::average(trade.gross, shares).is_none_or(|price| price > max_price) {
             return Err(ContractError::SlippageExceeded);
         }
+        let shares = shares.amount();
 
-        market.outcomes[outcome_id as usize].total_shares =
-            market.outcomes[outcome_id as usize]
-                .total_shares
-                .saturating_add(shares);
-        market.total_liquidity = market.total_liquidity.saturating_add(amount);
-
-        let position = market
-            .positions
-            .entry(player_id)
-            .or_insert(PlayerPosition {
-                shares_by_outcome: BTreeMap::new(),
-                total_invested: Amount::ZERO,
-                entry_time: current_time,
-            });
-        let current_shares = position
-            .shares_by_outcome
-            .get(&outcome_id)
-            .copied()
-            .unwrap_or(Amount::ZERO);
-        position
-            .shares_by_outcome
-            .insert(outcome_id, current_shares.saturating_add(shares));
-        position.total_invested = position.total_invested.saturating_add(amount);
+        let probability = market.implied_probability_bps(outcome_id);
+        market.add_shares(player_id, outcome_id, shares, trade.net.amount(), current_time);
+        market.total_liquidity = market.total_liquidity.saturating_add(trade.net.amount());
+        if market.curve.max_price_impact_bps > 0 {
+            impact.used_bps += market.implied_probability_bps(outcome_id).saturating_sub(probability);
+            let mut windows = self.state.price_impact_windows.try_load_entry_mut(&market_id).await?;
+            windows.insert(&player_id, impact)?;
+        }
 
-        if !player.active_markets.contains(&market_id) {
-            player.active_markets.push(market_id);
-            market.total_participants += 1;
+        let first_entry = self.join_market(player_id, market_id).await?;
+        if first_entry {
+            self.add_participant(&mut market, player_id).await?;
+            player.markets_participated += 1;
         }
+        let stake = Tokens::new(amount);
+        let balance = Tokens::new(player.token_balance);
         player.token_balance = player.token_balance.saturating_sub(amount);
         player.total_spent = player.total_spent.saturating_add(amount);
-        player.markets_participated += 1;
-        self.add_experience(&mut player, 10).await?;
+        player.trading_volume = player.trading_volume.saturating_add(amount);
+        let xp = self.trade_xp(market_id, player_id, first_entry, stake, balance, current_time).await?;
+        let xp = self.event_boost(&market, current_time).xp(xp);
+        let xp = self.featured_xp(market_id, xp, current_time).await?;
+        let xp = self.self_trade_xp(&market, player_id, xp);
+        self.add_experience(&mut player, xp).await?;
+        self.share_mentorship_xp(&mut player, xp).await?;
 
         market.outcomes[outcome_id as usize].current_price =
-            self.calculate_current_price(&market, outcome_id)?;
+            self.calculate_current_price(&market, outcome_id)?.amount();
 
+        let delta = PlayerStatsDelta {
+            level: player.level,
+            spent: amount,
+            markets_participated: u64::from(first_entry),
+            volume: amount,
+            ..PlayerStatsDelta::default()
+        };
         self.state.markets.insert(&market_id, market)?;
-        self.state.players.insert(&player_id, player)?;
+        self.save_player(player).await?;
+        self.report_player_stats(player_id, delta);
 
         // Distribute trading fees to market creator
-        self.distribute_trading_fees(market_id, amount).await?;
+        self.distribute_trading_fees(market_id, Some(player_id), amount, trade.fee_bps, current_time).await?;
+        self.pay_maker_rebate(player_id, trade.fee).await?;
+        self.record_market_activity(market_id, outcome_id, TradeSide::Buy, amount, current_time).await?;
+        self.notify_large_trade(market_id, player_id, outcome_id, amount);
 
         self
             .runtime
@@ -586,367 +1457,6202 @@ impl PredictionMarketContract {
                 price: amount,
             })
             .send_to(self.runtime.chain_id());
-        Ok(())
+        Ok(TradeFill::new(market_id, outcome_id, shares, trade))
     }
 
-    /// Sell shares in a market outcome
-    /// Allows players to sell their existing shares for tokens
-    /// 
-    /// # Arguments
-    /// * `player_id` - The player selling shares
-    /// * `market_id` - The market to sell shares in
-    /// * `outcome_id` - Which outcome to sell shares for
-    /// * `shares` - How many shares to sell
-    /// * `min_price_per_share` - Minimum price willing to accept per share (slippage protection)
-    /// * `current_time` - Current timestamp for market timing
+    /// Commit tokens to an outcome during a market's bootstrapping phase
+    /// Commitments are priced together when the phase ends, so everyone committing to
+    /// the same outcome pays the same average price
     /// 
     /// # Returns
-    /// * `Ok(())` - Shares sold successfully
-    /// * `Err(MarketNotActive)` - Market is not active
-    /// * `Err(NoPosition)` - Player has no position in this market
-    /// * `Err(InsufficientShares)` - Player doesn't have enough shares to sell
-    /// * `Err(SlippageExceeded)` - Price per share below minimum
-    async fn sell_shares(
+    /// * `Ok(())` - Commitment recorded and tokens taken from the player's balance
+    /// * `Err(NotBootstrapping)` - Market is not bootstrapping, or the phase has ended
+    /// * `Err(InvalidOutcome)` - Outcome doesn't exist
+    /// * `Err(InsufficientBalance)` - Player doesn't have enough tokens
+    /// * `Err(MarketAccessDenied)` - Player is not allowed into a private market
+    async fn commit_to_outcome(
         &mut self,
         player_id: PlayerId,
         market_id: MarketId,
         outcome_id: OutcomeId,
-        shares: Amount,
-        min_price_per_share: Amount,
+        amount: Amount,
         current_time: Timestamp,
     ) -> Result<(), ContractError> {
         let mut market = self.get_market(&market_id).await?;
         let mut player = self.get_player(&player_id).await?;
 
-        if market.status != MarketStatus::Active {
-            return Err(ContractError::MarketNotActive);
+        Self::ensure_market_access(&market, &player)?;
+        Self::ensure_reviewed(&market, current_time)?;
+        if market.status != MarketStatus::Bootstrapping
+            || market.bootstrap_end.is_none_or(|end| current_time >= end)
+        {
+            return Err(ContractError::NotBootstrapping);
         }
-
-        let position = market.positions.get(&player_id).ok_or(ContractError::NoPosition)?;
-        let owned_shares = position
-            .shares_by_outcome
-            .get(&outcome_id)
-            .copied()
-            .unwrap_or(Amount::ZERO);
-        if owned_shares < shares {
-            return Err(ContractError::InsufficientShares);
+        if outcome_id >= market.outcomes.len() as OutcomeId {
+            return Err(ContractError::InvalidOutcome);
         }
-
-        let sell_value = self.calculate_sell_value(&market, outcome_id, shares)?;
-        // Avoid dividing Amount by Amount; compare totals instead
-        if sell_value < min_price_per_share {
-            return Err(ContractError::SlippageExceeded);
+        if amount == Amount::ZERO || player.token_balance < amount {
+            return Err(ContractError::InsufficientBalance);
         }
+        Self::charge_risk_limits(&mut player, amount, current_time)?;
 
-        market.outcomes[outcome_id as usize].total_shares =
-            market.outcomes[outcome_id as usize]
-                .total_shares
-                .saturating_sub(shares);
-        market.total_liquidity = market.total_liquidity.saturating_sub(sell_value);
-
-        let position = market.positions.get_mut(&player_id).unwrap();
-        let new_shares = owned_shares.saturating_sub(shares);
-        if new_shares == Amount::ZERO {
-            position.shares_by_outcome.remove(&outcome_id);
-        } else {
-            position.shares_by_outcome.insert(outcome_id, new_shares);
+        match market
+            .bootstrap_commitments
+            .iter_mut()
+            .find(|commitment| commitment.player == player_id && commitment.outcome_id == outcome_id)
+        {
+            Some(commitment) => commitment.amount = commitment.amount.saturating_add(amount),
+            None => market.bootstrap_commitments.push(BootstrapCommitment {
+                player: player_id,
+                outcome_id,
+                amount,
+            }),
         }
-
-        // Add sell value to player's points (no external transfer needed)
-
-        player.token_balance = player.token_balance.saturating_add(sell_value);
-        market.outcomes[outcome_id as usize].current_price =
-            self.calculate_current_price(&market, outcome_id)?;
+        if self.join_market(player_id, market_id).await? {
+            self.add_participant(&mut market, player_id).await?;
+            player.markets_participated += 1;
+        }
+        player.token_balance = player.token_balance.saturating_sub(amount);
+        player.total_spent = player.total_spent.saturating_add(amount);
 
         self.state.markets.insert(&market_id, market)?;
-        self.state.players.insert(&player_id, player)?;
-        
-        // Distribute trading fees to market creator
-        self.distribute_trading_fees(market_id, sell_value).await?;
-        
-        let _ = current_time; // not used in this minimal implementation
+        self.save_player(player).await?;
         Ok(())
     }
 
-    /// Vote on the outcome of a market
-    /// Allows players to vote on which outcome should win (for OracleVoting resolution)
-    /// 
-    /// # Arguments
-    /// * `voter_id` - The player casting the vote
-    /// * `market_id` - The market to vote on
-    /// * `outcome_id` - Which outcome the player thinks will win
-    /// * `current_time` - Current timestamp for voting period
+    /// Open trading on a bootstrapped market once its commitment phase is over
+    /// Anyone may call this. Each outcome's commitments buy shares on the curve as a single
+    /// order and every committer receives shares in proportion to what they committed
+    async fn finalize_bootstrap(&mut self, market_id: MarketId, current_time: Timestamp) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Bootstrapping {
+            return Err(ContractError::NotBootstrapping);
+        }
+        if market.bootstrap_end.is_some_and(|end| current_time < end) {
+            return Err(ContractError::BootstrapNotEnded);
+        }
+
+        let curve = market.bonding_curve();
+        let commitments = std::mem::take(&mut market.bootstrap_commitments);
+        for outcome_id in 0..market.outcomes.len() as OutcomeId {
+            let committed: u128 = commitments
+                .iter()
+                .filter(|commitment| commitment.outcome_id == outcome_id)
+                .map(|commitment| u128::from(commitment.amount))
+                .sum();
+            if committed == 0 {
+                continue;
+            }
+            let outcome_shares = curve.shares_for_amount(0, committed);
+            for commitment in commitments.iter().filter(|commitment| commitment.outcome_id == outcome_id) {
+                let shares = math::mul_div(outcome_shares, u128::from(commitment.amount), committed);
+                market.add_shares(
+                    commitment.player,
+                    outcome_id,
+                    Amount::from_attos(shares),
+                    commitment.amount,
+                    current_time,
+                );
+            }
+            market.total_liquidity = market.total_liquidity.saturating_add(Amount::from_attos(committed));
+            market.outcomes[outcome_id as usize].current_price =
+                self.calculate_current_price(&market, outcome_id)?.amount();
+        }
+        market.bootstrap_commitments = commitments;
+        market.transition_status(MarketStatus::Bootstrapping, MarketStatus::Active)?;
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Seal a trade during a market's blind finish
+    /// The tokens leave the player's balance now; which outcome they buy stays hidden
+    /// until `reveal_blind_trade`, so late trades can't be read and front-run
     /// 
     /// # Returns
-    /// * `Ok(())` - Vote cast successfully
-    /// * `Err(MarketNotReadyForVoting)` - Market is not in voting phase
-    /// * `Err(InvalidResolutionMethod)` - Market doesn't use OracleVoting
-    /// * `Err(AlreadyVoted)` - Player has already voted in this market
-    async fn vote_on_outcome(
+    /// * `Ok(())` - Commitment recorded and tokens escrowed
+    /// * `Err(NotInBlindFinish)` - Market isn't active or isn't in its blind finish
+    /// * `Err(InsufficientBalance)` - Player doesn't have enough tokens
+    /// * `Err(MarketAccessDenied)` - Player is not allowed into a private market
+    async fn commit_blind_trade(
         &mut self,
-        voter_id: PlayerId,
+        player_id: PlayerId,
+        market_id: MarketId,
+        commitment: CryptoHash,
+        amount: Amount,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        let mut player = self.get_player(&player_id).await?;
+
+        Self::ensure_market_access(&market, &player)?;
+        if market.status != MarketStatus::Active || !market.in_blind_finish(current_time) {
+            return Err(ContractError::NotInBlindFinish);
+        }
+        if amount == Amount::ZERO || player.token_balance < amount {
+            return Err(ContractError::InsufficientBalance);
+        }
+        Self::charge_risk_limits(&mut player, amount, current_time)?;
+
+        market.blind_commitments.push(BlindCommitment {
+            player: player_id,
+            commitment,
+            amount,
+            committed_at: current_time,
+            revealed: false,
+        });
+        player.token_balance = player.token_balance.saturating_sub(amount);
+        player.total_spent = player.total_spent.saturating_add(amount);
+        // Tracked now so emergency withdrawals find the escrow; it counts as participation
+        // once revealed
+        self.join_market(player_id, market_id).await?;
+
+        self.state.markets.insert(&market_id, market)?;
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    /// Reveal a sealed trade once the market has ended and buy shares with its escrow
+    /// Reveals are accepted until `Market::reveal_deadline`; trades still sealed when the
+    /// market resolves are forfeited to the pool
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Shares bought with the escrowed tokens
+    /// * `Err(RevealWindowClosed)` - Market hasn't ended, or the reveal window has passed
+    /// * `Err(InvalidOutcome)` - Outcome doesn't exist
+    /// * `Err(InvalidReveal)` - No unrevealed commitment of the player matches
+    async fn reveal_blind_trade(
+        &mut self,
+        player_id: PlayerId,
         market_id: MarketId,
         outcome_id: OutcomeId,
+        salt: String,
         current_time: Timestamp,
     ) -> Result<(), ContractError> {
-        let market = self.get_market(&market_id).await?;
-        let player = self.get_player(&voter_id).await?;
+        let mut market = self.get_market(&market_id).await?;
+        let mut player = self.get_player(&player_id).await?;
 
-        if market.status != MarketStatus::Closed {
-            return Err(ContractError::MarketNotReadyForVoting);
+        if !matches!(market.status, MarketStatus::Active | MarketStatus::Closed | MarketStatus::Voting)
+            || current_time < market.end_time
+            || current_time >= market.reveal_deadline()
+        {
+            return Err(ContractError::RevealWindowClosed);
         }
-        if !matches!(market.resolution_method, ResolutionMethod::OracleVoting) {
-            return Err(ContractError::InvalidResolutionMethod);
+        if outcome_id >= market.outcomes.len() as OutcomeId {
+            return Err(ContractError::InvalidOutcome);
+        }
+        let commitment = BlindTrade { market_id, player: player_id, outcome_id, salt }.commitment();
+        let sealed = market
+            .blind_commitments
+            .iter_mut()
+            .find(|sealed| sealed.player == player_id && sealed.commitment == commitment && !sealed.revealed)
+            .ok_or(ContractError::InvalidReveal)?;
+        sealed.revealed = true;
+        let amount = sealed.amount;
+        let committed_at = sealed.committed_at;
+
+        // Priced as if traded when committed, not at reveal after close
+        let (trade, shares) = self.price_buy(&market, outcome_id, Tokens::new(amount), committed_at)?;
+        let shares = shares.amount();
+        let first_position = !market.positions.contains_key(&player_id);
+        market.add_shares(player_id, outcome_id, shares, trade.net.amount(), current_time);
+        market.total_liquidity = market.total_liquidity.saturating_add(trade.net.amount());
+        if first_position {
+            self.add_participant(&mut market, player_id).await?;
+            player.markets_participated += 1;
+        }
+        player.trading_volume = player.trading_volume.saturating_add(amount);
+        // The stake left the balance when committed
+        let stake = Tokens::new(amount);
+        let balance = Tokens::new(player.token_balance).saturating_add(stake);
+        let xp = self.trade_xp(market_id, player_id, first_position, stake, balance, committed_at).await?;
+        let xp = self.event_boost(&market, committed_at).xp(xp);
+        let xp = self.featured_xp(market_id, xp, committed_at).await?;
+        let xp = self.self_trade_xp(&market, player_id, xp);
+        self.add_experience(&mut player, xp).await?;
+        self.share_mentorship_xp(&mut player, xp).await?;
+        market.outcomes[outcome_id as usize].current_price =
+            self.calculate_current_price(&market, outcome_id)?.amount();
+
+        self.state.markets.insert(&market_id, market)?;
+        self.save_player(player).await?;
+        self.distribute_trading_fees(market_id, Some(player_id), amount, trade.fee_bps, current_time).await?;
+        self.pay_maker_rebate(player_id, trade.fee).await?;
+        self.record_market_activity(market_id, outcome_id, TradeSide::Buy, amount, current_time).await?;
+        self.notify_large_trade(market_id, player_id, outcome_id, amount);
+
+        self
+            .runtime
+            .prepare_message(Message::TradeExecuted {
+                player_id,
+                market_id,
+                outcome_id,
+                shares,
+                price: amount,
+            })
+            .send_to(self.runtime.chain_id());
+        Ok(())
+    }
+
+    /// Sell shares in a market outcome
+    /// Allows players to sell their existing shares for tokens
+    /// 
+    /// # Arguments
+    /// * `player_id` - The player selling shares
+    /// * `market_id` - The market to sell shares in
+    /// * `outcome_id` - Which outcome to sell shares for
+    /// * `shares` - How many shares to sell
+    /// * `min_price_per_share` - Minimum price willing to accept per share (slippage protection)
+    /// * `valid_until` - Optional deadline after which the order is no longer executed
+    /// * `current_time` - Current timestamp for market timing
+    /// 
+    /// # Returns
+    /// * `Ok(fill)` - Shares sold successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(OrderExpired)` - The block time is past the order's deadline
+    /// * `Err(MarketNotActive)` - Market is not active
+    /// * `Err(NoPosition)` - Player has no position in this market
+    /// * `Err(InsufficientShares)` - Player doesn't have enough shares to sell
+    /// * `Err(SlippageExceeded)` - Price per share below minimum
+    #[allow(clippy::too_many_arguments)]
+    async fn sell_shares(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+        min_price_per_share: Amount,
+        valid_until: Option<Timestamp>,
+        current_time: Timestamp,
+    ) -> Result<TradeFill, ContractError> {
+        Self::ensure_order_valid(valid_until, current_time)?;
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let mut player = self.get_player(&player_id).await?;
+
+        // No access check: a holder dropped from the allowlist or the guild can still exit
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+
+        let position = market.positions.get(&player_id).ok_or(ContractError::NoPosition)?;
+        let owned_shares = position
+            .shares_by_outcome
+            .get(&outcome_id)
+            .copied()
+            .unwrap_or(Amount::ZERO);
+        if owned_shares < shares {
+            return Err(ContractError::InsufficientShares);
+        }
+
+        // The shares' value leaves the pool; the fee comes out of it and the seller gets the rest
+        let trade = self.price_sell(&market, outcome_id, Shares::new(shares), current_time)?;
+        let min_price = PricePerShare::new(min_price_per_share);
+        if PricePerShare::average(trade.net, Shares::new(shares)).is_none_or(|price| price < min_price) {
+            return Err(ContractError::SlippageExceeded);
+        }
+        let sell_value = trade.gross.amount();
+
+        market.outcomes[outcome_id as usize].total_shares =
+            market.outcomes[outcome_id as usize]
+                .total_shares
+                .saturating_sub(shares);
+        market.total_liquidity = market.total_liquidity.saturating_sub(sell_value);
+
+        let position = market.positions.get_mut(&player_id).unwrap();
+        position.total_invested = position.total_invested.saturating_sub(sell_value);
+        let new_shares = owned_shares.saturating_sub(shares);
+        if new_shares == Amount::ZERO {
+            position.shares_by_outcome.remove(&outcome_id);
+            let no_shares_left = position.shares_by_outcome.is_empty();
+            let outcome = &mut market.outcomes[outcome_id as usize];
+            outcome.holders = outcome.holders.saturating_sub(1);
+            if no_shares_left {
+                market.current_holders = market.current_holders.saturating_sub(1);
+            }
+        } else {
+            position.shares_by_outcome.insert(outcome_id, new_shares);
+        }
+        market.refresh_open_interest(outcome_id);
+
+        player.token_balance = player.token_balance.saturating_add(trade.net.amount());
+        player.trading_volume = player.trading_volume.saturating_add(sell_value);
+        market.outcomes[outcome_id as usize].current_price =
+            self.calculate_current_price(&market, outcome_id)?.amount();
+
+        self.state.markets.insert(&market_id, market)?;
+        self.save_player(player).await?;
+        
+        // Distribute trading fees to market creator
+        self.distribute_trading_fees(market_id, Some(player_id), sell_value, trade.fee_bps, current_time).await?;
+        self.pay_maker_rebate(player_id, trade.fee).await?;
+        self.record_market_activity(market_id, outcome_id, TradeSide::Sell, sell_value, current_time).await?;
+        self.notify_large_trade(market_id, player_id, outcome_id, sell_value);
+        Ok(TradeFill::new(market_id, outcome_id, shares, trade))
+    }
+
+    /// Buy `sets` shares of every outcome at once, each priced on its own curve
+    /// A complete set pays at most one token at resolution whatever the result, since the
+    /// payout weights add up to no more than 100%, and costs at least the base price per
+    /// outcome, so the pool always covers it; selling the outcomes a player disbelieves then
+    /// leaves them short
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Sets minted successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(MarketNotActive)` - Market is not active
+    /// * `Err(InsufficientShares)` - No sets requested
+    /// * `Err(SlippageExceeded)` - Total cost, fees included, is above `max_cost`
+    /// * `Err(InsufficientBalance)` - Player can't pay the cost
+    /// * `Err(MarketAccessDenied)` - Player is not allowed into a private market
+    async fn mint_complete_set(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        sets: Amount,
+        max_cost: Amount,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let mut player = self.get_player(&player_id).await?;
+        Self::ensure_market_access(&market, &player)?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+        if sets == Amount::ZERO {
+            return Err(ContractError::InsufficientShares);
+        }
+        // The shares' cost goes into the pool and each outcome's fee is charged on top of it
+        let curve = market.bonding_curve();
+        let costs: Vec<(Amount, TradeAmounts)> = market
+            .outcomes
+            .iter()
+            .map(|outcome| {
+                let supply = u128::from(outcome.total_shares);
+                let price_before = PricePerShare::from_attos(curve.price_at(supply));
+                let price_after = PricePerShare::from_attos(curve.price_at(supply.saturating_add(u128::from(sets))));
+                let fee_bps = self.trading_fee_bps(&market, price_before, price_after, current_time);
+                let outcome_cost = Tokens::from_attos(curve.buy_cost(supply, u128::from(sets)));
+                (outcome_cost.amount(), TradeAmounts::carve(outcome_cost, fee_bps))
+            })
+            .collect();
+        let cost = costs.iter().fold(Amount::ZERO, |total, (cost, _)| total.saturating_add(*cost));
+        let charged = costs
+            .iter()
+            .fold(cost, |total, (_, trade)| total.saturating_add(trade.fee.amount()));
+        if charged > max_cost {
+            return Err(ContractError::SlippageExceeded);
+        }
+        if player.token_balance < charged {
+            return Err(ContractError::InsufficientBalance);
+        }
+        Self::charge_risk_limits(&mut player, charged, current_time)?;
+
+        let mut fees = Vec::new();
+        for (outcome_id, (outcome_cost, trade)) in costs.into_iter().enumerate() {
+            let outcome_id = outcome_id as OutcomeId;
+            market.add_shares(player_id, outcome_id, sets, outcome_cost, current_time);
+            market.outcomes[outcome_id as usize].current_price =
+                self.calculate_current_price(&market, outcome_id)?.amount();
+            fees.push((outcome_cost, trade.fee_bps));
+        }
+        market.total_liquidity = market.total_liquidity.saturating_add(cost);
+
+        if self.join_market(player_id, market_id).await? {
+            self.add_participant(&mut market, player_id).await?;
+            player.markets_participated += 1;
+        }
+        player.token_balance = player.token_balance.saturating_sub(charged);
+        player.total_spent = player.total_spent.saturating_add(charged);
+        player.trading_volume = player.trading_volume.saturating_add(cost);
+        let delta = PlayerStatsDelta {
+            level: player.level,
+            spent: charged,
+            volume: cost,
+            ..PlayerStatsDelta::default()
+        };
+        self.state.markets.insert(&market_id, market)?;
+        self.save_player(player).await?;
+        self.report_player_stats(player_id, delta);
+
+        for (outcome_id, (outcome_cost, fee_bps)) in fees.into_iter().enumerate() {
+            self.distribute_trading_fees(market_id, Some(player_id), outcome_cost, fee_bps, current_time).await?;
+            self.record_market_activity(market_id, outcome_id as OutcomeId, TradeSide::Buy, outcome_cost, current_time)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Sell `sets` complete sets back down every outcome's curve
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Sets burned successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(MarketNotActive)` - Market is not active
+    /// * `Err(NoPosition)` - Player has no position in this market
+    /// * `Err(InsufficientShares)` - Player holds fewer than `sets` shares of some outcome
+    /// * `Err(SlippageExceeded)` - Total value after fees is below `min_value`
+    async fn burn_complete_set(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        sets: Amount,
+        min_value: Amount,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let mut player = self.get_player(&player_id).await?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+        let position = market.positions.get(&player_id).ok_or(ContractError::NoPosition)?;
+        let outcome_count = market.outcomes.len() as OutcomeId;
+        let complete = (0..outcome_count).all(|outcome_id| {
+            position
+                .shares_by_outcome
+                .get(&outcome_id)
+                .is_some_and(|held| *held >= sets)
+        });
+        if sets == Amount::ZERO || !complete {
+            return Err(ContractError::InsufficientShares);
         }
+        // Each outcome is priced on its own curve, so all of them can be priced up front
+        let trades = (0..outcome_count)
+            .map(|outcome_id| self.price_sell(&market, outcome_id, Shares::new(sets), current_time))
+            .collect::<Result<Vec<_>, _>>()?;
+        let value = trades.iter().fold(Amount::ZERO, |total, trade| total.saturating_add(trade.gross.amount()));
+        let received = trades.iter().fold(Amount::ZERO, |total, trade| total.saturating_add(trade.net.amount()));
+        if received < min_value {
+            return Err(ContractError::SlippageExceeded);
+        }
+
+        let mut fees = Vec::new();
+        for (outcome_id, trade) in trades.into_iter().enumerate() {
+            let outcome_id = outcome_id as OutcomeId;
+            market.withdraw_shares(&player_id, outcome_id, sets);
+            let outcome = &mut market.outcomes[outcome_id as usize];
+            outcome.total_shares = outcome.total_shares.saturating_sub(sets);
+            market.refresh_open_interest(outcome_id);
+            market.outcomes[outcome_id as usize].current_price =
+                self.calculate_current_price(&market, outcome_id)?.amount();
+            fees.push((trade.gross.amount(), trade.fee_bps));
+        }
+        market.total_liquidity = market.total_liquidity.saturating_sub(value);
+
+        player.token_balance = player.token_balance.saturating_add(received);
+        player.trading_volume = player.trading_volume.saturating_add(value);
+        self.state.markets.insert(&market_id, market)?;
+        self.save_player(player).await?;
+
+        for (outcome_id, (outcome_value, fee_bps)) in fees.into_iter().enumerate() {
+            self.distribute_trading_fees(market_id, Some(player_id), outcome_value, fee_bps, current_time).await?;
+            self.record_market_activity(market_id, outcome_id as OutcomeId, TradeSide::Sell, outcome_value, current_time)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Put shares up for a descending-price auction so a large position can be sold to other
+    /// players without pushing the AMM price down; the shares leave the seller's position
+    /// until they're bought or the auction is cancelled
+    /// 
+    /// # Returns
+    /// * `Ok(auction_id)` - Auction started successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(MarketNotActive)` - Market is not active
+    /// * `Err(InvalidOutcome)` - Outcome doesn't exist
+    /// * `Err(InvalidAuction)` - Floor above the start price, zero duration, or the auction
+    ///   would run past the end of trading
+    /// * `Err(InsufficientShares)` - Seller doesn't hold that many shares
+    #[allow(clippy::too_many_arguments)]
+    async fn start_position_auction(
+        &mut self,
+        seller: PlayerId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+        start_price: Amount,
+        floor_price: Amount,
+        duration_seconds: u64,
+        current_time: Timestamp,
+    ) -> Result<AuctionId, ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+        if outcome_id as usize >= market.outcomes.len() {
+            return Err(ContractError::InvalidOutcome);
+        }
+        let ends_at = current_time.saturating_add(TimeDelta::from_secs(duration_seconds));
+        if floor_price > start_price || duration_seconds == 0 || ends_at > market.end_time {
+            return Err(ContractError::InvalidAuction);
+        }
+        let (cost_basis, acquired_at) = market
+            .withdraw_shares(&seller, outcome_id, shares)
+            .ok_or(ContractError::InsufficientShares)?;
+
+        let id = market.auctions.len() as AuctionId;
+        market.auctions.push(PositionAuction {
+            id,
+            seller,
+            outcome_id,
+            shares,
+            start_price,
+            floor_price,
+            starts_at: current_time,
+            ends_at,
+            cost_basis,
+            acquired_at,
+            proceeds: Amount::ZERO,
+            open: true,
+        });
+        self.state.markets.insert(&market_id, market)?;
+        Ok(id)
+    }
+
+    /// Buy shares from a position auction at its current price, paid straight to the seller
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Shares bought successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(AuctionNotFound)` - No auction with this ID on the market
+    /// * `Err(AuctionClosed)` - Auction was cancelled or sold out
+    /// * `Err(InvalidAuction)` - Seller can't buy from their own auction
+    /// * `Err(InsufficientShares)` - Auction has fewer shares left
+    /// * `Err(SlippageExceeded)` - Current price is above the buyer's maximum
+    /// * `Err(InsufficientBalance)` - Buyer can't pay for the shares
+    /// * `Err(MarketAccessDenied)` - Buyer is not allowed into a private market
+    async fn fill_position_auction(
+        &mut self,
+        buyer: PlayerId,
+        market_id: MarketId,
+        auction_id: AuctionId,
+        shares: Amount,
+        max_price_per_share: Amount,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let mut player = self.get_player(&buyer).await?;
+        Self::ensure_market_access(&market, &player)?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+
+        let auction = market
+            .auctions
+            .get_mut(auction_id as usize)
+            .ok_or(ContractError::AuctionNotFound)?;
+        if !auction.open {
+            return Err(ContractError::AuctionClosed);
+        }
+        if auction.seller == buyer {
+            return Err(ContractError::InvalidAuction);
+        }
+        if shares == Amount::ZERO || shares > auction.shares {
+            return Err(ContractError::InsufficientShares);
+        }
+        let price = auction.price_at(current_time);
+        if price > max_price_per_share {
+            return Err(ContractError::SlippageExceeded);
+        }
+        let cost = Amount::from_attos(math::mul_div(u128::from(price), u128::from(shares), math::ONE));
+        if player.token_balance < cost {
+            return Err(ContractError::InsufficientBalance);
+        }
+        Self::charge_risk_limits(&mut player, cost, current_time)?;
+
+        let sold_basis = Amount::from_attos(math::mul_div(
+            u128::from(auction.cost_basis),
+            u128::from(shares),
+            u128::from(auction.shares),
+        ));
+        auction.cost_basis = auction.cost_basis.saturating_sub(sold_basis);
+        auction.shares = auction.shares.saturating_sub(shares);
+        auction.proceeds = auction.proceeds.saturating_add(cost);
+        if auction.shares == Amount::ZERO {
+            auction.open = false;
+        }
+        let (seller, outcome_id, acquired_at) = (auction.seller, auction.outcome_id, auction.acquired_at);
+        market.deposit_shares(buyer, outcome_id, shares, cost, acquired_at);
+        if self.join_market(buyer, market_id).await? {
+            self.add_participant(&mut market, buyer).await?;
+            player.markets_participated += 1;
+        }
+
+        player.token_balance = player.token_balance.saturating_sub(cost);
+        player.trading_volume = player.trading_volume.saturating_add(cost);
+        self.save_player(player).await?;
+        let mut seller_player = self.get_player(&seller).await?;
+        seller_player.token_balance = seller_player.token_balance.saturating_add(cost);
+        seller_player.trading_volume = seller_player.trading_volume.saturating_add(cost);
+        self.save_player(seller_player).await?;
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Cancel a position auction, returning the unsold shares to the seller
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Auction cancelled successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(AuctionNotFound)` - No auction with this ID on the market
+    /// * `Err(Unauthorized)` - Caller is not the seller
+    /// * `Err(AuctionClosed)` - Auction was already cancelled or sold out
+    async fn cancel_position_auction(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        auction_id: AuctionId,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let auction = market
+            .auctions
+            .get_mut(auction_id as usize)
+            .ok_or(ContractError::AuctionNotFound)?;
+        if auction.seller != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if !auction.open {
+            return Err(ContractError::AuctionClosed);
+        }
+        auction.open = false;
+        let (outcome_id, shares, cost_basis, acquired_at) =
+            (auction.outcome_id, auction.shares, auction.cost_basis, auction.acquired_at);
+        auction.shares = Amount::ZERO;
+        auction.cost_basis = Amount::ZERO;
+        market.deposit_shares(caller, outcome_id, shares, cost_basis, acquired_at);
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Give shares to another player; the cost basis moves with them and market liquidity
+    /// doesn't change
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Shares transferred successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(MarketNotActive)` - Market is not active
+    /// * `Err(InvalidShareOffer)` - Sender and recipient are the same player
+    /// * `Err(PlayerNotFound)` - Recipient is not registered
+    /// * `Err(MarketAccessDenied)` - Recipient is not allowed into a private market
+    /// * `Err(InsufficientShares)` - Sender doesn't hold that many shares
+    async fn transfer_shares(
+        &mut self,
+        sender: PlayerId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        to: PlayerId,
+        shares: Amount,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+        if sender == to {
+            return Err(ContractError::InvalidShareOffer);
+        }
+        let mut recipient = self.get_player(&to).await?;
+        Self::ensure_market_access(&market, &recipient)?;
+        let (cost_basis, acquired_at) = market
+            .withdraw_shares(&sender, outcome_id, shares)
+            .ok_or(ContractError::InsufficientShares)?;
+        market.deposit_shares(to, outcome_id, shares, cost_basis, acquired_at);
+        if self.join_market(to, market_id).await? {
+            self.add_participant(&mut market, to).await?;
+            recipient.markets_participated += 1;
+            self.save_player(recipient).await?;
+        }
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Offer shares for a fixed total price, to one player or to anyone; the shares leave the
+    /// seller's position until the offer is accepted or cancelled
+    /// 
+    /// # Returns
+    /// * `Ok(offer_id)` - Offer created successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(MarketNotActive)` - Market is not active
+    /// * `Err(InvalidShareOffer)` - Seller named themselves as the buyer
+    /// * `Err(InsufficientShares)` - Seller doesn't hold that many shares
+    #[allow(clippy::too_many_arguments)]
+    async fn create_share_offer(
+        &mut self,
+        seller: PlayerId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+        price: Amount,
+        buyer: Option<PlayerId>,
+        current_time: Timestamp,
+    ) -> Result<ShareOfferId, ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+        if buyer == Some(seller) {
+            return Err(ContractError::InvalidShareOffer);
+        }
+        let (cost_basis, acquired_at) = market
+            .withdraw_shares(&seller, outcome_id, shares)
+            .ok_or(ContractError::InsufficientShares)?;
+
+        let id = market.share_offers.len() as ShareOfferId;
+        market.share_offers.push(ShareOffer {
+            id,
+            seller,
+            buyer,
+            outcome_id,
+            shares,
+            price,
+            cost_basis,
+            acquired_at,
+            open: true,
+        });
+        self.state.markets.insert(&market_id, market)?;
+        Ok(id)
+    }
+
+    /// Buy the shares of an offer, paying its price straight to the seller
+    /// `price` is the total the buyer agreed to, and must match the offer
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Offer accepted successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(ShareOfferNotFound)` - No offer with this ID on the market
+    /// * `Err(ShareOfferClosed)` - Offer was accepted or cancelled
+    /// * `Err(Unauthorized)` - Offer is reserved for another player
+    /// * `Err(InvalidShareOffer)` - Seller can't accept their own offer
+    /// * `Err(SlippageExceeded)` - Price doesn't match the offer
+    /// * `Err(InsufficientBalance)` - Buyer can't pay the price
+    /// * `Err(MarketAccessDenied)` - Buyer is not allowed into a private market
+    async fn accept_share_offer(
+        &mut self,
+        buyer: PlayerId,
+        market_id: MarketId,
+        offer_id: ShareOfferId,
+        price: Amount,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let mut player = self.get_player(&buyer).await?;
+        Self::ensure_market_access(&market, &player)?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+
+        let offer = market
+            .share_offers
+            .get_mut(offer_id as usize)
+            .ok_or(ContractError::ShareOfferNotFound)?;
+        if !offer.open {
+            return Err(ContractError::ShareOfferClosed);
+        }
+        if offer.buyer.is_some_and(|reserved| reserved != buyer) {
+            return Err(ContractError::Unauthorized);
+        }
+        if offer.seller == buyer {
+            return Err(ContractError::InvalidShareOffer);
+        }
+        if offer.price != price {
+            return Err(ContractError::SlippageExceeded);
+        }
+        if player.token_balance < price {
+            return Err(ContractError::InsufficientBalance);
+        }
+        Self::charge_risk_limits(&mut player, price, current_time)?;
+        offer.open = false;
+        let (seller, outcome_id, shares, acquired_at) = (offer.seller, offer.outcome_id, offer.shares, offer.acquired_at);
+        market.deposit_shares(buyer, outcome_id, shares, price, acquired_at);
+        if self.join_market(buyer, market_id).await? {
+            self.add_participant(&mut market, buyer).await?;
+            player.markets_participated += 1;
+        }
+
+        player.token_balance = player.token_balance.saturating_sub(price);
+        player.trading_volume = player.trading_volume.saturating_add(price);
+        self.save_player(player).await?;
+        let mut seller_player = self.get_player(&seller).await?;
+        seller_player.token_balance = seller_player.token_balance.saturating_add(price);
+        seller_player.trading_volume = seller_player.trading_volume.saturating_add(price);
+        self.save_player(seller_player).await?;
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Withdraw a share offer, returning its shares to the seller
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Offer cancelled successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(ShareOfferNotFound)` - No offer with this ID on the market
+    /// * `Err(Unauthorized)` - Caller is not the seller
+    /// * `Err(ShareOfferClosed)` - Offer was already accepted or cancelled
+    async fn cancel_share_offer(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        offer_id: ShareOfferId,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let offer = market
+            .share_offers
+            .get_mut(offer_id as usize)
+            .ok_or(ContractError::ShareOfferNotFound)?;
+        if offer.seller != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if !offer.open {
+            return Err(ContractError::ShareOfferClosed);
+        }
+        offer.open = false;
+        let (outcome_id, shares, cost_basis, acquired_at) =
+            (offer.outcome_id, offer.shares, offer.cost_basis, offer.acquired_at);
+        market.deposit_shares(caller, outcome_id, shares, cost_basis, acquired_at);
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Vote on the outcome of a market
+    /// Allows players to vote on which outcome should win (for OracleVoting resolution)
+    /// 
+    /// # Arguments
+    /// * `voter_id` - The player casting the vote
+    /// * `market_id` - The market to vote on
+    /// * `outcome_id` - Which outcome the player thinks will win
+    /// * `evidence` - Optional source backing the vote, recorded on the market
+    /// * `current_time` - Current timestamp for voting period
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Vote cast successfully
+    /// * `Err(MarketNotReadyForVoting)` - Market is not in voting phase
+    /// * `Err(InvalidResolutionMethod)` - Market doesn't use OracleVoting
+    /// * `Err(AlreadyVoted)` - Player has already voted in this market
+    /// * `Err(MarketAccessDenied)` - Player is not allowed into a private market
+    /// * `Err(NotResolver)` / `Err(InsufficientStake)` - Voter hasn't staked enough as a resolver
+    /// * `Err(InvalidEvidence)` - Evidence URL or note is malformed
+    async fn vote_on_outcome(
+        &mut self,
+        voter_id: PlayerId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        evidence: Option<Evidence>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        let player = self.get_player(&voter_id).await?;
+        if let Some(evidence) = &evidence {
+            Self::validate_evidence(evidence)?;
+        }
+
+        Self::ensure_market_access(&market, &player)?;
+        if market.status != MarketStatus::Voting {
+            return Err(ContractError::MarketNotReadyForVoting);
+        }
+        if !matches!(market.resolution_method, ResolutionMethod::OracleVoting) {
+            return Err(ContractError::InvalidResolutionMethod);
+        }
+
+        let mut voting = match self.state.oracle_votes.get(&market_id).await? {
+            Some(voting) => voting,
+            None => self.new_oracle_voting(&market),
+        };
+
+        if voting.voters.contains(&voter_id) {
+            return Err(ContractError::AlreadyVoted);
+        }
+        let mut resolver = self.state.resolvers.get(&voter_id).await?.ok_or(ContractError::NotResolver)?;
+        if resolver.stake < self.state.config.get().min_resolver_stake {
+            return Err(ContractError::InsufficientStake);
+        }
+        // A vote joining a flagged partner's on the same outcome counts for less
+        let collusion = self.state.config.get().collusion;
+        let agreements = self.state.vote_agreements.get(&voter_id).await?.unwrap_or_default();
+        let joins_flagged_partner = voting.ballots.iter().any(|(other, other_outcome)| {
+            *other_outcome == outcome_id
+                && agreements.get(other).is_some_and(|agreement| agreement.is_flagged(&collusion))
+        });
+        let vote_weight = if joins_flagged_partner {
+            math::apply_percent(u128::from(player.reputation), collusion.weight_percent) as u64
+        } else {
+            player.reputation
+        };
+        resolver.pending_markets.push(market_id);
+        resolver.votes_cast += 1;
+        self.state.resolvers.insert(&voter_id, resolver)?;
+
+        let weighted_votes = voting
+            .votes
+            .entry(outcome_id)
+            .or_insert(WeightedVotes { total_weight: 0, voter_count: 0 });
+        weighted_votes.total_weight += vote_weight;
+        weighted_votes.voter_count += 1;
+        voting.voters.push(voter_id);
+        voting.ballots.insert(voter_id, outcome_id);
+        self.state.oracle_votes.insert(&market_id, voting)?;
+        if let Some(evidence) = evidence {
+            market.resolution_evidence.push(ResolutionEvidence {
+                submitted_by: voter_id,
+                outcome_id,
+                evidence,
+                submitted_at: current_time,
+            });
+            self.state.markets.insert(&market_id, market)?;
+        }
+        Ok(())
+    }
+
+    /// Stake tokens as a resolver; only resolvers may vote on oracle markets
+    /// Staking again adds to the existing stake
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Stake taken from the player's balance
+    /// * `Err(InsufficientStake)` - Resulting stake would be below `min_resolver_stake`
+    /// * `Err(InsufficientBalance)` - Player doesn't have enough tokens
+    async fn register_as_resolver(
+        &mut self,
+        player_id: PlayerId,
+        stake: Amount,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let mut resolver = self.state.resolvers.get(&player_id).await?.unwrap_or(Resolver {
+            stake: Amount::ZERO,
+            registered_at: current_time,
+            pending_markets: Vec::new(),
+            votes_cast: 0,
+            total_slashed: Amount::ZERO,
+            fees_earned: Amount::ZERO,
+        });
+        if stake == Amount::ZERO || resolver.stake.saturating_add(stake) < self.state.config.get().min_resolver_stake {
+            return Err(ContractError::InsufficientStake);
+        }
+        if player.token_balance < stake {
+            return Err(ContractError::InsufficientBalance);
+        }
+        player.token_balance = player.token_balance.saturating_sub(stake);
+        resolver.stake = resolver.stake.saturating_add(stake);
+        self.save_player(player).await?;
+        self.state.resolvers.insert(&player_id, resolver)?;
+        Ok(())
+    }
+
+    /// Return a resolver's whole stake and remove them from the resolver set
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Stake returned to the player's balance
+    /// * `Err(NotResolver)` - Player isn't a resolver
+    /// * `Err(ResolverVotesPending)` - A market the resolver voted on hasn't resolved yet
+    async fn withdraw_resolver_stake(&mut self, player_id: PlayerId) -> Result<(), ContractError> {
+        let resolver = self.state.resolvers.get(&player_id).await?.ok_or(ContractError::NotResolver)?;
+        if !resolver.pending_markets.is_empty() {
+            return Err(ContractError::ResolverVotesPending);
+        }
+        let mut player = self.get_player(&player_id).await?;
+        player.token_balance = player.token_balance.saturating_add(resolver.stake);
+        self.save_player(player).await?;
+        self.state.resolvers.remove(&player_id)?;
+        Ok(())
+    }
+
+    /// Slash and reward resolvers once an oracle market resolves
+    /// Resolvers who voted against `winning_outcome`, or who were registered before the market
+    /// ended, were allowed to vote in it and didn't, lose `resolver_slash_percent` of their
+    /// stake. The slashed tokens
+    /// and `resolver_fee` go to the resolvers who voted for the result, in proportion to stake
+    async fn settle_resolvers(
+        &mut self,
+        market: &Market,
+        winning_outcome: OutcomeId,
+        resolver_fee: Amount,
+    ) -> Result<(), ContractError> {
+        let ballots = match self.state.oracle_votes.get(&market.id).await? {
+            Some(voting) => voting.ballots,
+            None => BTreeMap::new(),
+        };
+        let slash_percent = self.state.config.get().resolver_slash_percent;
+        self.record_vote_agreements(&ballots).await?;
+
+        let mut resolvers = Vec::new();
+        self.state.resolvers.for_each_index_value(|player_id, resolver| {
+            resolvers.push((player_id, resolver.into_owned()));
+            Ok(())
+        }).await?;
+
+        let mut pool = u128::from(resolver_fee);
+        let mut correct_stake = 0u128;
+        for (player_id, resolver) in &mut resolvers {
+            resolver.pending_markets.retain(|market_id| *market_id != market.id);
+            let voted = ballots.get(player_id);
+            // Resolvers shut out of a guild or allowlisted market couldn't have voted
+            let missed = voted.is_none()
+                && resolver.registered_at < market.end_time
+                && self
+                    .state
+                    .players
+                    .get(player_id)
+                    .await?
+                    .is_some_and(|player| Self::ensure_market_access(market, &player).is_ok());
+            if voted == Some(&winning_outcome) {
+                correct_stake += u128::from(resolver.stake);
+            } else if voted.is_some() || missed {
+                let slashed = Amount::from_attos(math::apply_percent(u128::from(resolver.stake), slash_percent));
+                resolver.stake = resolver.stake.saturating_sub(slashed);
+                resolver.total_slashed = resolver.total_slashed.saturating_add(slashed);
+                pool += u128::from(slashed);
+            }
+        }
+
+        let mut paid = 0u128;
+        for (player_id, mut resolver) in resolvers {
+            if correct_stake > 0 && ballots.get(&player_id) == Some(&winning_outcome) {
+                let share = math::mul_div(pool, u128::from(resolver.stake), correct_stake);
+                if share > 0 {
+                    let mut player = self.get_player(&player_id).await?;
+                    player.token_balance = player.token_balance.saturating_add(Amount::from_attos(share));
+                    player.total_earned = player.total_earned.saturating_add(Amount::from_attos(share));
+                    self.save_player(player).await?;
+                    resolver.fees_earned = resolver.fees_earned.saturating_add(Amount::from_attos(share));
+                    paid += share;
+                }
+            }
+            self.state.resolvers.insert(&player_id, resolver)?;
+        }
+        // Rounding dust, or everything when nobody voted for the result
+        self.burn_tokens(Amount::from_attos(pool - paid)).await
+    }
+
+    /// Count a settled oracle vote towards how often each pair of its voters agree
+    /// Only votes split between outcomes count, and only their first `MAX_AGREEMENT_VOTERS`
+    /// ballots, bounding the pairs updated
+    async fn record_vote_agreements(&mut self, ballots: &BTreeMap<PlayerId, OutcomeId>) -> Result<(), ContractError> {
+        if ballots.values().collect::<BTreeSet<_>>().len() < 2 {
+            return Ok(());
+        }
+        let ballots: Vec<(&PlayerId, &OutcomeId)> = ballots.iter().take(MAX_AGREEMENT_VOTERS).collect();
+        for (voter, outcome) in &ballots {
+            let mut agreements = self.state.vote_agreements.get(voter).await?.unwrap_or_default();
+            for (other, other_outcome) in &ballots {
+                if other == voter {
+                    continue;
+                }
+                let agreement = agreements.entry(**other).or_default();
+                agreement.shared_votes += 1;
+                if other_outcome == outcome {
+                    agreement.agreed_votes += 1;
+                }
+            }
+            self.state.vote_agreements.insert(voter, agreements)?;
+        }
+        Ok(())
+    }
+
+    /// Refund a resolving market's creator bond after a clean resolution, or forfeit it to the
+    /// treasury
+    async fn settle_creator_bond(&mut self, market: &mut Market, clean: bool) -> Result<(), ContractError> {
+        if market.bond_status != BondStatus::Held {
+            return Ok(());
+        }
+        if clean {
+            let mut creator = self.get_player(&market.creator).await?;
+            creator.token_balance = creator.token_balance.saturating_add(market.creator_bond);
+            self.save_player(creator).await?;
+            market.bond_status = BondStatus::Refunded;
+        } else {
+            let treasury = self.state.treasury.get().saturating_add(market.creator_bond);
+            self.state.treasury.set(treasury);
+            market.bond_status = BondStatus::Forfeited;
+        }
+        Ok(())
+    }
+
+    /// Trigger the resolution of a market
+    /// Resolves a market after it has ended, determining the winning outcome
+    /// 
+    /// # Arguments
+    /// * `market_id` - The market to resolve
+    /// * `current_time` - Current timestamp for resolution timing
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market resolved successfully
+    /// * `Err(MarketNotEnded)` - Market hasn't ended yet
+    async fn trigger_market_resolution(
+        &mut self,
+        market_id: MarketId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if current_time < market.end_time {
+            return Err(ContractError::MarketNotEnded);
+        }
+        Self::ensure_reveals_settled(&market, current_time)?;
+        if market.status == MarketStatus::Active {
+            market.close(current_time, self.state.config.get().oracle_voting_duration_seconds)?;
+            self.state.markets_by_end_time.remove(&MarketEndKey { end_time: market.end_time, market_id })?;
+            self.state.markets.insert(&market_id, market.clone())?;
+        }
+
+        let ranked_outcomes = match market.resolution_method {
+            ResolutionMethod::OracleVoting => self.resolve_by_oracle_vote(&market).await?,
+            ResolutionMethod::Automated => self.resolve_automated(&market).await?,
+            ResolutionMethod::CrowdConsensus => Self::resolve_by_crowd_consensus(&market),
+            ResolutionMethod::CreatorDecides => {
+                // Creator resolves through ResolveMarket
+                return Ok(())
+            }
+            ResolutionMethod::DesignatedOracle { .. } => {
+                // The oracle account resolves through SubmitOracleResult
+                return Ok(())
+            }
+        };
+
+        self.finalize_resolution(market, ranked_outcomes, current_time).await
+    }
+
+    /// Resolve a CreatorDecides market with a ranked list of winning outcomes
+    /// The list must contain one distinct outcome per payout weight, best first
+    /// 
+    /// # Arguments
+    /// * `caller` - The player submitting the result (must be the creator)
+    /// * `market_id` - The market to resolve
+    /// * `ranked_outcomes` - Winning outcomes ordered by rank
+    /// * `evidence` - Optional source backing the result, recorded on the market
+    /// * `current_time` - Current timestamp for resolution timing
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market resolved successfully
+    /// * `Err(Unauthorized)` - Caller is not the market creator
+    /// * `Err(InvalidResolutionMethod)` - Market is not resolved by its creator
+    /// * `Err(MarketNotEnded)` - Market hasn't ended yet
+    /// * `Err(InvalidRankedOutcomes)` - Wrong length, duplicate or unknown outcomes
+    /// * `Err(InvalidEvidence)` - Evidence URL or note is malformed
+    async fn resolve_market_by_creator(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        ranked_outcomes: Vec<OutcomeId>,
+        evidence: Option<Evidence>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.creator != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if market.group_id.is_some() {
+            return Err(ContractError::MarketInGroup);
+        }
+        Self::ensure_creator_resolvable(&market, &ranked_outcomes, current_time)?;
+        if let Some(evidence) = evidence {
+            Self::validate_evidence(&evidence)?;
+            market.resolution_evidence.push(ResolutionEvidence {
+                submitted_by: caller,
+                outcome_id: *ranked_outcomes.first().ok_or(ContractError::InvalidRankedOutcomes)?,
+                evidence,
+                submitted_at: current_time,
+            });
+        }
+        self.finalize_resolution(market, ranked_outcomes, current_time).await
+    }
+
+    /// Check that a CreatorDecides market has ended and can be resolved to `ranked_outcomes`
+    fn ensure_creator_resolvable(
+        market: &Market,
+        ranked_outcomes: &[OutcomeId],
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        if market.resolution_method != ResolutionMethod::CreatorDecides {
+            return Err(ContractError::InvalidResolutionMethod);
+        }
+        if current_time < market.end_time {
+            return Err(ContractError::MarketNotEnded);
+        }
+        Self::ensure_reveals_settled(market, current_time)?;
+        if !matches!(market.status, MarketStatus::Active | MarketStatus::Closed) {
+            return Err(ContractError::MarketNotActive);
+        }
+        if ranked_outcomes.len() != market.payout_weights.len() {
+            return Err(ContractError::InvalidRankedOutcomes);
+        }
+        for (rank, outcome_id) in ranked_outcomes.iter().enumerate() {
+            if *outcome_id >= market.outcomes.len() as OutcomeId
+                || ranked_outcomes[..rank].contains(outcome_id)
+            {
+                return Err(ContractError::InvalidRankedOutcomes);
+            }
+        }
+        Ok(())
+    }
+
+    /// Resolve a DesignatedOracle market with the result reported by its oracle account
+    /// Scalar markets take a value observed between the end time and now, and resolve to the
+    /// outcome whose part of the range holds it; other markets take the outcome directly
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market resolved successfully
+    /// * `Err(InvalidResolutionMethod)` - Market has no designated oracle
+    /// * `Err(Unauthorized)` - Caller is not the market's oracle account
+    /// * `Err(MarketNotEnded)` - Market hasn't ended yet
+    /// * `Err(InvalidOracleResult)` - Wrong kind of result, or a value outside the range or
+    ///   observed outside the window
+    /// * `Err(InvalidOutcome)` - Outcome doesn't exist
+    async fn submit_oracle_result(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        outcome_id: Option<OutcomeId>,
+        value: Option<OracleValue>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        let ResolutionMethod::DesignatedOracle { oracle_account, scalar_range } = market.resolution_method else {
+            return Err(ContractError::InvalidResolutionMethod);
+        };
+        if caller != oracle_account {
+            return Err(ContractError::Unauthorized);
+        }
+        if current_time < market.end_time {
+            return Err(ContractError::MarketNotEnded);
+        }
+        Self::ensure_reveals_settled(&market, current_time)?;
+        if !matches!(market.status, MarketStatus::Active | MarketStatus::Closed) {
+            return Err(ContractError::MarketNotActive);
+        }
+        let outcome_id = match (scalar_range, outcome_id, value) {
+            (None, Some(outcome_id), None) => outcome_id,
+            (Some(range), None, Some(value)) => {
+                if value.observed_at < market.end_time || value.observed_at > current_time {
+                    return Err(ContractError::InvalidOracleResult);
+                }
+                let bucket = math::scalar_bucket(value.value, range.min, range.max, market.outcomes.len() as u32)
+                    .ok_or(ContractError::InvalidOracleResult)?;
+                market.oracle_value = Some(value);
+                bucket
+            }
+            _ => return Err(ContractError::InvalidOracleResult),
+        };
+        if outcome_id >= market.outcomes.len() as OutcomeId {
+            return Err(ContractError::InvalidOutcome);
+        }
+        self.finalize_resolution(market, vec![outcome_id], current_time).await
+    }
+
+    /// Record the ranked winners on a market and announce the resolution
+    /// Part of the liquidity that will not be paid out to winners is burned
+    async fn finalize_resolution(
+        &mut self,
+        mut market: Market,
+        ranked_outcomes: Vec<OutcomeId>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let market_id = market.id;
+        let winning_outcome = *ranked_outcomes.first().ok_or(ContractError::InvalidRankedOutcomes)?;
+        // Markets resolved without being triggered first close on the way
+        if market.status == MarketStatus::Active {
+            market.close(current_time, self.state.config.get().oracle_voting_duration_seconds)?;
+        }
+        if !market.status.can_transition_to(MarketStatus::Resolved) {
+            return Err(InvalidTransition { from: market.status, to: MarketStatus::Resolved }.into());
+        }
+        market.winning_outcome = Some(winning_outcome);
+        market.winning_outcomes = ranked_outcomes;
+        // Shares still held by auctions, offers and bot quotes go back to their sellers so
+        // they're paid out with the position, and bids are refunded
+        market.return_escrowed_shares(None);
+        let closed = market.close_bot_quotes(|_| true);
+        for (maker_id, bid_escrow) in &closed {
+            let mut maker = self.get_player(maker_id).await?;
+            maker.token_balance = maker.token_balance.saturating_add(*bid_escrow);
+            self.save_player(maker).await?;
+        }
+        self.release_bot_quotes(&closed).await?;
+
+        // Sealed trades nobody revealed stay in the pool
+        let forfeited = market
+            .blind_commitments
+            .iter()
+            .filter(|sealed| !sealed.revealed)
+            .fold(Amount::ZERO, |total, sealed| total.saturating_add(sealed.amount));
+        market.total_liquidity = market.total_liquidity.saturating_add(forfeited);
+
+        let winning_shares: Vec<u128> = market
+            .winning_outcomes
+            .iter()
+            .map(|outcome_id| u128::from(market.outcomes[*outcome_id as usize].total_shares))
+            .collect();
+        let winning_payouts =
+            Amount::from_attos(math::ranked_payout(&winning_shares, &market.payout_weights));
+        let losing_liquidity = market.total_liquidity.saturating_sub(winning_payouts);
+        let burn_percent = self.state.config.get().losing_liquidity_burn_percent;
+        let burn = Amount::from_attos(math::apply_percent(u128::from(losing_liquidity), burn_percent));
+        market.total_liquidity = market.total_liquidity.saturating_sub(burn);
+        self.burn_tokens(burn).await?;
+        if market.resolution_method == ResolutionMethod::OracleVoting {
+            let fee_percent = self.state.config.get().resolver_fee_percent;
+            let resolver_fee = Amount::from_attos(math::apply_percent(u128::from(losing_liquidity), fee_percent));
+            market.total_liquidity = market.total_liquidity.saturating_sub(resolver_fee);
+            self.settle_resolvers(&market, winning_outcome, resolver_fee).await?;
+        }
+        // A market the council had to settle was too ambiguous for its vote to resolve it
+        let arbitrated = self.state.arbitrations.contains_key(&market_id).await?;
+        self.settle_creator_bond(&mut market, !arbitrated).await?;
+
+        market.transition_status(market.status, MarketStatus::Resolved)?;
+        market.resolution_time = Some(current_time);
+        self.state.markets_by_end_time.remove(&MarketEndKey { end_time: market.end_time, market_id: market.id })?;
+        let claim_window_seconds = self.state.config.get().claim_window_seconds;
+        if claim_window_seconds > 0 {
+            market.claims_expire_at = Some(current_time.saturating_add(TimeDelta::from_secs(claim_window_seconds)));
+        }
+
+        let config = self.state.config.get();
+        if config.early_conviction_rewards {
+            let max_bonus = config.early_conviction_max_xp;
+            self.award_early_conviction_xp(&market, max_bonus, current_time).await?;
+        }
+        self.score_forecasts(&market).await?;
+
+        let mut stats = self.state.creator_stats.get(&market.creator).await?.unwrap_or_default();
+        stats.markets_resolved += 1;
+        // A resolution the council had to rule on counts as a dispute against the creator
+        if arbitrated {
+            stats.disputed_resolutions += 1;
+        }
+        if market.is_wash_traded(self.state.config.get().wash_trade_volume_percent) {
+            stats.wash_traded_markets += 1;
+        }
+        self.state.creator_stats.insert(&market.creator, stats)?;
+        // Players with nothing to claim are done with the market, including those whose only
+        // stake was a forfeited sealed trade
+        let finished: BTreeSet<PlayerId> = market
+            .positions
+            .iter()
+            .filter(|(_, position)| market.payout_for(position) == Amount::ZERO)
+            .map(|(player_id, _)| *player_id)
+            .chain(
+                market
+                    .blind_commitments
+                    .iter()
+                    .map(|sealed| sealed.player)
+                    .filter(|player_id| !market.positions.contains_key(player_id)),
+            )
+            .collect();
+        self.state.markets.insert(&market_id, market)?;
+        for player_id in finished {
+            self.archive_player_market(player_id, market_id).await?;
+        }
+
+        if self.state.notification_filter.get().market_resolved {
+            self.notify(NotificationKind::MarketResolved, market_id, None, winning_outcome, None);
+        }
+        self
+            .runtime
+            .prepare_message(Message::MarketResolved { market_id, winning_outcome })
+            .send_to(self.runtime.chain_id());
+        if self.state.config.get().auto_settlement {
+            self.runtime
+                .prepare_message(Message::SettleWinners { market_id, after: None })
+                .send_to(self.runtime.chain_id());
+        }
+
+        // Notify subscribed chains so downstream applications can react
+        let subscribers = self.state.market_subscribers.get(&market_id).await?.unwrap_or_default();
+        for chain_id in subscribers {
+            self.runtime
+                .prepare_message(Message::MarketResolved { market_id, winning_outcome })
+                .send_to(chain_id);
+        }
+        Ok(())
+    }
+
+    /// Subscribe a chain to a market's resolution
+    /// The chain receives a `MarketResolved` message once the market resolves; the player
+    /// subscribing pays the subscription fee, which is burned
+    /// 
+    /// # Arguments
+    /// * `player_id` - The player paying for the subscription
+    /// * `market_id` - The market to watch
+    /// * `chain_id` - The chain to notify
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Subscription recorded (subscribing twice is a no-op and costs nothing)
+    /// * `Err(MarketNotFound)` - Market doesn't exist
+    /// * `Err(MarketAlreadyResolved)` - Market has already resolved
+    /// * `Err(TooManySubscribers)` - Market has reached its subscriber limit
+    /// * `Err(InsufficientBalance)` - Player can't pay the subscription fee
+    async fn subscribe_to_market(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        chain_id: ChainId,
+    ) -> Result<(), ContractError> {
+        let market = self.get_market(&market_id).await?;
+        if market.status == MarketStatus::Resolved {
+            return Err(ContractError::MarketAlreadyResolved);
+        }
+        let mut subscribers = self.state.market_subscribers.get(&market_id).await?.unwrap_or_default();
+        if subscribers.contains(&chain_id) {
+            return Ok(());
+        }
+        if subscribers.len() >= MAX_MARKET_SUBSCRIBERS {
+            return Err(ContractError::TooManySubscribers);
+        }
+        let fee = self.state.config.get().market_subscription_fee;
+        let mut player = self.get_player(&player_id).await?;
+        if player.token_balance < fee {
+            return Err(ContractError::InsufficientBalance);
+        }
+        player.token_balance = player.token_balance.saturating_sub(fee);
+        player.total_spent = player.total_spent.saturating_add(fee);
+        self.save_player(player).await?;
+        self.burn_tokens(fee).await?;
+
+        subscribers.push(chain_id);
+        self.state.market_subscribers.insert(&market_id, subscribers)?;
+        Ok(())
+    }
+
+    /// Close a market before its end time (creator only)
+    /// Only markets created with early close enabled qualify; trading halts immediately and
+    /// the resolution flow starts: oracle voting opens, automated markets resolve right away
+    /// 
+    /// # Arguments
+    /// * `caller` - The player closing the market (must be the creator)
+    /// * `market_id` - The market to close
+    /// * `current_time` - Current timestamp, which becomes the market's end time
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market closed successfully
+    /// * `Err(Unauthorized)` - Caller is not the market creator
+    /// * `Err(EarlyCloseNotAllowed)` - Market was not created with early close enabled
+    /// * `Err(MarketNotActive)` - Market is not active
+    /// * `Err(MarketEnded)` - Market has already reached its end time
+    async fn close_market_early(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.creator != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if !market.allow_early_close {
+            return Err(ContractError::EarlyCloseNotAllowed);
+        }
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        if current_time >= market.end_time {
+            return Err(ContractError::MarketEnded);
+        }
+
+        self.state.markets_by_end_time.remove(&MarketEndKey { end_time: market.end_time, market_id })?;
+        market.close(current_time, self.state.config.get().oracle_voting_duration_seconds)?;
+        market.end_time = current_time;
+        market.closed_early_at = Some(current_time);
+        let resolution_method = market.resolution_method;
+        let voting = self.new_oracle_voting(&market);
+        self.state.markets.insert(&market_id, market)?;
+
+        match resolution_method {
+            ResolutionMethod::OracleVoting => {
+                self.state.oracle_votes.insert(&market_id, voting)?;
+                Ok(())
+            }
+            ResolutionMethod::Automated | ResolutionMethod::CrowdConsensus => {
+                self.trigger_market_resolution(market_id, current_time).await
+            }
+            // The oracle account settles the market through `SubmitOracleResult`
+            ResolutionMethod::CreatorDecides | ResolutionMethod::DesignatedOracle { .. } => Ok(()),
+        }
+    }
+
+    /// Exit a position at cost basis after an early close
+    /// Traders who entered within the protection window before the close get their
+    /// invested tokens back and give up their shares
+    /// 
+    /// # Arguments
+    /// * `player_id` - The player exiting
+    /// * `market_id` - The market that was closed early
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Position refunded successfully
+    /// * `Err(NotEligibleForExit)` - Market wasn't closed early, is already resolved, or the
+    ///   position was opened before the protection window
+    /// * `Err(NoPosition)` - Player has no position in this market
+    async fn exit_at_cost_basis(&mut self, player_id: PlayerId, market_id: MarketId) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        let closed_at = market.closed_early_at.ok_or(ContractError::NotEligibleForExit)?;
+        if !matches!(market.status, MarketStatus::Closed | MarketStatus::Voting) {
+            return Err(ContractError::NotEligibleForExit);
+        }
+        let protection_micros = self
+            .state
+            .config
+            .get()
+            .early_close_protection_seconds
+            .saturating_mul(1_000_000);
+        market.return_escrowed_shares(Some(player_id));
+        let position = market.remove_position(&player_id).ok_or(ContractError::NoPosition)?;
+        if position.entry_time.micros() < closed_at.micros().saturating_sub(protection_micros) {
+            return Err(ContractError::NotEligibleForExit);
+        }
+        market.total_liquidity = market.total_liquidity.saturating_sub(position.total_invested);
+
+        let mut player = self.get_player(&player_id).await?;
+        player.token_balance = player.token_balance.saturating_add(position.total_invested);
+        self.save_player(player).await?;
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Edit a market's title, description or end time
+    /// Creators may fix mistakes until the first trade, or within the configured edit window
+    /// after creation; every edit is appended to the market's audit log
+    /// 
+    /// # Arguments
+    /// * `caller` - The player editing the market (must be the creator)
+    /// * `market_id` - The market to edit
+    /// * `title` - New title, if changing
+    /// * `description` - New description, if changing
+    /// * `end_time` - New end time, if changing
+    /// * `current_time` - Current timestamp for the edit window
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market updated successfully
+    /// * `Err(Unauthorized)` - Caller is not the market creator
+    /// * `Err(MarketNotActive)` - Market is no longer active
+    /// * `Err(EditWindowClosed)` - Market has liquidity and the edit window has passed
+    /// * `Err(InvalidEndTime)` - End time is in the past or below the minimum duration
+    async fn update_market(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        title: Option<String>,
+        description: Option<String>,
+        end_time: Option<Timestamp>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        let config = self.state.config.get();
+        if market.creator != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        let edit_deadline = market.creation_time.micros()
+            .saturating_add(config.market_edit_window_seconds.saturating_mul(1_000_000));
+        if market.total_liquidity > Amount::ZERO && current_time.micros() > edit_deadline {
+            return Err(ContractError::EditWindowClosed);
+        }
+
+        let mut edit = MarketEdit {
+            editor: caller,
+            edited_at: current_time,
+            previous_title: None,
+            previous_description: None,
+            previous_end_time: None,
+            corrected_outcome: None,
+            previous_outcome_name: None,
+        };
+        if let Some(end_time) = end_time {
+            let end_time = self.round_end_time(end_time);
+            let min_end_time = market.creation_time.micros()
+                .saturating_add(config.min_market_duration_seconds.saturating_mul(1_000_000));
+            if end_time <= current_time || end_time.micros() < min_end_time {
+                return Err(ContractError::InvalidEndTime);
+            }
+            let previous_end_time = std::mem::replace(&mut market.end_time, end_time);
+            self.state.markets_by_end_time.remove(&MarketEndKey { end_time: previous_end_time, market_id })?;
+            self.state.markets_by_end_time.insert(&MarketEndKey { end_time, market_id })?;
+            edit.previous_end_time = Some(previous_end_time);
+        }
+        if let Some(title) = title {
+            edit.previous_title = Some(std::mem::replace(&mut market.title, title));
+        }
+        if let Some(description) = description {
+            edit.previous_description = Some(std::mem::replace(&mut market.description, description));
+        }
+        if edit.previous_title.is_some() || edit.previous_description.is_some() {
+            let previous = search::market_keywords(
+                edit.previous_title.as_deref().unwrap_or(&market.title),
+                edit.previous_description.as_deref().unwrap_or(&market.description),
+            );
+            let current = search::market_keywords(&market.title, &market.description);
+            self.update_search_index(market_id, &previous, &current).await?;
+        }
+        market.edit_history.push(edit);
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Propose renaming an outcome, replacing any pending proposal
+    /// Applied at once if the creator's own shares are a majority or nobody holds shares
+    ///
+    /// # Returns
+    /// * `Ok(())` - Correction proposed, or applied
+    /// * `Err(Unauthorized)` - Caller is not the market creator
+    /// * `Err(MarketNotActive)` - Market is no longer active
+    /// * `Err(InvalidOutcome)` - Outcome doesn't exist
+    /// * `Err(InvalidOutcomeCorrection)` - New name is empty, too long or unchanged
+    async fn propose_outcome_correction(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        new_name: String,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.creator != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        let outcome = market.outcomes.get(outcome_id as usize).ok_or(ContractError::InvalidOutcome)?;
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() || new_name.chars().count() > MAX_OUTCOME_NAME_LENGTH || new_name == outcome.name {
+            return Err(ContractError::InvalidOutcomeCorrection);
+        }
+        market.pending_correction = Some(OutcomeCorrection {
+            outcome_id,
+            new_name,
+            proposed_at: current_time,
+            approvals: vec![caller],
+        });
+        Self::apply_correction_if_approved(&mut market, current_time);
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Approve the pending outcome correction, applying it once approvals hold a majority
+    ///
+    /// # Returns
+    /// * `Ok(())` - Approval recorded, and the correction applied if it now has a majority
+    /// * `Err(MarketNotActive)` - Market is no longer active
+    /// * `Err(NoPendingCorrection)` - No correction is waiting for approval
+    /// * `Err(NoPosition)` - Player holds no shares in this market
+    async fn approve_outcome_correction(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        if !market
+            .positions
+            .get(&player_id)
+            .is_some_and(|position| position.shares_by_outcome.values().any(|shares| *shares > Amount::ZERO))
+        {
+            return Err(ContractError::NoPosition);
+        }
+        let correction = market.pending_correction.as_mut().ok_or(ContractError::NoPendingCorrection)?;
+        if !correction.approvals.contains(&player_id) {
+            correction.approvals.push(player_id);
+        }
+        Self::apply_correction_if_approved(&mut market, current_time);
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Flag a market in its review window as ambiguous, pausing it once the flaggers'
+    /// reputation reaches `ambiguity_pause_weight`
+    ///
+    /// # Returns
+    /// * `Ok(())` - Flag recorded, and the market paused if the flags now weigh enough
+    /// * `Err(MarketPaused)` - Market is already paused
+    /// * `Err(NotInReview)` - Market's review window is over, or it never had one
+    /// * `Err(Unauthorized)` - Caller created the market
+    /// * `Err(AlreadyFlagged)` - Player flagged the market before
+    /// * `Err(InvalidAmbiguityFlag)` - Reason is empty or too long
+    async fn flag_ambiguous_market(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        reason: String,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        let player = self.get_player(&player_id).await?;
+        Self::ensure_market_access(&market, &player)?;
+        if market.paused_at.is_some() {
+            return Err(ContractError::MarketPaused);
+        }
+        if !market.in_review(current_time) {
+            return Err(ContractError::NotInReview);
+        }
+        if market.creator == player_id {
+            return Err(ContractError::Unauthorized);
+        }
+        if market.ambiguity_flags.iter().any(|flag| flag.player == player_id) {
+            return Err(ContractError::AlreadyFlagged);
+        }
+        let reason = reason.trim().to_string();
+        if reason.is_empty() || reason.chars().count() > MAX_FLAG_REASON_LENGTH {
+            return Err(ContractError::InvalidAmbiguityFlag);
+        }
+        market.ambiguity_flags.push(AmbiguityFlag {
+            player: player_id,
+            weight: player.reputation,
+            reason,
+            flagged_at: current_time,
+        });
+        if market.ambiguity_weight() >= self.state.config.get().ambiguity_pause_weight {
+            market.paused_at = Some(current_time);
+        }
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Reopen a paused market and clear its flags
+    /// The creator must have edited the market since it paused, and its review window starts
+    /// over; the admin can resume it as it is. The market's schedule moves back by however
+    /// long trading's opening slipped, so it keeps its full duration
+    ///
+    /// # Returns
+    /// * `Ok(())` - Market resumed
+    /// * `Err(MarketNotPaused)` - Market isn't paused
+    /// * `Err(Unauthorized)` - Caller is neither the creator nor the admin
+    /// * `Err(MarketNotClarified)` - Creator hasn't edited the market since it paused
+    async fn resume_market(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        let paused_at = market.paused_at.ok_or(ContractError::MarketNotPaused)?;
+        let review_seconds = if self.ensure_admin(caller).is_ok() {
+            0
+        } else if caller == market.creator {
+            if !market.edit_history.iter().any(|edit| edit.edited_at >= paused_at) {
+                return Err(ContractError::MarketNotClarified);
+            }
+            self.state.config.get().ambiguity_review_seconds
+        } else {
+            return Err(ContractError::Unauthorized);
+        };
+
+        let opened_at = market.review_end.unwrap_or(paused_at);
+        let reopens_at = opened_at.max(current_time.saturating_add(TimeDelta::from_secs(review_seconds)));
+        let slipped = TimeDelta::from_micros(reopens_at.micros().saturating_sub(opened_at.micros()));
+        market.review_end = Some(reopens_at);
+        market.bootstrap_end = market.bootstrap_end.map(|end| end.saturating_add(slipped));
+        let previous_end_time = market.end_time;
+        market.end_time = self.round_end_time(previous_end_time.saturating_add(slipped));
+        self.state.markets_by_end_time.remove(&MarketEndKey { end_time: previous_end_time, market_id })?;
+        self.state.markets_by_end_time.insert(&MarketEndKey { end_time: market.end_time, market_id })?;
+        market.paused_at = None;
+        market.ambiguity_flags.clear();
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Rename the outcome if the pending correction's approvals hold a majority of shares now,
+    /// recording the old name in the edit history
+    fn apply_correction_if_approved(market: &mut Market, current_time: Timestamp) {
+        let approved = market
+            .pending_correction
+            .as_ref()
+            .is_some_and(|correction| market.has_majority(&correction.approvals));
+        let Some(correction) = market.pending_correction.take_if(|_| approved) else {
+            return;
+        };
+        let outcome = &mut market.outcomes[correction.outcome_id as usize];
+        let previous_name = std::mem::replace(&mut outcome.name, correction.new_name);
+        market.edit_history.push(MarketEdit {
+            editor: market.creator,
+            edited_at: current_time,
+            previous_title: None,
+            previous_description: None,
+            previous_end_time: None,
+            corrected_outcome: Some(correction.outcome_id),
+            previous_outcome_name: Some(previous_name),
+        });
+    }
+
+    /// Add or remove players on a Whitelist market's allowlist
+    /// Only the market creator can manage who may participate
+    /// 
+    /// # Arguments
+    /// * `caller` - The player updating the allowlist (must be the creator)
+    /// * `market_id` - The market to update
+    /// * `add` - Players to allow
+    /// * `remove` - Players to remove from the allowlist
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Allowlist updated successfully
+    /// * `Err(Unauthorized)` - Caller is not the market creator
+    /// * `Err(InvalidVisibility)` - Market is not a Whitelist market
+    async fn update_market_allowlist(
+        &mut self,
+        caller: PlayerId,
+        market_id: MarketId,
+        add: Vec<PlayerId>,
+        remove: Vec<PlayerId>,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.creator != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if market.visibility != MarketVisibility::Whitelist {
+            return Err(ContractError::InvalidVisibility);
+        }
+        market.allowlist.retain(|p| !remove.contains(p));
+        for player_id in add {
+            if !market.allowlist.contains(&player_id) {
+                market.allowlist.push(player_id);
+            }
+        }
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Claim winnings from a resolved market
+    /// Allows players to claim their tokens from winning bets
+    /// 
+    /// # Arguments
+    /// * `player_id` - The player claiming winnings
+    /// * `market_id` - The market to claim winnings from
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Winnings claimed successfully
+    /// * `Err(NotResolved)` - Market hasn't been resolved yet
+    /// * `Err(NoWinnings)` - Player has no winning shares in this market
+    /// * `Err(AlreadySettled)` - This position's winnings were already paid out
+    /// * `Err(ClaimWindowExpired)` - The market's claim window has closed
+    async fn claim_winnings(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Resolved || market.winning_outcomes.is_empty() {
+            return Err(ContractError::NotResolved);
+        }
+        if market.claims_expire_at.is_some_and(|expires_at| current_time >= expires_at) {
+            return Err(ContractError::ClaimWindowExpired);
+        }
+        let position = market.positions.get(&player_id).ok_or(ContractError::NoPosition)?;
+        if position.settled {
+            return Err(ContractError::AlreadySettled);
+        }
+        let payout = market.payout_for(position);
+        if payout == Amount::ZERO {
+            return Err(ContractError::NoWinnings);
+        }
+        if let Some(position) = market.positions.get_mut(&player_id) {
+            position.settled = true;
+        }
+        self.state.markets.insert(&market_id, market)?;
+        self.archive_player_market(player_id, market_id).await?;
+        self.credit_winnings(player_id, payout).await
+    }
+
+    /// Record that a player is taking part in a market; returns whether it's new to them
+    /// A market the player left for their history, such as after an emergency withdrawal,
+    /// becomes active again without counting as new
+    async fn join_market(&mut self, player_id: PlayerId, market_id: MarketId) -> Result<bool, ContractError> {
+        let mut markets = self.state.active_markets.try_load_entry_mut(&player_id).await?;
+        if markets.contains(&market_id).await? {
+            return Ok(false);
+        }
+        markets.insert(&market_id)?;
+        let archived = self.state.archived_market_ids.try_load_entry(&player_id).await?;
+        Ok(match archived {
+            Some(archived) => !archived.contains(&market_id).await?,
+            None => true,
+        })
+    }
+
+    /// Count a player who just joined a market and add them to its participant list
+    async fn add_participant(&mut self, market: &mut Market, player_id: PlayerId) -> Result<(), ContractError> {
+        market.total_participants += 1;
+        self.state.market_participants.try_load_entry_mut(&market.id).await?.push(player_id);
+        Ok(())
+    }
+
+    /// Move a market a player is done with from their active set to their history
+    async fn archive_player_market(&mut self, player_id: PlayerId, market_id: MarketId) -> Result<(), ContractError> {
+        let mut markets = self.state.active_markets.try_load_entry_mut(&player_id).await?;
+        if !markets.contains(&market_id).await? {
+            return Ok(());
+        }
+        markets.remove(&market_id)?;
+        // A market the player returned to is already in their history
+        let mut archived = self.state.archived_market_ids.try_load_entry_mut(&player_id).await?;
+        if !archived.contains(&market_id).await? {
+            archived.insert(&market_id)?;
+            self.state.archived_markets.try_load_entry_mut(&player_id).await?.push(market_id);
+        }
+        Ok(())
+    }
+
+    /// Warn every winner who hasn't claimed that the market's claim window is closing
+    /// Anyone may call this, once, during the last `claim_expiry_warning_seconds` of the window
+    /// 
+    /// # Returns
+    /// * `Ok(())` - A `ClaimExpiring` notification sent for each unclaimed winning position
+    /// * `Err(NotResolved)` - Market hasn't been resolved
+    /// * `Err(ExpiryWarningNotDue)` - No claim window, too early, already warned or already closed
+    async fn warn_expiring_claims(&mut self, market_id: MarketId, current_time: Timestamp) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Resolved {
+            return Err(ContractError::NotResolved);
+        }
+        let expires_at = market.claims_expire_at.ok_or(ContractError::ExpiryWarningNotDue)?;
+        let warning_seconds = self.state.config.get().claim_expiry_warning_seconds;
+        let warn_from = Timestamp::from(expires_at.micros().saturating_sub(warning_seconds.saturating_mul(1_000_000)));
+        if market.expiry_warning_sent || current_time < warn_from || current_time >= expires_at {
+            return Err(ContractError::ExpiryWarningNotDue);
+        }
+        for (player_id, position) in &market.positions {
+            let payout = market.payout_for(position);
+            if position.settled || payout == Amount::ZERO {
+                continue;
+            }
+            self.runtime
+                .prepare_message(Message::ClaimExpiring {
+                    market_id,
+                    player_id: *player_id,
+                    payout,
+                    expires_at,
+                })
+                .send_to(self.runtime.chain_id());
+        }
+        market.expiry_warning_sent = true;
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Move a resolved market whose winnings are all paid out into the archive
+    /// Anyone may call this once `market_archive_after_days` have passed since resolution.
+    /// The full market, its votes, ratings and search keywords are dropped; only its
+    /// summary stays, for the `archivedMarket` query
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market replaced by its summary
+    /// * `Err(NotResolved)` - Market hasn't been resolved
+    /// * `Err(ArchiveTooEarly)` - Market resolved too recently
+    /// * `Err(MarketNotSettled)` - Some winnings haven't been claimed, settled or swept
+    async fn archive_market(&mut self, market_id: MarketId, current_time: Timestamp) -> Result<(), ContractError> {
+        let market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Resolved {
+            return Err(ContractError::NotResolved);
+        }
+        let archive_after = TimeDelta::from_secs(self.state.config.get().market_archive_after_days * 24 * 60 * 60);
+        if market
+            .resolution_time
+            .is_none_or(|resolved_at| current_time < resolved_at.saturating_add(archive_after))
+        {
+            return Err(ContractError::ArchiveTooEarly);
+        }
+        if !market.is_fully_settled() {
+            return Err(ContractError::MarketNotSettled);
+        }
+
+        let keywords = search::market_keywords(&market.title, &market.description);
+        self.update_search_index(market_id, &keywords, &BTreeSet::new()).await?;
+        self.state.oracle_votes.remove(&market_id)?;
+        self.state.market_raters.remove(&market_id)?;
+        self.state.market_subscribers.remove(&market_id)?;
+        self.state.repeat_trade_xp.remove_entry(&market_id)?;
+        self.state.price_impact_windows.remove_entry(&market_id)?;
+        self.state.market_activity.remove(&market_id)?;
+        self.state.market_archive.insert(&market_id, market.summary())?;
+        self.state.markets.remove(&market_id)?;
+        Ok(())
+    }
+
+    /// Move winnings nobody claimed before the claim window closed to the treasury
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Unclaimed positions marked settled and their payouts added to the treasury
+    /// * `Err(NotResolved)` - Market hasn't been resolved
+    /// * `Err(ClaimWindowOpen)` - Market has no claim window, or it hasn't closed yet
+    /// * `Err(NoWinnings)` - Nothing is left to sweep
+    async fn sweep_expired_winnings(&mut self, market_id: MarketId, current_time: Timestamp) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Resolved {
+            return Err(ContractError::NotResolved);
+        }
+        if market.claims_expire_at.is_none_or(|expires_at| current_time < expires_at) {
+            return Err(ContractError::ClaimWindowOpen);
+        }
+        let payouts: Vec<(PlayerId, Amount)> = market
+            .positions
+            .iter()
+            .filter(|(_, position)| !position.settled)
+            .map(|(player_id, position)| (*player_id, market.payout_for(position)))
+            .filter(|(_, payout)| *payout > Amount::ZERO)
+            .collect();
+        if payouts.is_empty() {
+            return Err(ContractError::NoWinnings);
+        }
+        let mut swept = Amount::ZERO;
+        for (player_id, payout) in payouts {
+            if let Some(position) = market.positions.get_mut(&player_id) {
+                position.settled = true;
+            }
+            self.archive_player_market(player_id, market_id).await?;
+            swept = swept.saturating_add(payout);
+        }
+        let treasury = self.state.treasury.get().saturating_add(swept);
+        self.state.treasury.set(treasury);
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Add winnings to a player's balance (no external transfer needed)
+    async fn credit_winnings(&mut self, player_id: PlayerId, payout: Amount) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        player.token_balance = player.token_balance.saturating_add(payout);
+        player.total_earned = player.total_earned.saturating_add(payout);
+        let delta = PlayerStatsDelta {
+            level: player.level,
+            earned: payout,
+            ..PlayerStatsDelta::default()
+        };
+        self.save_player(player).await?;
+        self.report_player_stats(player_id, delta);
+        Ok(())
+    }
+
+    /// Pay one batch of a resolved market's winners, in player ID order after `after`
+    /// Each batch is capped by `settlement_batch_size` and schedules the next one itself,
+    /// so large markets settle over several blocks
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Batch paid and the next one scheduled if positions remain
+    /// * `Err(Unauthorized)` - Message didn't come from this chain
+    /// * `Err(NotResolved)` - Market hasn't been resolved
+    async fn settle_winners(&mut self, market_id: MarketId, after: Option<PlayerId>) -> Result<(), ContractError> {
+        if self.runtime.message_origin_chain_id() != Some(self.runtime.chain_id()) {
+            return Err(ContractError::Unauthorized);
+        }
+        let mut market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Resolved || market.winning_outcomes.is_empty() {
+            return Err(ContractError::NotResolved);
+        }
+        let batch_size = self.state.config.get().settlement_batch_size.max(1) as usize;
+        let start = match after {
+            Some(player_id) => Bound::Excluded(player_id),
+            None => Bound::Unbounded,
+        };
+        let batch: Vec<PlayerId> = market
+            .positions
+            .range((start, Bound::Unbounded))
+            .map(|(player_id, _)| *player_id)
+            .take(batch_size + 1)
+            .collect();
+
+        // Mark the batch settled and save the market before paying anyone, so a failed credit
+        // can't leave a paid position open to be paid again. Traders registered on another
+        // chain have no local record to credit and stay unsettled.
+        let mut payouts = Vec::new();
+        for player_id in batch.iter().take(batch_size) {
+            if market.positions.get(player_id).is_none_or(|position| position.settled)
+                || !self.state.players.contains_key(player_id).await?
+            {
+                continue;
+            }
+            let payout = market.payout_for(&market.positions[player_id]);
+            if let Some(position) = market.positions.get_mut(player_id) {
+                position.settled = true;
+            }
+            payouts.push((*player_id, payout));
+        }
+        self.state.markets.insert(&market_id, market)?;
+        for (player_id, payout) in payouts {
+            if payout > Amount::ZERO {
+                self.credit_winnings(player_id, payout).await?;
+            }
+            self.archive_player_market(player_id, market_id).await?;
+        }
+
+        if batch.len() > batch_size {
+            self.runtime
+                .prepare_message(Message::SettleWinners {
+                    market_id,
+                    after: Some(batch[batch_size - 1]),
+                })
+                .send_to(self.runtime.chain_id());
+        }
+        Ok(())
+    }
+
+    /// Rate a resolved market once, as a participant
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Rating recorded on the market and the creator's stats
+    /// * `Err(NotResolved)` - Market hasn't resolved yet
+    /// * `Err(NoPosition)` - Player did not participate in the market
+    /// * `Err(AlreadyRated)` - Player already rated this market
+    async fn rate_market(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        thumbs_up: bool,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Resolved {
+            return Err(ContractError::NotResolved);
+        }
+        if !market.positions.contains_key(&player_id) {
+            return Err(ContractError::NoPosition);
+        }
+        let mut raters = self.state.market_raters.get(&market_id).await?.unwrap_or_default();
+        if raters.contains(&player_id) {
+            return Err(ContractError::AlreadyRated);
+        }
+        raters.push(player_id);
+
+        let mut stats = self.state.creator_stats.get(&market.creator).await?.unwrap_or_default();
+        if thumbs_up {
+            market.thumbs_up += 1;
+            stats.thumbs_up += 1;
+        } else {
+            market.thumbs_down += 1;
+            stats.thumbs_down += 1;
+        }
+        self.state.creator_stats.insert(&market.creator, stats)?;
+        self.state.market_raters.insert(&market_id, raters)?;
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Create a new guild
+    /// Allows players to form social groups for collaborative gameplay
+    /// 
+    /// # Arguments
+    /// * `founder` - The player creating the guild
+    /// * `name` - The name of the guild
+    /// * `current_time` - Current timestamp for guild creation
+    /// 
+    /// # Returns
+    /// * `Ok(guild_id)` - Guild created successfully
+    /// * `Err(AlreadyInGuild)` - Founder is already in a guild
+    async fn create_guild(
+        &mut self,
+        founder: PlayerId,
+        name: String,
+        current_time: Timestamp,
+    ) -> Result<GuildId, ContractError> {
+        let mut player = self.get_player(&founder).await?;
+        if player.guild_id.is_some() {
+            return Err(ContractError::AlreadyInGuild);
+        }
+        let new_id = self.generate_guild_id().await?;
+        let guild = Guild {
+            id: new_id,
+            name: name.clone(),
+            founder,
+            members: vec![founder],
+            officers: vec![founder],
+            creation_time: current_time,
+            total_guild_profit: Amount::ZERO,
+            guild_level: 1,
+            shared_pool: Amount::ZERO,
+            total_contributed: Amount::ZERO,
+            members_recruited: 0,
+            achievements_earned: Vec::new(),
+            stake_approvals_required: 1,
+            league_tier: LeagueTier::Bronze,
+            token_application: None,
+            token_pool: Amount::ZERO,
+        };
+        self.state.guilds.insert(&new_id, guild)?;
+        player.guild_id = Some(new_id);
+        self.save_player(player).await?;
+
+        self
+            .runtime
+            .prepare_message(Message::GuildCreated { guild_id: new_id, name })
+            .send_to(self.runtime.chain_id());
+        Ok(new_id)
+    }
+
+    /// Join an existing guild
+    /// Allows players to join guilds created by other players
+    /// 
+    /// # Arguments
+    /// * `player_id` - The player joining the guild
+    /// * `guild_id` - The guild to join
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Successfully joined guild
+    /// * `Err(AlreadyInGuild)` - Player is already in a guild
+    /// * `Err(GuildNotFound)` - Guild doesn't exist
+    async fn join_guild(&mut self, player_id: PlayerId, guild_id: GuildId) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        if player.guild_id.is_some() {
+            return Err(ContractError::AlreadyInGuild);
+        }
+        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        guild.members.push(player_id);
+        // Players who leave and rejoin were only recruited once
+        let mut recruits = self.state.guild_recruits.try_load_entry_mut(&guild_id).await?;
+        if player_id != guild.founder && !recruits.contains(&player_id).await? {
+            recruits.insert(&player_id)?;
+            guild.members_recruited += 1;
+        }
+        drop(recruits);
+        self.check_guild_achievements(&mut guild).await?;
+        self.state.guilds.insert(&guild_id, guild)?;
+        player.guild_id = Some(guild_id);
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    /// Leave the current guild
+    /// Allows players to leave their current guild
+    /// 
+    /// # Arguments
+    /// * `player_id` - The player leaving the guild
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Successfully left guild
+    /// * `Err(NotGuildMember)` - Player is not in a guild
+    async fn leave_guild(&mut self, player_id: PlayerId) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
+        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        guild.members.retain(|m| m != &player_id);
+        guild.officers.retain(|m| m != &player_id);
+        self.state.guilds.insert(&guild_id, guild)?;
+        player.guild_id = None;
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    /// Contribute tokens to the guild's shared pool
+    /// Allows guild members to contribute tokens to the guild's collective fund
+    /// 
+    /// # Arguments
+    /// * `player_id` - The player contributing tokens
+    /// * `amount` - How many tokens to contribute
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Contribution successful
+    /// * `Err(NotGuildMember)` - Player is not in a guild
+    /// * `Err(InsufficientBalance)` - Player doesn't have enough tokens
+    async fn contribute_to_guild(&mut self, player_id: PlayerId, amount: Amount) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
+        if player.token_balance < amount { return Err(ContractError::InsufficientBalance); }
+        
+        // Deduct contribution from player's points (no external transfer needed)
+        
+        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        player.token_balance = player.token_balance.saturating_sub(amount);
+        guild.shared_pool = guild.shared_pool.saturating_add(amount);
+        guild.total_contributed = guild.total_contributed.saturating_add(amount);
+        self.check_guild_achievements(&mut guild).await?;
+        self.save_player(player).await?;
+        self.state.guilds.insert(&guild_id, guild)?;
+        Ok(())
+    }
+
+    /// Grant or revoke officer rights in the caller's guild (founder only)
+    /// 
+    /// # Arguments
+    /// * `caller` - The player changing roles (must be the guild founder)
+    /// * `member` - The guild member whose role changes
+    /// * `is_officer` - Whether the member should be an officer
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Role updated successfully
+    /// * `Err(NotGuildMember)` - Caller or member is not in the guild
+    /// * `Err(Unauthorized)` - Caller is not the founder, or tried to demote the founder
+    async fn set_guild_officer(
+        &mut self,
+        caller: PlayerId,
+        member: PlayerId,
+        is_officer: bool,
+    ) -> Result<(), ContractError> {
+        let player = self.get_player(&caller).await?;
+        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
+        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        if guild.founder != caller || member == guild.founder {
+            return Err(ContractError::Unauthorized);
+        }
+        if !guild.members.contains(&member) {
+            return Err(ContractError::NotGuildMember);
+        }
+        guild.officers.retain(|m| m != &member);
+        if is_officer {
+            guild.officers.push(member);
+        }
+        self.state.guilds.insert(&guild_id, guild)?;
+        Ok(())
+    }
+
+    /// Post an announcement to the caller's guild board (officers only)
+    /// The board keeps the most recent announcements and drops the oldest
+    /// 
+    /// # Arguments
+    /// * `author` - The officer posting
+    /// * `text` - Announcement text
+    /// * `current_time` - Current timestamp, recorded on the announcement
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Announcement posted
+    /// * `Err(NotGuildMember)` - Author is not in a guild
+    /// * `Err(Unauthorized)` - Author is not an officer
+    /// * `Err(InvalidAnnouncement)` - Text is empty or too long
+    async fn post_guild_announcement(
+        &mut self,
+        author: PlayerId,
+        text: String,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let player = self.get_player(&author).await?;
+        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
+        let guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        if !guild.officers.contains(&author) {
+            return Err(ContractError::Unauthorized);
+        }
+        let text = text.trim().to_string();
+        if text.is_empty() || text.len() > MAX_ANNOUNCEMENT_LENGTH {
+            return Err(ContractError::InvalidAnnouncement);
+        }
+
+        let mut board = self
+            .state
+            .guild_announcements
+            .get(&guild_id)
+            .await?
+            .unwrap_or_default();
+        board.push(GuildAnnouncement { author, text, posted_at: current_time });
+        if board.len() > MAX_GUILD_ANNOUNCEMENTS {
+            board.drain(..board.len() - MAX_GUILD_ANNOUNCEMENTS);
+        }
+        self.state.guild_announcements.insert(&guild_id, board)?;
+        Ok(())
+    }
+
+    /// Propose investing part of the guild's shared pool in a market outcome
+    /// The proposer's approval counts, so guilds needing a single approval invest immediately
+    /// 
+    /// # Returns
+    /// * `Ok(proposal_id)` - Proposal recorded, and executed if it already has enough approvals
+    /// * `Err(Unauthorized)` - Player is not an officer of their guild
+    /// * `Err(InsufficientBalance)` - Amount is zero or exceeds the shared pool
+    /// * `Err(InvalidOutcome)` - Outcome doesn't exist
+    async fn guild_buy_shares(
+        &mut self,
+        officer: PlayerId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        amount: Amount,
+        current_time: Timestamp,
+    ) -> Result<GuildStakeId, ContractError> {
+        let guild = self.get_officer_guild(&officer).await?;
+        if amount == Amount::ZERO || guild.shared_pool < amount {
+            return Err(ContractError::InsufficientBalance);
+        }
+        let market = self.get_market(&market_id).await?;
+        if outcome_id >= market.outcomes.len() as OutcomeId {
+            return Err(ContractError::InvalidOutcome);
+        }
+
+        let proposal_id = *self.state.next_guild_stake_id.get();
+        self.state.next_guild_stake_id.set(proposal_id + 1);
+        let mut proposal = GuildStakeProposal {
+            id: proposal_id,
+            guild_id: guild.id,
+            market_id,
+            outcome_id,
+            amount,
+            proposer: officer,
+            approvals: vec![officer],
+            created_at: current_time,
+            executed: false,
+        };
+        if proposal.approvals.len() as u32 >= guild.stake_approvals_required {
+            self.execute_guild_stake(&mut proposal, current_time).await?;
+        }
+        self.state.guild_stake_proposals.insert(&proposal_id, proposal)?;
+        Ok(proposal_id)
+    }
+
+    /// Add an officer's approval to a staking proposal, executing it once the guild's
+    /// threshold is reached
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Approval recorded, and the proposal executed if it now has enough
+    /// * `Err(GuildStakeNotFound)` - No proposal with this ID in the officer's guild
+    /// * `Err(GuildStakeExecuted)` - Proposal already executed
+    /// * `Err(AlreadyApproved)` - Officer already approved it
+    async fn approve_guild_stake(
+        &mut self,
+        officer: PlayerId,
+        proposal_id: GuildStakeId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let guild = self.get_officer_guild(&officer).await?;
+        let mut proposal = self
+            .state
+            .guild_stake_proposals
+            .get(&proposal_id)
+            .await?
+            .filter(|proposal| proposal.guild_id == guild.id)
+            .ok_or(ContractError::GuildStakeNotFound)?;
+        if proposal.executed {
+            return Err(ContractError::GuildStakeExecuted);
+        }
+        if proposal.approvals.contains(&officer) {
+            return Err(ContractError::AlreadyApproved);
+        }
+        proposal.approvals.push(officer);
+        if proposal.approvals.len() as u32 >= guild.stake_approvals_required {
+            self.execute_guild_stake(&mut proposal, current_time).await?;
+        }
+        self.state.guild_stake_proposals.insert(&proposal_id, proposal)?;
+        Ok(())
+    }
+
+    /// Buy shares for the guild with its shared pool
+    /// Fails without side effects if the market no longer takes trades or the pool has
+    /// shrunk below the proposed amount
+    async fn execute_guild_stake(
+        &mut self,
+        proposal: &mut GuildStakeProposal,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut guild = self.state.guilds.get(&proposal.guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        let mut market = self.get_market(&proposal.market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let allowed = match market.visibility {
+            MarketVisibility::Public => true,
+            MarketVisibility::GuildOnly => market.allowed_guild == Some(guild.id),
+            MarketVisibility::Whitelist => false,
+        };
+        if !allowed {
+            return Err(ContractError::MarketAccessDenied);
+        }
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+        if guild.shared_pool < proposal.amount {
+            return Err(ContractError::InsufficientBalance);
+        }
+
+        let (trade, shares) = self.price_buy(&market, proposal.outcome_id, Tokens::new(proposal.amount), current_time)?;
+        market.add_guild_shares(guild.id, proposal.outcome_id, shares.amount(), trade.net.amount(), current_time);
+        market.total_liquidity = market.total_liquidity.saturating_add(trade.net.amount());
+        market.outcomes[proposal.outcome_id as usize].current_price =
+            self.calculate_current_price(&market, proposal.outcome_id)?.amount();
+        guild.shared_pool = guild.shared_pool.saturating_sub(proposal.amount);
+        proposal.executed = true;
+
+        self.state.markets.insert(&proposal.market_id, market)?;
+        let guild_id = guild.id;
+        self.state.guilds.insert(&guild_id, guild)?;
+        self.distribute_trading_fees(proposal.market_id, None, proposal.amount, trade.fee_bps, current_time).await?;
+        self.record_market_activity(proposal.market_id, proposal.outcome_id, TradeSide::Buy, proposal.amount, current_time)
+            .await?;
+        Ok(())
+    }
+
+    /// Set how many officer approvals the guild's staking proposals need
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Threshold updated; it applies to pending proposals on their next approval
+    /// * `Err(Unauthorized)` - Player is not the guild founder
+    /// * `Err(InvalidApprovalThreshold)` - Zero, or more than the guild has officers
+    async fn set_guild_stake_approvals(&mut self, founder: PlayerId, approvals: u32) -> Result<(), ContractError> {
+        let mut guild = self.get_officer_guild(&founder).await?;
+        if guild.founder != founder {
+            return Err(ContractError::Unauthorized);
+        }
+        if approvals == 0 || approvals as usize > guild.officers.len() {
+            return Err(ContractError::InvalidApprovalThreshold);
+        }
+        guild.stake_approvals_required = approvals;
+        let guild_id = guild.id;
+        self.state.guilds.insert(&guild_id, guild)?;
+        Ok(())
+    }
+
+    /// Pay the guild's winnings from a resolved market into its shared pool
+    /// Anything returned above the invested amount counts towards the guild's profit
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Winnings added to the shared pool
+    /// * `Err(NotResolved)` - Market hasn't resolved yet
+    /// * `Err(NoPosition)` - Guild has no unclaimed position in this market
+    /// * `Err(NoWinnings)` - Guild's position won nothing
+    async fn claim_guild_winnings(&mut self, officer: PlayerId, market_id: MarketId) -> Result<(), ContractError> {
+        let mut guild = self.get_officer_guild(&officer).await?;
+        let mut market = self.get_market(&market_id).await?;
+        if market.status != MarketStatus::Resolved || market.winning_outcomes.is_empty() {
+            return Err(ContractError::NotResolved);
+        }
+        let position = market
+            .guild_positions
+            .get(&guild.id)
+            .filter(|position| !position.claimed)
+            .ok_or(ContractError::NoPosition)?;
+        let payout = market.guild_payout_for(position);
+        if payout == Amount::ZERO {
+            return Err(ContractError::NoWinnings);
+        }
+        let profit = payout.saturating_sub(position.total_invested);
+
+        guild.shared_pool = guild.shared_pool.saturating_add(payout);
+        guild.total_guild_profit = guild.total_guild_profit.saturating_add(profit);
+        if let Some(position) = market.guild_positions.get_mut(&guild.id) {
+            position.claimed = true;
+        }
+        let guild_id = guild.id;
+        self.state.guilds.insert(&guild_id, guild)?;
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Choose the fungible token application the caller's guild keeps its token pool in
+    /// Guild pools otherwise hold only in-game tokens
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Token application set, or cleared with `None`
+    /// * `Err(NotGuildMember)` - Caller is not in a guild
+    /// * `Err(Unauthorized)` - Caller is not the founder
+    /// * `Err(GuildTokenPoolNotEmpty)` - Tokens of the current application are still pooled
+    async fn set_guild_token_application(
+        &mut self,
+        caller: PlayerId,
+        application_id: Option<ApplicationId>,
+    ) -> Result<(), ContractError> {
+        let player = self.get_player(&caller).await?;
+        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
+        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        if guild.founder != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if guild.token_pool > Amount::ZERO {
+            return Err(ContractError::GuildTokenPoolNotEmpty);
+        }
+        guild.token_application = application_id;
+        self.state.guilds.insert(&guild_id, guild)?;
+        Ok(())
+    }
+
+    /// Move the caller's tokens in the guild's token application to this application's account
+    /// and credit them to the guild's token pool
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Tokens transferred and pooled
+    /// * `Err(NotGuildMember)` - Caller is not in a guild
+    /// * `Err(NoGuildTokenApplication)` - The guild's pool holds only in-game tokens
+    async fn deposit_guild_tokens(&mut self, player_id: PlayerId, amount: Amount) -> Result<(), ContractError> {
+        let player = self.get_player(&player_id).await?;
+        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
+        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        let token = guild.token_application.ok_or(ContractError::NoGuildTokenApplication)?;
+
+        // The token application rejects the call, and the block, if the signer is short
+        let target_account = Account { chain_id: self.runtime.chain_id(), owner: self.application_account() };
+        self.call_token_application(token, FungibleOperation::Transfer { owner: player_id, amount, target_account });
+        guild.token_pool = guild.token_pool.saturating_add(amount);
+        self.state.guilds.insert(&guild_id, guild)?;
+        Ok(())
+    }
+
+    /// Propose paying tokens from the caller's guild's token pool to `recipient`'s account on
+    /// this chain
+    /// Like staking proposals, it needs `stake_approvals_required` officer approvals, the
+    /// proposer's included, so guilds needing a single approval pay out immediately
+    /// 
+    /// # Returns
+    /// * `Ok(withdrawal_id)` - Withdrawal recorded, and executed if it already has enough approvals
+    /// * `Err(NotGuildMember)` - Caller is not in a guild
+    /// * `Err(Unauthorized)` - Caller is not an officer
+    /// * `Err(NoGuildTokenApplication)` - The guild's pool holds only in-game tokens
+    /// * `Err(InsufficientBalance)` - Amount is zero or exceeds the pool
+    async fn withdraw_guild_tokens(
+        &mut self,
+        officer: PlayerId,
+        amount: Amount,
+        recipient: AccountOwner,
+        current_time: Timestamp,
+    ) -> Result<GuildWithdrawalId, ContractError> {
+        let guild = self.get_officer_guild(&officer).await?;
+        if guild.token_application.is_none() {
+            return Err(ContractError::NoGuildTokenApplication);
+        }
+        if amount == Amount::ZERO || guild.token_pool < amount {
+            return Err(ContractError::InsufficientBalance);
+        }
+
+        let withdrawal_id = *self.state.next_guild_withdrawal_id.get();
+        self.state.next_guild_withdrawal_id.set(withdrawal_id + 1);
+        let mut withdrawal = GuildTokenWithdrawal {
+            id: withdrawal_id,
+            guild_id: guild.id,
+            amount,
+            recipient,
+            proposer: officer,
+            approvals: vec![officer],
+            created_at: current_time,
+            executed: false,
+        };
+        if withdrawal.approvals.len() as u32 >= guild.stake_approvals_required {
+            self.execute_guild_token_withdrawal(&mut withdrawal).await?;
+        }
+        self.state.guild_token_withdrawals.insert(&withdrawal_id, withdrawal)?;
+        Ok(withdrawal_id)
+    }
+
+    /// Add an officer's approval to a token pool withdrawal, executing it once the guild's
+    /// threshold is reached
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Approval recorded, and the withdrawal executed if it now has enough
+    /// * `Err(GuildWithdrawalNotFound)` - No withdrawal with this ID in the officer's guild
+    /// * `Err(GuildWithdrawalExecuted)` - Withdrawal already executed
+    /// * `Err(AlreadyApproved)` - Officer already approved it
+    async fn approve_guild_token_withdrawal(
+        &mut self,
+        officer: PlayerId,
+        withdrawal_id: GuildWithdrawalId,
+    ) -> Result<(), ContractError> {
+        let guild = self.get_officer_guild(&officer).await?;
+        let mut withdrawal = self
+            .state
+            .guild_token_withdrawals
+            .get(&withdrawal_id)
+            .await?
+            .filter(|withdrawal| withdrawal.guild_id == guild.id)
+            .ok_or(ContractError::GuildWithdrawalNotFound)?;
+        if withdrawal.executed {
+            return Err(ContractError::GuildWithdrawalExecuted);
+        }
+        if withdrawal.approvals.contains(&officer) {
+            return Err(ContractError::AlreadyApproved);
+        }
+        withdrawal.approvals.push(officer);
+        if withdrawal.approvals.len() as u32 >= guild.stake_approvals_required {
+            self.execute_guild_token_withdrawal(&mut withdrawal).await?;
+        }
+        self.state.guild_token_withdrawals.insert(&withdrawal_id, withdrawal)?;
+        Ok(())
+    }
+
+    /// Pay an approved withdrawal out of the guild's token pool
+    /// Fails without side effects if the pool has shrunk below the proposed amount
+    async fn execute_guild_token_withdrawal(
+        &mut self,
+        withdrawal: &mut GuildTokenWithdrawal,
+    ) -> Result<(), ContractError> {
+        let mut guild = self.state.guilds.get(&withdrawal.guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        let token = guild.token_application.ok_or(ContractError::NoGuildTokenApplication)?;
+        if guild.token_pool < withdrawal.amount {
+            return Err(ContractError::InsufficientBalance);
+        }
+
+        let owner = self.application_account();
+        let target_account = Account { chain_id: self.runtime.chain_id(), owner: withdrawal.recipient };
+        let amount = withdrawal.amount;
+        self.call_token_application(token, FungibleOperation::Transfer { owner, amount, target_account });
+        guild.token_pool = guild.token_pool.saturating_sub(amount);
+        withdrawal.executed = true;
+        let guild_id = guild.id;
+        self.state.guilds.insert(&guild_id, guild)?;
+        Ok(())
+    }
+
+    /// Record how the tokens this application holds in a token application compare with the
+    /// token pools of the guilds using it
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Reconciliation recorded for the `tokenReconciliation` query
+    /// * `Err(UnexpectedTokenResponse)` - The application didn't answer with a balance
+    async fn reconcile_guild_tokens(
+        &mut self,
+        application_id: ApplicationId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let owner = self.application_account();
+        let FungibleResponse::Balance(held) =
+            self.call_token_application(application_id, FungibleOperation::Balance { owner })
+        else {
+            return Err(ContractError::UnexpectedTokenResponse);
+        };
+        let mut tracked = Amount::ZERO;
+        self.state.guilds.for_each_index_value(|_, guild| {
+            if guild.token_application == Some(application_id) {
+                tracked = tracked.saturating_add(guild.token_pool);
+            }
+            Ok(())
+        }).await?;
+        let reconciliation = TokenReconciliation { application_id, held, tracked, checked_at: current_time };
+        self.state.token_reconciliations.insert(&application_id, reconciliation)?;
+        Ok(())
+    }
+
+    /// This application's own account owner in token applications
+    fn application_account(&mut self) -> AccountOwner {
+        self.runtime.application_id().forget_abi().into()
+    }
+
+    /// Call a fungible token application as this application, on behalf of the signer
+    fn call_token_application(&mut self, application_id: ApplicationId, operation: FungibleOperation) -> FungibleResponse {
+        self.runtime.call_application(true, application_id.with_abi::<FungibleTokenAbi>(), &operation)
+    }
+
+    /// Update the game configuration (Admin only)
+    /// The change is scheduled like a proposal, taking effect once the config's notice period
+    /// has passed
+    /// 
+    /// # Arguments
+    /// * `caller` - The player attempting to update config
+    /// * `config` - The new game configuration
+    /// * `current_time` - Current timestamp, from which the notice period runs
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Configuration change scheduled, replacing any earlier pending change
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidConfig)` - A parameter is out of bounds
+    /// * `Err(AdminChangeNotAllowed)` - `config` names a different admin
+    async fn update_game_config(
+        &mut self,
+        caller: PlayerId,
+        config: GameConfig,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let notice = self.state.config.get().config_change_notice_seconds;
+        let effective_at = current_time.saturating_add(TimeDelta::from_secs(notice));
+        self.propose_config_change(caller, config, effective_at, current_time).await
+    }
+
+    /// Schedule a config change so that players get notice before it takes effect
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Change scheduled, replacing any earlier pending change
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidEffectiveTime)` - `effective_at` gives less notice than the current config
+    ///   requires
+    /// * `Err(InvalidConfig)` - A parameter is out of bounds
+    /// * `Err(AdminChangeNotAllowed)` - `config` names a different admin
+    async fn propose_config_change(
+        &mut self,
+        caller: PlayerId,
+        config: GameConfig,
+        effective_at: Timestamp,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        self.ensure_config_admin(caller)?;
+        config.validate()?;
+        self.ensure_same_admin(&config)?;
+        let notice = self.state.config.get().config_change_notice_seconds;
+        if effective_at < current_time.saturating_add(TimeDelta::from_secs(notice)) {
+            return Err(ContractError::InvalidEffectiveTime);
+        }
+        self.state.pending_config.set(Some(PendingConfigChange {
+            config,
+            proposed_by: caller,
+            proposed_at: current_time,
+            effective_at,
+        }));
+        Ok(())
+    }
+
+    /// Drop the scheduled config change (admin only)
+    async fn cancel_config_change(&mut self, caller: PlayerId) -> Result<(), ContractError> {
+        self.ensure_config_admin(caller)?;
+        if self.state.pending_config.get().is_none() {
+            return Err(ContractError::NoPendingConfigChange);
+        }
+        self.state.pending_config.set(None);
+        Ok(())
+    }
+
+    /// Apply the scheduled config change once its effective time has passed
+    async fn apply_due_config_change(&mut self, current_time: Timestamp) -> Result<(), ContractError> {
+        let Some(pending) = self.state.pending_config.get().clone() else {
+            return Ok(());
+        };
+        if current_time < pending.effective_at {
+            return Ok(());
+        }
+        self.state.pending_config.set(None);
+        // The admin may have been handed over or renounced since the change was proposed
+        let mut config = pending.config;
+        config.admin = self.state.config.get().admin;
+        self.apply_game_config(config).await
+    }
+
+    /// Config changes keep the current admin; handing over goes through `transfer_admin`
+    fn ensure_same_admin(&self, config: &GameConfig) -> Result<(), ContractError> {
+        if config.admin != self.state.config.get().admin {
+            return Err(ContractError::AdminChangeNotAllowed);
+        }
+        Ok(())
+    }
+
+    /// Offer the admin role to `new_admin`; it only moves once they accept
+    /// A new offer replaces any earlier one
+    fn transfer_admin(&mut self, caller: PlayerId, new_admin: PlayerId) -> Result<(), ContractError> {
+        self.ensure_config_admin(caller)?;
+        self.state.pending_admin.set(Some(new_admin));
+        Ok(())
+    }
+
+    /// Take over the admin role offered to the caller and send the config to market chains
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Caller is now the admin
+    /// * `Err(NotPendingAdmin)` - The role hasn't been offered to the caller
+    async fn accept_admin(&mut self, caller: PlayerId) -> Result<(), ContractError> {
+        if !self.is_home_chain() {
+            return Err(ContractError::NotHomeChain);
+        }
+        if *self.state.pending_admin.get() != Some(caller) {
+            return Err(ContractError::NotPendingAdmin);
+        }
+        self.state.pending_admin.set(None);
+        let mut config = self.state.config.get().clone();
+        config.admin = Some(caller);
+        self.apply_game_config(config).await
+    }
+
+    /// Give up the admin role for good, locking the config and emergency mode
+    /// A change already scheduled still applies at its effective time, since players were
+    /// given notice of it
+    async fn renounce_admin(&mut self, caller: PlayerId) -> Result<(), ContractError> {
+        self.ensure_config_admin(caller)?;
+        self.state.pending_admin.set(None);
+        let mut config = self.state.config.get().clone();
+        config.admin = None;
+        self.apply_game_config(config).await
+    }
+
+    /// Seed the chain with curated markets created by the admin, free of creation costs and bonds
+    /// Each market is created on its own, so an invalid one doesn't stop the rest
+    /// 
+    /// # Returns
+    /// * `Ok(results)` - For each market in batch order, its ID or the error that kept it from
+    ///   being created
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidImport)` - The batch is empty or larger than `MAX_IMPORT_MARKETS`
+    async fn import_markets(
+        &mut self,
+        caller: PlayerId,
+        markets: Vec<MarketSpec>,
+        current_time: Timestamp,
+    ) -> Result<Vec<Result<MarketId, String>>, ContractError> {
+        self.ensure_admin(caller)?;
+        if markets.is_empty() || markets.len() > MAX_IMPORT_MARKETS {
+            return Err(ContractError::InvalidImport);
+        }
+        let mut results = Vec::with_capacity(markets.len());
+        for spec in markets {
+            let result = self
+                .create_market(
+                    caller,
+                    spec.title,
+                    spec.description,
+                    MarketType::QuickPrediction,
+                    spec.outcome_names,
+                    spec.duration_seconds,
+                    spec.resolution_method,
+                    spec.payout_weights.unwrap_or_else(|| vec![BASIS_POINTS]),
+                    spec.visibility.unwrap_or_default(),
+                    spec.allowed_guild,
+                    spec.allowlist.unwrap_or_default(),
+                    spec.metadata.unwrap_or_default(),
+                    spec.allow_early_close.unwrap_or(false),
+                    spec.bootstrap_seconds,
+                    spec.blind_finish_seconds,
+                    Amount::ZERO,
+                    Amount::ZERO,
+                    current_time,
+                )
+                .await;
+            results.push(match result {
+                // Storage failures abort the whole batch
+                Err(ContractError::View(error)) => return Err(error.into()),
+                result => result.map_err(|error| error.to_string()),
+            });
+        }
+        Ok(results)
+    }
+
+    /// Translate an achievement's name and description into `locale` (admin only)
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Translation stored, replacing any earlier one for the locale
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(AchievementNotFound)` - No achievement with this ID
+    /// * `Err(InvalidTranslation)` - Invalid locale tag, or an empty name or description
+    async fn translate_achievement(
+        &mut self,
+        caller: PlayerId,
+        achievement_id: AchievementId,
+        locale: String,
+        name: String,
+        description: String,
+    ) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        let mut achievement = self
+            .state
+            .achievements
+            .get(&achievement_id)
+            .await?
+            .ok_or(ContractError::AchievementNotFound)?;
+        if !is_valid_locale(&locale) || name.trim().is_empty() || description.trim().is_empty() {
+            return Err(ContractError::InvalidTranslation);
+        }
+        achievement.localized_names.insert(locale.clone(), name);
+        achievement.localized_descriptions.insert(locale, description);
+        self.state.achievements.insert(&achievement_id, achievement)?;
+        Ok(())
+    }
+
+    /// Config changes are made by the admin on the home chain
+    fn ensure_config_admin(&mut self, caller: PlayerId) -> Result<(), ContractError> {
+        if !self.is_home_chain() {
+            return Err(ContractError::NotHomeChain);
+        }
+        self.ensure_admin(caller)
+    }
+
+    fn ensure_admin(&self, caller: PlayerId) -> Result<(), ContractError> {
+        match self.state.config.get().admin {
+            Some(admin) if admin == caller => Ok(()),
+            _ => Err(ContractError::NotAdmin),
+        }
+    }
+
+    // ============================================================================
+    // Market Makers
+    // ============================================================================
+    //
+    // The admin approves bots to quote both sides of outcomes. A bot posts its quotes in
+    // batches, each replacing its open quote on the outcome, and players trade against them
+    // at the quoted prices instead of the curve. Every bot has its own risk limits on quote
+    // size, open quotes and the tokens held by its bids, and is credited back part of the
+    // trading fees it pays on the curve.
+
+    /// Approve a player as a market maker, or update an approved one's rebate and limits
+    /// (admin only)
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market maker approved
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(PlayerNotFound)` - Player is not registered
+    /// * `Err(InvalidMarketMaker)` - Rebate is above 100%
+    async fn approve_market_maker(
+        &mut self,
+        caller: PlayerId,
+        player_id: PlayerId,
+        rebate_bps: u32,
+        limits: MarketMakerLimits,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        self.get_player(&player_id).await?;
+        if rebate_bps > BASIS_POINTS {
+            return Err(ContractError::InvalidMarketMaker);
+        }
+        let maker = match self.state.market_makers.get(&player_id).await? {
+            Some(maker) => MarketMaker {
+                approved: true,
+                rebate_bps,
+                limits,
+                ..maker
+            },
+            None => MarketMaker {
+                player_id,
+                approved: true,
+                approved_at: current_time,
+                rebate_bps,
+                limits,
+                open_quotes: 0,
+                bid_exposure: Amount::ZERO,
+                rebates_earned: Amount::ZERO,
+            },
+        };
+        self.state.market_makers.insert(&player_id, maker)?;
+        Ok(())
+    }
+
+    /// Stop a market maker from posting quotes (admin only)
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market maker revoked
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(NotMarketMaker)` - Player was never approved
+    async fn revoke_market_maker(&mut self, caller: PlayerId, player_id: PlayerId) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        let mut maker = self
+            .state
+            .market_makers
+            .get(&player_id)
+            .await?
+            .ok_or(ContractError::NotMarketMaker)?;
+        maker.approved = false;
+        self.state.market_makers.insert(&player_id, maker)?;
+        Ok(())
+    }
+
+    /// Post a batch of quotes, each closing the maker's open quote on its outcome first
+    /// The whole batch is checked before anything is stored, so it's posted entirely or not
+    /// at all. Cancelling needs no approval, so revoked makers can still pull their quotes
+    /// 
+    /// # Returns
+    /// * `Ok(quote_ids)` - Quotes posted, with their IDs in batch order
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(NotMarketMaker)` - Caller isn't an approved market maker
+    /// * `Err(InvalidBotQuote)` - Empty or oversized batch, or a side without a price or a bid
+    ///   at or above the ask
+    /// * `Err(MarketNotActive)` - A market is not active
+    /// * `Err(InvalidOutcome)` - An outcome doesn't exist
+    /// * `Err(MarketAccessDenied)` - Maker is not allowed into a private market
+    /// * `Err(InsufficientBalance)` - Maker can't fund the bids
+    /// * `Err(InsufficientShares)` - Maker doesn't hold the shares to ask
+    /// * `Err(MarketMakerLimitExceeded)` - Quotes are bigger, more numerous or hold more tokens
+    ///   than the maker's limits allow
+    async fn post_two_sided_quotes(
+        &mut self,
+        maker_id: PlayerId,
+        quotes: Vec<TwoSidedQuote>,
+        current_time: Timestamp,
+    ) -> Result<Vec<Option<BotQuoteId>>, ContractError> {
+        let mut maker = self
+            .state
+            .market_makers
+            .get(&maker_id)
+            .await?
+            .ok_or(ContractError::NotMarketMaker)?;
+        if quotes.is_empty() || quotes.len() > MAX_QUOTES_PER_BATCH {
+            return Err(ContractError::InvalidBotQuote);
+        }
+        let mut player = self.get_player(&maker_id).await?;
+        let mut markets = BTreeMap::new();
+        let mut quote_ids = Vec::with_capacity(quotes.len());
+        for quote in quotes {
+            if !quote.is_cancel() && !maker.approved {
+                return Err(ContractError::NotMarketMaker);
+            }
+            if !quote.is_valid() {
+                return Err(ContractError::InvalidBotQuote);
+            }
+            if quote.bid_shares.max(quote.ask_shares) > maker.limits.max_quote_shares {
+                return Err(ContractError::MarketMakerLimitExceeded);
+            }
+            let market = match markets.entry(quote.market_id) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let market = self.get_market(&quote.market_id).await?;
+                    Self::ensure_positions_unlocked(&market)?;
+                    Self::ensure_market_access(&market, &player)?;
+                    if market.status != MarketStatus::Active {
+                        return Err(ContractError::MarketNotActive);
+                    }
+                    self.ensure_trading_open(&market, current_time)?;
+                    entry.insert(market)
+                }
+            };
+            if quote.outcome_id >= market.outcomes.len() as OutcomeId {
+                return Err(ContractError::InvalidOutcome);
+            }
+
+            let replaced = market.close_bot_quotes(|open| {
+                open.maker == maker_id && open.outcome_id == quote.outcome_id
+            });
+            for (_, bid_escrow) in replaced {
+                player.token_balance = player.token_balance.saturating_add(bid_escrow);
+                maker.release_quote(bid_escrow);
+            }
+            if quote.is_cancel() {
+                quote_ids.push(None);
+                continue;
+            }
+
+            let bid_escrow = Shares::new(quote.bid_shares).value_at(PricePerShare::new(quote.bid_price)).amount();
+            if player.token_balance < bid_escrow {
+                return Err(ContractError::InsufficientBalance);
+            }
+            Self::charge_risk_limits(&mut player, bid_escrow, current_time)?;
+            let (ask_cost_basis, ask_acquired_at) = if quote.ask_shares > Amount::ZERO {
+                market
+                    .withdraw_shares(&maker_id, quote.outcome_id, quote.ask_shares)
+                    .ok_or(ContractError::InsufficientShares)?
+            } else {
+                (Amount::ZERO, current_time)
+            };
+            maker.open_quotes += 1;
+            maker.bid_exposure = maker.bid_exposure.saturating_add(bid_escrow);
+            if !maker.within_limits() {
+                return Err(ContractError::MarketMakerLimitExceeded);
+            }
+            player.token_balance = player.token_balance.saturating_sub(bid_escrow);
+
+            let id = market.bot_quotes.len() as BotQuoteId;
+            market.bot_quotes.push(BotQuote {
+                id,
+                maker: maker_id,
+                outcome_id: quote.outcome_id,
+                bid_price: quote.bid_price,
+                bid_shares: quote.bid_shares,
+                bid_escrow,
+                ask_price: quote.ask_price,
+                ask_shares: quote.ask_shares,
+                ask_cost_basis,
+                ask_acquired_at,
+                posted_at: current_time,
+                open: true,
+            });
+            quote_ids.push(Some(id));
+        }
+
+        for (market_id, market) in markets {
+            self.state.markets.insert(&market_id, market)?;
+        }
+        self.save_player(player).await?;
+        self.state.market_makers.insert(&maker_id, maker)?;
+        Ok(quote_ids)
+    }
+
+    /// Trade `shares` against one side of a market maker's quote at that side's price
+    /// Hitting the bid sells the taker's shares to the maker for the tokens the bid holds;
+    /// lifting the ask buys the shares the ask holds. The quote closes once both sides are
+    /// used up
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Quote filled successfully
+    /// * `Err(PositionsLocked)` - Market has closed and isn't resolved yet
+    /// * `Err(MarketNotActive)` - Market is not active
+    /// * `Err(MarketAccessDenied)` - Taker is not allowed into a private market
+    /// * `Err(BotQuoteNotFound)` - No quote with this ID on the market
+    /// * `Err(BotQuoteClosed)` - Quote was used up or closed
+    /// * `Err(InvalidBotQuote)` - Maker can't fill their own quote
+    /// * `Err(SlippageExceeded)` - Price doesn't match the quoted side
+    /// * `Err(QuoteSizeExceeded)` - No shares, or more than the side has left
+    /// * `Err(InsufficientShares)` - Taker doesn't hold the shares to sell
+    /// * `Err(InsufficientBalance)` - Taker can't pay for the shares
+    #[allow(clippy::too_many_arguments)]
+    async fn fill_bot_quote(
+        &mut self,
+        taker_id: PlayerId,
+        market_id: MarketId,
+        quote_id: BotQuoteId,
+        side: QuoteSide,
+        shares: Amount,
+        price_per_share: Amount,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut market = self.get_market(&market_id).await?;
+        Self::ensure_positions_unlocked(&market)?;
+        let mut taker = self.get_player(&taker_id).await?;
+        Self::ensure_market_access(&market, &taker)?;
+        if market.status != MarketStatus::Active {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.ensure_trading_open(&market, current_time)?;
+        let mut quote = market
+            .bot_quotes
+            .get(quote_id as usize)
+            .cloned()
+            .ok_or(ContractError::BotQuoteNotFound)?;
+        if !quote.open {
+            return Err(ContractError::BotQuoteClosed);
+        }
+        if quote.maker == taker_id {
+            return Err(ContractError::InvalidBotQuote);
+        }
+        let (quoted_price, quoted_shares) = match side {
+            QuoteSide::Bid => (quote.bid_price, quote.bid_shares),
+            QuoteSide::Ask => (quote.ask_price, quote.ask_shares),
+        };
+        if price_per_share != quoted_price {
+            return Err(ContractError::SlippageExceeded);
+        }
+        if shares == Amount::ZERO || shares > quoted_shares {
+            return Err(ContractError::QuoteSizeExceeded);
+        }
+        let mut maker = self
+            .state
+            .market_makers
+            .get(&quote.maker)
+            .await?
+            .ok_or(ContractError::NotMarketMaker)?;
+        let mut maker_player = self.get_player(&quote.maker).await?;
+
+        let payment = match side {
+            QuoteSide::Bid => {
+                // The last fill takes whatever the bid holds, so rounding leaves nothing behind
+                let payment = if shares == quote.bid_shares {
+                    quote.bid_escrow
+                } else {
+                    Shares::new(shares).value_at(PricePerShare::new(quote.bid_price)).amount()
+                };
+                let (_, acquired_at) = market
+                    .withdraw_shares(&taker_id, quote.outcome_id, shares)
+                    .ok_or(ContractError::InsufficientShares)?;
+                market.deposit_shares(quote.maker, quote.outcome_id, shares, payment, acquired_at);
+                if self.join_market(quote.maker, market_id).await? {
+                    self.add_participant(&mut market, quote.maker).await?;
+                    maker_player.markets_participated += 1;
+                }
+                quote.bid_shares = quote.bid_shares.saturating_sub(shares);
+                quote.bid_escrow = quote.bid_escrow.saturating_sub(payment);
+                maker.bid_exposure = maker.bid_exposure.saturating_sub(payment);
+                taker.token_balance = taker.token_balance.saturating_add(payment);
+                payment
+            }
+            QuoteSide::Ask => {
+                let payment = Shares::new(shares).value_at(PricePerShare::new(quote.ask_price)).amount();
+                if taker.token_balance < payment {
+                    return Err(ContractError::InsufficientBalance);
+                }
+                Self::charge_risk_limits(&mut taker, payment, current_time)?;
+                let cost_basis = Amount::from_attos(math::mul_div(
+                    u128::from(quote.ask_cost_basis),
+                    u128::from(shares),
+                    u128::from(quote.ask_shares),
+                ));
+                market.deposit_shares(taker_id, quote.outcome_id, shares, payment, quote.ask_acquired_at);
+                if self.join_market(taker_id, market_id).await? {
+                    self.add_participant(&mut market, taker_id).await?;
+                    taker.markets_participated += 1;
+                }
+                quote.ask_shares = quote.ask_shares.saturating_sub(shares);
+                quote.ask_cost_basis = quote.ask_cost_basis.saturating_sub(cost_basis);
+                taker.token_balance = taker.token_balance.saturating_sub(payment);
+                maker_player.token_balance = maker_player.token_balance.saturating_add(payment);
+                payment
+            }
+        };
+        if quote.bid_shares == Amount::ZERO && quote.ask_shares == Amount::ZERO {
+            quote.open = false;
+            maker.release_quote(Amount::ZERO);
+        }
+        market.bot_quotes[quote_id as usize] = quote;
+
+        taker.trading_volume = taker.trading_volume.saturating_add(payment);
+        maker_player.trading_volume = maker_player.trading_volume.saturating_add(payment);
+        self.save_player(taker).await?;
+        self.save_player(maker_player).await?;
+        let maker_id = maker.player_id;
+        self.state.market_makers.insert(&maker_id, maker)?;
+        self.state.markets.insert(&market_id, market)?;
+        Ok(())
+    }
+
+    /// Take quotes closed by the market off their makers' open quotes
+    async fn release_bot_quotes(&mut self, closed: &[(PlayerId, Amount)]) -> Result<(), ContractError> {
+        for (maker_id, bid_escrow) in closed {
+            if let Some(mut maker) = self.state.market_makers.get(maker_id).await? {
+                maker.release_quote(*bid_escrow);
+                self.state.market_makers.insert(maker_id, maker)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Credit a market maker back its share of a trading fee it paid on the curve
+    async fn pay_maker_rebate(&mut self, player_id: PlayerId, fee: Tokens) -> Result<(), ContractError> {
+        let Some(mut maker) = self.state.market_makers.get(&player_id).await?.filter(|maker| maker.approved) else {
+            return Ok(());
+        };
+        let rebate = Amount::from_attos(math::apply_basis_points(fee.attos(), maker.rebate_bps));
+        if rebate == Amount::ZERO {
+            return Ok(());
+        }
+        let mut player = self.get_player(&player_id).await?;
+        player.token_balance = player.token_balance.saturating_add(rebate);
+        self.save_player(player).await?;
+        maker.rebates_earned = maker.rebates_earned.saturating_add(rebate);
+        self.state.market_makers.insert(&player_id, maker)?;
+        self.record_token_flow(TokenFlow::FeeReward, rebate).await
+    }
+
+    // ============================================================================
+    // Arbitration
+    // ============================================================================
+    //
+    // An oracle vote ends contested when its voting period closes without enough voted
+    // outcomes to rank the winners. Any member of the council the admin appoints can then
+    // settle the market with a binding ruling; resolver fees and slashing follow the ruled
+    // outcome as they would a vote, and the ruling is kept as a permanent record.
+
+    /// Appoint or dismiss a member of the arbitration council (admin only)
+    fn set_arbitrator(&mut self, caller: PlayerId, member: PlayerId, is_arbitrator: bool) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        if is_arbitrator {
+            self.state.arbitrators.insert(&member)?;
+        } else {
+            self.state.arbitrators.remove(&member)?;
+        }
+        Ok(())
+    }
+
+    /// Resolve a contested oracle market to `outcome_id`
+    /// Remaining payout ranks go to the other outcomes by vote weight, then by ID
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market resolved and the ruling recorded
+    /// * `Err(NotArbitrator)` - Caller is not on the arbitration council
+    /// * `Err(InvalidResolutionMethod)` - Market is not resolved by oracle vote
+    /// * `Err(InvalidOutcome)` - Outcome doesn't exist
+    /// * `Err(NotContested)` - Market is already settled or its vote hasn't ended contested
+    async fn arbitrate_market(
+        &mut self,
+        arbitrator: PlayerId,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        if !self.state.arbitrators.contains(&arbitrator).await? {
+            return Err(ContractError::NotArbitrator);
+        }
+        let market = self.get_market(&market_id).await?;
+        if market.resolution_method != ResolutionMethod::OracleVoting {
+            return Err(ContractError::InvalidResolutionMethod);
+        }
+        if outcome_id >= market.outcomes.len() as OutcomeId {
+            return Err(ContractError::InvalidOutcome);
+        }
+        if !matches!(market.status, MarketStatus::Active | MarketStatus::Voting) {
+            return Err(ContractError::NotContested);
+        }
+        Self::ensure_reveals_settled(&market, current_time)?;
+        let voting = self.state.oracle_votes.get(&market_id).await?;
+        let voting_end = market.voting_end.unwrap_or_else(|| {
+            market
+                .end_time
+                .saturating_add(TimeDelta::from_secs(self.state.config.get().oracle_voting_duration_seconds))
+        });
+        let vote_weights: BTreeMap<OutcomeId, u64> = voting
+            .map(|voting| {
+                voting
+                    .votes
+                    .into_iter()
+                    .map(|(outcome_id, votes)| (outcome_id, votes.total_weight))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if current_time < voting_end || vote_weights.len() >= market.payout_weights.len() {
+            return Err(ContractError::NotContested);
+        }
+
+        let mut others: Vec<(OutcomeId, u64)> = (0..market.outcomes.len() as OutcomeId)
+            .filter(|other| *other != outcome_id)
+            .map(|other| (other, vote_weights.get(&other).copied().unwrap_or(0)))
+            .collect();
+        others.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        let ranked_outcomes: Vec<OutcomeId> = std::iter::once(outcome_id)
+            .chain(others.into_iter().map(|(other, _)| other))
+            .take(market.payout_weights.len())
+            .collect();
+
+        self.state.arbitrations.insert(
+            &market_id,
+            ArbitrationRecord {
+                market_id,
+                arbitrator,
+                outcome_id,
+                ranked_outcomes: ranked_outcomes.clone(),
+                vote_weights,
+                ruled_at: current_time,
+            },
+        )?;
+        self.finalize_resolution(market, ranked_outcomes, current_time).await
+    }
+
+    // ============================================================================
+    // Market Groups
+    // ============================================================================
+    //
+    // A creator bundles CreatorDecides markets they own, such as every match of a tournament
+    // round, and names a resolver who settles them all at once. The creator escrows a prize
+    // for the player with the best record across the group; it goes back to the creator when
+    // nobody took part.
+
+    /// Bundle markets into a group resolved by `resolver`, escrowing `prize` from the creator
+    /// 
+    /// # Returns
+    /// * `Ok(group_id)` - Group created successfully
+    /// * `Err(InvalidMarketGroup)` - Empty name, fewer than two or too many markets, or a
+    ///   market listed twice
+    /// * `Err(Unauthorized)` - Caller didn't create every market
+    /// * `Err(InvalidResolutionMethod)` - A market isn't resolved by its creator
+    /// * `Err(MarketNotActive)` - A market is already settled
+    /// * `Err(MarketInGroup)` - A market already belongs to a group
+    /// * `Err(InsufficientBalance)` - Creator can't fund the prize
+    async fn create_market_group(
+        &mut self,
+        creator: PlayerId,
+        name: String,
+        market_ids: Vec<MarketId>,
+        resolver: PlayerId,
+        prize: Amount,
+        current_time: Timestamp,
+    ) -> Result<GroupId, ContractError> {
+        let name = name.trim().to_string();
+        if name.is_empty() || market_ids.len() < 2 || market_ids.len() > MAX_GROUP_MARKETS {
+            return Err(ContractError::InvalidMarketGroup);
+        }
+        let mut markets = Vec::new();
+        for (index, market_id) in market_ids.iter().enumerate() {
+            if market_ids[..index].contains(market_id) {
+                return Err(ContractError::InvalidMarketGroup);
+            }
+            let market = self.get_market(market_id).await?;
+            if market.creator != creator {
+                return Err(ContractError::Unauthorized);
+            }
+            if market.resolution_method != ResolutionMethod::CreatorDecides {
+                return Err(ContractError::InvalidResolutionMethod);
+            }
+            if !matches!(market.status, MarketStatus::Bootstrapping | MarketStatus::Active | MarketStatus::Closed) {
+                return Err(ContractError::MarketNotActive);
+            }
+            if market.group_id.is_some() {
+                return Err(ContractError::MarketInGroup);
+            }
+            markets.push(market);
+        }
+        let mut player = self.get_player(&creator).await?;
+        if player.token_balance < prize {
+            return Err(ContractError::InsufficientBalance);
+        }
+        player.token_balance = player.token_balance.saturating_sub(prize);
+        self.save_player(player).await?;
+
+        let id = *self.state.next_group_id.get();
+        self.state.next_group_id.set(id + 1);
+        for mut market in markets {
+            market.group_id = Some(id);
+            let market_id = market.id;
+            self.state.markets.insert(&market_id, market)?;
+        }
+        self.state.market_groups.insert(&id, MarketGroup {
+            id,
+            name,
+            creator,
+            resolver,
+            market_ids,
+            prize,
+            created_at: current_time,
+            resolved_at: None,
+            total_volume: Amount::ZERO,
+            participants: 0,
+            standings: Vec::new(),
+            prize_winner: None,
+        })?;
+        Ok(id)
+    }
+
+    /// Resolve every market of a group, `results` giving the ranked winners of each market
+    /// in the group's order, then rank the participants and pay the prize
+    /// The best record has the most correct picks, then the fewest picks, then the lowest
+    /// player ID
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Group resolved and the prize paid
+    /// * `Err(MarketGroupNotFound)` - No group with this ID
+    /// * `Err(Unauthorized)` - Caller is not the group's resolver
+    /// * `Err(MarketGroupResolved)` - Group was already resolved
+    /// * `Err(InvalidMarketGroup)` - Not one result per market
+    /// * `Err(MarketNotEnded)` / `Err(InvalidRankedOutcomes)` - A market can't be resolved
+    ///   to its result yet
+    async fn resolve_market_group(
+        &mut self,
+        caller: PlayerId,
+        group_id: GroupId,
+        results: Vec<Vec<OutcomeId>>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut group = self
+            .state
+            .market_groups
+            .get(&group_id)
+            .await?
+            .ok_or(ContractError::MarketGroupNotFound)?;
+        if group.resolver != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        if group.resolved_at.is_some() {
+            return Err(ContractError::MarketGroupResolved);
+        }
+        if results.len() != group.market_ids.len() {
+            return Err(ContractError::InvalidMarketGroup);
+        }
+        let mut markets = Vec::new();
+        for (market_id, ranked_outcomes) in group.market_ids.iter().zip(&results) {
+            let market = self.get_market(market_id).await?;
+            Self::ensure_creator_resolvable(&market, ranked_outcomes, current_time)?;
+            markets.push(market);
+        }
+
+        let mut records: BTreeMap<PlayerId, GroupStanding> = BTreeMap::new();
+        for (market, ranked_outcomes) in markets.into_iter().zip(results) {
+            for (player_id, position) in &market.positions {
+                let pick = position
+                    .shares_by_outcome
+                    .iter()
+                    .max_by(|a, b| a.1.cmp(b.1).then(b.0.cmp(a.0)))
+                    .map(|(outcome_id, _)| *outcome_id);
+                let Some(pick) = pick else {
+                    continue;
+                };
+                let record = records.entry(*player_id).or_insert(GroupStanding {
+                    player_id: *player_id,
+                    correct: 0,
+                    picks: 0,
+                });
+                record.picks += 1;
+                if ranked_outcomes.first() == Some(&pick) {
+                    record.correct += 1;
+                }
+            }
+            group.total_volume = group.total_volume.saturating_add(market.volume);
+            self.finalize_resolution(market, ranked_outcomes, current_time).await?;
+        }
+
+        let mut standings: Vec<GroupStanding> = records.into_values().collect();
+        standings.sort_by(|a, b| {
+            b.correct
+                .cmp(&a.correct)
+                .then(a.picks.cmp(&b.picks))
+                .then(a.player_id.cmp(&b.player_id))
+        });
+        group.participants = standings.len() as u32;
+        group.prize_winner = standings.first().map(|standing| standing.player_id);
+        group.standings = standings;
+        group.resolved_at = Some(current_time);
+        let recipient = group.prize_winner.unwrap_or(group.creator);
+        let mut player = self.get_player(&recipient).await?;
+        player.token_balance = player.token_balance.saturating_add(group.prize);
+        if group.prize_winner.is_some() {
+            player.total_earned = player.total_earned.saturating_add(group.prize);
+        }
+        self.save_player(player).await?;
+        self.state.market_groups.insert(&group_id, group)?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Brackets
+    // ============================================================================
+    //
+    // Players predict a whole knockout tournament before it locks, paying an entry fee into
+    // the pool. Each match is decided by a market the creator links to it; scoring the
+    // resolved market awards the match's round points to every entry that picked the winner,
+    // later rounds being worth more. Scoring the final splits the pool between the best
+    // scores by the bracket's payout weights.
+
+    async fn get_bracket(&self, bracket_id: BracketId) -> Result<Bracket, ContractError> {
+        self.state.brackets.get(&bracket_id).await?.ok_or(ContractError::BracketNotFound)
+    }
+
+    /// Open a bracket for `teams`, taking entries until `locks_at`
+    /// 
+    /// # Returns
+    /// * `Ok(bracket_id)` - Bracket created successfully
+    /// * `Err(InvalidBracket)` - Empty name or team name, team count not a power of two
+    ///   between 4 and `MAX_BRACKET_TEAMS`, or a lock time already passed
+    /// * `Err(InvalidPayoutWeights)` - Weights are empty, contain a zero or don't add up
+    ///   to 100%
+    #[allow(clippy::too_many_arguments)]
+    async fn create_bracket(
+        &mut self,
+        creator: PlayerId,
+        name: String,
+        teams: Vec<String>,
+        entry_fee: Amount,
+        locks_at: Timestamp,
+        payout_weights: Option<Vec<u32>>,
+        current_time: Timestamp,
+    ) -> Result<BracketId, ContractError> {
+        self.get_player(&creator).await?;
+        let name = name.trim().to_string();
+        if name.is_empty()
+            || teams.len() < 4
+            || teams.len() > MAX_BRACKET_TEAMS
+            || !teams.len().is_power_of_two()
+            || teams.iter().any(|team| team.trim().is_empty())
+            || locks_at <= current_time
+        {
+            return Err(ContractError::InvalidBracket);
+        }
+        let payout_weights = payout_weights.unwrap_or_else(|| vec![BASIS_POINTS]);
+        let total_weight: u64 = payout_weights.iter().map(|weight| *weight as u64).sum();
+        if payout_weights.is_empty() || payout_weights.contains(&0) || total_weight != BASIS_POINTS as u64 {
+            return Err(ContractError::InvalidPayoutWeights);
+        }
+
+        let id = *self.state.next_bracket_id.get();
+        self.state.next_bracket_id.set(id + 1);
+        let match_count = teams.len() - 1;
+        self.state.brackets.insert(&id, Bracket {
+            id,
+            name,
+            creator,
+            teams,
+            entry_fee,
+            locks_at,
+            payout_weights,
+            match_markets: vec![None; match_count],
+            winners: vec![None; match_count],
+            entries: Vec::new(),
+            pool: Amount::ZERO,
+            status: BracketStatus::Open,
+            created_at: current_time,
+        })?;
+        Ok(id)
+    }
+
+    /// Enter a bracket with a winner picked for every match, paying the entry fee
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Entry recorded and the fee added to the pool
+    /// * `Err(BracketLocked)` - Bracket no longer takes entries
+    /// * `Err(AlreadyEntered)` - Player already has an entry
+    /// * `Err(InvalidBracketPicks)` - A pick is missing or couldn't reach its match
+    /// * `Err(InsufficientBalance)` - Player can't pay the entry fee
+    async fn enter_bracket(
+        &mut self,
+        player_id: PlayerId,
+        bracket_id: BracketId,
+        picks: Vec<u32>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut bracket = self.get_bracket(bracket_id).await?;
+        let mut player = self.get_player(&player_id).await?;
+        if bracket.status != BracketStatus::Open || current_time >= bracket.locks_at {
+            return Err(ContractError::BracketLocked);
+        }
+        if bracket.entries.iter().any(|entry| entry.player == player_id) {
+            return Err(ContractError::AlreadyEntered);
+        }
+        if !bracket.picks_valid(&picks) {
+            return Err(ContractError::InvalidBracketPicks);
+        }
+        if player.token_balance < bracket.entry_fee {
+            return Err(ContractError::InsufficientBalance);
+        }
+        player.token_balance = player.token_balance.saturating_sub(bracket.entry_fee);
+        bracket.pool = bracket.pool.saturating_add(bracket.entry_fee);
+        bracket.entries.push(BracketEntry {
+            player: player_id,
+            picks,
+            score: 0,
+            entered_at: current_time,
+            payout: Amount::ZERO,
+        });
+        self.save_player(player).await?;
+        self.state.brackets.insert(&bracket_id, bracket)?;
+        Ok(())
+    }
+
+    /// Name the market deciding a bracket match (bracket creator only); a link can be
+    /// replaced until the match is scored
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Market linked successfully
+    /// * `Err(Unauthorized)` - Caller didn't create the bracket
+    /// * `Err(InvalidBracket)` - No such match
+    /// * `Err(BracketMatchScored)` - Match was already scored
+    /// * `Err(InvalidOutcomeCount)` - Market doesn't have exactly two outcomes
+    async fn link_bracket_match(
+        &mut self,
+        caller: PlayerId,
+        bracket_id: BracketId,
+        match_index: u32,
+        market_id: MarketId,
+    ) -> Result<(), ContractError> {
+        let mut bracket = self.get_bracket(bracket_id).await?;
+        if bracket.creator != caller {
+            return Err(ContractError::Unauthorized);
+        }
+        let match_index = match_index as usize;
+        match bracket.winners.get(match_index) {
+            None => return Err(ContractError::InvalidBracket),
+            Some(Some(_)) => return Err(ContractError::BracketMatchScored),
+            Some(None) => {}
+        }
+        let market = self.get_market(&market_id).await?;
+        if market.outcomes.len() != 2 {
+            return Err(ContractError::InvalidOutcomeCount);
+        }
+        bracket.match_markets[match_index] = Some(market_id);
+        self.state.brackets.insert(&bracket_id, bracket)?;
+        Ok(())
+    }
+
+    /// Score a bracket match from its resolved market; anyone can trigger it
+    /// Scoring the last match finalizes the bracket and pays out the pool
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Match scored successfully
+    /// * `Err(InvalidBracket)` - No such match
+    /// * `Err(BracketMatchScored)` - Match was already scored
+    /// * `Err(BracketMatchPending)` - Teams aren't known yet, or no resolved market is
+    ///   linked to the match
+    async fn score_bracket_match(&mut self, bracket_id: BracketId, match_index: u32) -> Result<(), ContractError> {
+        let mut bracket = self.get_bracket(bracket_id).await?;
+        let match_index = match_index as usize;
+        let (round, _) = math::bracket_match_round(bracket.teams.len(), match_index)
+            .ok_or(ContractError::InvalidBracket)?;
+        if bracket.winners[match_index].is_some() {
+            return Err(ContractError::BracketMatchScored);
+        }
+        let teams = bracket.match_teams(match_index).ok_or(ContractError::BracketMatchPending)?;
+        let market_id = bracket.match_markets[match_index].ok_or(ContractError::BracketMatchPending)?;
+        let winning_outcome = match self.state.markets.get(&market_id).await? {
+            Some(market) if market.status == MarketStatus::Resolved => market.winning_outcome,
+            Some(_) => None,
+            None => self
+                .state
+                .market_archive
+                .get(&market_id)
+                .await?
+                .and_then(|summary| summary.winning_outcomes.first().copied()),
+        };
+        let winner = match winning_outcome.ok_or(ContractError::BracketMatchPending)? {
+            0 => teams.0,
+            _ => teams.1,
+        };
+
+        bracket.winners[match_index] = Some(winner);
+        let points = math::bracket_round_points(round);
+        for entry in &mut bracket.entries {
+            if entry.picks[match_index] == winner {
+                entry.score = entry.score.saturating_add(points);
+            }
+        }
+        if bracket.winners.iter().all(Option::is_some) {
+            self.pay_bracket_pool(&mut bracket).await?;
+        }
+        self.state.brackets.insert(&bracket_id, bracket)?;
+        Ok(())
+    }
+
+    /// Split a finished bracket's pool between the best scores by its payout weights
+    /// Earlier entries win ties; with fewer entries than paid ranks the weights of the ranks
+    /// filled are scaled up, and rounding dust goes to the treasury
+    async fn pay_bracket_pool(&mut self, bracket: &mut Bracket) -> Result<(), ContractError> {
+        bracket.status = BracketStatus::Finalized;
+        let mut ranking: Vec<usize> = (0..bracket.entries.len()).collect();
+        ranking.sort_by(|a, b| {
+            let (a, b) = (&bracket.entries[*a], &bracket.entries[*b]);
+            b.score.cmp(&a.score).then(a.entered_at.cmp(&b.entered_at))
+        });
+        let paid_ranks = ranking.len().min(bracket.payout_weights.len());
+        let weight_used: u128 = bracket.payout_weights[..paid_ranks].iter().map(|weight| u128::from(*weight)).sum();
+        let mut paid = Amount::ZERO;
+        for (rank, index) in ranking.into_iter().take(paid_ranks).enumerate() {
+            let payout = Amount::from_attos(math::mul_div(
+                u128::from(bracket.pool),
+                u128::from(bracket.payout_weights[rank]),
+                weight_used,
+            ));
+            let entry = &mut bracket.entries[index];
+            entry.payout = payout;
+            paid = paid.saturating_add(payout);
+            let mut player = self.get_player(&entry.player).await?;
+            player.token_balance = player.token_balance.saturating_add(payout);
+            player.total_earned = player.total_earned.saturating_add(payout);
+            self.save_player(player).await?;
+        }
+        let treasury = *self.state.treasury.get();
+        self.state.treasury.set(treasury.saturating_add(bracket.pool.saturating_sub(paid)));
+        Ok(())
+    }
+
+    // ============================================================================
+    // Cosmetic Shop
+    // ============================================================================
+    //
+    // Profile frames and market card skins bought with tokens. Prices are burned, making the
+    // shop a token sink; items are purely cosmetic and can't be resold.
+
+    /// Put a new cosmetic item up for sale (admin only)
+    /// 
+    /// # Returns
+    /// * `Ok(item_id)` - Item added to the shop
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidShopItem)` - Name is empty or too long, or the supply is zero
+    async fn create_shop_item(
+        &mut self,
+        caller: PlayerId,
+        name: String,
+        kind: ItemKind,
+        price: Amount,
+        max_supply: Option<u64>,
+    ) -> Result<ItemId, ContractError> {
+        self.ensure_admin(caller)?;
+        let name = name.trim().to_string();
+        if name.is_empty() || name.len() > MAX_ITEM_NAME_LENGTH || max_supply == Some(0) {
+            return Err(ContractError::InvalidShopItem);
+        }
+        let id = *self.state.next_item_id.get();
+        self.state.next_item_id.set(id + 1);
+        self.state.shop_items.insert(&id, ShopItem {
+            id,
+            name,
+            kind,
+            price,
+            available: true,
+            max_supply,
+            sold: 0,
+        })?;
+        Ok(id)
+    }
+
+    /// Put a shop item on or off sale (admin only); owned copies are unaffected
+    async fn set_shop_item_available(
+        &mut self,
+        caller: PlayerId,
+        item_id: ItemId,
+        available: bool,
+    ) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        let mut item = self.state.shop_items.get(&item_id).await?.ok_or(ContractError::ItemNotFound)?;
+        item.available = available;
+        self.state.shop_items.insert(&item_id, item)?;
+        Ok(())
+    }
+
+    /// Buy a cosmetic item, burning its price
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Item added to the player's inventory
+    /// * `Err(ItemNotFound)` / `Err(ItemUnavailable)` - Item doesn't exist or is off sale
+    /// * `Err(ItemSoldOut)` - Every copy of a limited item has been sold
+    /// * `Err(ItemAlreadyOwned)` - Player already owns the item
+    /// * `Err(InsufficientBalance)` - Player can't afford the item
+    async fn purchase_item(&mut self, player_id: PlayerId, item_id: ItemId) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let mut item = self.state.shop_items.get(&item_id).await?.ok_or(ContractError::ItemNotFound)?;
+        if !item.available {
+            return Err(ContractError::ItemUnavailable);
+        }
+        if item.max_supply.is_some_and(|max_supply| item.sold >= max_supply) {
+            return Err(ContractError::ItemSoldOut);
+        }
+        let mut inventory = self.state.inventories.try_load_entry_mut(&player_id).await?;
+        if inventory.contains(&item_id).await? {
+            return Err(ContractError::ItemAlreadyOwned);
+        }
+        if player.token_balance < item.price {
+            return Err(ContractError::InsufficientBalance);
+        }
+        inventory.insert(&item_id)?;
+        drop(inventory);
+
+        player.token_balance = player.token_balance.saturating_sub(item.price);
+        player.total_spent = player.total_spent.saturating_add(item.price);
+        self.save_player(player).await?;
+        item.sold += 1;
+        let price = item.price;
+        self.state.shop_items.insert(&item_id, item)?;
+        self.burn_tokens(price).await
+    }
+
+    /// Wear an owned item in its slot
+    async fn equip_item(&mut self, player_id: PlayerId, item_id: ItemId) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let owned = match self.state.inventories.try_load_entry(&player_id).await? {
+            Some(inventory) => inventory.contains(&item_id).await?,
+            None => false,
+        };
+        if !owned {
+            return Err(ContractError::ItemNotOwned);
+        }
+        let item = self.state.shop_items.get(&item_id).await?.ok_or(ContractError::ItemNotFound)?;
+        *player.equipped_items.slot_mut(item.kind) = Some(item_id);
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    /// Empty a cosmetic slot
+    async fn unequip_item(&mut self, player_id: PlayerId, kind: ItemKind) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        *player.equipped_items.slot_mut(kind) = None;
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Raffles
+    // ============================================================================
+    //
+    // Players buy tickets with tokens; when a raffle ends anyone can draw it. Part of the
+    // ticket sales is burned and the rest, topped up from the treasury, goes to the winners.
+    // Every ticket sale chains its block's chain, height and time into the raffle's seed, so the
+    // seed is settled when sales close and the draw can be recomputed by anyone from it.
+
+    /// Open a raffle (admin only)
+    /// 
+    /// # Returns
+    /// * `Ok(raffle_id)` - Raffle open for ticket sales
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidRaffle)` - Ticket price, duration or winner count is zero
+    #[allow(clippy::too_many_arguments)]
+    fn create_raffle(
+        &mut self,
+        caller: PlayerId,
+        ticket_price: Amount,
+        duration_seconds: u64,
+        winner_count: u32,
+        treasury_top_up: Amount,
+        recurring: bool,
+        current_time: Timestamp,
+    ) -> Result<RaffleId, ContractError> {
+        self.ensure_admin(caller)?;
+        if ticket_price == Amount::ZERO || duration_seconds == 0 || winner_count == 0 {
+            return Err(ContractError::InvalidRaffle);
+        }
+        let id = *self.state.next_raffle_id.get();
+        self.state.next_raffle_id.set(id + 1);
+        self.state.raffles.insert(&id, Raffle {
+            id,
+            ticket_price,
+            opened_at: current_time,
+            ends_at: Timestamp::from(current_time.micros().saturating_add(duration_seconds * 1_000_000)),
+            winner_count,
+            treasury_top_up,
+            recurring,
+            entries: Vec::new(),
+            total_tickets: 0,
+            pot: Amount::ZERO,
+            status: RaffleStatus::Open,
+            winners: Vec::new(),
+            prize_per_winner: Amount::ZERO,
+            seed: None,
+        })?;
+        Ok(id)
+    }
+
+    /// Buy raffle tickets
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Tickets added to the player's entry and paid into the pot
+    /// * `Err(RaffleClosed)` - Raffle has ended or been drawn
+    /// * `Err(InvalidRaffle)` - No tickets requested
+    /// * `Err(InsufficientBalance)` - Player can't afford the tickets
+    async fn buy_raffle_tickets(
+        &mut self,
+        player_id: PlayerId,
+        raffle_id: RaffleId,
+        tickets: u64,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let mut raffle = self.state.raffles.get(&raffle_id).await?.ok_or(ContractError::RaffleNotFound)?;
+        if raffle.status != RaffleStatus::Open || current_time >= raffle.ends_at {
+            return Err(ContractError::RaffleClosed);
+        }
+        if tickets == 0 {
+            return Err(ContractError::InvalidRaffle);
+        }
+        let cost = Amount::from_attos(u128::from(raffle.ticket_price).saturating_mul(u128::from(tickets)));
+        if player.token_balance < cost {
+            return Err(ContractError::InsufficientBalance);
+        }
+        player.token_balance = player.token_balance.saturating_sub(cost);
+        player.total_spent = player.total_spent.saturating_add(cost);
+        self.save_player(player).await?;
+
+        match raffle.entries.iter_mut().find(|entry| entry.player == player_id) {
+            Some(entry) => entry.tickets += tickets,
+            None => raffle.entries.push(RaffleEntry { player: player_id, tickets }),
+        }
+        raffle.total_tickets += tickets;
+        raffle.pot = raffle.pot.saturating_add(cost);
+        raffle.seed = Some(CryptoHash::new(&RaffleSeed {
+            raffle_id,
+            previous: raffle.seed,
+            chain_id: self.runtime.chain_id(),
+            block_height: self.runtime.block_height().0,
+            bought_at: current_time,
+            player: player_id,
+            tickets,
+        }));
+        self.state.raffles.insert(&raffle_id, raffle)?;
+        Ok(())
+    }
+
+    /// Draw an ended raffle's winners and pay them, then open the next one if it recurs
+    /// A raffle without tickets closes without winners or a treasury top-up
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Winners drawn and paid
+    /// * `Err(RaffleNotEnded)` - Tickets are still on sale
+    /// * `Err(RaffleClosed)` - Raffle was already drawn
+    async fn draw_raffle(&mut self, raffle_id: RaffleId, current_time: Timestamp) -> Result<(), ContractError> {
+        let mut raffle = self.state.raffles.get(&raffle_id).await?.ok_or(ContractError::RaffleNotFound)?;
+        if raffle.status != RaffleStatus::Open {
+            return Err(ContractError::RaffleClosed);
+        }
+        if current_time < raffle.ends_at {
+            return Err(ContractError::RaffleNotEnded);
+        }
+        raffle.status = RaffleStatus::Drawn;
+
+        if let Some(seed) = raffle.seed.filter(|_| raffle.total_tickets > 0) {
+            raffle.winners = (0..raffle.winner_count)
+                .filter_map(|index| raffle.ticket_holder(RaffleDraw { seed, index }.ticket(raffle.total_tickets)))
+                .collect();
+
+            let burn_percent = self.state.config.get().raffle_burn_percent;
+            let burn = Amount::from_attos(math::apply_percent(u128::from(raffle.pot), burn_percent));
+            self.burn_tokens(burn).await?;
+            let treasury = *self.state.treasury.get();
+            let top_up = raffle.treasury_top_up.min(treasury);
+            let prize = raffle.pot.saturating_sub(burn).saturating_add(top_up);
+            raffle.prize_per_winner = Amount::from_attos(u128::from(prize) / u128::from(raffle.winner_count));
+            // Rounding dust stays in the treasury
+            let paid = Amount::from_attos(u128::from(raffle.prize_per_winner) * u128::from(raffle.winner_count));
+            self.state.treasury.set(treasury.saturating_sub(top_up).saturating_add(prize.saturating_sub(paid)));
+            for winner in raffle.winners.clone() {
+                let mut player = self.get_player(&winner).await?;
+                player.token_balance = player.token_balance.saturating_add(raffle.prize_per_winner);
+                player.total_earned = player.total_earned.saturating_add(raffle.prize_per_winner);
+                self.save_player(player).await?;
+            }
+        }
+
+        if raffle.recurring {
+            let id = *self.state.next_raffle_id.get();
+            self.state.next_raffle_id.set(id + 1);
+            let duration_micros = raffle.ends_at.micros() - raffle.opened_at.micros();
+            self.state.raffles.insert(&id, Raffle {
+                id,
+                opened_at: current_time,
+                ends_at: Timestamp::from(current_time.micros().saturating_add(duration_micros)),
+                entries: Vec::new(),
+                total_tickets: 0,
+                pot: Amount::ZERO,
+                status: RaffleStatus::Open,
+                winners: Vec::new(),
+                prize_per_winner: Amount::ZERO,
+                seed: None,
+                ..raffle.clone()
+            })?;
+        }
+        self.state.raffles.insert(&raffle_id, raffle)?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Mystery Chests
+    // ============================================================================
+    //
+    // Every few levels a player earns a chest, which they open for one drop of the admin's
+    // table: tokens, XP or both. Like raffle draws, the drop is picked from a hash settled
+    // before the chest can be opened, of its chain and when it was earned, and the hash is
+    // kept on the chest.
+
+    /// Replace the chest table (admin only)
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Table replaced; chests already earned draw from it when opened
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidChestTable)` - Too many drops, a zero weight, or no drops while chests are earned
+    fn set_chest_table(&mut self, caller: PlayerId, table: ChestTable) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        if !table.is_valid() {
+            return Err(ContractError::InvalidChestTable);
+        }
+        self.state.chest_table.set(table);
+        Ok(())
+    }
+
+    /// Give a player a chest for each milestone level between `old_level` and `new_level`
+    async fn award_chests(&mut self, player_id: PlayerId, old_level: u32, new_level: u32) -> Result<(), ContractError> {
+        let levels: Vec<u32> = self.state.chest_table.get().chest_levels(old_level, new_level).collect();
+        if levels.is_empty() {
+            return Ok(());
+        }
+        let awarded_at = self.runtime.system_time();
+        let mut chests = self.state.chests.get(&player_id).await?.unwrap_or_default();
+        for level in levels {
+            chests.push(MysteryChest {
+                id: chests.len() as ChestId,
+                level,
+                awarded_at,
+                opened_at: None,
+                seed: None,
+                contents: None,
+            });
+        }
+        self.state.chests.insert(&player_id, chests)?;
+        Ok(())
+    }
+
+    /// Open an earned chest and pay out the drop it lands on
+    /// 
+    /// # Returns
+    /// * `Ok(contents)` - Drop credited to the player
+    /// * `Err(ChestNotFound)` - Player has no chest with this ID
+    /// * `Err(ChestAlreadyOpened)` - Chest was opened before
+    /// * `Err(InvalidChestTable)` - Table has no drops to pick from
+    async fn open_chest(
+        &mut self,
+        player_id: PlayerId,
+        chest_id: ChestId,
+        current_time: Timestamp,
+    ) -> Result<ChestDrop, ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let mut chests = self.state.chests.get(&player_id).await?.unwrap_or_default();
+        let chest = chests.get_mut(chest_id as usize).ok_or(ContractError::ChestNotFound)?;
+        if chest.opened_at.is_some() {
+            return Err(ContractError::ChestAlreadyOpened);
+        }
+        let (seed, roll) = ChestSeed {
+            player: player_id,
+            chest_id,
+            chain_id: self.runtime.chain_id(),
+            level: chest.level,
+            awarded_at: chest.awarded_at,
+        }
+        .roll();
+        let contents = self.state.chest_table.get().pick(roll).ok_or(ContractError::InvalidChestTable)?;
+        chest.opened_at = Some(current_time);
+        chest.seed = Some(seed);
+        chest.contents = Some(contents);
+        self.state.chests.insert(&player_id, chests)?;
+
+        player.token_balance = player.token_balance.saturating_add(contents.tokens);
+        player.total_earned = player.total_earned.saturating_add(contents.tokens);
+        self.record_token_flow(TokenFlow::ChestReward, contents.tokens).await?;
+        self.add_experience(&mut player, contents.xp).await?;
+        self.save_player(player).await?;
+        Ok(contents)
+    }
+
+    // ============================================================================
+    // Season Pass
+    // ============================================================================
+    //
+    // XP earned while a season runs counts towards its pass. Every tier reached pays its free
+    // reward; players who burned tokens for the premium track also get the premium reward,
+    // including for tiers reached before they bought it.
+
+    /// Schedule a season; it must not start before the latest season ends
+    /// 
+    /// # Returns
+    /// * `Ok(season_id)` - Season scheduled
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidSeason)` - Empty name, zero duration, a start in the past or overlapping
+    ///   the latest season, or invalid tiers
+    #[allow(clippy::too_many_arguments)]
+    async fn create_season(
+        &mut self,
+        caller: PlayerId,
+        name: String,
+        starts_at: Timestamp,
+        duration_seconds: u64,
+        premium_price: Amount,
+        tiers: Vec<PassTier>,
+        current_time: Timestamp,
+    ) -> Result<SeasonId, ContractError> {
+        self.ensure_admin(caller)?;
+        let name = name.trim().to_string();
+        if name.is_empty() || duration_seconds == 0 || starts_at < current_time || !Season::tiers_valid(&tiers) {
+            return Err(ContractError::InvalidSeason);
+        }
+        let id = *self.state.next_season_id.get();
+        if let Some(latest) = id.checked_sub(1) {
+            let latest = self.state.seasons.get(&latest).await?.ok_or(ContractError::SeasonNotFound)?;
+            if starts_at < latest.ends_at {
+                return Err(ContractError::InvalidSeason);
+            }
+        }
+        self.state.next_season_id.set(id + 1);
+        self.state.seasons.insert(&id, Season {
+            id,
+            name,
+            starts_at,
+            ends_at: Timestamp::from(starts_at.micros().saturating_add(duration_seconds.saturating_mul(1_000_000))),
+            premium_price,
+            tiers,
+        })?;
+        Ok(id)
+    }
+
+    /// Replace a season's tiers before it starts
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Tiers replaced
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(SeasonStarted)` - Players may already have reached the current tiers
+    /// * `Err(InvalidSeason)` - Tiers are invalid
+    async fn set_pass_tiers(
+        &mut self,
+        caller: PlayerId,
+        season_id: SeasonId,
+        tiers: Vec<PassTier>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        let mut season = self.state.seasons.get(&season_id).await?.ok_or(ContractError::SeasonNotFound)?;
+        if current_time >= season.starts_at {
+            return Err(ContractError::SeasonStarted);
+        }
+        if !Season::tiers_valid(&tiers) {
+            return Err(ContractError::InvalidSeason);
+        }
+        season.tiers = tiers;
+        self.state.seasons.insert(&season_id, season)?;
+        Ok(())
+    }
+
+    /// Burn the premium price to unlock a season's premium track
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Premium rewards paid from now on, for past tiers too
+    /// * `Err(SeasonEnded)` - Season is over
+    /// * `Err(PremiumPassOwned)` - Player already has the premium track
+    /// * `Err(InsufficientBalance)` - Player can't afford it
+    async fn buy_premium_pass(
+        &mut self,
+        player_id: PlayerId,
+        season_id: SeasonId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let season = self.state.seasons.get(&season_id).await?.ok_or(ContractError::SeasonNotFound)?;
+        if current_time >= season.ends_at {
+            return Err(ContractError::SeasonEnded);
+        }
+        let mut passes = self.state.season_passes.try_load_entry_mut(&season_id).await?;
+        let mut pass = passes.get(&player_id).await?.unwrap_or_default();
+        if pass.premium {
+            return Err(ContractError::PremiumPassOwned);
+        }
+        if player.token_balance < season.premium_price {
+            return Err(ContractError::InsufficientBalance);
+        }
+        pass.premium = true;
+        passes.insert(&player_id, pass)?;
+        drop(passes);
+
+        player.token_balance = player.token_balance.saturating_sub(season.premium_price);
+        player.total_spent = player.total_spent.saturating_add(season.premium_price);
+        self.save_player(player).await?;
+        self.burn_tokens(season.premium_price).await
+    }
+
+    /// Pay the free, and for premium holders the premium, rewards of every tier reached and
+    /// not yet paid. Rewards stay claimable after the season ends
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Rewards added to the player's balance
+    /// * `Err(NoPassRewards)` - No new tier reached since the last claim
+    async fn claim_pass_rewards(&mut self, player_id: PlayerId, season_id: SeasonId) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let season = self.state.seasons.get(&season_id).await?.ok_or(ContractError::SeasonNotFound)?;
+        let mut passes = self.state.season_passes.try_load_entry_mut(&season_id).await?;
+        let mut pass = passes.get(&player_id).await?.ok_or(ContractError::NoPassRewards)?;
+        let reached = pass.tier as usize;
+        let mut reward = Amount::ZERO;
+        for tier in &season.tiers[pass.free_tiers_claimed as usize..reached] {
+            reward = reward.saturating_add(tier.free_reward);
+        }
+        if pass.premium {
+            for tier in &season.tiers[pass.premium_tiers_claimed as usize..reached] {
+                reward = reward.saturating_add(tier.premium_reward);
+            }
+            pass.premium_tiers_claimed = pass.tier;
+        }
+        if reward == Amount::ZERO {
+            return Err(ContractError::NoPassRewards);
+        }
+        pass.free_tiers_claimed = pass.tier;
+        passes.insert(&player_id, pass)?;
+        drop(passes);
+
+        player.token_balance = player.token_balance.saturating_add(reward);
+        player.total_earned = player.total_earned.saturating_add(reward);
+        self.save_player(player).await?;
+        let total_supply = self.state.total_supply.get().saturating_add(reward);
+        self.state.total_supply.set(total_supply);
+        self.record_token_flow(TokenFlow::SeasonReward, reward).await
+    }
+
+    /// Credit XP to the player's pass for the season running now, if any
+    async fn add_season_xp(&mut self, player_id: PlayerId, xp: u64) -> Result<(), ContractError> {
+        let now = self.runtime.system_time();
+        // Seasons don't overlap and start in ID order, so the running one, if any, is the
+        // latest to have started
+        let mut season_id = *self.state.next_season_id.get();
+        let season = loop {
+            let Some(previous) = season_id.checked_sub(1) else {
+                return Ok(());
+            };
+            season_id = previous;
+            let Some(season) = self.state.seasons.get(&season_id).await? else {
+                return Ok(());
+            };
+            if season.starts_at <= now {
+                break season;
+            }
+        };
+        if !season.is_active(now) {
+            return Ok(());
+        }
+        let mut passes = self.state.season_passes.try_load_entry_mut(&season_id).await?;
+        let mut pass = passes.get(&player_id).await?.unwrap_or_default();
+        pass.xp = pass.xp.saturating_add(xp);
+        pass.tier = season.tiers_reached(pass.xp);
+        passes.insert(&player_id, pass)?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Guild Leagues
+    // ============================================================================
+    //
+    // Guilds play in Bronze, Silver and Gold leagues, ranked on the season pass XP their
+    // members earn during each season. Once a season ends anyone can settle it: every league
+    // pays its reward table into the shared pools of its best guilds, the top guilds move up
+    // a league and the bottom ones move down. Seasons are settled in order.
+
+    /// Set the tokens each rank of a league is paid per season (admin only)
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Reward table replaced
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidLeagueRewards)` - More than `MAX_LEAGUE_REWARD_RANKS` ranks
+    fn set_league_rewards(&mut self, caller: PlayerId, tier: LeagueTier, rewards: Vec<Amount>) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        if rewards.len() > MAX_LEAGUE_REWARD_RANKS {
+            return Err(ContractError::InvalidLeagueRewards);
+        }
+        self.state.league_rewards.insert(&tier, rewards)?;
+        Ok(())
+    }
+
+    /// Rank every league for an ended season, pay the rewards and move guilds between leagues
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Season settled and its standings recorded
+    /// * `Err(SeasonNotFound)` - No season with this ID
+    /// * `Err(SeasonNotEnded)` - Season is still running
+    /// * `Err(LeaguesSettled)` - This season, or a later one, was already settled
+    async fn settle_guild_leagues(&mut self, season_id: SeasonId, current_time: Timestamp) -> Result<(), ContractError> {
+        let season = self.state.seasons.get(&season_id).await?.ok_or(ContractError::SeasonNotFound)?;
+        if current_time < season.ends_at {
+            return Err(ContractError::SeasonNotEnded);
+        }
+        if self.state.league_season_settled.get().is_some_and(|settled| season_id <= settled) {
+            return Err(ContractError::LeaguesSettled);
+        }
+
+        let mut guilds = Vec::new();
+        self.state.guilds.for_each_index_value(|_, guild| {
+            guilds.push(guild.into_owned());
+            Ok(())
+        }).await?;
+        let mut scores = Vec::new();
+        if let Some(passes) = self.state.season_passes.try_load_entry(&season_id).await? {
+            for guild in &guilds {
+                let mut season_xp = 0u64;
+                for member in &guild.members {
+                    let xp = passes.get(member).await?.map_or(0, |pass| pass.xp);
+                    season_xp = season_xp.saturating_add(xp);
+                }
+                scores.push((guild, season_xp));
+            }
+        } else {
+            scores.extend(guilds.iter().map(|guild| (guild, 0)));
+        }
+        let mut results = Vec::new();
+        for tier in LeagueTier::ALL {
+            let rewards = self.state.league_rewards.get(&tier).await?.unwrap_or_default();
+            results.extend(rank_league(tier, &scores, &rewards));
+        }
+
+        let mut total_reward = Amount::ZERO;
+        let mut guilds: BTreeMap<GuildId, Guild> = guilds.into_iter().map(|guild| (guild.id, guild)).collect();
+        for standing in &results {
+            let Some(mut guild) = guilds.remove(&standing.guild_id) else {
+                continue;
+            };
+            guild.shared_pool = guild.shared_pool.saturating_add(standing.reward);
+            guild.league_tier = standing.next_tier;
+            self.state.guilds.insert(&standing.guild_id, guild)?;
+            total_reward = total_reward.saturating_add(standing.reward);
+        }
+        let total_supply = self.state.total_supply.get().saturating_add(total_reward);
+        self.state.total_supply.set(total_supply);
+        self.record_token_flow(TokenFlow::SeasonReward, total_reward).await?;
+        self.state.league_results.insert(&season_id, results)?;
+        self.state.league_season_settled.set(Some(season_id));
+        Ok(())
+    }
+
+    // ============================================================================
+    // Special Events
+    // ============================================================================
+    //
+    // The admin schedules time-boxed boosts such as a double XP weekend or a zero-fee day,
+    // optionally limited to one category of markets. Trades look up the events running at
+    // their time; ended events are pruned whenever a new one is scheduled.
+
+    /// Boost of the special events running at `time` for `market`
+    fn event_boost(&self, market: &Market, time: Timestamp) -> EventBoost {
+        EventBoost::running(self.state.special_events.get(), &market.market_type, time)
+    }
+
+    /// Schedule a special event; multipliers left unset leave XP or fees unchanged
+    ///
+    /// # Returns
+    /// * `Ok(event_id)` - Event is on the calendar
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidEvent)` - Bad name, period or multipliers, or the event has already ended
+    /// * `Err(TooManyEvents)` - Calendar is full
+    #[allow(clippy::too_many_arguments)]
+    fn schedule_event(
+        &mut self,
+        caller: PlayerId,
+        name: String,
+        starts_at: Timestamp,
+        ends_at: Timestamp,
+        xp_multiplier_bps: Option<u32>,
+        fee_multiplier_bps: Option<u32>,
+        category: Option<MarketCategory>,
+        current_time: Timestamp,
+    ) -> Result<EventId, ContractError> {
+        self.ensure_admin(caller)?;
+        let event_id = *self.state.next_event_id.get();
+        let event = SpecialEvent {
+            id: event_id,
+            name,
+            starts_at,
+            ends_at,
+            xp_multiplier_bps: xp_multiplier_bps.unwrap_or(BASIS_POINTS),
+            fee_multiplier_bps: fee_multiplier_bps.unwrap_or(BASIS_POINTS),
+            category,
+        };
+        if !event.is_valid() || ends_at <= current_time {
+            return Err(ContractError::InvalidEvent);
+        }
+        let mut events = self.state.special_events.get().clone();
+        events.retain(|event| event.ends_at > current_time);
+        if events.len() >= MAX_SCHEDULED_EVENTS {
+            return Err(ContractError::TooManyEvents);
+        }
+        let index = events.partition_point(|other| other.starts_at <= starts_at);
+        events.insert(index, event);
+        self.state.special_events.set(events);
+        self.state.next_event_id.set(event_id + 1);
+        Ok(event_id)
+    }
+
+    /// Take a special event off the calendar, even while it is running
+    ///
+    /// # Returns
+    /// * `Ok(())` - Event no longer applies
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(EventNotFound)` - No such upcoming or running event
+    fn cancel_event(&mut self, caller: PlayerId, event_id: EventId) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        let mut events = self.state.special_events.get().clone();
+        let count = events.len();
+        events.retain(|event| event.id != event_id);
+        if events.len() == count {
+            return Err(ContractError::EventNotFound);
+        }
+        self.state.special_events.set(events);
+        Ok(())
+    }
+
+    // ============================================================================
+    // Featured Market
+    // ============================================================================
+    //
+    // Each day one open market is featured and its trades earn extra XP, drawing liquidity to
+    // it. The admin lines markets up in advance; when the list runs out, the open market with
+    // the most attention the day before is featured instead, scoring its volume plus its
+    // views. Players count their own views, once a market a day, and a relayer account can
+    // report views a frontend batched up. The choice is made by the day's first trade, view
+    // or `SelectFeaturedMarket`, and kept per day.
+
+    /// Line up markets to be featured, replacing the current list
+    ///
+    /// # Returns
+    /// * `Ok(())` - Markets will be featured in order on the coming days
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidFeaturedMarkets)` - More than `MAX_FEATURED_QUEUE` markets, or a repeated one
+    /// * `Err(MarketNotFound)` - A market doesn't exist
+    async fn set_featured_markets(&mut self, caller: PlayerId, market_ids: Vec<MarketId>) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        if market_ids.len() > MAX_FEATURED_QUEUE
+            || market_ids.iter().collect::<BTreeSet<_>>().len() != market_ids.len()
+        {
+            return Err(ContractError::InvalidFeaturedMarkets);
+        }
+        for market_id in &market_ids {
+            if !self.state.markets.contains_key(market_id).await? {
+                return Err(ContractError::MarketNotFound);
+            }
+        }
+        self.state.featured_queue.set(market_ids);
+        Ok(())
+    }
+
+    /// Choose today's featured market unless it was already: the next open market on the
+    /// admin's list, or else the open market with the most attention yesterday
+    /// Markets on the list that closed before their day are skipped
+    async fn select_featured_market(&mut self, current_time: Timestamp) -> Result<(), ContractError> {
+        let day = day_index(current_time);
+        if *self.state.featured_day.get() == Some(day) {
+            return Ok(());
+        }
+        self.state.featured_day.set(Some(day));
+
+        let mut queue = self.state.featured_queue.get().clone();
+        let mut featured = None;
+        while featured.is_none() && !queue.is_empty() {
+            let market_id = queue.remove(0);
+            if self.is_open_market(market_id, current_time).await? {
+                featured = Some((market_id, FeaturedSource::Curated));
+            }
+        }
+        self.state.featured_queue.set(queue);
+
+        if featured.is_none() && *self.state.volume_day.get() == day.checked_sub(1) {
+            let mut scores = BTreeMap::<MarketId, u128>::new();
+            self.state
+                .daily_volumes
+                .for_each_index_value(|market_id, volume| {
+                    *scores.entry(market_id).or_default() += u128::from(*volume);
+                    Ok(())
+                })
+                .await?;
+            let view_value = u128::from(self.state.config.get().trending_view_value);
+            self.state
+                .daily_views
+                .for_each_index_value(|market_id, views| {
+                    let score = scores.entry(market_id).or_default();
+                    *score = score.saturating_add(u128::from(*views).saturating_mul(view_value));
+                    Ok(())
+                })
+                .await?;
+            // Most attention first, lowest ID first among equals
+            let mut scores: Vec<(MarketId, u128)> = scores.into_iter().collect();
+            scores.sort_by(|(id_a, score_a), (id_b, score_b)| score_b.cmp(score_a).then(id_a.cmp(id_b)));
+            for (market_id, _) in scores {
+                if self.is_open_market(market_id, current_time).await? {
+                    featured = Some((market_id, FeaturedSource::Trending));
+                    break;
+                }
+            }
+        }
+        if let Some((market_id, source)) = featured {
+            self.state.featured_markets.insert(&day, FeaturedMarket { day, market_id, source })?;
+        }
+        Ok(())
+    }
+
+    /// Count a player's view of an open market; views after the first of the day are ignored
+    ///
+    /// # Returns
+    /// * `Ok(())` - View counted, or ignored as a repeat
+    /// * `Err(MarketNotActive)` - Market doesn't exist or isn't open
+    /// * `Err(InvalidViewBatch)` - Player's views already count for `MAX_DAILY_VIEWED_MARKETS` markets today
+    async fn record_view(
+        &mut self,
+        player_id: PlayerId,
+        market_id: MarketId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        if !self.is_open_market(market_id, current_time).await? {
+            return Err(ContractError::MarketNotActive);
+        }
+        self.roll_engagement_day(current_time).await?;
+        let mut viewed = self.state.daily_viewers.get(&player_id).await?.unwrap_or_default();
+        if viewed.contains(&market_id) {
+            return Ok(());
+        }
+        if viewed.len() >= MAX_DAILY_VIEWED_MARKETS {
+            return Err(ContractError::InvalidViewBatch);
+        }
+        viewed.push(market_id);
+        self.state.daily_viewers.insert(&player_id, viewed)?;
+        self.add_views(market_id, 1).await
+    }
+
+    /// Count views a frontend batched up (engagement relayer only)
+    ///
+    /// # Returns
+    /// * `Ok(())` - Views counted
+    /// * `Err(NotEngagementRelayer)` - Caller is not the configured relayer
+    /// * `Err(InvalidViewBatch)` - Batch is empty or has more than `MAX_VIEW_BATCH` entries
+    /// * `Err(MarketNotActive)` - A market doesn't exist or isn't open
+    async fn record_views(
+        &mut self,
+        caller: PlayerId,
+        views: Vec<MarketViewCount>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        if self.state.config.get().engagement_relayer != Some(caller) {
+            return Err(ContractError::NotEngagementRelayer);
+        }
+        if views.is_empty() || views.len() > MAX_VIEW_BATCH {
+            return Err(ContractError::InvalidViewBatch);
+        }
+        for count in &views {
+            if !self.is_open_market(count.market_id, current_time).await? {
+                return Err(ContractError::MarketNotActive);
+            }
+        }
+        self.roll_engagement_day(current_time).await?;
+        for count in views {
+            self.add_views(count.market_id, count.views).await?;
+        }
+        Ok(())
+    }
+
+    async fn add_views(&mut self, market_id: MarketId, views: u64) -> Result<(), ContractError> {
+        let today = self.state.daily_views.get(&market_id).await?.unwrap_or_default();
+        self.state.daily_views.insert(&market_id, today.saturating_add(views))?;
+        let total = self.state.market_views.get(&market_id).await?.unwrap_or_default();
+        self.state.market_views.insert(&market_id, total.saturating_add(views))?;
+        Ok(())
+    }
+
+    async fn is_open_market(&self, market_id: MarketId, current_time: Timestamp) -> Result<bool, ContractError> {
+        Ok(self
+            .state
+            .markets
+            .get(&market_id)
+            .await?
+            .is_some_and(|market| market.status == MarketStatus::Active && current_time < market.end_time))
+    }
+
+    /// XP for a trade placed at `trade_time`, multiplied if its market was featured that day
+    async fn featured_xp(&mut self, market_id: MarketId, xp: u64, trade_time: Timestamp) -> Result<u64, ContractError> {
+        self.select_featured_market(trade_time).await?;
+        let featured = self.state.featured_markets.get(&day_index(trade_time)).await?;
+        if featured.is_none_or(|featured| featured.market_id != market_id) {
+            return Ok(xp);
+        }
+        let boost = EventBoost {
+            xp_multiplier_bps: self.state.config.get().featured_market_xp_multiplier_bps,
+            ..EventBoost::default()
+        };
+        Ok(boost.xp(xp))
+    }
+
+    /// XP for a trade staking `stake` out of the `balance` held before it, placed at
+    /// `trade_time`, before any boost: the full amount on the player's first entry into the
+    /// market, then the smaller repeat amount until their repeat XP in the market that day
+    /// reaches the cap. Either is scaled down for a small stake, and all of it stops once the
+    /// player's trade XP that day reaches the daily cap
+    async fn trade_xp(
+        &mut self,
+        market_id: MarketId,
+        player_id: PlayerId,
+        first_entry: bool,
+        stake: Tokens,
+        balance: Tokens,
+        trade_time: Timestamp,
+    ) -> Result<u64, ContractError> {
+        let trade_xp = self.state.config.get().trade_xp;
+        let day = day_index(trade_time);
+        let mut overall = self
+            .state
+            .daily_trade_xp
+            .get(&player_id)
+            .await?
+            .filter(|today| today.day == day)
+            .unwrap_or(DailyTradeXp { day, xp: 0 });
+        let base = if first_entry { trade_xp.first_trade } else { trade_xp.repeat_trade };
+        let xp = math::stake_scaled_xp(base, stake.attos(), balance.attos(), trade_xp.full_xp_stake_bps)
+            .min(trade_xp.daily_cap.saturating_sub(overall.xp));
+        let xp = if first_entry {
+            xp
+        } else {
+            let mut earned = self.state.repeat_trade_xp.try_load_entry_mut(&market_id).await?;
+            let mut today = earned
+                .get(&player_id)
+                .await?
+                .filter(|today| today.day == day)
+                .unwrap_or(DailyTradeXp { day, xp: 0 });
+            let xp = xp.min(trade_xp.repeat_daily_cap.saturating_sub(today.xp));
+            today.xp += xp;
+            earned.insert(&player_id, today)?;
+            xp
+        };
+        overall.xp += xp;
+        self.state.daily_trade_xp.insert(&player_id, overall)?;
+        Ok(xp)
+    }
+
+    /// Price impact `player_id`'s buys in a market have used up in their current window, or a
+    /// fresh window starting now once the last one has run out
+    async fn price_impact_window(
+        &self,
+        market_id: MarketId,
+        player_id: PlayerId,
+        current_time: Timestamp,
+    ) -> Result<PriceImpactWindow, ContractError> {
+        let length = TimeDelta::from_secs(self.state.config.get().price_impact_window_seconds);
+        let window = match self.state.price_impact_windows.try_load_entry(&market_id).await? {
+            Some(windows) => windows.get(&player_id).await?,
+            None => None,
+        };
+        Ok(window
+            .filter(|window| current_time < window.started_at.saturating_add(length))
+            .unwrap_or(PriceImpactWindow {
+                started_at: current_time,
+                used_bps: 0,
+            }))
+    }
+
+    /// XP for a trade, cut to `self_trade_xp_percent` when the trader created the market
+    fn self_trade_xp(&self, market: &Market, trader: PlayerId, xp: u64) -> u64 {
+        if market.creator != trader {
+            return xp;
+        }
+        math::apply_percent(u128::from(xp), self.state.config.get().self_trade_xp_percent) as u64
+    }
+
+    /// Restart the daily volume and view tallies on a new day, once yesterday's were used to
+    /// pick today's featured market
+    async fn roll_engagement_day(&mut self, now: Timestamp) -> Result<(), ContractError> {
+        self.select_featured_market(now).await?;
+        let day = day_index(now);
+        if *self.state.volume_day.get() != Some(day) {
+            self.state.daily_volumes.clear();
+            self.state.daily_views.clear();
+            self.state.daily_viewers.clear();
+            self.state.volume_day.set(Some(day));
+        }
+        Ok(())
+    }
+
+    /// Add a trade to its market's volume for today
+    async fn record_daily_volume(&mut self, market_id: MarketId, amount: Amount, current_time: Timestamp) -> Result<(), ContractError> {
+        self.roll_engagement_day(current_time).await?;
+        let volume = self.state.daily_volumes.get(&market_id).await?.unwrap_or_default();
+        self.state.daily_volumes.insert(&market_id, volume.saturating_add(amount))?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Attestations
+    // ============================================================================
+    //
+    // A player asks the chain to vouch for something they achieved. The chain checks the
+    // claim against its own state and records an attestation hashed over its content and the
+    // issuing chain. Sent to another chain, it is kept there only if it arrived from the chain
+    // it names and its hash still matches, so applications on that chain can rely on it.
+
+    /// Issue an attestation of a claim about the signer, sending it to `recipient` if given
+    /// 
+    /// # Returns
+    /// * `Ok((attestation_id, hash))` - Attestation recorded, and sent if a recipient was given
+    /// * `Err(PlayerNotFound)` - Signer isn't registered
+    /// * `Err(MarketNotFound)` - No market with this ID
+    /// * `Err(ClaimNotAchieved)` - The market isn't resolved in the signer's favour, or the
+    ///   signer's level is too low
+    async fn request_attestation(
+        &mut self,
+        player_id: PlayerId,
+        claim: AttestationClaim,
+        recipient: Option<ChainId>,
+        current_time: Timestamp,
+    ) -> Result<(AttestationId, CryptoHash), ContractError> {
+        let player = self.get_player(&player_id).await?;
+        let achieved = match &claim {
+            AttestationClaim::WonMarket { market_id } => {
+                let market = self.get_market(market_id).await?;
+                market.status == MarketStatus::Resolved
+                    && market
+                        .positions
+                        .get(&player_id)
+                        .is_some_and(|position| market.payout_for(position) > Amount::ZERO)
+            }
+            AttestationClaim::ReachedLevel { level } => player.level >= *level,
+        };
+        if !achieved {
+            return Err(ContractError::ClaimNotAchieved);
+        }
+
+        let id = *self.state.next_attestation_id.get();
+        self.state.next_attestation_id.set(id + 1);
+        let attestation = Attestation::new(id, player_id, claim, self.runtime.chain_id(), current_time);
+        let hash = attestation.hash;
+        self.state.attestations.insert(&id, attestation.clone())?;
+        if let Some(chain_id) = recipient {
+            self.runtime
+                .prepare_message(Message::AttestationIssued { attestation })
+                .send_to(chain_id);
+        }
+        Ok((id, hash))
+    }
+
+    /// Keep an attestation sent by the chain that issued it
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Attestation stored under its hash
+    /// * `Err(InvalidAttestation)` - Sent from another chain, or its hash doesn't match
+    fn receive_attestation(&mut self, attestation: Attestation) -> Result<(), ContractError> {
+        if self.runtime.message_origin_chain_id() != Some(attestation.chain_id) || !attestation.is_intact() {
+            return Err(ContractError::InvalidAttestation);
+        }
+        let hash = attestation.hash;
+        self.state.received_attestations.insert(&hash, attestation)?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Head-to-Head Challenges
+    // ============================================================================
+    //
+    // Two players stake equal amounts on opposite outcomes. Without an oracle, the players
+    // report the result themselves: matching reports pay the winner the pot minus the trading
+    // fee, while conflicting reports void the challenge and refund both stakes. A player who
+    // never reports can't hold the pot hostage: once the report window is over, a lone report
+    // stands.
+
+    /// Open a challenge, escrowing the challenger's stake
+    /// 
+    /// # Returns
+    /// * `Ok(challenge_id)` - Challenge created and waiting for the opponent
+    /// * `Err(InvalidChallenge)` - Self-challenge, zero stake or not exactly two outcomes
+    /// * `Err(DurationTooShort)` - Duration is below the minimum market duration
+    /// * `Err(InsufficientBalance)` - Challenger can't cover the stake
+    async fn create_head_to_head(
+        &mut self,
+        challenger: PlayerId,
+        opponent: PlayerId,
+        outcome_names: Vec<String>,
+        stake: Amount,
+        duration_seconds: u64,
+        current_time: Timestamp,
+    ) -> Result<ChallengeId, ContractError> {
+        if opponent == challenger || stake == Amount::ZERO || outcome_names.len() != 2 {
+            return Err(ContractError::InvalidChallenge);
+        }
+        if duration_seconds < self.state.config.get().min_market_duration_seconds {
+            return Err(ContractError::DurationTooShort);
+        }
+        self.get_player(&opponent).await?;
+        let mut player = self.get_player(&challenger).await?;
+        if player.token_balance < stake {
+            return Err(ContractError::InsufficientBalance);
+        }
+        Self::charge_risk_limits(&mut player, stake, current_time)?;
+        player.token_balance = player.token_balance.saturating_sub(stake);
+        self.save_player(player).await?;
+
+        let challenge_id = *self.state.next_challenge_id.get();
+        self.state.next_challenge_id.set(challenge_id + 1);
+        self.state.challenges.insert(&challenge_id, Challenge {
+            id: challenge_id,
+            challenger,
+            opponent,
+            outcome_names,
+            stake,
+            duration_seconds,
+            created_at: current_time,
+            end_time: None,
+            status: ChallengeStatus::Pending,
+            challenger_report: None,
+            opponent_report: None,
+            winner: None,
+            payout: Amount::ZERO,
+        })?;
+        Ok(challenge_id)
+    }
+
+    /// Match the stake and start the clock (opponent only)
+    async fn accept_challenge(
+        &mut self,
+        player_id: PlayerId,
+        challenge_id: ChallengeId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut challenge = self.get_challenge(challenge_id).await?;
+        if challenge.opponent != player_id {
+            return Err(ContractError::Unauthorized);
+        }
+        if challenge.status != ChallengeStatus::Pending {
+            return Err(ContractError::InvalidChallengeStatus);
+        }
+        let mut player = self.get_player(&player_id).await?;
+        if player.token_balance < challenge.stake {
+            return Err(ContractError::InsufficientBalance);
+        }
+        Self::charge_risk_limits(&mut player, challenge.stake, current_time)?;
+        player.token_balance = player.token_balance.saturating_sub(challenge.stake);
+        self.save_player(player).await?;
+
+        challenge.status = ChallengeStatus::Active;
+        challenge.end_time = Some(Timestamp::from(
+            current_time.micros().saturating_add(challenge.duration_seconds.saturating_mul(1_000_000)),
+        ));
+        self.state.challenges.insert(&challenge_id, challenge)?;
+        Ok(())
+    }
+
+    /// Withdraw a pending challenge and refund the stake (challenger only)
+    async fn cancel_challenge(&mut self, player_id: PlayerId, challenge_id: ChallengeId) -> Result<(), ContractError> {
+        let mut challenge = self.get_challenge(challenge_id).await?;
+        if challenge.challenger != player_id {
+            return Err(ContractError::Unauthorized);
+        }
+        if challenge.status != ChallengeStatus::Pending {
+            return Err(ContractError::InvalidChallengeStatus);
+        }
+        self.credit_player(&player_id, challenge.stake).await?;
+        challenge.status = ChallengeStatus::Cancelled;
+        self.state.challenges.insert(&challenge_id, challenge)?;
+        Ok(())
+    }
+
+    /// Record a player's view of the result and settle once both players have reported
+    /// After the report window closes no new reports are taken, and the call settles the
+    /// challenge on the reports already in: a lone report stands, and with none the stakes
+    /// are refunded
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Report recorded; the challenge settles or is voided once both are in
+    /// * `Err(Unauthorized)` - Caller is not one of the two players
+    /// * `Err(ChallengeNotEnded)` - The end time has not passed
+    /// * `Err(InvalidOutcome)` - Outcome is not 0 or 1
+    async fn report_challenge_outcome(
+        &mut self,
+        player_id: PlayerId,
+        challenge_id: ChallengeId,
+        outcome_id: OutcomeId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut challenge = self.get_challenge(challenge_id).await?;
+        if challenge.status != ChallengeStatus::Active {
+            return Err(ContractError::InvalidChallengeStatus);
+        }
+        let Some(end_time) = challenge.end_time.filter(|end_time| current_time >= *end_time) else {
+            return Err(ContractError::ChallengeNotEnded);
+        };
+        if outcome_id as usize >= challenge.outcome_names.len() {
+            return Err(ContractError::InvalidOutcome);
+        }
+        if player_id != challenge.challenger && player_id != challenge.opponent {
+            return Err(ContractError::Unauthorized);
+        }
+        let window_seconds = self.state.config.get().challenge_report_window_seconds;
+        let window_closed = current_time >= end_time.saturating_add(TimeDelta::from_secs(window_seconds));
+        if !window_closed {
+            if player_id == challenge.challenger {
+                challenge.challenger_report = Some(outcome_id);
+            } else {
+                challenge.opponent_report = Some(outcome_id);
+            }
+        }
+
+        match (challenge.challenger_report, challenge.opponent_report) {
+            (Some(reported), Some(confirmed)) if reported == confirmed => {
+                self.pay_challenge_winner(&mut challenge, reported).await?;
+            }
+            (Some(_), Some(_)) => {
+                self.void_challenge(&mut challenge).await?;
+            }
+            (Some(reported), None) | (None, Some(reported)) if window_closed => {
+                self.pay_challenge_winner(&mut challenge, reported).await?;
+            }
+            (None, None) if window_closed => {
+                self.void_challenge(&mut challenge).await?;
+            }
+            _ => {}
+        }
+        self.state.challenges.insert(&challenge_id, challenge)?;
+        Ok(())
+    }
+
+    /// Pay the pot, less the trading fee, to whoever backed `outcome_id`
+    async fn pay_challenge_winner(&mut self, challenge: &mut Challenge, outcome_id: OutcomeId) -> Result<(), ContractError> {
+        let winner = if outcome_id == 0 { challenge.challenger } else { challenge.opponent };
+        let pot = challenge.stake.saturating_mul(2);
+        let config = self.state.config.get();
+        let split = math::trading_fee(u128::from(pot), config.fee_schedule.base_bps, config.trading_fee_burn_percent);
+        let fee = Amount::from_attos(split.total());
+        let payout = pot.saturating_sub(fee);
+
+        let mut player = self.get_player(&winner).await?;
+        player.token_balance = player.token_balance.saturating_add(payout);
+        player.total_earned = player.total_earned.saturating_add(payout);
+        player.total_profit = player.total_profit.saturating_add(payout.saturating_sub(challenge.stake));
+        self.save_player(player).await?;
+        self.record_token_flow(TokenFlow::TradingFee, fee).await?;
+        self.burn_tokens(Amount::from_attos(split.burned)).await?;
+
+        challenge.status = ChallengeStatus::Settled;
+        challenge.winner = Some(winner);
+        challenge.payout = payout;
+        Ok(())
+    }
+
+    /// Refund both stakes of an accepted challenge
+    async fn void_challenge(&mut self, challenge: &mut Challenge) -> Result<(), ContractError> {
+        self.credit_player(&challenge.challenger, challenge.stake).await?;
+        self.credit_player(&challenge.opponent, challenge.stake).await?;
+        challenge.status = ChallengeStatus::Cancelled;
+        Ok(())
+    }
+
+    /// Challenges a player is part of that still hold stakes
+    async fn open_challenges(&self, player_id: PlayerId) -> Result<Vec<ChallengeId>, ContractError> {
+        let mut challenge_ids = Vec::new();
+        self.state.challenges.for_each_index_value(|challenge_id, challenge| {
+            let open = matches!(challenge.status, ChallengeStatus::Pending | ChallengeStatus::Active);
+            if open && (challenge.challenger == player_id || challenge.opponent == player_id) {
+                challenge_ids.push(challenge_id);
+            }
+            Ok(())
+        }).await?;
+        Ok(challenge_ids)
+    }
+
+    async fn get_challenge(&self, challenge_id: ChallengeId) -> Result<Challenge, ContractError> {
+        self.state.challenges.get(&challenge_id).await?.ok_or(ContractError::ChallengeNotFound)
+    }
+
+    /// Return escrowed tokens to a player's balance
+    async fn credit_player(&mut self, player_id: &PlayerId, amount: Amount) -> Result<(), ContractError> {
+        let mut player = self.get_player(player_id).await?;
+        player.token_balance = player.token_balance.saturating_add(amount);
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Mentorship
+    // ============================================================================
+    //
+    // A player at the configured mentor level offers to take on a lower level player, who
+    // becomes their apprentice by accepting. Trade XP the apprentice earns is raised by a bonus
+    // and a share of it goes to the mentor; both are paid each milestone's rewards as the
+    // apprentice levels up. The pairing ends when either player ends it or the apprentice
+    // reaches the mentor level.
+
+    /// Offer to mentor a player; offering again is a no-op
+    ///
+    /// # Returns
+    /// * `Ok(())` - Offer is waiting for the apprentice
+    /// * `Err(InvalidMentorship)` - Self-mentoring, mentorship disabled, a mentor below the
+    ///   mentor level, an apprentice at or above it, or an apprentice who already has a mentor
+    /// * `Err(TooManyApprentices)` - Mentor has no room for another apprentice
+    /// * `Err(TooManyMentorshipOffers)` - Apprentice has too many offers pending
+    async fn offer_mentorship(&mut self, mentor_id: PlayerId, apprentice_id: PlayerId) -> Result<(), ContractError> {
+        self.ensure_can_pair(mentor_id, apprentice_id).await?;
+        let mut offers = self.state.mentorship_offers.get(&apprentice_id).await?.unwrap_or_default();
+        if offers.contains(&mentor_id) {
+            return Ok(());
+        }
+        if offers.len() >= MAX_MENTORSHIP_OFFERS {
+            return Err(ContractError::TooManyMentorshipOffers);
+        }
+        offers.push(mentor_id);
+        self.state.mentorship_offers.insert(&apprentice_id, offers)?;
+        Ok(())
+    }
+
+    /// Accept a mentor's offer, dropping every other pending offer
+    ///
+    /// # Returns
+    /// * `Ok(())` - Mentorship started
+    /// * `Err(MentorshipNotFound)` - Mentor made no offer
+    /// * `Err(InvalidMentorship)` - Either player's level no longer allows the pairing
+    /// * `Err(TooManyApprentices)` - Mentor took on other apprentices since offering
+    async fn accept_mentorship(
+        &mut self,
+        apprentice_id: PlayerId,
+        mentor_id: PlayerId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let offers = self.state.mentorship_offers.get(&apprentice_id).await?.unwrap_or_default();
+        if !offers.contains(&mentor_id) {
+            return Err(ContractError::MentorshipNotFound);
+        }
+        let apprentice = self.ensure_can_pair(mentor_id, apprentice_id).await?;
+        self.state.mentorship_offers.remove(&apprentice_id)?;
+        let mut apprentices = self.state.apprentices.get(&mentor_id).await?.unwrap_or_default();
+        apprentices.push(apprentice_id);
+        self.state.apprentices.insert(&mentor_id, apprentices)?;
+        self.state.mentorships.insert(&apprentice_id, Mentorship {
+            mentor: mentor_id,
+            apprentice: apprentice_id,
+            started_at: current_time,
+            last_milestone_level: apprentice.level,
+            apprentice_bonus_xp: 0,
+            mentor_bonus_xp: 0,
+        })?;
+        Ok(())
+    }
+
+    /// Turn down a mentor's offer
+    ///
+    /// # Returns
+    /// * `Ok(())` - Offer removed
+    /// * `Err(MentorshipNotFound)` - Mentor made no offer
+    async fn decline_mentorship(&mut self, apprentice_id: PlayerId, mentor_id: PlayerId) -> Result<(), ContractError> {
+        let mut offers = self.state.mentorship_offers.get(&apprentice_id).await?.unwrap_or_default();
+        let Some(index) = offers.iter().position(|offer| *offer == mentor_id) else {
+            return Err(ContractError::MentorshipNotFound);
+        };
+        offers.remove(index);
+        if offers.is_empty() {
+            self.state.mentorship_offers.remove(&apprentice_id)?;
+        } else {
+            self.state.mentorship_offers.insert(&apprentice_id, offers)?;
+        }
+        Ok(())
+    }
+
+    /// End a mentorship (apprentice or mentor only)
+    ///
+    /// # Returns
+    /// * `Ok(())` - Mentorship ended
+    /// * `Err(MentorshipNotFound)` - Apprentice has no mentor
+    /// * `Err(Unauthorized)` - Caller is neither the apprentice nor their mentor
+    async fn end_mentorship(&mut self, caller: PlayerId, apprentice_id: PlayerId) -> Result<(), ContractError> {
+        let mentorship = self
+            .state
+            .mentorships
+            .get(&apprentice_id)
+            .await?
+            .ok_or(ContractError::MentorshipNotFound)?;
+        if caller != mentorship.apprentice && caller != mentorship.mentor {
+            return Err(ContractError::Unauthorized);
+        }
+        self.remove_mentorship(&mentorship).await
+    }
+
+    /// Check `mentor_id` may take `apprentice_id` on, returning the apprentice
+    async fn ensure_can_pair(&self, mentor_id: PlayerId, apprentice_id: PlayerId) -> Result<Player, ContractError> {
+        let config = &self.state.config.get().mentorship;
+        if mentor_id == apprentice_id || config.max_apprentices == 0 {
+            return Err(ContractError::InvalidMentorship);
+        }
+        let mentor = self.get_player(&mentor_id).await?;
+        let apprentice = self.get_player(&apprentice_id).await?;
+        if mentor.level < config.mentor_level
+            || apprentice.level >= config.mentor_level
+            || self.state.mentorships.contains_key(&apprentice_id).await?
+        {
+            return Err(ContractError::InvalidMentorship);
+        }
+        let apprentices = self.state.apprentices.get(&mentor_id).await?.unwrap_or_default();
+        if apprentices.len() >= config.max_apprentices as usize {
+            return Err(ContractError::TooManyApprentices);
+        }
+        Ok(apprentice)
+    }
+
+    async fn remove_mentorship(&mut self, mentorship: &Mentorship) -> Result<(), ContractError> {
+        self.state.mentorships.remove(&mentorship.apprentice)?;
+        let mut apprentices = self.state.apprentices.get(&mentorship.mentor).await?.unwrap_or_default();
+        apprentices.retain(|apprentice| *apprentice != mentorship.apprentice);
+        if apprentices.is_empty() {
+            self.state.apprentices.remove(&mentorship.mentor)?;
+        } else {
+            self.state.apprentices.insert(&mentorship.mentor, apprentices)?;
+        }
+        Ok(())
+    }
+
+    /// Give an apprentice and their mentor their bonuses on `xp` earned from a trade, pay the
+    /// milestones the apprentice has reached and graduate them at the mentor level
+    /// The caller saves the apprentice
+    async fn share_mentorship_xp(&mut self, apprentice: &mut Player, xp: u64) -> Result<(), ContractError> {
+        let Some(mut mentorship) = self.state.mentorships.get(&apprentice.id).await? else {
+            return Ok(());
+        };
+        let config = self.state.config.get().mentorship.clone();
+        let apprentice_bonus = xp.saturating_mul(u64::from(config.apprentice_bonus_percent)) / 100;
+        let mentor_bonus = xp.saturating_mul(u64::from(config.mentor_bonus_percent)) / 100;
+        if apprentice_bonus > 0 {
+            self.add_experience(apprentice, apprentice_bonus).await?;
+            mentorship.apprentice_bonus_xp = mentorship.apprentice_bonus_xp.saturating_add(apprentice_bonus);
+        }
+
+        // Mentors registered on another chain have no local record to credit
+        let mut mentor = self.state.players.get(&mentorship.mentor).await?;
+        if let Some(mentor) = mentor.as_mut().filter(|_| mentor_bonus > 0) {
+            self.add_experience(mentor, mentor_bonus).await?;
+            mentorship.mentor_bonus_xp = mentorship.mentor_bonus_xp.saturating_add(mentor_bonus);
+        }
+        let mut minted = Amount::ZERO;
+        for milestone in &config.milestones {
+            if milestone.level <= mentorship.last_milestone_level || milestone.level > apprentice.level {
+                continue;
+            }
+            apprentice.token_balance = apprentice.token_balance.saturating_add(milestone.apprentice_reward);
+            apprentice.total_earned = apprentice.total_earned.saturating_add(milestone.apprentice_reward);
+            minted = minted.saturating_add(milestone.apprentice_reward);
+            if let Some(mentor) = mentor.as_mut() {
+                mentor.token_balance = mentor.token_balance.saturating_add(milestone.mentor_reward);
+                mentor.total_earned = mentor.total_earned.saturating_add(milestone.mentor_reward);
+                minted = minted.saturating_add(milestone.mentor_reward);
+            }
+            mentorship.last_milestone_level = milestone.level;
+        }
+        if let Some(mentor) = mentor {
+            self.save_player(mentor).await?;
+        }
+        let total_supply = self.state.total_supply.get().saturating_add(minted);
+        self.state.total_supply.set(total_supply);
+        self.record_token_flow(TokenFlow::MentorshipReward, minted).await?;
+
+        if apprentice.level >= config.mentor_level {
+            self.remove_mentorship(&mentorship).await
+        } else {
+            self.state.mentorships.insert(&apprentice.id, mentorship)?;
+            Ok(())
+        }
+    }
+
+    // ============================================================================
+    // Parlays
+    // ============================================================================
+    //
+    // A parlay combines picks on several markets. Its odds are fixed at creation from the
+    // current outcome prices, less the house edge, and it pays only if every pick is its
+    // market's winning outcome. The treasury backs parlays: winnings above the stake are held
+    // back from it when a parlay is placed, so open parlays can never promise more than it
+    // holds, and lost parlays return their stake and held-back winnings to it.
+
+    /// Place a parlay, taking the stake from the player's balance
+    /// 
+    /// # Returns
+    /// * `Ok(parlay_id)` - Parlay placed
+    /// * `Err(InvalidParlay)` - Fewer than two or more than `MAX_PARLAY_LEGS` legs, a repeated
+    ///   market, or a zero stake
+    /// * `Err(ConflictedParlayLeg)` - A leg's market was created by the player, or the player
+    ///   has a say in its resolution
+    /// * `Err(MarketNotActive)` / `Err(MarketEnded)` - A leg's market is no longer trading
+    /// * `Err(InvalidOutcome)` - A leg picks an outcome its market doesn't have
+    /// * `Err(InsufficientBalance)` - Player can't cover the stake
+    /// * `Err(ParlayPoolExhausted)` - The treasury can't cover the winnings
+    async fn create_parlay(
+        &mut self,
+        player_id: PlayerId,
+        legs: Vec<ParlayLeg>,
+        stake: Amount,
+        current_time: Timestamp,
+    ) -> Result<ParlayId, ContractError> {
+        if legs.len() < 2 || legs.len() > MAX_PARLAY_LEGS || stake == Amount::ZERO {
+            return Err(ContractError::InvalidParlay);
+        }
+        let mut player = self.get_player(&player_id).await?;
+        let mut prices = Vec::with_capacity(legs.len());
+        for (index, leg) in legs.iter().enumerate() {
+            if legs[..index].iter().any(|other| other.market_id == leg.market_id) {
+                return Err(ContractError::InvalidParlay);
+            }
+            let market = self.get_market(&leg.market_id).await?;
+            Self::ensure_market_access(&market, &player)?;
+            if self.can_decide_resolution(&market, player_id).await? {
+                return Err(ContractError::ConflictedParlayLeg);
+            }
+            if market.status != MarketStatus::Active {
+                return Err(ContractError::MarketNotActive);
+            }
+            self.ensure_trading_open(&market, current_time)?;
+            let curve = market.bonding_curve();
+            let outcome_prices: Vec<u128> = market
+                .outcomes
+                .iter()
+                .map(|outcome| curve.price_at(u128::from(outcome.total_shares)))
+                .collect();
+            let price = *outcome_prices
+                .get(leg.outcome_id as usize)
+                .ok_or(ContractError::InvalidOutcome)?;
+            prices.push((price, outcome_prices.iter().sum()));
+        }
+        if player.token_balance < stake {
+            return Err(ContractError::InsufficientBalance);
+        }
+        let odds = math::parlay_odds(&prices, self.state.config.get().parlay_house_edge_bps);
+        let potential_payout = Amount::from_attos(math::mul_div(u128::from(stake), odds, math::ONE));
+        let winnings = potential_payout.saturating_sub(stake);
+        let treasury = *self.state.treasury.get();
+        if treasury < winnings {
+            return Err(ContractError::ParlayPoolExhausted);
+        }
+        Self::charge_risk_limits(&mut player, stake, current_time)?;
+        player.token_balance = player.token_balance.saturating_sub(stake);
+        player.total_spent = player.total_spent.saturating_add(stake);
+        self.save_player(player).await?;
+        self.state.treasury.set(treasury.saturating_sub(winnings));
+
+        let parlay_id = *self.state.next_parlay_id.get();
+        self.state.next_parlay_id.set(parlay_id + 1);
+        self.state.parlays.insert(&parlay_id, Parlay {
+            id: parlay_id,
+            player: player_id,
+            legs,
+            stake,
+            odds: Amount::from_attos(odds),
+            potential_payout,
+            created_at: current_time,
+            status: ParlayStatus::Open,
+        })?;
+        Ok(parlay_id)
+    }
+
+    /// Settle a parlay: lost as soon as one leg loses, won once every leg has won
+    /// Anyone may settle a parlay
+    async fn settle_parlay(&mut self, parlay_id: ParlayId) -> Result<(), ContractError> {
+        let mut parlay = self.state.parlays.get(&parlay_id).await?.ok_or(ContractError::ParlayNotFound)?;
+        if parlay.status != ParlayStatus::Open {
+            return Err(ContractError::ParlayNotSettled);
+        }
+        let mut all_won = true;
+        let mut any_lost = false;
+        for leg in &parlay.legs {
+            // Archived markets were resolved; their summary keeps the result
+            let (resolved, winning_outcome) = match self.state.markets.get(&leg.market_id).await? {
+                Some(market) => (market.status == MarketStatus::Resolved, market.winning_outcome),
+                None => {
+                    let summary = self
+                        .state
+                        .market_archive
+                        .get(&leg.market_id)
+                        .await?
+                        .ok_or(ContractError::MarketNotFound)?;
+                    (true, summary.winning_outcomes.first().copied())
+                }
+            };
+            if !resolved {
+                all_won = false;
+            } else if winning_outcome != Some(leg.outcome_id) {
+                any_lost = true;
+            }
+        }
+
+        if any_lost {
+            parlay.status = ParlayStatus::Lost;
+            // The stake and the winnings held back for it go to the treasury
+            let returned = parlay.stake.max(parlay.potential_payout);
+            let treasury = self.state.treasury.get().saturating_add(returned);
+            self.state.treasury.set(treasury);
+        } else if all_won {
+            parlay.status = ParlayStatus::Won;
+            let winnings = parlay.potential_payout.saturating_sub(parlay.stake);
+            let mut player = self.get_player(&parlay.player).await?;
+            player.token_balance = player.token_balance.saturating_add(parlay.potential_payout);
+            player.total_earned = player.total_earned.saturating_add(parlay.potential_payout);
+            player.total_profit = player.total_profit.saturating_add(winnings);
+            self.save_player(player).await?;
+            // With the edge above the fair odds' gain the payout is below the stake; the
+            // treasury keeps the difference
+            let kept = parlay.stake.saturating_sub(parlay.potential_payout);
+            let treasury = self.state.treasury.get().saturating_add(kept);
+            self.state.treasury.set(treasury);
+            self.record_token_flow(TokenFlow::ParlayWinnings, winnings).await?;
+        } else {
+            return Err(ContractError::ParlayNotSettled);
+        }
+        self.state.parlays.insert(&parlay_id, parlay)?;
+        Ok(())
+    }
+
+    /// Whether a player has a say in how a market resolves: its creator, its designated
+    /// oracle, or for an oracle vote a staked resolver or a member of the arbitration council
+    async fn can_decide_resolution(&self, market: &Market, player_id: PlayerId) -> Result<bool, ContractError> {
+        if market.creator == player_id {
+            return Ok(true);
+        }
+        let decides = match market.resolution_method {
+            ResolutionMethod::DesignatedOracle { oracle_account, .. } => oracle_account == player_id,
+            ResolutionMethod::OracleVoting => {
+                self.state.arbitrators.contains(&player_id).await?
+                    || self
+                        .state
+                        .resolvers
+                        .get(&player_id)
+                        .await?
+                        .is_some_and(|resolver| resolver.stake > Amount::ZERO)
+            }
+            _ => false,
+        };
+        Ok(decides)
+    }
+
+    // ============================================================================
+    // Risk Controls
+    // ============================================================================
+    //
+    // Players can cap the tokens they put into markets each day and exclude themselves
+    // from trading for a while. Every path that takes tokens into a market, challenge or
+    // parlay goes through `charge_risk_limits`.
+
+    /// Set or remove the signer's daily loss limit
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Limit updated
+    /// * `Err(RiskControlsLocked)` - Raising or removing a limit set earlier the same day
+    async fn set_loss_limit(
+        &mut self,
+        player_id: PlayerId,
+        per_day: Option<Amount>,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let today = day_index(current_time);
+        let controls = &mut player.risk_controls;
+        if controls.loosens(per_day) && controls.limit_set_day >= today {
+            return Err(ContractError::RiskControlsLocked);
+        }
+        controls.daily_loss_limit = per_day;
+        controls.limit_set_day = today;
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    /// Exclude the signer from trading until `until`; an active exclusion can only be extended
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Exclusion set
+    /// * `Err(RiskControlsLocked)` - Shortening an exclusion that is still running
+    async fn self_exclude(
+        &mut self,
+        player_id: PlayerId,
+        until: Timestamp,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut player = self.get_player(&player_id).await?;
+        let controls = &mut player.risk_controls;
+        if controls.excluded_until.is_some_and(|current| current > current_time && until < current) {
+            return Err(ContractError::RiskControlsLocked);
+        }
+        controls.excluded_until = Some(until);
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    /// Check a player's self-imposed limits before they put `amount` into a market and
+    /// count it towards today's usage
+    fn charge_risk_limits(player: &mut Player, amount: Amount, current_time: Timestamp) -> Result<(), ContractError> {
+        let controls = &mut player.risk_controls;
+        if controls.excluded_until.is_some_and(|until| current_time < until) {
+            return Err(ContractError::SelfExcluded);
+        }
+        let today = day_index(current_time);
+        let spent = controls.spent_on(today).saturating_add(amount);
+        if controls.daily_loss_limit.is_some_and(|limit| spent > limit) {
+            return Err(ContractError::LossLimitExceeded);
+        }
+        controls.usage_day = today;
+        controls.spent_today = spent;
+        Ok(())
+    }
+
+    // ============================================================================
+    // Account Recovery
+    // ============================================================================
+    //
+    // A player names a recovery owner ahead of time. Once the link is older than
+    // `RECOVERY_DELAY_SECONDS`, the recovery owner can move the whole account (balance,
+    // stats, achievements, guild membership, positions and created markets) to its own
+    // player ID, merging with any account it already has.
+
+    /// Name the owner allowed to recover this account; relinking restarts the delay
+    async fn link_recovery_owner(
+        &mut self,
+        player_id: PlayerId,
+        owner: AccountOwner,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        if owner == player_id {
+            return Err(ContractError::InvalidRecoveryOwner);
+        }
+        let mut player = self.get_player(&player_id).await?;
+        player.recovery_owner = Some(owner);
+        player.recovery_linked_at = Some(current_time);
+        self.save_player(player).await?;
+        Ok(())
+    }
+
+    /// Move an account to the signer's player ID
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Account moved; the old player ID no longer exists
+    /// * `Err(NotRecoveryOwner)` - Signer is not the old account's recovery owner
+    /// * `Err(RecoveryCooldown)` - The recovery owner was linked too recently
+    async fn recover_account(
+        &mut self,
+        new_player_id: PlayerId,
+        old_player_id: PlayerId,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let old = self.get_player(&old_player_id).await?;
+        if old.recovery_owner != Some(new_player_id) {
+            return Err(ContractError::NotRecoveryOwner);
+        }
+        let linked_at = old.recovery_linked_at.unwrap_or(current_time);
+        if current_time.micros() < linked_at.micros().saturating_add(RECOVERY_DELAY_SECONDS * 1_000_000) {
+            return Err(ContractError::RecoveryCooldown);
+        }
+
+        let mut player = match self.state.players.get(&new_player_id).await? {
+            // Merge into the signer's existing account
+            Some(mut player) => {
+                player.token_balance = player.token_balance.saturating_add(old.token_balance);
+                player.total_earned = player.total_earned.saturating_add(old.total_earned);
+                player.total_spent = player.total_spent.saturating_add(old.total_spent);
+                player.total_profit = player.total_profit.saturating_add(old.total_profit);
+                player.reputation = player.reputation.saturating_add(old.reputation);
+                player.markets_participated += old.markets_participated;
+                player.markets_won += old.markets_won;
+                player.best_win_streak = player.best_win_streak.max(old.best_win_streak);
+                player.forecasts_scored += old.forecasts_scored;
+                player.brier_score_total = player.brier_score_total.saturating_add(old.brier_score_total);
+                player.trading_volume = player.trading_volume.saturating_add(old.trading_volume);
+                // Recovery must not lift a self-exclusion
+                player.risk_controls.excluded_until =
+                    player.risk_controls.excluded_until.max(old.risk_controls.excluded_until);
+                if (old.level, old.experience_points) > (player.level, player.experience_points) {
+                    player.level = old.level;
+                    player.experience_points = old.experience_points;
+                }
+                player
+            }
+            None => Player {
+                id: new_player_id,
+                achievements_earned: Vec::new(),
+                guild_id: None,
+                recovery_owner: None,
+                recovery_linked_at: None,
+                ..old.clone()
+            },
+        };
+        for achievement_id in &old.achievements_earned {
+            if !player.achievements_earned.contains(achievement_id) {
+                player.achievements_earned.push(*achievement_id);
+            }
+        }
+        let old_markets = match self.state.active_markets.try_load_entry(&old_player_id).await? {
+            Some(markets) => markets.indices().await?,
+            None => Vec::new(),
+        };
+        let mut markets = self.state.active_markets.try_load_entry_mut(&new_player_id).await?;
+        for market_id in &old_markets {
+            markets.insert(market_id)?;
+        }
+        self.state.active_markets.remove_entry(&old_player_id)?;
+        let old_history = match self.state.archived_markets.try_load_entry(&old_player_id).await? {
+            Some(history) => history.read(0..history.count()).await?,
+            None => Vec::new(),
+        };
+        let mut history = self.state.archived_markets.try_load_entry_mut(&new_player_id).await?;
+        let mut archived = self.state.archived_market_ids.try_load_entry_mut(&new_player_id).await?;
+        for market_id in old_history {
+            if !archived.contains(&market_id).await? {
+                archived.insert(&market_id)?;
+                history.push(market_id);
+            }
+        }
+        self.state.archived_markets.remove_entry(&old_player_id)?;
+        self.state.archived_market_ids.remove_entry(&old_player_id)?;
+        let old_items = match self.state.inventories.try_load_entry(&old_player_id).await? {
+            Some(items) => items.indices().await?,
+            None => Vec::new(),
+        };
+        let mut items = self.state.inventories.try_load_entry_mut(&new_player_id).await?;
+        for item_id in &old_items {
+            items.insert(item_id)?;
+        }
+        self.state.inventories.remove_entry(&old_player_id)?;
+
+        // The signer keeps its own guild; otherwise it takes the old account's seat
+        if let Some(guild_id) = old.guild_id {
+            if let Some(mut guild) = self.state.guilds.get(&guild_id).await? {
+                let was_officer = guild.officers.contains(&old_player_id);
+                guild.members.retain(|member| member != &old_player_id);
+                guild.officers.retain(|member| member != &old_player_id);
+                if player.guild_id.is_none() {
+                    player.guild_id = Some(guild_id);
+                    guild.members.push(new_player_id);
+                    if was_officer {
+                        guild.officers.push(new_player_id);
+                    }
+                    if guild.founder == old_player_id {
+                        guild.founder = new_player_id;
+                    } else {
+                        self.state.guild_recruits.try_load_entry_mut(&guild_id).await?.insert(&new_player_id)?;
+                    }
+                }
+                self.state.guilds.insert(&guild_id, guild)?;
+            }
+        }
+
+        // Positions and creator rights move with the account
+        let mut market_ids = Vec::new();
+        self.state.markets.for_each_index_value(|market_id, market| {
+            if market.creator == old_player_id || market.positions.contains_key(&old_player_id) {
+                market_ids.push(market_id);
+            }
+            Ok(())
+        }).await?;
+        for market_id in &market_ids {
+            let mut market = self.get_market(market_id).await?;
+            if market.creator == old_player_id {
+                market.creator = new_player_id;
+            }
+            market.transfer_position(&old_player_id, new_player_id);
+            self.state.markets.insert(market_id, market)?;
+        }
+
+        // Open challenges move with the account; one against the signer itself is called off
+        for challenge_id in self.open_challenges(old_player_id).await? {
+            let mut challenge = self.get_challenge(challenge_id).await?;
+            for seat in [&mut challenge.challenger, &mut challenge.opponent] {
+                if *seat == old_player_id {
+                    *seat = new_player_id;
+                }
+            }
+            if challenge.challenger == challenge.opponent {
+                let stakes = if challenge.status == ChallengeStatus::Active { 2 } else { 1 };
+                player.token_balance = player.token_balance.saturating_add(challenge.stake.saturating_mul(stakes));
+                challenge.status = ChallengeStatus::Cancelled;
+            }
+            self.state.challenges.insert(&challenge_id, challenge)?;
+        }
+
+        if let Some(old_stats) = self.state.creator_stats.get(&old_player_id).await? {
+            let mut stats = self.state.creator_stats.get(&new_player_id).await?.unwrap_or_default();
+            stats.merge(&old_stats);
+            self.state.creator_stats.insert(&new_player_id, stats)?;
+            self.state.creator_stats.remove(&old_player_id)?;
+        }
+
+        self.save_player(player).await?;
+        self.unrank(RankKey::of(&old))?;
+        self.state.players.remove(&old_player_id)?;
+        self.state.account_recoveries.insert(&old_player_id, AccountRecovery {
+            old_player_id,
+            new_player_id,
+            recovered_at: current_time,
+            tokens_moved: old.token_balance,
+            markets_moved: market_ids.len() as u32,
+        })?;
+        Ok(())
+    }
 
-        let mut voting = if let Some(v) = self.state.oracle_votes.get(&market_id).await? {
-            v
-        } else {
-            let config = self.state.config.get();
-            OracleVoting {
-                market_id,
-                voting_start: current_time,
-                voting_end: Timestamp::from(
-                    current_time.micros() + config.oracle_voting_duration_seconds * 1_000_000,
-                ),
-                votes: BTreeMap::new(),
-                voters: Vec::new(),
-                resolved: false,
-            }
-        };
+    // ============================================================================
+    // Emergency Mode
+    // ============================================================================
+    //
+    // A safety valve for critical bugs: the admin suspends every market on the chain and
+    // players pull their tokens out with `EmergencyWithdraw`.
 
-        if voting.voters.contains(&voter_id) {
-            return Err(ContractError::AlreadyVoted);
+    /// Operations that are suspended while emergency mode is active
+    fn is_market_operation(operation: &predictive_manager::Operation) -> bool {
+        use predictive_manager::Operation::*;
+        matches!(
+            operation,
+            CreateMarket { .. }
+                | ImportMarkets { .. }
+                | UpdateMarket { .. }
+                | ProposeOutcomeCorrection { .. }
+                | ApproveOutcomeCorrection { .. }
+                | FlagAmbiguousMarket { .. }
+                | ResumeMarket { .. }
+                | CommitToOutcome { .. }
+                | FinalizeBootstrap { .. }
+                | CommitBlindTrade { .. }
+                | RevealBlindTrade { .. }
+                | CloseMarketEarly { .. }
+                | ExitAtCostBasis { .. }
+                | UpdateMarketAllowlist { .. }
+                | BuyShares { .. }
+                | SellShares { .. }
+                | MintCompleteSet { .. }
+                | BurnCompleteSet { .. }
+                | StartPositionAuction { .. }
+                | FillPositionAuction { .. }
+                | CancelPositionAuction { .. }
+                | TransferShares { .. }
+                | CreateShareOffer { .. }
+                | AcceptShareOffer { .. }
+                | CancelShareOffer { .. }
+                | PostTwoSidedQuotes { .. }
+                | FillBotQuote { .. }
+                | VoteOnOutcome { .. }
+                | TriggerResolution { .. }
+                | ResolveMarket { .. }
+                | SubmitOracleResult { .. }
+                | ArbitrateMarket { .. }
+                | CreateMarketGroup { .. }
+                | ResolveMarketGroup { .. }
+                | ClaimWinnings { .. }
+                | WarnExpiringClaims { .. }
+                | SweepExpiredWinnings { .. }
+                | ArchiveMarket { .. }
+                | RateMarket { .. }
+                | CreateHeadToHead { .. }
+                | AcceptChallenge { .. }
+                | ReportChallengeOutcome { .. }
+                | CreateParlay { .. }
+                | SettleParlay { .. }
+                | GuildBuyShares { .. }
+                | ApproveGuildStake { .. }
+                | ClaimGuildWinnings { .. }
+                | SubscribeToMarket { .. }
+        )
+    }
+
+    /// Turn emergency mode on or off (admin only)
+    fn set_emergency_mode(&mut self, caller: PlayerId, enabled: bool) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        self.state.emergency_mode.set(enabled);
+        if !enabled {
+            self.state.guild_emergency_exits.clear();
         }
+        Ok(())
+    }
 
-        let vote_weight = player.reputation;
-        let weighted_votes = voting
-            .votes
-            .entry(outcome_id)
-            .or_insert(WeightedVotes { total_weight: 0, voter_count: 0 });
-        weighted_votes.total_weight += vote_weight;
-        weighted_votes.voter_count += 1;
-        voting.voters.push(voter_id);
-        self.state.oracle_votes.insert(&market_id, voting)?;
+    /// Choose which notifications the outbox records from now on (admin only)
+    fn set_notification_filter(&mut self, caller: PlayerId, filter: NotificationFilter) -> Result<(), ContractError> {
+        self.ensure_admin(caller)?;
+        self.state.notification_filter.set(filter);
         Ok(())
     }
 
-    /// Trigger the resolution of a market
-    /// Resolves a market after it has ended, determining the winning outcome
-    /// 
-    /// # Arguments
-    /// * `market_id` - The market to resolve
-    /// * `current_time` - Current timestamp for resolution timing
+    /// Close all of a player's positions while emergency mode is active
+    /// Positions in unresolved markets are refunded at cost basis; positions in resolved
+    /// markets are paid their winnings; open challenges are called off and both stakes refunded
+    /// A guild member is also paid, once per emergency, an equal share of their guild's shared
+    /// pool and token pool among the members not yet paid, after the guild's own positions are
+    /// closed into the shared pool the same way
     /// 
     /// # Returns
-    /// * `Ok(())` - Market resolved successfully
-    /// * `Err(MarketNotEnded)` - Market hasn't ended yet
-    async fn trigger_market_resolution(
-        &mut self,
-        market_id: MarketId,
-        current_time: Timestamp,
-    ) -> Result<(), ContractError> {
-        let mut market = self.get_market(&market_id).await?;
-        if current_time < market.end_time {
-            return Err(ContractError::MarketNotEnded);
-        }
-        if market.status == MarketStatus::Active {
-            market.status = MarketStatus::Closed;
-            self.state.markets.insert(&market_id, market.clone())?;
+    /// * `Ok(())` - Positions closed and tokens credited
+    /// * `Err(EmergencyModeInactive)` - Emergency mode is not active
+    /// * `Err(NoPosition)` - Player has no positions left
+    async fn emergency_withdraw(&mut self, player_id: PlayerId) -> Result<(), ContractError> {
+        if !*self.state.emergency_mode.get() {
+            return Err(ContractError::EmergencyModeInactive);
         }
+        let mut player = self.get_player(&player_id).await?;
 
-        let winning_outcome = match market.resolution_method {
-            ResolutionMethod::OracleVoting => self.resolve_by_oracle_vote(market_id).await?,
-            ResolutionMethod::Automated => self.resolve_automated(market_id).await?,
-            ResolutionMethod::CreatorDecides => {
-                // Creator must set externally; noop
-                return Ok(())
+        // Only markets the player is still involved in can hold anything of theirs
+        let candidates = match self.state.active_markets.try_load_entry(&player_id).await? {
+            Some(markets) => markets.indices().await?,
+            None => Vec::new(),
+        };
+        let mut markets = Vec::new();
+        for market_id in candidates {
+            // Archived markets have nothing left to withdraw
+            let Some(market) = self.state.markets.get(&market_id).await? else {
+                continue;
+            };
+            let committed = market.status == MarketStatus::Bootstrapping
+                && market.bootstrap_commitments.iter().any(|commitment| commitment.player == player_id);
+            let sealed = market.status != MarketStatus::Resolved
+                && market.blind_commitments.iter().any(|sealed| sealed.player == player_id && !sealed.revealed);
+            let auctioning = market.auctions.iter().any(|auction| auction.open && auction.seller == player_id)
+                || market.share_offers.iter().any(|offer| offer.open && offer.seller == player_id)
+                || market.bot_quotes.iter().any(|quote| quote.open && quote.maker == player_id);
+            if market.positions.contains_key(&player_id) || committed || sealed || auctioning {
+                markets.push(market);
             }
+        }
+        let challenge_ids = self.open_challenges(player_id).await?;
+        let guild_share = match player.guild_id {
+            Some(guild_id) => self.emergency_guild_share(guild_id, player_id).await?,
+            None => None,
         };
+        if markets.is_empty() && challenge_ids.is_empty() && guild_share.is_none() {
+            return Err(ContractError::NoPosition);
+        }
 
-        market.winning_outcome = Some(winning_outcome);
-        market.status = MarketStatus::Resolved;
-        market.resolution_time = Some(current_time);
-        self.state.markets.insert(&market_id, market.clone())?;
+        let mut refunded = Amount::ZERO;
+        let mut winnings = Amount::ZERO;
+        for mut market in markets {
+            let market_id = market.id;
+            if market.status != MarketStatus::Resolved {
+                market.return_escrowed_shares(Some(player_id));
+                let closed = market.close_bot_quotes(|quote| quote.maker == player_id);
+                for (_, bid_escrow) in &closed {
+                    refunded = refunded.saturating_add(*bid_escrow);
+                }
+                self.release_bot_quotes(&closed).await?;
+                market.blind_commitments.retain(|sealed| {
+                    let refund = sealed.player == player_id && !sealed.revealed;
+                    if refund {
+                        refunded = refunded.saturating_add(sealed.amount);
+                    }
+                    !refund
+                });
+            }
+            if market.status == MarketStatus::Bootstrapping {
+                market.bootstrap_commitments.retain(|commitment| {
+                    if commitment.player == player_id {
+                        refunded = refunded.saturating_add(commitment.amount);
+                    }
+                    commitment.player != player_id
+                });
+            } else if market.status == MarketStatus::Resolved {
+                if let Some(position) = market.positions.remove(&player_id).filter(|position| !position.settled) {
+                    winnings = winnings.saturating_add(market.payout_for(&position));
+                }
+            } else if let Some(position) = market.remove_position(&player_id) {
+                market.total_liquidity = market.total_liquidity.saturating_sub(position.total_invested);
+                refunded = refunded.saturating_add(position.total_invested);
+            }
+            self.state.markets.insert(&market_id, market)?;
+            self.archive_player_market(player_id, market_id).await?;
+        }
+        // Open challenges are called off, returning the other player's stake too; a pending
+        // challenge only holds the challenger's
+        for challenge_id in challenge_ids {
+            let mut challenge = self.get_challenge(challenge_id).await?;
+            let active = challenge.status == ChallengeStatus::Active;
+            let other = if challenge.challenger == player_id { challenge.opponent } else { challenge.challenger };
+            if active || challenge.challenger == player_id {
+                refunded = refunded.saturating_add(challenge.stake);
+            }
+            if active || challenge.challenger == other {
+                self.credit_player(&other, challenge.stake).await?;
+            }
+            challenge.status = ChallengeStatus::Cancelled;
+            self.state.challenges.insert(&challenge_id, challenge)?;
+        }
+        if let Some((shared, pooled_tokens)) = guild_share {
+            refunded = refunded.saturating_add(shared);
+            if let Some((token, amount)) = pooled_tokens {
+                let owner = self.application_account();
+                let target_account = Account { chain_id: self.runtime.chain_id(), owner: player_id };
+                self.call_token_application(token, FungibleOperation::Transfer { owner, amount, target_account });
+            }
+        }
 
-        self
-            .runtime
-            .prepare_message(Message::MarketResolved { market_id, winning_outcome })
-            .send_to(self.runtime.chain_id());
+        player.token_balance = player
+            .token_balance
+            .saturating_add(refunded)
+            .saturating_add(winnings);
+        player.total_earned = player.total_earned.saturating_add(winnings);
+        self.save_player(player).await?;
         Ok(())
     }
 
-    /// Claim winnings from a resolved market
-    /// Allows players to claim their tokens from winning bets
-    /// 
-    /// # Arguments
-    /// * `player_id` - The player claiming winnings
-    /// * `market_id` - The market to claim winnings from
-    /// 
-    /// # Returns
-    /// * `Ok(())` - Winnings claimed successfully
-    /// * `Err(NotResolved)` - Market hasn't been resolved yet
-    /// * `Err(NoWinnings)` - Player has no winning shares in this market
-    async fn claim_winnings(&mut self, player_id: PlayerId, market_id: MarketId) -> Result<(), ContractError> {
-        let market = self.get_market(&market_id).await?;
-        if market.status != MarketStatus::Resolved {
-            return Err(ContractError::NotResolved);
+    /// Close a guild's market positions into its shared pool, then take a member's equal share
+    /// of the shared pool and token pool
+    ///
+    /// Returns the shared pool tokens and, if the guild pools tokens of a token application,
+    /// the application and the amount to transfer; `None` if the member was already paid in
+    /// this emergency or both shares are zero
+    async fn emergency_guild_share(
+        &mut self,
+        guild_id: GuildId,
+        player_id: PlayerId,
+    ) -> Result<Option<(Amount, Option<(ApplicationId, Amount)>)>, ContractError> {
+        let mut exits = self.state.guild_emergency_exits.try_load_entry_mut(&guild_id).await?;
+        if exits.contains(&player_id).await? {
+            return Ok(None);
         }
-        let winning = market.winning_outcome.ok_or(ContractError::NotResolved)?;
-        let position = market.positions.get(&player_id).ok_or(ContractError::NoPosition)?;
-        let shares = position
-            .shares_by_outcome
-            .get(&winning)
-            .copied()
-            .unwrap_or(Amount::ZERO);
-        if shares == Amount::ZERO {
-            return Err(ContractError::NoWinnings);
+        let Some(mut guild) = self.state.guilds.get(&guild_id).await? else {
+            return Ok(None);
+        };
+        let remaining = guild.members.len().saturating_sub(exits.count().await?).max(1) as u128;
+        exits.insert(&player_id)?;
+        drop(exits);
+
+        let mut market_ids = Vec::new();
+        self.state.markets.for_each_index_value(|market_id, market| {
+            if market.guild_positions.contains_key(&guild_id) {
+                market_ids.push(market_id);
+            }
+            Ok(())
+        }).await?;
+        for market_id in market_ids {
+            let mut market = self.get_market(&market_id).await?;
+            if market.status == MarketStatus::Resolved {
+                if let Some(position) = market.guild_positions.remove(&guild_id).filter(|position| !position.claimed) {
+                    guild.shared_pool = guild.shared_pool.saturating_add(market.guild_payout_for(&position));
+                }
+            } else if let Some(position) = market.remove_guild_position(guild_id) {
+                market.total_liquidity = market.total_liquidity.saturating_sub(position.total_invested);
+                guild.shared_pool = guild.shared_pool.saturating_add(position.total_invested);
+            }
+            self.state.markets.insert(&market_id, market)?;
+        }
+
+        let shared = Amount::from_attos(u128::from(guild.shared_pool) / remaining);
+        let pooled = Amount::from_attos(u128::from(guild.token_pool) / remaining);
+        guild.shared_pool = guild.shared_pool.saturating_sub(shared);
+        guild.token_pool = guild.token_pool.saturating_sub(pooled);
+        let pooled_tokens = guild.token_application.filter(|_| pooled > Amount::ZERO).map(|token| (token, pooled));
+        self.state.guilds.insert(&guild_id, guild)?;
+        if shared == Amount::ZERO && pooled_tokens.is_none() {
+            return Ok(None);
+        }
+        Ok(Some((shared, pooled_tokens)))
+    }
+
+    /// Store a new config and send it to every market chain
+    async fn apply_game_config(&mut self, config: GameConfig) -> Result<(), ContractError> {
+        self.state.config.set(config.clone());
+
+        // Keep market chains on the same config
+        let mut chains = Vec::new();
+        self.state.network_chains.for_each_index(|chain_id| {
+            chains.push(chain_id);
+            Ok(())
+        }).await?;
+        for chain_id in chains {
+            self.runtime
+                .prepare_message(Message::SyncConfig { config: Box::new(config.clone()) })
+                .send_to(chain_id);
         }
-        let mut player = self.get_player(&player_id).await?;
-        
-        // Add winnings to player's points (no external transfer needed)
-        
-        // simplistic: payout equals shares (1:1)
-        player.token_balance = player.token_balance.saturating_add(shares);
-        player.total_earned = player.total_earned.saturating_add(shares);
-        self.state.players.insert(&player_id, player)?;
         Ok(())
     }
 
-    /// Create a new guild
-    /// Allows players to form social groups for collaborative gameplay
-    /// 
-    /// # Arguments
-    /// * `founder` - The player creating the guild
-    /// * `name` - The name of the guild
-    /// * `current_time` - Current timestamp for guild creation
+    // ============================================================================
+    // System Announcements
+    // ============================================================================
+    //
+    // The admin posts a single game-wide notice on the home chain, which copies it to every
+    // market chain so all frontends show the same thing. Expired announcements stay stored
+    // until replaced or cleared; the service stops returning them.
+
+    /// Post an announcement, replacing the current one (admin only, on the home chain)
     /// 
     /// # Returns
-    /// * `Ok(())` - Guild created successfully
-    /// * `Err(AlreadyInGuild)` - Founder is already in a guild
-    async fn create_guild(
+    /// * `Ok(())` - Announcement stored and sent to market chains
+    /// * `Err(NotHomeChain)` - Called on a market chain
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    /// * `Err(InvalidAnnouncement)` - Empty or longer than `MAX_ANNOUNCEMENT_LENGTH`, or
+    ///   already expired
+    async fn set_announcement(
         &mut self,
-        founder: PlayerId,
-        name: String,
+        caller: PlayerId,
+        text: String,
+        severity: AnnouncementSeverity,
+        expires_at: Option<Timestamp>,
         current_time: Timestamp,
     ) -> Result<(), ContractError> {
-        let mut player = self.get_player(&founder).await?;
-        if player.guild_id.is_some() {
-            return Err(ContractError::AlreadyInGuild);
+        if !self.is_home_chain() {
+            return Err(ContractError::NotHomeChain);
         }
-        let new_id = self.next_guild_id().await?;
-        let guild = Guild {
-            id: new_id,
-            name: name.clone(),
-            founder,
-            members: vec![founder],
-            creation_time: current_time,
-            total_guild_profit: Amount::ZERO,
-            guild_level: 1,
-            shared_pool: Amount::ZERO,
-        };
-        self.state.guilds.insert(&new_id, guild)?;
-        player.guild_id = Some(new_id);
-        self.state.players.insert(&founder, player)?;
-
-        self
-            .runtime
-            .prepare_message(Message::GuildCreated { guild_id: new_id, name })
-            .send_to(self.runtime.chain_id());
-        Ok(())
+        self.ensure_admin(caller)?;
+        let text = text.trim().to_string();
+        if text.is_empty()
+            || text.chars().count() > MAX_ANNOUNCEMENT_LENGTH
+            || expires_at.is_some_and(|expires_at| expires_at <= current_time)
+        {
+            return Err(ContractError::InvalidAnnouncement);
+        }
+        self.publish_announcement(Some(SystemAnnouncement {
+            text,
+            severity,
+            posted_at: current_time,
+            expires_at,
+        }))
+        .await
     }
 
-    /// Join an existing guild
-    /// Allows players to join guilds created by other players
-    /// 
-    /// # Arguments
-    /// * `player_id` - The player joining the guild
-    /// * `guild_id` - The guild to join
+    /// Remove the current announcement (admin only, on the home chain)
     /// 
     /// # Returns
-    /// * `Ok(())` - Successfully joined guild
-    /// * `Err(AlreadyInGuild)` - Player is already in a guild
-    /// * `Err(GuildNotFound)` - Guild doesn't exist
-    async fn join_guild(&mut self, player_id: PlayerId, guild_id: GuildId) -> Result<(), ContractError> {
-        let mut player = self.get_player(&player_id).await?;
-        if player.guild_id.is_some() {
-            return Err(ContractError::AlreadyInGuild);
+    /// * `Ok(())` - Announcement removed here and on market chains
+    /// * `Err(NotHomeChain)` - Called on a market chain
+    /// * `Err(NotAdmin)` - Caller is not the admin
+    async fn clear_announcement(&mut self, caller: PlayerId) -> Result<(), ContractError> {
+        if !self.is_home_chain() {
+            return Err(ContractError::NotHomeChain);
+        }
+        self.ensure_admin(caller)?;
+        self.publish_announcement(None).await
+    }
+
+    /// Store the announcement and send it to every market chain
+    async fn publish_announcement(&mut self, announcement: Option<SystemAnnouncement>) -> Result<(), ContractError> {
+        self.state.announcement.set(announcement.clone());
+        let mut chains = Vec::new();
+        self.state.network_chains.for_each_index(|chain_id| {
+            chains.push(chain_id);
+            Ok(())
+        }).await?;
+        for chain_id in chains {
+            self.runtime
+                .prepare_message(Message::SyncAnnouncement { announcement: announcement.clone() })
+                .send_to(chain_id);
         }
-        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
-        guild.members.push(player_id);
-        self.state.guilds.insert(&guild_id, guild)?;
-        player.guild_id = Some(guild_id);
-        self.state.players.insert(&player_id, player)?;
         Ok(())
     }
 
-    /// Leave the current guild
-    /// Allows players to leave their current guild
-    /// 
-    /// # Arguments
-    /// * `player_id` - The player leaving the guild
+    // ============================================================================
+    // Multi-chain Network
+    // ============================================================================
+    //
+    // The chain that created the application is the home chain: it owns the config, the
+    // global leaderboard and achievements. Markets can live on any other chain running the
+    // application; those chains join the network to receive the config and report player
+    // stat changes back to the home chain.
+
+    /// Whether this chain is the application's home chain
+    fn is_home_chain(&mut self) -> bool {
+        self.runtime.chain_id() == self.runtime.application_creator_chain_id()
+    }
+
+    /// Ask the home chain to register this market chain
     /// 
     /// # Returns
-    /// * `Ok(())` - Successfully left guild
-    /// * `Err(NotGuildMember)` - Player is not in a guild
-    async fn leave_guild(&mut self, player_id: PlayerId) -> Result<(), ContractError> {
-        let mut player = self.get_player(&player_id).await?;
-        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
-        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
-        guild.members.retain(|m| m != &player_id);
-        self.state.guilds.insert(&guild_id, guild)?;
-        player.guild_id = None;
-        self.state.players.insert(&player_id, player)?;
+    /// * `Ok(())` - Registration request sent
+    /// * `Err(AlreadyHomeChain)` - Called on the home chain itself
+    fn join_network(&mut self) -> Result<(), ContractError> {
+        if self.is_home_chain() {
+            return Err(ContractError::AlreadyHomeChain);
+        }
+        let home_chain = self.runtime.application_creator_chain_id();
+        self.runtime
+            .prepare_message(Message::RegisterChain)
+            .with_tracking()
+            .send_to(home_chain);
         Ok(())
     }
 
-    /// Contribute tokens to the guild's shared pool
-    /// Allows guild members to contribute tokens to the guild's collective fund
-    /// 
-    /// # Arguments
-    /// * `player_id` - The player contributing tokens
-    /// * `amount` - How many tokens to contribute
-    /// 
-    /// # Returns
-    /// * `Ok(())` - Contribution successful
-    /// * `Err(NotGuildMember)` - Player is not in a guild
-    /// * `Err(InsufficientBalance)` - Player doesn't have enough tokens
-    async fn contribute_to_guild(&mut self, player_id: PlayerId, amount: Amount) -> Result<(), ContractError> {
-        let mut player = self.get_player(&player_id).await?;
-        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
-        if player.token_balance < amount { return Err(ContractError::InsufficientBalance); }
-        
-        // Deduct contribution from player's points (no external transfer needed)
-        
-        let mut guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
-        player.token_balance = player.token_balance.saturating_sub(amount);
-        guild.shared_pool = guild.shared_pool.saturating_add(amount);
-        self.state.players.insert(&player_id, player)?;
-        self.state.guilds.insert(&guild_id, guild)?;
+    /// Record a market chain on the home chain and send it the current config
+    async fn register_network_chain(&mut self) -> Result<(), ContractError> {
+        if !self.is_home_chain() {
+            return Err(ContractError::NotHomeChain);
+        }
+        let chain_id = self.runtime.message_origin_chain_id().ok_or(ContractError::Unauthorized)?;
+        let current_time = self.runtime.system_time();
+        self.state.network_chains.insert(&chain_id, current_time)?;
+        let config = self.state.config.get().clone();
+        self.runtime
+            .prepare_message(Message::SyncConfig { config: Box::new(config) })
+            .send_to(chain_id);
+        let announcement = self.state.announcement.get().clone();
+        if announcement.is_some() {
+            self.runtime
+                .prepare_message(Message::SyncAnnouncement { announcement })
+                .send_to(chain_id);
+        }
         Ok(())
     }
 
-    /// Update the game configuration (Admin only)
-    /// Allows the admin to modify game parameters like token amounts and market settings
-    /// 
-    /// # Arguments
-    /// * `caller` - The player attempting to update config
-    /// * `config` - The new game configuration
-    /// 
-    /// # Returns
-    /// * `Ok(())` - Configuration updated successfully
-    /// * `Err(NotAdmin)` - Caller is not the admin
-    async fn update_game_config(&mut self, caller: PlayerId, config: GameConfig) -> Result<(), ContractError> {
-        let current = self.state.config.get();
-        if let Some(admin) = current.admin {
-            if caller != admin { return Err(ContractError::NotAdmin); }
-        } else {
-            return Err(ContractError::NotAdmin);
+    /// Adopt the config sent by the home chain
+    fn sync_config(&mut self, config: GameConfig) -> Result<(), ContractError> {
+        let home_chain = self.runtime.application_creator_chain_id();
+        if self.runtime.message_origin_chain_id() != Some(home_chain) || self.is_home_chain() {
+            return Err(ContractError::Unauthorized);
         }
         self.state.config.set(config);
         Ok(())
     }
 
+    /// Adopt the announcement sent by the home chain
+    fn sync_announcement(&mut self, announcement: Option<SystemAnnouncement>) -> Result<(), ContractError> {
+        let home_chain = self.runtime.application_creator_chain_id();
+        if self.runtime.message_origin_chain_id() != Some(home_chain) || self.is_home_chain() {
+            return Err(ContractError::Unauthorized);
+        }
+        self.state.announcement.set(announcement);
+        Ok(())
+    }
+
+    /// Send a player's stat changes to the home chain (no-op on the home chain itself)
+    fn report_player_stats(&mut self, player_id: PlayerId, delta: PlayerStatsDelta) {
+        if self.is_home_chain() {
+            return;
+        }
+        let home_chain = self.runtime.application_creator_chain_id();
+        self.runtime
+            .prepare_message(Message::PlayerStatsUpdated { player_id, delta })
+            .with_tracking()
+            .send_to(home_chain);
+    }
+
+    /// Apply stat changes reported by a market chain to the home chain's player record
+    /// Players first seen this way get a stats-only record with no token balance; reports
+    /// from chains that haven't joined the network are ignored
+    async fn apply_player_stats(&mut self, player_id: PlayerId, delta: PlayerStatsDelta) -> Result<(), ContractError> {
+        if !self.is_home_chain() {
+            return Err(ContractError::NotHomeChain);
+        }
+        let origin = self.runtime.message_origin_chain_id().ok_or(ContractError::Unauthorized)?;
+        if !self.state.network_chains.contains_key(&origin).await? {
+            return Err(ContractError::Unauthorized);
+        }
+        let current_time = self.runtime.system_time();
+        let mut player = match self.state.players.get(&player_id).await? {
+            Some(player) => player,
+            None => Player {
+                id: player_id,
+                display_name: None,
+                registration_time: current_time,
+                last_login: current_time,
+                token_balance: Amount::ZERO,
+                total_earned: Amount::ZERO,
+                total_spent: Amount::ZERO,
+                level: 1,
+                experience_points: 0,
+                reputation: 100,
+                markets_participated: 0,
+                markets_won: 0,
+                total_profit: Amount::ZERO,
+                win_streak: 0,
+                best_win_streak: 0,
+                guild_id: None,
+                achievements_earned: Vec::new(),
+                recovery_owner: None,
+                recovery_linked_at: None,
+                risk_controls: RiskControls::default(),
+                equipped_items: EquippedItems::default(),
+                forecasts_scored: 0,
+                brier_score_total: 0,
+                trading_volume: Amount::ZERO,
+                privacy: PrivacySettings::default(),
+            },
+        };
+        if delta.display_name.is_some() {
+            player.display_name = delta.display_name;
+        }
+        player.level = player.level.max(delta.level);
+        player.total_earned = player.total_earned.saturating_add(delta.earned);
+        player.total_spent = player.total_spent.saturating_add(delta.spent);
+        player.markets_participated += delta.markets_participated;
+        player.markets_won += delta.markets_won;
+        player.trading_volume = player.trading_volume.saturating_add(delta.volume);
+        self.save_player(player.clone()).await?;
+
+        self.check_achievements(&mut player).await?;
+        Ok(())
+    }
+
 // ============================================================================
 // Single-File Prediction Market Game
 // ============================================================================
@@ -964,56 +7670,196 @@ impl PredictionMarketContract {
     // Enhanced Leaderboard System
     // ============================================================================
     
+    /// Rebuild the leaderboards and boards, paying the caller the keeper reward
+    /// Operations only keep the rank index current, so anyone may do this once per refresh
+    /// interval
+    /// 
+    /// # Returns
+    /// * `Ok(())` - Leaderboards rebuilt and the reward paid
+    /// * `Err(PlayerNotFound)` - Caller isn't registered
+    /// * `Err(LeaderboardFresh)` - The last refresh was less than an interval ago
+    async fn refresh_leaderboard(&mut self, keeper: PlayerId, current_time: Timestamp) -> Result<(), ContractError> {
+        let mut player = self.get_player(&keeper).await?;
+        let config = self.state.config.get();
+        let reward = config.leaderboard_refresh_reward;
+        let due_at = self
+            .state
+            .leaderboard
+            .get()
+            .last_updated
+            .saturating_add(TimeDelta::from_secs(config.leaderboard_refresh_interval_seconds));
+        if current_time < due_at {
+            return Err(ContractError::LeaderboardFresh);
+        }
+
+        player.token_balance = player.token_balance.saturating_add(reward);
+        player.total_earned = player.total_earned.saturating_add(reward);
+        self.save_player(player).await?;
+        let total_supply = self.state.total_supply.get().saturating_add(reward);
+        self.state.total_supply.set(total_supply);
+        self.record_token_flow(TokenFlow::KeeperReward, reward).await?;
+        self.update_enhanced_leaderboard().await;
+        Ok(())
+    }
+
     /// Update the enhanced leaderboard with sophisticated ranking algorithms
     async fn update_enhanced_leaderboard(&mut self) {
         let mut top_traders = Vec::new();
         let mut top_guilds = Vec::new();
         
-        // Collect all players and calculate enhanced scores
-        let mut player_scores = Vec::new();
+        // Collect all players, leaving out those who opted out of the leaderboards
+        let mut players = BTreeMap::new();
+        let mut hidden_players = Vec::new();
         self.state.players.for_each_index_value(|player_id, player| {
-            let player = player.into_owned();
-            // Use enhanced scoring (simplified to avoid self capture)
-            let score: f64 = 100.0; // Simplified scoring for now
-            player_scores.push((player_id, player, score));
+            if player.privacy.hide_from_leaderboard {
+                hidden_players.push(player_id);
+            } else {
+                players.insert(player_id, player.into_owned());
+            }
+            Ok(())
+        }).await.expect("Failed to iterate players");
+
+        // The rank index already holds them in order of profit, then level and experience
+        let mut player_scores = Vec::new();
+        self.state.rank_index.for_each_index(|key| {
+            if let Some(player) = players.remove(&key.player_id) {
+                player_scores.push((key.player_id, player));
+            }
             Ok(())
-        }).await.expect("Failed to iterate players");
-        
-        // Sort by enhanced score (profit + win_rate + level + reputation)
-        player_scores.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
-        
+        }).await.expect("Failed to iterate the rank index");
+        let total_ranked_players = player_scores.len() as u32;
+
+        // Rank forecasters by accuracy, then by how many forecasts back it
+        let min_forecasts = self.state.config.get().min_forecasts_for_accuracy_rank;
+        let mut forecasters: Vec<(PlayerId, &Player, u32)> = player_scores
+            .iter()
+            .filter(|(_, player)| player.forecasts_scored >= min_forecasts)
+            .filter_map(|(player_id, player)| Some((*player_id, player, player.accuracy()?)))
+            .collect();
+        forecasters.sort_by(|(a_id, a, a_accuracy), (b_id, b, b_accuracy)| {
+            b_accuracy
+                .cmp(a_accuracy)
+                .then(b.forecasts_scored.cmp(&a.forecasts_scored))
+                .then(a_id.cmp(b_id))
+        });
+        let top_forecasters: Vec<AccuracyLeaderboardEntry> = forecasters
+            .into_iter()
+            .take(50)
+            .map(|(player_id, player, accuracy)| AccuracyLeaderboardEntry {
+                player_id,
+                display_name: player.public_name(),
+                accuracy,
+                forecasts_scored: player.forecasts_scored,
+            })
+            .collect();
+
+        // Each category keeps its own board, ranked on that stat alone
+        let profit_board: Vec<BoardEntry> = player_scores
+            .iter()
+            .map(|(player_id, player)| BoardEntry {
+                player_id: *player_id,
+                display_name: player.public_name(),
+                amount: player.total_profit,
+                score: 0,
+            })
+            .collect();
+        let accuracy_board: Vec<BoardEntry> = top_forecasters
+            .iter()
+            .map(|entry| BoardEntry {
+                player_id: entry.player_id,
+                display_name: entry.display_name.clone(),
+                amount: Amount::ZERO,
+                score: u64::from(entry.accuracy),
+            })
+            .collect();
+        let mut volume_board: Vec<BoardEntry> = player_scores
+            .iter()
+            .filter(|(_, player)| player.trading_volume > Amount::ZERO)
+            .map(|(player_id, player)| BoardEntry {
+                player_id: *player_id,
+                display_name: player.public_name(),
+                amount: player.trading_volume,
+                score: 0,
+            })
+            .collect();
+        let mut streak_board: Vec<BoardEntry> = player_scores
+            .iter()
+            .filter(|(_, player)| player.best_win_streak > 0)
+            .map(|(player_id, player)| BoardEntry {
+                player_id: *player_id,
+                display_name: player.public_name(),
+                amount: Amount::ZERO,
+                score: u64::from(player.best_win_streak),
+            })
+            .collect();
+        let mut creator_board = Vec::new();
+        self.state.creator_stats.for_each_index_value(|creator, stats| {
+            let earned = stats.trading_fees_earned.saturating_add(stats.creation_fees_earned);
+            if earned > Amount::ZERO {
+                creator_board.push(BoardEntry {
+                    player_id: creator,
+                    display_name: None,
+                    amount: earned,
+                    score: 0,
+                });
+            }
+            Ok(())
+        }).await.expect("Failed to iterate creator stats");
+        creator_board.retain(|entry| !hidden_players.contains(&entry.player_id));
+        for entry in &mut creator_board {
+            entry.display_name = player_scores
+                .iter()
+                .find(|(player_id, _)| *player_id == entry.player_id)
+                .and_then(|(_, player)| player.public_name());
+        }
+        for board in [&mut volume_board, &mut streak_board, &mut creator_board] {
+            board.sort_by(|a, b| {
+                b.amount
+                    .cmp(&a.amount)
+                    .then(b.score.cmp(&a.score))
+                    .then(a.player_id.cmp(&b.player_id))
+            });
+        }
+        for (category, board) in [
+            (LeaderboardCategory::Profit, profit_board),
+            (LeaderboardCategory::Volume, volume_board),
+            (LeaderboardCategory::Accuracy, accuracy_board),
+            (LeaderboardCategory::BestStreak, streak_board),
+            (LeaderboardCategory::CreatorEarnings, creator_board),
+        ] {
+            self.store_board(category, board).await;
+        }
+
         // Take top 50 traders
-        for (player_id, player, _score) in player_scores.into_iter().take(50) {
-            let win_rate = if player.markets_participated > 0 {
-                (player.markets_won as f64 / player.markets_participated as f64) * 100.0
-            } else {
-                0.0
-            };
-            
+        for (player_id, player) in player_scores.into_iter().take(50) {
+            let win_rate = math::win_rate(player.markets_won, player.markets_participated);
+
             top_traders.push(LeaderboardEntry {
                 player_id,
-                display_name: player.display_name,
+                display_name: player.public_name(),
                 total_profit: player.total_profit,
                 win_rate,
                 level: player.level,
             });
         }
         
-        // Collect all guilds and calculate enhanced scores
+        // Collect all guilds
         let mut guild_scores = Vec::new();
         self.state.guilds.for_each_index_value(|guild_id, guild| {
-            let guild = guild.into_owned();
-            // Simplified scoring to avoid self capture
-            let score: f64 = 100.0; // Simplified scoring
-            guild_scores.push((guild_id, guild, score));
+            guild_scores.push((guild_id, guild.into_owned()));
             Ok(())
         }).await.expect("Failed to iterate guilds");
         
-        // Sort by enhanced score (profit + member_count + level)
-        guild_scores.sort_by(|a, b| b.2.partial_cmp(&a.2).unwrap_or(std::cmp::Ordering::Equal));
+        // Sort by profit, then member count, then id so ties order the same on every validator
+        guild_scores.sort_by(|(a_id, a), (b_id, b)| {
+            b.total_guild_profit
+                .cmp(&a.total_guild_profit)
+                .then(b.members.len().cmp(&a.members.len()))
+                .then(a_id.cmp(b_id))
+        });
         
         // Take top 20 guilds
-        for (guild_id, guild, _score) in guild_scores.into_iter().take(20) {
+        for (guild_id, guild) in guild_scores.into_iter().take(20) {
             top_guilds.push(GuildLeaderboardEntry {
                 guild_id,
                 name: guild.name,
@@ -1026,9 +7872,20 @@ impl PredictionMarketContract {
         let mut leaderboard = self.state.leaderboard.get().clone();
         leaderboard.top_traders = top_traders;
         leaderboard.top_guilds = top_guilds;
+        leaderboard.top_forecasters = top_forecasters;
         leaderboard.last_updated = self.runtime.system_time();
+        leaderboard.total_ranked_players = total_ranked_players;
         self.state.leaderboard.set(leaderboard);
     }
+
+    /// Keep the top 50 entries of one leaderboard category, writing the board only when it changed
+    async fn store_board(&mut self, category: LeaderboardCategory, mut board: Vec<BoardEntry>) {
+        board.truncate(50);
+        let previous = self.state.leaderboard_boards.get(&category).await.expect("Failed to read board");
+        if previous.as_ref() != Some(&board) {
+            self.state.leaderboard_boards.insert(&category, board).expect("Failed to write board");
+        }
+    }
     
 
     // ============================================================================
@@ -1041,61 +7898,254 @@ impl PredictionMarketContract {
         creator: PlayerId, 
         total_fee: Amount
     ) -> Result<(), ContractError> {
-        // Simplified fee distribution: give creator a small portion back
-        let creator_fee_amount = total_fee.saturating_mul(2).saturating_div(Amount::from_tokens(100));
-        let platform_fee_amount = total_fee.saturating_mul(1).saturating_div(Amount::from_tokens(100));
+        // Give creator and platform their configured percentages of the creation cost
+        let config = self.state.config.get();
+        let split = math::creation_fee(
+            u128::from(total_fee),
+            config.market_creator_fee_percent,
+            config.platform_fee_percent,
+        );
+        let creator_fee_amount = Amount::from_attos(split.creator);
+        let platform_fee_amount = Amount::from_attos(split.platform);
         
         // Give creator their fee (add to their balance)
-        if creator_fee_amount > Amount::ZERO.into() {
+        if creator_fee_amount > Amount::ZERO {
             let mut creator_player = self.get_player(&creator).await?;
-            creator_player.token_balance = creator_player.token_balance.saturating_add(Amount::from_tokens(creator_fee_amount));
-            creator_player.total_earned = creator_player.total_earned.saturating_add(Amount::from_tokens(creator_fee_amount));
-            self.state.players.insert(&creator, creator_player)?;
+            creator_player.token_balance = creator_player.token_balance.saturating_add(creator_fee_amount);
+            creator_player.total_earned = creator_player.total_earned.saturating_add(creator_fee_amount);
+            self.save_player(creator_player).await?;
+
+            let mut stats = self.state.creator_stats.get(&creator).await?.unwrap_or_default();
+            stats.creation_fees_earned = stats.creation_fees_earned.saturating_add(creator_fee_amount);
+            self.state.creator_stats.insert(&creator, stats)?;
         }
         
         // Platform fee goes to total supply (can be used for rewards, etc.)
-        if platform_fee_amount > Amount::ZERO.into() {
+        if platform_fee_amount > Amount::ZERO {
             let current_supply = self.state.total_supply.get();
-            self.state.total_supply.set(current_supply.saturating_add(Amount::from_tokens(platform_fee_amount)));
+            self.state.total_supply.set(current_supply.saturating_add(platform_fee_amount));
         }
-        
-        // Update leaderboard after fee distribution
-        self.update_enhanced_leaderboard().await;
-        
-        Ok(())
+        self.record_token_flow(TokenFlow::FeeReward, Amount::from_attos(split.distributed())).await
     }
     
+    /// Fee rate for a trade placed at `trade_time` that moves an outcome's price from
+    /// `price_before` to `price_after`, following the configured fee schedule and discounted by
+    /// any special event running at that time
+    fn trading_fee_bps(
+        &self,
+        market: &Market,
+        price_before: PricePerShare,
+        price_after: PricePerShare,
+        trade_time: Timestamp,
+    ) -> u32 {
+        let impact_bps = math::price_impact_bps(price_before.attos(), price_after.attos());
+        let seconds_left = market.end_time.micros().saturating_sub(trade_time.micros()) / 1_000_000;
+        let fee_bps = self.state.config.get().fee_schedule.fee_bps(impact_bps, seconds_left);
+        self.event_boost(market, trade_time).fee_bps(fee_bps)
+    }
+
+    /// Price a buy of `gross` tokens of an outcome placed at `trade_time`, with the trading fee
+    /// taken out of them and the rest buying shares
+    /// The fee rate follows the price impact of spending the whole amount, as quoted
+    fn price_buy(
+        &self,
+        market: &Market,
+        outcome_id: OutcomeId,
+        gross: Tokens,
+        trade_time: Timestamp,
+    ) -> Result<(TradeAmounts, Shares), ContractError> {
+        let outcome = market
+            .outcomes
+            .get(outcome_id as usize)
+            .ok_or(ContractError::InvalidOutcome)?;
+        let curve = market.bonding_curve();
+        let supply = u128::from(outcome.total_shares);
+        let price_before = PricePerShare::from_attos(curve.price_at(supply));
+        let spent_supply = supply.saturating_add(curve.shares_for_amount(supply, gross.attos()));
+        let price_after = PricePerShare::from_attos(curve.price_at(spent_supply));
+        let trade = TradeAmounts::carve(gross, self.trading_fee_bps(market, price_before, price_after, trade_time));
+        let shares = self.calculate_shares_for_amount(market, outcome_id, trade.net)?;
+        Ok((trade, shares))
+    }
+
+    /// Price a sale of `shares` of an outcome placed at `trade_time`, with the trading fee taken
+    /// out of their value
+    fn price_sell(
+        &self,
+        market: &Market,
+        outcome_id: OutcomeId,
+        shares: Shares,
+        trade_time: Timestamp,
+    ) -> Result<TradeAmounts, ContractError> {
+        let outcome = market
+            .outcomes
+            .get(outcome_id as usize)
+            .ok_or(ContractError::InvalidOutcome)?;
+        let curve = market.bonding_curve();
+        let supply = u128::from(outcome.total_shares);
+        let price_before = PricePerShare::from_attos(curve.price_at(supply));
+        let price_after = PricePerShare::from_attos(curve.price_at(supply.saturating_sub(shares.attos())));
+        let gross = self.calculate_sell_value(market, outcome_id, shares)?;
+        Ok(TradeAmounts::carve(gross, self.trading_fee_bps(market, price_before, price_after, trade_time)))
+    }
+
     /// Distribute trading fees to market creator and platform
+    /// A configured share of each fee is burned before the split. A creator trading in their
+    /// own market gets no rebate: the creator's share goes to the platform
+    /// 
+    /// # Arguments
+    /// * `trader` - The player who traded, or `None` for a guild trade
     async fn distribute_trading_fees(
         &mut self,
         market_id: MarketId,
-        trade_amount: Amount
+        trader: Option<PlayerId>,
+        trade_amount: Amount,
+        fee_bps: u32,
+        current_time: Timestamp,
     ) -> Result<(), ContractError> {
-        let market = self.get_market(&market_id).await?;
-        let _config = self.state.config.get();
+        let mut market = self.get_market(&market_id).await?;
+        let burn_percent = self.state.config.get().trading_fee_burn_percent;
         
         // Calculate trading fees (smaller percentage than creation fees)
-        let trading_fee = trade_amount.saturating_mul(1).saturating_div(Amount::from_tokens(200));
+        let mut split = math::trading_fee(u128::from(trade_amount), fee_bps, burn_percent);
+        if trader == Some(market.creator) {
+            market.creator_volume = market.creator_volume.saturating_add(trade_amount);
+            split.platform += split.creator;
+            split.creator = 0;
+        }
+        let trading_fee = Amount::from_attos(split.total());
+
+        market.volume = market.volume.saturating_add(trade_amount);
+        self.record_daily_volume(market_id, trade_amount, current_time).await?;
+        market.fees_collected = market.fees_collected.saturating_add(trading_fee);
+        market.creator_fees_earned = market.creator_fees_earned.saturating_add(Amount::from_attos(split.creator));
+        let mut stats = self.state.creator_stats.get(&market.creator).await?.unwrap_or_default();
+        stats.total_volume = stats.total_volume.saturating_add(trade_amount);
+        stats.total_fees_generated = stats.total_fees_generated.saturating_add(trading_fee);
+        stats.trading_fees_earned = stats.trading_fees_earned.saturating_add(Amount::from_attos(split.creator));
+        self.state.creator_stats.insert(&market.creator, stats)?;
+        let creator = market.creator;
+        self.state.markets.insert(&market_id, market)?;
         
-        if trading_fee > Amount::ZERO.into() {
-            // Split between creator and platform
-            let creator_share = trading_fee.saturating_div(Amount::from_tokens(2).into());
-            let platform_share = trading_fee.saturating_sub(creator_share);
+        if trading_fee > Amount::ZERO {
+            let burn = Amount::from_attos(split.burned);
+            let distributable = Amount::from_attos(split.distributed());
+            let creator_share = Amount::from_attos(split.creator);
+            let platform_share = Amount::from_attos(split.platform);
             
             // Give creator their share
-            let mut creator_player = self.get_player(&market.creator).await?;
-            creator_player.token_balance = creator_player.token_balance.saturating_add(Amount::from_tokens(creator_share));
-            creator_player.total_earned = creator_player.total_earned.saturating_add(Amount::from_tokens(creator_share));
-            self.state.players.insert(&market.creator, creator_player)?;
+            let mut creator_player = self.get_player(&creator).await?;
+            creator_player.token_balance = creator_player.token_balance.saturating_add(creator_share);
+            creator_player.total_earned = creator_player.total_earned.saturating_add(creator_share);
+            self.save_player(creator_player).await?;
             
             // Add platform share to total supply
             let current_supply = self.state.total_supply.get();
-            self.state.total_supply.set(current_supply.saturating_add(Amount::from_tokens(platform_share)));
+            self.state.total_supply.set(current_supply.saturating_add(platform_share));
+
+            self.record_token_flow(TokenFlow::TradingFee, trading_fee).await?;
+            self.record_token_flow(TokenFlow::FeeReward, distributable).await?;
+
+            self.burn_tokens(burn).await?;
         }
         
         Ok(())
     }
 
+    /// Append an operation or message that executed successfully to the event log
+    /// Its sequence number is its index in the log, so indexers can resume after the last one seen
+    fn log_event(&mut self, source: EventSource, actor: Option<PlayerId>, payload: serde_json::Value) {
+        // Unit variants serialize as their name, the others as an object keyed by it
+        let kind = match &payload {
+            serde_json::Value::String(name) => name.clone(),
+            serde_json::Value::Object(fields) => fields.keys().next().cloned().unwrap_or_default(),
+            _ => String::new(),
+        };
+        let sequence = self.state.event_log.count() as u64;
+        self.state.event_log.push(ChainEvent {
+            sequence,
+            timestamp: self.runtime.system_time(),
+            source,
+            actor,
+            kind,
+            payload: payload.to_string(),
+        });
+    }
+
+    /// Remove tokens from circulation
+    async fn burn_tokens(&mut self, amount: Amount) -> Result<(), ContractError> {
+        if amount == Amount::ZERO {
+            return Ok(());
+        }
+        let total_supply = self.state.total_supply.get().saturating_sub(amount);
+        self.state.total_supply.set(total_supply);
+        let total_burned = self.state.total_burned.get().saturating_add(amount);
+        self.state.total_burned.set(total_burned);
+        self.record_token_flow(TokenFlow::Burn, amount).await
+    }
+
+    /// Add a notification to the outbox for off-chain relays
+    /// Its sequence number is its index in the outbox, so relays can resume after the last one
+    /// delivered
+    fn notify(
+        &mut self,
+        kind: NotificationKind,
+        market_id: MarketId,
+        player_id: Option<PlayerId>,
+        outcome_id: OutcomeId,
+        amount: Option<Amount>,
+    ) {
+        let sequence = self.state.outbox.count() as u64;
+        self.state.outbox.push(Notification {
+            sequence,
+            timestamp: self.runtime.system_time(),
+            kind,
+            market_id,
+            player_id,
+            outcome_id,
+            amount,
+        });
+    }
+
+    /// Notify relays of a trade worth at least the filter's threshold
+    fn notify_large_trade(&mut self, market_id: MarketId, player_id: PlayerId, outcome_id: OutcomeId, amount: Amount) {
+        if self.state.notification_filter.get().is_large_trade(amount) {
+            self.notify(NotificationKind::LargeTrade, market_id, Some(player_id), outcome_id, Some(amount));
+        }
+    }
+
+    /// Count a trade in its market's spectator statistics
+    async fn record_market_activity(
+        &mut self,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        side: TradeSide,
+        amount: Amount,
+        time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let mut activity = self.state.market_activity.get(&market_id).await?.unwrap_or_default();
+        activity.record(outcome_id, side, amount, time);
+        self.state.market_activity.insert(&market_id, activity)?;
+        Ok(())
+    }
+
+    /// Add a token movement to today's economy metrics
+    async fn record_token_flow(&mut self, flow: TokenFlow, amount: Amount) -> Result<(), ContractError> {
+        if amount == Amount::ZERO {
+            return Ok(());
+        }
+        let day = day_index(self.runtime.system_time());
+        let mut metrics = self
+            .state
+            .economy_metrics
+            .get(&day)
+            .await?
+            .unwrap_or_else(|| EconomyDay::new(day));
+        metrics.record(flow, amount);
+        self.state.economy_metrics.insert(&day, metrics)?;
+        Ok(())
+    }
+
     // ============================================================================
     // Helper Functions
     // ============================================================================
@@ -1110,6 +8160,33 @@ impl PredictionMarketContract {
             .ok_or(ContractError::PlayerNotFound)
     }
 
+    /// Store a player, moving their entry in the rank index if their standing changed
+    async fn save_player(&mut self, player: Player) -> Result<(), ContractError> {
+        let previous = self.state.players.get(&player.id).await?.and_then(|previous| RankKey::of(&previous));
+        let key = RankKey::of(&player);
+        if previous != key {
+            self.unrank(previous)?;
+            if let Some(key) = &key {
+                self.state.rank_index.insert(key)?;
+                let ranked = *self.state.ranked_players.get();
+                self.state.ranked_players.set(ranked + 1);
+            }
+        }
+        let id = player.id;
+        self.state.players.insert(&id, player)?;
+        Ok(())
+    }
+
+    /// Remove an entry from the rank index
+    fn unrank(&mut self, key: Option<RankKey>) -> Result<(), ContractError> {
+        if let Some(key) = key {
+            self.state.rank_index.remove(&key)?;
+            let ranked = *self.state.ranked_players.get();
+            self.state.ranked_players.set(ranked.saturating_sub(1));
+        }
+        Ok(())
+    }
+
     /// Get a market by its ID
     /// Helper function to retrieve market data from storage
     async fn get_market(&self, market_id: &MarketId) -> Result<Market, ContractError> {
@@ -1120,6 +8197,150 @@ impl PredictionMarketContract {
             .ok_or(ContractError::MarketNotFound)
     }
 
+    /// Load the guild a player is an officer of
+    async fn get_officer_guild(&self, player_id: &PlayerId) -> Result<Guild, ContractError> {
+        let player = self.get_player(player_id).await?;
+        let guild_id = player.guild_id.ok_or(ContractError::NotGuildMember)?;
+        let guild = self.state.guilds.get(&guild_id).await?.ok_or(ContractError::GuildNotFound)?;
+        if !guild.officers.contains(player_id) {
+            return Err(ContractError::Unauthorized);
+        }
+        Ok(guild)
+    }
+
+    /// Check that a player may trade or vote in a market given its visibility
+    /// The creator always has access to their own market
+    fn ensure_market_access(market: &Market, player: &Player) -> Result<(), ContractError> {
+        let allowed = player.id == market.creator
+            || match market.visibility {
+                MarketVisibility::Public => true,
+                MarketVisibility::GuildOnly => {
+                    market.allowed_guild.is_some() && player.guild_id == market.allowed_guild
+                }
+                MarketVisibility::Whitelist => market.allowlist.contains(&player.id),
+            };
+        if allowed {
+            Ok(())
+        } else {
+            Err(ContractError::MarketAccessDenied)
+        }
+    }
+
+    /// Reject trades after a market's end time, during its blind finish or within its final
+    /// grace period
+    fn ensure_trading_open(&self, market: &Market, current_time: Timestamp) -> Result<(), ContractError> {
+        if current_time >= market.end_time {
+            return Err(ContractError::MarketEnded);
+        }
+        Self::ensure_reviewed(market, current_time)?;
+        if market.in_blind_finish(current_time) {
+            return Err(ContractError::BlindFinishActive);
+        }
+        let grace_micros = self.state.config.get().trading_grace_seconds.saturating_mul(1_000_000);
+        if current_time.micros() >= market.end_time.micros().saturating_sub(grace_micros) {
+            return Err(ContractError::InGracePeriod);
+        }
+        Ok(())
+    }
+
+    /// Reject anything that moves shares between trading closing and the market resolving;
+    /// every flow that trades, transfers or escrows positions checks this before its own
+    /// status checks
+    fn ensure_positions_unlocked(market: &Market) -> Result<(), ContractError> {
+        if market.status.locks_positions() {
+            return Err(ContractError::PositionsLocked);
+        }
+        Ok(())
+    }
+
+    /// Keep liquidity out of a market that is paused or still in its review window
+    fn ensure_reviewed(market: &Market, current_time: Timestamp) -> Result<(), ContractError> {
+        if market.paused_at.is_some() {
+            return Err(ContractError::MarketPaused);
+        }
+        if market.in_review(current_time) {
+            return Err(ContractError::MarketInReview);
+        }
+        Ok(())
+    }
+
+    /// Hold resolution until every sealed trade is revealed or the reveal window has passed
+    fn ensure_reveals_settled(market: &Market, current_time: Timestamp) -> Result<(), ContractError> {
+        if current_time < market.reveal_deadline() && market.blind_commitments.iter().any(|sealed| !sealed.revealed) {
+            return Err(ContractError::RevealPeriodOpen);
+        }
+        Ok(())
+    }
+
+    /// Round an end time up to the configured granularity
+    fn round_end_time(&self, end_time: Timestamp) -> Timestamp {
+        let granularity_micros = self.state.config.get().end_time_granularity_seconds.saturating_mul(1_000_000);
+        Timestamp::from(math::round_up_to_multiple(end_time.micros(), granularity_micros))
+    }
+
+    /// Reject orders whose deadline has passed
+    fn ensure_order_valid(valid_until: Option<Timestamp>, current_time: Timestamp) -> Result<(), ContractError> {
+        if valid_until.is_some_and(|deadline| current_time > deadline) {
+            return Err(ContractError::OrderExpired);
+        }
+        Ok(())
+    }
+
+    /// Validate market metadata lengths
+    /// URIs must be non-empty without whitespace; locales are short tags like "en-US", and
+    /// each translation map holds at most `MAX_TRANSLATIONS` non-empty texts
+    fn validate_metadata(metadata: &MarketMetadata) -> Result<(), ContractError> {
+        let valid_uri = |uri: &String| {
+            !uri.is_empty()
+                && uri.len() <= MAX_METADATA_URI_LENGTH
+                && !uri.chars().any(char::is_whitespace)
+        };
+        let valid_translations = |texts: &BTreeMap<String, String>| {
+            texts.len() <= MAX_TRANSLATIONS
+                && texts.iter().all(|(locale, text)| is_valid_locale(locale) && !text.trim().is_empty())
+        };
+        if metadata.image_uri.as_ref().is_some_and(|uri| !valid_uri(uri))
+            || metadata.source_url.as_ref().is_some_and(|uri| !valid_uri(uri))
+            || metadata.locale.as_ref().is_some_and(|locale| !is_valid_locale(locale))
+            || !valid_translations(&metadata.localized_titles)
+            || !valid_translations(&metadata.localized_descriptions)
+            || !metadata.outcome_display.iter().all(OutcomeDisplay::is_valid)
+        {
+            return Err(ContractError::InvalidMetadata);
+        }
+        Ok(())
+    }
+
+    /// Validate evidence: a URI like market metadata's and a short note
+    fn validate_evidence(evidence: &Evidence) -> Result<(), ContractError> {
+        if evidence.url.is_empty()
+            || evidence.url.len() > MAX_METADATA_URI_LENGTH
+            || evidence.url.chars().any(char::is_whitespace)
+            || evidence.note.len() > MAX_EVIDENCE_NOTE_LENGTH
+        {
+            return Err(ContractError::InvalidEvidence);
+        }
+        Ok(())
+    }
+
+    /// Start a fresh oracle voting round for a market, over the voting period it entered when
+    /// it closed
+    fn new_oracle_voting(&self, market: &Market) -> OracleVoting {
+        let voting_start = market.voting_start.unwrap_or(market.end_time);
+        let voting_seconds = self.state.config.get().oracle_voting_duration_seconds;
+        OracleVoting {
+            market_id: market.id,
+            voting_start,
+            voting_end: market
+                .voting_end
+                .unwrap_or_else(|| voting_start.saturating_add(TimeDelta::from_secs(voting_seconds))),
+            votes: BTreeMap::new(),
+            voters: Vec::new(),
+            ballots: BTreeMap::new(),
+            resolved: false,
+        }
+    }
+
     /// Generate a unique market ID
     /// Helper function to create unique IDs for new markets
     async fn generate_market_id(&mut self) -> Result<MarketId, ContractError> {
@@ -1129,126 +8350,185 @@ impl PredictionMarketContract {
         Ok(id)
     }
 
+    /// Claim a slug for a new market from its title, numbering it from 2 if already taken
+    async fn claim_slug(&mut self, market_id: MarketId, title: &str) -> Result<String, ContractError> {
+        let base = search::slug(title);
+        let mut slug = base.clone();
+        let mut suffix = 2;
+        while self.state.market_slugs.contains_key(&slug).await? {
+            slug = format!("{base}-{suffix}");
+            suffix += 1;
+        }
+        self.state.market_slugs.insert(&slug, market_id)?;
+        Ok(slug)
+    }
+
+    /// Move a market between keyword entries of the search index
+    async fn update_search_index(
+        &mut self,
+        market_id: MarketId,
+        previous: &BTreeSet<String>,
+        current: &BTreeSet<String>,
+    ) -> Result<(), ContractError> {
+        for keyword in previous.difference(current) {
+            let mut market_ids = self.state.market_search_index.get(keyword).await?.unwrap_or_default();
+            market_ids.retain(|id| *id != market_id);
+            if market_ids.is_empty() {
+                self.state.market_search_index.remove(keyword)?;
+            } else {
+                self.state.market_search_index.insert(keyword, market_ids)?;
+            }
+        }
+        for keyword in current.difference(previous) {
+            let mut market_ids = self.state.market_search_index.get(keyword).await?.unwrap_or_default();
+            // Edited titles add keywords to older markets, so insert in order rather than append
+            if let Err(position) = market_ids.binary_search(&market_id) {
+                market_ids.insert(position, market_id);
+            }
+            self.state.market_search_index.insert(keyword, market_ids)?;
+        }
+        Ok(())
+    }
+
     /// Generate a unique guild ID
     /// Helper function to create unique IDs for new guilds
-    async fn next_guild_id(&mut self) -> Result<GuildId, ContractError> {
-        // naive: number of guilds as next id
-        // MapView has no len; use timestamp lower bits for uniqueness
-        Ok((self.runtime.system_time().micros() & 0xFFFF_FFFF) as u64)
+    async fn generate_guild_id(&mut self) -> Result<GuildId, ContractError> {
+        if !*self.state.guild_ids_migrated.get() {
+            // Guilds created before the counter existed used timestamp bits as IDs,
+            // so continue numbering above the highest one
+            let mut next_id = 0;
+            self.state
+                .guilds
+                .for_each_index(|guild_id| {
+                    next_id = next_id.max(guild_id + 1);
+                    Ok(())
+                })
+                .await?;
+            self.state.next_guild_id.set(next_id);
+            self.state.guild_ids_migrated.set(true);
+        }
+        let id = *self.state.next_guild_id.get();
+        self.state.next_guild_id.set(id + 1);
+        Ok(id)
     }
 
     /// Calculate how many shares a player gets for their investment
-    /// Uses arcade-style AMM pricing: Share_Price = Base_Price × (Current_Shares_Sold / Total_Supply)^smoothing_factor
+    /// Uses the market's bonding curve over the outcome's outstanding shares
     fn calculate_shares_for_amount(
         &self,
         market: &Market,
         outcome_id: OutcomeId,
-        amount: Amount,
-    ) -> Result<Amount, ContractError> {
-        if outcome_id >= market.outcomes.len() as OutcomeId {
-            return Err(ContractError::InvalidOutcome);
-        }
-        
-        let outcome = &market.outcomes[outcome_id as usize];
-        let _current_shares = outcome.total_shares;
-        let total_supply = market.total_liquidity;
-        
-        if total_supply == Amount::ZERO {
-            // First purchase: 1:1 ratio
-            return Ok(amount);
-        }
-        
-        // AMM Formula: Share_Price = Base_Price × (Current_Shares_Sold / Total_Supply)^smoothing_factor
-        let base_price = market.base_price;
-        let smoothing_factor = market.smoothing_factor;
-        
-        // Calculate current price per share using simplified ratio
-        let price_ratio = if total_supply > Amount::ZERO {
-            // Use a simple ratio calculation
-            Amount::from_tokens(1) // Simplified for now
-        } else {
-            Amount::from_tokens(1)
-        };
-        
-        // Apply smoothing factor (simplified calculation)
-        let _adjusted_ratio = if smoothing_factor > 1.0 {
-            // Increase price as more shares are sold
-            price_ratio.saturating_mul((smoothing_factor * 1000.0) as u128)
-        } else {
-            price_ratio
-        };
-        
-        let price_per_share = base_price; // Simplified: use base price directly
-        
-        // Calculate shares received for the amount using simplified logic
-        if price_per_share > Amount::ZERO {
-            // Use a simple 1:1 ratio for now to avoid complex Amount arithmetic
-            Ok(amount)
-        } else {
-        Ok(amount)
-        }
+        amount: Tokens,
+    ) -> Result<Shares, ContractError> {
+        let outcome = market
+            .outcomes
+            .get(outcome_id as usize)
+            .ok_or(ContractError::InvalidOutcome)?;
+        let shares = market.bonding_curve()
+            .shares_for_amount(u128::from(outcome.total_shares), amount.attos());
+        Ok(Shares::from_attos(shares))
     }
 
     /// Calculate the current price per share for an outcome
-    /// Uses AMM formula for dynamic pricing
-    fn calculate_current_price(&self, market: &Market, outcome_id: OutcomeId) -> Result<Amount, ContractError> {
-        if outcome_id >= market.outcomes.len() as OutcomeId {
-            return Err(ContractError::InvalidOutcome);
-        }
-        
-        let outcome = &market.outcomes[outcome_id as usize];
-        let _current_shares = outcome.total_shares;
-        let total_supply = market.total_liquidity;
-        
-        if total_supply == Amount::ZERO {
-            return Ok(market.base_price);
-        }
-        
-        // AMM Formula: Share_Price = Base_Price × (Current_Shares_Sold / Total_Supply)^smoothing_factor
-        let base_price = market.base_price;
-        let smoothing_factor = market.smoothing_factor;
-        
-        let price_ratio = if total_supply > Amount::ZERO {
-            Amount::from_tokens(1) // Simplified for now
-        } else {
-            Amount::from_tokens(1)
-        };
-        
-        // Apply smoothing factor
-        let _adjusted_ratio = if smoothing_factor > 1.0 {
-            price_ratio.saturating_mul((smoothing_factor * 1000.0) as u128)
-        } else {
-            price_ratio
-        };
-        
-        let price_per_share = base_price; // Simplified: use base price directly
-        Ok(price_per_share.max(market.base_price)) // Ensure minimum base price
+    /// The marginal price stays between the base price and the curve's cap
+    fn calculate_current_price(&self, market: &Market, outcome_id: OutcomeId) -> Result<PricePerShare, ContractError> {
+        let outcome = market
+            .outcomes
+            .get(outcome_id as usize)
+            .ok_or(ContractError::InvalidOutcome)?;
+        let price = market.bonding_curve().price_at(u128::from(outcome.total_shares));
+        Ok(PricePerShare::from_attos(price))
     }
 
     /// Calculate the value received when selling shares
-    /// Helper function for market pricing logic (simplified 1:1 for now)
+    /// Selling walks back down the same curve the shares were bought on
     fn calculate_sell_value(
         &self,
-        _market: &Market,
-        _outcome_id: OutcomeId,
-        shares: Amount,
-    ) -> Result<Amount, ContractError> {
-        Ok(shares)
+        market: &Market,
+        outcome_id: OutcomeId,
+        shares: Shares,
+    ) -> Result<Tokens, ContractError> {
+        let outcome = market
+            .outcomes
+            .get(outcome_id as usize)
+            .ok_or(ContractError::InvalidOutcome)?;
+        let value = market.bonding_curve()
+            .sell_value(u128::from(outcome.total_shares), shares.attos());
+        Ok(Tokens::from_attos(value))
+    }
+
+    /// Give each winning position bonus XP for how early it was opened
+    /// A position's entry time is when its shares of the winning outcomes were acquired,
+    /// averaged over the shares, so a token early buy of another outcome earns nothing
+    async fn award_early_conviction_xp(
+        &mut self,
+        market: &Market,
+        max_bonus: u64,
+        current_time: Timestamp,
+    ) -> Result<(), ContractError> {
+        let boost = self.event_boost(market, current_time);
+        for (player_id, position) in &market.positions {
+            let won = market.winning_outcomes.iter().any(|outcome_id| {
+                position
+                    .shares_by_outcome
+                    .get(outcome_id)
+                    .is_some_and(|shares| *shares > Amount::ZERO)
+            });
+            if !won {
+                continue;
+            }
+            let xp = boost.xp(math::early_conviction_xp(
+                max_bonus,
+                market.creation_time.micros(),
+                market.end_time.micros(),
+                position.entry_time_for(&market.winning_outcomes).micros(),
+            ));
+            // Traders registered on another chain have no local record to credit
+            let Some(mut player) = self.state.players.get(player_id).await? else {
+                continue;
+            };
+            if xp > 0 {
+                self.add_experience(&mut player, xp).await?;
+                self.save_player(player).await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Add the Brier score of every outcome each trader entered to their accuracy record,
+    /// counting the top-ranked outcome as the one that happened
+    async fn score_forecasts(&mut self, market: &Market) -> Result<(), ContractError> {
+        for (player_id, position) in &market.positions {
+            if position.entry_probabilities.is_empty() {
+                continue;
+            }
+            // Traders registered on another chain have no local record to credit
+            let Some(mut player) = self.state.players.get(player_id).await? else {
+                continue;
+            };
+            for (outcome_id, probability) in &position.entry_probabilities {
+                let happened = market.winning_outcome == Some(*outcome_id);
+                player.brier_score_total =
+                    player.brier_score_total.saturating_add(math::brier_score_bps(*probability, happened));
+                player.forecasts_scored += 1;
+            }
+            self.save_player(player).await?;
+        }
+        Ok(())
     }
 
     /// Add experience points to a player and handle leveling up
     /// Helper function for player progression system
     async fn add_experience(&mut self, player: &mut Player, xp: u64) -> Result<(), ContractError> {
-        player.experience_points += xp;
         let old_level = player.level;
-        while player.experience_points >= (player.level as u64) * 100 {
-            player.experience_points -= (player.level as u64) * 100;
-            player.level += 1;
-        }
+        (player.level, player.experience_points) =
+            math::apply_experience(player.level, player.experience_points, xp);
+        self.add_season_xp(player.id, xp).await?;
         
         // Check for level-based achievements
         if player.level > old_level {
             self.check_achievements(player).await?;
+            self.award_chests(player.id, old_level, player.level).await?;
         }
         
         Ok(())
@@ -1268,6 +8548,7 @@ impl PredictionMarketContract {
                         player.token_balance = player.token_balance.saturating_add(achievement.reward_tokens);
                         player.total_earned = player.total_earned.saturating_add(achievement.reward_tokens);
                         player.experience_points += achievement.reward_xp;
+                        self.record_token_flow(TokenFlow::AchievementReward, achievement.reward_tokens).await?;
                         
                         new_achievements.push(achievement_id);
                         
@@ -1285,12 +8566,55 @@ impl PredictionMarketContract {
         
         // Update player with new achievements
         if !new_achievements.is_empty() {
-            self.state.players.insert(&player.id, player.clone())?;
+            self.save_player(player.clone()).await?;
         }
         
         Ok(())
     }
     
+    /// Check and award collective achievements for a guild
+    /// Rewards are deposited into the guild's shared pool; the caller saves the guild
+    async fn check_guild_achievements(&mut self, guild: &mut Guild) -> Result<(), ContractError> {
+        let mut achievement_ids = Vec::new();
+        self.state
+            .guild_achievements
+            .for_each_index(|achievement_id| {
+                achievement_ids.push(achievement_id);
+                Ok(())
+            })
+            .await?;
+
+        for achievement_id in achievement_ids {
+            if guild.achievements_earned.contains(&achievement_id) {
+                continue;
+            }
+            let Some(achievement) = self.state.guild_achievements.get(&achievement_id).await? else {
+                continue;
+            };
+            let reached = match achievement.requirement {
+                GuildAchievementRequirement::PoolContributed(amount) => guild.total_contributed >= amount,
+                GuildAchievementRequirement::MembersRecruited(count) => guild.members_recruited >= count,
+            };
+            if !reached {
+                continue;
+            }
+
+            guild.achievements_earned.push(achievement_id);
+            guild.shared_pool = guild.shared_pool.saturating_add(achievement.reward_tokens);
+            let total_supply = self.state.total_supply.get().saturating_add(achievement.reward_tokens);
+            self.state.total_supply.set(total_supply);
+            self.record_token_flow(TokenFlow::AchievementReward, achievement.reward_tokens).await?;
+
+            self.runtime
+                .prepare_message(Message::GuildAchievementUnlocked {
+                    guild_id: guild.id,
+                    achievement_id,
+                })
+                .send_to(self.runtime.chain_id());
+        }
+        Ok(())
+    }
+
     /// Check if a player meets an achievement requirement
     async fn check_achievement_requirement(
         &self, 
@@ -1303,16 +8627,8 @@ impl PredictionMarketContract {
             AchievementRequirement::TotalProfit(profit) => Ok(player.total_profit >= *profit),
             AchievementRequirement::ParticipateInMarkets(count) => Ok(player.markets_participated >= *count),
             AchievementRequirement::CreateMarkets(count) => {
-                // Count markets created by this player
-                let mut created_count = 0;
-                for market_id in &player.active_markets {
-                    if let Some(market) = self.state.markets.get(market_id).await? {
-                        if market.creator == player.id {
-                            created_count += 1;
-                        }
-                    }
-                }
-                Ok(created_count >= *count)
+                let stats = self.state.creator_stats.get(&player.id).await?.unwrap_or_default();
+                Ok(stats.markets_created >= *count)
             },
             AchievementRequirement::JoinGuild => Ok(player.guild_id.is_some()),
             AchievementRequirement::ReachLevel(level) => Ok(player.level >= *level),
@@ -1320,29 +8636,60 @@ impl PredictionMarketContract {
     }
 
     /// Resolve a market using oracle voting results
-    /// Helper function for market resolution logic
-    async fn resolve_by_oracle_vote(&mut self, market_id: MarketId) -> Result<OutcomeId, ContractError> {
+    /// Ranks outcomes by vote weight (ties go to the lower outcome ID) and keeps one per payout rank
+    async fn resolve_by_oracle_vote(&mut self, market: &Market) -> Result<Vec<OutcomeId>, ContractError> {
         let voting = self
             .state
             .oracle_votes
-            .get(&market_id)
+            .get(&market.id)
             .await?
             .ok_or(ContractError::OracleNotReady)?;
-        let mut best: Option<(OutcomeId, u64)> = None;
-        for (oid, w) in voting.votes {
-            if let Some((_, bw)) = best {
-                if w.total_weight > bw { best = Some((oid, w.total_weight)); }
-            } else {
-                best = Some((oid, w.total_weight));
-            }
+        let mut ranked: Vec<(OutcomeId, u64)> = voting
+            .votes
+            .into_iter()
+            .map(|(oid, w)| (oid, w.total_weight))
+            .collect();
+        ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        if ranked.len() < market.payout_weights.len() {
+            return Err(ContractError::OracleNotReady);
         }
-        best.map(|(o, _)| o).ok_or(ContractError::OracleNotReady)
+        Ok(ranked
+            .into_iter()
+            .take(market.payout_weights.len())
+            .map(|(o, _)| o)
+            .collect())
     }
 
     /// Resolve a market using automated logic
     /// Helper function for market resolution logic (placeholder implementation)
-    async fn resolve_automated(&self, _market_id: MarketId) -> Result<OutcomeId, ContractError> {
-        // Placeholder: choose outcome 0
-        Ok(0)
+    async fn resolve_automated(&self, market: &Market) -> Result<Vec<OutcomeId>, ContractError> {
+        // Placeholder: rank outcomes in creation order
+        Ok((0..market.payout_weights.len() as OutcomeId).collect())
+    }
+
+    /// Rank outcomes by implied probability at close, i.e. by closing price
+    /// Ties (e.g. outcomes that both reached the price cap) go to the outcome with more
+    /// outstanding shares, then to the outcome listed first
+    fn resolve_by_crowd_consensus(market: &Market) -> Vec<OutcomeId> {
+        let curve = market.bonding_curve();
+        let mut outcomes: Vec<(OutcomeId, u128, u128)> = market
+            .outcomes
+            .iter()
+            .map(|outcome| {
+                let supply = u128::from(outcome.total_shares);
+                (outcome.id, curve.price_at(supply), supply)
+            })
+            .collect();
+        outcomes.sort_by(|(a_id, a_price, a_supply), (b_id, b_price, b_supply)| {
+            b_price
+                .cmp(a_price)
+                .then(b_supply.cmp(a_supply))
+                .then(a_id.cmp(b_id))
+        });
+        outcomes
+            .into_iter()
+            .take(market.payout_weights.len())
+            .map(|(outcome_id, _, _)| outcome_id)
+            .collect()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,156 @@
+// Special events: time-boxed boosts the admin schedules, such as a double XP weekend, a
+// zero-fee day or an XP boost on one category of markets. Trades consult the events running
+// at their time for the XP they earn and the fee they pay.
+
+use async_graphql::{Enum, SimpleObject};
+use linera_sdk::linera_base_types::Timestamp;
+use serde::{Deserialize, Serialize};
+
+use crate::math::{self, BASIS_POINTS};
+use crate::state::MarketType;
+
+pub type EventId = u64;
+
+/// Maximum number of upcoming and running events; ended ones are dropped when scheduling
+pub const MAX_SCHEDULED_EVENTS: usize = 20;
+/// Largest XP multiplier an event can apply, in basis points
+pub const MAX_XP_MULTIPLIER_BPS: u32 = 5 * BASIS_POINTS;
+/// Maximum length of an event name
+pub const MAX_EVENT_NAME_LENGTH: usize = 64;
+
+/// Kind of market an event can be limited to, following the market type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Enum)]
+pub enum MarketCategory {
+    QuickPrediction,
+    Tournament,
+    Seasonal,
+    PvP,
+}
+
+impl MarketCategory {
+    pub fn of(market_type: &MarketType) -> Self {
+        match market_type {
+            MarketType::QuickPrediction => MarketCategory::QuickPrediction,
+            MarketType::TournamentMarket => MarketCategory::Tournament,
+            MarketType::SeasonalEvent => MarketCategory::Seasonal,
+            MarketType::PvPChallenge { .. } => MarketCategory::PvP,
+        }
+    }
+}
+
+/// A time-boxed boost scheduled by the admin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+pub struct SpecialEvent {
+    pub id: EventId,
+    pub name: String,
+    pub starts_at: Timestamp,
+    pub ends_at: Timestamp,
+    /// XP earned from trades is scaled by this many basis points; 10_000 leaves it unchanged
+    pub xp_multiplier_bps: u32,
+    /// Trading fees are scaled by this many basis points; zero waives them
+    pub fee_multiplier_bps: u32,
+    /// Markets the event is limited to; every market if unset
+    pub category: Option<MarketCategory>,
+}
+
+impl SpecialEvent {
+    /// Whether the event boosts without penalizing, over a non-empty period
+    pub fn is_valid(&self) -> bool {
+        !self.name.trim().is_empty()
+            && self.name.chars().count() <= MAX_EVENT_NAME_LENGTH
+            && self.starts_at < self.ends_at
+            && (BASIS_POINTS..=MAX_XP_MULTIPLIER_BPS).contains(&self.xp_multiplier_bps)
+            && self.fee_multiplier_bps <= BASIS_POINTS
+    }
+
+    pub fn is_running(&self, time: Timestamp) -> bool {
+        self.starts_at <= time && time < self.ends_at
+    }
+
+    pub fn applies_to(&self, market_type: &MarketType) -> bool {
+        self.category.is_none_or(|category| category == MarketCategory::of(market_type))
+    }
+}
+
+/// Combined effect of the events running for a trade: the highest XP multiplier and the
+/// lowest fee multiplier among them, so overlapping events don't stack
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventBoost {
+    pub xp_multiplier_bps: u32,
+    pub fee_multiplier_bps: u32,
+}
+
+impl Default for EventBoost {
+    fn default() -> Self {
+        EventBoost {
+            xp_multiplier_bps: BASIS_POINTS,
+            fee_multiplier_bps: BASIS_POINTS,
+        }
+    }
+}
+
+impl EventBoost {
+    /// Boost of the events running at `time` for a market of `market_type`
+    pub fn running(events: &[SpecialEvent], market_type: &MarketType, time: Timestamp) -> Self {
+        events
+            .iter()
+            .filter(|event| event.is_running(time) && event.applies_to(market_type))
+            .fold(EventBoost::default(), |boost, event| EventBoost {
+                xp_multiplier_bps: boost.xp_multiplier_bps.max(event.xp_multiplier_bps),
+                fee_multiplier_bps: boost.fee_multiplier_bps.min(event.fee_multiplier_bps),
+            })
+    }
+
+    pub fn xp(&self, xp: u64) -> u64 {
+        let boosted = math::mul_div(u128::from(xp), u128::from(self.xp_multiplier_bps), u128::from(BASIS_POINTS));
+        u64::try_from(boosted).unwrap_or(u64::MAX)
+    }
+
+    pub fn fee_bps(&self, fee_bps: u32) -> u32 {
+        // The multiplier is at most 100%, so the result fits
+        (u64::from(fee_bps) * u64::from(self.fee_multiplier_bps) / u64::from(BASIS_POINTS)) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(xp_multiplier_bps: u32, fee_multiplier_bps: u32, category: Option<MarketCategory>) -> SpecialEvent {
+        SpecialEvent {
+            id: 0,
+            name: "Launch week".to_string(),
+            starts_at: Timestamp::from(100),
+            ends_at: Timestamp::from(200),
+            xp_multiplier_bps,
+            fee_multiplier_bps,
+            category,
+        }
+    }
+
+    #[test]
+    fn overlapping_events_take_the_best_of_each() {
+        let events = [
+            event(2 * BASIS_POINTS, BASIS_POINTS, None),
+            event(BASIS_POINTS, 0, None),
+            event(3 * BASIS_POINTS, BASIS_POINTS, Some(MarketCategory::Tournament)),
+        ];
+        let boost = EventBoost::running(&events, &MarketType::QuickPrediction, Timestamp::from(150));
+        assert_eq!(boost.xp(10), 20);
+        assert_eq!(boost.fee_bps(100), 0);
+
+        let boost = EventBoost::running(&events, &MarketType::TournamentMarket, Timestamp::from(150));
+        assert_eq!(boost.xp(10), 30);
+    }
+
+    #[test]
+    fn events_apply_only_while_running() {
+        let events = [event(2 * BASIS_POINTS, BASIS_POINTS / 2, None)];
+        for time in [99, 200] {
+            let boost = EventBoost::running(&events, &MarketType::QuickPrediction, Timestamp::from(time));
+            assert_eq!(boost, EventBoost::default());
+        }
+        let boost = EventBoost::running(&events, &MarketType::QuickPrediction, Timestamp::from(100));
+        assert_eq!(boost.fee_bps(100), 50);
+    }
+}
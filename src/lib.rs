@@ -1,24 +1,39 @@
+// `GraphQLMutationRoot` generates one mutation per operation, taking each field as an argument
+#![allow(clippy::too_many_arguments)]
+
 use async_graphql::{Request, Response};
 use linera_sdk::{
     graphql::GraphQLMutationRoot,
-    linera_base_types::{Amount, ContractAbi, ServiceAbi},
+    linera_base_types::{AccountOwner, Amount, ApplicationId, ChainId, ContractAbi, CryptoHash, ServiceAbi, Timestamp},
 };
 use serde::{Deserialize, Serialize};
 
+pub mod chests;
+pub mod events;
+pub mod math;
+pub mod metrics;
+pub mod search;
+#[cfg(all(feature = "simulation", not(target_arch = "wasm32")))]
+pub mod simulation;
 pub mod state;
+pub mod units;
 
 // Re-export types for convenience
+pub use chests::{ChestDrop, ChestId, ChestTable};
+pub use events::{EventId, MarketCategory};
 pub use state::{
-    MarketId, PlayerId, OutcomeId, GuildId, AchievementId,
-    MarketType, MarketStatus, ResolutionMethod,
-    GameConfig, Market, Player, Guild, Leaderboard,
+    MarketId, PlayerId, OutcomeId, AuctionId, ShareOfferId, BotQuoteId, GuildId, AchievementId, ChallengeId, GuildStakeId, GuildWithdrawalId, ParlayId, ParlayLeg,
+    MarketType, MarketStatus, MarketVisibility, ResolutionMethod, ItemId, ItemKind, RaffleId, SeasonId, GroupId, BracketId, PassTier, LeagueTier,
+    AttestationId, AttestationClaim, AnnouncementSeverity,
+    CollusionConfig, ConfigError, CreatorMarketCap, CurveParams, FeeSchedule, GameConfig, MentorshipConfig, MentorshipMilestone, TradeXp, Market, MarketMetadata, MarketSpec, OutcomeDisplay, Evidence, OracleValue, ScalarRange,
+    Player, PrivacySettings, Guild, Leaderboard, NotificationFilter, MarketViewCount, MarketMakerLimits, TwoSidedQuote, QuoteSide,
 };
 
 pub struct PredictiveManagerAbi;
 
 impl ContractAbi for PredictiveManagerAbi {
     type Operation = Operation;
-    type Response = ();
+    type Response = OperationResponse;
 }
 
 impl ServiceAbi for PredictiveManagerAbi {
@@ -30,8 +45,20 @@ impl ServiceAbi for PredictiveManagerAbi {
 pub enum Operation {
     // Player operations
     RegisterPlayer { display_name: Option<String> },
-    UpdateProfile { display_name: Option<String> },
+    /// Set the display name, and the privacy settings unless `privacy` is unset
+    UpdateProfile {
+        display_name: Option<String>,
+        privacy: Option<PrivacySettings>,
+    },
     ClaimDailyReward,
+    /// Name an owner allowed to recover this account after `RECOVERY_DELAY_SECONDS`
+    LinkRecoveryOwner { owner: AccountOwner },
+    /// Move `old_player_id`'s account into the signer's, who must be its recovery owner
+    RecoverAccount { old_player_id: PlayerId },
+    /// Cap the tokens the signer can put into markets per day; `None` removes the cap
+    SetLossLimit { per_day: Option<Amount> },
+    /// Block the signer from putting tokens into markets until `until`
+    SelfExclude { until: Timestamp },
     
     // Market operations
     CreateMarket {
@@ -40,34 +67,446 @@ pub enum Operation {
         outcome_names: Vec<String>,
         duration_seconds: u64,
         resolution_method: ResolutionMethod,
+        payout_weights: Option<Vec<u32>>,
+        visibility: Option<MarketVisibility>,
+        allowed_guild: Option<GuildId>,
+        allowlist: Option<Vec<PlayerId>>,
+        metadata: Option<MarketMetadata>,
+        allow_early_close: Option<bool>,
+        /// Collect commitments for this long before trading opens
+        bootstrap_seconds: Option<u64>,
+        /// Take only sealed trades for this long before the end time
+        blind_finish_seconds: Option<u64>,
+    },
+    /// Commit tokens to an outcome while the market is bootstrapping
+    CommitToOutcome { market_id: MarketId, outcome_id: OutcomeId, amount: Amount },
+    /// Convert commitments to shares and open trading once the bootstrapping phase is over
+    FinalizeBootstrap { market_id: MarketId },
+    /// Escrow `amount` behind a sealed trade during a market's blind finish
+    CommitBlindTrade { market_id: MarketId, commitment: CryptoHash, amount: Amount },
+    /// Open a sealed trade after the market ends, buying shares with its escrowed tokens
+    RevealBlindTrade { market_id: MarketId, outcome_id: OutcomeId, salt: String },
+    UpdateMarket {
+        market_id: MarketId,
+        title: Option<String>,
+        description: Option<String>,
+        end_time: Option<Timestamp>,
+    },
+    /// Propose renaming a mislabeled outcome (creator only); applied once players holding a
+    /// majority of the market's shares approve, replacing any pending proposal
+    ProposeOutcomeCorrection { market_id: MarketId, outcome_id: OutcomeId, new_name: String },
+    /// Approve the market's pending outcome correction (holders only)
+    ApproveOutcomeCorrection { market_id: MarketId },
+    /// Flag a market in its review window as ambiguous; once the flaggers' reputation reaches
+    /// `ambiguity_pause_weight` the market pauses
+    FlagAmbiguousMarket { market_id: MarketId, reason: String },
+    /// Reopen a paused market: by the creator once they've edited it, restarting the review
+    /// window, or by the admin after reviewing the flags
+    ResumeMarket { market_id: MarketId },
+    CloseMarketEarly { market_id: MarketId },
+    ExitAtCostBasis { market_id: MarketId },
+    UpdateMarketAllowlist {
+        market_id: MarketId,
+        add: Vec<PlayerId>,
+        remove: Vec<PlayerId>,
     },
     BuyShares {
         market_id: MarketId,
         outcome_id: OutcomeId,
         amount: Amount,
         max_price_per_share: Amount,
+        /// The trade rejects if executed after this time
+        valid_until: Option<Timestamp>,
     },
     SellShares {
         market_id: MarketId,
         outcome_id: OutcomeId,
         shares: Amount,
         min_price_per_share: Amount,
+        /// The trade rejects if executed after this time
+        valid_until: Option<Timestamp>,
+    },
+    /// Buy `sets` shares of every outcome in one trade; selling the unwanted outcomes
+    /// afterwards shorts them
+    MintCompleteSet {
+        market_id: MarketId,
+        sets: Amount,
+        max_cost: Amount,
+    },
+    /// Sell `sets` shares of every outcome back to the market
+    BurnCompleteSet {
+        market_id: MarketId,
+        sets: Amount,
+        min_value: Amount,
+    },
+    /// Sell shares to other players through an auction whose price per share falls from
+    /// `start_price` to `floor_price` over `duration_seconds`
+    StartPositionAuction {
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+        start_price: Amount,
+        floor_price: Amount,
+        duration_seconds: u64,
+    },
+    /// Buy shares from a position auction at its current price
+    FillPositionAuction {
+        market_id: MarketId,
+        auction_id: AuctionId,
+        shares: Amount,
+        max_price_per_share: Amount,
+    },
+    /// Take the unsold shares of a position auction back (seller only)
+    CancelPositionAuction { market_id: MarketId, auction_id: AuctionId },
+    /// Give shares to another player without touching market liquidity
+    TransferShares {
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        to: PlayerId,
+        shares: Amount,
+    },
+    /// Offer shares for a fixed total `price`, to `buyer` only when set
+    CreateShareOffer {
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+        price: Amount,
+        buyer: Option<PlayerId>,
+    },
+    /// Buy an offer's shares; `price` must match the offer
+    AcceptShareOffer {
+        market_id: MarketId,
+        offer_id: ShareOfferId,
+        price: Amount,
+    },
+    /// Take an offer's shares back (seller only)
+    CancelShareOffer { market_id: MarketId, offer_id: ShareOfferId },
+
+    // Market maker operations
+    /// Approve a player as a market-maker bot, or update an approved bot's rebate and
+    /// limits (admin only)
+    ApproveMarketMaker { player: PlayerId, rebate_bps: u32, limits: MarketMakerLimits },
+    /// Stop a bot from posting quotes; its open quotes can still be filled or cancelled (admin only)
+    RevokeMarketMaker { player: PlayerId },
+    /// Post a batch of quotes, each replacing the bot's open quote on its outcome (market makers only)
+    PostTwoSidedQuotes { quotes: Vec<TwoSidedQuote> },
+    /// Trade `shares` against one side of a bot's quote; `price_per_share` must match that side
+    FillBotQuote {
+        market_id: MarketId,
+        quote_id: BotQuoteId,
+        side: QuoteSide,
+        shares: Amount,
+        price_per_share: Amount,
     },
     
     // Voting operations
     VoteOnOutcome {
         market_id: MarketId,
         outcome_id: OutcomeId,
+        /// Source backing the vote, kept with the market's resolution
+        evidence: Option<Evidence>,
     },
     TriggerResolution { market_id: MarketId },
+    ResolveMarket {
+        market_id: MarketId,
+        ranked_outcomes: Vec<OutcomeId>,
+        /// Source backing the resolution, kept with the market
+        evidence: Option<Evidence>,
+    },
+    /// Report the result of a `DesignatedOracle` market (its oracle account only): the winning
+    /// outcome, or the observed value for a scalar market
+    SubmitOracleResult {
+        market_id: MarketId,
+        outcome_id: Option<OutcomeId>,
+        value: Option<OracleValue>,
+    },
     ClaimWinnings { market_id: MarketId },
+    /// Stake tokens to become a resolver, or add to an existing stake
+    RegisterAsResolver { stake: Amount },
+    /// Stop being a resolver and get the stake back once no voted market is pending
+    WithdrawResolverStake,
+    /// Appoint or dismiss a member of the arbitration council (admin only)
+    SetArbitrator { member: PlayerId, is_arbitrator: bool },
+    /// Settle an oracle market whose vote ended contested with a binding ruling (council only)
+    ArbitrateMarket { market_id: MarketId, outcome: OutcomeId },
+    /// Bundle markets the signer created into a group settled by `resolver`, escrowing
+    /// `prize` for the best record across the group
+    CreateMarketGroup {
+        name: String,
+        market_ids: Vec<MarketId>,
+        resolver: PlayerId,
+        prize: Amount,
+    },
+    /// Resolve every market of a group at once (its resolver only); `results` holds the
+    /// ranked winners of each market, in the group's order
+    ResolveMarketGroup { group_id: GroupId, results: Vec<Vec<OutcomeId>> },
+    /// Open a knockout bracket; `teams` is a power of two in seeding order and the pool is
+    /// paid by `payout_weights`, all of it to the best score by default
+    CreateBracket {
+        name: String,
+        teams: Vec<String>,
+        entry_fee: Amount,
+        locks_at: Timestamp,
+        payout_weights: Option<Vec<u32>>,
+    },
+    /// Enter a bracket before it locks, picking the winning team index of every match
+    EnterBracket { bracket_id: BracketId, picks: Vec<u32> },
+    /// Name the market deciding a bracket match (bracket creator only)
+    LinkBracketMatch { bracket_id: BracketId, match_index: u32, market_id: MarketId },
+    /// Score a bracket match once its market resolves
+    ScoreBracketMatch { bracket_id: BracketId, match_index: u32 },
+    /// Notify winners who haven't claimed once a market's claim window is about to close
+    WarnExpiringClaims { market_id: MarketId },
+    /// Move winnings left unclaimed past the claim window to the treasury
+    SweepExpiredWinnings { market_id: MarketId },
+    /// Replace a long-settled market with its summary
+    ArchiveMarket { market_id: MarketId },
+    /// Rate a resolved market you participated in; feeds the creator's quality score
+    RateMarket { market_id: MarketId, thumbs_up: bool },
+    /// Have `MarketResolved` delivered to `chain_id` when the market resolves
+    SubscribeToMarket { market_id: MarketId, chain_id: ChainId },
     
+    // Head-to-head operations
+    /// Challenge `opponent` to a two-outcome bet; the challenger backs the first outcome
+    CreateHeadToHead {
+        opponent: PlayerId,
+        outcome_names: Vec<String>,
+        stake: Amount,
+        duration_seconds: u64,
+    },
+    /// Match the stake and start the challenge (opponent only)
+    AcceptChallenge { challenge_id: ChallengeId },
+    /// Withdraw a challenge that was not accepted yet (challenger only)
+    CancelChallenge { challenge_id: ChallengeId },
+    /// Report the winning outcome after the end time; settles once both players agree, or on
+    /// the reports already in once the report window is over
+    ReportChallengeOutcome { challenge_id: ChallengeId, outcome_id: OutcomeId },
+
+    // Mentorship operations
+    /// Offer to mentor `apprentice`; they become your apprentice once they accept
+    OfferMentorship { apprentice: PlayerId },
+    /// Become the apprentice of a mentor who offered (apprentice only)
+    AcceptMentorship { mentor: PlayerId },
+    /// Turn down a mentor's offer (apprentice only)
+    DeclineMentorship { mentor: PlayerId },
+    /// End a mentorship; either the apprentice or their mentor may
+    EndMentorship { apprentice: PlayerId },
+
+    // Parlay operations
+    /// Bet `stake` on every leg winning, at the odds implied by current prices less the house edge
+    CreateParlay { legs: Vec<ParlayLeg>, stake: Amount },
+    /// Settle a parlay once a leg has lost or every leg has won
+    SettleParlay { parlay_id: ParlayId },
+
+    // Network operations
+    /// Register this chain with the home chain to receive its config and report player stats
+    JoinNetwork,
+
     // Guild operations
     CreateGuild { name: String },
     JoinGuild { guild_id: GuildId },
     LeaveGuild,
     ContributeToGuild { amount: Amount },
+    /// Grant or revoke officer rights for a guild member (founder only)
+    SetGuildOfficer { member: PlayerId, is_officer: bool },
+    /// Post to the guild's announcement board (officers only)
+    PostGuildAnnouncement { text: String },
+    /// Propose investing the guild's shared pool in a market outcome (officers only)
+    GuildBuyShares { market_id: MarketId, outcome_id: OutcomeId, amount: Amount },
+    /// Approve a pending staking proposal; it executes once enough officers approve
+    ApproveGuildStake { proposal_id: GuildStakeId },
+    /// Set how many officer approvals staking proposals need (founder only)
+    SetGuildStakeApprovals { approvals: u32 },
+    /// Pay the guild's winnings from a resolved market into its shared pool (officers only)
+    ClaimGuildWinnings { market_id: MarketId },
+    /// Choose the fungible token application the guild's token pool holds, or none; the pool
+    /// must be empty (founder only)
+    SetGuildTokenApplication { application_id: Option<ApplicationId> },
+    /// Move the signer's tokens in the guild's token application into the guild's token pool
+    DepositGuildTokens { amount: Amount },
+    /// Propose paying tokens out of the guild's token pool to an account on this chain
+    /// (officers only); it executes once as many officers approve as staking proposals need
+    WithdrawGuildTokens { amount: Amount, recipient: AccountOwner },
+    /// Approve a pending token pool withdrawal; it executes once enough officers approve
+    ApproveGuildTokenWithdrawal { withdrawal_id: GuildWithdrawalId },
+    /// Compare the tokens held in a token application with the guild pools using it
+    ReconcileGuildTokens { application_id: ApplicationId },
     
+    // Emergency operations
+    /// Reclaim cost basis in unresolved markets and winnings in resolved ones (emergency mode only)
+    EmergencyWithdraw,
+
     // Admin operations
+    /// Schedule a config change for the earliest time the config's notice period allows
     UpdateGameConfig { config: GameConfig },
+    /// Suspend or resume all market activity on this chain
+    SetEmergencyMode { enabled: bool },
+    /// Show an announcement on every chain of the network until `expires_at` or until it is
+    /// replaced or cleared
+    SetAnnouncement { text: String, severity: AnnouncementSeverity, expires_at: Option<Timestamp> },
+    /// Remove the current announcement
+    ClearAnnouncement,
+    /// Choose which market resolutions and trades are recorded in the notification outbox
+    SetNotificationFilter { filter: NotificationFilter },
+    /// Schedule a config change at least the config's notice period ahead; it replaces any
+    /// change already scheduled
+    ProposeConfigChange { config: GameConfig, effective_at: Timestamp },
+    /// Drop the scheduled config change
+    CancelConfigChange,
+    /// Offer the admin role to another player, who takes it over with `AcceptAdmin`
+    TransferAdmin { new_admin: PlayerId },
+    /// Take over the admin role offered to the signer
+    AcceptAdmin,
+    /// Give up the admin role for good; after this only changes already scheduled apply
+    RenounceAdmin,
+    /// Create up to `MAX_IMPORT_MARKETS` curated markets at once, free of creation costs;
+    /// the response says which were created and why any weren't
+    ImportMarkets { markets: Vec<MarketSpec> },
+    /// Schedule a special event; multipliers default to leaving XP and fees unchanged
+    ScheduleEvent {
+        name: String,
+        starts_at: Timestamp,
+        ends_at: Timestamp,
+        xp_multiplier_bps: Option<u32>,
+        fee_multiplier_bps: Option<u32>,
+        category: Option<MarketCategory>,
+    },
+    /// Call off a special event, even one already running
+    CancelEvent { event_id: EventId },
+    /// Translate an achievement's name and description into `locale`
+    TranslateAchievement { achievement_id: AchievementId, locale: String, name: String, description: String },
+
+    // Cosmetic shop
+    /// Put a cosmetic item up for sale (admin only)
+    CreateShopItem { name: String, kind: ItemKind, price: Amount, max_supply: Option<u64> },
+    /// Put a shop item on or off sale (admin only)
+    SetShopItemAvailable { item_id: ItemId, available: bool },
+    /// Buy a cosmetic item; its price is burned
+    PurchaseItem { item_id: ItemId },
+    /// Wear an owned item in its slot, replacing whatever was there
+    EquipItem { item_id: ItemId },
+    /// Empty a cosmetic slot
+    UnequipItem { kind: ItemKind },
+
+    // Raffles
+    /// Open a raffle selling tickets for `duration_seconds` (admin only)
+    CreateRaffle {
+        ticket_price: Amount,
+        duration_seconds: u64,
+        winner_count: u32,
+        treasury_top_up: Amount,
+        recurring: bool,
+    },
+    /// Buy tickets in an open raffle
+    BuyRaffleTickets { raffle_id: RaffleId, tickets: u64 },
+    /// Draw an ended raffle's winners and pay them; anyone can trigger it
+    DrawRaffle { raffle_id: RaffleId },
+
+    // Mystery chests
+    /// Replace the chest drop table and how often chests are earned (admin only)
+    SetChestTable { table: ChestTable },
+    /// Open an earned chest and collect its contents
+    OpenChest { chest_id: ChestId },
+
+    // Season pass
+    /// Schedule a season with its pass tiers (admin only)
+    CreateSeason {
+        name: String,
+        starts_at: Timestamp,
+        duration_seconds: u64,
+        premium_price: Amount,
+        tiers: Vec<PassTier>,
+    },
+    /// Replace the tiers of a season that hasn't started (admin only)
+    SetPassTiers { season_id: SeasonId, tiers: Vec<PassTier> },
+    /// Unlock the premium track of a season's pass; the price is burned
+    BuyPremiumPass { season_id: SeasonId },
+    /// Collect the rewards of every tier reached and not yet paid
+    ClaimPassRewards { season_id: SeasonId },
+
+    // Guild leagues
+    /// Set the tokens each rank of a league is paid per season, best rank first (admin only)
+    SetLeagueRewards { tier: LeagueTier, rewards: Vec<Amount> },
+    /// Rank the guild leagues for an ended season, paying rewards and applying promotion and
+    /// relegation; anyone can trigger it
+    SettleGuildLeagues { season_id: SeasonId },
+
+    // Leaderboards
+    /// Rebuild the leaderboards and rank index, for a small reward; allowed once every
+    /// `leaderboard_refresh_interval_seconds`
+    RefreshLeaderboard,
+
+    // Featured market
+    /// Line up markets to be featured on the coming days, one a day, replacing the current
+    /// list (admin only)
+    SetFeaturedMarkets { market_ids: Vec<MarketId> },
+    /// Choose today's featured market ahead of the day's first trade; anyone can trigger it
+    SelectFeaturedMarket,
+    /// Count a view of an open market, once a market a day per player
+    RecordView { market_id: MarketId },
+    /// Count views a frontend batched up (engagement relayer only)
+    RecordViews { views: Vec<MarketViewCount> },
+
+    // Attestations
+    /// Issue an attestation of something the signer achieved, optionally sending it to another
+    /// chain where other applications or airdrop programs can check it
+    RequestAttestation { claim: AttestationClaim, recipient: Option<ChainId> },
+}
+
+/// What an operation created or traded, so clients don't have to query for it
+/// Operations that create nothing, fail, or are blocked in emergency mode return `Done`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum OperationResponse {
+    #[default]
+    Done,
+    MarketCreated { market_id: MarketId },
+    /// For each imported market in batch order, its ID or why it couldn't be created
+    MarketsImported { results: Vec<Result<MarketId, String>> },
+    SharesBought(TradeFill),
+    SharesSold(TradeFill),
+    AuctionStarted { market_id: MarketId, auction_id: AuctionId },
+    ShareOfferCreated { market_id: MarketId, offer_id: ShareOfferId },
+    /// For each posted quote in batch order, its ID on its market, or `None` for a cancellation
+    QuotesPosted { quote_ids: Vec<Option<BotQuoteId>> },
+    MarketGroupCreated { group_id: GroupId },
+    BracketCreated { bracket_id: BracketId },
+    ChallengeCreated { challenge_id: ChallengeId },
+    ParlayCreated { parlay_id: ParlayId },
+    GuildCreated { guild_id: GuildId },
+    GuildStakeProposed { proposal_id: GuildStakeId },
+    GuildWithdrawalProposed { withdrawal_id: GuildWithdrawalId },
+    ShopItemCreated { item_id: ItemId },
+    RaffleCreated { raffle_id: RaffleId },
+    SeasonCreated { season_id: SeasonId },
+    AttestationIssued { attestation_id: AttestationId, hash: CryptoHash },
+    EventScheduled { event_id: EventId },
+    ChestOpened { chest_id: ChestId, contents: ChestDrop },
+}
+
+/// A trade against a market's curve
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct TradeFill {
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
+    /// Shares bought or sold; a buy capped by the price impact limit gets fewer than asked
+    pub shares: Amount,
+    /// Tokens paid for a buy, or the value of the shares sold
+    pub gross: Amount,
+    /// Trading fee taken out of `gross`
+    pub fee: Amount,
+    /// Tokens that bought the shares, or that the seller received
+    pub net: Amount,
+}
+
+impl TradeFill {
+    pub fn new(market_id: MarketId, outcome_id: OutcomeId, shares: Amount, trade: units::TradeAmounts) -> Self {
+        TradeFill {
+            market_id,
+            outcome_id,
+            shares,
+            gross: trade.gross.amount(),
+            fee: trade.fee.amount(),
+            net: trade.net.amount(),
+        }
+    }
 }
@@ -0,0 +1,659 @@
+// Pure pricing, fee, payout and XP math shared by the contract, the service and the tests
+//
+// All quantities are plain integers in attos (10^-18 of a token or of a share), so nothing
+// here depends on `Amount`, async code or application state.
+
+/// One whole token or share, in attos
+pub const ONE: u128 = 1_000_000_000_000_000_000;
+/// Denominator for payout weights and other ratios expressed in basis points
+pub const BASIS_POINTS: u32 = 10_000;
+/// Trading fee charged on every buy and sell (0.5%)
+pub const TRADING_FEE_BASIS_POINTS: u32 = 50;
+/// Experience needed per level: reaching level `n + 1` from level `n` costs `n * XP_PER_LEVEL`
+pub const XP_PER_LEVEL: u64 = 100;
+/// Points for a correct first-round bracket pick; each later round is worth twice the last
+pub const BRACKET_ROUND_ONE_POINTS: u64 = 10;
+/// Default ratio between the price cap and the base price of a curve
+pub const DEFAULT_PRICE_CAP_MULTIPLIER: u32 = 2;
+/// Default number of outstanding shares (in attos) at which an outcome reaches its price cap
+pub const DEFAULT_CURVE_DEPTH: u128 = 1_000 * ONE;
+
+/// Compute `a * b / c` rounding down, with a 256-bit intermediate product
+/// Saturates at `u128::MAX` when the quotient does not fit; returns 0 when `c` is 0
+pub fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    if c == 0 {
+        return 0;
+    }
+    if let Some(product) = a.checked_mul(b) {
+        return product / c;
+    }
+    let (high, low) = wide_mul(a, b);
+    if high >= c {
+        return u128::MAX;
+    }
+    // Long division of the 256-bit product, one bit at a time
+    let mut remainder = high;
+    let mut quotient = 0u128;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= c {
+            remainder = remainder.wrapping_sub(c);
+            quotient |= 1;
+        }
+    }
+    quotient
+}
+
+/// Take `basis_points / 10_000` of `amount`, rounding down
+pub fn apply_basis_points(amount: u128, basis_points: u32) -> u128 {
+    mul_div(amount, basis_points as u128, BASIS_POINTS as u128)
+}
+
+/// Take `percent / 100` of `amount`, rounding down
+pub fn apply_percent(amount: u128, percent: u8) -> u128 {
+    mul_div(amount, percent as u128, 100)
+}
+
+/// Full 256-bit product of two u128 values, as (high, low) halves
+fn wide_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+
+    let middle = (low_low >> 64) + (high_low & MASK) + (low_high & MASK);
+    let low = (low_low & MASK) | (middle << 64);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+    (high, low)
+}
+
+/// Per-outcome bonding curve: the price of a share rises linearly with the outcome's
+/// outstanding shares from `base_price` at zero to `price_cap` at `depth`, then stays flat
+///
+/// Prices are in token attos per whole share. Because the cost of a position only depends
+/// on the outstanding shares, buying and then selling the same shares returns at most what
+/// was paid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BondingCurve {
+    pub base_price: u128,
+    pub price_cap: u128,
+    pub depth: u128,
+}
+
+impl BondingCurve {
+    /// Build a curve, clamping the cap to at least the base price and the depth to at least one atto
+    pub fn new(base_price: u128, price_cap: u128, depth: u128) -> Self {
+        Self {
+            base_price,
+            price_cap: price_cap.max(base_price),
+            depth: depth.max(1),
+        }
+    }
+
+    /// Curve whose cap is `price_cap_multiplier` times the base price
+    pub fn with_multiplier(base_price: u128, price_cap_multiplier: u32, depth: u128) -> Self {
+        Self::new(base_price, base_price.saturating_mul(u128::from(price_cap_multiplier)), depth)
+    }
+
+    /// Marginal price of the next share when `supply` shares are outstanding
+    pub fn price_at(&self, supply: u128) -> u128 {
+        let slope_range = self.price_cap - self.base_price;
+        self.base_price + mul_div(slope_range, supply.min(self.depth), self.depth)
+    }
+
+    /// Total cost of buying the first `supply` shares, rounded down
+    pub fn cost(&self, supply: u128) -> u128 {
+        let sloped = supply.min(self.depth);
+        // Average price over the sloped part is the midpoint of its start and end prices
+        let average_price = self.base_price
+            + mul_div(self.price_cap - self.base_price, sloped, 2 * self.depth);
+        let sloped_cost = mul_div(sloped, average_price, ONE);
+        let flat_cost = mul_div(supply - sloped, self.price_cap, ONE);
+        sloped_cost.saturating_add(flat_cost)
+    }
+
+    /// Cost of buying `shares` more shares when `supply` are outstanding
+    pub fn buy_cost(&self, supply: u128, shares: u128) -> u128 {
+        self.cost(supply.saturating_add(shares)) - self.cost(supply)
+    }
+
+    /// Most shares that `amount` buys when `supply` are outstanding
+    pub fn shares_for_amount(&self, supply: u128, amount: u128) -> u128 {
+        if self.base_price == 0 {
+            return 0;
+        }
+        // Every share costs at least the base price, which bounds the search
+        let mut low = 0;
+        let mut high = mul_div(amount, ONE, self.base_price).saturating_add(1);
+        while low < high {
+            let middle = low + (high - low).div_ceil(2);
+            if self.buy_cost(supply, middle) <= amount {
+                low = middle;
+            } else {
+                high = middle - 1;
+            }
+        }
+        low
+    }
+
+    /// Value returned for selling `shares` when `supply` are outstanding
+    pub fn sell_value(&self, supply: u128, shares: u128) -> u128 {
+        let shares = shares.min(supply);
+        self.cost(supply) - self.cost(supply - shares)
+    }
+}
+
+/// Most of `amount` that can buy into an outcome priced on `curve`, with `supply` shares
+/// outstanding, without raising its implied probability by more than `max_impact_bps`
+/// The implied probability is the outcome's price as a share of `total_price`, the sum of
+/// every outcome's price
+pub fn cap_buy_for_impact(
+    curve: &BondingCurve,
+    supply: u128,
+    total_price: u128,
+    amount: u128,
+    max_impact_bps: u32,
+) -> u128 {
+    let other_prices = total_price.saturating_sub(curve.price_at(supply));
+    let probability = |supply: u128| {
+        let price = curve.price_at(supply);
+        mul_div(price, BASIS_POINTS as u128, other_prices + price)
+    };
+    let limit = probability(supply) + u128::from(max_impact_bps);
+    // The price stops rising at the curve's depth, so no buy can move it further
+    let mut low = 0;
+    let mut high = curve.depth.saturating_sub(supply);
+    if probability(supply + high) <= limit {
+        return amount;
+    }
+    while low < high {
+        let middle = low + (high - low).div_ceil(2);
+        if probability(supply + middle) <= limit {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    amount.min(curve.buy_cost(supply, low))
+}
+
+/// How a fee is divided; the parts always add up to the whole fee
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub burned: u128,
+    pub creator: u128,
+    pub platform: u128,
+}
+
+impl FeeSplit {
+    /// The whole fee
+    pub fn total(&self) -> u128 {
+        self.burned + self.creator + self.platform
+    }
+
+    /// The part of the fee that is paid out rather than burned
+    pub fn distributed(&self) -> u128 {
+        self.creator + self.platform
+    }
+}
+
+/// Relative move from `price_before` to `price_after`, in basis points of `price_before`
+pub fn price_impact_bps(price_before: u128, price_after: u128) -> u128 {
+    mul_div(price_before.abs_diff(price_after), BASIS_POINTS as u128, price_before)
+}
+
+/// Fee rate in basis points for a trade that moved a price by `impact_bps`, placed
+/// `seconds_left` before the market's close: `base_bps`, plus `impact_percent` of the impact,
+/// plus a surcharge rising linearly to `late_bps` over the last `late_window_seconds`,
+/// capped at `max_bps`
+pub fn dynamic_fee_bps(
+    base_bps: u32,
+    impact_bps: u128,
+    impact_percent: u8,
+    seconds_left: u64,
+    late_window_seconds: u64,
+    late_bps: u32,
+    max_bps: u32,
+) -> u32 {
+    let impact_fee = apply_percent(impact_bps, impact_percent);
+    let late_fee = if seconds_left < late_window_seconds {
+        mul_div(
+            u128::from(late_bps),
+            u128::from(late_window_seconds - seconds_left),
+            u128::from(late_window_seconds),
+        )
+    } else {
+        0
+    };
+    let fee = u128::from(base_bps) + impact_fee + late_fee;
+    fee.min(u128::from(max_bps)) as u32
+}
+
+/// Trading fee of `fee_bps` on a trade of `trade_amount`: `burn_percent` of it is burned and the
+/// rest is shared equally between the market creator and the platform (the platform gets any
+/// odd atto)
+pub fn trading_fee(trade_amount: u128, fee_bps: u32, burn_percent: u8) -> FeeSplit {
+    let fee = apply_basis_points(trade_amount, fee_bps);
+    let burned = apply_percent(fee, burn_percent);
+    let creator = (fee - burned) / 2;
+    FeeSplit {
+        burned,
+        creator,
+        platform: fee - burned - creator,
+    }
+}
+
+/// Market creation fee rewards: percentages of the creation cost credited to the creator
+/// and the platform
+pub fn creation_fee(
+    creation_cost: u128,
+    creator_fee_percent: u8,
+    platform_fee_percent: u8,
+) -> FeeSplit {
+    FeeSplit {
+        burned: 0,
+        creator: apply_percent(creation_cost, creator_fee_percent),
+        platform: apply_percent(creation_cost, platform_fee_percent),
+    }
+}
+
+/// Payout for shares held on each winning outcome, in rank order, weighted by the payout
+/// weight (in basis points) of that rank
+pub fn ranked_payout(shares_by_rank: &[u128], payout_weights: &[u32]) -> u128 {
+    shares_by_rank
+        .iter()
+        .zip(payout_weights)
+        .fold(0, |total: u128, (shares, weight)| {
+            total.saturating_add(apply_basis_points(*shares, *weight))
+        })
+}
+
+/// Payout multiplier of a parlay in attos (`ONE` returns the stake), given for each leg the
+/// price of the picked outcome and the sum of all outcome prices in its market
+///
+/// Each leg multiplies the odds by the inverse of the picked outcome's implied probability,
+/// and the house edge then takes `house_edge_bps` off the result.
+pub fn parlay_odds(legs: &[(u128, u128)], house_edge_bps: u32) -> u128 {
+    let fair = legs
+        .iter()
+        .fold(ONE, |odds, (price, total_price)| mul_div(odds, *total_price, *price));
+    mul_div(fair, BASIS_POINTS.saturating_sub(house_edge_bps) as u128, BASIS_POINTS as u128)
+}
+
+/// XP a trade staking `stake` out of the `balance` held before it earns towards `xp`: all of
+/// it from `full_stake_bps` of the balance up, and below that the square root of the stake's
+/// share of the threshold, so smaller trades earn proportionally more per token and dust earns
+/// nothing; every trade earns it all for a zero threshold
+pub fn stake_scaled_xp(xp: u64, stake: u128, balance: u128, full_stake_bps: u32) -> u64 {
+    if full_stake_bps == 0 {
+        return xp;
+    }
+    let full_stake = mul_div(balance, full_stake_bps as u128, BASIS_POINTS as u128);
+    if stake >= full_stake {
+        return xp;
+    }
+    // Square root of the stake's share of the threshold, both in basis points
+    let root_bps = (mul_div(stake, BASIS_POINTS as u128, full_stake) * BASIS_POINTS as u128).isqrt();
+    mul_div(xp as u128, root_bps, BASIS_POINTS as u128) as u64
+}
+
+/// Points for a correct pick in a bracket round, counting rounds from zero
+pub fn bracket_round_points(round: u32) -> u64 {
+    BRACKET_ROUND_ONE_POINTS.saturating_mul(1u64.checked_shl(round).unwrap_or(u64::MAX))
+}
+
+/// Round and position within the round of a bracket match for `teams` teams
+///
+/// Matches are numbered round by round, so the first round holds matches `0..teams / 2`
+/// and the final is match `teams - 2`.
+pub fn bracket_match_round(teams: usize, match_index: usize) -> Option<(u32, usize)> {
+    let mut round = 0;
+    let mut offset = 0;
+    let mut matches = teams / 2;
+    while matches > 0 {
+        if match_index < offset + matches {
+            return Some((round, match_index - offset));
+        }
+        offset += matches;
+        matches /= 2;
+        round += 1;
+    }
+    None
+}
+
+/// Experience required to advance from `level` to the next level
+pub fn xp_to_next_level(level: u32) -> u64 {
+    (level as u64).saturating_mul(XP_PER_LEVEL)
+}
+
+/// Add `xp` to a player at `level` holding `experience`, returning the new level and the
+/// experience left over towards the following level
+pub fn apply_experience(level: u32, experience: u64, xp: u64) -> (u32, u64) {
+    let mut level = level.max(1);
+    let mut experience = experience.saturating_add(xp);
+    while experience >= xp_to_next_level(level) {
+        experience -= xp_to_next_level(level);
+        level += 1;
+    }
+    (level, experience)
+}
+
+/// Round `value` up to a multiple of `step`; a zero step leaves it unchanged
+pub fn round_up_to_multiple(value: u64, step: u64) -> u64 {
+    if step == 0 {
+        return value;
+    }
+    value.div_ceil(step).saturating_mul(step)
+}
+
+/// Average of `a` and `b` weighted by `weight_a` and `weight_b`, rounding towards `a`;
+/// `a` when both weights are zero
+pub fn weighted_average(a: u64, weight_a: u128, b: u64, weight_b: u128) -> u64 {
+    let total = weight_a.saturating_add(weight_b);
+    let shift = mul_div(a.abs_diff(b) as u128, weight_b, total) as u64;
+    if b >= a {
+        a.saturating_add(shift)
+    } else {
+        a.saturating_sub(shift)
+    }
+}
+
+/// Bonus XP for a winning position opened at `entry_time`: the full `max_bonus` when the market
+/// was created, falling linearly to nothing at its end time (all times in microseconds)
+pub fn early_conviction_xp(max_bonus: u64, created_at: u64, end_time: u64, entry_time: u64) -> u64 {
+    if end_time <= created_at {
+        return 0;
+    }
+    let remaining = end_time.saturating_sub(entry_time.max(created_at));
+    mul_div(max_bonus as u128, remaining as u128, (end_time - created_at) as u128) as u64
+}
+
+/// Price of a descending auction at `now`: `start_price` at `starts_at`, falling linearly to
+/// `floor_price` at `ends_at` and staying there (all times in microseconds)
+pub fn dutch_auction_price(start_price: u128, floor_price: u128, starts_at: u64, ends_at: u64, now: u64) -> u128 {
+    if now >= ends_at || ends_at <= starts_at {
+        return floor_price;
+    }
+    let elapsed = now.saturating_sub(starts_at);
+    let drop = mul_div(start_price.saturating_sub(floor_price), elapsed as u128, (ends_at - starts_at) as u128);
+    start_price.saturating_sub(drop).max(floor_price)
+}
+
+/// Index of the bucket `value` falls in when `[min, max]` is split into `buckets` equal parts,
+/// `max` itself belonging to the last one; `None` for values outside the range
+pub fn scalar_bucket(value: i64, min: i64, max: i64, buckets: u32) -> Option<u32> {
+    if buckets == 0 || min >= max || value < min || value > max {
+        return None;
+    }
+    let offset = (i128::from(value) - i128::from(min)) as u128;
+    let span = (i128::from(max) - i128::from(min)) as u128;
+    Some((mul_div(offset, u128::from(buckets), span) as u32).min(buckets - 1))
+}
+
+/// Share of markets won in basis points, 0 for a player who hasn't traded
+pub fn win_rate(markets_won: u64, markets_participated: u64) -> u32 {
+    if markets_participated == 0 {
+        return 0;
+    }
+    let rate = mul_div(markets_won as u128, BASIS_POINTS as u128, markets_participated as u128);
+    rate.min(BASIS_POINTS as u128) as u32
+}
+
+/// Brier score of a forecast that gave an outcome `probability_bps`, in basis points: 0 for
+/// certainty about what happened, `BASIS_POINTS` for certainty about what didn't
+pub fn brier_score_bps(probability_bps: u32, happened: bool) -> u64 {
+    let target = if happened { BASIS_POINTS } else { 0 };
+    let error = u64::from(probability_bps.min(BASIS_POINTS).abs_diff(target));
+    error * error / u64::from(BASIS_POINTS)
+}
+
+/// Forecast accuracy in basis points, `BASIS_POINTS` less the mean Brier score; `None` before
+/// any forecast has been scored
+pub fn forecast_accuracy_bps(brier_score_total: u64, forecasts_scored: u64) -> Option<u32> {
+    if forecasts_scored == 0 {
+        return None;
+    }
+    let mean = (brier_score_total / forecasts_scored).min(u64::from(BASIS_POINTS));
+    Some(BASIS_POINTS - mean as u32)
+}
+
+/// Convert a win rate stored by older versions as the bits of an `f64` percentage into basis
+/// points, using integer arithmetic only; negative and non-finite values become 0
+pub fn legacy_percent_bits_to_basis_points(bits: u64) -> u32 {
+    let exponent = ((bits >> 52) & 0x7ff) as i64;
+    if bits >> 63 == 1 || exponent == 0x7ff || exponent == 0 {
+        return 0;
+    }
+    // value = mantissa * 2^(exponent - 1075), so basis points = value * 100
+    let mantissa = ((bits & ((1 << 52) - 1)) | (1 << 52)) as u128;
+    let shift = 1075 - exponent;
+    let basis_points = if shift >= 128 {
+        0
+    } else if shift >= 0 {
+        (mantissa * 100) >> shift
+    } else {
+        BASIS_POINTS as u128
+    };
+    basis_points.min(BASIS_POINTS as u128) as u32
+}
+
+/// Creator quality score in basis points: 60% resolution reliability (resolutions that were
+/// not disputed) and 40% participant satisfaction (share of thumbs up)
+///
+/// Both parts are smoothed with one good and one bad pseudo-observation, so a creator without
+/// history scores 5_000 and a few ratings cannot swing the score to an extreme.
+pub fn creator_quality_score(
+    markets_resolved: u64,
+    disputed_resolutions: u64,
+    thumbs_up: u64,
+    thumbs_down: u64,
+) -> u32 {
+    let clean = markets_resolved.saturating_sub(disputed_resolutions) as u128;
+    let reliability = mul_div(clean + 1, BASIS_POINTS as u128, markets_resolved as u128 + 2);
+    let ratings = thumbs_up as u128 + thumbs_down as u128;
+    let satisfaction = mul_div(thumbs_up as u128 + 1, BASIS_POINTS as u128, ratings + 2);
+    ((reliability * 6 + satisfaction * 4) / 10) as u32
+}
+
+/// Gini coefficient of `balances` in basis points: 0 when everyone holds the same, nearing
+/// `BASIS_POINTS` as one holder has it all; 0 for no balances or none above zero
+pub fn gini_bps(balances: &[u128]) -> u32 {
+    let mut sorted = balances.to_vec();
+    sorted.sort_unstable();
+    let count = sorted.len() as u128;
+    let total = sorted.iter().fold(0u128, |total, balance| total.saturating_add(*balance));
+    if total == 0 {
+        return 0;
+    }
+    // G = 2 * sum(i * x_i) / (n * sum(x)) - (n + 1) / n, with holders ranked from 1 upwards
+    let weighted = sorted
+        .iter()
+        .zip(1u128..)
+        .fold(0u128, |weighted, (balance, rank)| weighted.saturating_add(mul_div(*balance, rank, count)));
+    let scaled = mul_div(2 * weighted, BASIS_POINTS as u128, total);
+    let offset = mul_div(count + 1, BASIS_POINTS as u128, count);
+    scaled.saturating_sub(offset).min(BASIS_POINTS as u128) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounding_up_to_multiples() {
+        assert_eq!(round_up_to_multiple(307, 60), 360);
+        assert_eq!(round_up_to_multiple(360, 60), 360);
+        assert_eq!(round_up_to_multiple(0, 60), 0);
+        assert_eq!(round_up_to_multiple(307, 0), 307);
+    }
+
+    #[test]
+    fn early_conviction_xp_decays_linearly() {
+        assert_eq!(early_conviction_xp(50, 100, 200, 100), 50);
+        assert_eq!(early_conviction_xp(50, 100, 200, 150), 25);
+        assert_eq!(early_conviction_xp(50, 100, 200, 200), 0);
+        assert_eq!(early_conviction_xp(50, 100, 200, 250), 0);
+        assert_eq!(early_conviction_xp(50, 200, 200, 200), 0);
+    }
+
+    #[test]
+    fn weighted_average_leans_to_the_heavier_side() {
+        assert_eq!(weighted_average(100, 3, 200, 1), 125);
+        assert_eq!(weighted_average(200, 1, 100, 3), 125);
+        assert_eq!(weighted_average(100, 0, 200, 5 * ONE), 200);
+        assert_eq!(weighted_average(100, 0, 200, 0), 100);
+    }
+
+    #[test]
+    fn dutch_auction_price_falls_to_the_floor() {
+        assert_eq!(dutch_auction_price(100, 40, 1_000, 2_000, 500), 100);
+        assert_eq!(dutch_auction_price(100, 40, 1_000, 2_000, 1_000), 100);
+        assert_eq!(dutch_auction_price(100, 40, 1_000, 2_000, 1_500), 70);
+        assert_eq!(dutch_auction_price(100, 40, 1_000, 2_000, 2_000), 40);
+        assert_eq!(dutch_auction_price(100, 40, 1_000, 2_000, 9_000), 40);
+    }
+
+    #[test]
+    fn scalar_buckets_split_the_range_evenly() {
+        assert_eq!(scalar_bucket(0, 0, 100, 4), Some(0));
+        assert_eq!(scalar_bucket(24, 0, 100, 4), Some(0));
+        assert_eq!(scalar_bucket(25, 0, 100, 4), Some(1));
+        assert_eq!(scalar_bucket(100, 0, 100, 4), Some(3));
+        assert_eq!(scalar_bucket(-5, -10, 10, 2), Some(0));
+        assert_eq!(scalar_bucket(i64::MAX, i64::MIN, i64::MAX, 2), Some(1));
+        assert_eq!(scalar_bucket(101, 0, 100, 4), None);
+        assert_eq!(scalar_bucket(5, 10, 10, 4), None);
+    }
+
+    #[test]
+    fn win_rate_and_legacy_conversion() {
+        assert_eq!(win_rate(0, 0), 0);
+        assert_eq!(win_rate(1, 3), 3_333);
+        assert_eq!(win_rate(5, 4), BASIS_POINTS);
+        assert_eq!(legacy_percent_bits_to_basis_points(0f64.to_bits()), 0);
+        assert_eq!(legacy_percent_bits_to_basis_points(50f64.to_bits()), 5_000);
+        assert_eq!(legacy_percent_bits_to_basis_points(100f64.to_bits()), BASIS_POINTS);
+        assert_eq!(legacy_percent_bits_to_basis_points(12.5f64.to_bits()), 1_250);
+        assert_eq!(legacy_percent_bits_to_basis_points(f64::NAN.to_bits()), 0);
+    }
+
+    #[test]
+    fn brier_scores_and_accuracy() {
+        assert_eq!(brier_score_bps(BASIS_POINTS, true), 0);
+        assert_eq!(brier_score_bps(BASIS_POINTS, false), u64::from(BASIS_POINTS));
+        assert_eq!(brier_score_bps(5_000, true), 2_500);
+        assert_eq!(brier_score_bps(5_000, false), 2_500);
+        assert_eq!(brier_score_bps(2_000, false), 400);
+        assert_eq!(forecast_accuracy_bps(0, 0), None);
+        assert_eq!(forecast_accuracy_bps(2_500 + 400, 2), Some(8_550));
+    }
+
+    #[test]
+    fn trading_fee_split() {
+        // 0.5% of 100 tokens is 0.5 token; 20% of it burned, the rest halved
+        let split = trading_fee(100 * ONE, TRADING_FEE_BASIS_POINTS, 20);
+        assert_eq!(split.total(), ONE / 2);
+        assert_eq!(split.burned, ONE / 10);
+        assert_eq!(split.creator, ONE / 5);
+        assert_eq!(split.platform, ONE / 5);
+    }
+
+    #[test]
+    fn trading_fee_keeps_odd_atto() {
+        let split = trading_fee(600, TRADING_FEE_BASIS_POINTS, 0);
+        assert_eq!(split, FeeSplit { burned: 0, creator: 1, platform: 2 });
+    }
+
+    #[test]
+    fn dynamic_fee_scales_with_impact_and_close() {
+        // Flat base far from close with no impact
+        assert_eq!(dynamic_fee_bps(50, 0, 10, 3_600, 600, 200, 500), 50);
+        // A 10% price move adds 10% of 1,000 bps
+        assert_eq!(dynamic_fee_bps(50, 1_000, 10, 3_600, 600, 200, 500), 150);
+        // Halfway into the late window adds half the surcharge, at close all of it
+        assert_eq!(dynamic_fee_bps(50, 0, 10, 300, 600, 200, 500), 150);
+        assert_eq!(dynamic_fee_bps(50, 0, 10, 0, 600, 200, 500), 250);
+        // Never more than the cap
+        assert_eq!(dynamic_fee_bps(50, 5_000, 10, 0, 600, 200, 500), 500);
+    }
+
+    #[test]
+    fn ranked_payout_weights_each_rank() {
+        let payout = ranked_payout(&[10 * ONE, 4 * ONE], &[7_000, 3_000]);
+        assert_eq!(payout, 7 * ONE + 12 * ONE / 10);
+    }
+
+    #[test]
+    fn creator_quality_score_is_smoothed() {
+        assert_eq!(creator_quality_score(0, 0, 0, 0), 5_000);
+        // 8 clean resolutions out of 8 and 8 thumbs up out of 8: 9/10 on both parts
+        assert_eq!(creator_quality_score(8, 0, 8, 0), 9_000);
+        // Disputes and thumbs down pull the score below neutral
+        assert!(creator_quality_score(4, 3, 1, 5) < 5_000);
+    }
+
+    #[test]
+    fn small_stakes_earn_diminishing_xp() {
+        // A quarter of the threshold earns half the XP, a hundredth of it a tenth
+        assert_eq!(stake_scaled_xp(10, 250, 100_000, 100), 5);
+        assert_eq!(stake_scaled_xp(10, 10, 100_000, 100), 1);
+        assert_eq!(stake_scaled_xp(10, 1, 100_000_000, 100), 0);
+        assert_eq!(stake_scaled_xp(10, 500, 1_000, 100), 10);
+        assert_eq!(stake_scaled_xp(10, 1, 100_000, 0), 10);
+    }
+
+    #[test]
+    fn bracket_matches_are_numbered_by_round() {
+        assert_eq!(bracket_match_round(8, 0), Some((0, 0)));
+        assert_eq!(bracket_match_round(8, 3), Some((0, 3)));
+        assert_eq!(bracket_match_round(8, 5), Some((1, 1)));
+        assert_eq!(bracket_match_round(8, 6), Some((2, 0)));
+        assert_eq!(bracket_match_round(8, 7), None);
+        assert_eq!(bracket_round_points(0), 10);
+        assert_eq!(bracket_round_points(3), 80);
+    }
+
+    #[test]
+    fn parlay_odds_multiply() {
+        // Even odds (1 of 2) on one leg and 1 of 4 on another pay 8x
+        assert_eq!(parlay_odds(&[(ONE, 2 * ONE), (ONE, 4 * ONE)], 0), 8 * ONE);
+        assert_eq!(parlay_odds(&[], 0), ONE);
+        // A 5% edge takes 0.4 off 8x
+        assert_eq!(parlay_odds(&[(ONE, 2 * ONE), (ONE, 4 * ONE)], 500), 76 * ONE / 10);
+    }
+
+    #[test]
+    fn price_impact_caps_buys() {
+        let curve = BondingCurve::with_multiplier(ONE, 2, 1_000 * ONE);
+        // Two outcomes at the base price start at 50%; reaching 60% takes a price of 1.5,
+        // i.e. 500 shares costing 625 tokens
+        let capped = cap_buy_for_impact(&curve, 0, 2 * ONE, 1_000 * ONE, 1_000);
+        assert!((625 * ONE..626 * ONE).contains(&capped));
+        assert_eq!(cap_buy_for_impact(&curve, 0, 2 * ONE, 100 * ONE, 1_000), 100 * ONE);
+        // Past the curve's depth prices are flat and nothing is capped
+        assert_eq!(cap_buy_for_impact(&curve, 1_000 * ONE, 3 * ONE, 5_000 * ONE, 1), 5_000 * ONE);
+    }
+
+    #[test]
+    fn gini_of_balances() {
+        assert_eq!(gini_bps(&[]), 0);
+        assert_eq!(gini_bps(&[0, 0]), 0);
+        assert_eq!(gini_bps(&[5 * ONE, 5 * ONE, 5 * ONE]), 0);
+        // One of four holding everything gives (n - 1) / n
+        assert_eq!(gini_bps(&[0, 0, 100 * ONE, 0]), 7_500);
+    }
+
+    #[test]
+    fn experience_carries_over_levels() {
+        // Level 1 needs 100 XP, level 2 needs 200 XP
+        assert_eq!(apply_experience(1, 0, 99), (1, 99));
+        assert_eq!(apply_experience(1, 90, 20), (2, 10));
+        assert_eq!(apply_experience(1, 0, 350), (3, 50));
+    }
+}
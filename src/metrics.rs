@@ -0,0 +1,156 @@
+// Economy metrics: daily totals of tokens entering and leaving circulation, and running trade
+// tallies of each market for spectators
+
+use async_graphql::{ComplexObject, SimpleObject};
+use linera_sdk::linera_base_types::{Amount, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::state::OutcomeId;
+
+/// Microseconds in one metrics day
+pub const MICROS_PER_DAY: u64 = 24 * 60 * 60 * 1_000_000;
+/// Maximum number of days returned by a single metrics query
+pub const MAX_METRICS_RANGE_DAYS: u64 = 366;
+
+/// Microseconds in one hour of market activity
+pub const MICROS_PER_HOUR: u64 = 60 * 60 * 1_000_000;
+/// Hours of trading volume kept for each market, counting the current one
+pub const MAX_ACTIVITY_HOURS: u64 = 7 * 24;
+
+/// Index of the UTC day containing `time`
+pub fn day_index(time: Timestamp) -> u64 {
+    time.micros() / MICROS_PER_DAY
+}
+
+/// Index of the UTC hour containing `time`
+pub fn hour_index(time: Timestamp) -> u64 {
+    time.micros() / MICROS_PER_HOUR
+}
+
+/// A movement of tokens that the economy metrics account for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenFlow {
+    // Sources
+    RegistrationBonus,
+    DailyReward,
+    AchievementReward,
+    FeeReward,
+    /// Winnings of a parlay above its stake
+    ParlayWinnings,
+    /// Rewards claimed from season pass tiers and paid to guilds by league
+    SeasonReward,
+    /// Rewards paid for refreshing the leaderboards
+    KeeperReward,
+    /// Rewards paid to apprentices and mentors at mentorship milestones
+    MentorshipReward,
+    /// Tokens found in mystery chests
+    ChestReward,
+    // Sinks
+    CreationCost,
+    TradingFee,
+    Burn,
+}
+
+/// Token sources and sinks recorded during one day
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, SimpleObject)]
+#[graphql(complex)]
+pub struct EconomyDay {
+    pub day: u64,
+    /// Starting balances granted to new players
+    pub registration_bonuses: Amount,
+    pub daily_rewards: Amount,
+    pub achievement_rewards: Amount,
+    /// Creation and trading fees credited to market creators and the platform
+    pub fee_rewards: Amount,
+    pub parlay_winnings: Amount,
+    pub season_rewards: Amount,
+    pub keeper_rewards: Amount,
+    pub mentorship_rewards: Amount,
+    pub chest_rewards: Amount,
+    pub creation_costs: Amount,
+    pub trading_fees: Amount,
+    pub burned: Amount,
+}
+
+impl EconomyDay {
+    pub fn new(day: u64) -> Self {
+        Self { day, ..Self::default() }
+    }
+
+    /// Add `amount` to the bucket for `flow`
+    pub fn record(&mut self, flow: TokenFlow, amount: Amount) {
+        let bucket = match flow {
+            TokenFlow::RegistrationBonus => &mut self.registration_bonuses,
+            TokenFlow::DailyReward => &mut self.daily_rewards,
+            TokenFlow::AchievementReward => &mut self.achievement_rewards,
+            TokenFlow::FeeReward => &mut self.fee_rewards,
+            TokenFlow::ParlayWinnings => &mut self.parlay_winnings,
+            TokenFlow::SeasonReward => &mut self.season_rewards,
+            TokenFlow::KeeperReward => &mut self.keeper_rewards,
+            TokenFlow::MentorshipReward => &mut self.mentorship_rewards,
+            TokenFlow::ChestReward => &mut self.chest_rewards,
+            TokenFlow::CreationCost => &mut self.creation_costs,
+            TokenFlow::TradingFee => &mut self.trading_fees,
+            TokenFlow::Burn => &mut self.burned,
+        };
+        *bucket = bucket.saturating_add(amount);
+    }
+}
+
+#[ComplexObject]
+impl EconomyDay {
+    /// Tokens that entered circulation
+    async fn total_sources(&self) -> Amount {
+        self.registration_bonuses
+            .saturating_add(self.daily_rewards)
+            .saturating_add(self.achievement_rewards)
+            .saturating_add(self.fee_rewards)
+            .saturating_add(self.parlay_winnings)
+            .saturating_add(self.season_rewards)
+            .saturating_add(self.keeper_rewards)
+            .saturating_add(self.mentorship_rewards)
+            .saturating_add(self.chest_rewards)
+    }
+
+    /// Tokens that left player balances
+    async fn total_sinks(&self) -> Amount {
+        self.creation_costs
+            .saturating_add(self.trading_fees)
+            .saturating_add(self.burned)
+    }
+}
+
+/// Whether a trade put tokens into a market's curves or took them out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// Running trade tallies of one market, so spectators can follow it without reading positions
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MarketActivity {
+    /// Tokens traded in each of the last `MAX_ACTIVITY_HOURS` hours with trades, by hour index
+    pub hourly_volume: BTreeMap<u64, Amount>,
+    /// Number of buys of each outcome
+    pub buys: BTreeMap<OutcomeId, u64>,
+    /// Number of sells of each outcome
+    pub sells: BTreeMap<OutcomeId, u64>,
+}
+
+impl MarketActivity {
+    /// Count a trade of `amount` tokens in an outcome, dropping hours that fell out of the window
+    pub fn record(&mut self, outcome_id: OutcomeId, side: TradeSide, amount: Amount, time: Timestamp) {
+        let hour = hour_index(time);
+        let volume = self.hourly_volume.entry(hour).or_default();
+        *volume = volume.saturating_add(amount);
+        let oldest = hour.saturating_sub(MAX_ACTIVITY_HOURS - 1);
+        self.hourly_volume.retain(|hour, _| *hour >= oldest);
+        let trades = match side {
+            TradeSide::Buy => &mut self.buys,
+            TradeSide::Sell => &mut self.sells,
+        };
+        *trades.entry(outcome_id).or_default() += 1;
+    }
+}
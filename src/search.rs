@@ -0,0 +1,69 @@
+// Keyword search over market text: tokenization shared by the contract, which maintains the
+// index, and the service, which looks queries up in it; also the slugs markets are linked by
+
+use std::collections::BTreeSet;
+
+/// Shorter words are too common to be worth indexing
+pub const MIN_KEYWORD_LENGTH: usize = 2;
+/// Longer words are truncated to this many characters
+pub const MAX_KEYWORD_LENGTH: usize = 32;
+/// Maximum number of markets returned by a single search
+pub const MAX_SEARCH_RESULTS: usize = 50;
+/// Longer slugs are truncated to this many characters, before any uniqueness suffix
+pub const MAX_SLUG_LENGTH: usize = 64;
+
+/// Lowercase alphanumeric words of `text`, without duplicates
+pub fn keywords(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| word.chars().count() >= MIN_KEYWORD_LENGTH)
+        .map(|word| word.to_lowercase().chars().take(MAX_KEYWORD_LENGTH).collect())
+        .collect()
+}
+
+/// Keywords indexed for a market
+pub fn market_keywords(title: &str, description: &str) -> BTreeSet<String> {
+    let mut words = keywords(title);
+    words.extend(keywords(description));
+    words
+}
+
+/// Lowercase ASCII words of `title` joined by hyphens, or "market" if it has none
+pub fn slug(title: &str) -> String {
+    let mut slug = title
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("-");
+    // Only ASCII is left, so any byte is a character boundary
+    slug.truncate(MAX_SLUG_LENGTH);
+    match slug.trim_end_matches('-') {
+        "" => "market".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keywords_are_normalized() {
+        let words = keywords("Will BTC hit $100k? (BTC, a coin)");
+        let expected: BTreeSet<String> = ["will", "btc", "hit", "100k", "coin"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        assert_eq!(words, expected);
+    }
+
+    #[test]
+    fn slugs_are_url_friendly() {
+        assert_eq!(slug("Will BTC hit $100k? (by 2025)"), "will-btc-hit-100k-by-2025");
+        assert_eq!(slug("¿Quién ganará?"), "qui-n-ganar");
+        assert_eq!(slug("!!!"), "market");
+        assert_eq!(slug(&"long ".repeat(20)).len(), MAX_SLUG_LENGTH);
+        // A cut right after a hyphen drops it
+        assert_eq!(slug(&"abc ".repeat(20)).len(), MAX_SLUG_LENGTH - 1);
+    }
+}
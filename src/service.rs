@@ -1,18 +1,32 @@
 #![cfg_attr(target_arch = "wasm32", no_main)]
 
-use std::sync::Arc;
+use std::{collections::BTreeMap, convert::Infallible, sync::Arc};
 
-use async_graphql::{EmptySubscription, Object, Schema};
+use async_graphql::{
+    parser::{
+        parse_query,
+        types::{OperationType, Selection},
+    },
+    EmptySubscription, Enum, Name, Object, Request, Response, Schema, ServerError, SimpleObject, Value,
+};
 use linera_sdk::{
-    graphql::GraphQLMutationRoot, linera_base_types::{Amount, WithServiceAbi}, views::View, Service,
+    graphql::GraphQLMutationRoot, linera_base_types::{Amount, ApplicationId, ChainId, CryptoHash, TimeDelta, Timestamp, WithServiceAbi}, views::View, Service,
     ServiceRuntime,
 };
+use linera_sdk::views::{MapView, ViewError};
+use serde::{de::DeserializeOwned, Serialize};
 
+use predictive_manager::chests::{ChestTable, MysteryChest};
+use predictive_manager::events::{EventBoost, SpecialEvent};
+use predictive_manager::math;
+use predictive_manager::metrics::{day_index, EconomyDay, MAX_METRICS_RANGE_DAYS, MICROS_PER_HOUR};
+use predictive_manager::search;
 use predictive_manager::Operation;
 use predictive_manager::state::*;
+use predictive_manager::units::{PricePerShare, Shares, Tokens, TradeAmounts};
 
 pub struct PredictiveManagerService {
-    state: PredictionMarketState,
+    state: Arc<PredictionMarketState>,
     runtime: Arc<ServiceRuntime<Self>>,
 }
 
@@ -30,19 +44,25 @@ impl Service for PredictiveManagerService {
             .await
             .expect("Failed to load state");
         PredictiveManagerService {
-            state,
+            state: Arc::new(state),
             runtime: Arc::new(runtime),
         }
     }
 
     async fn handle_query(&self, query: Self::Query) -> Self::QueryResponse {
+        let errors = mutation_errors(&query, self.state.config.get());
+        if !errors.is_empty() {
+            return Response::from_errors(errors);
+        }
         Schema::build(
             QueryRoot {
-                total_supply: *self.state.total_supply.get(),
+                state: self.state.clone(),
+                runtime: self.runtime.clone(),
             },
             Operation::mutation_root(self.runtime.clone()),
             EmptySubscription,
         )
+        .data(self.state.clone())
         .finish()
         .execute(query)
         .await
@@ -50,13 +70,1291 @@ impl Service for PredictiveManagerService {
 }
 
 struct QueryRoot {
+    state: Arc<PredictionMarketState>,
+    runtime: Arc<ServiceRuntime<PredictiveManagerService>>,
+}
+
+#[derive(SimpleObject)]
+struct SystemStatus {
+    /// Whether markets on this chain are suspended
+    emergency_mode: bool,
+    /// Current announcement, unless it has expired
+    announcement: Option<SystemAnnouncement>,
+    /// When the scheduled config change takes effect, if one is scheduled
+    config_change_at: Option<Timestamp>,
+}
+
+#[derive(SimpleObject)]
+struct SupplyMetrics {
+    /// Tokens currently in circulation
     total_supply: Amount,
+    /// Tokens burned since instantiation
+    total_burned: Amount,
+    /// Unclaimed winnings and lost parlay stakes held by the treasury, less the winnings held
+    /// back for open parlays
+    treasury: Amount,
+}
+
+/// Read-only statistics of a market for spectators, naming no player
+#[derive(SimpleObject)]
+struct SpectatorStats {
+    market_id: MarketId,
+    status: MarketStatus,
+    /// Largest holdings of a single outcome, most shares first
+    largest_positions: Vec<AnonymousPosition>,
+    /// Tokens traded in each recent hour that had trades, oldest first
+    hourly_volume: Vec<HourlyVolume>,
+    /// Number of buys and sells of each outcome
+    outcome_trades: Vec<OutcomeTrades>,
+}
+
+#[derive(SimpleObject)]
+struct AnonymousPosition {
+    outcome_id: OutcomeId,
+    shares: Amount,
+}
+
+#[derive(SimpleObject)]
+struct HourlyVolume {
+    starts_at: Timestamp,
+    volume: Amount,
+}
+
+#[derive(SimpleObject)]
+struct OutcomeTrades {
+    outcome_id: OutcomeId,
+    buys: u64,
+    sells: u64,
+}
+
+#[derive(SimpleObject)]
+struct PlayerRank {
+    /// 1-based position among all players
+    rank: u32,
+    total_players: u32,
+    display_name: Option<String>,
+    total_profit: Amount,
+    level: u32,
+}
+
+/// Why tokens are waiting for a player in a market
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+enum ClaimKind {
+    /// Winnings of a resolved market, paid by `ClaimWinnings`
+    Winnings,
+    /// Tokens in an unresolved market, returned by `EmergencyWithdraw` while markets are suspended
+    Refund,
+}
+
+/// Tokens a player can claim from one market
+#[derive(SimpleObject)]
+struct Claimable {
+    market_id: MarketId,
+    kind: ClaimKind,
+    amount: Amount,
+    /// When unclaimed winnings are swept to the treasury, if they are
+    expires_at: Option<Timestamp>,
+}
+
+/// Everything a player can claim, for a single "to claim" badge
+#[derive(SimpleObject)]
+struct Claimables {
+    total: Amount,
+    markets: Vec<Claimable>,
+}
+
+/// Format version of snapshot chunks, bumped whenever an exported record changes shape
+const SNAPSHOT_VERSION: u32 = 1;
+/// Most records in one snapshot chunk
+const MAX_SNAPSHOT_CHUNK: usize = 100;
+/// Most positions listed in one market's spectator statistics
+const MAX_SPECTATOR_POSITIONS: usize = 50;
+
+/// Part of the state exported by `exportSnapshot`
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+enum SnapshotSection {
+    Players,
+    Markets,
+    Guilds,
+}
+
+/// One page of a state export
+#[derive(SimpleObject)]
+struct SnapshotChunk {
+    version: u32,
+    section: SnapshotSection,
+    offset: usize,
+    /// Records in the whole section
+    total: usize,
+    /// Offset of the next chunk, or null after the last one
+    next_offset: Option<usize>,
+    /// The chunk's records as a JSON array, in key order
+    data: String,
+}
+
+/// Preview of a trade, priced exactly as executing it would be
+#[derive(SimpleObject)]
+struct TradeQuote {
+    /// Shares bought or sold
+    shares: Amount,
+    /// Tokens paid for a buy, or the value of the shares sold
+    amount: Amount,
+    /// Tokens left to buy the shares, or received for a sell, once the fee is taken out
+    net: Amount,
+    /// Average price per share over the whole trade, fee included
+    effective_price: Amount,
+    /// Trading fee taken out of the trade
+    fee: Amount,
+    /// Fee rate in basis points, which grows with price impact and near close
+    fee_bps: u32,
+    /// Marginal price before the trade
+    price_before: Amount,
+    /// Marginal price after the trade
+    price_after: Amount,
+    /// Relative price move caused by the trade, in basis points
+    price_impact_bps: u64,
+}
+
+impl TradeQuote {
+    fn new(shares: Shares, trade: TradeAmounts, price_before: PricePerShare, price_after: PricePerShare) -> Self {
+        let impact = math::price_impact_bps(price_before.attos(), price_after.attos());
+        TradeQuote {
+            shares: shares.amount(),
+            amount: trade.gross.amount(),
+            net: trade.net.amount(),
+            effective_price: PricePerShare::average(trade.gross, shares).unwrap_or_default().amount(),
+            fee: trade.fee.amount(),
+            fee_bps: trade.fee_bps,
+            price_before: price_before.amount(),
+            price_after: price_after.amount(),
+            price_impact_bps: u64::try_from(impact).unwrap_or(u64::MAX),
+        }
+    }
+}
+
+impl QueryRoot {
+    /// A market, translated into `locale` if one was asked for
+    async fn localized_market(&self, market_id: MarketId, locale: Option<&str>) -> Result<Option<Market>, ViewError> {
+        let market = self.state.markets.get(&market_id).await?;
+        Ok(market.map(|market| match locale {
+            Some(locale) => market.localized(locale),
+            None => market,
+        }))
+    }
+
+    /// Seconds until a market closes, as of the latest block
+    fn seconds_left(&self, market: &Market) -> u64 {
+        let now = self.runtime.system_time();
+        market.end_time.micros().saturating_sub(now.micros()) / 1_000_000
+    }
+
+    /// Resolver pairs flagged under the current config, each listed once
+    async fn flagged_voters(&self) -> Result<Vec<CollusionFlag>, ViewError> {
+        let config = self.state.config.get().collusion;
+        let mut flags = Vec::new();
+        self.state
+            .vote_agreements
+            .for_each_index_value(|first, agreements| {
+                for (second, agreement) in agreements.iter() {
+                    if first < *second && agreement.is_flagged(&config) {
+                        flags.push(CollusionFlag {
+                            first,
+                            second: *second,
+                            shared_votes: agreement.shared_votes,
+                            agreed_votes: agreement.agreed_votes,
+                        });
+                    }
+                }
+                Ok(())
+            })
+            .await?;
+        Ok(flags)
+    }
+
+    /// Whether a player keeps their portfolio out of queries
+    async fn hides_portfolio(&self, player_id: &PlayerId) -> Result<bool, ViewError> {
+        let player = self.state.players.get(player_id).await?;
+        Ok(player.is_some_and(|player| player.privacy.hide_portfolio))
+    }
+
+    /// Boost of the special events running for a market, as of the latest block
+    fn event_boost(&self, market: &Market) -> EventBoost {
+        EventBoost::running(self.state.special_events.get(), &market.market_type, self.runtime.system_time())
+    }
+
+    /// Fee rate for a trade placed now that moves an outcome's price from `price_before` to
+    /// `price_after`, as the contract would charge it
+    fn trading_fee_bps(&self, market: &Market, price_before: u128, price_after: u128) -> u32 {
+        let impact = math::price_impact_bps(price_before, price_after);
+        let fee_bps = self.state.config.get().fee_schedule.fee_bps(impact, self.seconds_left(market));
+        self.event_boost(market).fee_bps(fee_bps)
+    }
+
+    /// Up to `limit` open markets ending after `after` and no later than `until`, in order of
+    /// end time; the index is read from the earliest end time onwards
+    async fn markets_ending(
+        &self,
+        after: Option<Timestamp>,
+        until: Timestamp,
+        limit: usize,
+    ) -> Result<Vec<MarketId>, ViewError> {
+        let mut market_ids = Vec::new();
+        self.state
+            .markets_by_end_time
+            .for_each_index_while(|key| {
+                if key.end_time > until || market_ids.len() >= limit {
+                    return Ok(false);
+                }
+                if after.is_none_or(|after| key.end_time > after) {
+                    market_ids.push(key.market_id);
+                }
+                Ok(true)
+            })
+            .await?;
+        Ok(market_ids)
+    }
+
+    /// Load a market that is open for trading, along with the outstanding shares of an outcome
+    async fn tradable_outcome(
+        &self,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+    ) -> async_graphql::Result<(Market, u128)> {
+        let market = self
+            .state
+            .markets
+            .get(&market_id)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("market not found"))?;
+        if market.status != MarketStatus::Active {
+            return Err(async_graphql::Error::new("market not active"));
+        }
+        let supply = market
+            .outcomes
+            .get(outcome_id as usize)
+            .map(|outcome| u128::from(outcome.total_shares))
+            .ok_or_else(|| async_graphql::Error::new("invalid outcome"))?;
+        Ok((market, supply))
+    }
 }
 
 #[Object]
 impl QueryRoot {
     async fn total_supply(&self) -> &Amount {
-        &self.total_supply
+        self.state.total_supply.get()
+    }
+
+    /// Supply figures for monitoring inflation
+    async fn supply_metrics(&self) -> SupplyMetrics {
+        SupplyMetrics {
+            total_supply: *self.state.total_supply.get(),
+            total_burned: *self.state.total_burned.get(),
+            treasury: *self.state.treasury.get(),
+        }
+    }
+
+    /// Daily token sources and sinks for days `from_day..=to_day`
+    /// Days are counted since the Unix epoch; days without activity are omitted
+    async fn economy_metrics(
+        &self,
+        from_day: u64,
+        to_day: u64,
+    ) -> async_graphql::Result<Vec<EconomyDay>> {
+        if to_day < from_day || to_day - from_day >= MAX_METRICS_RANGE_DAYS {
+            return Err(async_graphql::Error::new("invalid day range"));
+        }
+        let mut days = Vec::new();
+        for day in from_day..=to_day {
+            if let Some(metrics) = self.state.economy_metrics.get(&day).await? {
+                days.push(metrics);
+            }
+        }
+        Ok(days)
+    }
+
+    /// The config currently in effect
+    async fn game_config(&self) -> &GameConfig {
+        self.state.config.get()
+    }
+
+    /// Whether markets on this chain are suspended
+    async fn emergency_mode(&self) -> bool {
+        *self.state.emergency_mode.get()
+    }
+
+    /// What frontends should tell players about the game as a whole: suspended markets, the
+    /// admin's announcement and upcoming config changes
+    async fn system_status(&self) -> SystemStatus {
+        let now = self.runtime.system_time();
+        SystemStatus {
+            emergency_mode: *self.state.emergency_mode.get(),
+            announcement: self
+                .state
+                .announcement
+                .get()
+                .clone()
+                .filter(|announcement| !announcement.is_expired(now)),
+            config_change_at: self.state.pending_config.get().as_ref().map(|change| change.effective_at),
+        }
+    }
+
+    /// Special events that are running or yet to start, ordered by start
+    async fn event_calendar(&self) -> Vec<SpecialEvent> {
+        let now = self.runtime.system_time();
+        self.state
+            .special_events
+            .get()
+            .iter()
+            .filter(|event| event.ends_at > now)
+            .cloned()
+            .collect()
+    }
+
+    /// Market featured on `day`, counted since the Unix epoch, or today; none until the day's
+    /// first trade chooses it
+    async fn featured_market(&self, day: Option<u64>) -> async_graphql::Result<Option<FeaturedMarket>> {
+        let day = day.unwrap_or_else(|| day_index(self.runtime.system_time()));
+        Ok(self.state.featured_markets.get(&day).await?)
+    }
+
+    /// How often a market has been viewed, in total and on the latest day of activity
+    async fn market_views(&self, market_id: MarketId) -> async_graphql::Result<MarketViews> {
+        Ok(MarketViews {
+            total: self.state.market_views.get(&market_id).await?.unwrap_or_default(),
+            today: self.state.daily_views.get(&market_id).await?.unwrap_or_default(),
+        })
+    }
+
+    /// Markets the admin lined up to be featured on the coming days, next first
+    async fn featured_queue(&self) -> &Vec<MarketId> {
+        self.state.featured_queue.get()
+    }
+
+    /// Scheduled config change, so players can see economic changes before they apply
+    async fn pending_config_change(&self) -> &Option<PendingConfigChange> {
+        self.state.pending_config.get()
+    }
+
+    /// Player the admin role has been offered to and who hasn't accepted yet
+    async fn pending_admin(&self) -> &Option<PlayerId> {
+        self.state.pending_admin.get()
+    }
+
+    /// Top traders and guilds as of the last refresh
+    async fn leaderboard(&self) -> &Leaderboard {
+        self.state.leaderboard.get()
+    }
+
+    /// Top players on a single leaderboard category, best first
+    async fn board(&self, category: LeaderboardCategory) -> async_graphql::Result<Vec<BoardEntry>> {
+        Ok(self.state.leaderboard_boards.get(&category).await?.unwrap_or_default())
+    }
+
+    /// A player's current standing, including players outside the top traders; read from the
+    /// rank index, which every write keeps up to date
+    async fn my_rank(&self, player_id: PlayerId) -> async_graphql::Result<Option<PlayerRank>> {
+        let Some(player) = self.state.players.get(&player_id).await? else {
+            return Ok(None);
+        };
+        let mut rank = 0;
+        let mut found = false;
+        self.state
+            .rank_index
+            .for_each_index_while(|key| {
+                rank += 1;
+                found = key.player_id == player_id;
+                Ok(!found)
+            })
+            .await?;
+        if !found {
+            return Ok(None);
+        }
+        Ok(Some(PlayerRank {
+            rank,
+            total_players: *self.state.ranked_players.get(),
+            display_name: player.public_name(),
+            total_profit: player.total_profit,
+            level: player.level,
+        }))
+    }
+
+    /// A single player's profile and balances, without the display name if they hide it
+    async fn player(&self, player_id: PlayerId) -> async_graphql::Result<Option<Player>> {
+        Ok(self.state.players.get(&player_id).await?.map(|player| Player {
+            display_name: player.public_name(),
+            ..player
+        }))
+    }
+
+    /// Where an account was moved by recovery, if it was
+    async fn account_recovery(
+        &self,
+        old_player_id: PlayerId,
+    ) -> async_graphql::Result<Option<AccountRecovery>> {
+        Ok(self.state.account_recoveries.get(&old_player_id).await?)
+    }
+
+    /// A head-to-head challenge by ID
+    async fn challenge(&self, challenge_id: ChallengeId) -> async_graphql::Result<Option<Challenge>> {
+        Ok(self.state.challenges.get(&challenge_id).await?)
+    }
+
+    /// A player's current mentorship as an apprentice
+    async fn mentorship(&self, apprentice: PlayerId) -> async_graphql::Result<Option<Mentorship>> {
+        Ok(self.state.mentorships.get(&apprentice).await?)
+    }
+
+    /// Mentorships of a mentor's apprentices, in the order they accepted
+    async fn apprentices(&self, mentor: PlayerId) -> async_graphql::Result<Vec<Mentorship>> {
+        let mut mentorships = Vec::new();
+        for apprentice in self.state.apprentices.get(&mentor).await?.unwrap_or_default() {
+            if let Some(mentorship) = self.state.mentorships.get(&apprentice).await? {
+                mentorships.push(mentorship);
+            }
+        }
+        Ok(mentorships)
+    }
+
+    /// Mentors waiting for a player to accept their offer, oldest offer first
+    async fn mentorship_offers(&self, player_id: PlayerId) -> async_graphql::Result<Vec<PlayerId>> {
+        Ok(self.state.mentorship_offers.get(&player_id).await?.unwrap_or_default())
+    }
+
+    /// A page of players, markets or guilds as versioned JSON, for backups and analytics
+    /// Only served while the admin has enabled snapshot exports in the config
+    async fn export_snapshot(
+        &self,
+        section: SnapshotSection,
+        #[graphql(default = 0)] offset: usize,
+        #[graphql(default = 100)] limit: usize,
+    ) -> async_graphql::Result<SnapshotChunk> {
+        if !self.state.config.get().snapshot_exports_enabled {
+            return Err(async_graphql::Error::new("snapshot exports are disabled"));
+        }
+        let limit = limit.min(MAX_SNAPSHOT_CHUNK);
+        let (total, data) = match section {
+            SnapshotSection::Players => export_page(&self.state.players, offset, limit).await?,
+            SnapshotSection::Markets => export_page(&self.state.markets, offset, limit).await?,
+            SnapshotSection::Guilds => export_page(&self.state.guilds, offset, limit).await?,
+        };
+        let end = offset.saturating_add(limit);
+        Ok(SnapshotChunk {
+            version: SNAPSHOT_VERSION,
+            section,
+            offset,
+            total,
+            next_offset: (end < total).then_some(end),
+            data,
+        })
+    }
+
+    /// Logged events after `afterSeq` (from the start when omitted), oldest first
+    async fn events(
+        &self,
+        after_seq: Option<u64>,
+        #[graphql(default = 100)] limit: u32,
+    ) -> async_graphql::Result<Vec<ChainEvent>> {
+        let start = after_seq.map_or(0, |sequence| sequence.saturating_add(1)) as usize;
+        let end = start
+            .saturating_add(limit.min(MAX_EVENTS_PAGE) as usize)
+            .min(self.state.event_log.count());
+        if start >= end {
+            return Ok(Vec::new());
+        }
+        Ok(self.state.event_log.read(start..end).await?)
+    }
+
+    /// Outbox notifications after `afterSeq` (from the start when omitted), oldest first
+    async fn notifications(
+        &self,
+        after_seq: Option<u64>,
+        #[graphql(default = 100)] limit: u32,
+    ) -> async_graphql::Result<Vec<Notification>> {
+        let start = after_seq.map_or(0, |sequence| sequence.saturating_add(1)) as usize;
+        let end = start
+            .saturating_add(limit.min(MAX_EVENTS_PAGE) as usize)
+            .min(self.state.outbox.count());
+        if start >= end {
+            return Ok(Vec::new());
+        }
+        Ok(self.state.outbox.read(start..end).await?)
+    }
+
+    /// Which notifications the outbox records
+    async fn notification_filter(&self) -> NotificationFilter {
+        self.state.notification_filter.get().clone()
+    }
+
+    /// All seasons, ordered by ID
+    async fn seasons(&self) -> async_graphql::Result<Vec<Season>> {
+        let mut seasons = Vec::new();
+        self.state
+            .seasons
+            .for_each_index_value(|_, season| {
+                seasons.push(season.into_owned());
+                Ok(())
+            })
+            .await?;
+        Ok(seasons)
+    }
+
+    /// A player's progress through a season's pass
+    async fn season_pass(
+        &self,
+        season_id: SeasonId,
+        player_id: PlayerId,
+    ) -> async_graphql::Result<Option<SeasonPass>> {
+        match self.state.season_passes.try_load_entry(&season_id).await? {
+            Some(passes) => Ok(passes.get(&player_id).await?),
+            None => Ok(None),
+        }
+    }
+
+    /// A market group by ID, with its standings once resolved
+    async fn market_group(&self, group_id: GroupId) -> async_graphql::Result<Option<MarketGroup>> {
+        Ok(self.state.market_groups.get(&group_id).await?)
+    }
+
+    /// A bracket by ID, with its entries and scores
+    async fn bracket(&self, bracket_id: BracketId) -> async_graphql::Result<Option<Bracket>> {
+        Ok(self.state.brackets.get(&bracket_id).await?)
+    }
+
+    /// A raffle by ID
+    async fn raffle(&self, raffle_id: RaffleId) -> async_graphql::Result<Option<Raffle>> {
+        Ok(self.state.raffles.get(&raffle_id).await?)
+    }
+
+    /// All raffles, ordered by ID
+    async fn raffles(&self) -> async_graphql::Result<Vec<Raffle>> {
+        let mut raffles = Vec::new();
+        self.state
+            .raffles
+            .for_each_index_value(|_, raffle| {
+                raffles.push(raffle.into_owned());
+                Ok(())
+            })
+            .await?;
+        Ok(raffles)
+    }
+
+    /// Chests a player earned, opened or not, ordered by ID
+    async fn chests(&self, player_id: PlayerId) -> async_graphql::Result<Vec<MysteryChest>> {
+        Ok(self.state.chests.get(&player_id).await?.unwrap_or_default())
+    }
+
+    /// When chests are earned and what they can hold
+    async fn chest_table(&self) -> async_graphql::Result<ChestTable> {
+        Ok(self.state.chest_table.get().clone())
+    }
+
+    /// Cosmetic items in the shop, including those off sale, ordered by ID
+    async fn shop_items(&self) -> async_graphql::Result<Vec<ShopItem>> {
+        let mut items = Vec::new();
+        self.state
+            .shop_items
+            .for_each_index_value(|_, item| {
+                items.push(item.into_owned());
+                Ok(())
+            })
+            .await?;
+        Ok(items)
+    }
+
+    /// Cosmetic items a player owns
+    async fn inventory(&self, player_id: PlayerId) -> async_graphql::Result<Vec<ShopItem>> {
+        let item_ids = match self.state.inventories.try_load_entry(&player_id).await? {
+            Some(items) => items.indices().await?,
+            None => Vec::new(),
+        };
+        let mut items = Vec::new();
+        for item_id in item_ids {
+            if let Some(item) = self.state.shop_items.get(&item_id).await? {
+                items.push(item);
+            }
+        }
+        Ok(items)
+    }
+
+    /// Markets a player is still involved in; empty if they hide their portfolio
+    async fn active_markets(&self, player_id: PlayerId) -> async_graphql::Result<Vec<MarketId>> {
+        if self.hides_portfolio(&player_id).await? {
+            return Ok(Vec::new());
+        }
+        Ok(match self.state.active_markets.try_load_entry(&player_id).await? {
+            Some(markets) => markets.indices().await?,
+            None => Vec::new(),
+        })
+    }
+
+    /// Page through the markets a player has finished with, oldest first; empty if they hide
+    /// their portfolio
+    async fn archived_markets(
+        &self,
+        player_id: PlayerId,
+        #[graphql(default = 0)] offset: u32,
+        #[graphql(default = 100)] limit: u32,
+    ) -> async_graphql::Result<Vec<MarketId>> {
+        if self.hides_portfolio(&player_id).await? {
+            return Ok(Vec::new());
+        }
+        let Some(history) = self.state.archived_markets.try_load_entry(&player_id).await? else {
+            return Ok(Vec::new());
+        };
+        let start = offset as usize;
+        let end = start
+            .saturating_add(limit.min(MAX_EVENTS_PAGE) as usize)
+            .min(history.count());
+        if start >= end {
+            return Ok(Vec::new());
+        }
+        Ok(history.read(start..end).await?)
+    }
+
+    /// Markets where a player has winnings to claim or, while markets are suspended, tokens
+    /// to withdraw; empty if they hide their portfolio
+    async fn claimables(&self, player_id: PlayerId) -> async_graphql::Result<Claimables> {
+        let mut claimables = Claimables {
+            total: Amount::ZERO,
+            markets: Vec::new(),
+        };
+        if self.hides_portfolio(&player_id).await? {
+            return Ok(claimables);
+        }
+        // Claiming or withdrawing moves a market out of the player's active set
+        let market_ids = match self.state.active_markets.try_load_entry(&player_id).await? {
+            Some(markets) => markets.indices().await?,
+            None => Vec::new(),
+        };
+        let now = self.runtime.system_time();
+        let emergency_mode = *self.state.emergency_mode.get();
+        for market_id in market_ids {
+            let Some(market) = self.state.markets.get(&market_id).await? else {
+                continue;
+            };
+            let position = market.positions.get(&player_id);
+            let claimable = if market.status == MarketStatus::Resolved {
+                let amount = position
+                    .filter(|position| !position.settled)
+                    .map_or(Amount::ZERO, |position| market.payout_for(position));
+                let expired = market.claims_expire_at.is_some_and(|expires_at| now >= expires_at);
+                (!expired).then_some((ClaimKind::Winnings, amount))
+            } else if emergency_mode {
+                let sealed = market
+                    .blind_commitments
+                    .iter()
+                    .filter(|sealed| sealed.player == player_id && !sealed.revealed)
+                    .fold(Amount::ZERO, |total, sealed| total.saturating_add(sealed.amount));
+                let amount = if market.status == MarketStatus::Bootstrapping {
+                    market
+                        .bootstrap_commitments
+                        .iter()
+                        .filter(|commitment| commitment.player == player_id)
+                        .fold(sealed, |total, commitment| total.saturating_add(commitment.amount))
+                } else {
+                    sealed.saturating_add(position.map_or(Amount::ZERO, |position| position.total_invested))
+                };
+                Some((ClaimKind::Refund, amount))
+            } else {
+                None
+            };
+            let Some((kind, amount)) = claimable.filter(|(_, amount)| *amount > Amount::ZERO) else {
+                continue;
+            };
+            claimables.total = claimables.total.saturating_add(amount);
+            claimables.markets.push(Claimable {
+                market_id,
+                kind,
+                amount,
+                expires_at: market.claims_expire_at.filter(|_| kind == ClaimKind::Winnings),
+            });
+        }
+        Ok(claimables)
+    }
+
+    /// A staked resolver
+    async fn resolver(&self, player_id: PlayerId) -> async_graphql::Result<Option<Resolver>> {
+        Ok(self.state.resolvers.get(&player_id).await?)
+    }
+
+    /// An approved market-maker bot, with its limits and what it has open
+    async fn market_maker(&self, player_id: PlayerId) -> async_graphql::Result<Option<MarketMaker>> {
+        Ok(self.state.market_makers.get(&player_id).await?)
+    }
+
+    /// Resolver pairs whose votes agree often enough on contested markets to be flagged,
+    /// for moderators to review; each pair is listed once
+    async fn collusion_flags(&self) -> async_graphql::Result<Vec<CollusionFlag>> {
+        Ok(self.flagged_voters().await?)
+    }
+
+    /// Groups of resolvers linked through flagged pairs
+    async fn collusion_clusters(&self) -> async_graphql::Result<Vec<Vec<PlayerId>>> {
+        let pairs: Vec<(PlayerId, PlayerId)> = self
+            .flagged_voters()
+            .await?
+            .into_iter()
+            .map(|flag| (flag.first, flag.second))
+            .collect();
+        Ok(collusion_clusters(&pairs))
+    }
+
+    /// Open bot quotes on a market, optionally only on one outcome
+    async fn bot_quotes(
+        &self,
+        market_id: MarketId,
+        outcome_id: Option<OutcomeId>,
+    ) -> async_graphql::Result<Vec<BotQuote>> {
+        let Some(market) = self.state.markets.get(&market_id).await? else {
+            return Ok(Vec::new());
+        };
+        Ok(market
+            .bot_quotes
+            .into_iter()
+            .filter(|quote| quote.open && outcome_id.is_none_or(|outcome_id| quote.outcome_id == outcome_id))
+            .collect())
+    }
+
+    /// Members of the arbitration council
+    async fn arbitrators(&self) -> async_graphql::Result<Vec<PlayerId>> {
+        Ok(self.state.arbitrators.indices().await?)
+    }
+
+    /// The council's ruling on a contested market, if it was arbitrated
+    async fn arbitration(&self, market_id: MarketId) -> async_graphql::Result<Option<ArbitrationRecord>> {
+        Ok(self.state.arbitrations.get(&market_id).await?)
+    }
+
+    /// A guild staking proposal by ID
+    async fn guild_stake_proposal(
+        &self,
+        proposal_id: GuildStakeId,
+    ) -> async_graphql::Result<Option<GuildStakeProposal>> {
+        Ok(self.state.guild_stake_proposals.get(&proposal_id).await?)
+    }
+
+    /// A guild token pool withdrawal by ID
+    async fn guild_token_withdrawal(
+        &self,
+        withdrawal_id: GuildWithdrawalId,
+    ) -> async_graphql::Result<Option<GuildTokenWithdrawal>> {
+        Ok(self.state.guild_token_withdrawals.get(&withdrawal_id).await?)
+    }
+
+    /// A parlay by ID
+    async fn parlay(&self, parlay_id: ParlayId) -> async_graphql::Result<Option<Parlay>> {
+        Ok(self.state.parlays.get(&parlay_id).await?)
+    }
+
+    /// Volume and fees across the markets a player created
+    async fn creator_stats(&self, player_id: PlayerId) -> async_graphql::Result<Option<CreatorStats>> {
+        Ok(self.state.creator_stats.get(&player_id).await?)
+    }
+
+    /// A single guild by ID
+    async fn guild(&self, guild_id: GuildId) -> async_graphql::Result<Option<Guild>> {
+        Ok(self.state.guilds.get(&guild_id).await?)
+    }
+
+    /// Latest reconciliation of a fungible token application's balance with the guild token
+    /// pools using it
+    async fn token_reconciliation(
+        &self,
+        application_id: ApplicationId,
+    ) -> async_graphql::Result<Option<TokenReconciliation>> {
+        Ok(self.state.token_reconciliations.get(&application_id).await?)
+    }
+
+    /// Standings of a guild league so far in a season, with the reward and league each guild
+    /// would get if the season were settled now
+    async fn league(&self, tier: LeagueTier, season_id: SeasonId) -> async_graphql::Result<Vec<LeagueStanding>> {
+        let mut guilds = Vec::new();
+        self.state
+            .guilds
+            .for_each_index_value(|_, guild| {
+                guilds.push(guild.into_owned());
+                Ok(())
+            })
+            .await?;
+        let passes = self.state.season_passes.try_load_entry(&season_id).await?;
+        let mut scores = Vec::new();
+        for guild in &guilds {
+            let mut season_xp = 0u64;
+            if let Some(passes) = &passes {
+                for member in &guild.members {
+                    let xp = passes.get(member).await?.map_or(0, |pass| pass.xp);
+                    season_xp = season_xp.saturating_add(xp);
+                }
+            }
+            scores.push((guild, season_xp));
+        }
+        let rewards = self.state.league_rewards.get(&tier).await?.unwrap_or_default();
+        Ok(rank_league(tier, &scores, &rewards))
+    }
+
+    /// Final league standings of a settled season, by tier then rank
+    async fn league_results(&self, season_id: SeasonId) -> async_graphql::Result<Vec<LeagueStanding>> {
+        Ok(self.state.league_results.get(&season_id).await?.unwrap_or_default())
+    }
+
+    /// An attestation issued by this chain
+    async fn attestation(&self, attestation_id: AttestationId) -> async_graphql::Result<Option<Attestation>> {
+        Ok(self.state.attestations.get(&attestation_id).await?)
+    }
+
+    /// An attestation another chain sent here, by its hash; only intact attestations from the
+    /// chain that issued them are kept
+    async fn received_attestation(&self, hash: CryptoHash) -> async_graphql::Result<Option<Attestation>> {
+        Ok(self.state.received_attestations.get(&hash).await?)
+    }
+
+    /// Preview buying an outcome for `amount` tokens without executing the trade
+    /// Buys past the market's price impact limit are quoted for the part that would fill for a
+    /// player who hasn't bought in it during the current window. The
+    /// fee comes out of the amount, at a rate set by the impact of spending all of it
+    async fn quote_buy(
+        &self,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        amount: Amount,
+    ) -> async_graphql::Result<TradeQuote> {
+        let (market, supply) = self.tradable_outcome(market_id, outcome_id).await?;
+        let curve = market.bonding_curve();
+        let gross = Tokens::new(market.buy_within_impact_limit(outcome_id, amount, 0));
+        let spent_supply = supply.saturating_add(curve.shares_for_amount(supply, gross.attos()));
+        let fee_bps = self.trading_fee_bps(&market, curve.price_at(supply), curve.price_at(spent_supply));
+        let trade = TradeAmounts::carve(gross, fee_bps);
+        let shares = curve.shares_for_amount(supply, trade.net.attos());
+        Ok(TradeQuote::new(
+            Shares::from_attos(shares),
+            trade,
+            PricePerShare::from_attos(curve.price_at(supply)),
+            PricePerShare::from_attos(curve.price_at(supply + shares)),
+        ))
+    }
+
+    /// Preview selling `shares` of an outcome without executing the trade
+    async fn quote_sell(
+        &self,
+        market_id: MarketId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+    ) -> async_graphql::Result<TradeQuote> {
+        let (market, supply) = self.tradable_outcome(market_id, outcome_id).await?;
+        let curve = market.bonding_curve();
+        let shares = u128::from(shares).min(supply);
+        let fee_bps = self.trading_fee_bps(&market, curve.price_at(supply), curve.price_at(supply - shares));
+        Ok(TradeQuote::new(
+            Shares::from_attos(shares),
+            TradeAmounts::carve(Tokens::from_attos(curve.sell_value(supply, shares)), fee_bps),
+            PricePerShare::from_attos(curve.price_at(supply)),
+            PricePerShare::from_attos(curve.price_at(supply - shares)),
+        ))
+    }
+
+    /// Collective achievements a guild has unlocked, in unlock order
+    async fn guild_achievements(
+        &self,
+        guild_id: GuildId,
+    ) -> async_graphql::Result<Vec<GuildAchievement>> {
+        let guild = self
+            .state
+            .guilds
+            .get(&guild_id)
+            .await?
+            .ok_or_else(|| async_graphql::Error::new("guild not found"))?;
+        let mut achievements = Vec::new();
+        for achievement_id in guild.achievements_earned {
+            if let Some(achievement) = self.state.guild_achievements.get(&achievement_id).await? {
+                achievements.push(achievement);
+            }
+        }
+        Ok(achievements)
+    }
+
+    /// Every achievement players can unlock, ordered by ID
+    async fn achievements(&self, locale: Option<String>) -> async_graphql::Result<Vec<Achievement>> {
+        let mut achievements = Vec::new();
+        self.state
+            .achievements
+            .for_each_index_value(|_, achievement| {
+                let achievement = achievement.into_owned();
+                achievements.push(match locale.as_deref() {
+                    Some(locale) => achievement.localized(locale),
+                    None => achievement,
+                });
+                Ok(())
+            })
+            .await?;
+        Ok(achievements)
+    }
+
+    /// A page of a guild's announcements, newest first
+    async fn guild_announcements(
+        &self,
+        guild_id: GuildId,
+        #[graphql(default = 0)] offset: usize,
+        #[graphql(default = 20)] limit: usize,
+    ) -> async_graphql::Result<Vec<GuildAnnouncement>> {
+        let board = self
+            .state
+            .guild_announcements
+            .get(&guild_id)
+            .await?
+            .unwrap_or_default();
+        Ok(board.into_iter().rev().skip(offset).take(limit).collect())
+    }
+
+    /// A page of a market's participants, in the order they joined
+    async fn market_participants(
+        &self,
+        market_id: MarketId,
+        #[graphql(default = 0)] offset: u32,
+        #[graphql(default = 100)] limit: u32,
+    ) -> async_graphql::Result<Vec<PlayerId>> {
+        let Some(participants) = self.state.market_participants.try_load_entry(&market_id).await? else {
+            return Ok(Vec::new());
+        };
+        let start = offset as usize;
+        let end = start
+            .saturating_add(limit.min(MAX_EVENTS_PAGE) as usize)
+            .min(participants.count());
+        if start >= end {
+            return Ok(Vec::new());
+        }
+        Ok(participants.read(start..end).await?)
+    }
+
+    /// Chains notified when a market resolves
+    async fn market_subscribers(&self, market_id: MarketId) -> async_graphql::Result<Vec<ChainId>> {
+        Ok(self.state.market_subscribers.get(&market_id).await?.unwrap_or_default())
+    }
+
+    /// A single market, including its metadata for rendering market cards
+    async fn market(&self, market_id: MarketId, locale: Option<String>) -> async_graphql::Result<Option<Market>> {
+        Ok(self.localized_market(market_id, locale.as_deref()).await?)
+    }
+
+    /// Spectator statistics of a market that hasn't been resolved or cancelled, listing at most
+    /// `limit` of its largest positions
+    async fn spectator_stats(
+        &self,
+        market_id: MarketId,
+        #[graphql(default = 10)] limit: usize,
+    ) -> async_graphql::Result<Option<SpectatorStats>> {
+        let Some(market) = self.state.markets.get(&market_id).await? else {
+            return Ok(None);
+        };
+        if matches!(market.status, MarketStatus::Resolved | MarketStatus::Cancelled) {
+            return Ok(None);
+        }
+        let activity = self.state.market_activity.get(&market_id).await?.unwrap_or_default();
+        let mut largest_positions: Vec<AnonymousPosition> = market
+            .positions
+            .values()
+            .flat_map(|position| position.shares_by_outcome.iter())
+            .map(|(outcome_id, shares)| AnonymousPosition {
+                outcome_id: *outcome_id,
+                shares: *shares,
+            })
+            .collect();
+        largest_positions.sort_by(|a, b| b.shares.cmp(&a.shares).then(a.outcome_id.cmp(&b.outcome_id)));
+        largest_positions.truncate(limit.min(MAX_SPECTATOR_POSITIONS));
+        let hourly_volume = activity
+            .hourly_volume
+            .iter()
+            .map(|(hour, volume)| HourlyVolume {
+                starts_at: Timestamp::from(hour * MICROS_PER_HOUR),
+                volume: *volume,
+            })
+            .collect();
+        let outcome_trades = (0..market.outcomes.len() as OutcomeId)
+            .map(|outcome_id| OutcomeTrades {
+                outcome_id,
+                buys: activity.buys.get(&outcome_id).copied().unwrap_or(0),
+                sells: activity.sells.get(&outcome_id).copied().unwrap_or(0),
+            })
+            .collect();
+        Ok(Some(SpectatorStats {
+            market_id,
+            status: market.status,
+            largest_positions,
+            hourly_volume,
+            outcome_trades,
+        }))
+    }
+
+    /// Market a slug was given to, unless it has been archived
+    async fn market_by_slug(&self, slug: String, locale: Option<String>) -> async_graphql::Result<Option<Market>> {
+        let Some(market_id) = self.state.market_slugs.get(&slug).await? else {
+            return Ok(None);
+        };
+        Ok(self.localized_market(market_id, locale.as_deref()).await?)
+    }
+
+    /// Summary of a market that has been archived
+    async fn archived_market(&self, market_id: MarketId) -> async_graphql::Result<Option<MarketSummary>> {
+        Ok(self.state.market_archive.get(&market_id).await?)
+    }
+
+    /// Markets whose title or description contains every word of `query`, newest first
+    async fn search_markets(
+        &self,
+        query: String,
+        #[graphql(default = 20)] limit: usize,
+        locale: Option<String>,
+    ) -> async_graphql::Result<Vec<Market>> {
+        let keywords = search::keywords(&query);
+        let mut matches: Option<Vec<MarketId>> = None;
+        for keyword in &keywords {
+            let market_ids = self
+                .state
+                .market_search_index
+                .get(keyword)
+                .await?
+                .unwrap_or_default();
+            matches = Some(match matches {
+                None => market_ids,
+                Some(previous) => previous
+                    .into_iter()
+                    .filter(|id| market_ids.binary_search(id).is_ok())
+                    .collect(),
+            });
+        }
+        let mut markets = Vec::new();
+        for market_id in matches
+            .unwrap_or_default()
+            .into_iter()
+            .rev()
+            .take(limit.min(search::MAX_SEARCH_RESULTS))
+        {
+            if let Some(market) = self.localized_market(market_id, locale.as_deref()).await? {
+                markets.push(market);
+            }
+        }
+        Ok(markets)
+    }
+
+    /// Open markets closing within `within_seconds` of the latest block, soonest first
+    async fn ending_soon(
+        &self,
+        within_seconds: u64,
+        #[graphql(default = 20)] limit: usize,
+        locale: Option<String>,
+    ) -> async_graphql::Result<Vec<Market>> {
+        let now = self.runtime.system_time();
+        let until = now.saturating_add(TimeDelta::from_secs(within_seconds));
+        let mut markets = Vec::new();
+        for market_id in self.markets_ending(Some(now), until, limit).await? {
+            if let Some(market) = self.localized_market(market_id, locale.as_deref()).await? {
+                markets.push(market);
+            }
+        }
+        Ok(markets)
+    }
+
+    /// Markets past their end time that haven't closed yet, earliest first; keepers close
+    /// them with `TriggerResolution`
+    async fn due_markets(&self, #[graphql(default = 20)] limit: usize) -> async_graphql::Result<Vec<MarketId>> {
+        Ok(self.markets_ending(None, self.runtime.system_time(), limit).await?)
+    }
+
+    /// All markets ordered by ID
+    async fn markets(&self, locale: Option<String>) -> async_graphql::Result<Vec<Market>> {
+        let mut markets = Vec::new();
+        self.state
+            .markets
+            .for_each_index_value(|_, market| {
+                let market = market.into_owned();
+                markets.push(match locale.as_deref() {
+                    Some(locale) => market.localized(locale),
+                    None => market,
+                });
+                Ok(())
+            })
+            .await?;
+        Ok(markets)
+    }
+}
+
+/// Serialize `limit` values of a map starting at `offset`, in key order, with the map's size
+async fn export_page<I, V>(
+    map: &MapView<I, V>,
+    offset: usize,
+    limit: usize,
+) -> async_graphql::Result<(usize, String)>
+where
+    I: Serialize + DeserializeOwned + Send + Sync + Clone + 'static,
+    V: Serialize + DeserializeOwned + Send + Sync + Clone + 'static,
+{
+    let indices = map.indices().await?;
+    let mut records = Vec::new();
+    for index in indices.iter().skip(offset).take(limit) {
+        if let Some(value) = map.get(index).await? {
+            records.push(value);
+        }
+    }
+    Ok((indices.len(), serde_json::to_string(&records)?))
+}
+
+/// Check the arguments of the mutations in `request` before any of them is scheduled, so a
+/// malformed operation is rejected with a descriptive error instead of failing in the contract
+/// Only top-level fields are checked, and documents that don't parse are left to the executor
+fn mutation_errors(request: &Request, config: &GameConfig) -> Vec<ServerError> {
+    let Ok(document) = parse_query(&request.query) else {
+        return Vec::new();
+    };
+    let mut errors = Vec::new();
+    for (name, operation) in document.operations.iter() {
+        let selected = request
+            .operation_name
+            .as_deref()
+            .is_none_or(|wanted| name.is_some_and(|name| name.as_str() == wanted));
+        if operation.node.ty != OperationType::Mutation || !selected {
+            continue;
+        }
+        // A variable that wasn't passed takes its default value
+        let variable_value = |variable: Name| {
+            let default = operation
+                .node
+                .variable_definitions
+                .iter()
+                .find(|definition| definition.node.name.node == variable)
+                .and_then(|definition| definition.node.default_value.as_ref())
+                .map(|value| value.node.clone());
+            Ok::<_, Infallible>(request.variables.get(&variable).cloned().or(default).unwrap_or(Value::Null))
+        };
+        for selection in &operation.node.selection_set.node.items {
+            let Selection::Field(field) = &selection.node else {
+                continue;
+            };
+            let arguments: BTreeMap<&str, Value> = field
+                .node
+                .arguments
+                .iter()
+                .filter_map(|(name, value)| {
+                    let value = value.node.clone().into_const_with(&variable_value).ok()?;
+                    Some((name.node.as_str(), value))
+                })
+                .collect();
+            let mutation = field.node.name.node.as_str();
+            if let Err(message) = validate_mutation(mutation, &arguments, config) {
+                errors.push(ServerError::new(format!("{mutation}: {message}"), Some(field.pos)));
+            }
+        }
+    }
+    errors
+}
+
+/// Check a mutation's arguments against the limits the contract enforces
+/// Arguments that are missing or of the wrong type are left to the executor to report
+fn validate_mutation(mutation: &str, arguments: &BTreeMap<&str, Value>, config: &GameConfig) -> Result<(), String> {
+    match mutation {
+        "createMarket" => {
+            validate_title(arguments.get("title"))?;
+            validate_outcomes(arguments.get("outcomeNames"), 2, config.max_outcomes_per_market)?;
+            validate_duration(arguments.get("durationSeconds"), config)?;
+            if let Some(seconds) = arguments.get("bootstrapSeconds").and_then(as_u64) {
+                if seconds == 0 || seconds > MAX_BOOTSTRAP_SECONDS {
+                    return Err(format!("bootstrapSeconds must be between 1 and {MAX_BOOTSTRAP_SECONDS}"));
+                }
+            }
+        }
+        "updateMarket" => validate_title(arguments.get("title"))?,
+        "proposeOutcomeCorrection" => {
+            if matches!(arguments.get("newName"), Some(Value::String(name)) if name.trim().is_empty()) {
+                return Err("newName must not be empty".to_string());
+            }
+        }
+        "importMarkets" => {
+            let Some(Value::List(markets)) = arguments.get("markets") else {
+                return Ok(());
+            };
+            if markets.is_empty() || markets.len() > MAX_IMPORT_MARKETS {
+                return Err(format!(
+                    "markets must list between 1 and {MAX_IMPORT_MARKETS} markets, got {}",
+                    markets.len()
+                ));
+            }
+            for (index, market) in markets.iter().enumerate() {
+                let Value::Object(spec) = market else {
+                    continue;
+                };
+                validate_title(spec.get("title"))
+                    .and_then(|()| validate_outcomes(spec.get("outcomeNames"), 2, config.max_outcomes_per_market))
+                    .and_then(|()| validate_duration(spec.get("durationSeconds"), config))
+                    .map_err(|message| format!("markets[{index}]: {message}"))?;
+            }
+        }
+        "createHeadToHead" => {
+            validate_outcomes(arguments.get("outcomeNames"), 2, 2)?;
+            validate_duration(arguments.get("durationSeconds"), config)?;
+        }
+        _ => {}
+    }
+    let amount_argument = match mutation {
+        "buyShares" | "commitToOutcome" | "commitBlindTrade" | "contributeToGuild" | "guildBuyShares"
+        | "depositGuildTokens" | "withdrawGuildTokens" => "amount",
+        "sellShares" | "startPositionAuction" | "fillPositionAuction" | "transferShares" | "createShareOffer" => "shares",
+        "mintCompleteSet" | "burnCompleteSet" => "sets",
+        "registerAsResolver" | "createHeadToHead" | "createParlay" => "stake",
+        _ => return Ok(()),
+    };
+    let amount = match arguments.get(amount_argument) {
+        Some(Value::String(amount)) => amount.parse::<Amount>().ok(),
+        _ => None,
+    };
+    if amount == Some(Amount::ZERO) {
+        return Err(format!("{amount_argument} must be greater than zero"));
+    }
+    Ok(())
+}
+
+fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(number) => number.as_u64(),
+        _ => None,
+    }
+}
+
+fn validate_title(title: Option<&Value>) -> Result<(), String> {
+    let Some(Value::String(title)) = title else {
+        return Ok(());
+    };
+    if title.trim().is_empty() {
+        return Err("title must not be empty".to_string());
+    }
+    if title.chars().count() > MAX_MARKET_TITLE_LENGTH {
+        return Err(format!("title must be at most {MAX_MARKET_TITLE_LENGTH} characters"));
+    }
+    Ok(())
+}
+
+fn validate_outcomes(outcome_names: Option<&Value>, min: usize, max: usize) -> Result<(), String> {
+    let Some(Value::List(outcome_names)) = outcome_names else {
+        return Ok(());
+    };
+    let count = outcome_names.len();
+    if count < min || count > max {
+        return Err(if min == max {
+            format!("outcomeNames must list exactly {min} outcomes, got {count}")
+        } else {
+            format!("outcomeNames must list between {min} and {max} outcomes, got {count}")
+        });
+    }
+    if outcome_names.iter().any(|name| matches!(name, Value::String(name) if name.trim().is_empty())) {
+        return Err("outcome names must not be empty".to_string());
+    }
+    Ok(())
+}
+
+fn validate_duration(duration_seconds: Option<&Value>, config: &GameConfig) -> Result<(), String> {
+    match duration_seconds.and_then(as_u64) {
+        Some(seconds) if seconds < config.min_market_duration_seconds => Err(format!(
+            "durationSeconds must be at least {}, got {seconds}",
+            config.min_market_duration_seconds
+        )),
+        _ => Ok(()),
     }
 }
 
@@ -64,7 +1362,7 @@ impl QueryRoot {
 mod tests {
     use std::sync::Arc;
 
-    use async_graphql::{Request, Response, Value};
+    use async_graphql::{Request, Response, Value, Variables};
     use futures::FutureExt as _;
     use linera_sdk::{linera_base_types::Amount, util::BlockingWait, views::View, Service, ServiceRuntime};
     use serde_json::json;
@@ -80,7 +1378,10 @@ mod tests {
             .expect("Failed to read from mock key value store");
         state.total_supply.set(total_supply);
 
-        let service = PredictiveManagerService { state, runtime };
+        let service = PredictiveManagerService {
+            state: Arc::new(state),
+            runtime,
+        };
         let request = Request::new("{ totalSupply }");
 
         let response = service
@@ -92,4 +1393,32 @@ mod tests {
 
         assert_eq!(response, expected)
     }
+
+    #[test]
+    fn malformed_mutations_are_rejected() {
+        let runtime = Arc::new(ServiceRuntime::<PredictiveManagerService>::new());
+        let state = PredictionMarketState::load(runtime.root_view_storage_context())
+            .blocking_wait()
+            .expect("Failed to read from mock key value store");
+        let service = PredictiveManagerService {
+            state: Arc::new(state),
+            runtime,
+        };
+        let request = Request::new(
+            "mutation($amount: Amount!) { \
+                createMarket(title: \" \", description: \"\", outcomeNames: [\"Yes\"], durationSeconds: 3600, \
+                    resolutionMethod: \"CREATOR\") \
+                buyShares(marketId: 0, outcomeId: 0, amount: $amount, maxPricePerShare: \"1\") \
+            }",
+        )
+        .variables(Variables::from_json(json!({"amount": "0"})));
+
+        let response = service
+            .handle_query(request)
+            .now_or_never()
+            .expect("Validation should not await anything");
+
+        let messages: Vec<_> = response.errors.iter().map(|error| error.message.as_str()).collect();
+        assert_eq!(messages, ["createMarket: title must not be empty", "buyShares: amount must be greater than zero"]);
+    }
 }
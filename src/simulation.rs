@@ -0,0 +1,437 @@
+// Simulation: an off-chain backtest of the token economy for tuning a `GameConfig` before it is
+// deployed. Synthetic players claim daily rewards, create markets and trade them over simulated
+// days with the same curves, fee schedule and payout math as the contract, and every day ends
+// with an economy report. Only the pure game logic is driven, so no chain or views are involved
+// and the same seed always plays out the same way.
+
+use linera_sdk::linera_base_types::Amount;
+use std::collections::BTreeMap;
+
+use crate::math::{self, BondingCurve, BASIS_POINTS, ONE};
+use crate::metrics::{EconomyDay, TokenFlow};
+use crate::state::{GameConfig, MarketType, OutcomeId};
+use crate::units::{Tokens, TradeAmounts};
+
+/// Seconds in a simulated day
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Shape of the synthetic population and its activity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimulationParams {
+    pub players: u32,
+    pub days: u32,
+    /// Markets opened each day, by players who can pay the creation cost
+    pub markets_per_day: u32,
+    pub outcomes_per_market: u32,
+    /// Days each market trades before it resolves
+    pub market_days: u32,
+    /// Chance that a player logs in on a given day and claims the daily reward
+    pub login_percent: u8,
+    /// Trades each player who logged in places that day
+    pub trades_per_login: u32,
+    /// Largest share of their balance a player spends on one buy
+    pub max_buy_percent: u8,
+    /// Chance that a trade sells part of a position instead of buying
+    pub sell_percent: u8,
+    pub seed: u64,
+}
+
+impl Default for SimulationParams {
+    fn default() -> Self {
+        Self {
+            players: 100,
+            days: 30,
+            markets_per_day: 3,
+            outcomes_per_market: 2,
+            market_days: 3,
+            login_percent: 60,
+            trades_per_login: 2,
+            max_buy_percent: 10,
+            sell_percent: 25,
+            seed: 0,
+        }
+    }
+}
+
+/// State of the economy at the end of a simulated day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayReport {
+    pub day: u64,
+    /// Token sources and sinks of the day, bucketed as the contract's economy metrics
+    pub flows: EconomyDay,
+    /// Tokens in player balances and the platform's share of fees
+    pub circulating: Amount,
+    /// Tokens held by market pools, including what resolved markets didn't pay out
+    pub locked: Amount,
+    /// Change of the total supply over the day, in basis points of the previous day's
+    pub inflation_bps: i64,
+    /// Inequality of player balances, in basis points
+    pub gini_bps: u32,
+    pub open_markets: u32,
+}
+
+impl DayReport {
+    pub fn total_supply(&self) -> Amount {
+        self.circulating.saturating_add(self.locked)
+    }
+}
+
+/// Every day of a simulation, in order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SimulationReport {
+    pub days: Vec<DayReport>,
+}
+
+impl SimulationReport {
+    /// Compound inflation over the whole run, in basis points of the first day's opening supply
+    pub fn total_inflation_bps(&self, opening_supply: Amount) -> i64 {
+        let closing = self.days.last().map_or(opening_supply, DayReport::total_supply);
+        change_bps(u128::from(opening_supply), u128::from(closing))
+    }
+}
+
+/// A market of the simulation: its curve, pool and positions
+#[derive(Debug, Clone)]
+struct SimMarket {
+    creator: usize,
+    curve: BondingCurve,
+    supplies: Vec<u128>,
+    liquidity: u128,
+    positions: BTreeMap<(usize, OutcomeId), u128>,
+    resolves_on: u64,
+    resolved: bool,
+}
+
+/// A small deterministic generator (SplitMix64), so runs can be replayed from their seed
+#[derive(Debug, Clone)]
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `0..bound`; 0 for an empty range
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            return 0;
+        }
+        self.next_u64() % bound
+    }
+
+    fn chance(&mut self, percent: u8) -> bool {
+        self.below(100) < u64::from(percent)
+    }
+}
+
+/// A running simulation, advanced one day at a time
+pub struct Simulation {
+    config: GameConfig,
+    params: SimulationParams,
+    rng: Rng,
+    balances: Vec<u128>,
+    platform: u128,
+    markets: Vec<SimMarket>,
+    day: u64,
+    /// Flows of the day being simulated
+    flows: EconomyDay,
+}
+
+impl Simulation {
+    /// Register every player with the configured starting balance
+    pub fn new(config: GameConfig, params: SimulationParams) -> Self {
+        let mut simulation = Self {
+            rng: Rng(params.seed),
+            balances: vec![u128::from(config.initial_player_tokens); params.players as usize],
+            platform: 0,
+            markets: Vec::new(),
+            day: 0,
+            flows: EconomyDay::new(0),
+            config,
+            params,
+        };
+        let bonuses = u128::from(simulation.config.initial_player_tokens).saturating_mul(u128::from(params.players));
+        simulation.record(TokenFlow::RegistrationBonus, bonuses);
+        simulation
+    }
+
+    /// Simulate `params.days` days from a fresh start
+    pub fn run(config: GameConfig, params: SimulationParams) -> SimulationReport {
+        let mut simulation = Self::new(config, params);
+        SimulationReport {
+            days: (0..params.days).map(|_| simulation.step_day()).collect(),
+        }
+    }
+
+    /// Supply the first day starts from: every player's registration bonus
+    pub fn opening_supply(config: &GameConfig, params: &SimulationParams) -> Amount {
+        Amount::from_attos(u128::from(config.initial_player_tokens).saturating_mul(u128::from(params.players)))
+    }
+
+    /// Play one day: logins and the trades of those who logged in, new markets, then the
+    /// resolutions of the markets due
+    pub fn step_day(&mut self) -> DayReport {
+        let supply_before = self.total_supply();
+        for player in 0..self.balances.len() {
+            if !self.rng.chance(self.params.login_percent) {
+                continue;
+            }
+            let reward = u128::from(self.config.daily_login_reward);
+            self.balances[player] = self.balances[player].saturating_add(reward);
+            self.record(TokenFlow::DailyReward, reward);
+            for _ in 0..self.params.trades_per_login {
+                self.trade(player);
+            }
+        }
+        for _ in 0..self.params.markets_per_day {
+            self.create_market();
+        }
+        for index in 0..self.markets.len() {
+            if !self.markets[index].resolved && self.markets[index].resolves_on <= self.day {
+                self.resolve(index);
+            }
+        }
+
+        let locked = self.locked();
+        let circulating = self.total_supply().saturating_sub(locked);
+        let report = DayReport {
+            day: self.day,
+            flows: std::mem::replace(&mut self.flows, EconomyDay::new(self.day + 1)),
+            circulating: Amount::from_attos(circulating),
+            locked: Amount::from_attos(locked),
+            inflation_bps: change_bps(supply_before, circulating.saturating_add(locked)),
+            gini_bps: math::gini_bps(&self.balances),
+            open_markets: self.markets.iter().filter(|market| !market.resolved).count() as u32,
+        };
+        self.day += 1;
+        report
+    }
+
+    fn total_supply(&self) -> u128 {
+        let balances = self.balances.iter().fold(0u128, |total, balance| total.saturating_add(*balance));
+        balances.saturating_add(self.platform).saturating_add(self.locked())
+    }
+
+    fn locked(&self) -> u128 {
+        self.markets.iter().fold(0u128, |total, market| total.saturating_add(market.liquidity))
+    }
+
+    fn record(&mut self, flow: TokenFlow, attos: u128) {
+        self.flows.record(flow, Amount::from_attos(attos));
+    }
+
+    /// A random player who can pay the creation cost opens a market, as `CreateMarket` would
+    fn create_market(&mut self) {
+        let cost = u128::from(self.config.market_creation_cost);
+        let creator = self.rng.below(self.balances.len() as u64) as usize;
+        if self.balances.is_empty() || self.balances[creator] < cost {
+            return;
+        }
+        self.balances[creator] -= cost;
+        self.record(TokenFlow::CreationCost, cost);
+        let split = math::creation_fee(
+            cost,
+            self.config.market_creator_fee_percent,
+            self.config.platform_fee_percent,
+        );
+        self.balances[creator] = self.balances[creator].saturating_add(split.creator);
+        self.platform = self.platform.saturating_add(split.platform);
+        self.record(TokenFlow::FeeReward, split.distributed());
+
+        let curve = self.config.curve_for(&MarketType::QuickPrediction);
+        self.markets.push(SimMarket {
+            creator,
+            curve: BondingCurve::with_multiplier(ONE, curve.price_cap_multiplier, u128::from(curve.depth)),
+            supplies: vec![0; self.params.outcomes_per_market.max(2) as usize],
+            liquidity: 0,
+            positions: BTreeMap::new(),
+            resolves_on: self.day + u64::from(self.params.market_days.max(1)),
+            resolved: false,
+        });
+    }
+
+    /// Buy into a random open market, or sometimes sell part of a position in it
+    fn trade(&mut self, player: usize) {
+        let open: Vec<usize> = (0..self.markets.len()).filter(|index| !self.markets[*index].resolved).collect();
+        if open.is_empty() {
+            return;
+        }
+        let index = open[self.rng.below(open.len() as u64) as usize];
+        let outcome_id = self.rng.below(self.markets[index].supplies.len() as u64) as OutcomeId;
+        let held = self.markets[index].positions.get(&(player, outcome_id)).copied().unwrap_or(0);
+        if held > 0 && self.rng.chance(self.params.sell_percent) {
+            let percent = 1 + self.rng.below(100) as u8;
+            self.sell(player, index, outcome_id, math::apply_percent(held, percent));
+        } else {
+            let percent = 1 + self.rng.below(u64::from(self.params.max_buy_percent.clamp(1, 100))) as u8;
+            self.buy(player, index, outcome_id, math::apply_percent(self.balances[player], percent));
+        }
+    }
+
+    /// Spend `gross` tokens on an outcome, the fee carved out as `BuyShares` does
+    fn buy(&mut self, player: usize, index: usize, outcome_id: OutcomeId, gross: u128) {
+        if gross == 0 {
+            return;
+        }
+        let market = &self.markets[index];
+        let supply = market.supplies[outcome_id as usize];
+        let bought = supply.saturating_add(market.curve.shares_for_amount(supply, gross));
+        let fee_bps = self.fee_bps(index, supply, bought);
+        let trade = TradeAmounts::carve(Tokens::from_attos(gross), fee_bps);
+        let market = &mut self.markets[index];
+        let shares = market.curve.shares_for_amount(supply, trade.net.attos());
+        market.supplies[outcome_id as usize] = supply.saturating_add(shares);
+        market.liquidity = market.liquidity.saturating_add(trade.net.attos());
+        *market.positions.entry((player, outcome_id)).or_default() += shares;
+        self.balances[player] -= gross;
+        self.collect_fee(player, index, gross, fee_bps);
+    }
+
+    /// Sell `shares` of an outcome back to the curve, the fee coming out of their value
+    fn sell(&mut self, player: usize, index: usize, outcome_id: OutcomeId, shares: u128) {
+        if shares == 0 {
+            return;
+        }
+        let market = &self.markets[index];
+        let supply = market.supplies[outcome_id as usize];
+        let gross = market.curve.sell_value(supply, shares);
+        let fee_bps = self.fee_bps(index, supply, supply.saturating_sub(shares));
+        let trade = TradeAmounts::carve(Tokens::from_attos(gross), fee_bps);
+        let market = &mut self.markets[index];
+        market.supplies[outcome_id as usize] = supply.saturating_sub(shares);
+        market.liquidity = market.liquidity.saturating_sub(gross);
+        if let Some(held) = market.positions.get_mut(&(player, outcome_id)) {
+            *held = held.saturating_sub(shares);
+        }
+        self.balances[player] = self.balances[player].saturating_add(trade.net.attos());
+        self.collect_fee(player, index, gross, fee_bps);
+    }
+
+    /// Fee rate of a trade moving an outcome's supply from `supply` to `supply_after` today
+    fn fee_bps(&self, index: usize, supply: u128, supply_after: u128) -> u32 {
+        let market = &self.markets[index];
+        let impact_bps = math::price_impact_bps(market.curve.price_at(supply), market.curve.price_at(supply_after));
+        let seconds_left = market.resolves_on.saturating_sub(self.day).saturating_mul(SECONDS_PER_DAY);
+        self.config.fee_schedule.fee_bps(impact_bps, seconds_left)
+    }
+
+    /// Split a trading fee as the contract does: part burned, the rest shared between the
+    /// creator and the platform, the creator's share going to the platform on their own market
+    fn collect_fee(&mut self, trader: usize, index: usize, trade_amount: u128, fee_bps: u32) {
+        let mut split = math::trading_fee(trade_amount, fee_bps, self.config.trading_fee_burn_percent);
+        let creator = self.markets[index].creator;
+        if trader == creator {
+            split.platform += split.creator;
+            split.creator = 0;
+        }
+        self.balances[creator] = self.balances[creator].saturating_add(split.creator);
+        self.platform = self.platform.saturating_add(split.platform);
+        self.record(TokenFlow::TradingFee, split.total());
+        self.record(TokenFlow::FeeReward, split.distributed());
+        self.record(TokenFlow::Burn, split.burned);
+    }
+
+    /// Resolve a market to an outcome drawn in proportion to its price, pay the winners one
+    /// token per share and burn the configured part of the losing liquidity
+    fn resolve(&mut self, index: usize) {
+        let market = &self.markets[index];
+        let prices: Vec<u128> = market.supplies.iter().map(|supply| market.curve.price_at(*supply)).collect();
+        let total_price = prices.iter().sum::<u128>();
+        let mut draw = math::mul_div(u128::from(self.rng.next_u64()), total_price, u128::from(u64::MAX));
+        let winner = prices
+            .iter()
+            .position(|price| {
+                let hit = draw < *price;
+                draw = draw.saturating_sub(*price);
+                hit
+            })
+            .unwrap_or(prices.len() - 1) as OutcomeId;
+
+        let market = &mut self.markets[index];
+        market.resolved = true;
+        let winning_shares = market.supplies[winner as usize];
+        let payouts = math::ranked_payout(&[winning_shares], &[BASIS_POINTS]);
+        let losing = market.liquidity.saturating_sub(payouts);
+        let burn = math::apply_percent(losing, self.config.losing_liquidity_burn_percent);
+        market.liquidity = market.liquidity.saturating_sub(burn);
+        let mut paid = Vec::new();
+        for ((player, outcome_id), shares) in &market.positions {
+            if *outcome_id == winner && *shares > 0 {
+                let payout = math::ranked_payout(&[*shares], &[BASIS_POINTS]).min(market.liquidity);
+                market.liquidity -= payout;
+                paid.push((*player, payout));
+            }
+        }
+        for (player, payout) in paid {
+            self.balances[player] = self.balances[player].saturating_add(payout);
+        }
+        self.record(TokenFlow::Burn, burn);
+    }
+}
+
+/// Relative change from `before` to `after` in basis points of `before`, negative for a fall
+fn change_bps(before: u128, after: u128) -> i64 {
+    let change = math::mul_div(before.abs_diff(after), u128::from(BASIS_POINTS), before).min(i64::MAX as u128) as i64;
+    if after < before {
+        -change
+    } else {
+        change
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_replay_from_their_seed() {
+        let params = SimulationParams { players: 20, days: 10, ..SimulationParams::default() };
+        let first = Simulation::run(GameConfig::default(), params);
+        assert_eq!(first, Simulation::run(GameConfig::default(), params));
+        assert_ne!(first, Simulation::run(GameConfig::default(), SimulationParams { seed: 1, ..params }));
+        assert_eq!(first.days.len(), 10);
+    }
+
+    #[test]
+    fn daily_rewards_alone_inflate_evenly() {
+        let params = SimulationParams {
+            players: 10,
+            days: 1,
+            markets_per_day: 0,
+            login_percent: 100,
+            ..SimulationParams::default()
+        };
+        let report = Simulation::run(GameConfig::default(), params);
+        let day = &report.days[0];
+        // Ten players holding 1000 each claim 10 each: 1% inflation, evenly spread
+        assert_eq!(day.total_supply(), Amount::from_tokens(10_100));
+        assert_eq!(day.inflation_bps, 100);
+        assert_eq!(day.gini_bps, 0);
+        assert_eq!(day.flows.daily_rewards, Amount::from_tokens(100));
+    }
+
+    #[test]
+    fn only_rewards_and_burns_change_the_supply() {
+        let config = GameConfig::default();
+        let params = SimulationParams { players: 30, days: 15, ..SimulationParams::default() };
+        let report = Simulation::run(config.clone(), params);
+        let mut supply = u128::from(Simulation::opening_supply(&config, &params));
+        for day in &report.days {
+            // Creation costs leave and their fee rewards come back, trades move tokens between
+            // balances, pools and the platform, and burns destroy them
+            let creation_rewards = math::creation_fee(
+                u128::from(day.flows.creation_costs),
+                config.market_creator_fee_percent,
+                config.platform_fee_percent,
+            );
+            supply += u128::from(day.flows.daily_rewards) + creation_rewards.distributed();
+            supply -= u128::from(day.flows.creation_costs) + u128::from(day.flows.burned);
+            assert_eq!(u128::from(day.total_supply()), supply);
+        }
+        assert!(report.days.iter().any(|day| day.flows.trading_fees > Amount::ZERO));
+    }
+}
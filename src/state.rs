@@ -1,26 +1,207 @@
-use linera_sdk::views::{linera_views, MapView, RegisterView, RootView, ViewStorageContext};
-use linera_sdk::linera_base_types::{AccountOwner, Amount, Timestamp};
-use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use linera_sdk::views::{linera_views, CustomSerialize, LogView, MapView, RegisterView, RootView, SetView, ViewError, ViewStorageContext};
+use linera_views::reentrant_collection_view::ReentrantCollectionView;
+use linera_views::set_view::CustomSetView;
+use linera_sdk::linera_base_types::{AccountOwner, Amount, ApplicationId, BcsHashable, ChainId, CryptoHash, Timestamp};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
+use crate::math::{self, BondingCurve};
+use crate::chests::{ChestTable, MysteryChest};
+use crate::events::{EventId, SpecialEvent, MAX_XP_MULTIPLIER_BPS};
+use crate::metrics::{EconomyDay, MarketActivity};
 
 pub type MarketId = u64;
 pub type PlayerId = AccountOwner;
 pub type OutcomeId = u32;
+/// Index of an auction among its market's auctions
+pub type AuctionId = u32;
+/// Index of a share offer among its market's offers
+pub type ShareOfferId = u32;
+/// Index of a market-maker quote among its market's quotes
+pub type BotQuoteId = u32;
 pub type GuildId = u64;
 pub type AchievementId = u32;
+pub type ChallengeId = u64;
+pub type GuildStakeId = u64;
+pub type GuildWithdrawalId = u64;
+pub type ParlayId = u64;
+pub type ItemId = u64;
+pub type RaffleId = u64;
+pub type SeasonId = u64;
+pub type GroupId = u64;
+pub type BracketId = u64;
+pub type AttestationId = u64;
 
-#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::InputObject)]
+pub use crate::math::BASIS_POINTS;
+/// Maximum number of tiers in a season pass
+pub const MAX_PASS_TIERS: usize = 100;
+/// Maximum number of markets the admin can import in one operation
+pub const MAX_IMPORT_MARKETS: usize = 50;
+/// Maximum number of markets in one batch of views reported by the engagement relayer
+pub const MAX_VIEW_BATCH: usize = 50;
+/// Maximum number of markets a player's own views are counted for in a day
+pub const MAX_DAILY_VIEWED_MARKETS: usize = 100;
+/// Maximum number of quotes a market maker can post in one operation
+pub const MAX_QUOTES_PER_BATCH: usize = 50;
+/// Maximum length of a market title accepted by the service's mutations
+pub const MAX_MARKET_TITLE_LENGTH: usize = 200;
+/// Maximum length of a URI stored in market metadata
+pub const MAX_METADATA_URI_LENGTH: usize = 512;
+/// Maximum number of characters in an outcome's emoji, enough for joined sequences
+pub const MAX_OUTCOME_EMOJI_CHARS: usize = 8;
+/// Maximum length of a locale tag (e.g. "en-US")
+pub const MAX_LOCALE_LENGTH: usize = 16;
+/// Locale of achievement text, and of market text whose metadata names no locale
+pub const DEFAULT_LOCALE: &str = "en";
+/// Maximum number of locales a market title or description can be translated into
+pub const MAX_TRANSLATIONS: usize = 20;
+/// Maximum length of a guild or system announcement
+pub const MAX_ANNOUNCEMENT_LENGTH: usize = 280;
+/// Number of announcements kept per guild; older ones are dropped
+pub const MAX_GUILD_ANNOUNCEMENTS: usize = 50;
+/// Longest allowed bootstrapping phase before trading opens
+pub const MAX_BOOTSTRAP_SECONDS: u64 = 7 * 24 * 60 * 60;
+/// Maximum number of legs in a parlay
+pub const MAX_PARLAY_LEGS: usize = 8;
+/// How long a recovery owner must have been linked before it can recover the account
+pub const RECOVERY_DELAY_SECONDS: u64 = 3 * 24 * 60 * 60;
+/// Maximum number of chains notified when a market resolves
+pub const MAX_MARKET_SUBSCRIBERS: usize = 100;
+/// Most events returned by one `events` query
+pub const MAX_EVENTS_PAGE: u32 = 500;
+/// Highest allowed ratio between a curve's price cap and its base price
+pub const MAX_PRICE_CAP_MULTIPLIER: u32 = 100;
+/// Largest token amount a config may grant, charge or require
+pub const MAX_CONFIG_TOKENS: u128 = 1_000_000;
+/// Longest notice a config may require before its changes take effect
+pub const MAX_CONFIG_NOTICE_SECONDS: u64 = 30 * 24 * 60 * 60;
+/// Allowed range for `min_market_duration_seconds`
+pub const MIN_MARKET_DURATION_BOUNDS: std::ops::RangeInclusive<u64> = 60..=365 * 24 * 60 * 60;
+/// Highest allowed `max_outcomes_per_market`; a market needs at least two
+pub const MAX_OUTCOMES_LIMIT: usize = 100;
+/// Highest trading fee rate a fee schedule may charge
+pub const MAX_TRADING_FEE_BPS: u32 = 1_000;
+/// Maximum length of a shop item's name
+pub const MAX_ITEM_NAME_LENGTH: usize = 64;
+/// Maximum length of the note attached to resolution evidence
+pub const MAX_EVIDENCE_NOTE_LENGTH: usize = 280;
+/// Maximum length of an outcome's name
+pub const MAX_OUTCOME_NAME_LENGTH: usize = 100;
+/// Maximum length of the reason given when flagging a market as ambiguous
+pub const MAX_FLAG_REASON_LENGTH: usize = 280;
+/// Maximum number of markets the admin can line up to be featured
+pub const MAX_FEATURED_QUEUE: usize = 30;
+/// Maximum number of mentorship milestones in a config
+pub const MAX_MENTORSHIP_MILESTONES: usize = 20;
+/// Maximum number of mentors a player can have pending offers from
+pub const MAX_MENTORSHIP_OFFERS: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "GameConfigInput")]
 pub struct GameConfig {
     pub admin: Option<AccountOwner>,
     pub initial_player_tokens: Amount,
     pub daily_login_reward: Amount,
     pub market_creation_cost: Amount,
+    /// Tokens a creator posts on top of the creation cost, refunded when the market resolves
+    /// without the arbitration council having to settle it; zero requires none
+    pub market_creator_bond: Amount,
+    /// Tokens burned when a chain subscribes to a market's resolution, so the subscriber
+    /// slots can't be filled for free
+    pub market_subscription_fee: Amount,
+    /// How long new markets wait before trading opens, while players may flag them as
+    /// ambiguous; zero opens trading at once
+    pub ambiguity_review_seconds: u64,
+    /// Reputation the flags on a market must add up to for it to pause
+    pub ambiguity_pause_weight: u64,
     pub min_market_duration_seconds: u64,
     pub max_outcomes_per_market: usize,
     pub oracle_voting_duration_seconds: u64,
     pub min_oracle_voters: u32,
+    /// Tokens a resolver must have staked to vote on oracle markets
+    pub min_resolver_stake: Amount,
+    /// Share of a resolver's stake slashed for voting against the result or missing a vote
+    pub resolver_slash_percent: u8,
+    /// Share of an oracle market's losing liquidity paid to resolvers who voted for the result
+    pub resolver_fee_percent: u8,
     pub market_creator_fee_percent: u8,
     pub platform_fee_percent: u8,
+    /// How long after creation a creator may still edit a market that already has trades
+    pub market_edit_window_seconds: u64,
+    /// Traders who entered this close to an early close may exit at cost basis
+    pub early_close_protection_seconds: u64,
+    /// Share of every trading fee that is burned instead of distributed
+    pub trading_fee_burn_percent: u8,
+    /// How trading fee rates scale with a trade's price impact and the time left to close
+    pub fee_schedule: FeeSchedule,
+    /// Share of the liquidity not paid out to winners that is burned at resolution
+    pub losing_liquidity_burn_percent: u8,
+    /// Share of a raffle's ticket sales burned when it is drawn
+    pub raffle_burn_percent: u8,
+    /// Market end times are rounded up to a multiple of this, so they don't hinge on a
+    /// proposer's clock at microsecond precision
+    pub end_time_granularity_seconds: u64,
+    /// Trades are rejected this close to a market's end time
+    pub trading_grace_seconds: u64,
+    /// Serve the `exportSnapshot` query; services can't authenticate callers, so exports are
+    /// off until the admin turns them on
+    pub snapshot_exports_enabled: bool,
+    /// How long winners have to claim after resolution; zero keeps claims open forever
+    pub claim_window_seconds: u64,
+    /// How long before the claim window closes that unclaimed winners can be warned
+    pub claim_expiry_warning_seconds: u64,
+    /// Settled markets can be archived this many days after they resolve
+    pub market_archive_after_days: u64,
+    /// Pay winners automatically when a market resolves instead of waiting for claims
+    pub auto_settlement: bool,
+    /// Positions paid per settlement message; the rest continue in follow-up messages
+    pub settlement_batch_size: u32,
+    /// Give winners bonus XP for how early they entered the market
+    pub early_conviction_rewards: bool,
+    /// Bonus XP for a winning position opened when its market was created
+    pub early_conviction_max_xp: u64,
+    /// Settled forecasts a player needs before appearing on the accuracy leaderboard
+    pub min_forecasts_for_accuracy_rank: u64,
+    /// Bonding curve shape for each kind of market
+    pub quick_prediction_curve: CurveParams,
+    pub tournament_curve: CurveParams,
+    pub seasonal_curve: CurveParams,
+    pub pvp_curve: CurveParams,
+    /// Reject buys that would exceed their market's price impact limit instead of partially
+    /// filling them
+    pub reject_oversized_buys: bool,
+    /// A player's buys in a market share one price impact limit for this long after the first
+    /// of them, so splitting an order doesn't get around it; zero limits each buy on its own
+    pub price_impact_window_seconds: u64,
+    /// Tokens paid to whoever refreshes the leaderboards with `RefreshLeaderboard`
+    pub leaderboard_refresh_reward: Amount,
+    /// Shortest time between two leaderboard refreshes
+    pub leaderboard_refresh_interval_seconds: u64,
+    /// Who may mentor whom, and the bonuses and milestone rewards pairs earn
+    pub mentorship: MentorshipConfig,
+    /// XP earned from trades in the day's featured market is scaled by this many basis points
+    pub featured_market_xp_multiplier_bps: u32,
+    /// Percentage of the usual trade XP a creator earns trading in their own market
+    pub self_trade_xp_percent: u8,
+    /// A resolved market counts as wash-traded against its creator's quality score when the
+    /// creator traded more than this percentage of its volume
+    pub wash_trade_volume_percent: u8,
+    /// XP earned for entering a market and for trading in it again
+    pub trade_xp: TradeXp,
+    /// How many markets each creator may have active at once
+    pub creator_market_cap: CreatorMarketCap,
+    /// Account allowed to report views batched by a frontend with `RecordViews`
+    pub engagement_relayer: Option<AccountOwner>,
+    /// Tokens of trading volume a view counts as when picking the trending market
+    pub trending_view_value: Amount,
+    /// When resolvers who keep voting together are flagged, and how much their votes count
+    pub collusion: CollusionConfig,
+    /// Shortest notice players get before a config change takes effect
+    pub config_change_notice_seconds: u64,
+    /// How long after a head-to-head challenge ends its players have to report the result;
+    /// after that a lone report stands
+    pub challenge_report_window_seconds: u64,
+    /// Basis points the house takes off a parlay's fair odds
+    pub parlay_house_edge_bps: u32,
 }
 
 impl Default for GameConfig {
@@ -30,21 +211,513 @@ impl Default for GameConfig {
             initial_player_tokens: Amount::from_tokens(1000),
             daily_login_reward: Amount::from_tokens(10),
             market_creation_cost: Amount::from_tokens(100),
+            market_creator_bond: Amount::ZERO,
+            market_subscription_fee: Amount::from_tokens(5),
+            ambiguity_review_seconds: 0,
+            ambiguity_pause_weight: 300,
             min_market_duration_seconds: 300,
             max_outcomes_per_market: 10,
             oracle_voting_duration_seconds: 3600,
             min_oracle_voters: 3,
+            min_resolver_stake: Amount::from_tokens(100),
+            resolver_slash_percent: 10,
+            resolver_fee_percent: 5,
             market_creator_fee_percent: 2,
             platform_fee_percent: 1,
+            market_edit_window_seconds: 600,
+            early_close_protection_seconds: 900,
+            trading_fee_burn_percent: 20,
+            fee_schedule: FeeSchedule::default(),
+            losing_liquidity_burn_percent: 10,
+            raffle_burn_percent: 10,
+            end_time_granularity_seconds: 1,
+            trading_grace_seconds: 5,
+            snapshot_exports_enabled: false,
+            claim_window_seconds: 0,
+            claim_expiry_warning_seconds: 24 * 60 * 60,
+            market_archive_after_days: 30,
+            auto_settlement: false,
+            settlement_batch_size: 50,
+            early_conviction_rewards: false,
+            early_conviction_max_xp: 50,
+            min_forecasts_for_accuracy_rank: 5,
+            quick_prediction_curve: CurveParams::default(),
+            tournament_curve: CurveParams {
+                price_cap_multiplier: 3,
+                depth: Amount::from_tokens(5_000),
+                max_price_impact_bps: 2_000,
+            },
+            seasonal_curve: CurveParams {
+                price_cap_multiplier: 4,
+                depth: Amount::from_tokens(20_000),
+                max_price_impact_bps: 1_000,
+            },
+            pvp_curve: CurveParams {
+                price_cap_multiplier: 2,
+                depth: Amount::from_tokens(100),
+                max_price_impact_bps: 0,
+            },
+            reject_oversized_buys: false,
+            price_impact_window_seconds: 60 * 60,
+            leaderboard_refresh_reward: Amount::from_tokens(1),
+            leaderboard_refresh_interval_seconds: 600,
+            mentorship: MentorshipConfig::default(),
+            featured_market_xp_multiplier_bps: 15_000,
+            self_trade_xp_percent: 50,
+            wash_trade_volume_percent: 50,
+            trade_xp: TradeXp::default(),
+            creator_market_cap: CreatorMarketCap::default(),
+            engagement_relayer: None,
+            trending_view_value: Amount::from_millis(100),
+            collusion: CollusionConfig::default(),
+            config_change_notice_seconds: 60 * 60,
+            challenge_report_window_seconds: 2 * 24 * 60 * 60,
+            parlay_house_edge_bps: 500,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl GameConfig {
+    /// Curve parameters new markets of this type are created with
+    pub fn curve_for(&self, market_type: &MarketType) -> CurveParams {
+        match market_type {
+            MarketType::QuickPrediction => self.quick_prediction_curve,
+            MarketType::TournamentMarket => self.tournament_curve,
+            MarketType::SeasonalEvent => self.seasonal_curve,
+            MarketType::PvPChallenge { .. } => self.pvp_curve,
+        }
+    }
+
+    /// Check every parameter is within its bounds, so a config can't stall the game with
+    /// markets no one can create or resolve, or flood the supply with rewards
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let max_tokens = Amount::from_tokens(MAX_CONFIG_TOKENS);
+        if [
+            self.initial_player_tokens,
+            self.daily_login_reward,
+            self.market_creation_cost,
+            self.market_creator_bond,
+            self.market_subscription_fee,
+            self.min_resolver_stake,
+            self.leaderboard_refresh_reward,
+            self.trending_view_value,
+        ]
+        .iter()
+        .chain(
+            self.mentorship
+                .milestones
+                .iter()
+                .flat_map(|milestone| [&milestone.apprentice_reward, &milestone.mentor_reward]),
+        )
+        .any(|amount| *amount > max_tokens)
+        {
+            return Err(ConfigError::TokenAmountTooLarge);
+        }
+        if !MIN_MARKET_DURATION_BOUNDS.contains(&self.min_market_duration_seconds) {
+            return Err(ConfigError::InvalidMarketDuration);
+        }
+        if !(2..=MAX_OUTCOMES_LIMIT).contains(&self.max_outcomes_per_market) {
+            return Err(ConfigError::InvalidMaxOutcomes);
+        }
+        if self.oracle_voting_duration_seconds == 0 || self.min_oracle_voters == 0 {
+            return Err(ConfigError::InvalidOracleVoting);
+        }
+        let percent_sums = [
+            u32::from(self.market_creator_fee_percent) + u32::from(self.platform_fee_percent),
+            u32::from(self.losing_liquidity_burn_percent) + u32::from(self.resolver_fee_percent),
+            u32::from(self.resolver_slash_percent),
+            u32::from(self.trading_fee_burn_percent),
+            u32::from(self.raffle_burn_percent),
+            u32::from(self.self_trade_xp_percent),
+            u32::from(self.wash_trade_volume_percent),
+            u32::from(self.collusion.agreement_percent),
+            u32::from(self.collusion.weight_percent),
+        ];
+        if percent_sums.iter().any(|percent| *percent > 100) {
+            return Err(ConfigError::InvalidPercent);
+        }
+        if self.end_time_granularity_seconds == 0
+            || self.trading_grace_seconds >= self.min_market_duration_seconds
+        {
+            return Err(ConfigError::InvalidMarketTiming);
+        }
+        if self.settlement_batch_size == 0 {
+            return Err(ConfigError::InvalidSettlementBatchSize);
+        }
+        if self.ambiguity_review_seconds > 0 && self.ambiguity_pause_weight == 0 {
+            return Err(ConfigError::InvalidAmbiguityReview);
+        }
+        if ![self.quick_prediction_curve, self.tournament_curve, self.seasonal_curve, self.pvp_curve]
+            .iter()
+            .all(CurveParams::is_valid)
+        {
+            return Err(ConfigError::InvalidCurve);
+        }
+        if !self.fee_schedule.is_valid() {
+            return Err(ConfigError::InvalidFeeSchedule);
+        }
+        if !self.mentorship.is_valid() {
+            return Err(ConfigError::InvalidMentorship);
+        }
+        if !self.trade_xp.is_valid() {
+            return Err(ConfigError::InvalidTradeXp);
+        }
+        if !self.creator_market_cap.is_valid() {
+            return Err(ConfigError::InvalidCreatorMarketCap);
+        }
+        if !(math::BASIS_POINTS..=MAX_XP_MULTIPLIER_BPS).contains(&self.featured_market_xp_multiplier_bps) {
+            return Err(ConfigError::InvalidFeaturedMultiplier);
+        }
+        if self.config_change_notice_seconds > MAX_CONFIG_NOTICE_SECONDS {
+            return Err(ConfigError::InvalidConfigNotice);
+        }
+        if self.challenge_report_window_seconds == 0 {
+            return Err(ConfigError::InvalidChallengeReportWindow);
+        }
+        if self.parlay_house_edge_bps >= math::BASIS_POINTS {
+            return Err(ConfigError::InvalidParlayHouseEdge);
+        }
+        Ok(())
+    }
+}
+
+/// Why a `GameConfig` was rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ConfigError {
+    #[error("token amounts in the config must be at most MAX_CONFIG_TOKENS")] TokenAmountTooLarge,
+    #[error("minimum market duration is out of bounds")] InvalidMarketDuration,
+    #[error("max outcomes per market must allow at least two and at most MAX_OUTCOMES_LIMIT")] InvalidMaxOutcomes,
+    #[error("oracle voting needs a duration and at least one voter")] InvalidOracleVoting,
+    #[error("percentages must not add up to more than 100")] InvalidPercent,
+    #[error("end time granularity must be positive and the trading grace shorter than a market")] InvalidMarketTiming,
+    #[error("settlement batch size must be positive")] InvalidSettlementBatchSize,
+    #[error("ambiguity flags must need some reputation to pause a market")] InvalidAmbiguityReview,
+    #[error("invalid curve parameters")] InvalidCurve,
+    #[error("invalid fee schedule")] InvalidFeeSchedule,
+    #[error("invalid mentorship settings")] InvalidMentorship,
+    #[error("featured market XP multiplier is out of bounds")] InvalidFeaturedMultiplier,
+    #[error("repeat trades must earn no more XP than entering a market, within the daily cap")] InvalidTradeXp,
+    #[error("creator market cap quality threshold is above 100%")] InvalidCreatorMarketCap,
+    #[error("config change notice must be at most MAX_CONFIG_NOTICE_SECONDS")] InvalidConfigNotice,
+    #[error("challenge report window must be positive")] InvalidChallengeReportWindow,
+    #[error("parlay house edge must be below 100%")] InvalidParlayHouseEdge,
+}
+
+/// Trading fee rates: every trade pays `base_bps`, plus `impact_percent` of the price impact
+/// it causes, plus a surcharge rising linearly to `late_bps` over the last `late_window_seconds`
+/// before close, never more than `max_bps` in total. The default is a flat 0.5%
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "FeeScheduleInput")]
+pub struct FeeSchedule {
+    pub base_bps: u32,
+    pub impact_percent: u8,
+    pub late_bps: u32,
+    pub late_window_seconds: u64,
+    pub max_bps: u32,
+}
+
+impl Default for FeeSchedule {
+    fn default() -> Self {
+        Self {
+            base_bps: math::TRADING_FEE_BASIS_POINTS,
+            impact_percent: 0,
+            late_bps: 0,
+            late_window_seconds: 0,
+            max_bps: 500,
+        }
+    }
+}
+
+impl FeeSchedule {
+    /// A usable schedule starts no higher than its cap, which is at most `MAX_TRADING_FEE_BPS`
+    pub fn is_valid(&self) -> bool {
+        self.base_bps <= self.max_bps && self.max_bps <= MAX_TRADING_FEE_BPS && self.impact_percent <= 100
+    }
+
+    /// Fee rate for a trade that moved a price by `impact_bps`, `seconds_left` before close
+    pub fn fee_bps(&self, impact_bps: u128, seconds_left: u64) -> u32 {
+        math::dynamic_fee_bps(
+            self.base_bps,
+            impact_bps,
+            self.impact_percent,
+            seconds_left,
+            self.late_window_seconds,
+            self.late_bps,
+            self.max_bps,
+        )
+    }
+}
+
+/// Shape of a market's bonding curve: prices rise linearly from the base price to
+/// `price_cap_multiplier` times the base price once `depth` shares of an outcome are outstanding.
+/// A player's buys in one price impact window may move the market's implied probabilities by at
+/// most `max_price_impact_bps` in total, or by any amount when it is 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "CurveParamsInput")]
+pub struct CurveParams {
+    pub price_cap_multiplier: u32,
+    pub depth: Amount,
+    pub max_price_impact_bps: u32,
+}
+
+impl Default for CurveParams {
+    fn default() -> Self {
+        Self {
+            price_cap_multiplier: math::DEFAULT_PRICE_CAP_MULTIPLIER,
+            depth: Amount::from_attos(math::DEFAULT_CURVE_DEPTH),
+            max_price_impact_bps: 0,
+        }
+    }
+}
+
+impl CurveParams {
+    /// A usable curve caps prices at one to `MAX_PRICE_CAP_MULTIPLIER` times the base price and
+    /// is at least one share deep
+    pub fn is_valid(&self) -> bool {
+        (1..=MAX_PRICE_CAP_MULTIPLIER).contains(&self.price_cap_multiplier)
+            && self.depth >= Amount::ONE
+            && self.max_price_impact_bps <= math::BASIS_POINTS
+    }
+}
+
+/// Players of `mentor_level` or above may each take up to `max_apprentices` apprentices below
+/// that level. Trade XP an apprentice earns is raised by `apprentice_bonus_percent`, and their
+/// mentor earns `mentor_bonus_percent` of it too. Apprentices graduate on reaching `mentor_level`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "MentorshipConfigInput")]
+pub struct MentorshipConfig {
+    pub mentor_level: u32,
+    /// Zero turns mentorship off
+    pub max_apprentices: u32,
+    pub apprentice_bonus_percent: u8,
+    pub mentor_bonus_percent: u8,
+    /// Tokens paid to both players as the apprentice reaches each level, in order of level
+    pub milestones: Vec<MentorshipMilestone>,
+}
+
+/// XP for buying into a market: `first_trade` on a player's first entry, then `repeat_trade`
+/// for each further trade until they've earned `repeat_daily_cap` from repeats in that market
+/// that day. Trades staking less than `full_xp_stake_bps` of the trader's balance earn less,
+/// and no trader earns more than `daily_cap` from trades a day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "TradeXpInput")]
+pub struct TradeXp {
+    pub first_trade: u64,
+    pub repeat_trade: u64,
+    pub repeat_daily_cap: u64,
+    /// Share of the balance a trade must stake for full XP; zero gives every trade full XP
+    pub full_xp_stake_bps: u32,
+    pub daily_cap: u64,
+}
+
+impl Default for TradeXp {
+    fn default() -> Self {
+        Self {
+            first_trade: 10,
+            repeat_trade: 2,
+            repeat_daily_cap: 10,
+            full_xp_stake_bps: 50,
+            daily_cap: 200,
+        }
+    }
+}
+
+impl TradeXp {
+    pub fn is_valid(&self) -> bool {
+        self.repeat_trade <= self.first_trade
+            && self.repeat_trade <= self.repeat_daily_cap
+            && self.full_xp_stake_bps <= math::BASIS_POINTS
+    }
+}
+
+/// A pair of resolvers is flagged once they've voted on at least `min_shared_votes` contested
+/// oracle markets together and picked the same outcome in `agreement_percent` of them. A vote
+/// cast alongside a flagged partner's vote for the same outcome counts `weight_percent` of its
+/// usual weight
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "CollusionConfigInput")]
+pub struct CollusionConfig {
+    /// Zero turns detection off
+    pub min_shared_votes: u32,
+    pub agreement_percent: u8,
+    pub weight_percent: u8,
+}
+
+impl Default for CollusionConfig {
+    fn default() -> Self {
+        Self {
+            min_shared_votes: 5,
+            agreement_percent: 90,
+            weight_percent: 50,
+        }
+    }
+}
+
+/// Maximum number of ballots of one oracle vote counted towards voting agreement
+pub const MAX_AGREEMENT_VOTERS: usize = 64;
+
+/// How often a resolver's votes matched another's on contested oracle markets, i.e. markets
+/// whose votes split between outcomes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VoteAgreement {
+    pub shared_votes: u32,
+    pub agreed_votes: u32,
+}
+
+impl VoteAgreement {
+    pub fn is_flagged(&self, config: &CollusionConfig) -> bool {
+        config.min_shared_votes > 0
+            && self.shared_votes >= config.min_shared_votes
+            && u64::from(self.agreed_votes) * 100 >= u64::from(self.shared_votes) * u64::from(config.agreement_percent)
+    }
+}
+
+/// Two resolvers flagged for voting together, for moderators to review
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct CollusionFlag {
+    pub first: PlayerId,
+    pub second: PlayerId,
+    /// Contested markets both voted on
+    pub shared_votes: u32,
+    /// Of those, markets where they picked the same outcome
+    pub agreed_votes: u32,
+}
+
+/// Group flagged pairs into clusters of players linked through them, each cluster sorted and
+/// the clusters in order of their first member
+pub fn collusion_clusters<T: Ord + Clone>(pairs: &[(T, T)]) -> Vec<Vec<T>> {
+    let mut clusters: Vec<BTreeSet<T>> = Vec::new();
+    for (first, second) in pairs {
+        let (linked, mut rest): (Vec<_>, Vec<_>) = clusters
+            .into_iter()
+            .partition(|cluster| cluster.contains(first) || cluster.contains(second));
+        let mut merged: BTreeSet<T> = linked.into_iter().flatten().collect();
+        merged.insert(first.clone());
+        merged.insert(second.clone());
+        rest.push(merged);
+        clusters = rest;
+    }
+    let mut clusters: Vec<Vec<T>> = clusters.into_iter().map(|cluster| cluster.into_iter().collect()).collect();
+    clusters.sort();
+    clusters
+}
+
+/// How many markets a creator may have active at once: `base`, one more for every
+/// `levels_per_extra` levels past the first, and `proven_bonus` more once they've resolved
+/// `proven_min_resolved` markets with a quality score of at least `proven_quality_bps`.
+/// A zero base lifts the cap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "CreatorMarketCapInput")]
+pub struct CreatorMarketCap {
+    pub base: u32,
+    /// Zero grants nothing for levels
+    pub levels_per_extra: u32,
+    pub proven_min_resolved: u64,
+    pub proven_quality_bps: u32,
+    pub proven_bonus: u32,
+}
+
+impl Default for CreatorMarketCap {
+    fn default() -> Self {
+        Self {
+            base: 10,
+            levels_per_extra: 5,
+            proven_min_resolved: 5,
+            proven_quality_bps: 8_000,
+            proven_bonus: 10,
+        }
+    }
+}
+
+impl CreatorMarketCap {
+    pub fn is_valid(&self) -> bool {
+        self.proven_quality_bps <= math::BASIS_POINTS
+    }
+
+    /// Markets a creator at `level` with these stats may have active; `None` if uncapped
+    pub fn limit(&self, level: u32, stats: &CreatorStats) -> Option<u64> {
+        if self.base == 0 {
+            return None;
+        }
+        let from_levels = match self.levels_per_extra {
+            0 => 0,
+            every => level.saturating_sub(1) / every,
+        };
+        let proven = stats.markets_resolved >= self.proven_min_resolved
+            && stats.quality_score() >= self.proven_quality_bps;
+        let bonus = if proven { self.proven_bonus } else { 0 };
+        Some(u64::from(self.base) + u64::from(from_levels) + u64::from(bonus))
+    }
+}
+
+/// Trade XP a player has earned on `day`, from repeats in one market or from all their trades
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DailyTradeXp {
+    pub day: u64,
+    pub xp: u64,
+}
+
+/// Price impact a player's buys in one market have used up since `started_at`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceImpactWindow {
+    pub started_at: Timestamp,
+    pub used_bps: u32,
+}
+
+/// Rewards paid once an apprentice reaches `level`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "MentorshipMilestoneInput")]
+pub struct MentorshipMilestone {
+    pub level: u32,
+    pub apprentice_reward: Amount,
+    pub mentor_reward: Amount,
+}
+
+impl Default for MentorshipConfig {
+    fn default() -> Self {
+        Self {
+            mentor_level: 10,
+            max_apprentices: 3,
+            apprentice_bonus_percent: 20,
+            mentor_bonus_percent: 10,
+            milestones: vec![
+                MentorshipMilestone {
+                    level: 3,
+                    apprentice_reward: Amount::from_tokens(25),
+                    mentor_reward: Amount::from_tokens(25),
+                },
+                MentorshipMilestone {
+                    level: 6,
+                    apprentice_reward: Amount::from_tokens(50),
+                    mentor_reward: Amount::from_tokens(50),
+                },
+            ],
+        }
+    }
+}
+
+impl MentorshipConfig {
+    /// Apprentices start at level 1 and must be able to graduate, and each milestone is
+    /// reached before graduation and after the previous one
+    pub fn is_valid(&self) -> bool {
+        self.mentor_level >= 2
+            && self.apprentice_bonus_percent <= 100
+            && self.mentor_bonus_percent <= 100
+            && self.milestones.len() <= MAX_MENTORSHIP_MILESTONES
+            && self.milestones.iter().all(|milestone| (2..=self.mentor_level).contains(&milestone.level))
+            && self.milestones.windows(2).all(|pair| pair[0].level < pair[1].level)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
 pub struct Market {
     pub id: MarketId,
     pub creator: PlayerId,
     pub title: String,
+    /// URL-friendly name derived from the title at creation; unique, and kept when the title
+    /// is edited so shared links keep working
+    pub slug: String,
     pub description: String,
     pub market_type: MarketType,
     pub outcomes: Vec<Outcome>,
@@ -54,11 +727,1167 @@ pub struct Market {
     pub status: MarketStatus,
     pub total_liquidity: Amount,
     pub positions: BTreeMap<PlayerId, PlayerPosition>,
+    /// Shares bought with guild shared pools
+    pub guild_positions: BTreeMap<GuildId, GuildPosition>,
     pub total_participants: u64,
     pub base_price: Amount,
-    pub smoothing_factor: f64,
+    pub curve: CurveParams,
     pub winning_outcome: Option<OutcomeId>,
     pub resolution_method: ResolutionMethod,
+    /// Payout weight (in basis points) for each winning rank; a single-winner market is `[10_000]`
+    pub payout_weights: Vec<u32>,
+    /// Winning outcomes ordered by rank, set on resolution
+    pub winning_outcomes: Vec<OutcomeId>,
+    pub visibility: MarketVisibility,
+    /// Guild whose members may participate in a GuildOnly market
+    pub allowed_guild: Option<GuildId>,
+    /// Players allowed to participate in a Whitelist market
+    pub allowlist: Vec<PlayerId>,
+    pub metadata: MarketMetadata,
+    /// Audit log of creator edits, oldest first
+    pub edit_history: Vec<MarketEdit>,
+    /// Whether the creator may close the market before its end time
+    pub allow_early_close: bool,
+    pub closed_early_at: Option<Timestamp>,
+    /// Players currently holding shares of any outcome
+    pub current_holders: u64,
+    /// Tokens traded in buys and sells
+    pub volume: Amount,
+    /// Trading fees charged on this market, including the burned part
+    pub fees_collected: Amount,
+    /// Part of the trading fees paid to the creator
+    pub creator_fees_earned: Amount,
+    /// Post-resolution ratings from participants
+    pub thumbs_up: u64,
+    pub thumbs_down: u64,
+    /// End of the bootstrapping phase, for markets that had one
+    pub bootstrap_end: Option<Timestamp>,
+    /// Tokens committed during the bootstrapping phase, converted to shares when it ends
+    pub bootstrap_commitments: Vec<BootstrapCommitment>,
+    /// Length of the blind finish before `end_time`; zero when the market has none
+    pub blind_finish_seconds: u64,
+    /// Sealed trades placed during the blind finish, executed as they are revealed
+    pub blind_commitments: Vec<BlindCommitment>,
+    /// Winnings still unclaimed at this time can be swept to the treasury
+    pub claims_expire_at: Option<Timestamp>,
+    /// Unclaimed winners were sent a `ClaimExpiring` notification
+    pub expiry_warning_sent: bool,
+    /// Evidence attached by the resolver and oracle voters, in the order it was submitted
+    pub resolution_evidence: Vec<ResolutionEvidence>,
+    /// Reading a designated oracle resolved this scalar market with
+    pub oracle_value: Option<OracleValue>,
+    /// Descending-price sales of held shares, indexed by auction ID
+    pub auctions: Vec<PositionAuction>,
+    /// Fixed-price sales of held shares, indexed by offer ID
+    pub share_offers: Vec<ShareOffer>,
+    /// Two-sided quotes posted by market-maker bots, indexed by quote ID
+    pub bot_quotes: Vec<BotQuote>,
+    /// Group this market settles with, if any
+    pub group_id: Option<GroupId>,
+    /// Outcome rename proposed by the creator and waiting for holders to approve
+    pub pending_correction: Option<OutcomeCorrection>,
+    /// When resolvers could start voting, for OracleVoting markets that have closed
+    pub voting_start: Option<Timestamp>,
+    /// When the voting period ends and the council can rule on a vote that didn't settle
+    pub voting_end: Option<Timestamp>,
+    /// Volume the creator traded in their own market
+    pub creator_volume: Amount,
+    /// Tokens the creator posted as a bond when creating the market
+    pub creator_bond: Amount,
+    pub bond_status: BondStatus,
+    /// End of the review window before trading opens, in which players may flag the market
+    pub review_end: Option<Timestamp>,
+    pub ambiguity_flags: Vec<AmbiguityFlag>,
+    /// When the flags paused the market, until the creator clarifies it or the admin resumes it
+    pub paused_at: Option<Timestamp>,
+}
+
+/// A player's flag that a market's question is ambiguous
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct AmbiguityFlag {
+    pub player: PlayerId,
+    /// The player's reputation when they flagged
+    pub weight: u64,
+    pub reason: String,
+    pub flagged_at: Timestamp,
+}
+
+/// What became of a market creator's bond
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum BondStatus {
+    /// Held until the market resolves
+    Held,
+    /// Returned to the creator after a clean resolution
+    Refunded,
+    /// Paid to the treasury because the arbitration council had to settle the market
+    Forfeited,
+}
+
+impl Market {
+    /// Move the market from `from` to `to`, if it is in `from` and the lifecycle allows the step
+    pub fn transition_status(&mut self, from: MarketStatus, to: MarketStatus) -> Result<(), InvalidTransition> {
+        if self.status != from || !from.can_transition_to(to) {
+            return Err(InvalidTransition { from: self.status, to });
+        }
+        self.status = to;
+        Ok(())
+    }
+
+    /// Stop trading on an active market. OracleVoting markets move to `Voting` with a voting
+    /// period of `voting_seconds` from `time`; other markets wait `Closed` for their result
+    pub fn close(&mut self, time: Timestamp, voting_seconds: u64) -> Result<(), InvalidTransition> {
+        if self.resolution_method != ResolutionMethod::OracleVoting {
+            return self.transition_status(MarketStatus::Active, MarketStatus::Closed);
+        }
+        self.transition_status(MarketStatus::Active, MarketStatus::Voting)?;
+        self.voting_start = Some(time);
+        self.voting_end = Some(Timestamp::from(time.micros().saturating_add(voting_seconds.saturating_mul(1_000_000))));
+        Ok(())
+    }
+
+    /// Whether the creator traded more than `percent` of the market's volume themselves
+    pub fn is_wash_traded(&self, percent: u8) -> bool {
+        let volume = u128::from(self.volume);
+        volume > 0 && u128::from(self.creator_volume) > math::apply_percent(volume, percent)
+    }
+
+    /// Whether `time` falls in the review window, before trading opens
+    pub fn in_review(&self, time: Timestamp) -> bool {
+        self.review_end.is_some_and(|end| time < end)
+    }
+
+    /// Reputation behind the flags on the market
+    pub fn ambiguity_weight(&self) -> u64 {
+        self.ambiguity_flags.iter().map(|flag| flag.weight).sum()
+    }
+
+    /// Whether `time` falls in the blind finish, where trades must be committed sealed
+    pub fn in_blind_finish(&self, time: Timestamp) -> bool {
+        self.blind_finish_seconds > 0
+            && time < self.end_time
+            && time.micros() >= self.end_time.micros().saturating_sub(self.blind_finish_seconds * 1_000_000)
+    }
+
+    /// End of the window after `end_time` in which blind trades can be revealed
+    pub fn reveal_deadline(&self) -> Timestamp {
+        Timestamp::from(self.end_time.micros().saturating_add(self.blind_finish_seconds * 1_000_000))
+    }
+
+    /// Whether every winning position, including guild positions, has been paid out
+    pub fn is_fully_settled(&self) -> bool {
+        self.positions
+            .values()
+            .all(|position| position.settled || self.payout_for(position) == Amount::ZERO)
+            && self
+                .guild_positions
+                .values()
+                .all(|position| position.claimed || self.guild_payout_for(position) == Amount::ZERO)
+    }
+
+    /// The market with its title and description in `locale`, where translated
+    pub fn localized(mut self, locale: &str) -> Self {
+        if let Some(title) = localized(&self.metadata.localized_titles, locale) {
+            self.title = title.clone();
+        }
+        if let Some(description) = localized(&self.metadata.localized_descriptions, locale) {
+            self.description = description.clone();
+        }
+        self
+    }
+
+    /// The compact record kept once the market is archived
+    pub fn summary(&self) -> MarketSummary {
+        MarketSummary {
+            id: self.id,
+            creator: self.creator,
+            title: self.title.clone(),
+            slug: self.slug.clone(),
+            market_type: self.market_type.clone(),
+            outcome_names: self.outcomes.iter().map(|outcome| outcome.name.clone()).collect(),
+            creation_time: self.creation_time,
+            end_time: self.end_time,
+            resolution_time: self.resolution_time,
+            winning_outcomes: self.winning_outcomes.clone(),
+            total_participants: self.total_participants,
+            volume: self.volume,
+            total_liquidity: self.total_liquidity,
+            fees_collected: self.fees_collected,
+            thumbs_up: self.thumbs_up,
+            thumbs_down: self.thumbs_down,
+            resolution_evidence: self.resolution_evidence.clone(),
+        }
+    }
+
+    /// Pricing curve shared by all outcomes of this market
+    pub fn bonding_curve(&self) -> BondingCurve {
+        BondingCurve::with_multiplier(
+            u128::from(self.base_price),
+            self.curve.price_cap_multiplier,
+            u128::from(self.curve.depth),
+        )
+    }
+
+    /// Implied probability of an outcome in basis points: its price as a share of the sum of
+    /// every outcome's price
+    pub fn implied_probability_bps(&self, outcome_id: OutcomeId) -> u32 {
+        let curve = self.bonding_curve();
+        let prices: Vec<u128> = self
+            .outcomes
+            .iter()
+            .map(|outcome| curve.price_at(u128::from(outcome.total_shares)))
+            .collect();
+        let total_price: u128 = prices.iter().sum();
+        match prices.get(outcome_id as usize) {
+            Some(price) if total_price > 0 => math::mul_div(*price, BASIS_POINTS as u128, total_price) as u32,
+            _ => 0,
+        }
+    }
+
+    /// Part of `amount` a buy of an outcome may spend under the curve's price impact limit, once
+    /// `used_bps` of it has gone to the buyer's earlier trades in the window
+    pub fn buy_within_impact_limit(&self, outcome_id: OutcomeId, amount: Amount, used_bps: u32) -> Amount {
+        let Some(outcome) = self.outcomes.get(outcome_id as usize) else {
+            return amount;
+        };
+        if self.curve.max_price_impact_bps == 0 {
+            return amount;
+        }
+        let curve = self.bonding_curve();
+        let total_price = self
+            .outcomes
+            .iter()
+            .map(|outcome| curve.price_at(u128::from(outcome.total_shares)))
+            .sum();
+        Amount::from_attos(math::cap_buy_for_impact(
+            &curve,
+            u128::from(outcome.total_shares),
+            total_price,
+            u128::from(amount),
+            self.curve.max_price_impact_bps.saturating_sub(used_bps),
+        ))
+    }
+
+    /// Winnings owed to a position once the market is resolved: each winning rank pays its
+    /// weight per share held on that outcome
+    pub fn payout_for(&self, position: &PlayerPosition) -> Amount {
+        self.payout_for_shares(&position.shares_by_outcome)
+    }
+
+    /// Winnings owed to a guild position once the market is resolved
+    pub fn guild_payout_for(&self, position: &GuildPosition) -> Amount {
+        self.payout_for_shares(&position.shares_by_outcome)
+    }
+
+    /// Remove a guild's position and take its shares out of the outcome totals
+    pub fn remove_guild_position(&mut self, guild_id: GuildId) -> Option<GuildPosition> {
+        let position = self.guild_positions.remove(&guild_id)?;
+        for (outcome_id, shares) in &position.shares_by_outcome {
+            if let Some(outcome) = self.outcomes.get_mut(*outcome_id as usize) {
+                outcome.total_shares = outcome.total_shares.saturating_sub(*shares);
+            }
+            self.refresh_open_interest(*outcome_id);
+        }
+        Some(position)
+    }
+
+    fn payout_for_shares(&self, shares_by_outcome: &BTreeMap<OutcomeId, Amount>) -> Amount {
+        let shares_by_rank: Vec<u128> = self
+            .winning_outcomes
+            .iter()
+            .map(|outcome_id| {
+                shares_by_outcome
+                    .get(outcome_id)
+                    .copied()
+                    .map_or(0, u128::from)
+            })
+            .collect();
+        Amount::from_attos(math::ranked_payout(&shares_by_rank, &self.payout_weights))
+    }
+
+    /// Credit `shares` of an outcome bought for `cost` to a guild's position
+    /// Guilds are not counted as holders, which track individual players
+    pub fn add_guild_shares(
+        &mut self,
+        guild_id: GuildId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+        cost: Amount,
+        current_time: Timestamp,
+    ) {
+        let position = self.guild_positions.entry(guild_id).or_insert(GuildPosition {
+            shares_by_outcome: BTreeMap::new(),
+            total_invested: Amount::ZERO,
+            entry_time: current_time,
+            claimed: false,
+        });
+        let held = position.shares_by_outcome.entry(outcome_id).or_insert(Amount::ZERO);
+        *held = held.saturating_add(shares);
+        position.total_invested = position.total_invested.saturating_add(cost);
+
+        let outcome = &mut self.outcomes[outcome_id as usize];
+        outcome.total_shares = outcome.total_shares.saturating_add(shares);
+        self.refresh_open_interest(outcome_id);
+    }
+
+    /// Credit `shares` of an outcome bought for `cost` to a player's position, keeping the
+    /// outcome totals, holder counts and open interest in step
+    pub fn add_shares(
+        &mut self,
+        player_id: PlayerId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+        cost: Amount,
+        current_time: Timestamp,
+    ) {
+        self.deposit_shares(player_id, outcome_id, shares, cost, current_time);
+        let outcome = &mut self.outcomes[outcome_id as usize];
+        outcome.total_shares = outcome.total_shares.saturating_add(shares);
+        self.refresh_open_interest(outcome_id);
+    }
+
+    /// Credit existing shares of an outcome to a player's position at a cost basis of `cost`,
+    /// such as shares bought from another player; the outcome's supply doesn't change
+    /// The shares keep `acquired_at`, when they were first bought, so passing them to another
+    /// player doesn't make them a late entry
+    pub fn deposit_shares(
+        &mut self,
+        player_id: PlayerId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+        cost: Amount,
+        acquired_at: Timestamp,
+    ) {
+        let entry_probability = self.implied_probability_bps(outcome_id);
+        let position = self.positions.entry(player_id).or_insert(PlayerPosition {
+            shares_by_outcome: BTreeMap::new(),
+            total_invested: Amount::ZERO,
+            entry_time: acquired_at,
+            settled: false,
+            entry_probabilities: BTreeMap::new(),
+            entry_times: BTreeMap::new(),
+        });
+        position.entry_time = position.entry_time.min(acquired_at);
+        position.entry_probabilities.entry(outcome_id).or_insert(entry_probability);
+        let was_holder = !position.shares_by_outcome.is_empty();
+        let current_shares = position
+            .shares_by_outcome
+            .get(&outcome_id)
+            .copied()
+            .unwrap_or(Amount::ZERO);
+        position.add_entry_time(outcome_id, current_shares, shares, acquired_at);
+        position
+            .shares_by_outcome
+            .insert(outcome_id, current_shares.saturating_add(shares));
+        position.total_invested = position.total_invested.saturating_add(cost);
+
+        let outcome = &mut self.outcomes[outcome_id as usize];
+        if current_shares == Amount::ZERO && shares > Amount::ZERO {
+            outcome.holders += 1;
+        }
+        if !was_holder {
+            self.current_holders += 1;
+        }
+    }
+
+    /// Take shares out of a player's position without changing the outcome's supply
+    /// Returns the part of the position's cost basis that goes with them and when they were
+    /// bought, or `None` when the player holds fewer shares of the outcome
+    pub fn withdraw_shares(
+        &mut self,
+        player_id: &PlayerId,
+        outcome_id: OutcomeId,
+        shares: Amount,
+    ) -> Option<(Amount, Timestamp)> {
+        let position = self.positions.get_mut(player_id)?;
+        let held = position.shares_by_outcome.get(&outcome_id).copied().unwrap_or(Amount::ZERO);
+        if shares == Amount::ZERO || held < shares {
+            return None;
+        }
+        let acquired_at = position.entry_time_for(&[outcome_id]);
+        let total_held: u128 = position.shares_by_outcome.values().map(|shares| u128::from(*shares)).sum();
+        let cost_basis = Amount::from_attos(math::mul_div(
+            u128::from(position.total_invested),
+            u128::from(shares),
+            total_held,
+        ));
+        position.total_invested = position.total_invested.saturating_sub(cost_basis);
+        if held == shares {
+            position.shares_by_outcome.remove(&outcome_id);
+            position.entry_times.remove(&outcome_id);
+            let outcome = &mut self.outcomes[outcome_id as usize];
+            outcome.holders = outcome.holders.saturating_sub(1);
+            if position.shares_by_outcome.is_empty() {
+                self.current_holders = self.current_holders.saturating_sub(1);
+            }
+        } else {
+            position.shares_by_outcome.insert(outcome_id, held.saturating_sub(shares));
+        }
+        Some((cost_basis, acquired_at))
+    }
+
+    /// Close every open auction and share offer, or only `seller`'s, returning the shares they
+    /// hold to the sellers
+    pub fn return_escrowed_shares(&mut self, seller: Option<PlayerId>) {
+        for index in 0..self.auctions.len() {
+            let auction = &mut self.auctions[index];
+            if !auction.open || seller.is_some_and(|seller| seller != auction.seller) {
+                continue;
+            }
+            auction.open = false;
+            let (seller, outcome_id, shares, cost_basis, acquired_at) =
+                (auction.seller, auction.outcome_id, auction.shares, auction.cost_basis, auction.acquired_at);
+            auction.shares = Amount::ZERO;
+            auction.cost_basis = Amount::ZERO;
+            if shares > Amount::ZERO {
+                self.deposit_shares(seller, outcome_id, shares, cost_basis, acquired_at);
+            }
+        }
+        for index in 0..self.share_offers.len() {
+            let offer = &mut self.share_offers[index];
+            if !offer.open || seller.is_some_and(|seller| seller != offer.seller) {
+                continue;
+            }
+            offer.open = false;
+            let (seller, outcome_id, shares, cost_basis, acquired_at) =
+                (offer.seller, offer.outcome_id, offer.shares, offer.cost_basis, offer.acquired_at);
+            self.deposit_shares(seller, outcome_id, shares, cost_basis, acquired_at);
+        }
+    }
+
+    /// Close the open bot quotes matching `closes`, returning the shares their asks hold to
+    /// the makers
+    /// Returns each closed quote's maker with the tokens its bid still held, to refund them
+    pub fn close_bot_quotes(&mut self, closes: impl Fn(&BotQuote) -> bool) -> Vec<(PlayerId, Amount)> {
+        let mut closed = Vec::new();
+        for index in 0..self.bot_quotes.len() {
+            if !self.bot_quotes[index].open || !closes(&self.bot_quotes[index]) {
+                continue;
+            }
+            let quote = &mut self.bot_quotes[index];
+            quote.open = false;
+            let (maker, outcome_id, shares, cost_basis, acquired_at) =
+                (quote.maker, quote.outcome_id, quote.ask_shares, quote.ask_cost_basis, quote.ask_acquired_at);
+            closed.push((maker, quote.bid_escrow));
+            quote.bid_shares = Amount::ZERO;
+            quote.bid_escrow = Amount::ZERO;
+            quote.ask_shares = Amount::ZERO;
+            quote.ask_cost_basis = Amount::ZERO;
+            if shares > Amount::ZERO {
+                self.deposit_shares(maker, outcome_id, shares, cost_basis, acquired_at);
+            }
+        }
+        closed
+    }
+
+    /// Move a player's position to another player, merging it into any position they hold
+    ///
+    /// When only one of the two positions has been paid out, the paid one's shares are
+    /// dropped and the merged position keeps the unpaid one's claim, so winnings are
+    /// neither lost nor paid twice. Outcome share totals are left alone, as the paid
+    /// shares already took their part of the pool.
+    pub fn transfer_position(&mut self, from: &PlayerId, to: PlayerId) {
+        let Some(mut position) = self.positions.remove(from) else {
+            return;
+        };
+        let Some(existing) = self.positions.get_mut(&to) else {
+            self.positions.insert(to, position);
+            return;
+        };
+        if existing.settled != position.settled {
+            if existing.settled {
+                std::mem::swap(existing, &mut position);
+            }
+            // `position` is the paid one; its shares have no claim left
+            if !position.shares_by_outcome.is_empty() {
+                self.current_holders = self.current_holders.saturating_sub(1);
+            }
+            for outcome_id in position.shares_by_outcome.keys() {
+                if let Some(outcome) = self.outcomes.get_mut(*outcome_id as usize) {
+                    outcome.holders = outcome.holders.saturating_sub(1);
+                }
+            }
+            existing.total_invested = existing.total_invested.saturating_add(position.total_invested);
+            existing.entry_time = existing.entry_time.min(position.entry_time);
+            return;
+        }
+        // Both players counted as holders; after merging only one remains
+        if !position.shares_by_outcome.is_empty() && !existing.shares_by_outcome.is_empty() {
+            self.current_holders = self.current_holders.saturating_sub(1);
+        }
+        for (outcome_id, shares) in position.shares_by_outcome {
+            let entry_time = position.entry_times.get(&outcome_id).copied().unwrap_or(position.entry_time);
+            let held = existing.shares_by_outcome.get(&outcome_id).copied().unwrap_or(Amount::ZERO);
+            existing.add_entry_time(outcome_id, held, shares, entry_time);
+            match existing.shares_by_outcome.get_mut(&outcome_id) {
+                Some(held) => {
+                    *held = held.saturating_add(shares);
+                    if let Some(outcome) = self.outcomes.get_mut(outcome_id as usize) {
+                        outcome.holders = outcome.holders.saturating_sub(1);
+                    }
+                }
+                None => {
+                    existing.shares_by_outcome.insert(outcome_id, shares);
+                }
+            }
+        }
+        existing.total_invested = existing.total_invested.saturating_add(position.total_invested);
+        existing.entry_time = existing.entry_time.min(position.entry_time);
+        for (outcome_id, probability) in position.entry_probabilities {
+            existing.entry_probabilities.entry(outcome_id).or_insert(probability);
+        }
+    }
+
+    /// Remove a player's position and take its shares out of the outcome totals
+    pub fn remove_position(&mut self, player_id: &PlayerId) -> Option<PlayerPosition> {
+        let position = self.positions.remove(player_id)?;
+        for (outcome_id, shares) in &position.shares_by_outcome {
+            if let Some(outcome) = self.outcomes.get_mut(*outcome_id as usize) {
+                outcome.total_shares = outcome.total_shares.saturating_sub(*shares);
+                outcome.holders = outcome.holders.saturating_sub(1);
+            }
+            self.refresh_open_interest(*outcome_id);
+        }
+        if !position.shares_by_outcome.is_empty() {
+            self.current_holders = self.current_holders.saturating_sub(1);
+        }
+        Some(position)
+    }
+
+    /// Whether `approvals` come from players holding more than half of the shares players hold
+    /// in this market, across all outcomes; shares held by guilds have no say
+    pub fn has_majority(&self, approvals: &[PlayerId]) -> bool {
+        let shares_held = |position: &PlayerPosition| {
+            position.shares_by_outcome.values().map(|shares| u128::from(*shares)).sum::<u128>()
+        };
+        let total: u128 = self.positions.values().map(shares_held).sum();
+        let approved: u128 = approvals
+            .iter()
+            .filter_map(|player_id| self.positions.get(player_id))
+            .map(shares_held)
+            .sum();
+        approved.saturating_mul(2) > total
+    }
+
+    /// Recompute an outcome's open interest from its outstanding shares
+    pub fn refresh_open_interest(&mut self, outcome_id: OutcomeId) {
+        let curve = self.bonding_curve();
+        if let Some(outcome) = self.outcomes.get_mut(outcome_id as usize) {
+            outcome.open_interest = Amount::from_attos(curve.cost(u128::from(outcome.total_shares)));
+        }
+    }
+}
+
+#[async_graphql::ComplexObject]
+impl Market {
+    /// Quality score of the market's creator, in basis points
+    async fn creator_quality_score(&self, ctx: &async_graphql::Context<'_>) -> async_graphql::Result<u32> {
+        let state = ctx.data::<std::sync::Arc<PredictionMarketState>>()?;
+        let stats = state.creator_stats.get(&self.creator).await?.unwrap_or_default();
+        Ok(stats.quality_score())
+    }
+}
+
+/// A holder selling shares at a price per share that falls from `start_price` to
+/// `floor_price` over the auction; the shares are held by the auction until bought, and the
+/// rest go back to the seller when it's cancelled or the market resolves
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct PositionAuction {
+    pub id: AuctionId,
+    pub seller: PlayerId,
+    pub outcome_id: OutcomeId,
+    /// Shares not bought yet
+    pub shares: Amount,
+    pub start_price: Amount,
+    pub floor_price: Amount,
+    pub starts_at: Timestamp,
+    pub ends_at: Timestamp,
+    /// Part of the seller's cost basis that goes with the unsold shares
+    pub cost_basis: Amount,
+    /// When the seller bought the shares, passed on to buyers
+    pub acquired_at: Timestamp,
+    /// Tokens paid to the seller so far
+    pub proceeds: Amount,
+    pub open: bool,
+}
+
+impl PositionAuction {
+    /// Price per share at `time`
+    pub fn price_at(&self, time: Timestamp) -> Amount {
+        Amount::from_attos(math::dutch_auction_price(
+            u128::from(self.start_price),
+            u128::from(self.floor_price),
+            self.starts_at.micros(),
+            self.ends_at.micros(),
+            time.micros(),
+        ))
+    }
+}
+
+/// A holder's offer to sell shares for a fixed total price, to one player or to anyone; the
+/// shares are held by the offer until it's accepted or cancelled
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ShareOffer {
+    pub id: ShareOfferId,
+    pub seller: PlayerId,
+    /// Only this player may accept, when set
+    pub buyer: Option<PlayerId>,
+    pub outcome_id: OutcomeId,
+    pub shares: Amount,
+    pub price: Amount,
+    /// Part of the seller's cost basis that goes with the shares
+    pub cost_basis: Amount,
+    /// When the seller bought the shares, passed on to the buyer
+    pub acquired_at: Timestamp,
+    pub open: bool,
+}
+
+/// Limits the admin sets on a market maker's quoting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "MarketMakerLimitsInput")]
+pub struct MarketMakerLimits {
+    /// Most shares either side of a single quote may cover
+    pub max_quote_shares: Amount,
+    /// Most tokens the maker's open bids may hold across all markets
+    pub max_bid_exposure: Amount,
+    /// Most quotes the maker may have open across all markets
+    pub max_open_quotes: u32,
+}
+
+/// A player the admin approved to quote both sides of markets
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct MarketMaker {
+    pub player_id: PlayerId,
+    /// Whether the maker may post quotes; a revoked maker can still cancel the open ones
+    pub approved: bool,
+    pub approved_at: Timestamp,
+    /// Part of the trading fees the maker pays that is credited back to them, in basis points
+    pub rebate_bps: u32,
+    pub limits: MarketMakerLimits,
+    pub open_quotes: u32,
+    /// Tokens held by the maker's open bids
+    pub bid_exposure: Amount,
+    pub rebates_earned: Amount,
+}
+
+impl MarketMaker {
+    /// Take a closed quote off the maker's open quotes, with the tokens its bid still held
+    pub fn release_quote(&mut self, bid_escrow: Amount) {
+        self.open_quotes = self.open_quotes.saturating_sub(1);
+        self.bid_exposure = self.bid_exposure.saturating_sub(bid_escrow);
+    }
+
+    pub fn within_limits(&self) -> bool {
+        self.open_quotes <= self.limits.max_open_quotes && self.bid_exposure <= self.limits.max_bid_exposure
+    }
+}
+
+/// One outcome's bid and ask posted by a market maker; a side with no shares isn't quoted,
+/// and a quote with neither side cancels the maker's quote on the outcome
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "TwoSidedQuoteInput")]
+pub struct TwoSidedQuote {
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
+    /// Price per share the maker pays for shares sold into the bid
+    pub bid_price: Amount,
+    pub bid_shares: Amount,
+    /// Price per share the maker asks for the shares it sells
+    pub ask_price: Amount,
+    pub ask_shares: Amount,
+}
+
+impl TwoSidedQuote {
+    pub fn is_cancel(&self) -> bool {
+        self.bid_shares == Amount::ZERO && self.ask_shares == Amount::ZERO
+    }
+
+    /// Whether each quoted side has a price and the bid is below the ask
+    pub fn is_valid(&self) -> bool {
+        let bid_quoted = self.bid_shares > Amount::ZERO;
+        let ask_quoted = self.ask_shares > Amount::ZERO;
+        (!bid_quoted || self.bid_price > Amount::ZERO)
+            && (!ask_quoted || self.ask_price > Amount::ZERO)
+            && (!bid_quoted || !ask_quoted || self.bid_price < self.ask_price)
+    }
+}
+
+/// Side of a market maker's quote a trade fills
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum QuoteSide {
+    /// The taker sells shares to the maker at the bid price
+    Bid,
+    /// The taker buys shares from the maker at the ask price
+    Ask,
+}
+
+/// A market maker's standing bid and ask on an outcome; the bid's tokens and the ask's
+/// shares are held by the quote until they're traded or it closes
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct BotQuote {
+    pub id: BotQuoteId,
+    pub maker: PlayerId,
+    pub outcome_id: OutcomeId,
+    pub bid_price: Amount,
+    /// Shares the bid still buys
+    pub bid_shares: Amount,
+    /// Tokens held to pay for them
+    pub bid_escrow: Amount,
+    pub ask_price: Amount,
+    /// Shares the ask still sells
+    pub ask_shares: Amount,
+    /// Part of the maker's cost basis that goes with them
+    pub ask_cost_basis: Amount,
+    /// When the maker bought them, passed on to takers
+    pub ask_acquired_at: Timestamp,
+    pub posted_at: Timestamp,
+    pub open: bool,
+}
+
+/// Tokens a player committed to an outcome while a market was bootstrapping
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct BootstrapCommitment {
+    pub player: PlayerId,
+    pub outcome_id: OutcomeId,
+    pub amount: Amount,
+}
+
+/// Where a day's featured market came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum FeaturedSource {
+    /// Next open market on the admin's list
+    Curated,
+    /// Open market with the most attention the day before: its volume plus its views, each
+    /// worth `trending_view_value`
+    Trending,
+}
+
+/// Views of a market reported by the engagement relayer
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, async_graphql::InputObject)]
+#[graphql(input_name = "MarketViewCountInput")]
+pub struct MarketViewCount {
+    pub market_id: MarketId,
+    pub views: u64,
+}
+
+/// How often a market has been viewed
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct MarketViews {
+    pub total: u64,
+    /// Views on the latest day any market was traded or viewed
+    pub today: u64,
+}
+
+/// Market featured on a day, whose trades earn extra XP
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct FeaturedMarket {
+    /// Days since the Unix epoch
+    pub day: u64,
+    pub market_id: MarketId,
+    pub source: FeaturedSource,
+}
+
+/// Summary stats of an archived market; positions and trading state are dropped
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct MarketSummary {
+    pub id: MarketId,
+    pub creator: PlayerId,
+    pub title: String,
+    pub slug: String,
+    pub market_type: MarketType,
+    pub outcome_names: Vec<String>,
+    pub creation_time: Timestamp,
+    pub end_time: Timestamp,
+    pub resolution_time: Option<Timestamp>,
+    pub winning_outcomes: Vec<OutcomeId>,
+    pub total_participants: u64,
+    pub volume: Amount,
+    pub total_liquidity: Amount,
+    pub fees_collected: Amount,
+    pub thumbs_up: u64,
+    pub thumbs_down: u64,
+    pub resolution_evidence: Vec<ResolutionEvidence>,
+}
+
+/// A sealed trade committed during a market's blind finish
+/// The tokens are escrowed at commit time; the outcome stays hidden until the reveal
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct BlindCommitment {
+    pub player: PlayerId,
+    /// Hash of the matching `BlindTrade`
+    pub commitment: CryptoHash,
+    pub amount: Amount,
+    pub committed_at: Timestamp,
+    pub revealed: bool,
+}
+
+/// The hidden contents of a blind trade, hashed into its commitment
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlindTrade {
+    pub market_id: MarketId,
+    pub player: PlayerId,
+    pub outcome_id: OutcomeId,
+    /// Secret chosen by the player so the outcome can't be guessed from the hash
+    pub salt: String,
+}
+
+impl BcsHashable<'_> for BlindTrade {}
+
+impl BlindTrade {
+    /// The commitment a player submits for this trade
+    pub fn commitment(&self) -> CryptoHash {
+        CryptoHash::new(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum RaffleStatus {
+    Open,
+    Drawn,
+}
+
+/// A raffle players buy tickets for; once it ends, the ticket sales less a burned share, plus
+/// a top-up from the treasury, are split between the drawn winners
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Raffle {
+    pub id: RaffleId,
+    pub ticket_price: Amount,
+    pub opened_at: Timestamp,
+    pub ends_at: Timestamp,
+    /// Number of tickets drawn; a player holding several winning tickets wins several shares
+    pub winner_count: u32,
+    /// Most the treasury adds to the prize, limited by what the treasury holds at the draw
+    pub treasury_top_up: Amount,
+    /// Open the next raffle on the same terms when this one is drawn
+    pub recurring: bool,
+    /// Tickets held by each player, in order of their first purchase
+    pub entries: Vec<RaffleEntry>,
+    pub total_tickets: u64,
+    /// Tokens paid for tickets
+    pub pot: Amount,
+    pub status: RaffleStatus,
+    /// Holder of each drawn ticket, in draw order
+    pub winners: Vec<PlayerId>,
+    pub prize_per_winner: Amount,
+    /// Hash chained through every ticket sale, fixed once sales close; the winning tickets are
+    /// derived from it, so the draw can't be steered by picking when to make it
+    pub seed: Option<CryptoHash>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct RaffleEntry {
+    pub player: PlayerId,
+    pub tickets: u64,
+}
+
+impl Raffle {
+    /// Player holding ticket number `ticket`, counting through the entries in order
+    pub fn ticket_holder(&self, ticket: u64) -> Option<PlayerId> {
+        let mut first = 0;
+        for entry in &self.entries {
+            if ticket < first + entry.tickets {
+                return Some(entry.player);
+            }
+            first += entry.tickets;
+        }
+        None
+    }
+}
+
+/// On-chain inputs a ticket sale folds into its raffle's seed, on top of the seed so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaffleSeed {
+    pub raffle_id: RaffleId,
+    pub previous: Option<CryptoHash>,
+    pub chain_id: ChainId,
+    pub block_height: u64,
+    pub bought_at: Timestamp,
+    pub player: PlayerId,
+    pub tickets: u64,
+}
+
+impl BcsHashable<'_> for RaffleSeed {}
+
+/// The `index`th ticket drawn from a raffle's seed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RaffleDraw {
+    pub seed: CryptoHash,
+    pub index: u32,
+}
+
+impl BcsHashable<'_> for RaffleDraw {}
+
+impl RaffleDraw {
+    /// The ticket number this draw picks out of `total_tickets`
+    pub fn ticket(&self, total_tickets: u64) -> u64 {
+        <[u64; 4]>::from(CryptoHash::new(self))[0] % total_tickets
+    }
+}
+
+/// A season pass tier, reached once a player has earned `xp_required` XP during the season
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "PassTierInput")]
+pub struct PassTier {
+    pub xp_required: u64,
+    /// Paid to every player reaching the tier
+    pub free_reward: Amount,
+    /// Paid on top of the free reward to holders of the premium pass
+    pub premium_reward: Amount,
+}
+
+/// A season with its pass tiers; seasons don't overlap
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Season {
+    pub id: SeasonId,
+    pub name: String,
+    pub starts_at: Timestamp,
+    pub ends_at: Timestamp,
+    /// Tokens burned to unlock the premium track
+    pub premium_price: Amount,
+    /// Tiers by increasing `xp_required`
+    pub tiers: Vec<PassTier>,
+}
+
+impl Season {
+    pub fn is_active(&self, time: Timestamp) -> bool {
+        self.starts_at <= time && time < self.ends_at
+    }
+
+    /// Number of tiers reached with `xp`
+    pub fn tiers_reached(&self, xp: u64) -> u32 {
+        self.tiers.iter().take_while(|tier| tier.xp_required <= xp).count() as u32
+    }
+
+    /// Whether `tiers` can be used for a season: at least one tier, no more than
+    /// `MAX_PASS_TIERS`, and strictly increasing non-zero XP requirements
+    pub fn tiers_valid(tiers: &[PassTier]) -> bool {
+        !tiers.is_empty()
+            && tiers.len() <= MAX_PASS_TIERS
+            && tiers[0].xp_required > 0
+            && tiers.windows(2).all(|pair| pair[0].xp_required < pair[1].xp_required)
+    }
+}
+
+/// A player's progress through one season's pass
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct SeasonPass {
+    /// XP earned during the season
+    pub xp: u64,
+    /// Number of tiers reached
+    pub tier: u32,
+    pub premium: bool,
+    /// Number of tiers whose free reward has been paid
+    pub free_tiers_claimed: u32,
+    /// Number of tiers whose premium reward has been paid
+    pub premium_tiers_claimed: u32,
+}
+
+/// A creator edit, recording the values that were replaced
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct MarketEdit {
+    pub editor: PlayerId,
+    pub edited_at: Timestamp,
+    pub previous_title: Option<String>,
+    pub previous_description: Option<String>,
+    pub previous_end_time: Option<Timestamp>,
+    /// Outcome renamed by an approved correction, and the name it replaced
+    pub corrected_outcome: Option<OutcomeId>,
+    pub previous_outcome_name: Option<String>,
+}
+
+/// A creator's proposal to rename a mislabeled outcome
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct OutcomeCorrection {
+    pub outcome_id: OutcomeId,
+    pub new_name: String,
+    pub proposed_at: Timestamp,
+    /// Players who approved, starting with the creator
+    pub approvals: Vec<PlayerId>,
+}
+
+/// Display and resolution hints for rendering market cards
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "MarketMetadataInput")]
+pub struct MarketMetadata {
+    pub image_uri: Option<String>,
+    /// Source that resolvers should consult to settle the market
+    pub source_url: Option<String>,
+    /// Locale of the title and description; `DEFAULT_LOCALE` if not given
+    pub locale: Option<String>,
+    /// Title in other locales, keyed by locale tag
+    pub localized_titles: BTreeMap<String, String>,
+    /// Description in other locales, keyed by locale tag
+    pub localized_descriptions: BTreeMap<String, String>,
+    /// How to render each outcome, in outcome order; moved onto the outcomes when the market
+    /// is created
+    #[graphql(skip_output)]
+    #[serde(default)]
+    pub outcome_display: Vec<OutcomeDisplay>,
+}
+
+/// How frontends render an outcome's option chip
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "OutcomeDisplayInput")]
+pub struct OutcomeDisplay {
+    pub emoji: Option<String>,
+    /// Color as `#rrggbb`
+    pub color: Option<String>,
+    pub icon_uri: Option<String>,
+}
+
+impl OutcomeDisplay {
+    /// Whether the emoji is a short symbol without letters or spaces, the color a hex triplet
+    /// and the icon a URI like market metadata's
+    pub fn is_valid(&self) -> bool {
+        let valid_emoji = |emoji: &String| {
+            let count = emoji.chars().count();
+            (1..=MAX_OUTCOME_EMOJI_CHARS).contains(&count)
+                && !emoji.chars().any(|c| c.is_whitespace() || c.is_ascii_alphanumeric())
+        };
+        let valid_color = |color: &String| {
+            color.len() == 7
+                && color.starts_with('#')
+                && color[1..].chars().all(|c| c.is_ascii_hexdigit())
+        };
+        let valid_uri = |uri: &String| {
+            !uri.is_empty() && uri.len() <= MAX_METADATA_URI_LENGTH && !uri.chars().any(char::is_whitespace)
+        };
+        self.emoji.as_ref().is_none_or(valid_emoji)
+            && self.color.as_ref().is_none_or(valid_color)
+            && self.icon_uri.as_ref().is_none_or(valid_uri)
+    }
+}
+
+/// Whether `locale` is a short tag like "en-US"
+pub fn is_valid_locale(locale: &str) -> bool {
+    !locale.is_empty()
+        && locale.len() <= MAX_LOCALE_LENGTH
+        && locale.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Text for `locale` in a map keyed by locale tag, falling back from a regional tag such as
+/// "pt-BR" to its language
+pub fn localized<'a>(texts: &'a BTreeMap<String, String>, locale: &str) -> Option<&'a String> {
+    texts.get(locale).or_else(|| {
+        let (language, _) = locale.split_once(['-', '_'])?;
+        texts.get(language)
+    })
+}
+
+/// A market to create in an `ImportMarkets` batch, with the arguments of `CreateMarket`
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::InputObject)]
+pub struct MarketSpec {
+    pub title: String,
+    pub description: String,
+    pub outcome_names: Vec<String>,
+    pub duration_seconds: u64,
+    pub resolution_method: ResolutionMethod,
+    pub payout_weights: Option<Vec<u32>>,
+    pub visibility: Option<MarketVisibility>,
+    pub allowed_guild: Option<GuildId>,
+    pub allowlist: Option<Vec<PlayerId>>,
+    pub metadata: Option<MarketMetadata>,
+    pub allow_early_close: Option<bool>,
+    pub bootstrap_seconds: Option<u64>,
+    pub blind_finish_seconds: Option<u64>,
+}
+
+/// Source backing a resolution or oracle vote
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "EvidenceInput")]
+pub struct Evidence {
+    pub url: String,
+    /// Hash of the content at `url` when it was consulted
+    pub hash: CryptoHash,
+    pub note: String,
+}
+
+/// Evidence as recorded on a market, with who submitted it and for which outcome
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ResolutionEvidence {
+    pub submitted_by: PlayerId,
+    /// Outcome the submitter voted for, or ranked first when resolving
+    pub outcome_id: OutcomeId,
+    pub evidence: Evidence,
+    pub submitted_at: Timestamp,
+}
+
+/// Entry of the end-time index; keys are stored big-endian so markets iterate in order of end
+/// time, then ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MarketEndKey {
+    pub end_time: Timestamp,
+    pub market_id: MarketId,
+}
+
+impl CustomSerialize for MarketEndKey {
+    fn to_custom_bytes(&self) -> Result<Vec<u8>, ViewError> {
+        let mut bytes = self.end_time.micros().to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.market_id.to_be_bytes());
+        Ok(bytes)
+    }
+
+    fn from_custom_bytes(bytes: &[u8]) -> Result<Self, ViewError> {
+        let bytes: [u8; 16] = bytes
+            .try_into()
+            .map_err(|_| ViewError::NotFound("malformed market end key".to_string()))?;
+        let (end_time, market_id) = bytes.split_at(8);
+        Ok(MarketEndKey {
+            end_time: Timestamp::from(u64::from_be_bytes(end_time.try_into().expect("eight bytes"))),
+            market_id: u64::from_be_bytes(market_id.try_into().expect("eight bytes")),
+        })
+    }
+}
+
+/// Entry of the rank index; keys are stored so players iterate best first: by profit, then
+/// level and experience, with ties in order of player ID
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankKey {
+    pub total_profit: Amount,
+    pub level: u32,
+    pub experience_points: u64,
+    pub player_id: PlayerId,
+}
+
+impl RankKey {
+    /// The player's entry, or `None` if they left the leaderboards
+    pub fn of(player: &Player) -> Option<Self> {
+        (!player.privacy.hide_from_leaderboard).then_some(RankKey {
+            total_profit: player.total_profit,
+            level: player.level,
+            experience_points: player.experience_points,
+            player_id: player.id,
+        })
+    }
+}
+
+impl CustomSerialize for RankKey {
+    fn to_custom_bytes(&self) -> Result<Vec<u8>, ViewError> {
+        // Complements make larger values sort first
+        let mut bytes = (u128::MAX - u128::from(self.total_profit)).to_be_bytes().to_vec();
+        bytes.extend_from_slice(&(u32::MAX - self.level).to_be_bytes());
+        bytes.extend_from_slice(&(u64::MAX - self.experience_points).to_be_bytes());
+        bytes.extend_from_slice(self.player_id.to_string().as_bytes());
+        Ok(bytes)
+    }
+
+    fn from_custom_bytes(bytes: &[u8]) -> Result<Self, ViewError> {
+        let malformed = || ViewError::NotFound("malformed rank key".to_string());
+        if bytes.len() < 28 {
+            return Err(malformed());
+        }
+        let (profit, rest) = bytes.split_at(16);
+        let (level, rest) = rest.split_at(4);
+        let (experience_points, player_id) = rest.split_at(8);
+        Ok(RankKey {
+            total_profit: Amount::from_attos(u128::MAX - u128::from_be_bytes(profit.try_into().expect("sixteen bytes"))),
+            level: u32::MAX - u32::from_be_bytes(level.try_into().expect("four bytes")),
+            experience_points: u64::MAX - u64::from_be_bytes(experience_points.try_into().expect("eight bytes")),
+            player_id: std::str::from_utf8(player_id)
+                .ok()
+                .and_then(|player_id| player_id.parse().ok())
+                .ok_or_else(malformed)?,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,37 +1898,159 @@ pub enum MarketType {
     PvPChallenge { challenger: PlayerId, challenged: PlayerId },
 }
 
+async_graphql::scalar!(MarketType);
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy, async_graphql::Enum)]
 pub enum MarketStatus {
     Active,
     Closed,
     Resolved,
     Cancelled,
+    /// Collecting commitments before trading opens
+    Bootstrapping,
+    /// Trading has stopped and resolvers are voting on the result
+    Voting,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, async_graphql::Enum)]
+impl MarketStatus {
+    /// Whether the market lifecycle allows moving from this status to `to`: bootstrapping
+    /// opens trading, trading closes or moves to a vote, and closed or voted markets resolve.
+    /// Any unresolved market can be cancelled, and resolved or cancelled markets never change
+    /// again
+    pub fn can_transition_to(self, to: MarketStatus) -> bool {
+        use MarketStatus::*;
+        matches!(
+            (self, to),
+            (Bootstrapping, Active)
+                | (Active, Closed | Voting)
+                | (Closed | Voting, Resolved)
+                | (Bootstrapping | Active | Closed | Voting, Cancelled)
+        )
+    }
+
+    /// Whether positions are frozen: once trading stops they can't be bought into, sold,
+    /// transferred, offered or auctioned until the market is resolved or cancelled, when
+    /// claims and refunds unlock them. Only settlement moves shares in between: revealing
+    /// trades sealed before close, cost-basis exits of early-closed markets, emergency
+    /// withdrawals and account recovery
+    pub fn locks_positions(self) -> bool {
+        matches!(self, MarketStatus::Closed | MarketStatus::Voting)
+    }
+}
+
+/// A market status change the lifecycle doesn't allow
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("market status can't change from {from:?} to {to:?}")]
+pub struct InvalidTransition {
+    pub from: MarketStatus,
+    pub to: MarketStatus,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq, Default, async_graphql::Enum)]
+pub enum MarketVisibility {
+    #[default]
+    Public,
+    GuildOnly,
+    Whitelist,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Copy, PartialEq, Eq)]
 pub enum ResolutionMethod {
     OracleVoting,
     Automated,
     CreatorDecides,
+    /// Resolves to the outcomes with the highest closing prices, without an oracle
+    CrowdConsensus,
+    /// Resolved by a single trusted account, such as a data feed bot, through `SubmitOracleResult`
+    /// With a scalar range the account reports a number, and the outcome whose part of the
+    /// range holds it wins
+    DesignatedOracle { oracle_account: AccountOwner, scalar_range: Option<ScalarRange> },
+}
+
+async_graphql::scalar!(ResolutionMethod);
+
+/// Range of a scalar market, split evenly between its outcomes in order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScalarRange {
+    pub min: i64,
+    pub max: i64,
+}
+
+/// A numeric reading reported by a data feed
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "OracleValueInput")]
+pub struct OracleValue {
+    pub value: i64,
+    /// When the feed observed the value
+    pub observed_at: Timestamp,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct Outcome {
     pub id: OutcomeId,
     pub name: String,
     pub total_shares: Amount,
     pub current_price: Amount,
+    /// Players currently holding shares of this outcome
+    pub holders: u64,
+    /// Tokens locked in this outcome's curve by its outstanding shares
+    pub open_interest: Amount,
+    pub display: OutcomeDisplay,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct PlayerPosition {
     pub shares_by_outcome: BTreeMap<OutcomeId, Amount>,
+    /// Tokens paid for shares, less tokens received from selling them
     pub total_invested: Amount,
     pub entry_time: Timestamp,
+    /// Winnings were paid out, by a claim, automatic settlement or a sweep
+    pub settled: bool,
+    /// Implied probability of each outcome when the player first bought it, in basis points
+    pub entry_probabilities: BTreeMap<OutcomeId, u32>,
+    /// When the player's shares of each outcome were acquired, averaged over the shares
+    pub entry_times: BTreeMap<OutcomeId, Timestamp>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl PlayerPosition {
+    /// When the player's shares of `outcome_ids` were acquired, averaged over the shares;
+    /// the position's first buy for shares acquired before entry times were kept per outcome
+    pub fn entry_time_for(&self, outcome_ids: &[OutcomeId]) -> Timestamp {
+        let mut average = None;
+        let mut weight = 0u128;
+        for outcome_id in outcome_ids {
+            let (Some(shares), Some(entry_time)) =
+                (self.shares_by_outcome.get(outcome_id), self.entry_times.get(outcome_id))
+            else {
+                continue;
+            };
+            let micros = match average {
+                Some(average) => math::weighted_average(average, weight, entry_time.micros(), u128::from(*shares)),
+                None => entry_time.micros(),
+            };
+            average = Some(micros);
+            weight = weight.saturating_add(u128::from(*shares));
+        }
+        average.map_or(self.entry_time, Timestamp::from)
+    }
+
+    /// Record `shares` of an outcome acquired at `acquired_at` in its average entry time
+    fn add_entry_time(&mut self, outcome_id: OutcomeId, held: Amount, shares: Amount, acquired_at: Timestamp) {
+        let entry_time = match self.entry_times.get(&outcome_id) {
+            Some(entry_time) if held > Amount::ZERO => math::weighted_average(
+                entry_time.micros(),
+                u128::from(held),
+                acquired_at.micros(),
+                u128::from(shares),
+            ),
+            _ => acquired_at.micros(),
+        };
+        self.entry_times.insert(outcome_id, Timestamp::from(entry_time));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
 pub struct Player {
     pub id: PlayerId,
     pub display_name: Option<String>,
@@ -118,14 +2069,142 @@ pub struct Player {
     pub best_win_streak: u32,
     pub guild_id: Option<GuildId>,
     pub achievements_earned: Vec<AchievementId>,
-    pub active_markets: Vec<MarketId>,
+    /// Owner allowed to move this account to a new key
+    pub recovery_owner: Option<AccountOwner>,
+    pub recovery_linked_at: Option<Timestamp>,
+    /// Limits the player set on their own trading
+    pub risk_controls: RiskControls,
+    /// Cosmetic items the player is wearing
+    pub equipped_items: EquippedItems,
+    /// Outcomes entered in resolved markets, each scored in `brier_score_total`
+    pub forecasts_scored: u64,
+    /// Sum of the Brier scores of the player's entry probabilities, in basis points
+    pub brier_score_total: u64,
+    /// Tokens the player has bought and sold shares for
+    pub trading_volume: Amount,
+    /// What the player keeps out of leaderboards and public queries
+    pub privacy: PrivacySettings,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl Player {
+    /// Display name to show other players, unless the player hides it
+    pub fn public_name(&self) -> Option<String> {
+        if self.privacy.hide_display_name {
+            return None;
+        }
+        self.display_name.clone()
+    }
+
+    /// Forecast accuracy in basis points; `None` until a forecast has been scored
+    pub fn accuracy(&self) -> Option<u32> {
+        math::forecast_accuracy_bps(self.brier_score_total, self.forecasts_scored)
+    }
+}
+
+#[async_graphql::ComplexObject]
+impl Player {
+    /// Forecast accuracy in basis points, from the implied probabilities the player entered at
+    #[graphql(name = "accuracy")]
+    async fn graphql_accuracy(&self) -> Option<u32> {
+        self.accuracy()
+    }
+}
+
+/// Slot a cosmetic item is worn in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum ItemKind {
+    ProfileFrame,
+    MarketCardSkin,
+}
+
+/// A cosmetic item sold in the shop; buying one burns its price
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ShopItem {
+    pub id: ItemId,
+    pub name: String,
+    pub kind: ItemKind,
+    pub price: Amount,
+    /// Whether the item can currently be bought
+    pub available: bool,
+    /// Most copies that will ever be sold, if limited
+    pub max_supply: Option<u64>,
+    pub sold: u64,
+}
+
+/// The item worn in each cosmetic slot
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct EquippedItems {
+    pub profile_frame: Option<ItemId>,
+    pub market_card_skin: Option<ItemId>,
+}
+
+impl EquippedItems {
+    /// The slot items of `kind` are worn in
+    pub fn slot_mut(&mut self, kind: ItemKind) -> &mut Option<ItemId> {
+        match kind {
+            ItemKind::ProfileFrame => &mut self.profile_frame,
+            ItemKind::MarketCardSkin => &mut self.market_card_skin,
+        }
+    }
+}
+
+/// What a player keeps out of leaderboards and public queries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "PrivacySettingsInput")]
+pub struct PrivacySettings {
+    /// Leave the player off every leaderboard and out of the rankings
+    pub hide_from_leaderboard: bool,
+    /// Show the player without a display name on leaderboards and their profile
+    pub hide_display_name: bool,
+    /// Keep the markets the player is in or has finished with out of queries
+    pub hide_portfolio: bool,
+}
+
+/// Self-imposed limits on how much a player can put into markets
+/// Limits can be tightened at any time but only loosened once the period they were set in is over
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct RiskControls {
+    /// Most tokens the player may put into markets per day
+    pub daily_loss_limit: Option<Amount>,
+    /// Day the limit was last set on; it can't be raised or removed before the next day
+    pub limit_set_day: u64,
+    /// No tokens may be put into markets before this time
+    pub excluded_until: Option<Timestamp>,
+    /// Day that `spent_today` counts
+    pub usage_day: u64,
+    /// Tokens put into markets on `usage_day`
+    pub spent_today: Amount,
+}
+
+impl RiskControls {
+    /// Tokens already put into markets on `day`
+    pub fn spent_on(&self, day: u64) -> Amount {
+        if self.usage_day == day {
+            self.spent_today
+        } else {
+            Amount::ZERO
+        }
+    }
+
+    /// Whether `limit` would allow more spending than the current limit
+    pub fn loosens(&self, limit: Option<Amount>) -> bool {
+        match (self.daily_loss_limit, limit) {
+            (None, _) => false,
+            (Some(_), None) => true,
+            (Some(current), Some(new)) => new > current,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct Leaderboard {
     pub top_traders: Vec<LeaderboardEntry>,
     pub top_guilds: Vec<GuildLeaderboardEntry>,
+    /// Players ranked by forecast accuracy rather than profit
+    pub top_forecasters: Vec<AccuracyLeaderboardEntry>,
     pub last_updated: Timestamp,
+    /// Number of players ranked at the last refresh
+    pub total_ranked_players: u32,
 }
 
 impl Default for Leaderboard {
@@ -133,33 +2212,541 @@ impl Default for Leaderboard {
         Self {
             top_traders: Vec::new(),
             top_guilds: Vec::new(),
+            top_forecasters: Vec::new(),
             last_updated: Timestamp::from(0u64),
+            total_ranked_players: 0,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct LeaderboardEntry {
     pub player_id: PlayerId,
     pub display_name: Option<String>,
     pub total_profit: Amount,
-    pub win_rate: f64,
+    /// Share of markets won, in basis points
+    #[serde(with = "win_rate_encoding")]
+    pub win_rate: u32,
     pub level: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Stat a leaderboard board ranks players by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, async_graphql::Enum)]
+pub enum LeaderboardCategory {
+    Profit,
+    Volume,
+    Accuracy,
+    BestStreak,
+    CreatorEarnings,
+}
+
+/// A player's place on one leaderboard board; their rank is their position in it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct BoardEntry {
+    pub player_id: PlayerId,
+    pub display_name: Option<String>,
+    /// Tokens ranked on the profit, volume and creator earnings boards
+    pub amount: Amount,
+    /// Accuracy in basis points on the accuracy board, wins in a row on the best streak board
+    pub score: u64,
+}
+
+/// A player ranked by forecast accuracy, so skill isn't conflated with bankroll size
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct AccuracyLeaderboardEntry {
+    pub player_id: PlayerId,
+    pub display_name: Option<String>,
+    /// Accuracy in basis points
+    pub accuracy: u32,
+    pub forecasts_scored: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct Guild {
     pub id: GuildId,
     pub name: String,
     pub founder: PlayerId,
     pub members: Vec<PlayerId>,
+    /// Members allowed to post announcements; the founder is always one
+    pub officers: Vec<PlayerId>,
     pub creation_time: Timestamp,
     pub total_guild_profit: Amount,
     pub guild_level: u32,
     pub shared_pool: Amount,
+    /// Tokens ever contributed by members, excluding achievement rewards
+    pub total_contributed: Amount,
+    /// Distinct players who joined after the guild was founded
+    pub members_recruited: u32,
+    pub achievements_earned: Vec<AchievementId>,
+    /// Officer approvals a staking proposal needs before the shared pool is invested
+    pub stake_approvals_required: u32,
+    pub league_tier: LeagueTier,
+    /// Fungible token application whose tokens the guild's token pool holds, if any
+    pub token_application: Option<ApplicationId>,
+    /// Tokens of `token_application` this application holds on the guild's behalf
+    pub token_pool: Amount,
+}
+
+/// A check of the tokens this application holds in a fungible token application against the
+/// token pools of the guilds using it
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct TokenReconciliation {
+    pub application_id: ApplicationId,
+    /// Balance of this application's account in the token application
+    pub held: Amount,
+    /// Sum of the token pools of guilds using the token application
+    pub tracked: Amount,
+    pub checked_at: Timestamp,
+}
+
+impl TokenReconciliation {
+    /// Tokens held but not owed to any guild, such as transfers made outside `DepositGuildTokens`
+    pub fn surplus(&self) -> Amount {
+        self.held.saturating_sub(self.tracked)
+    }
+
+    /// Tokens owed to guilds but no longer held
+    pub fn shortfall(&self) -> Amount {
+        self.tracked.saturating_sub(self.held)
+    }
+}
+
+/// Guilds promoted from, and relegated out of, each league when a season is settled
+pub const LEAGUE_MOVEMENT_SLOTS: usize = 2;
+/// Most ranks a league's reward table can pay
+pub const MAX_LEAGUE_REWARD_RANKS: usize = 10;
+
+/// Guild leagues, lowest first; new guilds start in Bronze
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, async_graphql::Enum)]
+pub enum LeagueTier {
+    Bronze,
+    Silver,
+    Gold,
 }
 
+impl LeagueTier {
+    pub const ALL: [LeagueTier; 3] = [LeagueTier::Bronze, LeagueTier::Silver, LeagueTier::Gold];
+
+    pub fn promoted(self) -> Self {
+        match self {
+            LeagueTier::Bronze => LeagueTier::Silver,
+            LeagueTier::Silver | LeagueTier::Gold => LeagueTier::Gold,
+        }
+    }
+
+    pub fn relegated(self) -> Self {
+        match self {
+            LeagueTier::Bronze | LeagueTier::Silver => LeagueTier::Bronze,
+            LeagueTier::Gold => LeagueTier::Silver,
+        }
+    }
+}
+
+/// A guild's place in its league for a season, with what it gets when the season is settled
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct LeagueStanding {
+    pub guild_id: GuildId,
+    pub name: String,
+    pub tier: LeagueTier,
+    /// 1-based rank within the league
+    pub rank: u32,
+    /// Season pass XP earned by the guild's members during the season
+    pub season_xp: u64,
+    /// Paid into the guild's shared pool from the league's reward table
+    pub reward: Amount,
+    /// League the guild plays in next season
+    pub next_tier: LeagueTier,
+}
+
+/// Rank the guilds of one league by season XP, then by ID, with the reward and next tier
+/// each gets: the top `LEAGUE_MOVEMENT_SLOTS` guilds that earned any XP go up and the bottom
+/// ones below them go down
+pub fn rank_league(tier: LeagueTier, guilds: &[(&Guild, u64)], rewards: &[Amount]) -> Vec<LeagueStanding> {
+    let mut guilds: Vec<(&Guild, u64)> =
+        guilds.iter().copied().filter(|(guild, _)| guild.league_tier == tier).collect();
+    guilds.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.id.cmp(&b.0.id)));
+    let relegated_from = guilds.len().saturating_sub(LEAGUE_MOVEMENT_SLOTS).max(LEAGUE_MOVEMENT_SLOTS);
+    guilds
+        .into_iter()
+        .enumerate()
+        .map(|(index, (guild, season_xp))| {
+            let next_tier = if index < LEAGUE_MOVEMENT_SLOTS && season_xp > 0 {
+                tier.promoted()
+            } else if index >= relegated_from {
+                tier.relegated()
+            } else {
+                tier
+            };
+            LeagueStanding {
+                guild_id: guild.id,
+                name: guild.name.clone(),
+                tier,
+                rank: index as u32 + 1,
+                season_xp,
+                reward: rewards.get(index).copied().unwrap_or(Amount::ZERO),
+                next_tier,
+            }
+        })
+        .collect()
+}
+
+/// Something a player achieved that the chain can vouch for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AttestationClaim {
+    /// The player held shares of a winning outcome of a resolved market
+    WonMarket { market_id: MarketId },
+    /// The player reached at least this level
+    ReachedLevel { level: u32 },
+}
+
+async_graphql::scalar!(AttestationClaim);
+
+/// A claim about a player issued by the chain at `chain_id`. `hash` commits to every other
+/// field, and receiving chains only accept an attestation sent from the chain it names
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Attestation {
+    pub id: AttestationId,
+    pub player_id: PlayerId,
+    pub claim: AttestationClaim,
+    pub chain_id: ChainId,
+    pub issued_at: Timestamp,
+    pub hash: CryptoHash,
+}
+
+/// The fields of an attestation its hash is computed over
 #[derive(Debug, Clone, Serialize, Deserialize)]
+struct AttestationContent {
+    id: AttestationId,
+    player_id: PlayerId,
+    claim: AttestationClaim,
+    chain_id: ChainId,
+    issued_at: Timestamp,
+}
+
+impl BcsHashable<'_> for AttestationContent {}
+
+impl Attestation {
+    pub fn new(id: AttestationId, player_id: PlayerId, claim: AttestationClaim, chain_id: ChainId, issued_at: Timestamp) -> Self {
+        let hash = CryptoHash::new(&AttestationContent { id, player_id, claim: claim.clone(), chain_id, issued_at });
+        Self { id, player_id, claim, chain_id, issued_at, hash }
+    }
+
+    /// Whether `hash` matches the attestation's other fields
+    pub fn is_intact(&self) -> bool {
+        Attestation::new(self.id, self.player_id, self.claim.clone(), self.chain_id, self.issued_at).hash == self.hash
+    }
+}
+
+/// Where a logged action came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum EventSource {
+    Operation,
+    Message,
+}
+
+/// A successfully executed operation or message, as recorded in the event log
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ChainEvent {
+    /// Position in the log: the first event is 0 and each one adds 1
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+    pub source: EventSource,
+    /// Signer of the operation; messages have none
+    pub actor: Option<PlayerId>,
+    /// Operation or message variant, e.g. `BuyShares`
+    pub kind: String,
+    /// The whole operation or message as JSON
+    pub payload: String,
+}
+
+/// What an outbox notification reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum NotificationKind {
+    MarketResolved,
+    /// A trade worth at least the filter's threshold
+    LargeTrade,
+}
+
+/// A notification waiting in the outbox for an off-chain relay to deliver
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Notification {
+    /// Position in the outbox: the first notification is 0 and each one adds 1
+    pub sequence: u64,
+    pub timestamp: Timestamp,
+    pub kind: NotificationKind,
+    pub market_id: MarketId,
+    /// Trader of a large trade
+    pub player_id: Option<PlayerId>,
+    /// Winning outcome of a resolved market, or traded outcome of a large trade
+    pub outcome_id: OutcomeId,
+    /// Tokens paid or received by a large trade
+    pub amount: Option<Amount>,
+}
+
+/// Which notifications the outbox records, set by the admin
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "NotificationFilterInput")]
+pub struct NotificationFilter {
+    pub market_resolved: bool,
+    /// Trades worth at least this many tokens are recorded; none are if unset
+    pub large_trade_threshold: Option<Amount>,
+}
+
+impl Default for NotificationFilter {
+    fn default() -> Self {
+        NotificationFilter {
+            market_resolved: true,
+            large_trade_threshold: None,
+        }
+    }
+}
+
+impl NotificationFilter {
+    pub fn is_large_trade(&self, amount: Amount) -> bool {
+        self.large_trade_threshold.is_some_and(|threshold| amount >= threshold)
+    }
+}
+
+/// Shares a guild holds in a market, bought from its shared pool
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct GuildPosition {
+    pub shares_by_outcome: BTreeMap<OutcomeId, Amount>,
+    /// Tokens taken from the shared pool for these shares
+    pub total_invested: Amount,
+    pub entry_time: Timestamp,
+    /// Set once the winnings have been paid into the shared pool
+    pub claimed: bool,
+}
+
+/// An officer's proposal to invest the guild's shared pool in a market outcome
+/// It executes as soon as it has the guild's required number of officer approvals
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct GuildStakeProposal {
+    pub id: GuildStakeId,
+    pub guild_id: GuildId,
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
+    pub amount: Amount,
+    pub proposer: PlayerId,
+    /// Officers who approved, starting with the proposer
+    pub approvals: Vec<PlayerId>,
+    pub created_at: Timestamp,
+    pub executed: bool,
+}
+
+/// An officer's proposal to pay tokens out of the guild's token pool
+/// It executes as soon as it has the guild's required number of officer approvals
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct GuildTokenWithdrawal {
+    pub id: GuildWithdrawalId,
+    pub guild_id: GuildId,
+    pub amount: Amount,
+    pub recipient: AccountOwner,
+    pub proposer: PlayerId,
+    /// Officers who approved, starting with the proposer
+    pub approvals: Vec<PlayerId>,
+    pub created_at: Timestamp,
+    pub executed: bool,
+}
+
+/// A config change waiting for its timelock to expire
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct PendingConfigChange {
+    pub config: GameConfig,
+    pub proposed_by: PlayerId,
+    pub proposed_at: Timestamp,
+    /// The change is applied by the first operation at or after this time
+    pub effective_at: Timestamp,
+}
+
+/// Aggregate activity of the markets a player created
+#[derive(Debug, Clone, Default, Serialize, Deserialize, async_graphql::SimpleObject)]
+#[graphql(complex)]
+pub struct CreatorStats {
+    pub markets_created: u64,
+    /// Tokens traded across the creator's markets
+    pub total_volume: Amount,
+    /// Trading fees charged across the creator's markets
+    pub total_fees_generated: Amount,
+    /// Trading fees paid to the creator
+    pub trading_fees_earned: Amount,
+    /// Creation fee rewards paid to the creator
+    pub creation_fees_earned: Amount,
+    pub markets_resolved: u64,
+    /// Resolutions the arbitration council had to rule on because the vote did not settle
+    pub disputed_resolutions: u64,
+    /// Participant ratings across the creator's resolved markets
+    pub thumbs_up: u64,
+    pub thumbs_down: u64,
+    /// Resolved markets where the creator traded most of the volume themselves
+    pub wash_traded_markets: u64,
+}
+
+impl CreatorStats {
+    /// Fold another creator's stats into these
+    pub fn merge(&mut self, other: &CreatorStats) {
+        self.markets_created += other.markets_created;
+        self.total_volume = self.total_volume.saturating_add(other.total_volume);
+        self.total_fees_generated = self.total_fees_generated.saturating_add(other.total_fees_generated);
+        self.trading_fees_earned = self.trading_fees_earned.saturating_add(other.trading_fees_earned);
+        self.creation_fees_earned = self.creation_fees_earned.saturating_add(other.creation_fees_earned);
+        self.markets_resolved += other.markets_resolved;
+        self.disputed_resolutions += other.disputed_resolutions;
+        self.thumbs_up += other.thumbs_up;
+        self.thumbs_down += other.thumbs_down;
+        self.wash_traded_markets += other.wash_traded_markets;
+    }
+
+    /// Markets created and not yet resolved
+    pub fn active_markets(&self) -> u64 {
+        self.markets_created.saturating_sub(self.markets_resolved)
+    }
+
+    /// Quality score in basis points, usable to gate premium market features
+    pub fn quality_score(&self) -> u32 {
+        // Wash-traded markets count against reliability like disputed resolutions
+        math::creator_quality_score(
+            self.markets_resolved,
+            self.disputed_resolutions.saturating_add(self.wash_traded_markets),
+            self.thumbs_up,
+            self.thumbs_down,
+        )
+    }
+}
+
+#[async_graphql::ComplexObject]
+impl CreatorStats {
+    /// Quality score in basis points; 5000 for a creator without history
+    #[graphql(name = "qualityScore")]
+    async fn graphql_quality_score(&self) -> u32 {
+        self.quality_score()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy, async_graphql::Enum)]
+pub enum ChallengeStatus {
+    /// Waiting for the opponent to accept
+    Pending,
+    Active,
+    /// Both players reported the same outcome, or only one reported before the deadline, and
+    /// the winner was paid
+    Settled,
+    /// Withdrawn before acceptance, or voided because the reports disagreed, nobody reported
+    /// or emergency mode refunded the stakes
+    Cancelled,
+}
+
+/// A direct bet between two players on opposite outcomes with equal stakes
+/// The challenger backs outcome 0 and the opponent outcome 1
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Challenge {
+    pub id: ChallengeId,
+    pub challenger: PlayerId,
+    pub opponent: PlayerId,
+    pub outcome_names: Vec<String>,
+    /// Amount each player stakes
+    pub stake: Amount,
+    pub duration_seconds: u64,
+    pub created_at: Timestamp,
+    /// Set when the opponent accepts
+    pub end_time: Option<Timestamp>,
+    pub status: ChallengeStatus,
+    /// Outcome reported by each player after the end time
+    pub challenger_report: Option<OutcomeId>,
+    pub opponent_report: Option<OutcomeId>,
+    pub winner: Option<PlayerId>,
+    /// Pot paid to the winner after fees
+    pub payout: Amount,
+}
+
+/// A mentor and their apprentice, from when the apprentice accepted
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Mentorship {
+    pub mentor: PlayerId,
+    pub apprentice: PlayerId,
+    pub started_at: Timestamp,
+    /// Level of the last milestone paid; zero before the first
+    pub last_milestone_level: u32,
+    /// Bonus XP the apprentice earned through the pairing
+    pub apprentice_bonus_xp: u64,
+    /// Bonus XP the mentor earned from the apprentice's trades
+    pub mentor_bonus_xp: u64,
+}
+
+/// One pick of a parlay
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject, async_graphql::InputObject)]
+#[graphql(input_name = "ParlayLegInput")]
+pub struct ParlayLeg {
+    pub market_id: MarketId,
+    pub outcome_id: OutcomeId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Copy, async_graphql::Enum)]
+pub enum ParlayStatus {
+    /// Some legs are still unresolved
+    Open,
+    Won,
+    Lost,
+}
+
+/// A bet that pays only if every leg's outcome wins, at odds fixed when it was placed
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Parlay {
+    pub id: ParlayId,
+    pub player: PlayerId,
+    pub legs: Vec<ParlayLeg>,
+    pub stake: Amount,
+    /// Payout multiplier, the product of each leg's inverse implied probability less the
+    /// house edge
+    pub odds: Amount,
+    pub potential_payout: Amount,
+    pub created_at: Timestamp,
+    pub status: ParlayStatus,
+}
+
+/// Audit record of an account moved to a new key
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct AccountRecovery {
+    pub old_player_id: PlayerId,
+    pub new_player_id: PlayerId,
+    pub recovered_at: Timestamp,
+    /// Token balance moved to the new account
+    pub tokens_moved: Amount,
+    pub markets_moved: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct GuildAnnouncement {
+    pub author: PlayerId,
+    pub text: String,
+    pub posted_at: Timestamp,
+}
+
+/// How prominently frontends should show a system announcement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum AnnouncementSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+/// A game-wide notice set by the admin, such as planned maintenance or an upcoming event
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct SystemAnnouncement {
+    pub text: String,
+    pub severity: AnnouncementSeverity,
+    pub posted_at: Timestamp,
+    /// Frontends stop showing the announcement after this time
+    pub expires_at: Option<Timestamp>,
+}
+
+impl SystemAnnouncement {
+    pub fn is_expired(&self, now: Timestamp) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct GuildLeaderboardEntry {
     pub guild_id: GuildId,
     pub name: String,
@@ -174,23 +2761,195 @@ pub struct OracleVoting {
     pub voting_end: Timestamp,
     pub votes: BTreeMap<OutcomeId, WeightedVotes>,
     pub voters: Vec<PlayerId>,
+    /// The outcome each voter picked
+    pub ballots: BTreeMap<PlayerId, OutcomeId>,
     pub resolved: bool,
 }
 
+/// A council ruling that settled a contested oracle market
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct ArbitrationRecord {
+    pub market_id: MarketId,
+    pub arbitrator: PlayerId,
+    /// Outcome the council ruled won
+    pub outcome_id: OutcomeId,
+    /// Winning outcomes the market resolved to, the ruled outcome first
+    pub ranked_outcomes: Vec<OutcomeId>,
+    /// Total vote weight each outcome had when the vote ended contested
+    pub vote_weights: BTreeMap<OutcomeId, u64>,
+    pub ruled_at: Timestamp,
+}
+
+/// Maximum number of markets in a market group
+pub const MAX_GROUP_MARKETS: usize = 32;
+
+/// Markets that settle together, such as every match of one tournament round
+/// The group's resolver resolves all of them in one operation; the prize goes to the player
+/// with the best record across the group
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct MarketGroup {
+    pub id: GroupId,
+    pub name: String,
+    pub creator: PlayerId,
+    pub resolver: PlayerId,
+    pub market_ids: Vec<MarketId>,
+    /// Tokens put up by the creator for the best record
+    pub prize: Amount,
+    pub created_at: Timestamp,
+    pub resolved_at: Option<Timestamp>,
+    /// Traded volume across the group's markets when it resolved
+    pub total_volume: Amount,
+    /// Players holding a position in any of the group's markets when it resolved
+    pub participants: u32,
+    /// Every participant's record, best first
+    pub standings: Vec<GroupStanding>,
+    pub prize_winner: Option<PlayerId>,
+}
+
+/// A player's record across a market group
+/// A player's pick in a market is the outcome they hold the most shares of
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct GroupStanding {
+    pub player_id: PlayerId,
+    /// Markets whose winning outcome was the player's pick
+    pub correct: u32,
+    /// Markets the player held a position in
+    pub picks: u32,
+}
+
+/// Most teams a bracket can have
+pub const MAX_BRACKET_TEAMS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, async_graphql::Enum)]
+pub enum BracketStatus {
+    Open,
+    Finalized,
+}
+
+/// A knockout tournament players predict in full before it locks
+/// Matches are numbered round by round (see `math::bracket_match_round`); the creator links
+/// a two-outcome market to each match once its teams are known, outcome 0 being the team
+/// coming from the lower-numbered slot. Scoring a resolved match awards its round's points
+/// to every entry that picked the winner, and the entry fees are split between the best
+/// scores when the final is scored.
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Bracket {
+    pub id: BracketId,
+    pub name: String,
+    pub creator: PlayerId,
+    /// Teams in seeding order; the first round pairs each even slot with the next one
+    pub teams: Vec<String>,
+    pub entry_fee: Amount,
+    /// Entries are no longer accepted from this time
+    pub locks_at: Timestamp,
+    /// Share of the pool, in basis points, paid to each final rank
+    pub payout_weights: Vec<u32>,
+    /// Market deciding each match, once linked
+    pub match_markets: Vec<Option<MarketId>>,
+    /// Team index that won each scored match
+    pub winners: Vec<Option<u32>>,
+    pub entries: Vec<BracketEntry>,
+    /// Entry fees paid in
+    pub pool: Amount,
+    pub status: BracketStatus,
+    pub created_at: Timestamp,
+}
+
+/// A player's predicted winner, as a team index, for every match of a bracket
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct BracketEntry {
+    pub player: PlayerId,
+    pub picks: Vec<u32>,
+    pub score: u64,
+    pub entered_at: Timestamp,
+    /// Share of the pool paid out when the bracket was finalized
+    pub payout: Amount,
+}
+
+impl Bracket {
+    /// The two matches whose winners meet in `match_index`, or `None` in the first round
+    pub fn feeder_matches(&self, match_index: usize) -> Option<(usize, usize)> {
+        let teams = self.teams.len();
+        let (round, position) = math::bracket_match_round(teams, match_index)?;
+        if round == 0 {
+            return None;
+        }
+        let previous_offset = teams - (teams >> (round - 1));
+        Some((previous_offset + 2 * position, previous_offset + 2 * position + 1))
+    }
+
+    /// Teams playing `match_index`, once the matches feeding it are scored
+    pub fn match_teams(&self, match_index: usize) -> Option<(u32, u32)> {
+        let (round, position) = math::bracket_match_round(self.teams.len(), match_index)?;
+        if round == 0 {
+            return Some((2 * position as u32, 2 * position as u32 + 1));
+        }
+        let (first, second) = self.feeder_matches(match_index)?;
+        Some(((*self.winners.get(first)?)?, (*self.winners.get(second)?)?))
+    }
+
+    /// Whether `picks` names a winner for every match that could reach it: a team of the
+    /// first-round pairing, or one of the picks for the matches feeding it
+    pub fn picks_valid(&self, picks: &[u32]) -> bool {
+        if picks.len() != self.match_markets.len() {
+            return false;
+        }
+        (0..picks.len()).all(|match_index| {
+            let candidates = match self.feeder_matches(match_index) {
+                Some((first, second)) => (picks[first], picks[second]),
+                None => (2 * match_index as u32, 2 * match_index as u32 + 1),
+            };
+            picks[match_index] == candidates.0 || picks[match_index] == candidates.1
+        })
+    }
+}
+
+/// A player staking tokens to vote on oracle markets
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct Resolver {
+    pub stake: Amount,
+    pub registered_at: Timestamp,
+    /// Markets voted on that haven't resolved yet; the stake is locked until they do
+    pub pending_markets: Vec<MarketId>,
+    pub votes_cast: u64,
+    pub total_slashed: Amount,
+    pub fees_earned: Amount,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WeightedVotes {
     pub total_weight: u64,
     pub voter_count: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
 pub struct Achievement {
     pub id: AchievementId,
+    /// Name in `DEFAULT_LOCALE`
     pub name: String,
+    /// Description in `DEFAULT_LOCALE`
     pub description: String,
     pub reward_tokens: Amount,
     pub reward_xp: u64,
+    #[graphql(skip)]
     pub requirement: AchievementRequirement,
+    /// Name in other locales, keyed by locale tag
+    pub localized_names: BTreeMap<String, String>,
+    /// Description in other locales, keyed by locale tag
+    pub localized_descriptions: BTreeMap<String, String>,
+}
+
+impl Achievement {
+    /// The achievement with its name and description in `locale`, where translated
+    pub fn localized(mut self, locale: &str) -> Self {
+        if let Some(name) = localized(&self.localized_names, locale) {
+            self.name = name.clone();
+        }
+        if let Some(description) = localized(&self.localized_descriptions, locale) {
+            self.description = description.clone();
+        }
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -204,23 +2963,190 @@ pub enum AchievementRequirement {
     ReachLevel(u32),
 }
 
+/// A collective milestone whose reward is deposited into the guild's shared pool
+#[derive(Debug, Clone, Serialize, Deserialize, async_graphql::SimpleObject)]
+pub struct GuildAchievement {
+    pub id: AchievementId,
+    pub name: String,
+    pub description: String,
+    pub reward_tokens: Amount,
+    #[graphql(skip)]
+    pub requirement: GuildAchievementRequirement,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GuildAchievementRequirement {
+    PoolContributed(Amount),
+    MembersRecruited(u32),
+}
+
 #[derive(RootView)]
 #[view(context = ViewStorageContext)]
 pub struct PredictionMarketState {
     pub config: RegisterView<GameConfig>,
+    /// While set, markets are suspended and players may only withdraw
+    pub emergency_mode: RegisterView<bool>,
+    /// Scheduled config change, if any
+    pub pending_config: RegisterView<Option<PendingConfigChange>>,
+    /// Player the admin role has been offered to, until they accept
+    pub pending_admin: RegisterView<Option<PlayerId>>,
+    /// Game-wide announcement set on the home chain and copied to market chains
+    pub announcement: RegisterView<Option<SystemAnnouncement>>,
+    /// Upcoming and running special events, in order of start time
+    pub special_events: RegisterView<Vec<SpecialEvent>>,
+    /// When mystery chests are earned and what they can hold
+    pub chest_table: RegisterView<ChestTable>,
+    /// Chests each player earned, indexed by chest ID
+    pub chests: MapView<PlayerId, Vec<MysteryChest>>,
+    pub next_event_id: RegisterView<EventId>,
     pub markets: MapView<MarketId, Market>,
+    /// Settled markets moved out of `markets`, kept as summaries
+    pub market_archive: MapView<MarketId, MarketSummary>,
     pub players: MapView<PlayerId, Player>,
+    /// Markets each player still holds shares, a commitment or a sealed trade in, however they
+    /// entered; emergency withdrawals look no further
+    pub active_markets: ReentrantCollectionView<ViewStorageContext, PlayerId, SetView<MarketId>>,
+    /// Players in the order they joined each market, so they can be paged through without
+    /// loading the market
+    pub market_participants: ReentrantCollectionView<ViewStorageContext, MarketId, LogView<PlayerId>>,
+    /// Markets each player was involved in that have since settled for them, oldest first
+    pub archived_markets: ReentrantCollectionView<ViewStorageContext, PlayerId, LogView<MarketId>>,
+    /// The markets in each player's `archived_markets`, so returning to one isn't counted as
+    /// joining it again
+    pub archived_market_ids: ReentrantCollectionView<ViewStorageContext, PlayerId, SetView<MarketId>>,
+    /// Cosmetic items for sale
+    pub shop_items: MapView<ItemId, ShopItem>,
+    /// Cosmetic items each player owns
+    pub inventories: ReentrantCollectionView<ViewStorageContext, PlayerId, SetView<ItemId>>,
+    pub raffles: MapView<RaffleId, Raffle>,
+    pub seasons: MapView<SeasonId, Season>,
+    /// Each player's pass progress, per season
+    pub season_passes: ReentrantCollectionView<ViewStorageContext, SeasonId, MapView<PlayerId, SeasonPass>>,
     pub leaderboard: RegisterView<Leaderboard>,
+    /// Players on the leaderboards, best first; kept up to date whenever a player is saved
+    pub rank_index: CustomSetView<ViewStorageContext, RankKey>,
+    /// Number of players in `rank_index`
+    pub ranked_players: RegisterView<u32>,
+    /// Top players on each leaderboard category, best first
+    pub leaderboard_boards: MapView<LeaderboardCategory, Vec<BoardEntry>>,
     pub guilds: MapView<GuildId, Guild>,
+    /// Every player who has ever joined each guild, so rejoining doesn't count as a recruit
+    pub guild_recruits: ReentrantCollectionView<ViewStorageContext, GuildId, SetView<PlayerId>>,
+    /// Members already paid their share of each guild's pools during the current emergency
+    pub guild_emergency_exits: ReentrantCollectionView<ViewStorageContext, GuildId, SetView<PlayerId>>,
     pub oracle_votes: MapView<MarketId, OracleVoting>,
+    /// Staked resolvers, who alone may vote on oracle markets
+    pub resolvers: MapView<PlayerId, Resolver>,
+    /// How often each resolver's votes matched every other resolver's on contested markets;
+    /// kept for both players of a pair
+    pub vote_agreements: MapView<PlayerId, BTreeMap<PlayerId, VoteAgreement>>,
+    /// Market-maker bots the admin approved, kept after they're revoked
+    pub market_makers: MapView<PlayerId, MarketMaker>,
     pub achievements: MapView<AchievementId, Achievement>,
+    pub guild_achievements: MapView<AchievementId, GuildAchievement>,
     pub total_supply: RegisterView<Amount>,
+    /// Cumulative tokens removed from circulation by burns
+    pub total_burned: RegisterView<Amount>,
+    /// Unclaimed winnings swept after their claim window closed and lost parlay stakes, less
+    /// the winnings held back for open parlays
+    pub treasury: RegisterView<Amount>,
+    /// Token sources and sinks per day, keyed by day index
+    pub economy_metrics: MapView<u64, EconomyDay>,
+    /// Trade tallies of each market, for spectator statistics
+    pub market_activity: MapView<MarketId, MarketActivity>,
     pub next_market_id: RegisterView<MarketId>,
+    pub next_item_id: RegisterView<ItemId>,
+    pub next_raffle_id: RegisterView<RaffleId>,
+    pub next_season_id: RegisterView<SeasonId>,
+    pub next_guild_id: RegisterView<GuildId>,
+    /// Whether `next_guild_id` has been moved past guilds created with timestamp-derived IDs
+    pub guild_ids_migrated: RegisterView<bool>,
+    /// Guild message boards, oldest announcement first
+    pub guild_announcements: MapView<GuildId, Vec<GuildAnnouncement>>,
+    /// Chains notified with `MarketResolved` when a market resolves
+    pub market_subscribers: MapView<MarketId, Vec<ChainId>>,
+    /// Market chains registered with this home chain, with their registration time
+    pub network_chains: MapView<ChainId, Timestamp>,
+    /// Markets whose title or description contains a keyword, in creation order
+    pub market_search_index: MapView<String, Vec<MarketId>>,
+    /// Market each slug was given to; archived markets keep theirs so a slug is never reused
+    pub market_slugs: MapView<String, MarketId>,
+    /// Markets the admin lined up to be featured, next first
+    pub featured_queue: RegisterView<Vec<MarketId>>,
+    /// Featured market of each day, by day index; days without an open market have none
+    pub featured_markets: MapView<u64, FeaturedMarket>,
+    /// Latest day a featured market was chosen for
+    pub featured_day: RegisterView<Option<u64>>,
+    /// Tokens traded in each market on `volume_day`, from which the next day's trending
+    /// market is picked
+    pub daily_volumes: MapView<MarketId, Amount>,
+    /// Views of each market on `volume_day`, counted towards trending with its volume
+    pub daily_views: MapView<MarketId, u64>,
+    /// Markets each player's own views were counted for on `volume_day`; a player's view
+    /// counts once a market a day
+    pub daily_viewers: MapView<PlayerId, Vec<MarketId>>,
+    pub volume_day: RegisterView<Option<u64>>,
+    /// Views of each market since it was created
+    pub market_views: MapView<MarketId, u64>,
+    /// Repeat-trade XP each player earned in each market today, counted against the daily cap
+    pub repeat_trade_xp: ReentrantCollectionView<ViewStorageContext, MarketId, MapView<PlayerId, DailyTradeXp>>,
+    /// XP each player earned from all their trades today, counted against the daily cap
+    pub daily_trade_xp: MapView<PlayerId, DailyTradeXp>,
+    /// Price impact each player's buys in each market used up in their current window
+    pub price_impact_windows: ReentrantCollectionView<ViewStorageContext, MarketId, MapView<PlayerId, PriceImpactWindow>>,
+    pub challenges: MapView<ChallengeId, Challenge>,
+    /// Current mentorship of each apprentice
+    pub mentorships: MapView<PlayerId, Mentorship>,
+    /// Apprentices of each mentor, in the order they accepted
+    pub apprentices: MapView<PlayerId, Vec<PlayerId>>,
+    /// Mentors who offered to take each player on, oldest offer first
+    pub mentorship_offers: MapView<PlayerId, Vec<PlayerId>>,
+    pub parlays: MapView<ParlayId, Parlay>,
+    pub next_parlay_id: RegisterView<ParlayId>,
+    pub next_challenge_id: RegisterView<ChallengeId>,
+    pub guild_stake_proposals: MapView<GuildStakeId, GuildStakeProposal>,
+    pub guild_token_withdrawals: MapView<GuildWithdrawalId, GuildTokenWithdrawal>,
+    pub next_guild_withdrawal_id: RegisterView<GuildWithdrawalId>,
+    /// Every successful operation and state-changing message, in execution order
+    pub event_log: LogView<ChainEvent>,
+    /// Notifications for off-chain relays, in the order they were raised
+    pub outbox: LogView<Notification>,
+    pub notification_filter: RegisterView<NotificationFilter>,
+    pub next_guild_stake_id: RegisterView<GuildStakeId>,
+    /// Participants who rated each resolved market
+    pub market_raters: MapView<MarketId, Vec<PlayerId>>,
+    /// Activity of each market creator's markets
+    pub creator_stats: MapView<PlayerId, CreatorStats>,
+    /// Recovered accounts, keyed by the old player ID
+    pub account_recoveries: MapView<PlayerId, AccountRecovery>,
+    /// Players the admin appointed to the arbitration council
+    pub arbitrators: SetView<PlayerId>,
+    /// Binding rulings on contested oracle markets; written once and never changed
+    pub arbitrations: MapView<MarketId, ArbitrationRecord>,
+    pub market_groups: MapView<GroupId, MarketGroup>,
+    pub next_group_id: RegisterView<GroupId>,
+    pub brackets: MapView<BracketId, Bracket>,
+    /// Tokens paid to each rank of a league when a season is settled, best rank first
+    pub league_rewards: MapView<LeagueTier, Vec<Amount>>,
+    /// Final league standings of each settled season, by tier then rank
+    pub league_results: MapView<SeasonId, Vec<LeagueStanding>>,
+    /// Latest season whose leagues were settled
+    pub league_season_settled: RegisterView<Option<SeasonId>>,
+    pub next_bracket_id: RegisterView<BracketId>,
+    /// Attestations issued by this chain
+    pub attestations: MapView<AttestationId, Attestation>,
+    pub next_attestation_id: RegisterView<AttestationId>,
+    /// Attestations other chains sent here, by hash
+    pub received_attestations: MapView<CryptoHash, Attestation>,
+    /// Latest reconciliation of each fungible token application guild pools hold
+    pub token_reconciliations: MapView<ApplicationId, TokenReconciliation>,
+    /// Markets still open for trading or bootstrapping, in order of end time
+    pub markets_by_end_time: CustomSetView<ViewStorageContext, MarketEndKey>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum Message {
-    MarketCreated { market_id: MarketId, creator: PlayerId },
+    MarketCreated { market_id: MarketId, creator: PlayerId, slug: String },
     MarketResolved { market_id: MarketId, winning_outcome: OutcomeId },
     TradeExecuted {
         player_id: PlayerId,
@@ -232,4 +3158,289 @@ pub enum Message {
     PlayerLeveledUp { player_id: PlayerId, new_level: u32 },
     AchievementUnlocked { player_id: PlayerId, achievement_id: AchievementId },
     GuildCreated { guild_id: GuildId, name: String },
+    GuildAchievementUnlocked { guild_id: GuildId, achievement_id: AchievementId },
+    /// A market chain asks the home chain to add it to the network
+    RegisterChain,
+    /// The home chain's current config, sent to market chains
+    SyncConfig { config: Box<GameConfig> },
+    /// The home chain's current announcement, sent to market chains
+    SyncAnnouncement { announcement: Option<SystemAnnouncement> },
+    /// Player stat changes on a market chain, applied on the home chain
+    PlayerStatsUpdated { player_id: PlayerId, delta: PlayerStatsDelta },
+    /// Pay the next batch of winners of a resolved market, starting after `after`
+    SettleWinners { market_id: MarketId, after: Option<PlayerId> },
+    /// A player's winnings will be swept to the treasury unless claimed by `expires_at`
+    ClaimExpiring { market_id: MarketId, player_id: PlayerId, payout: Amount, expires_at: Timestamp },
+    /// An attestation issued by the sending chain
+    AttestationIssued { attestation: Attestation },
+}
+
+/// Changes to a player's stats made on a market chain since the last report
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayerStatsDelta {
+    pub display_name: Option<String>,
+    /// The player's current level on the reporting chain
+    pub level: u32,
+    pub earned: Amount,
+    pub spent: Amount,
+    pub markets_participated: u64,
+    pub markets_won: u64,
+    /// Tokens traded on the reporting chain
+    pub volume: Amount,
+}
+
+/// Win rates were once stored as the bits of an `f64` percentage. Both encodings are a u64 on
+/// the wire and a basis-point value never exceeds `BASIS_POINTS`, while the bits of any positive
+/// float do, so older entries are recognised and converted as they are read.
+mod win_rate_encoding {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        u64::from(*value).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        let raw = u64::deserialize(deserializer)?;
+        Ok(if raw <= u64::from(math::BASIS_POINTS) {
+            raw as u32
+        } else {
+            math::legacy_percent_bits_to_basis_points(raw)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUSES: [MarketStatus; 6] = [
+        MarketStatus::Bootstrapping,
+        MarketStatus::Active,
+        MarketStatus::Closed,
+        MarketStatus::Voting,
+        MarketStatus::Resolved,
+        MarketStatus::Cancelled,
+    ];
+
+    #[test]
+    fn only_lifecycle_transitions_are_allowed() {
+        use MarketStatus::*;
+        let legal = [
+            (Bootstrapping, Active),
+            (Bootstrapping, Cancelled),
+            (Active, Closed),
+            (Active, Voting),
+            (Active, Cancelled),
+            (Closed, Resolved),
+            (Closed, Cancelled),
+            (Voting, Resolved),
+            (Voting, Cancelled),
+        ];
+        for from in STATUSES {
+            for to in STATUSES {
+                assert_eq!(
+                    from.can_transition_to(to),
+                    legal.contains(&(from, to)),
+                    "{from:?} -> {to:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn rank_keys_order_best_first_and_round_trip() {
+        let key = |profit: u128, level: u32, byte: u8| RankKey {
+            total_profit: Amount::from_tokens(profit),
+            level,
+            experience_points: 0,
+            player_id: AccountOwner::Address20([byte; 20]),
+        };
+        let keys = [key(50, 1, 3), key(10, 4, 2), key(10, 2, 1), key(10, 2, 2), key(0, 9, 1)];
+        for pair in keys.windows(2) {
+            assert!(pair[0].to_custom_bytes().unwrap() < pair[1].to_custom_bytes().unwrap());
+        }
+        for key in keys {
+            assert_eq!(RankKey::from_custom_bytes(&key.to_custom_bytes().unwrap()).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn creator_cap_grows_with_level_and_track_record() {
+        let cap = CreatorMarketCap::default();
+        let mut stats = CreatorStats::default();
+        assert_eq!(cap.limit(1, &stats), Some(10));
+        assert_eq!(cap.limit(11, &stats), Some(12));
+        stats.markets_resolved = 5;
+        stats.thumbs_up = 20;
+        assert_eq!(cap.limit(1, &stats), Some(20));
+        stats.disputed_resolutions = 3;
+        assert_eq!(cap.limit(1, &stats), Some(10));
+        assert_eq!(CreatorMarketCap { base: 0, ..cap }.limit(1, &stats), None);
+    }
+
+    #[test]
+    fn outcome_display_is_validated() {
+        let display = OutcomeDisplay {
+            emoji: Some("\u{1F3C6}".to_string()),
+            color: Some("#1a2B3c".to_string()),
+            icon_uri: Some("ipfs://trophy.png".to_string()),
+        };
+        assert!(display.is_valid());
+        assert!(OutcomeDisplay::default().is_valid());
+        for invalid in [
+            OutcomeDisplay { emoji: Some("yes".to_string()), ..display.clone() },
+            OutcomeDisplay { emoji: Some(String::new()), ..display.clone() },
+            OutcomeDisplay { color: Some("#12345".to_string()), ..display.clone() },
+            OutcomeDisplay { color: Some("red".to_string()), ..display.clone() },
+            OutcomeDisplay { icon_uri: Some("not a uri".to_string()), ..display.clone() },
+        ] {
+            assert!(!invalid.is_valid(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn flagged_pairs_form_clusters() {
+        let clusters = collusion_clusters(&[(4, 5), (1, 2), (7, 8), (2, 4)]);
+        assert_eq!(clusters, vec![vec![1, 2, 4, 5], vec![7, 8]]);
+        assert!(collusion_clusters::<u32>(&[]).is_empty());
+    }
+
+    #[test]
+    fn agreement_is_flagged_past_both_thresholds() {
+        let config = CollusionConfig::default();
+        assert!(VoteAgreement { shared_votes: 5, agreed_votes: 5 }.is_flagged(&config));
+        assert!(!VoteAgreement { shared_votes: 4, agreed_votes: 4 }.is_flagged(&config));
+        assert!(!VoteAgreement { shared_votes: 10, agreed_votes: 8 }.is_flagged(&config));
+        let off = CollusionConfig { min_shared_votes: 0, ..config };
+        assert!(!VoteAgreement { shared_votes: 10, agreed_votes: 10 }.is_flagged(&off));
+    }
+
+    #[test]
+    fn terminal_statuses_never_change() {
+        for from in [MarketStatus::Resolved, MarketStatus::Cancelled] {
+            assert!(STATUSES.iter().all(|to| !from.can_transition_to(*to)));
+        }
+    }
+
+    #[test]
+    fn positions_lock_between_close_and_resolution() {
+        let locked: Vec<MarketStatus> = STATUSES.iter().copied().filter(|status| status.locks_positions()).collect();
+        assert_eq!(locked, [MarketStatus::Closed, MarketStatus::Voting]);
+    }
+
+    fn market_with(positions: [(PlayerId, bool); 2]) -> Market {
+        let outcome = |id: OutcomeId| Outcome {
+            id,
+            name: format!("Outcome {id}"),
+            total_shares: Amount::from_tokens(20),
+            current_price: Amount::ONE,
+            holders: 2,
+            open_interest: Amount::ZERO,
+            display: OutcomeDisplay::default(),
+        };
+        let position = |settled: bool| PlayerPosition {
+            shares_by_outcome: BTreeMap::from([(0, Amount::from_tokens(10))]),
+            total_invested: Amount::from_tokens(10),
+            entry_time: Timestamp::from(0),
+            settled,
+            entry_probabilities: BTreeMap::new(),
+            entry_times: BTreeMap::new(),
+        };
+        Market {
+            id: 0,
+            creator: positions[0].0,
+            title: "Merge".to_string(),
+            slug: "merge".to_string(),
+            description: String::new(),
+            market_type: MarketType::QuickPrediction,
+            outcomes: vec![outcome(0), outcome(1)],
+            creation_time: Timestamp::from(0),
+            end_time: Timestamp::from(0),
+            resolution_time: None,
+            status: MarketStatus::Resolved,
+            total_liquidity: Amount::from_tokens(20),
+            positions: positions.into_iter().map(|(player, settled)| (player, position(settled))).collect(),
+            guild_positions: BTreeMap::new(),
+            total_participants: 2,
+            base_price: Amount::ONE,
+            curve: CurveParams::default(),
+            winning_outcome: Some(0),
+            resolution_method: ResolutionMethod::CreatorDecides,
+            payout_weights: vec![10_000],
+            winning_outcomes: vec![0],
+            visibility: MarketVisibility::Public,
+            allowed_guild: None,
+            allowlist: Vec::new(),
+            metadata: MarketMetadata::default(),
+            edit_history: Vec::new(),
+            allow_early_close: false,
+            closed_early_at: None,
+            current_holders: 2,
+            volume: Amount::ZERO,
+            fees_collected: Amount::ZERO,
+            creator_fees_earned: Amount::ZERO,
+            thumbs_up: 0,
+            thumbs_down: 0,
+            bootstrap_end: None,
+            bootstrap_commitments: Vec::new(),
+            blind_finish_seconds: 0,
+            blind_commitments: Vec::new(),
+            claims_expire_at: None,
+            expiry_warning_sent: false,
+            resolution_evidence: Vec::new(),
+            oracle_value: None,
+            auctions: Vec::new(),
+            share_offers: Vec::new(),
+            bot_quotes: Vec::new(),
+            group_id: None,
+            pending_correction: None,
+            voting_start: None,
+            voting_end: None,
+            creator_volume: Amount::ZERO,
+            creator_bond: Amount::ZERO,
+            bond_status: BondStatus::Held,
+            review_end: None,
+            ambiguity_flags: Vec::new(),
+            paused_at: None,
+        }
+    }
+
+    #[test]
+    fn merging_keeps_only_the_unpaid_claim() {
+        let old = AccountOwner::Address20([1; 20]);
+        let new = AccountOwner::Address20([2; 20]);
+        for old_settled in [false, true] {
+            let mut market = market_with([(old, old_settled), (new, !old_settled)]);
+            market.transfer_position(&old, new);
+            let merged = &market.positions[&new];
+            assert!(!merged.settled, "old settled: {old_settled}");
+            assert_eq!(merged.shares_by_outcome[&0], Amount::from_tokens(10));
+            assert_eq!(merged.total_invested, Amount::from_tokens(20));
+            assert!(!market.positions.contains_key(&old));
+            assert_eq!(market.current_holders, 1);
+            assert_eq!(market.outcomes[0].holders, 1);
+            assert_eq!(market.outcomes[0].total_shares, Amount::from_tokens(20));
+        }
+        let mut market = market_with([(old, false), (new, false)]);
+        market.transfer_position(&old, new);
+        assert_eq!(market.positions[&new].shares_by_outcome[&0], Amount::from_tokens(20));
+    }
+
+    #[test]
+    fn passed_on_shares_keep_their_entry_time() {
+        let seller = AccountOwner::Address20([1; 20]);
+        let holder = AccountOwner::Address20([2; 20]);
+        let buyer = AccountOwner::Address20([3; 20]);
+        let mut market = market_with([(seller, false), (holder, false)]);
+        let (cost_basis, acquired_at) = market.withdraw_shares(&seller, 0, Amount::from_tokens(5)).unwrap();
+        assert_eq!(acquired_at, Timestamp::from(0));
+        market.deposit_shares(buyer, 0, Amount::from_tokens(5), cost_basis, acquired_at);
+        let position = &market.positions[&buyer];
+        assert_eq!(position.entry_time, Timestamp::from(0));
+        assert_eq!(position.entry_time_for(&[0]), Timestamp::from(0));
+        // Shares passed to an existing holder can only make their entry earlier
+        market.positions.get_mut(&holder).unwrap().entry_time = Timestamp::from(1_000);
+        market.deposit_shares(holder, 1, Amount::from_tokens(5), cost_basis, acquired_at);
+        assert_eq!(market.positions[&holder].entry_time, Timestamp::from(0));
+    }
 }
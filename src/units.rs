@@ -0,0 +1,135 @@
+// Token units: token amounts, share counts and prices per share are all stored as `Amount`,
+// which lets one be passed where another is expected. The trading code works with these
+// wrappers instead, so mixing units fails to compile, and converts between them only through
+// the functions below.
+
+use linera_sdk::linera_base_types::Amount;
+
+use crate::math::{self, ONE};
+
+/// Shared constructors and accessors of a unit wrapper around `Amount`
+macro_rules! unit {
+    ($name:ident) => {
+        impl $name {
+            pub const ZERO: Self = Self(Amount::ZERO);
+
+            pub fn new(amount: Amount) -> Self {
+                Self(amount)
+            }
+
+            pub fn from_attos(attos: u128) -> Self {
+                Self(Amount::from_attos(attos))
+            }
+
+            /// The stored `Amount`, for fields and messages that keep the unit implicit
+            pub fn amount(self) -> Amount {
+                self.0
+            }
+
+            pub fn attos(self) -> u128 {
+                u128::from(self.0)
+            }
+
+            pub fn saturating_add(self, other: Self) -> Self {
+                Self(self.0.saturating_add(other.0))
+            }
+
+            pub fn saturating_sub(self, other: Self) -> Self {
+                Self(self.0.saturating_sub(other.0))
+            }
+        }
+
+        impl From<$name> for Amount {
+            fn from(value: $name) -> Amount {
+                value.0
+            }
+        }
+    };
+}
+
+/// Tokens paid, received or held
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Tokens(Amount);
+
+/// Shares of a market outcome
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Shares(Amount);
+
+/// Tokens per whole share
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PricePerShare(Amount);
+
+unit!(Tokens);
+unit!(Shares);
+unit!(PricePerShare);
+
+impl Tokens {
+    /// Shares these tokens buy at a fixed `price`, rounding down; none at a zero price
+    pub fn shares_at(self, price: PricePerShare) -> Shares {
+        Shares::from_attos(math::mul_div(self.attos(), ONE, price.attos()))
+    }
+}
+
+impl Shares {
+    /// Tokens these shares are worth at a fixed `price`, rounding down
+    pub fn value_at(self, price: PricePerShare) -> Tokens {
+        Tokens::from_attos(math::mul_div(self.attos(), price.attos(), ONE))
+    }
+}
+
+impl PricePerShare {
+    /// Average price of a trade of `shares` for `tokens`, rounding down; `None` for no shares
+    pub fn average(tokens: Tokens, shares: Shares) -> Option<Self> {
+        (shares > Shares::ZERO).then(|| Self::from_attos(math::mul_div(tokens.attos(), ONE, shares.attos())))
+    }
+}
+
+/// Tokens of a trade either side of its fee: a buy spends `gross` and `net` buys the shares,
+/// while a sell's shares are worth `gross` and the seller receives `net`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradeAmounts {
+    pub gross: Tokens,
+    pub fee_bps: u32,
+    pub fee: Tokens,
+    pub net: Tokens,
+}
+
+impl TradeAmounts {
+    /// Take a fee of `fee_bps` out of `gross`
+    pub fn carve(gross: Tokens, fee_bps: u32) -> Self {
+        let fee = Tokens::from_attos(math::apply_basis_points(gross.attos(), fee_bps)).min(gross);
+        Self {
+            gross,
+            fee_bps,
+            fee,
+            net: gross.saturating_sub(fee),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_agree_with_each_other() {
+        let price = PricePerShare::new(Amount::from_millis(1_500));
+        let shares = Tokens::new(Amount::from_tokens(3)).shares_at(price);
+        assert_eq!(shares, Shares::new(Amount::from_tokens(2)));
+        assert_eq!(shares.value_at(price), Tokens::new(Amount::from_tokens(3)));
+        assert_eq!(PricePerShare::average(Tokens::new(Amount::from_tokens(3)), shares), Some(price));
+    }
+
+    #[test]
+    fn fees_come_out_of_the_trade() {
+        let amounts = TradeAmounts::carve(Tokens::new(Amount::from_tokens(200)), 50);
+        assert_eq!(amounts.fee, Tokens::new(Amount::ONE));
+        assert_eq!(amounts.net, Tokens::new(Amount::from_tokens(199)));
+    }
+
+    #[test]
+    fn empty_trades_have_no_price() {
+        assert_eq!(PricePerShare::average(Tokens::new(Amount::ONE), Shares::ZERO), None);
+        assert_eq!(Tokens::new(Amount::ONE).shares_at(PricePerShare::ZERO), Shares::ZERO);
+    }
+}
@@ -0,0 +1,4268 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! End-to-end lifecycle tests for the predictive_manager application.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::{
+    linera_base_types::{
+        AccountOwner, AccountSecretKey, Amount, ApplicationId, TimeDelta, TimeoutConfig,
+    },
+    test::{ActiveChain, QueryOutcome, TestValidator},
+};
+use predictive_manager::{
+    state::BlindTrade, CollusionConfig, GameConfig, LeagueTier, MarketMakerLimits, MarketVisibility, MentorshipConfig, MentorshipMilestone, Operation,
+    OracleValue, ParlayLeg, PassTier, PredictiveManagerAbi, PrivacySettings, QuoteSide, ResolutionMethod, ScalarRange, TradeXp,
+    TwoSidedQuote,
+};
+use serde_json::Value;
+
+/// Create a chain running the application with a single required oracle voter
+async fn setup() -> (
+    TestValidator,
+    ActiveChain,
+    ApplicationId<PredictiveManagerAbi>,
+) {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        min_oracle_voters: 1,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    (validator, chain, application_id)
+}
+
+/// Hand the chain over to `key_pair` so that following operations are signed as that player
+async fn switch_player(
+    validator: &TestValidator,
+    chain: &mut ActiveChain,
+    key_pair: AccountSecretKey,
+) -> AccountOwner {
+    let owner = AccountOwner::from(key_pair.public());
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_owner_change(vec![], vec![(owner, 100)], 2, false, TimeoutConfig::default());
+        })
+        .await;
+    chain.set_key_pair(key_pair);
+    owner
+}
+
+async fn query(
+    chain: &ActiveChain,
+    application_id: ApplicationId<PredictiveManagerAbi>,
+    query: &str,
+) -> Value {
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    response
+}
+
+async fn player_balance(
+    chain: &ActiveChain,
+    application_id: ApplicationId<PredictiveManagerAbi>,
+    player: AccountOwner,
+) -> Amount {
+    let response = query(
+        chain,
+        application_id,
+        &format!("query {{ player(playerId: \"{player}\") {{ tokenBalance }} }}"),
+    )
+    .await;
+    response["player"]["tokenBalance"]
+        .as_str()
+        .expect("Failed to get token balance")
+        .parse()
+        .unwrap()
+}
+
+/// Register, create a market, trade from two players, close, vote, resolve and claim
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_lifecycle() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+
+    // Alice registers, creates an oracle market and backs "Yes"
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it rain?".to_string(),
+                        description: "Rain in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    // Bob registers and backs "No"
+    let bob_key = AccountSecretKey::generate();
+    let bob = switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        amount: Amount::from_tokens(50),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        Amount::from_tokens(950)
+    );
+
+    let response = query(
+        &chain,
+        application_id,
+        "query { market(marketId: 0) { status totalLiquidity totalParticipants \
+            outcomes { totalShares currentPrice } } \
+            marketParticipants(marketId: 0) later: marketParticipants(marketId: 0, offset: 1) }",
+    )
+    .await;
+    let market = &response["market"];
+    assert_eq!(market["status"], "ACTIVE");
+    assert_eq!(market["totalLiquidity"], "149.25");
+    assert_eq!(market["totalParticipants"], 2);
+    assert_eq!(
+        response["marketParticipants"],
+        serde_json::json!([alice.to_string(), bob.to_string()])
+    );
+    assert_eq!(response["later"], serde_json::json!([bob.to_string()]));
+    let yes_shares: Amount = market["outcomes"][0]["totalShares"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(yes_shares > Amount::ZERO);
+
+    // After the end time the market closes and Bob, staked as the only resolver, reports "Yes"
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 })
+                .with_operation(
+                    application_id,
+                    Operation::RegisterAsResolver {
+                        stake: Amount::from_tokens(100),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::VoteOnOutcome {
+                        market_id: 0,
+                        outcome_id: 0,
+                        evidence: None,
+                    },
+                )
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 });
+        })
+        .await;
+
+    let response = query(
+        &chain,
+        application_id,
+        "query { market(marketId: 0) { status winningOutcome winningOutcomes } }",
+    )
+    .await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    assert_eq!(response["market"]["winningOutcome"], 0);
+
+    // Alice claims; the sole winning rank pays one token per share
+    switch_player(&validator, &mut chain, alice_key).await;
+    let alice_balance = player_balance(&chain, application_id, alice).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 });
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        alice_balance.saturating_add(yes_shares)
+    );
+
+    // Claiming again pays nothing more
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 })
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 });
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        alice_balance.saturating_add(yes_shares)
+    );
+
+    // Bob backed the losing outcome and has nothing to claim beyond his resolver fee
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 });
+        })
+        .await;
+    let response = query(
+        &chain,
+        application_id,
+        &format!("query {{ resolver(playerId: \"{bob}\") {{ stake feesEarned }} }}"),
+    )
+    .await;
+    assert_eq!(response["resolver"]["stake"], "100.");
+    let fees: Amount = response["resolver"]["feesEarned"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(fees > Amount::ZERO);
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        Amount::from_tokens(850).saturating_add(fees)
+    );
+}
+
+/// Found a guild, have a second player join and contribute, then leave and rejoin
+#[tokio::test(flavor = "multi_thread")]
+async fn test_guild_lifecycle() {
+    let (validator, mut chain, application_id) = setup().await;
+    let founder = AccountOwner::from(chain.public_key());
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Founder".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateGuild {
+                        name: "Forecasters".to_string(),
+                    },
+                );
+        })
+        .await;
+
+    let member = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Member".to_string()),
+                    },
+                )
+                .with_operation(application_id, Operation::JoinGuild { guild_id: 0 })
+                .with_operation(
+                    application_id,
+                    Operation::ContributeToGuild {
+                        amount: Amount::from_tokens(25),
+                    },
+                );
+        })
+        .await;
+
+    let response = query(
+        &chain,
+        application_id,
+        "query { guild(guildId: 0) { founder members sharedPool } }",
+    )
+    .await;
+    let guild = &response["guild"];
+    assert_eq!(guild["founder"], founder.to_string());
+    assert_eq!(guild["members"].as_array().unwrap().len(), 2);
+    assert_eq!(guild["sharedPool"], "25.");
+    assert_eq!(
+        player_balance(&chain, application_id, member).await,
+        Amount::from_tokens(975)
+    );
+
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::LeaveGuild);
+        })
+        .await;
+
+    let response = query(
+        &chain,
+        application_id,
+        &format!(
+            "query {{ guild(guildId: 0) {{ members }} player(playerId: \"{member}\") {{ guildId }} }}"
+        ),
+    )
+    .await;
+    assert_eq!(response["guild"]["members"].as_array().unwrap().len(), 1);
+    assert_eq!(response["player"]["guildId"], Value::Null);
+
+    // Rejoining doesn't count as another recruit
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::JoinGuild { guild_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, "query { guild(guildId: 0) { members membersRecruited } }").await;
+    assert_eq!(response["guild"]["members"].as_array().unwrap().len(), 2);
+    assert_eq!(response["guild"]["membersRecruited"], 1);
+}
+
+/// Officers post to the guild board; other members need to be promoted first
+#[tokio::test(flavor = "multi_thread")]
+async fn test_guild_announcements() {
+    let (validator, mut chain, application_id) = setup().await;
+    let founder_key = chain.key_pair().copy();
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Founder".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateGuild {
+                        name: "Heralds".to_string(),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::PostGuildAnnouncement {
+                        text: "Welcome!".to_string(),
+                    },
+                );
+        })
+        .await;
+
+    // A regular member cannot post until promoted
+    let member_key = AccountSecretKey::generate();
+    let member = switch_player(&validator, &mut chain, member_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Member".to_string()),
+                    },
+                )
+                .with_operation(application_id, Operation::JoinGuild { guild_id: 0 })
+                .with_operation(
+                    application_id,
+                    Operation::PostGuildAnnouncement {
+                        text: "Ignored".to_string(),
+                    },
+                );
+        })
+        .await;
+
+    switch_player(&validator, &mut chain, founder_key).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::SetGuildOfficer {
+                    member,
+                    is_officer: true,
+                },
+            );
+        })
+        .await;
+
+    switch_player(&validator, &mut chain, member_key).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::PostGuildAnnouncement {
+                    text: "Meeting at noon".to_string(),
+                },
+            );
+        })
+        .await;
+
+    let response = query(
+        &chain,
+        application_id,
+        "query { latest: guildAnnouncements(guildId: 0, limit: 1) { text author } \
+            all: guildAnnouncements(guildId: 0) { text } }",
+    )
+    .await;
+    assert_eq!(response["latest"][0]["text"], "Meeting at noon");
+    assert_eq!(response["latest"][0]["author"], member.to_string());
+    let all = response["all"].as_array().unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[1]["text"], "Welcome!");
+}
+
+/// Only the founder picks the token application a guild's token pool holds
+#[tokio::test(flavor = "multi_thread")]
+async fn test_guild_token_application() {
+    let (validator, mut chain, application_id) = setup().await;
+    // Any application ID will do, as no tokens are moved
+    let token_application = application_id.forget_abi();
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None })
+                .with_operation(
+                    application_id,
+                    Operation::CreateGuild {
+                        name: "Treasurers".to_string(),
+                    },
+                )
+                // Nothing to deposit into before a token application is chosen
+                .with_operation(
+                    application_id,
+                    Operation::DepositGuildTokens {
+                        amount: Amount::from_tokens(5),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::SetGuildTokenApplication {
+                        application_id: Some(token_application),
+                    },
+                );
+        })
+        .await;
+
+    switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None })
+                .with_operation(application_id, Operation::JoinGuild { guild_id: 0 })
+                .with_operation(
+                    application_id,
+                    Operation::SetGuildTokenApplication { application_id: None },
+                );
+        })
+        .await;
+
+    let response = query(
+        &chain,
+        application_id,
+        "query { guild(guildId: 0) { tokenApplication tokenPool } }",
+    )
+    .await;
+    assert_eq!(response["guild"]["tokenApplication"], token_application.to_string());
+    assert_eq!(response["guild"]["tokenPool"], "0.");
+}
+
+/// Every registered player has a rank as soon as they register, and it agrees with the order
+/// of the leaderboard a keeper refreshes
+#[tokio::test(flavor = "multi_thread")]
+async fn test_player_ranks() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice = AccountOwner::from(chain.public_key());
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Alice".to_string()),
+                },
+            );
+        })
+        .await;
+
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Bob".to_string()),
+                },
+            );
+        })
+        .await;
+
+    // Ranks are current without waiting for a keeper to refresh the leaderboard
+    let rank_query = format!("query {{ myRank(playerId: \"{alice}\") {{ rank totalPlayers }} }}");
+    let response = query(&chain, application_id, &rank_query).await;
+    assert!(response["myRank"]["rank"].as_u64().is_some());
+    assert_eq!(response["myRank"]["totalPlayers"], 2);
+
+    // Bob refreshes and is paid for it; a second refresh within the interval pays nothing
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::RefreshLeaderboard)
+                .with_operation(application_id, Operation::RefreshLeaderboard);
+        })
+        .await;
+    assert_eq!(player_balance(&chain, application_id, bob).await, Amount::from_tokens(1001));
+
+    let response = query(
+        &chain,
+        application_id,
+        &format!(
+            "query {{ leaderboard {{ totalRankedPlayers topTraders {{ playerId }} }} \
+                alice: myRank(playerId: \"{alice}\") {{ rank totalPlayers displayName }} \
+                bob: myRank(playerId: \"{bob}\") {{ rank }} }}"
+        ),
+    )
+    .await;
+    assert_eq!(response["leaderboard"]["totalRankedPlayers"], 2);
+    assert_eq!(response["alice"]["totalPlayers"], 2);
+    assert_eq!(response["alice"]["displayName"], "Alice");
+
+    let top_traders = response["leaderboard"]["topTraders"].as_array().unwrap();
+    let alice_rank = response["alice"]["rank"].as_u64().unwrap() as usize;
+    let bob_rank = response["bob"]["rank"].as_u64().unwrap() as usize;
+    assert_eq!(top_traders[alice_rank - 1]["playerId"], alice.to_string());
+    assert_eq!(top_traders[bob_rank - 1]["playerId"], bob.to_string());
+
+    // Players who never registered have no rank
+    let stranger = AccountOwner::from(AccountSecretKey::generate().public());
+    let response = query(
+        &chain,
+        application_id,
+        &format!("query {{ myRank(playerId: \"{stranger}\") {{ rank }} }}"),
+    )
+    .await;
+    assert!(response["myRank"].is_null());
+}
+
+/// Players can leave the leaderboards and hide their name and portfolio, and undo it later
+#[tokio::test(flavor = "multi_thread")]
+async fn test_privacy_settings() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let update_privacy = |hidden| Operation::UpdateProfile {
+        display_name: Some("Alice".to_string()),
+        privacy: Some(PrivacySettings {
+            hide_from_leaderboard: hidden,
+            hide_display_name: hidden,
+            hide_portfolio: hidden,
+        }),
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it hail?".to_string(),
+                        description: "Hail in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(application_id, update_privacy(true));
+        })
+        .await;
+
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, Operation::RefreshLeaderboard);
+        })
+        .await;
+    let privacy_query = format!(
+        "query {{ leaderboard {{ totalRankedPlayers topTraders {{ playerId }} }} \
+            myRank(playerId: \"{alice}\") {{ rank displayName }} \
+            player(playerId: \"{alice}\") {{ displayName }} \
+            activeMarkets(playerId: \"{alice}\") }}"
+    );
+    let response = query(&chain, application_id, &privacy_query).await;
+    assert_eq!(response["leaderboard"]["totalRankedPlayers"], 1);
+    assert_eq!(response["leaderboard"]["topTraders"], serde_json::json!([{ "playerId": bob.to_string() }]));
+    assert!(response["myRank"].is_null());
+    assert!(response["player"]["displayName"].is_null());
+    assert_eq!(response["activeMarkets"], serde_json::json!([]));
+
+    // Alice shows herself again and is ranked at the next refresh
+    switch_player(&validator, &mut chain, alice_key).await;
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, update_privacy(false))
+                .with_operation(application_id, Operation::RefreshLeaderboard);
+        })
+        .await;
+    let response = query(&chain, application_id, &privacy_query).await;
+    assert_eq!(response["leaderboard"]["totalRankedPlayers"], 2);
+    assert_eq!(response["myRank"]["displayName"], "Alice");
+    assert_eq!(response["player"]["displayName"], "Alice");
+    assert_eq!(response["activeMarkets"], serde_json::json!([0]));
+}
+
+/// A recovery owner can take over an account only after the recovery delay
+#[tokio::test(flavor = "multi_thread")]
+async fn test_account_recovery() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice = AccountOwner::from(chain.public_key());
+    let bob_key = AccountSecretKey::generate();
+    let bob = AccountOwner::from(bob_key.public());
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Recoverable".to_string(),
+                        description: "Positions move with the account".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 30 * 24 * 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(application_id, Operation::LinkRecoveryOwner { owner: bob });
+        })
+        .await;
+    let alice_balance = player_balance(&chain, application_id, alice).await;
+
+    // Recovering straight away is rejected
+    switch_player(&validator, &mut chain, bob_key).await;
+    let recover = || Operation::RecoverAccount { old_player_id: alice };
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, recover());
+        })
+        .await;
+    let response = query(
+        &chain,
+        application_id,
+        &format!("query {{ player(playerId: \"{bob}\") {{ tokenBalance }} }}"),
+    )
+    .await;
+    assert!(response["player"].is_null());
+
+    validator.clock().add(TimeDelta::from_secs(3 * 24 * 3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, recover());
+        })
+        .await;
+
+    assert_eq!(player_balance(&chain, application_id, bob).await, alice_balance);
+    let response = query(
+        &chain,
+        application_id,
+        &format!(
+            "query {{ alice: player(playerId: \"{alice}\") {{ tokenBalance }} \
+                market(marketId: 0) {{ creator currentHolders }} \
+                accountRecovery(oldPlayerId: \"{alice}\") {{ newPlayerId marketsMoved }} }}"
+        ),
+    )
+    .await;
+    assert!(response["alice"].is_null());
+    assert_eq!(response["market"]["creator"], bob.to_string());
+    assert_eq!(response["market"]["currentHolders"], 1);
+    assert_eq!(response["accountRecovery"]["newPlayerId"], bob.to_string());
+    assert_eq!(response["accountRecovery"]["marketsMoved"], 1);
+}
+
+/// Two players settle a head-to-head challenge by reporting the same outcome
+#[tokio::test(flavor = "multi_thread")]
+async fn test_head_to_head_challenge() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Alice".to_string()),
+                },
+            );
+        })
+        .await;
+
+    let bob_key = AccountSecretKey::generate();
+    let bob = switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Bob".to_string()),
+                },
+            );
+        })
+        .await;
+    let starting_balance = player_balance(&chain, application_id, alice).await;
+
+    switch_player(&validator, &mut chain, alice_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::CreateHeadToHead {
+                    opponent: bob,
+                    outcome_names: vec!["Arsenal wins".to_string(), "Arsenal doesn't win".to_string()],
+                    stake: Amount::from_tokens(100),
+                    duration_seconds: 3600,
+                },
+            );
+        })
+        .await;
+
+    switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::AcceptChallenge { challenge_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, "query { challenge(challengeId: 0) { status } }").await;
+    assert_eq!(response["challenge"]["status"], "ACTIVE");
+
+    // Both players report that the challenger's outcome happened
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ReportChallengeOutcome {
+                    challenge_id: 0,
+                    outcome_id: 0,
+                },
+            );
+        })
+        .await;
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ReportChallengeOutcome {
+                    challenge_id: 0,
+                    outcome_id: 0,
+                },
+            );
+        })
+        .await;
+
+    // The 200 token pot pays out minus the 0.5% trading fee
+    let response = query(
+        &chain,
+        application_id,
+        "query { challenge(challengeId: 0) { status winner payout } }",
+    )
+    .await;
+    assert_eq!(response["challenge"]["status"], "SETTLED");
+    assert_eq!(response["challenge"]["winner"], alice.to_string());
+    assert_eq!(response["challenge"]["payout"], "199.");
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        starting_balance.saturating_add(Amount::from_tokens(99))
+    );
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        starting_balance.saturating_sub(Amount::from_tokens(100))
+    );
+}
+
+/// A lone report stands once the report window is over, and emergency withdrawals call off
+/// open challenges
+#[tokio::test(flavor = "multi_thread")]
+async fn test_challenge_report_deadline() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        admin: Some(alice),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let bob_key = AccountSecretKey::generate();
+    let bob = AccountOwner::from(bob_key.public());
+    let challenge = |tokens| Operation::CreateHeadToHead {
+        opponent: bob,
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        stake: Amount::from_tokens(tokens),
+        duration_seconds: 3600,
+    };
+    let report = || Operation::ReportChallengeOutcome {
+        challenge_id: 0,
+        outcome_id: 0,
+    };
+    let accept = |challenge_id| Operation::AcceptChallenge { challenge_id };
+
+    switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Bob".to_string()),
+                },
+            );
+        })
+        .await;
+    let starting_balance = player_balance(&chain, application_id, bob).await;
+    switch_player(&validator, &mut chain, alice_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(application_id, challenge(100));
+        })
+        .await;
+    switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, accept(0));
+        })
+        .await;
+
+    // Bob never reports; Alice's report alone doesn't settle it within the window
+    switch_player(&validator, &mut chain, alice_key.copy()).await;
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, report())
+                .with_operation(application_id, challenge(50));
+        })
+        .await;
+    let response = query(&chain, application_id, "query { challenge(challengeId: 0) { status } }").await;
+    assert_eq!(response["challenge"]["status"], "ACTIVE");
+
+    validator.clock().add(TimeDelta::from_secs(2 * 24 * 3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, report())
+                .with_operation(application_id, challenge(30));
+        })
+        .await;
+    let response = query(&chain, application_id, "query { challenge(challengeId: 0) { status winner } }").await;
+    assert_eq!(response["challenge"]["status"], "SETTLED");
+    assert_eq!(response["challenge"]["winner"], alice.to_string());
+
+    // Bob accepts the last challenge, then withdraws in emergency mode: the pending challenge
+    // returns Alice's stake and the active one both stakes
+    switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, accept(2));
+        })
+        .await;
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::SetEmergencyMode { enabled: true });
+        })
+        .await;
+    let alice_balance = player_balance(&chain, application_id, alice).await;
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, Operation::EmergencyWithdraw);
+        })
+        .await;
+    let response = query(
+        &chain,
+        application_id,
+        "query { pending: challenge(challengeId: 1) { status } active: challenge(challengeId: 2) { status } }",
+    )
+    .await;
+    assert_eq!(response["pending"]["status"], "CANCELLED");
+    assert_eq!(response["active"]["status"], "CANCELLED");
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        alice_balance.saturating_add(Amount::from_tokens(80))
+    );
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        starting_balance.saturating_sub(Amount::from_tokens(100))
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bootstrap_phase() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice = AccountOwner::from(chain.public_key());
+    let commit = |outcome_id, tokens| Operation::CommitToOutcome {
+        market_id: 0,
+        outcome_id,
+        amount: Amount::from_tokens(tokens),
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Opening night".to_string(),
+                        description: "Which film wins best picture".to_string(),
+                        outcome_names: vec!["Dune".to_string(), "Wicked".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: Some(600),
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+    let starting_balance = player_balance(&chain, application_id, alice).await;
+
+    // Trading is closed while the market bootstraps; commitments are taken instead
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(10),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(application_id, commit(0, 100));
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        starting_balance.saturating_sub(Amount::from_tokens(100))
+    );
+
+    let bob_key = AccountSecretKey::generate();
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, commit(0, 100))
+                .with_operation(application_id, commit(0, 200))
+                .with_operation(application_id, commit(1, 50));
+        })
+        .await;
+
+    // Finalizing early is rejected
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::FinalizeBootstrap { market_id: 0 });
+        })
+        .await;
+    let status_query = "query { market(marketId: 0) { status totalLiquidity bootstrapCommitments { amount } outcomes { totalShares holders } } }";
+    let response = query(&chain, application_id, status_query).await;
+    assert_eq!(response["market"]["status"], "BOOTSTRAPPING");
+    assert_eq!(response["market"]["bootstrapCommitments"].as_array().unwrap().len(), 3);
+
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::FinalizeBootstrap { market_id: 0 });
+        })
+        .await;
+
+    let response = query(&chain, application_id, status_query).await;
+    let market = &response["market"];
+    assert_eq!(market["status"], "ACTIVE");
+    assert_eq!(market["totalLiquidity"], "450.");
+    assert_eq!(market["outcomes"][0]["holders"], 2);
+    assert_eq!(market["outcomes"][1]["holders"], 1);
+    let yes_shares: f64 = market["outcomes"][0]["totalShares"].as_str().unwrap().parse().unwrap();
+    let no_shares: f64 = market["outcomes"][1]["totalShares"].as_str().unwrap().parse().unwrap();
+    assert!(yes_shares > no_shares && no_shares > 0.0);
+}
+
+/// Trades in the blind finish are sealed, revealed after the end time and hold up resolution
+#[tokio::test(flavor = "multi_thread")]
+async fn test_blind_finish() {
+    let (validator, chain, application_id) = setup().await;
+    let alice = AccountOwner::from(chain.public_key());
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(10),
+        valid_until: None,
+    };
+    let sealed = |outcome_id, salt: &str| BlindTrade {
+        market_id: 0,
+        player: alice,
+        outcome_id,
+        salt: salt.to_string(),
+    };
+    let reveal = |outcome_id, salt: &str| Operation::RevealBlindTrade {
+        market_id: 0,
+        outcome_id,
+        salt: salt.to_string(),
+    };
+    let resolve = || Operation::ResolveMarket {
+        market_id: 0,
+        ranked_outcomes: vec![0],
+        evidence: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Final whistle".to_string(),
+                        description: "Who wins the derby".to_string(),
+                        outcome_names: vec!["Home".to_string(), "Away".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: Some(600),
+                    },
+                )
+                .with_operation(application_id, buy());
+        })
+        .await;
+    let starting_balance = player_balance(&chain, application_id, alice).await;
+
+    // In the final ten minutes open trades are refused and sealed ones escrow their tokens
+    validator.clock().add(TimeDelta::from_secs(3000));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, buy())
+                .with_operation(
+                    application_id,
+                    Operation::CommitBlindTrade {
+                        market_id: 0,
+                        commitment: sealed(0, "first").commitment(),
+                        amount: Amount::from_tokens(50),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CommitBlindTrade {
+                        market_id: 0,
+                        commitment: sealed(1, "second").commitment(),
+                        amount: Amount::from_tokens(30),
+                    },
+                );
+        })
+        .await;
+    let market_query = "query { market(marketId: 0) { status totalLiquidity blindCommitments { amount revealed } } }";
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["totalLiquidity"], "9.95");
+    assert_eq!(response["market"]["blindCommitments"].as_array().unwrap().len(), 2);
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        starting_balance.saturating_sub(Amount::from_tokens(80))
+    );
+
+    // After the end a matching reveal buys shares; resolution waits for the reveal window
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, reveal(0, "wrong"))
+                .with_operation(application_id, reveal(0, "first"))
+                .with_operation(application_id, reveal(0, "first"))
+                .with_operation(application_id, resolve());
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["status"], "ACTIVE");
+    assert_eq!(response["market"]["totalLiquidity"], "59.7");
+    assert_eq!(response["market"]["blindCommitments"][0]["revealed"], true);
+
+    // Once the window passes, late reveals fail and the unrevealed stake joins the pool
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, reveal(1, "second"))
+                .with_operation(application_id, resolve());
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    assert_eq!(response["market"]["blindCommitments"][1]["revealed"], false);
+    let liquidity: f64 = response["market"]["totalLiquidity"].as_str().unwrap().parse().unwrap();
+    assert!(liquidity > 60.0 && liquidity < 90.0);
+}
+
+/// With automatic settlement on, winners are paid in batches without claiming
+#[tokio::test(flavor = "multi_thread")]
+async fn test_auto_settlement() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let config = GameConfig {
+        auto_settlement: true,
+        settlement_batch_size: 1,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let buy = |tokens| Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(tokens),
+        max_price_per_share: Amount::from_tokens(tokens),
+        valid_until: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Rain tomorrow".to_string(),
+                        description: "Will it rain in Lagos".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy(10));
+        })
+        .await;
+
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, buy(20));
+        })
+        .await;
+
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain.handle_received_messages().await;
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    let resolution = chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarket {
+                    market_id: 0,
+                    ranked_outcomes: vec![0],
+                    evidence: None,
+                },
+            );
+        })
+        .await;
+    let alice_before = player_balance(&chain, application_id, alice).await;
+    let bob_before = player_balance(&chain, application_id, bob).await;
+
+    // One position per batch, each batch scheduling the next
+    let paid = |alice_balance: Amount, bob_balance: Amount| {
+        usize::from(alice_balance > alice_before) + usize::from(bob_balance > bob_before)
+    };
+    let first_batch = chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_messages_from(&resolution);
+        })
+        .await;
+    assert_eq!(
+        paid(
+            player_balance(&chain, application_id, alice).await,
+            player_balance(&chain, application_id, bob).await
+        ),
+        1
+    );
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_messages_from(&first_batch);
+        })
+        .await;
+    let alice_after = player_balance(&chain, application_id, alice).await;
+    assert_eq!(paid(alice_after, player_balance(&chain, application_id, bob).await), 2);
+
+    // Paid positions can't be claimed again
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 });
+        })
+        .await;
+    assert_eq!(player_balance(&chain, application_id, alice).await, alice_after);
+}
+
+/// Officers invest the guild pool once enough of them approve, and winnings return to the pool
+#[tokio::test(flavor = "multi_thread")]
+async fn test_guild_staking() {
+    let (validator, mut chain, application_id) = setup().await;
+    let founder_key = chain.key_pair().copy();
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Founder".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateGuild {
+                        name: "Syndicate".to_string(),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::ContributeToGuild {
+                        amount: Amount::from_tokens(300),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Sentiment".to_string(),
+                        description: "Settled by the crowd".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 300,
+                        resolution_method: ResolutionMethod::CrowdConsensus,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    let officer_key = AccountSecretKey::generate();
+    let officer = switch_player(&validator, &mut chain, officer_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Officer".to_string()),
+                    },
+                )
+                .with_operation(application_id, Operation::JoinGuild { guild_id: 0 });
+        })
+        .await;
+
+    // With two approvals required, the founder's proposal waits for the officer
+    switch_player(&validator, &mut chain, founder_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::SetGuildOfficer {
+                        member: officer,
+                        is_officer: true,
+                    },
+                )
+                .with_operation(application_id, Operation::SetGuildStakeApprovals { approvals: 2 })
+                .with_operation(
+                    application_id,
+                    Operation::GuildBuyShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        amount: Amount::from_tokens(200),
+                    },
+                );
+        })
+        .await;
+    let state_query = "query { guildStakeProposal(proposalId: 0) { executed approvals } \
+        guild(guildId: 0) { sharedPool totalGuildProfit } \
+        market(marketId: 0) { status winningOutcomes outcomes { totalShares } } }";
+    let response = query(&chain, application_id, state_query).await;
+    assert_eq!(response["guildStakeProposal"]["executed"], false);
+    let pool = |response: &Value| -> f64 { response["guild"]["sharedPool"].as_str().unwrap().parse().unwrap() };
+    // Contributing also earned the guild an achievement reward
+    let starting_pool = pool(&response);
+    assert!(starting_pool >= 300.0);
+
+    switch_player(&validator, &mut chain, officer_key).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::ApproveGuildStake { proposal_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, state_query).await;
+    assert_eq!(response["guildStakeProposal"]["executed"], true);
+    assert_eq!(response["guildStakeProposal"]["approvals"].as_array().unwrap().len(), 2);
+    assert_eq!(pool(&response), starting_pool - 200.0);
+    assert_ne!(response["market"]["outcomes"][1]["totalShares"], "0.");
+
+    // The guild's outcome wins; its payout goes back to the pool, and only once
+    validator.clock().add(TimeDelta::from_secs(301));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 })
+                .with_operation(application_id, Operation::ClaimGuildWinnings { market_id: 0 })
+                .with_operation(application_id, Operation::ClaimGuildWinnings { market_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, state_query).await;
+    assert_eq!(response["market"]["winningOutcomes"], serde_json::json!([1]));
+    let shares: f64 = response["market"]["outcomes"][1]["totalShares"].as_str().unwrap().parse().unwrap();
+    assert!((pool(&response) - (starting_pool - 200.0 + shares)).abs() < 1e-9);
+}
+
+/// Only staked resolvers vote; wrong and missed votes are slashed and paid to correct voters
+#[tokio::test(flavor = "multi_thread")]
+async fn test_resolver_staking() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let register = |name: &str| Operation::RegisterPlayer {
+        display_name: Some(name.to_string()),
+    };
+    let stake = |tokens| Operation::RegisterAsResolver {
+        stake: Amount::from_tokens(tokens),
+    };
+    let vote = |outcome_id| Operation::VoteOnOutcome {
+        market_id: 0,
+        outcome_id,
+        evidence: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register("Alice"))
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Election night".to_string(),
+                        description: "Who takes the seat".to_string(),
+                        outcome_names: vec!["Incumbent".to_string(), "Challenger".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(application_id, stake(50))
+                .with_operation(application_id, stake(200));
+        })
+        .await;
+
+    // Carol stakes before the market ends but never votes
+    let carol = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register("Carol"))
+                .with_operation(application_id, stake(100));
+        })
+        .await;
+
+    // Bob can only vote once staked, and can't withdraw while the market is pending
+    let bob_key = AccountSecretKey::generate();
+    let bob = switch_player(&validator, &mut chain, bob_key.copy()).await;
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, register("Bob"))
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 })
+                .with_operation(application_id, vote(1))
+                .with_operation(application_id, stake(100))
+                .with_operation(application_id, vote(1))
+                .with_operation(application_id, Operation::WithdrawResolverStake);
+        })
+        .await;
+
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, vote(0))
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 });
+        })
+        .await;
+
+    let resolvers_query = format!(
+        "query {{ market(marketId: 0) {{ status winningOutcome }} \
+            alice: resolver(playerId: \"{alice}\") {{ stake feesEarned votesCast }} \
+            bob: resolver(playerId: \"{bob}\") {{ stake totalSlashed pendingMarkets }} \
+            carol: resolver(playerId: \"{carol}\") {{ stake totalSlashed }} }}"
+    );
+    let response = query(&chain, application_id, &resolvers_query).await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    assert_eq!(response["market"]["winningOutcome"], 0);
+    assert_eq!(response["alice"]["stake"], "200.");
+    assert_eq!(response["alice"]["votesCast"], 1);
+    let fees: f64 = response["alice"]["feesEarned"].as_str().unwrap().parse().unwrap();
+    assert!(fees > 20.0);
+    assert_eq!(response["bob"]["stake"], "90.");
+    assert_eq!(response["bob"]["totalSlashed"], "10.");
+    assert_eq!(response["bob"]["pendingMarkets"], serde_json::json!([]));
+    assert_eq!(response["carol"]["stake"], "90.");
+
+    // With the market resolved Bob can leave with what is left of his stake
+    switch_player(&validator, &mut chain, bob_key).await;
+    let balance = player_balance(&chain, application_id, bob).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::WithdrawResolverStake);
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        balance.saturating_add(Amount::from_tokens(90))
+    );
+    let response = query(&chain, application_id, &resolvers_query).await;
+    assert!(response["bob"].is_null());
+}
+
+/// Resolvers shut out of an allowlisted market aren't slashed for not voting in it
+#[tokio::test(flavor = "multi_thread")]
+async fn test_resolver_outside_market_not_slashed() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let register = |name: &str| Operation::RegisterPlayer {
+        display_name: Some(name.to_string()),
+    };
+    let stake = || Operation::RegisterAsResolver {
+        stake: Amount::from_tokens(100),
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register("Alice"))
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Members only".to_string(),
+                        description: "Only Alice may take part".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: Some(MarketVisibility::Whitelist),
+                        allowed_guild: None,
+                        allowlist: Some(vec![alice]),
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, stake());
+        })
+        .await;
+
+    let carol = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register("Carol"))
+                .with_operation(application_id, stake());
+        })
+        .await;
+
+    switch_player(&validator, &mut chain, alice_key).await;
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 })
+                .with_operation(
+                    application_id,
+                    Operation::VoteOnOutcome {
+                        market_id: 0,
+                        outcome_id: 0,
+                        evidence: None,
+                    },
+                )
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 });
+        })
+        .await;
+
+    let response = query(
+        &chain,
+        application_id,
+        &format!(
+            "query {{ market(marketId: 0) {{ status }} \
+                carol: resolver(playerId: \"{carol}\") {{ stake totalSlashed }} }}"
+        ),
+    )
+    .await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    assert_eq!(response["carol"]["stake"], "100.");
+    assert_eq!(response["carol"]["totalSlashed"], "0.");
+}
+
+/// An oracle vote nobody took part in goes to the arbitration council, whose ruling is final
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_arbitration() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        admin: Some(alice),
+        market_creator_bond: Amount::from_tokens(50),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let bob_key = AccountSecretKey::generate();
+    let bob = AccountOwner::from(bob_key.public());
+    let arbitrate = |outcome| Operation::ArbitrateMarket {
+        market_id: 0,
+        outcome,
+    };
+
+    // Alice stakes as a resolver before the market ends, then never votes
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Quiet vote".to_string(),
+                        description: "Nobody turns up to settle it".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::RegisterAsResolver {
+                        stake: Amount::from_tokens(100),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::SetArbitrator {
+                        member: bob,
+                        is_arbitrator: true,
+                    },
+                );
+        })
+        .await;
+
+    // Only the council can rule, and only once the voting period is over
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, arbitrate(0));
+        })
+        .await;
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, arbitrate(1));
+        })
+        .await;
+    let arbitration_query = format!(
+        "query {{ arbitrators market(marketId: 0) {{ status winningOutcome bondStatus }} \
+            arbitration(marketId: 0) {{ arbitrator outcomeId rankedOutcomes }} \
+            resolver(playerId: \"{alice}\") {{ stake totalSlashed }} \
+            creatorStats(playerId: \"{alice}\") {{ disputedResolutions }} }}"
+    );
+    let response = query(&chain, application_id, &arbitration_query).await;
+    assert_eq!(response["arbitrators"], serde_json::json!([bob.to_string()]));
+    assert_eq!(response["market"]["status"], "ACTIVE");
+    assert_eq!(response["market"]["bondStatus"], "HELD");
+    assert!(response["arbitration"].is_null());
+
+    // The ruling resolves the market and slashes the resolver who missed the vote
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, arbitrate(1))
+                .with_operation(application_id, arbitrate(0));
+        })
+        .await;
+    let response = query(&chain, application_id, &arbitration_query).await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    assert_eq!(response["market"]["winningOutcome"], 1);
+    assert_eq!(response["arbitration"]["arbitrator"], bob.to_string());
+    assert_eq!(response["arbitration"]["outcomeId"], 1);
+    assert_eq!(response["arbitration"]["rankedOutcomes"], serde_json::json!([1]));
+    // Needing the council forfeits the creator's bond
+    assert_eq!(response["market"]["bondStatus"], "FORFEITED");
+    assert_eq!(response["creatorStats"]["disputedResolutions"], 1);
+    assert_eq!(response["resolver"]["stake"], "90.");
+    assert_eq!(response["resolver"]["totalSlashed"], "10.");
+}
+
+/// Only the account named as a market's oracle can report its result; a scalar reading picks
+/// the outcome whose part of the range holds it
+#[tokio::test(flavor = "multi_thread")]
+async fn test_designated_oracle() {
+    let (validator, mut chain, application_id) = setup().await;
+    let feed_key = AccountSecretKey::generate();
+    let feed = AccountOwner::from(feed_key.public());
+    let market = |title: &str, outcomes: &[&str], scalar_range| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Reported by the sports feed".to_string(),
+        outcome_names: outcomes.iter().map(|name| name.to_string()).collect(),
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::DesignatedOracle {
+            oracle_account: feed,
+            scalar_range,
+        },
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let submit = || Operation::SubmitOracleResult {
+        market_id: 0,
+        outcome_id: Some(1),
+        value: None,
+    };
+    // The second market pays the quarter of 0-100 the total points scored land in
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(application_id, market("Final score", &["Home", "Away"], None))
+                .with_operation(
+                    application_id,
+                    market(
+                        "Total points",
+                        &["0-24", "25-49", "50-74", "75-100"],
+                        Some(ScalarRange { min: 0, max: 100 }),
+                    ),
+                );
+        })
+        .await;
+
+    // Neither the creator nor a resolution trigger can settle it
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, submit())
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 });
+        })
+        .await;
+    let market_query = "query { first: market(marketId: 0) { status winningOutcome } \
+        scalar: market(marketId: 1) { status winningOutcome oracleValue { value observedAt } } }";
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["first"]["status"], "CLOSED");
+    assert!(response["first"]["winningOutcome"].is_null());
+
+    // A scalar market only takes a value inside its range
+    let reading = |value| Operation::SubmitOracleResult {
+        market_id: 1,
+        outcome_id: None,
+        value: Some(OracleValue {
+            value,
+            observed_at: now,
+        }),
+    };
+    switch_player(&validator, &mut chain, feed_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, submit())
+                .with_operation(application_id, reading(101))
+                .with_operation(application_id, reading(30));
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["first"]["status"], "RESOLVED");
+    assert_eq!(response["first"]["winningOutcome"], 1);
+    assert_eq!(response["scalar"]["status"], "RESOLVED");
+    assert_eq!(response["scalar"]["winningOutcome"], 1);
+    assert_eq!(response["scalar"]["oracleValue"]["value"], 30);
+}
+
+/// Sell a position through a descending-price auction: a buyer takes half at the decayed
+/// price and the seller takes the rest back
+#[tokio::test(flavor = "multi_thread")]
+async fn test_position_auction() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it rain?".to_string(),
+                        description: "Rain in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+    let market_query = "query { market(marketId: 0) { totalLiquidity outcomes { totalShares } \
+        auctions { shares proceeds open } } }";
+    let response = query(&chain, application_id, market_query).await;
+    let shares: Amount = response["market"]["outcomes"][0]["totalShares"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let half = Amount::from_attos(u128::from(shares) / 2);
+    let alice_balance = player_balance(&chain, application_id, alice).await;
+
+    // The price falls from 2 to 1 over 1000 seconds; a floor above the start is rejected
+    let auction = |floor_price| Operation::StartPositionAuction {
+        market_id: 0,
+        outcome_id: 0,
+        shares,
+        start_price: Amount::from_tokens(2),
+        floor_price,
+        duration_seconds: 1000,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, auction(Amount::from_tokens(3)))
+                .with_operation(application_id, auction(Amount::from_tokens(1)));
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["auctions"].as_array().unwrap().len(), 1);
+    assert_eq!(response["market"]["auctions"][0]["shares"], shares.to_string());
+
+    // Halfway through, Bob buys half the shares at 1.5 each, below his limit but not
+    // below a limit of 1.4, and the cost counts towards his daily loss limit
+    let bob_key = AccountSecretKey::generate();
+    let bob = switch_player(&validator, &mut chain, bob_key).await;
+    validator.clock().add(TimeDelta::from_secs(500));
+    let now = validator.clock().current_time();
+    let fill = |max_price_per_share| Operation::FillPositionAuction {
+        market_id: 0,
+        auction_id: 0,
+        shares: half,
+        max_price_per_share,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::SetLossLimit {
+                        per_day: Some(Amount::from_tokens(100)),
+                    },
+                )
+                .with_operation(application_id, fill(Amount::from_millis(1400)))
+                .with_operation(application_id, fill(Amount::from_tokens(2)));
+        })
+        .await;
+    let cost = Amount::from_attos(u128::from(half) * 3 / 2);
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        Amount::from_tokens(1000).saturating_sub(cost)
+    );
+    let response = query(
+        &chain,
+        application_id,
+        &format!("query {{ player(playerId: \"{bob}\") {{ riskControls {{ spentToday }} }} }}"),
+    )
+    .await;
+    assert_eq!(response["player"]["riskControls"]["spentToday"], cost.to_string());
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        alice_balance.saturating_add(cost)
+    );
+    let response = query(&chain, application_id, market_query).await;
+    let market = &response["market"];
+    assert_eq!(market["totalLiquidity"], "99.5");
+    assert_eq!(market["outcomes"][0]["totalShares"], shares.to_string());
+    assert_eq!(market["auctions"][0]["proceeds"], cost.to_string());
+
+    // Alice takes the unsold shares back
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::CancelPositionAuction {
+                    market_id: 0,
+                    auction_id: 0,
+                },
+            );
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["auctions"][0]["open"], false);
+    assert_eq!(response["market"]["auctions"][0]["shares"], "0.");
+}
+
+/// Move shares between players by transfer and through a reserved offer, leaving the
+/// market's liquidity alone
+#[tokio::test(flavor = "multi_thread")]
+async fn test_share_transfers_and_offers() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it rain?".to_string(),
+                        description: "Rain in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+    let market_query = "query { market(marketId: 0) { totalLiquidity outcomes { totalShares holders } \
+        shareOffers { buyer shares open } } }";
+    let response = query(&chain, application_id, market_query).await;
+    let shares: Amount = response["market"]["outcomes"][0]["totalShares"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    let third = Amount::from_attos(u128::from(shares) / 3);
+
+    let bob_key = AccountSecretKey::generate();
+    let bob = switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Bob".to_string()),
+                },
+            );
+        })
+        .await;
+
+    // Alice gives Bob a third, offers him another for 30 and withdraws an open offer
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::TransferShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        to: bob,
+                        shares: third,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateShareOffer {
+                        market_id: 0,
+                        outcome_id: 0,
+                        shares: third,
+                        price: Amount::from_tokens(30),
+                        buyer: Some(bob),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateShareOffer {
+                        market_id: 0,
+                        outcome_id: 0,
+                        shares: third,
+                        price: Amount::from_tokens(50),
+                        buyer: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CancelShareOffer {
+                        market_id: 0,
+                        offer_id: 1,
+                    },
+                );
+        })
+        .await;
+    let alice_balance = player_balance(&chain, application_id, alice).await;
+    let response = query(&chain, application_id, market_query).await;
+    let market = &response["market"];
+    assert_eq!(market["outcomes"][0]["holders"], 2);
+    assert_eq!(market["shareOffers"][0]["buyer"], bob.to_string());
+    assert_eq!(market["shareOffers"][0]["open"], true);
+    assert_eq!(market["shareOffers"][1]["open"], false);
+    // Receiving shares puts the market among Bob's positions
+    let response = query(
+        &chain,
+        application_id,
+        &format!("query {{ activeMarkets(playerId: \"{bob}\") market(marketId: 0) {{ totalParticipants }} }}"),
+    )
+    .await;
+    assert_eq!(response["activeMarkets"], serde_json::json!([0]));
+    assert_eq!(response["market"]["totalParticipants"], 2);
+
+    // Bob must agree to the offer's price, which counts towards his daily loss limit; the
+    // cancelled offer can't be taken
+    let accept = |offer_id, tokens| Operation::AcceptShareOffer {
+        market_id: 0,
+        offer_id,
+        price: Amount::from_tokens(tokens),
+    };
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::SetLossLimit {
+                        per_day: Some(Amount::from_tokens(40)),
+                    },
+                )
+                .with_operation(application_id, accept(0, 29))
+                .with_operation(application_id, accept(1, 50))
+                .with_operation(application_id, accept(0, 30));
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        Amount::from_tokens(970)
+    );
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        alice_balance.saturating_add(Amount::from_tokens(30))
+    );
+    let response = query(&chain, application_id, market_query).await;
+    let market = &response["market"];
+    assert_eq!(market["totalLiquidity"], "99.5");
+    assert_eq!(market["outcomes"][0]["totalShares"], shares.to_string());
+    assert_eq!(market["outcomes"][0]["holders"], 2);
+    assert_eq!(market["shareOffers"][0]["open"], false);
+    let response = query(
+        &chain,
+        application_id,
+        &format!("query {{ player(playerId: \"{bob}\") {{ riskControls {{ spentToday }} }} }}"),
+    )
+    .await;
+    assert_eq!(response["player"]["riskControls"]["spentToday"], "30.");
+}
+
+/// Mint complete sets, burn some back, then short an outcome by selling it out of the set
+#[tokio::test(flavor = "multi_thread")]
+async fn test_complete_sets() {
+    let (validator, mut chain, application_id) = setup().await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it rain?".to_string(),
+                        description: "Rain in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    // Each share costs at least one token, so ten sets of two outcomes can't cost under 20
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    let mint = |max_cost| Operation::MintCompleteSet {
+        market_id: 0,
+        sets: Amount::from_tokens(10),
+        max_cost: Amount::from_tokens(max_cost),
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, mint(19))
+                .with_operation(application_id, mint(25));
+        })
+        .await;
+    let minted_balance = player_balance(&chain, application_id, bob).await;
+    assert!(minted_balance <= Amount::from_tokens(980));
+    let market_query = "query { market(marketId: 0) { outcomes { totalShares holders } } }";
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["outcomes"][0]["totalShares"], "10.");
+    assert_eq!(response["market"]["outcomes"][1]["totalShares"], "10.");
+    assert_eq!(response["market"]["outcomes"][1]["holders"], 1);
+
+    // Burning four sets pays Bob back; selling all of "No" leaves him short of it, so no
+    // complete set is left to burn
+    let burn = || Operation::BurnCompleteSet {
+        market_id: 0,
+        sets: Amount::from_tokens(4),
+        min_value: Amount::ZERO,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, burn())
+                .with_operation(
+                    application_id,
+                    Operation::SellShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        shares: Amount::from_tokens(6),
+                        min_price_per_share: Amount::ZERO,
+                        valid_until: None,
+                    },
+                )
+                .with_operation(application_id, burn());
+        })
+        .await;
+    assert!(player_balance(&chain, application_id, bob).await > minted_balance);
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["outcomes"][0]["totalShares"], "6.");
+    assert_eq!(response["market"]["outcomes"][1]["totalShares"], "0.");
+    assert_eq!(response["market"]["outcomes"][1]["holders"], 0);
+}
+
+/// Settle a round of markets together and give the group prize to the best record
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_group() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let resolver_key = AccountSecretKey::generate();
+    let resolver = AccountOwner::from(resolver_key.public());
+    let market = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Quarter-final of the cup".to_string(),
+        outcome_names: vec!["Home".to_string(), "Away".to_string()],
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(application_id, market("Match one"))
+                .with_operation(application_id, market("Match two"))
+                .with_operation(application_id, market("Match three"));
+        })
+        .await;
+
+    // Bob calls two matches and Carol all three
+    let buy = |market_id, outcome_id| Operation::BuyShares {
+        market_id,
+        outcome_id,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+    let register = |name: &str| Operation::RegisterPlayer {
+        display_name: Some(name.to_string()),
+    };
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register("Bob"))
+                .with_operation(application_id, buy(0, 0))
+                .with_operation(application_id, buy(1, 1));
+        })
+        .await;
+    let carol = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register("Carol"))
+                .with_operation(application_id, buy(0, 1))
+                .with_operation(application_id, buy(1, 1))
+                .with_operation(application_id, buy(2, 0));
+        })
+        .await;
+
+    // Once grouped, the creator can't resolve a match on its own
+    switch_player(&validator, &mut chain, alice_key).await;
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarketGroup {
+                        name: "Quarter-finals".to_string(),
+                        market_ids: vec![0, 1, 2],
+                        resolver,
+                        prize: Amount::from_tokens(50),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 0,
+                        ranked_outcomes: vec![0],
+                        evidence: None,
+                    },
+                );
+        })
+        .await;
+    let group_query = "query { marketGroup(groupId: 0) { marketIds prize resolvedAt participants \
+        prizeWinner standings { playerId correct picks } } market(marketId: 0) { status groupId } }";
+    let response = query(&chain, application_id, group_query).await;
+    assert_eq!(response["marketGroup"]["marketIds"], serde_json::json!([0, 1, 2]));
+    assert_eq!(response["marketGroup"]["prize"], "50.");
+    assert!(response["marketGroup"]["resolvedAt"].is_null());
+    assert_eq!(response["market"]["status"], "ACTIVE");
+    assert_eq!(response["market"]["groupId"], 0);
+
+    // The resolver settles the round: Bob called both his matches, Carol one of three
+    switch_player(&validator, &mut chain, resolver_key).await;
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarketGroup {
+                    group_id: 0,
+                    results: vec![vec![0], vec![1], vec![1]],
+                },
+            );
+        })
+        .await;
+    let response = query(&chain, application_id, group_query).await;
+    let group = &response["marketGroup"];
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    assert_eq!(group["participants"], 2);
+    assert_eq!(group["prizeWinner"], bob.to_string());
+    assert_eq!(group["standings"][0]["correct"], 2);
+    assert_eq!(group["standings"][0]["picks"], 2);
+    assert_eq!(group["standings"][1]["playerId"], carol.to_string());
+    assert_eq!(group["standings"][1]["correct"], 1);
+}
+
+/// Predict a four-team bracket, score each match from its market and pay the pool to the
+/// best bracket
+#[tokio::test(flavor = "multi_thread")]
+async fn test_bracket() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let market = |home: &str, away: &str| Operation::CreateMarket {
+        title: format!("{home} vs {away}"),
+        description: "Cup knockout match".to_string(),
+        outcome_names: vec![home.to_string(), away.to_string()],
+        duration_seconds: 300,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let link = |match_index, market_id| Operation::LinkBracketMatch {
+        bracket_id: 0,
+        match_index,
+        market_id,
+    };
+    let resolve = |market_id, outcome_id| Operation::ResolveMarket {
+        market_id,
+        ranked_outcomes: vec![outcome_id],
+        evidence: None,
+    };
+    let score = |match_index| Operation::ScoreBracketMatch {
+        bracket_id: 0,
+        match_index,
+    };
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateBracket {
+                        name: "Cup".to_string(),
+                        teams: ["A", "B", "C", "D"].iter().map(|team| team.to_string()).collect(),
+                        entry_fee: Amount::from_tokens(10),
+                        locks_at: now.saturating_add(TimeDelta::from_secs(300)),
+                        payout_weights: None,
+                    },
+                )
+                .with_operation(application_id, market("A", "B"))
+                .with_operation(application_id, market("C", "D"))
+                .with_operation(application_id, link(0, 0))
+                .with_operation(application_id, link(1, 1));
+        })
+        .await;
+
+    // Bob backs A to win it all and Carol D; a final between teams that can't meet is refused
+    let enter = |picks: &[u32]| Operation::EnterBracket {
+        bracket_id: 0,
+        picks: picks.to_vec(),
+    };
+    let register = |name: &str| Operation::RegisterPlayer {
+        display_name: Some(name.to_string()),
+    };
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, register("Bob"))
+                .with_operation(application_id, enter(&[0, 2, 1]))
+                .with_operation(application_id, enter(&[0, 2, 0]));
+        })
+        .await;
+    let carol = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, register("Carol"))
+                .with_operation(application_id, enter(&[1, 3, 3]));
+        })
+        .await;
+    let bracket_query = "query { bracket(bracketId: 0) { status pool winners entries { player score payout } } }";
+    let response = query(&chain, application_id, bracket_query).await;
+    assert_eq!(response["bracket"]["pool"], "20.");
+    assert_eq!(response["bracket"]["entries"].as_array().unwrap().len(), 2);
+
+    // A and D win the first round; the final can't be scored before it has a market
+    switch_player(&validator, &mut chain, alice_key).await;
+    validator.clock().add(TimeDelta::from_secs(301));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, resolve(0, 0))
+                .with_operation(application_id, resolve(1, 1))
+                .with_operation(application_id, score(0))
+                .with_operation(application_id, score(1))
+                .with_operation(application_id, score(2))
+                .with_operation(application_id, market("A", "D"))
+                .with_operation(application_id, link(2, 2));
+        })
+        .await;
+    let response = query(&chain, application_id, bracket_query).await;
+    let bracket = &response["bracket"];
+    assert_eq!(bracket["status"], "OPEN");
+    assert_eq!(bracket["winners"], serde_json::json!([0, 3, null]));
+    assert_eq!(bracket["entries"][0]["score"], 10);
+    assert_eq!(bracket["entries"][1]["score"], 10);
+
+    // D takes the final, worth twice a first-round match, and Carol the whole pool
+    validator.clock().add(TimeDelta::from_secs(301));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, resolve(2, 1))
+                .with_operation(application_id, score(2));
+        })
+        .await;
+    let response = query(&chain, application_id, bracket_query).await;
+    let bracket = &response["bracket"];
+    assert_eq!(bracket["status"], "FINALIZED");
+    assert_eq!(bracket["entries"][0]["player"], bob.to_string());
+    assert_eq!(bracket["entries"][0]["payout"], "0.");
+    assert_eq!(bracket["entries"][1]["score"], 30);
+    assert_eq!(bracket["entries"][1]["payout"], "20.");
+    assert_eq!(
+        player_balance(&chain, application_id, carol).await,
+        Amount::from_tokens(1010)
+    );
+}
+
+/// Hand the admin role over in two steps, then renounce it; a change scheduled before
+/// renouncing still applies
+#[tokio::test(flavor = "multi_thread")]
+async fn test_admin_handover() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice = AccountOwner::from(chain.public_key());
+    let alice_key = chain.key_pair().copy();
+    let config = GameConfig {
+        admin: Some(alice),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config.clone(), vec![])
+        .await;
+    let bob_key = AccountSecretKey::generate();
+    let bob = AccountOwner::from(bob_key.public());
+    let with_reward = |admin, tokens| GameConfig {
+        admin,
+        daily_login_reward: Amount::from_tokens(tokens),
+        ..config.clone()
+    };
+
+    // Naming a new admin in a config update is not a handover
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::TransferAdmin { new_admin: bob })
+                .with_operation(
+                    application_id,
+                    Operation::UpdateGameConfig {
+                        config: with_reward(Some(bob), 20),
+                    },
+                );
+        })
+        .await;
+    let admin_query = "query { gameConfig { admin dailyLoginReward } pendingAdmin \
+        pendingConfigChange { config { dailyLoginReward } } }";
+    let response = query(&chain, application_id, admin_query).await;
+    assert_eq!(response["gameConfig"]["admin"], alice.to_string());
+    assert!(response["pendingConfigChange"].is_null());
+    assert_eq!(response["pendingAdmin"], bob.to_string());
+
+    // Bob's first update waits out the notice period like any other change
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::AcceptAdmin)
+                .with_operation(
+                    application_id,
+                    Operation::UpdateGameConfig {
+                        config: with_reward(Some(bob), 20),
+                    },
+                );
+        })
+        .await;
+    let response = query(&chain, application_id, admin_query).await;
+    assert_eq!(response["gameConfig"]["admin"], bob.to_string());
+    assert_eq!(response["gameConfig"]["dailyLoginReward"], "10.");
+    assert_eq!(response["pendingConfigChange"]["config"]["dailyLoginReward"], "20.");
+    assert!(response["pendingAdmin"].is_null());
+
+    // Bob replaces it with another change and renounces; nobody can schedule changes anymore
+    let effective_at = validator.clock().current_time().saturating_add(TimeDelta::from_secs(3600));
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::ProposeConfigChange {
+                        config: with_reward(Some(bob), 30),
+                        effective_at,
+                    },
+                )
+                .with_operation(application_id, Operation::RenounceAdmin)
+                .with_operation(
+                    application_id,
+                    Operation::UpdateGameConfig {
+                        config: with_reward(None, 40),
+                    },
+                );
+        })
+        .await;
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::UpdateGameConfig {
+                    config: with_reward(Some(alice), 40),
+                },
+            );
+        })
+        .await;
+    let response = query(&chain, application_id, admin_query).await;
+    assert!(response["gameConfig"]["admin"].is_null());
+    assert_eq!(response["gameConfig"]["dailyLoginReward"], "10.");
+    assert_eq!(response["pendingConfigChange"]["config"]["dailyLoginReward"], "30.");
+
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Alice".to_string()),
+                },
+            );
+        })
+        .await;
+    let response = query(&chain, application_id, admin_query).await;
+    assert!(response["gameConfig"]["admin"].is_null());
+    assert_eq!(response["gameConfig"]["dailyLoginReward"], "30.");
+}
+
+/// Sell raffle tickets to two players, draw the winner once the raffle ends and check that
+/// a recurring raffle reopens
+#[tokio::test(flavor = "multi_thread")]
+async fn test_raffle() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        admin: Some(alice),
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateRaffle {
+                        ticket_price: Amount::from_tokens(10),
+                        duration_seconds: 60,
+                        winner_count: 1,
+                        treasury_top_up: Amount::from_tokens(50),
+                        recurring: true,
+                    },
+                )
+                .with_operation(application_id, Operation::BuyRaffleTickets { raffle_id: 0, tickets: 3 })
+                .with_operation(application_id, Operation::BuyRaffleTickets { raffle_id: 0, tickets: 0 })
+                .with_operation(application_id, Operation::DrawRaffle { raffle_id: 0 });
+        })
+        .await;
+
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, Operation::BuyRaffleTickets { raffle_id: 0, tickets: 2 })
+                .with_operation(
+                    application_id,
+                    Operation::CreateRaffle {
+                        ticket_price: Amount::from_tokens(1),
+                        duration_seconds: 60,
+                        winner_count: 1,
+                        treasury_top_up: Amount::ZERO,
+                        recurring: false,
+                    },
+                );
+        })
+        .await;
+
+    let raffle_query = "query { raffles { id status totalTickets pot winners prizePerWinner seed \
+        entries { player tickets } } supplyMetrics { totalBurned } }";
+    let response = query(&chain, application_id, raffle_query).await;
+    let raffles = response["raffles"].as_array().unwrap();
+    assert_eq!(raffles.len(), 1);
+    assert_eq!(raffles[0]["status"], "OPEN");
+    assert_eq!(raffles[0]["totalTickets"], 5);
+    assert_eq!(raffles[0]["pot"], "50.");
+    assert_eq!(raffles[0]["entries"][0]["player"], alice.to_string());
+    assert_eq!(raffles[0]["entries"][0]["tickets"], 3);
+    assert_eq!(raffles[0]["entries"][1]["player"], bob.to_string());
+    // The seed is settled by the ticket sales, before anyone can draw
+    let seed = raffles[0]["seed"].clone();
+    assert!(seed.is_string());
+    assert_eq!(player_balance(&chain, application_id, alice).await, Amount::from_tokens(970));
+    assert_eq!(player_balance(&chain, application_id, bob).await, Amount::from_tokens(980));
+    let burned_before: f64 = response["supplyMetrics"]["totalBurned"].as_str().unwrap().parse().unwrap();
+
+    // Once the raffle ends, tickets are no longer sold and anyone can draw it. The empty
+    // treasury adds nothing; a tenth of the pot is burned and the winner takes the rest
+    validator.clock().add(TimeDelta::from_secs(60));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::BuyRaffleTickets { raffle_id: 0, tickets: 1 })
+                .with_operation(application_id, Operation::DrawRaffle { raffle_id: 0 })
+                .with_operation(application_id, Operation::DrawRaffle { raffle_id: 0 });
+        })
+        .await;
+
+    let response = query(&chain, application_id, raffle_query).await;
+    let raffles = response["raffles"].as_array().unwrap();
+    assert_eq!(raffles.len(), 2);
+    assert_eq!(raffles[0]["status"], "DRAWN");
+    assert_eq!(raffles[0]["totalTickets"], 5);
+    assert_eq!(raffles[0]["prizePerWinner"], "45.");
+    assert_eq!(raffles[0]["seed"], seed);
+    let winners = raffles[0]["winners"].as_array().unwrap();
+    assert_eq!(winners.len(), 1);
+    let (winner_balance, loser_balance) = if winners[0] == alice.to_string() {
+        (Amount::from_tokens(1015), Amount::from_tokens(980))
+    } else {
+        assert_eq!(winners[0], bob.to_string());
+        (Amount::from_tokens(1025), Amount::from_tokens(970))
+    };
+    let alice_balance = player_balance(&chain, application_id, alice).await;
+    let bob_balance = player_balance(&chain, application_id, bob).await;
+    assert_eq!(alice_balance.saturating_add(bob_balance), winner_balance.saturating_add(loser_balance));
+    assert!(alice_balance == winner_balance || bob_balance == winner_balance);
+    let burned: f64 = response["supplyMetrics"]["totalBurned"].as_str().unwrap().parse().unwrap();
+    assert_eq!(burned - burned_before, 5.0);
+
+    // The recurring raffle reopened on the same terms
+    assert_eq!(raffles[1]["id"], 1);
+    assert_eq!(raffles[1]["status"], "OPEN");
+    assert_eq!(raffles[1]["totalTickets"], 0);
+}
+
+/// Earn season XP through trades, claim free tier rewards, then unlock the premium track and
+/// claim its rewards for the tiers already reached
+#[tokio::test(flavor = "multi_thread")]
+async fn test_season_pass() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        admin: Some(alice),
+        self_trade_xp_percent: 100,
+        trade_xp: TradeXp {
+            first_trade: 10,
+            repeat_trade: 10,
+            repeat_daily_cap: 1_000,
+            daily_cap: 1_000,
+            ..TradeXp::default()
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+    let tier = |xp_required, free, premium| PassTier {
+        xp_required,
+        free_reward: Amount::from_tokens(free),
+        premium_reward: Amount::from_tokens(premium),
+    };
+    let tiers = vec![tier(10, 5, 20), tier(20, 5, 20), tier(50, 10, 30)];
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+
+    // The season is scheduled a minute ahead; XP earned before it starts doesn't count and an
+    // overlapping season is refused
+    let starts_at = validator.clock().current_time().saturating_add(TimeDelta::from_secs(60));
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateSeason {
+                        name: "Season 1".to_string(),
+                        starts_at,
+                        duration_seconds: 3600,
+                        premium_price: Amount::from_tokens(50),
+                        tiers: vec![tier(10, 1, 1)],
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::SetPassTiers {
+                        season_id: 0,
+                        tiers: tiers.clone(),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateSeason {
+                        name: "Overlapping".to_string(),
+                        starts_at: starts_at.saturating_add(TimeDelta::from_secs(60)),
+                        duration_seconds: 3600,
+                        premium_price: Amount::ZERO,
+                        tiers: tiers.clone(),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it rain?".to_string(),
+                        description: "Rain in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 7200,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy());
+        })
+        .await;
+
+    let pass_query = format!(
+        "query {{ seasons {{ id tiers {{ xpRequired }} }} \
+         seasonPass(seasonId: 0, playerId: \"{alice}\") {{ xp tier premium freeTiersClaimed premiumTiersClaimed }} }}"
+    );
+    let response = query(&chain, application_id, &pass_query).await;
+    assert_eq!(response["seasons"].as_array().unwrap().len(), 1);
+    assert_eq!(response["seasons"][0]["tiers"].as_array().unwrap().len(), 3);
+    assert!(response["seasonPass"].is_null());
+
+    // Two trades during the season reach the second tier
+    validator.clock().add(TimeDelta::from_secs(60));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, buy())
+                .with_operation(application_id, buy())
+                .with_operation(
+                    application_id,
+                    Operation::SetPassTiers {
+                        season_id: 0,
+                        tiers: vec![tier(1, 100, 100)],
+                    },
+                );
+        })
+        .await;
+    let response = query(&chain, application_id, &pass_query).await;
+    assert_eq!(response["seasons"][0]["tiers"][0]["xpRequired"], 10);
+    assert_eq!(response["seasonPass"]["xp"], 20);
+    assert_eq!(response["seasonPass"]["tier"], 2);
+
+    let balance = player_balance(&chain, application_id, alice).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimPassRewards { season_id: 0 });
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        balance.saturating_add(Amount::from_tokens(10))
+    );
+
+    // The premium track pays out for the tiers reached before it was bought; a second claim
+    // finds nothing new
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::BuyPremiumPass { season_id: 0 })
+                .with_operation(application_id, Operation::ClaimPassRewards { season_id: 0 })
+                .with_operation(application_id, Operation::ClaimPassRewards { season_id: 0 })
+                .with_operation(application_id, Operation::BuyPremiumPass { season_id: 0 });
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        balance.saturating_add(Amount::from_tokens(10 + 40 - 50))
+    );
+    let response = query(&chain, application_id, &pass_query).await;
+    assert_eq!(response["seasonPass"]["premium"], true);
+    assert_eq!(response["seasonPass"]["freeTiersClaimed"], 2);
+    assert_eq!(response["seasonPass"]["premiumTiersClaimed"], 2);
+
+    // XP earned after the season ends doesn't count
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, buy());
+        })
+        .await;
+    let response = query(&chain, application_id, &pass_query).await;
+    assert_eq!(response["seasonPass"]["xp"], 20);
+}
+
+/// Rank guilds on their members' season XP, pay the league reward table and promote the
+/// most active guilds
+#[tokio::test(flavor = "multi_thread")]
+async fn test_guild_leagues() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        admin: Some(alice),
+        self_trade_xp_percent: 100,
+        trade_xp: TradeXp {
+            first_trade: 10,
+            repeat_trade: 10,
+            repeat_daily_cap: 1_000,
+            daily_cap: 1_000,
+            ..TradeXp::default()
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+    let starts_at = validator.clock().current_time().saturating_add(TimeDelta::from_secs(60));
+    let found = |player: &str, guild: &str| {
+        (
+            Operation::RegisterPlayer {
+                display_name: Some(player.to_string()),
+            },
+            Operation::CreateGuild {
+                name: guild.to_string(),
+            },
+        )
+    };
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+    let settle = || Operation::SettleGuildLeagues { season_id: 0 };
+    let (register, create_guild) = found("Alice", "Early Birds");
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register)
+                .with_operation(application_id, create_guild)
+                .with_operation(
+                    application_id,
+                    Operation::CreateSeason {
+                        name: "Season 1".to_string(),
+                        starts_at,
+                        duration_seconds: 600,
+                        premium_price: Amount::ZERO,
+                        tiers: vec![PassTier {
+                            xp_required: 100,
+                            free_reward: Amount::ZERO,
+                            premium_reward: Amount::ZERO,
+                        }],
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::SetLeagueRewards {
+                        tier: LeagueTier::Bronze,
+                        rewards: vec![Amount::from_tokens(30), Amount::from_tokens(10)],
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it rain?".to_string(),
+                        description: "Rain in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 7200,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    // Once the season runs, Bob's guild trades once and Alice's twice; Carol's sits it out
+    validator.clock().add(TimeDelta::from_secs(60));
+    let now = validator.clock().current_time();
+    switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    let (register, create_guild) = found("Bob", "Night Owls");
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, register)
+                .with_operation(application_id, create_guild)
+                .with_operation(application_id, buy());
+        })
+        .await;
+    switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    let (register, create_guild) = found("Carol", "Latecomers");
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, register)
+                .with_operation(application_id, create_guild);
+        })
+        .await;
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, buy())
+                .with_operation(application_id, buy())
+                .with_operation(application_id, settle());
+        })
+        .await;
+    let league_query = "query { league(tier: BRONZE, seasonId: 0) { guildId rank reward nextTier } \
+        leagueResults(seasonId: 0) { guildId } }";
+    let response = query(&chain, application_id, league_query).await;
+    let league = &response["league"];
+    assert_eq!(league[0]["guildId"], 0);
+    assert_eq!(league[0]["reward"], "30.");
+    assert_eq!(league[0]["nextTier"], "SILVER");
+    assert_eq!(league[1]["guildId"], 1);
+    assert_eq!(league[2]["nextTier"], "BRONZE");
+    assert_eq!(response["leagueResults"], serde_json::json!([]));
+
+    // After the season the rewards are paid and the top two guilds go up, only once
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, settle())
+                .with_operation(application_id, settle());
+        })
+        .await;
+    let response = query(
+        &chain,
+        application_id,
+        "query { leagueResults(seasonId: 0) { guildId rank seasonXp reward nextTier } \
+            first: guild(guildId: 0) { leagueTier sharedPool } \
+            third: guild(guildId: 2) { leagueTier sharedPool } }",
+    )
+    .await;
+    let results = &response["leagueResults"];
+    assert_eq!(results.as_array().unwrap().len(), 3);
+    assert_eq!(results[0]["seasonXp"], 20);
+    assert_eq!(results[1]["seasonXp"], 10);
+    assert_eq!(results[1]["reward"], "10.");
+    assert_eq!(results[2]["seasonXp"], 0);
+    assert_eq!(response["first"]["leagueTier"], "SILVER");
+    assert_eq!(response["first"]["sharedPool"], "30.");
+    assert_eq!(response["third"]["leagueTier"], "BRONZE");
+    assert_eq!(response["third"]["sharedPool"], "0.");
+}
+
+/// An apprentice earns bonus XP on trades, shares some with their mentor, and graduates with
+/// milestone rewards for both on reaching the mentor level
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mentorship() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let config = GameConfig {
+        mentorship: MentorshipConfig {
+            mentor_level: 2,
+            max_apprentices: 1,
+            apprentice_bonus_percent: 20,
+            mentor_bonus_percent: 10,
+            milestones: vec![MentorshipMilestone {
+                level: 2,
+                apprentice_reward: Amount::from_tokens(10),
+                mentor_reward: Amount::from_tokens(20),
+            }],
+        },
+        self_trade_xp_percent: 100,
+        trade_xp: TradeXp {
+            first_trade: 10,
+            repeat_trade: 10,
+            repeat_daily_cap: 1_000,
+            daily_cap: 1_000,
+            ..TradeXp::default()
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+    let create_market = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Mentorship practice".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::OracleVoting,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let buy = |market_id| Operation::BuyShares {
+        market_id,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+
+    // Alice trades her way to the mentor level
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(application_id, create_market("Alice's market"));
+            for _ in 0..10 {
+                block.with_operation(application_id, buy(0));
+            }
+        })
+        .await;
+
+    let bob_key = AccountSecretKey::generate();
+    let bob = switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, create_market("Bob's market"));
+        })
+        .await;
+
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::OfferMentorship { apprentice: bob })
+                // Ignored: apprentices must be below the mentor level
+                .with_operation(application_id, Operation::OfferMentorship { apprentice: alice });
+        })
+        .await;
+    let alice_balance = player_balance(&chain, application_id, alice).await;
+
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::AcceptMentorship { mentor: alice })
+                .with_operation(application_id, buy(1));
+        })
+        .await;
+    let mentorship_query = format!(
+        "query {{ mentorship(apprentice: \"{bob}\") {{ mentor apprenticeBonusXp mentorBonusXp }} \
+            apprentices(mentor: \"{alice}\") {{ apprentice }} \
+            mentorshipOffers(playerId: \"{bob}\") \
+            bob: player(playerId: \"{bob}\") {{ level }} }}"
+    );
+    let response = query(&chain, application_id, &mentorship_query).await;
+    assert_eq!(response["mentorship"]["mentor"], alice.to_string());
+    assert_eq!(response["mentorship"]["apprenticeBonusXp"], 2);
+    assert_eq!(response["mentorship"]["mentorBonusXp"], 1);
+    assert_eq!(response["apprentices"][0]["apprentice"], bob.to_string());
+    assert!(response["mentorshipOffers"].as_array().unwrap().is_empty());
+
+    // 12 XP a trade takes Bob to level 2, where the milestone pays out and he graduates
+    chain
+        .add_block(|block| {
+            for _ in 0..9 {
+                block.with_operation(application_id, buy(1));
+            }
+        })
+        .await;
+    let response = query(&chain, application_id, &mentorship_query).await;
+    assert_eq!(response["bob"]["level"], 2);
+    assert!(response["mentorship"].is_null());
+    assert!(response["apprentices"].as_array().unwrap().is_empty());
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        alice_balance.saturating_add(Amount::from_tokens(20))
+    );
+}
+
+/// A creator's outcome rename waits until holders of most of the market's shares approve
+#[tokio::test(flavor = "multi_thread")]
+async fn test_outcome_correction() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let buy = |outcome_id, tokens| Operation::BuyShares {
+        market_id: 0,
+        outcome_id,
+        amount: Amount::from_tokens(tokens),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Who wins the derby?".to_string(),
+                        description: "Outcome names got swapped".to_string(),
+                        outcome_names: vec!["Home".to_string(), "Hmoe".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy(0, 100));
+        })
+        .await;
+
+    let bob_key = AccountSecretKey::generate();
+    switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, buy(1, 200));
+        })
+        .await;
+
+    // Alice holds the minority of shares, so her proposal alone doesn't apply
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::ProposeOutcomeCorrection {
+                    market_id: 0,
+                    outcome_id: 1,
+                    new_name: "Away".to_string(),
+                },
+            );
+        })
+        .await;
+    let market_query = "query { market(marketId: 0) { outcomes { name } \
+        pendingCorrection { newName approvals } \
+        editHistory { correctedOutcome previousOutcomeName } } }";
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["outcomes"][1]["name"], "Hmoe");
+    assert_eq!(response["market"]["pendingCorrection"]["newName"], "Away");
+
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::ApproveOutcomeCorrection { market_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["outcomes"][1]["name"], "Away");
+    assert!(response["market"]["pendingCorrection"].is_null());
+    assert_eq!(response["market"]["editHistory"][0]["correctedOutcome"], 1);
+    assert_eq!(response["market"]["editHistory"][0]["previousOutcomeName"], "Hmoe");
+}
+
+/// A creator trading in their own market earns no fee rebate and half the XP, and a market
+/// they traded most of counts against their quality score
+#[tokio::test(flavor = "multi_thread")]
+async fn test_self_trading() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let buy = |tokens| Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(tokens),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it snow?".to_string(),
+                        description: "Snow in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy(100));
+        })
+        .await;
+    let fees_query = "query { market(marketId: 0) { feesCollected creatorFeesEarned } }";
+    let response = query(&chain, application_id, fees_query).await;
+    assert_ne!(response["market"]["feesCollected"], "0.");
+    assert_eq!(response["market"]["creatorFeesEarned"], "0.");
+
+    // Bob's trade pays Alice her share of the fee
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, buy(10));
+        })
+        .await;
+    let response = query(&chain, application_id, fees_query).await;
+    assert_ne!(response["market"]["creatorFeesEarned"], "0.");
+    let xp_query = format!(
+        "query {{ alice: player(playerId: \"{alice}\") {{ experiencePoints }} \
+            bob: player(playerId: \"{bob}\") {{ experiencePoints }} }}"
+    );
+    let response = query(&chain, application_id, &xp_query).await;
+    assert_eq!(
+        response["alice"]["experiencePoints"].as_u64().unwrap() + 5,
+        response["bob"]["experiencePoints"].as_u64().unwrap()
+    );
+
+    // Alice traded most of the volume, so the resolved market counts as wash-traded
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarket {
+                    market_id: 0,
+                    ranked_outcomes: vec![0],
+                    evidence: None,
+                },
+            );
+        })
+        .await;
+    let response = query(
+        &chain,
+        application_id,
+        &format!("query {{ creatorStats(playerId: \"{alice}\") {{ washTradedMarkets qualityScore }} }}"),
+    )
+    .await;
+    assert_eq!(response["creatorStats"]["washTradedMarkets"], 1);
+    assert!(response["creatorStats"]["qualityScore"].as_u64().unwrap() < 5_000);
+}
+
+/// An approved bot quotes both sides of an outcome within its limits, a player trades against
+/// each side, and the bot gets part of its curve fees back and pulls what's left of its quote
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_maker_quotes() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        admin: Some(alice),
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(100),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will it rain?".to_string(),
+                        description: "Rain in the city tomorrow".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy());
+        })
+        .await;
+
+    let bob_key = AccountSecretKey::generate();
+    let bob = switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Bot".to_string()),
+                },
+            );
+        })
+        .await;
+
+    // Only the admin approves bots, with half of their curve fees rebated
+    let approve = || Operation::ApproveMarketMaker {
+        player: bob,
+        rebate_bps: 5_000,
+        limits: MarketMakerLimits {
+            max_quote_shares: Amount::from_tokens(50),
+            max_bid_exposure: Amount::from_tokens(20),
+            max_open_quotes: 2,
+        },
+    };
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, approve());
+        })
+        .await;
+    let maker_query =
+        format!("query {{ marketMaker(playerId: \"{bob}\") {{ approved openQuotes bidExposure rebatesEarned }} }}");
+    assert!(query(&chain, application_id, &maker_query).await["marketMaker"].is_null());
+    switch_player(&validator, &mut chain, alice_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, approve());
+        })
+        .await;
+
+    // The 0.5 fee on the bot's buy is half rebated
+    switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, buy());
+        })
+        .await;
+    let response = query(&chain, application_id, &maker_query).await;
+    assert_eq!(response["marketMaker"]["approved"], true);
+    assert_eq!(response["marketMaker"]["rebatesEarned"], "0.25");
+
+    // Crossed quotes and quotes beyond the bot's limits are rejected as a whole batch
+    let quote = |outcome_id, bid_price, bid_shares, ask_price, ask_shares| TwoSidedQuote {
+        market_id: 0,
+        outcome_id,
+        bid_price: Amount::from_millis(bid_price),
+        bid_shares: Amount::from_tokens(bid_shares),
+        ask_price: Amount::from_millis(ask_price),
+        ask_shares: Amount::from_tokens(ask_shares),
+    };
+    let post = |quotes| Operation::PostTwoSidedQuotes { quotes };
+    let bob_balance = player_balance(&chain, application_id, bob).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, post(vec![quote(0, 2_000, 10, 1_000, 10)]))
+                .with_operation(application_id, post(vec![quote(0, 500, 60, 2_000, 10)]))
+                .with_operation(
+                    application_id,
+                    post(vec![quote(0, 500, 10, 2_000, 10), quote(1, 1_000, 30, 0, 0)]),
+                )
+                .with_operation(application_id, post(vec![quote(0, 500, 10, 2_000, 10)]));
+        })
+        .await;
+    let quotes_query = "query { botQuotes(marketId: 0) { id bidShares bidEscrow askShares open } }";
+    let response = query(&chain, application_id, quotes_query).await;
+    let quotes = response["botQuotes"].as_array().unwrap();
+    assert_eq!(quotes.len(), 1);
+    assert_eq!(quotes[0]["id"], 0);
+    assert_eq!(quotes[0]["bidEscrow"], "5.");
+    let response = query(&chain, application_id, &maker_query).await;
+    assert_eq!(response["marketMaker"]["openQuotes"], 1);
+    assert_eq!(response["marketMaker"]["bidExposure"], "5.");
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        bob_balance.saturating_sub(Amount::from_tokens(5))
+    );
+
+    // Alice sells 4 shares into the bid and lifts the whole ask at the quoted prices only,
+    // paying for the ask out of her daily loss limit
+    let fill = |side, shares, price| Operation::FillBotQuote {
+        market_id: 0,
+        quote_id: 0,
+        side,
+        shares: Amount::from_tokens(shares),
+        price_per_share: Amount::from_millis(price),
+    };
+    switch_player(&validator, &mut chain, alice_key).await;
+    let alice_balance = player_balance(&chain, application_id, alice).await;
+    let spent_query = format!("query {{ player(playerId: \"{alice}\") {{ riskControls {{ spentToday }} }} }}");
+    let spent_today = |response: Value| -> Amount {
+        response["player"]["riskControls"]["spentToday"].as_str().unwrap().parse().unwrap()
+    };
+    let spent_before = spent_today(query(&chain, application_id, &spent_query).await);
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, fill(QuoteSide::Bid, 4, 400))
+                .with_operation(application_id, fill(QuoteSide::Bid, 4, 500))
+                .with_operation(application_id, fill(QuoteSide::Ask, 11, 2_000))
+                .with_operation(application_id, fill(QuoteSide::Ask, 10, 2_000));
+        })
+        .await;
+    assert_eq!(
+        player_balance(&chain, application_id, alice).await,
+        alice_balance.saturating_add(Amount::from_tokens(2)).saturating_sub(Amount::from_tokens(20))
+    );
+    let response = query(&chain, application_id, quotes_query).await;
+    let quote_state = &response["botQuotes"][0];
+    assert_eq!(quote_state["bidShares"], "6.");
+    assert_eq!(quote_state["bidEscrow"], "3.");
+    assert_eq!(quote_state["askShares"], "0.");
+    assert_eq!(quote_state["open"], true);
+    assert_eq!(
+        spent_today(query(&chain, application_id, &spent_query).await),
+        spent_before.saturating_add(Amount::from_tokens(20))
+    );
+
+    // Once revoked the bot can't quote again, but can still pull its open quote
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::RevokeMarketMaker { player: bob });
+        })
+        .await;
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, post(vec![quote(0, 500, 10, 2_000, 10)]))
+                .with_operation(application_id, post(vec![quote(0, 0, 0, 0, 0)]));
+        })
+        .await;
+    let response = query(&chain, application_id, quotes_query).await;
+    assert_eq!(response["botQuotes"], serde_json::json!([]));
+    let response = query(&chain, application_id, &maker_query).await;
+    assert_eq!(response["marketMaker"]["approved"], false);
+    assert_eq!(response["marketMaker"]["openQuotes"], 0);
+    assert_eq!(response["marketMaker"]["bidExposure"], "0.");
+    // The ask's 20 tokens and the unused 3 of the bid came back to the bot
+    assert_eq!(
+        player_balance(&chain, application_id, bob).await,
+        bob_balance.saturating_add(Amount::from_tokens(18))
+    );
+}
+
+/// Resolvers who vote together on a contested market are flagged for moderators
+#[tokio::test(flavor = "multi_thread")]
+async fn test_collusion_flags() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice_key = chain.key_pair().copy();
+    let alice = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        collusion: CollusionConfig {
+            min_shared_votes: 1,
+            agreement_percent: 100,
+            weight_percent: 50,
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+    let register = |name: &str| Operation::RegisterPlayer {
+        display_name: Some(name.to_string()),
+    };
+    let stake = || Operation::RegisterAsResolver {
+        stake: Amount::from_tokens(100),
+    };
+    let vote = |outcome_id| Operation::VoteOnOutcome {
+        market_id: 0,
+        outcome_id,
+        evidence: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register("Alice"))
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Derby winner".to_string(),
+                        description: "Which side takes the derby".to_string(),
+                        outcome_names: vec!["Home".to_string(), "Away".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, stake());
+        })
+        .await;
+
+    // Carol votes against Alice and Bob, so the market is contested
+    let carol_key = AccountSecretKey::generate();
+    switch_player(&validator, &mut chain, carol_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, register("Carol"))
+                .with_operation(application_id, stake());
+        })
+        .await;
+    let bob = switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, register("Bob"))
+                .with_operation(application_id, stake())
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 })
+                .with_operation(application_id, vote(0));
+        })
+        .await;
+    switch_player(&validator, &mut chain, carol_key).await;
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, vote(1));
+        })
+        .await;
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, vote(0))
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 });
+        })
+        .await;
+
+    let response = query(
+        &chain,
+        application_id,
+        "query { market(marketId: 0) { status } \
+            collusionFlags { first second sharedVotes agreedVotes } collusionClusters }",
+    )
+    .await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    let flags = response["collusionFlags"].as_array().unwrap();
+    assert_eq!(flags.len(), 1);
+    let mut pair = [flags[0]["first"].as_str().unwrap(), flags[0]["second"].as_str().unwrap()];
+    pair.sort();
+    let (alice, bob) = (alice.to_string(), bob.to_string());
+    let mut expected = [alice.as_str(), bob.as_str()];
+    expected.sort();
+    assert_eq!(pair, expected);
+    assert_eq!(flags[0]["sharedVotes"], 1);
+    assert_eq!(flags[0]["agreedVotes"], 1);
+    assert_eq!(response["collusionClusters"].as_array().unwrap().len(), 1);
+    assert_eq!(response["collusionClusters"][0].as_array().unwrap().len(), 2);
+}
+
+/// Flags during the review window pause a market until its creator clarifies it
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ambiguity_review() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice_key = chain.key_pair().copy();
+    let config = GameConfig {
+        ambiguity_review_seconds: 600,
+        ambiguity_pause_weight: 100,
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+
+    // Nobody trades during the review window
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Big match".to_string(),
+                        description: "Who wins".to_string(),
+                        outcome_names: vec!["Home".to_string(), "Away".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy());
+        })
+        .await;
+
+    // Bob's flag carries enough reputation to pause the market
+    switch_player(&validator, &mut chain, AccountSecretKey::generate()).await;
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::FlagAmbiguousMarket {
+                        market_id: 0,
+                        reason: "Which match?".to_string(),
+                    },
+                );
+        })
+        .await;
+    let market_query = "query { market(marketId: 0) { totalLiquidity pausedAt reviewEnd ambiguityFlags { reason weight } } }";
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["totalLiquidity"], "0.");
+    assert!(!response["market"]["pausedAt"].is_null());
+    assert_eq!(response["market"]["ambiguityFlags"][0]["weight"], 100);
+
+    // Paused markets stay closed after the review window
+    validator.clock().add(TimeDelta::from_secs(601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, buy());
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert_eq!(response["market"]["totalLiquidity"], "0.");
+
+    // Alice has to clarify before resuming, which restarts the review window
+    let bob_key = chain.key_pair().copy();
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ResumeMarket { market_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert!(!response["market"]["pausedAt"].is_null());
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::UpdateMarket {
+                        market_id: 0,
+                        title: None,
+                        description: Some("Who wins Saturday's cup final".to_string()),
+                        end_time: None,
+                    },
+                )
+                .with_operation(application_id, Operation::ResumeMarket { market_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert!(response["market"]["pausedAt"].is_null());
+    assert_eq!(response["market"]["ambiguityFlags"], serde_json::json!([]));
+
+    switch_player(&validator, &mut chain, bob_key).await;
+    validator.clock().add(TimeDelta::from_secs(601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, buy());
+        })
+        .await;
+    let response = query(&chain, application_id, market_query).await;
+    assert_ne!(response["market"]["totalLiquidity"], "0.");
+}
+
+/// A player dropped from a market's allowlist can no longer buy but can still sell what
+/// they hold
+#[tokio::test(flavor = "multi_thread")]
+async fn test_removed_from_allowlist_can_exit() {
+    let (validator, mut chain, application_id) = setup().await;
+    let alice_key = chain.key_pair().copy();
+    let bob_key = AccountSecretKey::generate();
+    let bob = AccountOwner::from(bob_key.public());
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(50),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Office pool".to_string(),
+                        description: "Who wins the office pool".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: Some(MarketVisibility::Whitelist),
+                        allowed_guild: None,
+                        allowlist: Some(vec![bob]),
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    // Bob buys while on the allowlist
+    switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(application_id, buy());
+        })
+        .await;
+    let shares_query = "query { market(marketId: 0) { outcomes { totalShares } } }";
+    let response = query(&chain, application_id, shares_query).await;
+    let bought: Amount = response["market"]["outcomes"][0]["totalShares"].as_str().unwrap().parse().unwrap();
+    assert!(bought > Amount::ZERO);
+
+    // Alice drops him from the allowlist
+    switch_player(&validator, &mut chain, alice_key).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::UpdateMarketAllowlist {
+                    market_id: 0,
+                    add: vec![],
+                    remove: vec![bob],
+                },
+            );
+        })
+        .await;
+
+    // He can't add to the position, but selling it pays him out
+    switch_player(&validator, &mut chain, bob_key).await;
+    let balance = player_balance(&chain, application_id, bob).await;
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, buy());
+        })
+        .await;
+    assert_eq!(player_balance(&chain, application_id, bob).await, balance);
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::SellShares {
+                    market_id: 0,
+                    outcome_id: 0,
+                    shares: bought,
+                    min_price_per_share: Amount::ZERO,
+                    valid_until: None,
+                },
+            );
+        })
+        .await;
+    assert!(player_balance(&chain, application_id, bob).await > balance);
+    let response = query(&chain, application_id, shares_query).await;
+    assert_eq!(response["market"]["outcomes"][0]["totalShares"], "0.");
+}
+
+/// Parlays pay the combined odds less the house edge from the treasury, only when every leg
+/// wins, and can't include markets the bettor controls
+#[tokio::test(flavor = "multi_thread")]
+async fn test_parlays() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut chain = validator.new_chain().await;
+    let alice_key = chain.key_pair().copy();
+    let config = GameConfig {
+        claim_window_seconds: 60,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let create = |title: &str, duration_seconds| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Parlay leg".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let parlay = |outcome_ids: [u32; 2], tokens| Operation::CreateParlay {
+        legs: vec![
+            ParlayLeg { market_id: 1, outcome_id: outcome_ids[0] },
+            ParlayLeg { market_id: 2, outcome_id: outcome_ids[1] },
+        ],
+        stake: Amount::from_tokens(tokens),
+    };
+    let resolve = |market_id, outcome_id| Operation::ResolveMarket {
+        market_id,
+        ranked_outcomes: vec![outcome_id],
+        evidence: None,
+    };
+    let parlays_query = "query { won: parlay(parlayId: 0) { odds potentialPayout status } \
+        lost: parlay(parlayId: 1) { status } rejected: parlay(parlayId: 2) { status } \
+        supplyMetrics { totalSupply treasury } }";
+    let treasury = |response: &Value| -> Amount { response["supplyMetrics"]["treasury"].as_str().unwrap().parse().unwrap() };
+
+    // Alice can't bet on her own markets
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Alice".to_string()),
+                    },
+                )
+                .with_operation(application_id, create("Swept", 300))
+                .with_operation(application_id, create("First leg", 3600))
+                .with_operation(application_id, create("Second leg", 3600))
+                .with_operation(application_id, parlay([0, 1], 10));
+        })
+        .await;
+
+    // Bob's parlay is rejected while the treasury is empty
+    let bob_key = AccountSecretKey::generate();
+    switch_player(&validator, &mut chain, bob_key.copy()).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bob".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(application_id, parlay([0, 1], 10));
+        })
+        .await;
+    let response = query(&chain, application_id, parlays_query).await;
+    assert!(response["won"].is_null());
+
+    // Bob's unclaimed winnings fund the treasury
+    switch_player(&validator, &mut chain, alice_key.copy()).await;
+    validator.clock().add(TimeDelta::from_secs(301));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, resolve(0, 0));
+        })
+        .await;
+    validator.clock().add(TimeDelta::from_secs(61));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::SweepExpiredWinnings { market_id: 0 });
+        })
+        .await;
+    let funded = query(&chain, application_id, parlays_query).await;
+    let funded_treasury = treasury(&funded);
+    assert!(funded_treasury > Amount::from_tokens(56));
+
+    // Even legs pay 4x, less the 5% edge; the last parlay would need more than is left
+    switch_player(&validator, &mut chain, bob_key).await;
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, parlay([0, 1], 10))
+                .with_operation(application_id, parlay([1, 1], 10))
+                .with_operation(application_id, parlay([0, 0], 100));
+        })
+        .await;
+    let response = query(&chain, application_id, parlays_query).await;
+    assert_eq!(response["won"]["odds"], "3.8");
+    assert_eq!(response["won"]["potentialPayout"], "38.");
+    assert_eq!(response["won"]["status"], "OPEN");
+    assert!(response["rejected"].is_null());
+    assert_eq!(treasury(&response), funded_treasury.saturating_sub(Amount::from_tokens(56)));
+
+    switch_player(&validator, &mut chain, alice_key).await;
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, resolve(1, 0))
+                // The second parlay already lost its first leg; the first can't settle yet
+                .with_operation(application_id, Operation::SettleParlay { parlay_id: 1 })
+                .with_operation(application_id, Operation::SettleParlay { parlay_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, parlays_query).await;
+    assert_eq!(response["won"]["status"], "OPEN");
+    assert_eq!(response["lost"]["status"], "LOST");
+
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, resolve(2, 1))
+                .with_operation(application_id, Operation::SettleParlay { parlay_id: 0 });
+        })
+        .await;
+    let response = query(&chain, application_id, parlays_query).await;
+    assert_eq!(response["won"]["status"], "WON");
+    // The lost stake went to the treasury and the winnings came out of it; nothing was minted
+    assert_eq!(treasury(&response), funded_treasury.saturating_sub(Amount::from_tokens(18)));
+    assert_eq!(response["supplyMetrics"]["totalSupply"], funded["supplyMetrics"]["totalSupply"]);
+}
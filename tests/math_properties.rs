@@ -0,0 +1,86 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Property-based tests for the AMM pricing invariants.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use predictive_manager::math::{mul_div, BondingCurve, ONE};
+use proptest::prelude::*;
+
+/// Curves with a base price between 0.01 and 100 tokens, a cap up to 10x the base and a
+/// depth between 1 and 1,000,000 shares
+fn curve() -> impl Strategy<Value = BondingCurve> {
+    (ONE / 100..100 * ONE, 1u128..=10, ONE..1_000_000 * ONE).prop_map(
+        |(base_price, cap_multiplier, depth)| {
+            BondingCurve::new(base_price, base_price * cap_multiplier, depth)
+        },
+    )
+}
+
+/// Trade sizes up to one million tokens (or shares)
+fn quantity() -> impl Strategy<Value = u128> {
+    0..1_000_000 * ONE
+}
+
+proptest! {
+    #[test]
+    fn mul_div_matches_narrow_arithmetic(a in any::<u64>(), b in any::<u64>(), c in 1..u64::MAX) {
+        let expected = (a as u128) * (b as u128) / (c as u128);
+        prop_assert_eq!(mul_div(a as u128, b as u128, c as u128), expected);
+    }
+
+    #[test]
+    fn mul_div_handles_wide_products(a in any::<u128>(), b in any::<u128>()) {
+        // (a * b) / b == a whenever b is non-zero, even though a * b overflows u128
+        prop_assume!(b > 0);
+        prop_assert_eq!(mul_div(a, b, b), a);
+    }
+
+    #[test]
+    fn price_stays_within_base_and_cap(curve in curve(), supply in 0..10_000_000 * ONE) {
+        let price = curve.price_at(supply);
+        prop_assert!(price >= curve.base_price);
+        prop_assert!(price <= curve.price_cap);
+    }
+
+    #[test]
+    fn shares_never_cost_more_than_paid(curve in curve(), supply in quantity(), amount in quantity()) {
+        let shares = curve.shares_for_amount(supply, amount);
+        prop_assert!(curve.buy_cost(supply, shares) <= amount);
+    }
+
+    #[test]
+    fn buy_then_sell_never_profits(curve in curve(), supply in quantity(), amount in quantity()) {
+        let shares = curve.shares_for_amount(supply, amount);
+        let value = curve.sell_value(supply + shares, shares);
+        // Trading fees are taken from the proceeds, which can only lower the return further
+        let fee = value / 200;
+        prop_assert!(value - fee <= amount);
+    }
+
+    #[test]
+    fn payouts_never_exceed_liquidity(
+        curve in curve().prop_filter("shares must cost at least one token", |curve| curve.base_price >= ONE),
+        trades in prop::collection::vec((0usize..4, quantity(), any::<bool>()), 1..20),
+    ) {
+        let mut supplies = [0u128; 4];
+        let mut liquidity = 0u128;
+        for (outcome, quantity, is_buy) in trades {
+            if is_buy {
+                let shares = curve.shares_for_amount(supplies[outcome], quantity);
+                supplies[outcome] += shares;
+                liquidity += quantity;
+            } else {
+                let shares = quantity.min(supplies[outcome]);
+                let value = curve.sell_value(supplies[outcome], shares);
+                supplies[outcome] -= shares;
+                liquidity -= value;
+            }
+        }
+        // A winning share pays out one token, whichever outcome wins
+        for supply in supplies {
+            prop_assert!(supply <= liquidity);
+        }
+    }
+}
@@ -0,0 +1,234 @@
+// Copyright (c) Zefchain Labs, Inc.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Multi-chain tests: market chains syncing with the home chain.
+
+#![cfg(not(target_arch = "wasm32"))]
+
+use linera_sdk::{
+    linera_base_types::{AccountOwner, Amount},
+    test::{QueryOutcome, TestValidator},
+};
+use predictive_manager::{
+    AnnouncementSeverity, AttestationClaim, GameConfig, Operation, PredictiveManagerAbi, ResolutionMethod,
+};
+
+/// A market chain receives the home config and reports player stats back home
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_chain_syncs_with_home_chain() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut home_chain = validator.new_chain().await;
+    let market_chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        initial_player_tokens: Amount::from_tokens(500),
+        ..GameConfig::default()
+    };
+    let application_id = home_chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Join the network and pick up the home chain's config
+    market_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::JoinNetwork);
+        })
+        .await;
+    home_chain.handle_received_messages().await;
+    market_chain.handle_received_messages().await;
+
+    // Trade on the market chain
+    let trader = AccountOwner::from(market_chain.public_key());
+    market_chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Remote".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Remote Market".to_string(),
+                        description: "Lives on a market chain".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    let query = format!(
+        "query {{ player(playerId: \"{trader}\") {{ displayName tokenBalance totalSpent marketsParticipated }} }}"
+    );
+    let QueryOutcome { response, .. } = market_chain
+        .graphql_query(application_id, query.as_str())
+        .await;
+    assert!(response["player"]["tokenBalance"]
+        .as_str()
+        .unwrap()
+        .starts_with("39"));
+
+    // The home chain learns about the player and the trade
+    home_chain.handle_received_messages().await;
+    let QueryOutcome { response, .. } = home_chain
+        .graphql_query(application_id, query.as_str())
+        .await;
+    let player = &response["player"];
+    assert_eq!(player["displayName"], "Remote");
+    assert_eq!(player["totalSpent"], "10.");
+    assert_eq!(player["marketsParticipated"], 1);
+    // Achievements are awarded on the home chain: "First Steps" pays 50 tokens there
+    assert_eq!(player["tokenBalance"], "50.");
+
+    // A chain that never joined the network can't write stats on the home chain
+    let outsider_chain = validator.new_chain().await;
+    let outsider = AccountOwner::from(outsider_chain.public_key());
+    outsider_chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Outsider".to_string()),
+                },
+            );
+        })
+        .await;
+    home_chain.handle_received_messages().await;
+    let query = format!("query {{ player(playerId: \"{outsider}\") {{ displayName }} }}");
+    let QueryOutcome { response, .. } = home_chain
+        .graphql_query(application_id, query.as_str())
+        .await;
+    assert!(response["player"].is_null());
+}
+
+/// An attestation issued on one chain is checked and kept by the chain it is sent to
+#[tokio::test(flavor = "multi_thread")]
+async fn test_attestation_sent_to_another_chain() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut home_chain = validator.new_chain().await;
+    let airdrop_chain = validator.new_chain().await;
+    let application_id = home_chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+
+    // Level 1 is reached on registration; level 5 isn't, so only one attestation is issued
+    home_chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None })
+                .with_operation(
+                    application_id,
+                    Operation::RequestAttestation {
+                        claim: AttestationClaim::ReachedLevel { level: 5 },
+                        recipient: Some(airdrop_chain.id()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::RequestAttestation {
+                        claim: AttestationClaim::ReachedLevel { level: 1 },
+                        recipient: Some(airdrop_chain.id()),
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = home_chain
+        .graphql_query(application_id, "query { attestation(attestationId: 0) { claim chainId hash } }")
+        .await;
+    let attestation = &response["attestation"];
+    assert_eq!(attestation["claim"]["ReachedLevel"]["level"], 1);
+    assert_eq!(attestation["chainId"], home_chain.id().to_string());
+    let QueryOutcome { response, .. } = home_chain
+        .graphql_query(application_id, "query { attestation(attestationId: 1) { hash } }")
+        .await;
+    assert!(response["attestation"].is_null());
+
+    airdrop_chain.handle_received_messages().await;
+    let hash = attestation["hash"].as_str().unwrap();
+    let query = format!("query {{ receivedAttestation(hash: \"{hash}\") {{ id chainId }} }}");
+    let QueryOutcome { response, .. } = airdrop_chain
+        .graphql_query(application_id, query.as_str())
+        .await;
+    assert_eq!(response["receivedAttestation"]["id"], 0);
+    assert_eq!(response["receivedAttestation"]["chainId"], home_chain.id().to_string());
+}
+
+/// The admin's announcement on the home chain shows on market chains until it is cleared
+#[tokio::test(flavor = "multi_thread")]
+async fn test_announcement_reaches_market_chains() {
+    let (validator, module_id) =
+        TestValidator::with_current_module::<PredictiveManagerAbi, (), GameConfig>().await;
+    let mut home_chain = validator.new_chain().await;
+    let market_chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        admin: Some(AccountOwner::from(home_chain.public_key())),
+        ..GameConfig::default()
+    };
+    let application_id = home_chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    market_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::JoinNetwork);
+        })
+        .await;
+    home_chain.handle_received_messages().await;
+    market_chain.handle_received_messages().await;
+
+    home_chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::SetAnnouncement {
+                    text: "Maintenance tonight at 22:00 UTC".to_string(),
+                    severity: AnnouncementSeverity::Warning,
+                    expires_at: None,
+                },
+            );
+        })
+        .await;
+    market_chain.handle_received_messages().await;
+
+    let query = "query { systemStatus { emergencyMode announcement { text severity } } }";
+    let QueryOutcome { response, .. } = market_chain.graphql_query(application_id, query).await;
+    let status = &response["systemStatus"];
+    assert_eq!(status["emergencyMode"], false);
+    assert_eq!(status["announcement"]["text"], "Maintenance tonight at 22:00 UTC");
+    assert_eq!(status["announcement"]["severity"], "WARNING");
+
+    home_chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::ClearAnnouncement);
+        })
+        .await;
+    market_chain.handle_received_messages().await;
+    let QueryOutcome { response, .. } = market_chain.graphql_query(application_id, query).await;
+    assert!(response["systemStatus"]["announcement"].is_null());
+}
@@ -5,11 +5,16 @@
 
 #![cfg(not(target_arch = "wasm32"))]
 
+use std::collections::BTreeMap;
+
 use linera_sdk::{
-    linera_base_types::Amount,
+    linera_base_types::{AccountOwner, Amount, CryptoHash, TimeDelta, Timestamp},
     test::{QueryOutcome, TestValidator},
 };
-use predictive_manager::{GameConfig, Operation, ResolutionMethod};
+use predictive_manager::{
+    ChestDrop, ChestTable, CreatorMarketCap, CurveParams, Evidence, FeeSchedule, GameConfig, ItemKind, MarketMetadata, MarketSpec, MarketViewCount, MarketVisibility,
+    Operation, OutcomeDisplay, ResolutionMethod, TradeXp,
+};
 
 /// Test player registration and basic functionality
 #[tokio::test(flavor = "multi_thread")]
@@ -92,6 +97,14 @@ async fn test_market_creation() {
                     outcome_names: vec!["Option A".to_string(), "Option B".to_string()],
                     duration_seconds: 3600, // 1 hour
                     resolution_method: ResolutionMethod::OracleVoting,
+                    payout_weights: None,
+                    visibility: None,
+                    allowed_guild: None,
+                    allowlist: None,
+                    metadata: None,
+                    allow_early_close: None,
+                    bootstrap_seconds: None,
+                    blind_finish_seconds: None,
                 },
             );
         })
@@ -148,6 +161,14 @@ async fn test_buy_shares() {
                     outcome_names: vec!["Win".to_string(), "Lose".to_string()],
                     duration_seconds: 3600,
                     resolution_method: ResolutionMethod::OracleVoting,
+                    payout_weights: None,
+                    visibility: None,
+                    allowed_guild: None,
+                    allowlist: None,
+                    metadata: None,
+                    allow_early_close: None,
+                    bootstrap_seconds: None,
+                    blind_finish_seconds: None,
                 },
             );
         })
@@ -163,6 +184,7 @@ async fn test_buy_shares() {
                     outcome_id: 0,                               // First outcome
                     amount: Amount::from_tokens(50),             // 50 points
                     max_price_per_share: Amount::from_tokens(1), // Max 1 point per share
+                    valid_until: None,
                 },
             );
         })
@@ -326,6 +348,7 @@ async fn test_profile_updates() {
                 application_id,
                 Operation::UpdateProfile {
                     display_name: Some("UpdatedName".to_string()),
+                    privacy: None,
                 },
             );
         })
@@ -441,6 +464,14 @@ async fn test_market_creation_insufficient_balance() {
                     outcome_names: vec!["Option A".to_string(), "Option B".to_string()],
                     duration_seconds: 3600,
                     resolution_method: ResolutionMethod::OracleVoting,
+                    payout_weights: None,
+                    visibility: None,
+                    allowed_guild: None,
+                    allowlist: None,
+                    metadata: None,
+                    allow_early_close: None,
+                    bootstrap_seconds: None,
+                    blind_finish_seconds: None,
                 },
             );
         })
@@ -495,6 +526,14 @@ async fn test_buy_shares_insufficient_balance() {
                     outcome_names: vec!["Win".to_string(), "Lose".to_string()],
                     duration_seconds: 3600,
                     resolution_method: ResolutionMethod::OracleVoting,
+                    payout_weights: None,
+                    visibility: None,
+                    allowed_guild: None,
+                    allowlist: None,
+                    metadata: None,
+                    allow_early_close: None,
+                    bootstrap_seconds: None,
+                    blind_finish_seconds: None,
                 },
             );
         })
@@ -510,6 +549,7 @@ async fn test_buy_shares_insufficient_balance() {
                     outcome_id: 0,
                     amount: Amount::from_tokens(10000), // Way more than player has
                     max_price_per_share: Amount::from_tokens(1),
+                    valid_until: None,
                 },
             );
         })
@@ -563,6 +603,7 @@ async fn test_buy_shares_nonexistent_market() {
                     outcome_id: 0,
                     amount: Amount::from_tokens(50),
                     max_price_per_share: Amount::from_tokens(1),
+                    valid_until: None,
                 },
             );
         })
@@ -726,6 +767,14 @@ async fn test_sell_shares_without_position() {
                     outcome_names: vec!["Win".to_string(), "Lose".to_string()],
                     duration_seconds: 3600,
                     resolution_method: ResolutionMethod::OracleVoting,
+                    payout_weights: None,
+                    visibility: None,
+                    allowed_guild: None,
+                    allowlist: None,
+                    metadata: None,
+                    allow_early_close: None,
+                    bootstrap_seconds: None,
+                    blind_finish_seconds: None,
                 },
             );
         })
@@ -741,6 +790,7 @@ async fn test_sell_shares_without_position() {
                     outcome_id: 0,
                     shares: Amount::from_tokens(10),
                     min_price_per_share: Amount::from_tokens(1),
+                    valid_until: None,
                 },
             );
         })
@@ -792,6 +842,7 @@ async fn test_vote_nonexistent_market() {
                 Operation::VoteOnOutcome {
                     market_id: 999, // Non-existent market
                     outcome_id: 0,
+                    evidence: None,
                 },
             );
         })
@@ -907,3 +958,4635 @@ async fn test_admin_operations_unauthorized() {
         .expect("Failed to get total supply");
     assert!(!total_supply.is_empty());
 }
+
+/// Test resolving a creator-decided market with a ranked list of winners
+#[tokio::test(flavor = "multi_thread")]
+async fn test_ranked_outcome_resolution() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Register a player
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Bracketeer".to_string()),
+                },
+            );
+        })
+        .await;
+
+    // Create a "top 3 finishers" market paying 60% / 30% / 10%
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::CreateMarket {
+                    title: "Race Podium".to_string(),
+                    description: "Which runners finish on the podium".to_string(),
+                    outcome_names: vec![
+                        "Runner A".to_string(),
+                        "Runner B".to_string(),
+                        "Runner C".to_string(),
+                        "Runner D".to_string(),
+                    ],
+                    duration_seconds: 3600,
+                    resolution_method: ResolutionMethod::CreatorDecides,
+                    payout_weights: Some(vec![6000, 3000, 1000]),
+                    visibility: None,
+                    allowed_guild: None,
+                    allowlist: None,
+                    metadata: None,
+                    allow_early_close: None,
+                    bootstrap_seconds: None,
+                    blind_finish_seconds: None,
+                },
+            );
+        })
+        .await;
+
+    // Buy shares in the runner that finishes second
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::BuyShares {
+                    market_id: 0,
+                    outcome_id: 1,
+                    amount: Amount::from_tokens(50),
+                    max_price_per_share: Amount::from_tokens(100),
+                    valid_until: None,
+                },
+            );
+        })
+        .await;
+
+    let player = AccountOwner::from(chain.public_key());
+    let balance_query = format!("query {{ player(playerId: \"{player}\") {{ tokenBalance }} }}");
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*balance_query).await;
+    let before: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+
+    // Resolve after the market ends and claim the second-place payout
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let resolution_time = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(resolution_time)
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 0,
+                        ranked_outcomes: vec![2, 1, 0],
+                        evidence: None,
+                    },
+                )
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 });
+        })
+        .await;
+
+    // The whole ranking is recorded and the second-place holder is paid their weighted share
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { market(marketId: 0) { status winningOutcomes } }")
+        .await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    assert_eq!(response["market"]["winningOutcomes"], serde_json::json!([2, 1, 0]));
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*balance_query).await;
+    let after: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+    assert!(after > before);
+}
+
+/// Test a guild-only market rejecting players outside the guild
+#[tokio::test(flavor = "multi_thread")]
+async fn test_guild_only_market() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Register a player and found a guild
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("GuildCaptain".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateGuild {
+                        name: "Internal League".to_string(),
+                    },
+                );
+        })
+        .await;
+
+    // Create a market restricted to the guild
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::CreateMarket {
+                    title: "Guild Cup".to_string(),
+                    description: "Internal guild competition".to_string(),
+                    outcome_names: vec!["Team Red".to_string(), "Team Blue".to_string()],
+                    duration_seconds: 3600,
+                    resolution_method: ResolutionMethod::CreatorDecides,
+                    payout_weights: None,
+                    visibility: Some(MarketVisibility::GuildOnly),
+                    allowed_guild: Some(0),
+                    allowlist: None,
+                    metadata: None,
+                    allow_early_close: None,
+                    bootstrap_seconds: None,
+                    blind_finish_seconds: None,
+                },
+            );
+        })
+        .await;
+
+    // Trade, leave the guild, then try to trade again (should be denied)
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(application_id, Operation::LeaveGuild)
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    // Verify the system is still responsive
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { totalSupply }")
+        .await;
+    let total_supply = response["totalSupply"]
+        .as_str()
+        .expect("Failed to get total supply");
+    assert!(!total_supply.is_empty());
+}
+
+/// Test market metadata being stored and returned through GraphQL
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_metadata() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Register a player and create a market with metadata
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Curator".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Final Score".to_string(),
+                        description: "Who wins the final".to_string(),
+                        outcome_names: vec!["Home".to_string(), "Away".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: Some(MarketMetadata {
+                            image_uri: Some("ipfs://final-score.png".to_string()),
+                            source_url: Some("https://example.com/results".to_string()),
+                            locale: Some("en-US".to_string()),
+                            localized_titles: [("es".to_string(), "Resultado final".to_string())].into(),
+                            localized_descriptions: BTreeMap::new(),
+                            outcome_display: vec![OutcomeDisplay {
+                                emoji: Some("\u{1F3E0}".to_string()),
+                                color: Some("#1f77b4".to_string()),
+                                icon_uri: None,
+                            }],
+                        }),
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    // The metadata is returned with the market
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { title metadata { imageUri sourceUrl locale } \
+                outcomes { display { emoji color iconUri } } } }",
+        )
+        .await;
+    let metadata = &response["market"]["metadata"];
+    assert_eq!(response["market"]["title"], "Final Score");
+    let outcomes = &response["market"]["outcomes"];
+    assert_eq!(outcomes[0]["display"]["emoji"], "\u{1F3E0}");
+    assert_eq!(outcomes[0]["display"]["color"], "#1f77b4");
+    assert!(outcomes[1]["display"]["emoji"].is_null());
+    assert_eq!(metadata["imageUri"], "ipfs://final-score.png");
+    assert_eq!(metadata["sourceUrl"], "https://example.com/results");
+    assert_eq!(metadata["locale"], "en-US");
+
+    // Translated text replaces the default, falling back from a regional locale to its
+    // language and to the default text where there is no translation
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { \
+                spanish: market(marketId: 0, locale: \"es-MX\") { title description } \
+                french: market(marketId: 0, locale: \"fr\") { title } \
+            }",
+        )
+        .await;
+    assert_eq!(response["spanish"]["title"], "Resultado final");
+    assert_eq!(response["spanish"]["description"], "Who wins the final");
+    assert_eq!(response["french"]["title"], "Final Score");
+}
+
+/// Test the admin importing a batch of markets, skipping invalid ones
+#[tokio::test(flavor = "multi_thread")]
+async fn test_import_markets() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let admin = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        admin: Some(admin),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let spec = |title: &str, outcomes: &[&str]| MarketSpec {
+        title: title.to_string(),
+        description: "Curated at launch".to_string(),
+        outcome_names: outcomes.iter().map(|outcome| outcome.to_string()).collect(),
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::RegisterPlayer { display_name: None });
+        })
+        .await;
+    let balance_query = format!("query {{ player(playerId: \"{admin}\") {{ tokenBalance }} }}");
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*balance_query).await;
+    let balance = response["player"]["tokenBalance"].clone();
+
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::ImportMarkets {
+                    markets: vec![
+                        spec("Opening match", &["Home", "Away"]),
+                        spec("Only one outcome", &["Yes"]),
+                        spec("Top scorer", &["Alice", "Bob", "Carol"]),
+                    ],
+                },
+            );
+        })
+        .await;
+
+    // The invalid market is skipped and the admin pays nothing for the others
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { markets { id title } }")
+        .await;
+    assert_eq!(
+        response["markets"],
+        serde_json::json!([{ "id": 0, "title": "Opening match" }, { "id": 1, "title": "Top scorer" }])
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*balance_query).await;
+    assert_eq!(response["player"]["tokenBalance"], balance);
+}
+
+/// Test the admin translating achievements and players reading them in their locale
+#[tokio::test(flavor = "multi_thread")]
+async fn test_achievement_translations() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        admin: Some(AccountOwner::from(chain.public_key())),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::TranslateAchievement {
+                        achievement_id: 1,
+                        locale: "es".to_string(),
+                        name: "Primeros pasos".to_string(),
+                        description: "Haz tu primera predicción".to_string(),
+                    },
+                )
+                // Ignored: empty names aren't translations
+                .with_operation(
+                    application_id,
+                    Operation::TranslateAchievement {
+                        achievement_id: 2,
+                        locale: "es".to_string(),
+                        name: " ".to_string(),
+                        description: "Crea 5 mercados".to_string(),
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { \
+                english: achievements { name } \
+                spanish: achievements(locale: \"es\") { name description } \
+            }",
+        )
+        .await;
+    assert_eq!(response["english"][0]["name"], "First Steps");
+    assert_eq!(response["spanish"][0]["name"], "Primeros pasos");
+    assert_eq!(response["spanish"][0]["description"], "Haz tu primera predicción");
+    assert_eq!(response["spanish"][1]["name"], "Market Maker");
+}
+
+/// Test editing a market before the first trade
+#[tokio::test(flavor = "multi_thread")]
+async fn test_update_market() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Register a player and create a market with a typo
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Editor".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Who wins the fianl?".to_string(),
+                        description: "Championship final".to_string(),
+                        outcome_names: vec!["Home".to_string(), "Away".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    // Fix the title
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::UpdateMarket {
+                    market_id: 0,
+                    title: Some("Who wins the final?".to_string()),
+                    description: None,
+                    end_time: None,
+                },
+            );
+        })
+        .await;
+
+    // The new title is live and the old one is kept in the audit log
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { title editHistory { previousTitle } } }",
+        )
+        .await;
+    assert_eq!(response["market"]["title"], "Who wins the final?");
+    assert_eq!(
+        response["market"]["editHistory"][0]["previousTitle"],
+        "Who wins the fianl?"
+    );
+}
+
+/// Test closing a market early and exiting at cost basis
+#[tokio::test(flavor = "multi_thread")]
+async fn test_close_market_early() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Register a player, create an early-closable market and trade in it
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Organizer".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Will the match be called off?".to_string(),
+                        description: "Weather dependent".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 86400,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: Some(true),
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(20),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    // Close early, then exit the late position at cost basis
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::CloseMarketEarly { market_id: 0 })
+                .with_operation(application_id, Operation::ExitAtCostBasis { market_id: 0 });
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { status totalLiquidity positions votingStart votingEnd } }",
+        )
+        .await;
+    // Closing an oracle-voted market opens its voting period
+    assert_eq!(response["market"]["status"], "VOTING");
+    assert!(!response["market"]["votingStart"].is_null());
+    assert!(!response["market"]["votingEnd"].is_null());
+    assert_eq!(response["market"]["totalLiquidity"], "0.");
+    assert_eq!(response["market"]["positions"], serde_json::json!({}));
+}
+
+/// Test that markets are found by end time until they close or resolve
+#[tokio::test(flavor = "multi_thread")]
+async fn test_markets_by_end_time() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let application_id = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+
+    let market = |title: &str, duration_seconds| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Indexed by end time".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: Some(true),
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None })
+                .with_operation(application_id, market("Hour", 3600))
+                .with_operation(application_id, market("Ten minutes", 600))
+                .with_operation(application_id, market("Two hours", 7200));
+        })
+        .await;
+
+    let ending_soon = |within: u64| format!("query {{ endingSoon(withinSeconds: {within}) {{ id }} dueMarkets }}");
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*ending_soon(3600)).await;
+    assert_eq!(response["endingSoon"], serde_json::json!([{ "id": 1 }, { "id": 0 }]));
+    assert_eq!(response["dueMarkets"], serde_json::json!([]));
+
+    // Closing early takes a market out of the index
+    validator.clock().add(TimeDelta::from_secs(601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::CloseMarketEarly { market_id: 0 });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*ending_soon(7200)).await;
+    assert_eq!(response["endingSoon"], serde_json::json!([{ "id": 2 }]));
+    assert_eq!(response["dueMarkets"], serde_json::json!([1]));
+
+    // Resolving the due market leaves nothing to process
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarket {
+                    market_id: 1,
+                    ranked_outcomes: vec![0],
+                    evidence: None,
+                },
+            );
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*ending_soon(7200)).await;
+    assert_eq!(response["dueMarkets"], serde_json::json!([]));
+}
+
+/// Test that markets get unique slugs and can be looked up by them
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_slugs() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let application_id = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+
+    let market = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Linked by slug".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None })
+                .with_operation(application_id, market("Will it rain in Paris?"))
+                .with_operation(application_id, market("Will it rain in paris"));
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { \
+                first: market(marketId: 0) { slug } \
+                second: market(marketId: 1) { slug } \
+                marketBySlug(slug: \"will-it-rain-in-paris-2\") { id } \
+                missing: marketBySlug(slug: \"will-it-snow\") { id } \
+            }",
+        )
+        .await;
+    assert_eq!(response["first"]["slug"], "will-it-rain-in-paris");
+    assert_eq!(response["second"]["slug"], "will-it-rain-in-paris-2");
+    assert_eq!(response["marketBySlug"]["id"], 1);
+    assert!(response["missing"].is_null());
+}
+
+/// Test that part of every trading fee is burned
+#[tokio::test(flavor = "multi_thread")]
+async fn test_trading_fee_burn() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Register, create a market and trade 100 tokens (0.5 token fee, 20% burned)
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Burner".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Burn Test".to_string(),
+                        description: "Fee burn".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { supplyMetrics { totalSupply totalBurned } }",
+        )
+        .await;
+    assert_eq!(response["supplyMetrics"]["totalBurned"], "0.1");
+    // The creator traded their own market, so the platform takes the whole unburned 0.4
+    assert_eq!(response["supplyMetrics"]["totalSupply"], "1001.3");
+}
+
+/// Test that daily economy metrics record token sources and sinks
+#[tokio::test(flavor = "multi_thread")]
+async fn test_economy_metrics() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Register, create a market and trade 100 tokens (0.5 token fee, 20% burned)
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Burner".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Burn Test".to_string(),
+                        description: "Fee burn".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { economyMetrics(fromDay: 0, toDay: 6) { \
+                day registrationBonuses creationCosts tradingFees burned \
+                feeRewards totalSources totalSinks } }",
+        )
+        .await;
+    let days = response["economyMetrics"].as_array().unwrap();
+    assert_eq!(days.len(), 1);
+    assert_eq!(days[0]["day"], 0);
+    assert_eq!(days[0]["registrationBonuses"], "1000.");
+    assert_eq!(days[0]["creationCosts"], "100.");
+    assert_eq!(days[0]["tradingFees"], "0.5");
+    assert_eq!(days[0]["burned"], "0.1");
+    // 3 tokens from the creation fee plus 0.4 from the trading fee
+    assert_eq!(days[0]["feeRewards"], "3.4");
+    assert_eq!(days[0]["totalSources"], "1003.4");
+    assert_eq!(days[0]["totalSinks"], "100.6");
+}
+
+/// Test that guilds created at the same timestamp get distinct sequential IDs
+#[tokio::test(flavor = "multi_thread")]
+async fn test_sequential_guild_ids() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Found a guild, leave it and found another one in the same block
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Founder".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateGuild {
+                        name: "First".to_string(),
+                    },
+                )
+                .with_operation(application_id, Operation::LeaveGuild)
+                .with_operation(
+                    application_id,
+                    Operation::CreateGuild {
+                        name: "Second".to_string(),
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { first: guild(guildId: 0) { id name } second: guild(guildId: 1) { id name } }",
+        )
+        .await;
+    assert_eq!(response["first"]["name"], "First");
+    assert_eq!(response["second"]["id"], 1);
+    assert_eq!(response["second"]["name"], "Second");
+}
+
+/// Test that a buy quote matches the shares the trade then receives
+#[tokio::test(flavor = "multi_thread")]
+async fn test_trade_quotes() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Quoter".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Quote Test".to_string(),
+                        description: "Trade previews".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { quoteBuy(marketId: 0, outcomeId: 0, amount: \"100\") { \
+                shares fee priceBefore priceAfter priceImpactBps } }",
+        )
+        .await;
+    let quote = &response["quoteBuy"];
+    assert_eq!(quote["fee"], "0.5");
+    assert_eq!(quote["priceBefore"], "1.");
+    assert!(quote["priceImpactBps"].as_u64().unwrap() > 0);
+    let quoted_shares = quote["shares"].clone();
+
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::BuyShares {
+                    market_id: 0,
+                    outcome_id: 0,
+                    amount: Amount::from_tokens(100),
+                    max_price_per_share: Amount::from_tokens(100),
+                    valid_until: None,
+                },
+            );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { outcomes { totalShares currentPrice } } }",
+        )
+        .await;
+    let outcome = &response["market"]["outcomes"][0];
+    assert_eq!(outcome["totalShares"], quoted_shares);
+    assert_eq!(outcome["currentPrice"], quote["priceAfter"]);
+
+    // Selling everything back returns at most what was paid
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            format!(
+                "query {{ quoteSell(marketId: 0, outcomeId: 0, shares: {quoted_shares}) {{ amount }} }}"
+            ),
+        )
+        .await;
+    let proceeds: Amount = response["quoteSell"]["amount"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!(proceeds <= Amount::from_tokens(100));
+}
+
+/// Test that trades past their deadline are rejected
+#[tokio::test(flavor = "multi_thread")]
+async fn test_expired_order() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    validator.clock().add(TimeDelta::from_secs(60));
+    let now = validator.clock().current_time();
+    let deadline = Timestamp::from(now.micros() - 1_000_000);
+
+    // The first order expired a second ago, the second is still valid
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Latecomer".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Deadline Test".to_string(),
+                        description: "Expiring orders".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: Some(deadline),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        amount: Amount::from_tokens(20),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: Some(now),
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { totalLiquidity outcomes { totalShares } } }",
+        )
+        .await;
+    assert_eq!(response["market"]["totalLiquidity"], "19.9");
+    assert_eq!(response["market"]["outcomes"][0]["totalShares"], "0.");
+}
+
+/// Test that guild milestones unlock achievements paid into the shared pool
+#[tokio::test(flavor = "multi_thread")]
+async fn test_guild_achievements() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Treasurer".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateGuild {
+                        name: "Savers".to_string(),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::ContributeToGuild {
+                        amount: Amount::from_tokens(100),
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { guild(guildId: 0) { sharedPool totalContributed } \
+                guildAchievements(guildId: 0) { id name rewardTokens } }",
+        )
+        .await;
+    assert_eq!(response["guild"]["totalContributed"], "100.");
+    assert_eq!(response["guild"]["sharedPool"], "125.");
+    let achievements = response["guildAchievements"].as_array().unwrap();
+    assert_eq!(achievements.len(), 1);
+    assert_eq!(achievements[0]["name"], "Seed Fund");
+}
+
+/// Test that subscribed chains receive the market resolution
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_resolution_subscribers() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let subscriber = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // Register and create the market
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Publisher".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Subscribed".to_string(),
+                        description: "Resolution callbacks".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 300,
+                        resolution_method: ResolutionMethod::Automated,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    // Subscribing twice keeps a single entry and burns the fee once
+    let player = AccountOwner::from(chain.public_key());
+    let balance_query = format!("query {{ player(playerId: \"{player}\") {{ tokenBalance }} }}");
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*balance_query).await;
+    let before: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::SubscribeToMarket {
+                        market_id: 0,
+                        chain_id: subscriber.id(),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::SubscribeToMarket {
+                        market_id: 0,
+                        chain_id: subscriber.id(),
+                    },
+                );
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*balance_query).await;
+    let after: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+    assert_eq!(after, before.saturating_sub(GameConfig::default().market_subscription_fee));
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { marketSubscribers(marketId: 0) }")
+        .await;
+    assert_eq!(
+        response["marketSubscribers"],
+        serde_json::json!([subscriber.id().to_string()])
+    );
+
+    validator.clock().add(TimeDelta::from_secs(301));
+    let now = validator.clock().current_time();
+    let certificate = chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 });
+        })
+        .await;
+
+    // The resolution block carries a message for the subscriber chain
+    assert!(certificate.block().recipients().contains(&subscriber.id()));
+}
+
+/// Test that positions can't be offered or auctioned while the market is voting
+#[tokio::test(flavor = "multi_thread")]
+async fn test_positions_locked_while_voting() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None })
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Locked market".to_string(),
+                        description: "Positions freeze during the vote".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(50),
+                        max_price_per_share: Amount::from_tokens(1_000),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 })
+                .with_operation(
+                    application_id,
+                    Operation::CreateShareOffer {
+                        market_id: 0,
+                        outcome_id: 0,
+                        shares: Amount::ONE,
+                        price: Amount::ONE,
+                        buyer: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::StartPositionAuction {
+                        market_id: 0,
+                        outcome_id: 0,
+                        shares: Amount::ONE,
+                        start_price: Amount::from_tokens(2),
+                        floor_price: Amount::ONE,
+                        duration_seconds: 600,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { market(marketId: 0) { status shareOffers { shares } auctions { shares } } }")
+        .await;
+    assert_eq!(response["market"]["status"], "VOTING");
+    assert!(response["market"]["shareOffers"].as_array().unwrap().is_empty());
+    assert!(response["market"]["auctions"].as_array().unwrap().is_empty());
+}
+
+/// Test that per-outcome holder counts and open interest follow buys and sells
+#[tokio::test(flavor = "multi_thread")]
+async fn test_outcome_stats() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Holder".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Stats Test".to_string(),
+                        description: "Outcome stats".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        amount: Amount::from_tokens(50),
+                        max_price_per_share: Amount::from_tokens(50),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { currentHolders \
+                outcomes { holders openInterest totalShares } } }",
+        )
+        .await;
+    let market = &response["market"];
+    assert_eq!(market["currentHolders"], 1);
+    assert_eq!(market["outcomes"][0]["holders"], 1);
+    assert_eq!(market["outcomes"][1]["holders"], 1);
+    let open_interest: Amount = market["outcomes"][0]["openInterest"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+    // Rounding down in the curve can leave open interest an atto or so below the amount paid
+    assert!(open_interest <= Amount::from_tokens(100));
+    assert!(open_interest > Amount::from_tokens(99));
+    let no_shares: Amount = market["outcomes"][1]["totalShares"]
+        .as_str()
+        .unwrap()
+        .parse()
+        .unwrap();
+
+    // Selling every "No" share drops the holder and its open interest
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::SellShares {
+                    market_id: 0,
+                    outcome_id: 1,
+                    shares: no_shares,
+                    min_price_per_share: Amount::ZERO,
+                    valid_until: None,
+                },
+            );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { currentHolders outcomes { holders openInterest } } }",
+        )
+        .await;
+    let market = &response["market"];
+    assert_eq!(market["currentHolders"], 1);
+    assert_eq!(market["outcomes"][0]["holders"], 1);
+    assert_eq!(market["outcomes"][1]["holders"], 0);
+    assert_eq!(market["outcomes"][1]["openInterest"], "0.");
+}
+
+/// Test that a crowd consensus market resolves to the outcome with the highest closing price
+#[tokio::test(flavor = "multi_thread")]
+async fn test_crowd_consensus_resolution() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // "No" attracts more buying than "Yes", so it closes at a higher price
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Crowd".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Sentiment".to_string(),
+                        description: "Settled by the crowd".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 300,
+                        resolution_method: ResolutionMethod::CrowdConsensus,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(20),
+                        max_price_per_share: Amount::from_tokens(20),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        amount: Amount::from_tokens(60),
+                        max_price_per_share: Amount::from_tokens(60),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    validator.clock().add(TimeDelta::from_secs(301));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::TriggerResolution { market_id: 0 });
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { status winningOutcomes } }",
+        )
+        .await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    assert_eq!(response["market"]["winningOutcomes"], serde_json::json!([1]));
+}
+
+/// Test that scheduled config changes wait for their effective time and can be cancelled
+#[tokio::test(flavor = "multi_thread")]
+async fn test_config_timelock() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        admin: Some(AccountOwner::from(chain.public_key())),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config.clone(), vec![])
+        .await;
+
+    let effective_at = validator.clock().current_time().saturating_add(TimeDelta::from_secs(3600));
+    let proposed = GameConfig {
+        daily_login_reward: Amount::from_tokens(250),
+        ..config.clone()
+    };
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::ProposeConfigChange {
+                    config: proposed,
+                    effective_at,
+                },
+            );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { gameConfig { dailyLoginReward } \
+                pendingConfigChange { config { dailyLoginReward } } }",
+        )
+        .await;
+    assert_eq!(response["gameConfig"]["dailyLoginReward"], "10.");
+    assert_eq!(response["pendingConfigChange"]["config"]["dailyLoginReward"], "250.");
+
+    // The first operation after the effective time applies the change
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::RegisterPlayer {
+                    display_name: Some("Late".to_string()),
+                },
+            );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { gameConfig { dailyLoginReward } pendingConfigChange { effectiveAt } }",
+        )
+        .await;
+    assert_eq!(response["gameConfig"]["dailyLoginReward"], "250.");
+    assert!(response["pendingConfigChange"].is_null());
+
+    // A change with less than the config's notice is rejected
+    let config_query = "query { gameConfig { dailyLoginReward } pendingConfigChange { effectiveAt } }";
+    let effective_at = now.saturating_add(TimeDelta::from_secs(60));
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ProposeConfigChange {
+                    config: config.clone(),
+                    effective_at,
+                },
+            );
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, config_query).await;
+    assert!(response["pendingConfigChange"].is_null());
+
+    // A cancelled change never applies
+    let effective_at = now.saturating_add(TimeDelta::from_secs(3600));
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::ProposeConfigChange {
+                        config,
+                        effective_at,
+                    },
+                )
+                .with_operation(application_id, Operation::CancelConfigChange);
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, config_query).await;
+    assert_eq!(response["gameConfig"]["dailyLoginReward"], "250.");
+    assert!(response["pendingConfigChange"].is_null());
+}
+
+/// Test that emergency mode suspends trading and refunds open positions, including the
+/// guild's, at cost basis
+#[tokio::test(flavor = "multi_thread")]
+async fn test_emergency_withdraw() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let admin = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        admin: Some(admin),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(100),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Admin".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Emergency".to_string(),
+                        description: "Safety valve".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy())
+                .with_operation(application_id, Operation::CreateGuild { name: "Vault".to_string() })
+                .with_operation(application_id, Operation::ContributeToGuild { amount: Amount::from_tokens(60) })
+                .with_operation(
+                    application_id,
+                    Operation::GuildBuyShares {
+                        market_id: 0,
+                        outcome_id: 1,
+                        amount: Amount::from_tokens(40),
+                    },
+                )
+                .with_operation(application_id, Operation::SetEmergencyMode { enabled: true });
+        })
+        .await;
+
+    let balance_query = format!("query {{ player(playerId: \"{admin}\") {{ tokenBalance }} }}");
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*balance_query).await;
+    let before: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+
+    // Trading is suspended, withdrawing refunds the 99.5 tokens left after the 0.5 fee, and
+    // as the guild's only member the 20 tokens left in its pool and the 39.8 it invested
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, buy())
+                .with_operation(application_id, Operation::EmergencyWithdraw);
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*balance_query).await;
+    let after: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+    assert_eq!(after, before.saturating_add(Amount::from_millis(159_300)));
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { emergencyMode market(marketId: 0) { currentHolders totalLiquidity } guild(guildId: 0) { sharedPool } }",
+        )
+        .await;
+    assert_eq!(response["emergencyMode"], true);
+    assert_eq!(response["guild"]["sharedPool"], "0.");
+    assert_eq!(response["market"]["currentHolders"], 0);
+    assert_eq!(response["market"]["totalLiquidity"], "0.");
+
+    // Returning to the market afterwards doesn't count as joining it again
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::SetEmergencyMode { enabled: false })
+                .with_operation(application_id, buy());
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            format!(
+                "query {{ market(marketId: 0) {{ totalParticipants currentHolders }} \
+                    player(playerId: \"{admin}\") {{ marketsParticipated }} }}"
+            ),
+        )
+        .await;
+    assert_eq!(response["market"]["totalParticipants"], 1);
+    assert_eq!(response["market"]["currentHolders"], 1);
+    assert_eq!(response["player"]["marketsParticipated"], 1);
+}
+
+/// Test keyword search over market titles and descriptions, including after edits
+#[tokio::test(flavor = "multi_thread")]
+async fn test_search_markets() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let create = |title: &str, description: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: description.to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::OracleVoting,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Searcher".to_string()),
+                    },
+                )
+                .with_operation(application_id, create("Bitcoin above 100k?", "Crypto prices"))
+                .with_operation(application_id, create("Rain in Lagos", "Weather this week"))
+                .with_operation(application_id, create("Ethereum flips Bitcoin", "Crypto race"));
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { crypto: searchMarkets(query: \"CRYPTO bitcoin\") { id } \
+                weather: searchMarkets(query: \"weather\") { id } \
+                limited: searchMarkets(query: \"crypto\", limit: 1) { id } }",
+        )
+        .await;
+    assert_eq!(response["crypto"], serde_json::json!([{"id": 2}, {"id": 0}]));
+    assert_eq!(response["weather"], serde_json::json!([{"id": 1}]));
+    assert_eq!(response["limited"], serde_json::json!([{"id": 2}]));
+
+    // Editing the title re-indexes the market, keeping older markets in order
+    let retitle = |market_id: u64, title: &str| Operation::UpdateMarket {
+        market_id,
+        title: Some(title.to_string()),
+        description: None,
+        end_time: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, retitle(1, "Snow in Oslo"))
+                .with_operation(application_id, retitle(0, "Ethereum above 10k?"));
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { lagos: searchMarkets(query: \"lagos\") { id } \
+                oslo: searchMarkets(query: \"oslo weather\") { id } \
+                ethereum: searchMarkets(query: \"ethereum crypto\") { id } }",
+        )
+        .await;
+    assert_eq!(response["lagos"], serde_json::json!([]));
+    assert_eq!(response["oslo"], serde_json::json!([{"id": 1}]));
+    assert_eq!(response["ethereum"], serde_json::json!([{"id": 2}, {"id": 0}]));
+}
+
+/// Test that trade volume and fees are tracked per market and per creator
+#[tokio::test(flavor = "multi_thread")]
+async fn test_creator_stats() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let creator = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    // A 100 token buy pays a 0.5 token fee, 0.1 of it burned; the creator trading their own
+    // market gets no rebate, so their 0.2 share goes to the platform
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Creator".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Stats".to_string(),
+                        description: "Creator stats".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(100),
+                        max_price_per_share: Amount::from_tokens(100),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            format!(
+                "query {{ market(marketId: 0) {{ volume feesCollected creatorFeesEarned }} \
+                    creatorStats(playerId: \"{creator}\") {{ marketsCreated totalVolume \
+                    totalFeesGenerated tradingFeesEarned creationFeesEarned }} }}"
+            ),
+        )
+        .await;
+    let market = &response["market"];
+    assert_eq!(market["volume"], "100.");
+    assert_eq!(market["feesCollected"], "0.5");
+    assert_eq!(market["creatorFeesEarned"], "0.");
+    let stats = &response["creatorStats"];
+    assert_eq!(stats["marketsCreated"], 1);
+    assert_eq!(stats["totalVolume"], "100.");
+    assert_eq!(stats["totalFeesGenerated"], "0.5");
+    assert_eq!(stats["tradingFeesEarned"], "0.");
+    // 2% of the 100 token creation cost
+    assert_eq!(stats["creationFeesEarned"], "2.");
+}
+
+/// Test that participant ratings feed the creator's quality score
+#[tokio::test(flavor = "multi_thread")]
+async fn test_creator_quality_score() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let creator = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Creator".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Rated".to_string(),
+                        description: "Quality score".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 300,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(10),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { market(marketId: 0) { creatorQualityScore } }")
+        .await;
+    assert_eq!(response["market"]["creatorQualityScore"], 5000);
+
+    // Resolve, then rate twice: only the first rating counts
+    validator.clock().add(TimeDelta::from_secs(301));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 0,
+                        ranked_outcomes: vec![0],
+                        evidence: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::RateMarket {
+                        market_id: 0,
+                        thumbs_up: true,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::RateMarket {
+                        market_id: 0,
+                        thumbs_up: false,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            format!(
+                "query {{ market(marketId: 0) {{ thumbsUp thumbsDown creatorQualityScore }} \
+                    creatorStats(playerId: \"{creator}\") {{ marketsResolved disputedResolutions qualityScore }} }}"
+            ),
+        )
+        .await;
+    assert_eq!(response["market"]["thumbsUp"], 1);
+    assert_eq!(response["market"]["thumbsDown"], 0);
+    // Two of three smoothed observations are good on both parts
+    assert_eq!(response["market"]["creatorQualityScore"], 6666);
+    assert_eq!(response["creatorStats"]["marketsResolved"], 1);
+    assert_eq!(response["creatorStats"]["disputedResolutions"], 0);
+    assert_eq!(response["creatorStats"]["qualityScore"], 6666);
+}
+
+/// Test that markets take their curve from the config for their type
+#[tokio::test(flavor = "multi_thread")]
+async fn test_curve_params_per_market_type() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let steep = GameConfig {
+        quick_prediction_curve: CurveParams {
+            price_cap_multiplier: 5,
+            depth: Amount::from_tokens(10),
+            max_price_impact_bps: 0,
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), steep, vec![]).await;
+
+    let create = || Operation::CreateMarket {
+        title: "Steep market".to_string(),
+        description: "Prices move fast".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::OracleVoting,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Curvy".to_string()),
+                    },
+                )
+                .with_operation(application_id, create());
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { market(marketId: 0) { curve { priceCapMultiplier depth } } \
+                gameConfig { tournamentCurve { priceCapMultiplier depth } } }",
+        )
+        .await;
+    assert_eq!(response["market"]["curve"]["priceCapMultiplier"], 5);
+    assert_eq!(response["market"]["curve"]["depth"], "10.");
+    assert_eq!(response["gameConfig"]["tournamentCurve"]["priceCapMultiplier"], 3);
+}
+
+/// Test that loss limits and self-exclusion block buys and can't be loosened early
+#[tokio::test(flavor = "multi_thread")]
+async fn test_risk_controls() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let buy = |tokens| Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(tokens),
+        max_price_per_share: Amount::from_tokens(tokens),
+        valid_until: None,
+    };
+    // The second buy would take the day's total past the limit, and the limit can't be lifted today
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Careful".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Limits".to_string(),
+                        description: "Responsible trading".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3 * 24 * 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::SetLossLimit {
+                        per_day: Some(Amount::from_tokens(50)),
+                    },
+                )
+                .with_operation(application_id, buy(30))
+                .with_operation(application_id, buy(30))
+                .with_operation(application_id, Operation::SetLossLimit { per_day: None });
+        })
+        .await;
+
+    let state_query = format!(
+        "query {{ market(marketId: 0) {{ totalLiquidity }} \
+            player(playerId: \"{player}\") {{ riskControls {{ dailyLossLimit spentToday }} }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, state_query.as_str()).await;
+    assert_eq!(response["market"]["totalLiquidity"], "29.85");
+    assert_eq!(response["player"]["riskControls"]["dailyLossLimit"], "50.");
+    assert_eq!(response["player"]["riskControls"]["spentToday"], "30.");
+
+    // A day later the limit can be lifted, but a running exclusion blocks buys and can't be shortened
+    validator.clock().add(TimeDelta::from_secs(24 * 3600));
+    let now = validator.clock().current_time();
+    let excluded_until = Timestamp::from(now.micros() + 3600 * 1_000_000);
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::SetLossLimit { per_day: None })
+                .with_operation(application_id, Operation::SelfExclude { until: excluded_until })
+                .with_operation(application_id, Operation::SelfExclude { until: now })
+                .with_operation(application_id, buy(10));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, state_query.as_str()).await;
+    assert_eq!(response["market"]["totalLiquidity"], "29.85");
+    assert!(response["player"]["riskControls"]["dailyLossLimit"].is_null());
+
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, buy(80));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, state_query.as_str()).await;
+    assert_eq!(response["market"]["totalLiquidity"], "109.45");
+}
+
+/// Test that winners who entered early get bonus XP at resolution when the toggle is on,
+/// timed by when they bought the winning outcome rather than by their first buy
+#[tokio::test(flavor = "multi_thread")]
+async fn test_early_conviction_rewards() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let rewarded = GameConfig {
+        early_conviction_rewards: true,
+        early_conviction_max_xp: 40,
+        ..GameConfig::default()
+    };
+    let rewarded_app = chain.create_application(module_id, (), rewarded, vec![]).await;
+    let plain_app = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+    let buy = |outcome_id, tokens| Operation::BuyShares {
+        market_id: 0,
+        outcome_id,
+        amount: Amount::from_tokens(tokens),
+        max_price_per_share: Amount::from_tokens(tokens),
+        valid_until: None,
+    };
+
+    chain
+        .add_block(|block| {
+            for application_id in [rewarded_app, plain_app] {
+                block
+                    .with_operation(
+                        application_id,
+                        Operation::RegisterPlayer {
+                            display_name: Some("Early".to_string()),
+                        },
+                    )
+                    .with_operation(
+                        application_id,
+                        Operation::CreateMarket {
+                            title: "Sentiment".to_string(),
+                            description: "Settled by the crowd".to_string(),
+                            outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                            duration_seconds: 300,
+                            resolution_method: ResolutionMethod::CrowdConsensus,
+                            payout_weights: None,
+                            visibility: None,
+                            allowed_guild: None,
+                            allowlist: None,
+                            metadata: None,
+                            allow_early_close: None,
+                            bootstrap_seconds: None,
+                            blind_finish_seconds: None,
+                        },
+                    )
+                    .with_operation(application_id, buy(1, 1));
+            }
+        })
+        .await;
+
+    // The winning outcome is bought halfway through the market
+    validator.clock().add(TimeDelta::from_secs(150));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now);
+            for application_id in [rewarded_app, plain_app] {
+                block.with_operation(application_id, buy(0, 20));
+            }
+        })
+        .await;
+
+    validator.clock().add(TimeDelta::from_secs(151));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now);
+            for application_id in [rewarded_app, plain_app] {
+                block.with_operation(application_id, Operation::TriggerResolution { market_id: 0 });
+            }
+        })
+        .await;
+
+    let xp_query = format!("query {{ player(playerId: \"{player}\") {{ level experiencePoints }} }}");
+    let QueryOutcome { response: rewarded, .. } = chain.graphql_query(rewarded_app, xp_query.as_str()).await;
+    let QueryOutcome { response: plain, .. } = chain.graphql_query(plain_app, xp_query.as_str()).await;
+    assert_eq!(rewarded["player"]["level"], plain["player"]["level"]);
+    let bonus = rewarded["player"]["experiencePoints"].as_u64().unwrap()
+        - plain["player"]["experiencePoints"].as_u64().unwrap();
+    assert_eq!(bonus, 20);
+}
+
+/// Test that successful operations are logged with consecutive sequence numbers
+#[tokio::test(flavor = "multi_thread")]
+async fn test_event_log() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig::default();
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let buy = |market_id| Operation::BuyShares {
+        market_id,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(10),
+        valid_until: None,
+    };
+    // Buying into a market that doesn't exist fails and is not logged
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Indexed".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Logged".to_string(),
+                        description: "Every action is recorded".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy(7))
+                .with_operation(application_id, buy(0))
+                .with_operation(
+                    application_id,
+                    Operation::UpdateProfile {
+                        display_name: Some("Indexer".to_string()),
+                        privacy: None,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { all: events { sequence source actor kind } \
+                page: events(afterSeq: 1, limit: 1) { sequence kind payload } \
+                done: events(afterSeq: 3) { sequence } }",
+        )
+        .await;
+    let all = response["all"].as_array().unwrap();
+    let kinds: Vec<&str> = all.iter().map(|event| event["kind"].as_str().unwrap()).collect();
+    assert_eq!(kinds, ["RegisterPlayer", "CreateMarket", "BuyShares", "UpdateProfile"]);
+    for (index, event) in all.iter().enumerate() {
+        assert_eq!(event["sequence"], index);
+        assert_eq!(event["source"], "OPERATION");
+        assert_eq!(event["actor"], player.to_string());
+    }
+    assert_eq!(response["page"][0]["sequence"], 2);
+    let payload: serde_json::Value =
+        serde_json::from_str(response["page"][0]["payload"].as_str().unwrap()).unwrap();
+    assert_eq!(payload["BuyShares"]["market_id"], 0);
+    assert_eq!(response["done"], serde_json::json!([]));
+}
+
+/// Test that state exports are paginated and only served once enabled
+#[tokio::test(flavor = "multi_thread")]
+async fn test_export_snapshot() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let exporting = GameConfig {
+        snapshot_exports_enabled: true,
+        ..GameConfig::default()
+    };
+    let exporting_app = chain.create_application(module_id, (), exporting, vec![]).await;
+    let closed_app = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+
+    let create = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Exported".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::OracleVoting,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    chain
+        .add_block(|block| {
+            for application_id in [exporting_app, closed_app] {
+                block
+                    .with_operation(
+                        application_id,
+                        Operation::RegisterPlayer {
+                            display_name: Some("Operator".to_string()),
+                        },
+                    )
+                    .with_operation(application_id, create("First"))
+                    .with_operation(application_id, create("Second"));
+            }
+        })
+        .await;
+
+    let export_query = "query { first: exportSnapshot(section: MARKETS, limit: 1) { version total nextOffset data } \
+        last: exportSnapshot(section: MARKETS, offset: 1) { nextOffset data } \
+        players: exportSnapshot(section: PLAYERS) { total data } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(exporting_app, export_query).await;
+    assert_eq!(response["first"]["version"], 1);
+    assert_eq!(response["first"]["total"], 2);
+    assert_eq!(response["first"]["nextOffset"], 1);
+    let first: serde_json::Value = serde_json::from_str(response["first"]["data"].as_str().unwrap()).unwrap();
+    assert_eq!(first[0]["title"], "First");
+    assert!(response["last"]["nextOffset"].is_null());
+    let last: serde_json::Value = serde_json::from_str(response["last"]["data"].as_str().unwrap()).unwrap();
+    assert_eq!(last[0]["title"], "Second");
+    let players: serde_json::Value = serde_json::from_str(response["players"]["data"].as_str().unwrap()).unwrap();
+    assert_eq!(players[0]["display_name"], "Operator");
+
+    let response = chain.try_graphql_query(closed_app, export_query).await;
+    assert!(response.is_err());
+}
+
+/// Test that end times snap to the configured granularity and trading stops during the grace period
+#[tokio::test(flavor = "multi_thread")]
+async fn test_end_time_granularity_and_grace() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        end_time_granularity_seconds: 60,
+        trading_grace_seconds: 30,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(10),
+        valid_until: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Closer".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Snapped".to_string(),
+                        description: "Rounded end time".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 300,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy());
+        })
+        .await;
+
+    let state_query = "query { market(marketId: 0) { endTime totalLiquidity } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, state_query).await;
+    assert_eq!(response["market"]["totalLiquidity"], "9.95");
+    let end_time = &response["market"]["endTime"];
+    let end_micros = end_time
+        .as_u64()
+        .or_else(|| end_time.as_str().and_then(|micros| micros.parse().ok()))
+        .expect("end time should be in microseconds");
+    assert_eq!(end_micros % 60_000_000, 0);
+
+    // Twenty seconds before the end is inside the thirty-second grace window
+    let now = validator.clock().current_time();
+    validator.clock().add(TimeDelta::from_micros(end_micros - now.micros() - 20_000_000));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, buy());
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, state_query).await;
+    assert_eq!(response["market"]["totalLiquidity"], "9.95");
+}
+
+/// Test that winnings left unclaimed past the claim window are swept to the treasury
+#[tokio::test(flavor = "multi_thread")]
+async fn test_expiring_winnings() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        claim_window_seconds: 3600,
+        claim_expiry_warning_seconds: 600,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Forgetful".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Expiring".to_string(),
+                        description: "Claim it or lose it".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(10),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarket {
+                    market_id: 0,
+                    ranked_outcomes: vec![0],
+                    evidence: None,
+                },
+            );
+        })
+        .await;
+
+    // Ten minutes before expiry winners can be warned, once; sweeping is still too early
+    validator.clock().add(TimeDelta::from_secs(3100));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::WarnExpiringClaims { market_id: 0 })
+                .with_operation(application_id, Operation::WarnExpiringClaims { market_id: 0 })
+                .with_operation(application_id, Operation::SweepExpiredWinnings { market_id: 0 });
+        })
+        .await;
+    let state_query = format!(
+        "query {{ supplyMetrics {{ treasury }} events(limit: 100) {{ kind }} \
+            player(playerId: \"{player}\") {{ tokenBalance }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, state_query.as_str()).await;
+    assert_eq!(response["supplyMetrics"]["treasury"], "0.");
+    let warnings = response["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .filter(|event| event["kind"] == "WarnExpiringClaims")
+        .count();
+    assert_eq!(warnings, 1);
+    let balance = response["player"]["tokenBalance"].clone();
+
+    // After the window closes the claim is refused and the payout goes to the treasury
+    validator.clock().add(TimeDelta::from_secs(500));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 })
+                .with_operation(application_id, Operation::SweepExpiredWinnings { market_id: 0 });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, state_query.as_str()).await;
+    assert_eq!(response["player"]["tokenBalance"], balance);
+    let treasury: f64 = response["supplyMetrics"]["treasury"].as_str().unwrap().parse().unwrap();
+    assert!(treasury > 0.0);
+}
+
+/// Test that markets move from a player's active set to their history once settled
+#[tokio::test(flavor = "multi_thread")]
+async fn test_active_market_archival() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+    let application_id = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+
+    let create = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Archived later".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let buy = |market_id| Operation::BuyShares {
+        market_id,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(10),
+        valid_until: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Tidy".to_string()),
+                    },
+                )
+                .with_operation(application_id, create("Won"))
+                .with_operation(application_id, create("Lost"))
+                .with_operation(application_id, buy(0))
+                .with_operation(application_id, buy(1));
+        })
+        .await;
+
+    let markets_query = format!(
+        "query {{ activeMarkets(playerId: \"{player}\") \
+            archivedMarkets(playerId: \"{player}\") \
+            tail: archivedMarkets(playerId: \"{player}\", offset: 1) }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, markets_query.as_str()).await;
+    assert_eq!(response["activeMarkets"], serde_json::json!([0, 1]));
+    assert_eq!(response["archivedMarkets"], serde_json::json!([]));
+
+    // A losing market is archived at resolution, a winning one once claimed
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 0,
+                        ranked_outcomes: vec![0],
+                        evidence: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 1,
+                        ranked_outcomes: vec![1],
+                        evidence: None,
+                    },
+                );
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, markets_query.as_str()).await;
+    assert_eq!(response["activeMarkets"], serde_json::json!([0]));
+    assert_eq!(response["archivedMarkets"], serde_json::json!([1]));
+
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, markets_query.as_str()).await;
+    assert_eq!(response["activeMarkets"], serde_json::json!([]));
+    assert_eq!(response["archivedMarkets"], serde_json::json!([1, 0]));
+    assert_eq!(response["tail"], serde_json::json!([0]));
+}
+
+/// Test that settled markets are replaced by their summary once old enough
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_archival() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let config = GameConfig {
+        market_archive_after_days: 1,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Archivist".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Cold storage".to_string(),
+                        description: "Old news".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(10),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    // Too soon after resolution, and then blocked by the unclaimed winnings
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 0,
+                        ranked_outcomes: vec![0],
+                        evidence: None,
+                    },
+                )
+                .with_operation(application_id, Operation::ArchiveMarket { market_id: 0 });
+        })
+        .await;
+    validator.clock().add(TimeDelta::from_secs(24 * 3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ArchiveMarket { market_id: 0 });
+        })
+        .await;
+    let archive_query = "query { market(marketId: 0) { title } \
+        archivedMarket(marketId: 0) { title outcomeNames winningOutcomes totalParticipants } \
+        searchMarkets(query: \"storage\") { id } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, archive_query).await;
+    assert_eq!(response["market"]["title"], "Cold storage");
+    assert!(response["archivedMarket"].is_null());
+
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 })
+                .with_operation(application_id, Operation::ArchiveMarket { market_id: 0 });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, archive_query).await;
+    assert!(response["market"].is_null());
+    assert_eq!(response["searchMarkets"], serde_json::json!([]));
+    let summary = &response["archivedMarket"];
+    assert_eq!(summary["title"], "Cold storage");
+    assert_eq!(summary["outcomeNames"], serde_json::json!(["Yes", "No"]));
+    assert_eq!(summary["winningOutcomes"], serde_json::json!([0]));
+    assert_eq!(summary["totalParticipants"], 1);
+}
+
+/// A buy that would take a player's price impact in a market's window past the market type's
+/// limit is partially filled, or rejected when the config asks for it
+#[tokio::test(flavor = "multi_thread")]
+async fn test_price_impact_guard() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    // Two outcomes start at 50%; 5 points of impact allow a little under 250 tokens on the
+    // default curve
+    let guarded = GameConfig {
+        quick_prediction_curve: CurveParams {
+            max_price_impact_bps: 500,
+            ..CurveParams::default()
+        },
+        ..GameConfig::default()
+    };
+    let strict = GameConfig {
+        reject_oversized_buys: true,
+        ..guarded.clone()
+    };
+    let partial_app = chain.create_application(module_id, (), guarded, vec![]).await;
+    let strict_app = chain.create_application(module_id, (), strict, vec![]).await;
+    let buy = |tokens| Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(tokens),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+    for application_id in [partial_app, strict_app] {
+        chain
+            .add_block(|block| {
+                block
+                    .with_operation(
+                        application_id,
+                        Operation::RegisterPlayer {
+                            display_name: Some("Whale".to_string()),
+                        },
+                    )
+                    .with_operation(
+                        application_id,
+                        Operation::CreateMarket {
+                            title: "Whale watch".to_string(),
+                            description: "Oversized orders".to_string(),
+                            outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                            duration_seconds: 2 * 3600,
+                            resolution_method: ResolutionMethod::OracleVoting,
+                            payout_weights: None,
+                            visibility: None,
+                            allowed_guild: None,
+                            allowlist: None,
+                            metadata: None,
+                            allow_early_close: None,
+                            bootstrap_seconds: None,
+                            blind_finish_seconds: None,
+                        },
+                    );
+            })
+            .await;
+    }
+
+    let balance_query = format!(
+        "query {{ player(playerId: \"{player}\") {{ tokenBalance }} \
+            market(marketId: 0) {{ outcomes {{ totalShares }} }} \
+            quoteBuy(marketId: 0, outcomeId: 0, amount: \"500\") {{ amount shares }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(partial_app, &*balance_query).await;
+    let before: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+    let quoted: Amount = response["quoteBuy"]["amount"].as_str().unwrap().parse().unwrap();
+    assert!(quoted > Amount::from_tokens(240) && quoted < Amount::from_tokens(250));
+    let quoted_shares = response["quoteBuy"]["shares"].clone();
+
+    // The oversized buy only spends what fits under the limit
+    chain
+        .add_block(|block| {
+            block.with_operation(partial_app, buy(500));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(partial_app, &*balance_query).await;
+    let after: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+    assert!(before.saturating_sub(after) <= quoted);
+    assert_eq!(response["market"]["outcomes"][0]["totalShares"], quoted_shares);
+
+    // Splitting the order doesn't help: the rest of it waits for the next window
+    chain
+        .add_block(|block| {
+            block.with_operation(partial_app, buy(100));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(partial_app, &*balance_query).await;
+    assert_eq!(response["market"]["outcomes"][0]["totalShares"], quoted_shares);
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(partial_app, buy(100));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(partial_app, &*balance_query).await;
+    assert_ne!(response["market"]["outcomes"][0]["totalShares"], quoted_shares);
+
+    // The strict app rejects the same order outright but fills one within the limit
+    chain
+        .add_block(|block| {
+            block.with_operation(strict_app, buy(500));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(strict_app, &*balance_query).await;
+    assert_eq!(response["market"]["outcomes"][0]["totalShares"], "0.");
+    chain
+        .add_block(|block| {
+            block.with_operation(strict_app, buy(100));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(strict_app, &*balance_query).await;
+    assert_ne!(response["market"]["outcomes"][0]["totalShares"], "0.");
+}
+
+/// Fee rates grow with a trade's price impact and in the last minutes before close
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dynamic_fees() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        fee_schedule: FeeSchedule {
+            base_bps: 50,
+            impact_percent: 10,
+            late_bps: 200,
+            late_window_seconds: 600,
+            max_bps: 500,
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+    let buy = |tokens| Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(tokens),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Trader".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Thin market".to_string(),
+                        description: "Dynamic fees".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    let quotes = "query { small: quoteBuy(marketId: 0, outcomeId: 0, amount: \"1\") { feeBps } \
+        large: quoteBuy(marketId: 0, outcomeId: 0, amount: \"100\") { fee feeBps } \
+        market(marketId: 0) { feesCollected } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, quotes).await;
+    let small_fee_bps = response["small"]["feeBps"].as_u64().unwrap();
+    assert!(small_fee_bps < 60);
+    assert!(response["large"]["feeBps"].as_u64().unwrap() > small_fee_bps + 50);
+    let large_fee = response["large"]["fee"].clone();
+
+    // The charged fee matches the quote
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, buy(100));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, quotes).await;
+    assert_eq!(response["market"]["feesCollected"], large_fee);
+
+    // Five minutes before close, halfway into the late window, 100 bps are added
+    validator.clock().add(TimeDelta::from_secs(3300));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(application_id, buy(1));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, quotes).await;
+    assert!(response["small"]["feeBps"].as_u64().unwrap() >= small_fee_bps + 100);
+}
+
+/// Test that config updates and proposals outside the documented bounds are rejected
+#[tokio::test(flavor = "multi_thread")]
+async fn test_config_validation() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        admin: Some(AccountOwner::from(chain.public_key())),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config.clone(), vec![])
+        .await;
+
+    let no_outcomes = GameConfig {
+        max_outcomes_per_market: 0,
+        ..config.clone()
+    };
+    let instant_markets = GameConfig {
+        min_market_duration_seconds: 0,
+        ..config.clone()
+    };
+    let printer = GameConfig {
+        daily_login_reward: Amount::from_tokens(1_000_000_000),
+        ..config.clone()
+    };
+    let flat_curve = GameConfig {
+        pvp_curve: CurveParams {
+            price_cap_multiplier: 0,
+            ..CurveParams::default()
+        },
+        ..config.clone()
+    };
+    let effective_at = validator.clock().current_time().saturating_add(TimeDelta::from_secs(3600));
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::UpdateGameConfig { config: no_outcomes })
+                .with_operation(application_id, Operation::UpdateGameConfig { config: instant_markets })
+                .with_operation(application_id, Operation::UpdateGameConfig { config: printer })
+                .with_operation(
+                    application_id,
+                    Operation::ProposeConfigChange {
+                        config: flat_curve,
+                        effective_at,
+                    },
+                );
+        })
+        .await;
+
+    let config_query = "query { gameConfig { maxOutcomesPerMarket minMarketDurationSeconds \
+        dailyLoginReward pvpCurve { priceCapMultiplier } } pendingConfigChange { effectiveAt } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, config_query).await;
+    assert_eq!(response["gameConfig"]["maxOutcomesPerMarket"], 10);
+    assert_eq!(response["gameConfig"]["minMarketDurationSeconds"], 300);
+    assert_eq!(response["gameConfig"]["dailyLoginReward"], "10.");
+    assert!(response["pendingConfigChange"].is_null());
+
+    // A config within bounds still goes through, once the notice period has passed
+    let tighter = GameConfig {
+        max_outcomes_per_market: 4,
+        ..config
+    };
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::UpdateGameConfig { config: tighter });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, config_query).await;
+    assert_eq!(response["gameConfig"]["maxOutcomesPerMarket"], 10);
+    assert!(!response["pendingConfigChange"].is_null());
+
+    validator.clock().add(TimeDelta::from_secs(3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, config_query).await;
+    assert_eq!(response["gameConfig"]["maxOutcomesPerMarket"], 4);
+}
+
+/// Test buying cosmetic items from the shop, burning their price, and equipping them
+#[tokio::test(flavor = "multi_thread")]
+async fn test_cosmetic_shop() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let admin = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        admin: Some(admin),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let create_item = |name: &str, kind, tokens, max_supply| Operation::CreateShopItem {
+        name: name.to_string(),
+        kind,
+        price: Amount::from_tokens(tokens),
+        max_supply,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Collector".to_string()),
+                    },
+                )
+                .with_operation(application_id, create_item("Gold frame", ItemKind::ProfileFrame, 50, Some(1)))
+                .with_operation(application_id, create_item("Neon skin", ItemKind::MarketCardSkin, 5_000, None))
+                .with_operation(application_id, create_item("  ", ItemKind::ProfileFrame, 1, None))
+                .with_operation(application_id, Operation::PurchaseItem { item_id: 0 })
+                .with_operation(application_id, Operation::PurchaseItem { item_id: 0 })
+                .with_operation(application_id, Operation::PurchaseItem { item_id: 1 })
+                .with_operation(application_id, Operation::EquipItem { item_id: 0 })
+                .with_operation(application_id, Operation::EquipItem { item_id: 1 })
+                .with_operation(
+                    application_id,
+                    Operation::SetShopItemAvailable {
+                        item_id: 1,
+                        available: false,
+                    },
+                );
+        })
+        .await;
+
+    let shop_query = format!(
+        "query {{ player(playerId: \"{admin}\") {{ tokenBalance equippedItems {{ profileFrame marketCardSkin }} }} \
+            inventory(playerId: \"{admin}\") {{ id name }} \
+            shopItems {{ id sold available }} supplyMetrics {{ totalBurned }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*shop_query).await;
+    assert_eq!(response["player"]["tokenBalance"], "950.");
+    assert_eq!(response["player"]["equippedItems"]["profileFrame"], 0);
+    assert!(response["player"]["equippedItems"]["marketCardSkin"].is_null());
+    assert_eq!(response["inventory"], serde_json::json!([{ "id": 0, "name": "Gold frame" }]));
+    assert_eq!(
+        response["shopItems"],
+        serde_json::json!([
+            { "id": 0, "sold": 1, "available": true },
+            { "id": 1, "sold": 0, "available": false },
+        ])
+    );
+    assert_eq!(response["supplyMetrics"]["totalBurned"], "50.");
+
+    chain
+        .add_block(|block| {
+            block.with_operation(
+                application_id,
+                Operation::UnequipItem {
+                    kind: ItemKind::ProfileFrame,
+                },
+            );
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*shop_query).await;
+    assert!(response["player"]["equippedItems"]["profileFrame"].is_null());
+}
+
+/// Test that resolved markets score each entry's implied probability into the player's
+/// accuracy and the accuracy leaderboard
+#[tokio::test(flavor = "multi_thread")]
+async fn test_forecast_accuracy() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+    let config = GameConfig {
+        min_forecasts_for_accuracy_rank: 2,
+        ..GameConfig::default()
+    };
+    let application_id = chain.create_application(module_id, (), config, vec![]).await;
+
+    let market = |title: &str, outcomes: &[&str]| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Accuracy check".to_string(),
+        outcome_names: outcomes.iter().map(|name| name.to_string()).collect(),
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let buy = |market_id| Operation::BuyShares {
+        market_id,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+    // Each first buy enters at an even split: 50% and 33.33%
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Forecaster".to_string()),
+                    },
+                )
+                .with_operation(application_id, market("Coin toss", &["Heads", "Tails"]))
+                .with_operation(application_id, market("Podium", &["A", "B", "C"]))
+                .with_operation(application_id, buy(0))
+                .with_operation(application_id, buy(1));
+        })
+        .await;
+
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 0,
+                        ranked_outcomes: vec![0],
+                        evidence: None,
+                    },
+                )
+                .with_operation(application_id, Operation::RefreshLeaderboard);
+        })
+        .await;
+
+    let accuracy_query = format!(
+        "query {{ player(playerId: \"{player}\") {{ forecastsScored brierScoreTotal accuracy }} \
+         leaderboard {{ topForecasters {{ playerId accuracy forecastsScored }} }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*accuracy_query).await;
+    assert_eq!(response["player"]["forecastsScored"], 1);
+    assert_eq!(response["player"]["brierScoreTotal"], 2_500);
+    assert_eq!(response["player"]["accuracy"], 7_500);
+    // One forecast is below the leaderboard minimum
+    assert_eq!(response["leaderboard"]["topForecasters"].as_array().unwrap().len(), 0);
+
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 1,
+                        ranked_outcomes: vec![0],
+                        evidence: None,
+                    },
+                )
+                .with_operation(application_id, Operation::RefreshLeaderboard);
+        })
+        .await;
+
+    // (1 - 0.3333)² adds 4444 basis points, for a mean Brier score of 3472
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*accuracy_query).await;
+    assert_eq!(response["player"]["forecastsScored"], 2);
+    assert_eq!(response["player"]["brierScoreTotal"], 2_500 + 4_444);
+    assert_eq!(response["player"]["accuracy"], 6_528);
+    let forecasters = response["leaderboard"]["topForecasters"].as_array().unwrap();
+    assert_eq!(forecasters.len(), 1);
+    assert_eq!(forecasters[0]["playerId"], player.to_string());
+    assert_eq!(forecasters[0]["accuracy"], 6_528);
+}
+
+/// Test that each leaderboard category keeps its own board, queryable on its own
+#[tokio::test(flavor = "multi_thread")]
+async fn test_leaderboard_categories() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+    let application_id = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+
+    let buy = || Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(100),
+        valid_until: None,
+    };
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Ranked".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Boards".to_string(),
+                        description: "Ranked on every stat".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy())
+                .with_operation(application_id, buy());
+        })
+        .await;
+
+    validator.clock().add(TimeDelta::from_secs(600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::RefreshLeaderboard);
+        })
+        .await;
+
+    let board_query = |category: &str| {
+        format!("query {{ board(category: {category}) {{ playerId displayName amount score }} }}")
+    };
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*board_query("VOLUME")).await;
+    let volume = response["board"].as_array().unwrap();
+    assert_eq!(volume.len(), 1);
+    assert_eq!(volume[0]["playerId"], player.to_string());
+    assert_eq!(volume[0]["displayName"], "Ranked");
+    assert_eq!(volume[0]["amount"], "20.");
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*board_query("CREATOR_EARNINGS")).await;
+    let creators = response["board"].as_array().unwrap();
+    assert_eq!(creators.len(), 1);
+    assert_eq!(creators[0]["playerId"], player.to_string());
+    assert_ne!(creators[0]["amount"], "0.");
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*board_query("PROFIT")).await;
+    assert_eq!(response["board"].as_array().unwrap().len(), 1);
+
+    // Nothing has been scored or won yet
+    for category in ["ACCURACY", "BEST_STREAK"] {
+        let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*board_query(category)).await;
+        assert!(response["board"].as_array().unwrap().is_empty());
+    }
+}
+
+/// Test that evidence attached to a resolution is validated and returned with the market
+#[tokio::test(flavor = "multi_thread")]
+async fn test_resolution_evidence() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+    let application_id = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Judge".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Sourced".to_string(),
+                        description: "Resolved from a public record".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    let hash: CryptoHash = "ab".repeat(32).parse().unwrap();
+    let evidence = |url: &str| Evidence {
+        url: url.to_string(),
+        hash,
+        note: "Official results page".to_string(),
+    };
+    let resolve = |evidence| Operation::ResolveMarket {
+        market_id: 0,
+        ranked_outcomes: vec![1],
+        evidence: Some(evidence),
+    };
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    // A URL with whitespace is rejected along with the resolution
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, resolve(evidence("https://example.com/not a url")));
+        })
+        .await;
+    let evidence_query = "query { market(marketId: 0) { status resolutionEvidence { submittedBy outcomeId \
+        submittedAt evidence { url hash note } } } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, evidence_query).await;
+    assert_eq!(response["market"]["status"], "ACTIVE");
+    assert!(response["market"]["resolutionEvidence"].as_array().unwrap().is_empty());
+
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, resolve(evidence("https://example.com/results")));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, evidence_query).await;
+    assert_eq!(response["market"]["status"], "RESOLVED");
+    let records = response["market"]["resolutionEvidence"].as_array().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0]["submittedBy"], player.to_string());
+    assert_eq!(records[0]["outcomeId"], 1);
+    assert_eq!(records[0]["evidence"]["url"], "https://example.com/results");
+    assert_eq!(records[0]["evidence"]["hash"], hash.to_string());
+    assert_eq!(records[0]["evidence"]["note"], "Official results page");
+}
+
+/// Test that a special event waives fees while it runs and leaves the calendar when cancelled
+#[tokio::test(flavor = "multi_thread")]
+async fn test_special_events() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        admin: Some(AccountOwner::from(chain.public_key())),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    let now = validator.clock().current_time();
+    let ends_at = now.saturating_add(TimeDelta::from_secs(3600));
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::ScheduleEvent {
+                        name: "Zero-fee day".to_string(),
+                        starts_at: now,
+                        ends_at,
+                        xp_multiplier_bps: Some(20_000),
+                        fee_multiplier_bps: Some(0),
+                        category: None,
+                    },
+                )
+                // Ignored: events only boost, never penalize
+                .with_operation(
+                    application_id,
+                    Operation::ScheduleEvent {
+                        name: "Half XP".to_string(),
+                        starts_at: now,
+                        ends_at,
+                        xp_multiplier_bps: Some(5_000),
+                        fee_multiplier_bps: None,
+                        category: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Celebrant".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Event market".to_string(),
+                        description: "Traded during an event".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::OracleVoting,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    let query = "query { eventCalendar { id name xpMultiplierBps feeMultiplierBps } \
+        quoteBuy(marketId: 0, outcomeId: 0, amount: \"100\") { feeBps } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    let calendar = response["eventCalendar"].as_array().unwrap();
+    assert_eq!(calendar.len(), 1);
+    assert_eq!(calendar[0]["name"], "Zero-fee day");
+    assert_eq!(response["quoteBuy"]["feeBps"], 0);
+
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::CancelEvent { event_id: 0 });
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert!(response["eventCalendar"].as_array().unwrap().is_empty());
+    assert!(response["quoteBuy"]["feeBps"].as_u64().unwrap() > 0);
+}
+
+/// Test that the admin's next listed market is featured with boosted XP, and that the most
+/// traded market is featured the next day once the list runs out
+#[tokio::test(flavor = "multi_thread")]
+async fn test_featured_market() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        admin: Some(player),
+        self_trade_xp_percent: 100,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let create_market = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Featured market test".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3 * 24 * 3600,
+        resolution_method: ResolutionMethod::OracleVoting,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let buy = |market_id, tokens| Operation::BuyShares {
+        market_id,
+        outcome_id: 0,
+        amount: Amount::from_tokens(tokens),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Featured".to_string()),
+                    },
+                )
+                .with_operation(application_id, create_market("Quiet market"))
+                .with_operation(application_id, create_market("Featured market"))
+                .with_operation(application_id, Operation::SetFeaturedMarkets { market_ids: vec![1] })
+                .with_operation(application_id, Operation::SelectFeaturedMarket)
+                .with_operation(application_id, buy(0, 100));
+        })
+        .await;
+
+    let query = format!(
+        "query {{ featuredMarket {{ marketId source }} featuredQueue \
+            player(playerId: \"{player}\") {{ experiencePoints }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["featuredMarket"]["marketId"], 1);
+    assert_eq!(response["featuredMarket"]["source"], "CURATED");
+    assert!(response["featuredQueue"].as_array().unwrap().is_empty());
+    assert_eq!(response["player"]["experiencePoints"], 10);
+
+    // A trade in the featured market earns 1.5x the XP
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, buy(1, 10));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["player"]["experiencePoints"], 25);
+
+    // With nothing listed, yesterday's most traded market is featured
+    validator.clock().add(TimeDelta::from_secs(24 * 3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::SelectFeaturedMarket);
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["featuredMarket"]["marketId"], 0);
+    assert_eq!(response["featuredMarket"]["source"], "TRENDING");
+}
+
+/// Test that only a player's first trade in a market counts as participation and earns full
+/// XP, with repeat trades earning less up to a daily cap per market
+#[tokio::test(flavor = "multi_thread")]
+async fn test_repeat_trade_xp() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    // The trending market is featured the next day; leave its XP unboosted
+    let config = GameConfig {
+        self_trade_xp_percent: 100,
+        featured_market_xp_multiplier_bps: 10_000,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let create_market = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Repeat trade XP".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3 * 24 * 3600,
+        resolution_method: ResolutionMethod::OracleVoting,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let buy = |market_id| Operation::BuyShares {
+        market_id,
+        outcome_id: 0,
+        amount: Amount::from_tokens(5),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+
+    // 10 XP for entering, then 2 a trade until the 10 XP daily cap
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Regular".to_string()),
+                    },
+                )
+                .with_operation(application_id, create_market("Busy market"))
+                .with_operation(application_id, create_market("Second market"));
+            for _ in 0..8 {
+                block.with_operation(application_id, buy(0));
+            }
+        })
+        .await;
+    let query = format!("query {{ player(playerId: \"{player}\") {{ experiencePoints marketsParticipated }} }}");
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["player"]["experiencePoints"], 20);
+    assert_eq!(response["player"]["marketsParticipated"], 1);
+
+    // The cap resets the next day, and entering another market earns the full amount
+    validator.clock().add(TimeDelta::from_secs(24 * 3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, buy(0))
+                .with_operation(application_id, buy(1));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["player"]["experiencePoints"], 32);
+    assert_eq!(response["player"]["marketsParticipated"], 2);
+}
+
+/// Test that trades staking a small share of the balance earn less XP, and that trade XP stops
+/// at the daily cap
+#[tokio::test(flavor = "multi_thread")]
+async fn test_stake_scaled_trade_xp() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        self_trade_xp_percent: 100,
+        trade_xp: TradeXp {
+            first_trade: 40,
+            full_xp_stake_bps: 500,
+            daily_cap: 60,
+            ..TradeXp::default()
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let create_market = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Stake-scaled XP".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3 * 24 * 3600,
+        resolution_method: ResolutionMethod::OracleVoting,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+    let buy = |market_id, amount| Operation::BuyShares {
+        market_id,
+        outcome_id: 0,
+        amount,
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+
+    // A stake above 5% of the balance earns the full 40 XP, a dust trade nothing
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, Operation::RegisterPlayer { display_name: None });
+            for title in ["First", "Second", "Third"] {
+                block.with_operation(application_id, create_market(title));
+            }
+            block
+                .with_operation(application_id, buy(0, Amount::from_tokens(50)))
+                .with_operation(application_id, buy(1, Amount::from_millis(10)));
+        })
+        .await;
+    let query = format!("query {{ player(playerId: \"{player}\") {{ experiencePoints }} }}");
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["player"]["experiencePoints"], 40);
+
+    // Another full-sized entry is cut to what's left of the 60 XP daily cap
+    chain
+        .add_block(|block| {
+            block.with_operation(application_id, buy(2, Amount::from_tokens(50)));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["player"]["experiencePoints"], 60);
+}
+
+/// Test that spectators see a live market's volume and trade counts with anonymous positions
+#[tokio::test(flavor = "multi_thread")]
+async fn test_spectator_stats() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let application_id = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+    let buy = |outcome_id| Operation::BuyShares {
+        market_id: 0,
+        outcome_id,
+        amount: Amount::from_tokens(10),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Spectated".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Live match".to_string(),
+                        description: "Watched by spectators".to_string(),
+                        outcome_names: vec!["Home".to_string(), "Away".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy(0))
+                .with_operation(application_id, buy(0))
+                .with_operation(application_id, buy(1))
+                .with_operation(
+                    application_id,
+                    Operation::SellShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        shares: Amount::from_tokens(1),
+                        min_price_per_share: Amount::ZERO,
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { all: spectatorStats(marketId: 0) { status largestPositions { outcomeId shares } \
+                hourlyVolume { startsAt volume } outcomeTrades { outcomeId buys sells } } \
+                top: spectatorStats(marketId: 0, limit: 1) { largestPositions { outcomeId } } }",
+        )
+        .await;
+    let stats = &response["all"];
+    assert_eq!(stats["status"], "ACTIVE");
+    assert_eq!(stats["largestPositions"].as_array().unwrap().len(), 2);
+    assert_eq!(stats["hourlyVolume"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        stats["outcomeTrades"],
+        serde_json::json!([
+            { "outcomeId": 0, "buys": 2, "sells": 1 },
+            { "outcomeId": 1, "buys": 1, "sells": 0 },
+        ])
+    );
+    assert_eq!(response["top"]["largestPositions"].as_array().unwrap().len(), 1);
+
+    // Resolved markets are no longer spectated
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarket {
+                    market_id: 0,
+                    ranked_outcomes: vec![0],
+                    evidence: None,
+                },
+            );
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { spectatorStats(marketId: 0) { status } }")
+        .await;
+    assert!(response["spectatorStats"].is_null());
+}
+
+/// Test that resolutions and trades over the admin's threshold are queued for off-chain relays
+#[tokio::test(flavor = "multi_thread")]
+async fn test_notification_outbox() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        admin: Some(player),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let buy = |amount| Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 1,
+        amount: Amount::from_tokens(amount),
+        max_price_per_share: Amount::from_tokens(1_000),
+        valid_until: None,
+    };
+
+    // Trades are only recorded once the admin sets a threshold
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Whale".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Relayed".to_string(),
+                        description: "Pushed to phones".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy(100))
+                .with_operation(
+                    application_id,
+                    Operation::SetNotificationFilter {
+                        filter: predictive_manager::NotificationFilter {
+                            market_resolved: true,
+                            large_trade_threshold: Some(Amount::from_tokens(50)),
+                        },
+                    },
+                )
+                .with_operation(application_id, buy(10))
+                .with_operation(application_id, buy(50));
+        })
+        .await;
+
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarket {
+                    market_id: 0,
+                    ranked_outcomes: vec![1],
+                    evidence: None,
+                },
+            );
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(
+            application_id,
+            "query { all: notifications { sequence kind marketId playerId outcomeId amount } \
+                rest: notifications(afterSeq: 0) { kind } \
+                notificationFilter { marketResolved largeTradeThreshold } }",
+        )
+        .await;
+    let all = response["all"].as_array().unwrap();
+    assert_eq!(all.len(), 2);
+    assert_eq!(all[0]["sequence"], 0);
+    assert_eq!(all[0]["kind"], "LARGE_TRADE");
+    assert_eq!(all[0]["playerId"], player.to_string());
+    assert_eq!(all[0]["outcomeId"], 1);
+    assert_eq!(all[0]["amount"], "50.");
+    assert_eq!(all[1]["sequence"], 1);
+    assert_eq!(all[1]["kind"], "MARKET_RESOLVED");
+    assert_eq!(all[1]["outcomeId"], 1);
+    assert!(all[1]["playerId"].is_null());
+    assert_eq!(response["rest"], serde_json::json!([{ "kind": "MARKET_RESOLVED" }]));
+    assert_eq!(response["notificationFilter"]["largeTradeThreshold"], "50.");
+}
+
+/// Test that slippage limits compare the average price per share, not the trade's total
+#[tokio::test(flavor = "multi_thread")]
+async fn test_slippage_per_share() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let application_id = chain
+        .create_application(module_id, (), GameConfig::default(), vec![])
+        .await;
+    let buy = |max_price_per_share| Operation::BuyShares {
+        market_id: 0,
+        outcome_id: 0,
+        amount: Amount::from_tokens(20),
+        max_price_per_share,
+        valid_until: None,
+    };
+
+    // Shares start at the base price of one token and get dearer as they're bought
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Careful".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Priced per share".to_string(),
+                        description: "Limits are per share".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(application_id, buy(Amount::ONE))
+                .with_operation(application_id, buy(Amount::from_tokens(2)));
+        })
+        .await;
+
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { market(marketId: 0) { totalLiquidity } }")
+        .await;
+    assert_eq!(response["market"]["totalLiquidity"], "19.9");
+}
+
+/// Test that level milestones award chests that open once for a drop of the admin's table
+#[tokio::test(flavor = "multi_thread")]
+async fn test_mystery_chests() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    // The first trade earns exactly the XP to reach level 2
+    let config = GameConfig {
+        admin: Some(player),
+        self_trade_xp_percent: 100,
+        trade_xp: TradeXp {
+            first_trade: 100,
+            repeat_trade: 0,
+            repeat_daily_cap: 0,
+            ..TradeXp::default()
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::SetChestTable {
+                        table: ChestTable {
+                            levels_per_chest: 2,
+                            drops: vec![ChestDrop {
+                                weight: 1,
+                                tokens: Amount::from_tokens(25),
+                                xp: 0,
+                            }],
+                        },
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Lucky".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Chest market".to_string(),
+                        description: "Trading levels up".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(5),
+                        max_price_per_share: Amount::from_tokens(1_000),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+
+    let query = format!(
+        "query {{ player(playerId: \"{player}\") {{ level tokenBalance }} chests(playerId: \"{player}\") {{ level openedAt }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["player"]["level"], 2);
+    assert_eq!(response["chests"].as_array().unwrap().len(), 1);
+    assert_eq!(response["chests"][0]["level"], 2);
+    assert!(response["chests"][0]["openedAt"].is_null());
+    let balance: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+
+    // Opening pays the only drop; a second open and an unearned chest fail without paying
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::OpenChest { chest_id: 0 })
+                .with_operation(application_id, Operation::OpenChest { chest_id: 0 })
+                .with_operation(application_id, Operation::OpenChest { chest_id: 1 });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    let after: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+    assert_eq!(after, balance.saturating_add(Amount::from_tokens(25)));
+    assert!(!response["chests"][0]["openedAt"].is_null());
+}
+
+/// Test that a creator's bond is held while the market runs and refunded on a clean resolution
+#[tokio::test(flavor = "multi_thread")]
+async fn test_creator_bond() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let creator = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        market_creator_bond: Amount::from_tokens(50),
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Bonded".to_string()),
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Bonded market".to_string(),
+                        description: "Resolved cleanly by its creator".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                );
+        })
+        .await;
+
+    let query = format!(
+        "query {{ player(playerId: \"{creator}\") {{ tokenBalance }} market(marketId: 0) {{ creatorBond bondStatus }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["market"]["creatorBond"], "50.");
+    assert_eq!(response["market"]["bondStatus"], "HELD");
+    let balance: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarket {
+                    market_id: 0,
+                    ranked_outcomes: vec![0],
+                    evidence: None,
+                },
+            );
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["market"]["bondStatus"], "REFUNDED");
+    let after: Amount = response["player"]["tokenBalance"].as_str().unwrap().parse().unwrap();
+    assert_eq!(after, balance.saturating_add(Amount::from_tokens(50)));
+}
+
+/// Test that a creator at their cap of active markets can create another once one resolves
+#[tokio::test(flavor = "multi_thread")]
+async fn test_creator_market_cap() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+
+    let config = GameConfig {
+        creator_market_cap: CreatorMarketCap {
+            base: 1,
+            levels_per_extra: 0,
+            ..CreatorMarketCap::default()
+        },
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let create_market = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Creator market cap test".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3600,
+        resolution_method: ResolutionMethod::CreatorDecides,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None })
+                .with_operation(application_id, create_market("First"))
+                .with_operation(application_id, create_market("Over the cap"));
+        })
+        .await;
+    let query = "query { first: market(marketId: 0) { title } second: market(marketId: 1) { title } }";
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(response["first"]["title"], "First");
+    assert!(response["second"].is_null());
+
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(
+                    application_id,
+                    Operation::ResolveMarket {
+                        market_id: 0,
+                        ranked_outcomes: vec![0],
+                        evidence: None,
+                    },
+                )
+                .with_operation(application_id, create_market("Second"));
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, query).await;
+    assert_eq!(response["second"]["title"], "Second");
+}
+
+/// Test that winnings waiting to be claimed are listed with their claim deadline until claimed
+#[tokio::test(flavor = "multi_thread")]
+async fn test_claimables() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        claim_window_seconds: 24 * 3600,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(application_id, Operation::RegisterPlayer { display_name: None })
+                .with_operation(
+                    application_id,
+                    Operation::CreateMarket {
+                        title: "Claimable market".to_string(),
+                        description: "Winnings wait to be claimed".to_string(),
+                        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+                        duration_seconds: 3600,
+                        resolution_method: ResolutionMethod::CreatorDecides,
+                        payout_weights: None,
+                        visibility: None,
+                        allowed_guild: None,
+                        allowlist: None,
+                        metadata: None,
+                        allow_early_close: None,
+                        bootstrap_seconds: None,
+                        blind_finish_seconds: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(50),
+                        max_price_per_share: Amount::from_tokens(1_000),
+                        valid_until: None,
+                    },
+                );
+        })
+        .await;
+    let query = format!(
+        "query {{ claimables(playerId: \"{player}\") {{ total markets {{ marketId kind amount expiresAt }} }} }}"
+    );
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["claimables"]["total"], "0.");
+    assert!(response["claimables"]["markets"].as_array().unwrap().is_empty());
+
+    validator.clock().add(TimeDelta::from_secs(3601));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block.with_timestamp(now).with_operation(
+                application_id,
+                Operation::ResolveMarket {
+                    market_id: 0,
+                    ranked_outcomes: vec![0],
+                    evidence: None,
+                },
+            );
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    let claimable = &response["claimables"]["markets"][0];
+    assert_eq!(claimable["marketId"], 0);
+    assert_eq!(claimable["kind"], "WINNINGS");
+    assert_eq!(claimable["amount"], response["claimables"]["total"]);
+    assert_ne!(claimable["amount"], "0.");
+    assert!(!claimable["expiresAt"].is_null());
+
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::ClaimWinnings { market_id: 0 });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain.graphql_query(application_id, &*query).await;
+    assert_eq!(response["claimables"]["total"], "0.");
+    assert!(response["claimables"]["markets"].as_array().unwrap().is_empty());
+}
+
+/// Test that views count towards the trending market alongside volume
+#[tokio::test(flavor = "multi_thread")]
+async fn test_market_views() {
+    let (validator, module_id) = TestValidator::with_current_module::<
+        predictive_manager::PredictiveManagerAbi,
+        (),
+        GameConfig,
+    >()
+    .await;
+    let mut chain = validator.new_chain().await;
+    let player = AccountOwner::from(chain.public_key());
+
+    let config = GameConfig {
+        engagement_relayer: Some(player),
+        trending_view_value: Amount::ONE,
+        ..GameConfig::default()
+    };
+    let application_id = chain
+        .create_application(module_id, (), config, vec![])
+        .await;
+    let create_market = |title: &str| Operation::CreateMarket {
+        title: title.to_string(),
+        description: "Market views test".to_string(),
+        outcome_names: vec!["Yes".to_string(), "No".to_string()],
+        duration_seconds: 3 * 24 * 3600,
+        resolution_method: ResolutionMethod::OracleVoting,
+        payout_weights: None,
+        visibility: None,
+        allowed_guild: None,
+        allowlist: None,
+        metadata: None,
+        allow_early_close: None,
+        bootstrap_seconds: None,
+        blind_finish_seconds: None,
+    };
+
+    // Market 0 trades 10 tokens; market 1 is only watched, by 20 relayed views and the
+    // player's own view, counted once however often it's recorded
+    chain
+        .add_block(|block| {
+            block
+                .with_operation(
+                    application_id,
+                    Operation::RegisterPlayer {
+                        display_name: Some("Watcher".to_string()),
+                    },
+                )
+                .with_operation(application_id, create_market("Traded market"))
+                .with_operation(application_id, create_market("Watched market"))
+                .with_operation(
+                    application_id,
+                    Operation::BuyShares {
+                        market_id: 0,
+                        outcome_id: 0,
+                        amount: Amount::from_tokens(10),
+                        max_price_per_share: Amount::from_tokens(1_000),
+                        valid_until: None,
+                    },
+                )
+                .with_operation(
+                    application_id,
+                    Operation::RecordViews {
+                        views: vec![MarketViewCount { market_id: 1, views: 20 }],
+                    },
+                )
+                .with_operation(application_id, Operation::RecordView { market_id: 1 })
+                .with_operation(application_id, Operation::RecordView { market_id: 1 });
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { marketViews(marketId: 1) { total today } }")
+        .await;
+    assert_eq!(response["marketViews"]["total"], 21);
+    assert_eq!(response["marketViews"]["today"], 21);
+
+    validator.clock().add(TimeDelta::from_secs(24 * 3600));
+    let now = validator.clock().current_time();
+    chain
+        .add_block(|block| {
+            block
+                .with_timestamp(now)
+                .with_operation(application_id, Operation::SelectFeaturedMarket);
+        })
+        .await;
+    let QueryOutcome { response, .. } = chain
+        .graphql_query(application_id, "query { featuredMarket { marketId source } }")
+        .await;
+    assert_eq!(response["featuredMarket"]["marketId"], 1);
+    assert_eq!(response["featuredMarket"]["source"], "TRENDING");
+}